@@ -0,0 +1,328 @@
+//! age 加密分割（`age-split`）：与 `raw-split` 一样把单个文件按字节切分，但每份分片各自以
+//! [age](https://age-encryption.org/v1) 格式加密，写为 `<名称>.part-001.age`。不依赖 zip AES，
+//! 任何一份都可以用 `age -d` 单独解密，解密结果按顺序拼接即得原文件。
+//!
+//! 加密方式二选一（age 不允许口令与公钥混用）：
+//! - 口令：打包时的密码（含密钥文件），经 scrypt 派生密钥，每份分片各做一次，约一秒；
+//! - X25519 接收方公钥 `age1…`：可填多个，也可填每行一个公钥的接收方文件，持有任一对应身份文件
+//!   （`AGE-SECRET-KEY-1…`）的人都能合并。
+//!
+//! 完整性清单中 `payload` 为原文件的校验值，合并后照常比对。
+
+use age::{secrecy, x25519, Decryptor, Encryptor, Identity, IdentityFile, Recipient};
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    iter,
+    path::Path,
+    str::FromStr,
+};
+
+use crate::{
+    collect_part_group, compute_parts, copy_n_buffered, emit_progress, finish_checkpoint,
+    format_part_index, i18n::tr, integrity, jobs::JobContext, mark_part_done, output_base_name,
+    part_from_checkpoint, part_stem, prepare_parts_dir, secrets::SecretString, RestoreResult,
+    SplitResult,
+};
+
+pub(crate) const PART_SUFFIX: &str = ".age";
+
+/// 打包时的加密方式。
+pub(crate) enum Seal {
+    Passphrase(SecretString),
+    Recipients(Vec<x25519::Recipient>),
+}
+
+/// 解析接收方：`age1…` 公钥，或每行一个公钥的文件（忽略空行与 `#` 注释）。
+pub(crate) fn parse_recipients(values: &[String]) -> Result<Vec<x25519::Recipient>, String> {
+    let mut recipients = Vec::new();
+    for value in values
+        .iter()
+        .map(|value| value.trim())
+        .filter(|value| !value.is_empty())
+    {
+        if value.starts_with("age1") {
+            recipients.push(parse_recipient(value, value)?);
+            continue;
+        }
+        let text = fs::read_to_string(value).map_err(|_| tr!(AgeRecipientInvalid, value))?;
+        for line in text.lines().map(str::trim) {
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            recipients.push(parse_recipient(line, value)?);
+        }
+    }
+    Ok(recipients)
+}
+
+fn parse_recipient(key: &str, source: &str) -> Result<x25519::Recipient, String> {
+    x25519::Recipient::from_str(key).map_err(|_| tr!(AgeRecipientInvalid, source))
+}
+
+pub(crate) fn encryptor(seal: &Seal) -> Result<Encryptor, String> {
+    match seal {
+        Seal::Passphrase(passphrase) => Ok(Encryptor::with_user_passphrase(
+            secrecy::SecretString::from(passphrase.to_string()),
+        )),
+        Seal::Recipients(recipients) => Encryptor::with_recipients(
+            recipients
+                .iter()
+                .map(|recipient| recipient as &dyn Recipient),
+        )
+        .map_err(|e| e.to_string()),
+    }
+}
+
+pub(crate) fn split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    seal: &Seal,
+    overwrite_parts: bool,
+    hash_algorithm: integrity::HashAlgorithm,
+) -> Result<SplitResult, String> {
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    if metadata.is_dir() {
+        return Err(tr!(AgeSplitNeedsFile));
+    }
+    if metadata.len() == 0 {
+        return Err(tr!(EmptyInputFile));
+    }
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+    let source_size = metadata.len();
+    let (chunk_size, parts) = compute_parts(source_size, split_by, size_bytes, count)?;
+    let width = parts.to_string().len().max(3);
+    let stem = part_stem(&base_name, false);
+
+    let mut reader = BufReader::new(File::open(input_path).map_err(|e| e.to_string())?);
+    let mut output_files = Vec::with_capacity(parts);
+    let mut processed = 0u64;
+    for part_index in 1..=parts {
+        let part_size = chunk_size.min(source_size.saturating_sub(processed));
+        if part_size == 0 && split_by != "count" {
+            break;
+        }
+        let part_path = parts_dir.join(format!(
+            "{}{}{}",
+            stem,
+            format_part_index(part_index, width),
+            PART_SUFFIX
+        ));
+        if part_from_checkpoint(ctx, part_index, &part_path) {
+            reader
+                .seek_relative(part_size as i64)
+                .map_err(|e| e.to_string())?;
+            processed += part_size;
+            output_files.push(part_path.to_string_lossy().to_string());
+            continue;
+        }
+        emit_progress(
+            ctx,
+            "split",
+            processed,
+            source_size,
+            part_index,
+            parts,
+            tr!(PreparePart, part_index),
+        );
+
+        ctx.track_output(&part_path);
+        let file = BufWriter::new(File::create(&part_path).map_err(|e| e.to_string())?);
+        let mut writer = encryptor(seal)?
+            .wrap_output(file)
+            .map_err(|e| e.to_string())?;
+        copy_n_buffered(
+            &mut reader,
+            &mut writer,
+            part_size,
+            ctx.memory().buffer_size(),
+            ctx.cancel_token(),
+            |delta| {
+                processed += delta;
+                emit_progress(
+                    ctx,
+                    "split",
+                    processed,
+                    source_size,
+                    part_index,
+                    parts,
+                    tr!(EncryptingPart, part_index),
+                );
+            },
+        )
+        .map_err(|e| e.to_string())?;
+        writer
+            .finish()
+            .and_then(|mut file| file.flush())
+            .map_err(|e| e.to_string())?;
+        mark_part_done(ctx, part_index, &part_path)?;
+        output_files.push(part_path.to_string_lossy().to_string());
+    }
+
+    let part_sha256s = integrity::write_manifest(
+        ctx,
+        hash_algorithm,
+        &parts_dir,
+        &base_name,
+        "age-split",
+        input_path,
+        None,
+        &output_files,
+    )?;
+    finish_checkpoint(ctx);
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir: false,
+        base_name,
+        part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+/// 合并时的解密方式。
+pub(crate) enum Unseal {
+    Passphrase(SecretString),
+    Identities(Vec<Box<dyn Identity>>),
+}
+
+impl Unseal {
+    /// 有身份文件时用身份文件，否则用口令；两者都没有时报错。
+    pub(crate) fn new(password: Option<&str>, identity_files: &[String]) -> Result<Unseal, String> {
+        let mut identities = Vec::new();
+        for path in identity_files.iter().filter(|path| !path.is_empty()) {
+            let file = IdentityFile::from_file(path.clone())
+                .map_err(|e| tr!(AgeIdentityReadFailed, path, e))?;
+            identities.extend(
+                file.into_identities()
+                    .map_err(|e| tr!(AgeIdentityReadFailed, path, e))?,
+            );
+        }
+        if !identities.is_empty() {
+            return Ok(Unseal::Identities(identities));
+        }
+        match password {
+            Some(password) => Ok(Unseal::Passphrase(SecretString::from(password))),
+            None => Err(tr!(AgeSplitNeedsKey)),
+        }
+    }
+}
+
+/// 打开一份 age 加密的分片，返回解密后的内容。
+pub(crate) fn open_part(path: &Path, unseal: &Unseal) -> Result<impl Read, String> {
+    let file = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+    let decryptor = Decryptor::new(file).map_err(|e| tr!(AgeDecryptFailed, path.display(), e))?;
+    let result = match unseal {
+        Unseal::Passphrase(passphrase) => {
+            let identity =
+                age::scrypt::Identity::new(secrecy::SecretString::from(passphrase.to_string()));
+            decryptor.decrypt(iter::once(&identity as &dyn Identity))
+        }
+        Unseal::Identities(identities) => decryptor.decrypt(
+            identities
+                .iter()
+                .map(|identity| identity.as_ref() as &dyn Identity),
+        ),
+    };
+    result.map_err(|err| match err {
+        age::DecryptError::NoMatchingKeys | age::DecryptError::DecryptionFailed => {
+            tr!(AgeNoMatchingKey, path.display())
+        }
+        err => tr!(AgeDecryptFailed, path.display(), err),
+    })
+}
+
+/// 依次解密每份分片并拼接为原文件，写到 `output_dir` 下分片名去掉序号后的文件名。
+pub(crate) fn restore(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    identity_files: &[String],
+) -> Result<RestoreResult, String> {
+    let unseal = Unseal::new(password, identity_files)?;
+    let part_group = collect_part_group(input_path)?;
+    let merged_name = part_group.prefix.trim_end_matches('.').to_string();
+    let temp_path = output_dir.join(format!("{}.merge.tmp", merged_name));
+    let total_bytes: u64 = part_group
+        .parts
+        .iter()
+        .filter_map(|part| fs::metadata(&part.path).ok())
+        .map(|meta| meta.len())
+        .sum();
+
+    ctx.track_output(&temp_path);
+    let mut writer = BufWriter::new(File::create(&temp_path).map_err(|e| e.to_string())?);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut processed = 0u64;
+    let total_parts = part_group.parts.len();
+    for (idx, part) in part_group.parts.iter().enumerate() {
+        emit_progress(
+            ctx,
+            "merge",
+            processed,
+            total_bytes,
+            idx + 1,
+            total_parts,
+            tr!(DecryptingPart, idx + 1),
+        );
+        let mut reader = open_part(&part.path, &unseal)?;
+        loop {
+            ctx.check_cancelled()?;
+            let read_len = match reader.read(&mut buffer) {
+                Ok(0) => break,
+                Ok(read_len) => read_len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(tr!(AgeDecryptFailed, part.path.display(), err)),
+            };
+            writer
+                .write_all(&buffer[..read_len])
+                .map_err(|e| e.to_string())?;
+            // 解密后的大小略小于分片，进度按分片大小估算
+            processed = (processed + read_len as u64).min(total_bytes);
+            emit_progress(
+                ctx,
+                "merge",
+                processed,
+                total_bytes,
+                idx + 1,
+                total_parts,
+                tr!(DecryptingPart, idx + 1),
+            );
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    drop(writer);
+
+    let merged_path = output_dir.join(&merged_name);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let merged_file = merged_path.to_string_lossy().to_string();
+    Ok(RestoreResult {
+        merged_file: Some(merged_file.clone()),
+        extracted_dir: None,
+        output_files: vec![merged_file],
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
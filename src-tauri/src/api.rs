@@ -0,0 +1,265 @@
+//! 本机 HTTP 接口（默认关闭）：只监听 127.0.0.1，所有接口（除 `/api/health`）都需要令牌，
+//! 令牌通过 `Authorization: Bearer <token>` 或 `X-Api-Token` 请求头传入。
+//!
+//! - `POST /api/jobs`：提交任务，请求体 `{"kind": "pack" | "restore" | "restoreRange" | "extractEntry", "options": {...}}`
+//! - `GET /api/jobs`：任务列表（含历史）
+//! - `GET /api/jobs/{id}`：任务状态、最新进度与结果
+//! - `GET /api/jobs/{id}/events`：以 SSE 推送该任务的进度（`progress`）与结束（`done`）事件，
+//!   浏览器 `EventSource` 无法设置请求头时可改用 `?token=` 传入令牌
+//! - `POST /api/jobs/{id}/cancel`：取消任务
+//! - `POST /api/index-parts`、`POST /api/search-entries`：同名命令的同步版本
+//! - `POST /api/list-contents`：`list_archive_contents` 命令的同步版本
+//! - `POST /api/plan-restore`：`plan_restore` 命令的同步版本
+
+use rand::Rng;
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    io::{Cursor, Write},
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+};
+use tauri::AppHandle;
+use tiny_http::{Header, Method, Request, Response, Server, StatusCode};
+
+use crate::{
+    contents::list_contents_blocking,
+    estimate::plan_restore_blocking,
+    i18n::{tr, Msg},
+    index_parts_blocking,
+    jobs::{self, JobEvent},
+    search_entries_blocking,
+};
+
+struct RunningServer {
+    server: Arc<Server>,
+    url: String,
+    token: String,
+}
+
+fn running() -> &'static Mutex<Option<RunningServer>> {
+    static RUNNING: OnceLock<Mutex<Option<RunningServer>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(None))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitJob {
+    kind: String,
+    options: Value,
+}
+
+/// 启动 HTTP 接口，返回 `(地址, 令牌)`。未指定令牌时随机生成。
+pub(crate) fn start(
+    app: Option<AppHandle>,
+    port: u16,
+    token: Option<String>,
+) -> Result<(String, String), String> {
+    let mut guard = running().lock().map_err(|_| tr!(ApiStateError))?;
+    if let Some(current) = guard.as_ref() {
+        return Err(tr!(ApiAlreadyRunning, current.url));
+    }
+    let token = token
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(generate_token);
+    let server = Arc::new(
+        Server::http(("127.0.0.1", port)).map_err(|e| tr!(ApiStartFailed, e))?,
+    );
+    let url = match server.server_addr().to_ip() {
+        Some(addr) => format!("http://{}", addr),
+        None => format!("http://127.0.0.1:{}", port),
+    };
+
+    let worker = server.clone();
+    let expected = token.clone();
+    thread::spawn(move || {
+        for request in worker.incoming_requests() {
+            let app = app.clone();
+            let expected = expected.clone();
+            thread::spawn(move || handle_request(app, &expected, request));
+        }
+    });
+
+    *guard = Some(RunningServer {
+        server,
+        url: url.clone(),
+        token: token.clone(),
+    });
+    Ok((url, token))
+}
+
+pub(crate) fn stop() -> Result<(), String> {
+    let current = running()
+        .lock()
+        .map_err(|_| tr!(ApiStateError))?
+        .take()
+        .ok_or_else(|| tr!(ApiNotRunning))?;
+    current.server.unblock();
+    Ok(())
+}
+
+/// 当前运行中的接口地址与令牌。
+pub(crate) fn status() -> Option<(String, String)> {
+    let guard = running().lock().ok()?;
+    guard
+        .as_ref()
+        .map(|current| (current.url.clone(), current.token.clone()))
+}
+
+fn generate_token() -> String {
+    let bytes: [u8; 24] = rand::thread_rng().gen();
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn handle_request(app: Option<AppHandle>, expected_token: &str, mut request: Request) {
+    let method = request.method().clone();
+    let (path, query) = match request.url().split_once('?') {
+        Some((path, query)) => (path.to_string(), query.to_string()),
+        None => (request.url().to_string(), String::new()),
+    };
+    let path = path.trim_end_matches('/').to_string();
+
+    if method == Method::Get && path == "/api/health" {
+        let _ = request.respond(json_response(200, json!({ "ok": true })));
+        return;
+    }
+    if !authorized(&request, &query, expected_token) {
+        let _ = request.respond(error_response(401, Msg::InvalidToken.text()));
+        return;
+    }
+
+    let mut body = String::new();
+    if matches!(method, Method::Post) {
+        if let Err(err) = request.as_reader().read_to_string(&mut body) {
+            let _ = request.respond(error_response(400, &err.to_string()));
+            return;
+        }
+    }
+
+    let segments: Vec<&str> = path.split('/').filter(|part| !part.is_empty()).collect();
+    if let (Method::Get, ["api", "jobs", id, "events"]) = (&method, segments.as_slice()) {
+        match jobs::find_job(id) {
+            Some(job) => stream_job_events(request, job.subscribe()),
+            None => {
+                let _ = request.respond(error_response(404, Msg::JobNotFound.text()));
+            }
+        }
+        return;
+    }
+
+    let response = match (&method, segments.as_slice()) {
+        (Method::Post, ["api", "jobs"]) => submit_job(app, &body),
+        (Method::Get, ["api", "jobs"]) => Ok(json_response(200, json!(jobs::list_jobs()))),
+        (Method::Get, ["api", "jobs", id]) => match jobs::find_job(id) {
+            Some(job) => Ok(json_response(200, json!(job.snapshot()))),
+            None => Err((404, tr!(JobNotFound))),
+        },
+        (Method::Post, ["api", "jobs", id, "cancel"]) => match jobs::find_job(id) {
+            Some(job) => {
+                job.request_cancel();
+                Ok(json_response(200, json!(job.snapshot())))
+            }
+            None => Err((404, tr!(JobNotFound))),
+        },
+        (Method::Post, ["api", "index-parts"]) => parse_body(&body)
+            .and_then(|options| index_parts_blocking(options).map_err(|err| (400, err)))
+            .map(|result| json_response(200, json!(result))),
+        (Method::Post, ["api", "search-entries"]) => parse_body(&body)
+            .and_then(|options| search_entries_blocking(options).map_err(|err| (400, err)))
+            .map(|result| json_response(200, json!(result))),
+        (Method::Post, ["api", "list-contents"]) => parse_body(&body)
+            .and_then(|options| list_contents_blocking(options).map_err(|err| (400, err)))
+            .map(|result| json_response(200, json!(result))),
+        (Method::Post, ["api", "plan-restore"]) => parse_body(&body)
+            .and_then(|options| plan_restore_blocking(options).map_err(|err| (400, err)))
+            .map(|result| json_response(200, json!(result))),
+        _ => Err((404, tr!(RouteNotFound))),
+    };
+
+    let _ = match response {
+        Ok(response) => request.respond(response),
+        Err((status, message)) => request.respond(error_response(status, &message)),
+    };
+}
+
+fn submit_job(
+    app: Option<AppHandle>,
+    body: &str,
+) -> Result<Response<Cursor<Vec<u8>>>, (u16, String)> {
+    let submit: SubmitJob = parse_body(body)?;
+    let job = jobs::submit(app, &submit.kind, submit.options).map_err(|err| (400, err))?;
+    Ok(json_response(202, json!({ "jobId": job.id() })))
+}
+
+/// 直接写出 SSE 响应，每个事件立即刷新；`done` 事件之后不再写入。
+fn stream_job_events(request: Request, events: std::sync::mpsc::Receiver<JobEvent>) {
+    let mut writer = request.into_writer();
+    let header = "HTTP/1.1 200 OK\r\n\
+        Content-Type: text/event-stream; charset=utf-8\r\n\
+        Cache-Control: no-cache\r\n\
+        Connection: close\r\n\r\n";
+    if writer.write_all(header.as_bytes()).and_then(|_| writer.flush()).is_err() {
+        return;
+    }
+    for event in events {
+        let (name, data) = match event {
+            JobEvent::Progress(payload) => ("progress", json!(payload)),
+            JobEvent::Finished(snapshot) => ("done", json!(snapshot)),
+        };
+        let frame = format!("event: {}\ndata: {}\n\n", name, data);
+        if writer
+            .write_all(frame.as_bytes())
+            .and_then(|_| writer.flush())
+            .is_err()
+        {
+            return;
+        }
+    }
+}
+
+fn authorized(request: &Request, query: &str, expected_token: &str) -> bool {
+    let query_token = query
+        .split('&')
+        .filter_map(|pair| pair.split_once('='))
+        .any(|(key, value)| key == "token" && constant_time_eq(value, expected_token));
+    query_token || request.headers().iter().any(|header| {
+        let value = header.value.as_str();
+        if header.field.equiv("Authorization") {
+            value
+                .strip_prefix("Bearer ")
+                .map(|token| constant_time_eq(token.trim(), expected_token))
+                .unwrap_or(false)
+        } else if header.field.equiv("X-Api-Token") {
+            constant_time_eq(value.trim(), expected_token)
+        } else {
+            false
+        }
+    })
+}
+
+fn constant_time_eq(left: &str, right: &str) -> bool {
+    if left.len() != right.len() {
+        return false;
+    }
+    left.bytes()
+        .zip(right.bytes())
+        .fold(0u8, |acc, (a, b)| acc | (a ^ b))
+        == 0
+}
+
+fn parse_body<T: for<'de> Deserialize<'de>>(body: &str) -> Result<T, (u16, String)> {
+    serde_json::from_str(body).map_err(|e| (400, tr!(InvalidRequestBody, e)))
+}
+
+fn json_response(status: u16, value: Value) -> Response<Cursor<Vec<u8>>> {
+    Response::from_string(value.to_string())
+        .with_status_code(StatusCode(status))
+        .with_header(
+            Header::from_bytes(&b"Content-Type"[..], &b"application/json; charset=utf-8"[..])
+                .expect("static header"),
+        )
+}
+
+fn error_response(status: u16, message: &str) -> Response<Cursor<Vec<u8>>> {
+    json_response(status, json!({ "error": message }))
+}
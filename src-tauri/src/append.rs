@@ -0,0 +1,503 @@
+//! 追加打包：目录以 `zip-then-split` 打包时在分片目录写入清单 `<名称>.manifest.json`，
+//! 记录每个文件的大小与修改时间。之后对同一目录追加时只把新增或修改过的文件打成新的一批分片
+//! （`<名称>.append-002.zip.part-001` …），并更新清单，无需重新切分全部内容。
+//!
+//! 合并分片目录时按清单依次解压每一批，后一批覆盖前一批的同名文件；已删除的文件不会从早期分片中移除。
+//!
+//! 同一个清单文件也存放分片的校验值（参见 [`crate::integrity`]），追加的分片同样记录在内。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{BufReader, BufWriter},
+    path::{Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+use zip::{CompressionMethod, ZipWriter};
+
+use crate::{
+    build_file_options, check_archive_password, collect_part_group, copy_n_buffered, emit_progress,
+    extract,
+    i18n::tr,
+    include_dir_entry, index, integrity,
+    jobs::JobContext,
+    keyfile, merge_raw_parts, partial, remove_temp,
+    secrets::{self, SecretString},
+    signing, split_raw_file, RestoreResult,
+};
+
+pub(crate) const MANIFEST_SUFFIX: &str = ".manifest.json";
+pub(crate) const MANIFEST_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppendManifest {
+    version: u32,
+    base_name: String,
+    /// zip 内的顶层目录名（即源目录名）。
+    root_name: String,
+    /// 首批分片的每份大小，追加时默认沿用。
+    part_size: u64,
+    generations: Vec<Generation>,
+    /// 其他字段（如分片校验值），重写清单时原样保留。
+    #[serde(flatten)]
+    extra: Map<String, Value>,
+}
+
+/// 一批分片：首次打包为第 1 批，此后每次追加新增一批。
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Generation {
+    /// 分片名前缀，如 `photos.zip`、`photos.append-002.zip`。
+    prefix: String,
+    parts: Vec<String>,
+    /// 本批包含的文件（相对源目录，`/` 分隔）。
+    files: Vec<FileEntry>,
+    created_at: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FileEntry {
+    path: String,
+    size: u64,
+    /// 修改时间（Unix 秒）。
+    modified: u64,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppendOptions {
+    /// 已有的分片目录（含清单）。
+    parts_dir: String,
+    /// 源目录，与首次打包时相同。
+    input_path: String,
+    /// 新一批分片的每份大小，默认沿用首批。
+    size_bytes: Option<u64>,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
+    /// 密钥文件，与首次打包时相同。
+    keyfile: Option<String>,
+    compression_level: Option<i64>,
+    /// Ed25519 私钥文件；清单改动后原有签名失效，设置时重新签名。
+    signing_key: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct AppendResult {
+    /// 新增的批次序号；没有变化时为 `None`，不生成分片。
+    generation: Option<usize>,
+    parts: usize,
+    output_files: Vec<String>,
+    added_files: Vec<String>,
+    updated_files: Vec<String>,
+}
+
+impl AppendManifest {
+    /// 每一批的第一份分片，按批次顺序排列。
+    pub(crate) fn first_parts(&self, parts_dir: &Path) -> Vec<PathBuf> {
+        self.generations
+            .iter()
+            .filter_map(|generation| generation.parts.first())
+            .map(|name| parts_dir.join(name))
+            .collect()
+    }
+
+    /// 全部分片的文件名，按批次顺序排列。
+    pub(crate) fn part_names(&self) -> Vec<&str> {
+        self.generations
+            .iter()
+            .flat_map(|generation| &generation.parts)
+            .map(String::as_str)
+            .collect()
+    }
+}
+
+pub(crate) fn manifest_path(parts_dir: &Path, base_name: &str) -> PathBuf {
+    parts_dir.join(format!("{}{}", base_name, MANIFEST_SUFFIX))
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+fn file_name(path: &str) -> String {
+    Path::new(path)
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 查找分片目录中的追加清单；没有清单或清单只含校验值时返回 `None`。
+pub(crate) fn load_manifest(parts_dir: &Path) -> Result<Option<(PathBuf, AppendManifest)>, String> {
+    let Ok(entries) = fs::read_dir(parts_dir) else {
+        return Ok(None);
+    };
+    let mut found: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|name| name.to_str())
+                    .is_some_and(|name| name.ends_with(MANIFEST_SUFFIX))
+        })
+        .collect();
+    match found.len() {
+        0 => Ok(None),
+        1 => {
+            let path = found.remove(0);
+            let text = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+            let value: Value = serde_json::from_str(&text).map_err(|e| tr!(ManifestInvalid, e))?;
+            if value.get("generations").is_none() {
+                return Ok(None);
+            }
+            let manifest: AppendManifest =
+                serde_json::from_value(value).map_err(|e| tr!(ManifestInvalid, e))?;
+            if manifest.version > MANIFEST_VERSION {
+                return Err(tr!(ManifestVersionUnsupported, manifest.version));
+            }
+            Ok(Some((path, manifest)))
+        }
+        _ => Err(tr!(MultipleManifests)),
+    }
+}
+
+fn save_manifest(path: &Path, manifest: &mut AppendManifest) -> Result<(), String> {
+    signing::discard(path, &mut manifest.extra);
+    let text = serde_json::to_string_pretty(manifest).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, text).map_err(|e| e.to_string())?;
+    fs::rename(&temp, path).map_err(|e| e.to_string())
+}
+
+/// 列出源目录中会被打包的文件（与脚本的 `filter` 钩子一致），按路径排序。
+fn scan_files(ctx: &JobContext, root: &Path) -> Result<Vec<FileEntry>, String> {
+    let mut files = Vec::new();
+    scan_dir(ctx, root, root, &mut files)?;
+    files.sort_by(|a, b| a.path.cmp(&b.path));
+    Ok(files)
+}
+
+fn scan_dir(
+    ctx: &JobContext,
+    root: &Path,
+    current: &Path,
+    files: &mut Vec<FileEntry>,
+) -> Result<(), String> {
+    for entry in fs::read_dir(current).map_err(|e| e.to_string())? {
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        let size = if is_dir { 0 } else { metadata.len() };
+        if !include_dir_entry(ctx, root, &path, is_dir, size)? {
+            continue;
+        }
+        if is_dir {
+            scan_dir(ctx, root, &path, files)?;
+        } else if path.is_file() {
+            let rel = path.strip_prefix(root).map_err(|e| e.to_string())?;
+            let modified = metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map(|d| d.as_secs())
+                .unwrap_or(0);
+            files.push(FileEntry {
+                path: rel.to_string_lossy().replace('\\', "/"),
+                size,
+                modified,
+            });
+        }
+    }
+    Ok(())
+}
+
+/// 目录首次以 `zip-then-split` 打包后写入清单，作为第 1 批。按份数切分时
+/// `part_size` 为空，记录第一份的实际大小。
+pub(crate) fn write_initial_manifest(
+    ctx: &JobContext,
+    input_path: &Path,
+    parts_dir: &Path,
+    base_name: &str,
+    part_size: Option<u64>,
+    output_files: &[String],
+) -> Result<(), String> {
+    let root_name = input_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| tr!(CannotParseDirName))?
+        .to_string();
+    let part_size = part_size
+        .or_else(|| {
+            output_files
+                .first()
+                .and_then(|path| fs::metadata(path).ok())
+                .map(|meta| meta.len())
+        })
+        .unwrap_or(0);
+    let mut manifest = AppendManifest {
+        version: MANIFEST_VERSION,
+        base_name: base_name.to_string(),
+        root_name,
+        part_size,
+        generations: vec![Generation {
+            prefix: format!("{}.zip", base_name),
+            parts: output_files.iter().map(|path| file_name(path)).collect(),
+            files: scan_files(ctx, input_path)?,
+            created_at: unix_now(),
+        }],
+        extra: Map::new(),
+    };
+    save_manifest(&manifest_path(parts_dir, base_name), &mut manifest)
+}
+
+/// 新一批须与已有批次使用同一密码，否则合并时只能解开其中一部分。试解密已有批次中第一个加密的
+/// 文件条目，在写出任何分片之前发现密码不对；已有批次都没有加密或读不出时不检查。
+fn check_batch_password(
+    parts_dir: &Path,
+    manifest: &AppendManifest,
+    password: Option<&str>,
+) -> Result<(), String> {
+    for first in manifest.first_parts(parts_dir) {
+        let archive = collect_part_group(&first)
+            .and_then(|group| partial::open_part_archive(&group, "zip-then-split"));
+        let mut archive = match archive {
+            Ok(archive) => archive,
+            Err(err) => {
+                log::warn!("无法读取已有批次核对密码：{}（{}）", first.display(), err);
+                continue;
+            }
+        };
+        if check_archive_password(&mut archive, password)? {
+            break;
+        }
+    }
+    Ok(())
+}
+
+pub(crate) fn append_parts_blocking(
+    ctx: &JobContext,
+    mut options: AppendOptions,
+) -> Result<AppendResult, String> {
+    let parts_dir = PathBuf::from(&options.parts_dir);
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.is_dir() {
+        return Err(tr!(AppendInputNotDir));
+    }
+    let (path, mut manifest) = load_manifest(&parts_dir)?.ok_or_else(|| tr!(ManifestMissing))?;
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let password = password.as_deref();
+    check_batch_password(&parts_dir, &manifest, password)?;
+
+    // 各文件在已有批次中的最新状态
+    let mut known: HashMap<&str, (u64, u64)> = HashMap::new();
+    for generation in &manifest.generations {
+        for file in &generation.files {
+            known.insert(file.path.as_str(), (file.size, file.modified));
+        }
+    }
+    let current = scan_files(ctx, &input_path)?;
+    let mut added_files = Vec::new();
+    let mut updated_files = Vec::new();
+    let mut changed = Vec::new();
+    for file in current {
+        match known.get(file.path.as_str()) {
+            None => added_files.push(file.path.clone()),
+            Some(&state) if state != (file.size, file.modified) => {
+                updated_files.push(file.path.clone())
+            }
+            Some(_) => continue,
+        }
+        changed.push(file);
+    }
+    if changed.is_empty() {
+        return Ok(AppendResult {
+            generation: None,
+            parts: 0,
+            output_files: Vec::new(),
+            added_files,
+            updated_files,
+        });
+    }
+
+    let generation = manifest.generations.len() + 1;
+    let prefix = format!("{}.append-{:03}.zip", manifest.base_name, generation);
+    let zip_path = parts_dir.join(format!(".{}.tmp", prefix));
+    let result = zip_files(
+        ctx,
+        &input_path,
+        &manifest.root_name,
+        &changed,
+        &zip_path,
+        password,
+        options.compression_level,
+    )
+    .and_then(|_| {
+        let part_size = options.size_bytes.unwrap_or(manifest.part_size).max(1);
+        split_raw_file(
+            ctx,
+            &zip_path,
+            &parts_dir,
+            &format!("{}.part-", prefix),
+            "size",
+            Some(part_size),
+            None,
+        )
+    });
+    remove_temp(ctx, &zip_path, false);
+    if result.is_err() && ctx.is_cancelled() {
+        ctx.remove_outputs();
+    }
+    let (parts, output_files) = result?;
+
+    manifest.generations.push(Generation {
+        prefix,
+        parts: output_files.iter().map(|path| file_name(path)).collect(),
+        files: changed,
+        created_at: unix_now(),
+    });
+    save_manifest(&path, &mut manifest)?;
+    integrity::record_parts(ctx, &parts_dir, &manifest.base_name, &output_files)?;
+    let entries: Vec<(String, PathBuf)> = manifest
+        .generations
+        .last()
+        .map(|generation| {
+            generation
+                .files
+                .iter()
+                .map(|file| {
+                    (
+                        format!("{}/{}", manifest.root_name, file.path),
+                        input_path.join(&file.path),
+                    )
+                })
+                .collect()
+        })
+        .unwrap_or_default();
+    integrity::record_entries(ctx, &parts_dir, &manifest.base_name, &entries)?;
+    if let Some(key_path) = options.signing_key.as_deref().filter(|value| !value.is_empty()) {
+        signing::sign_manifest(&path, Path::new(key_path))?;
+    }
+    log::info!(
+        "追加第 {} 批分片：新增 {} 个文件，更新 {} 个文件，共 {} 份",
+        generation,
+        added_files.len(),
+        updated_files.len(),
+        parts
+    );
+    Ok(AppendResult {
+        generation: Some(generation),
+        parts,
+        output_files,
+        added_files,
+        updated_files,
+    })
+}
+
+/// 把选中的文件按首次打包时的布局（`<顶层目录>/<相对路径>`）写入 zip。
+fn zip_files(
+    ctx: &JobContext,
+    root: &Path,
+    root_name: &str,
+    files: &[FileEntry],
+    zip_path: &Path,
+    password: Option<&str>,
+    compression_level: Option<i64>,
+) -> Result<(), String> {
+    let total_size: u64 = files.iter().map(|file| file.size).sum();
+    let zip_file = File::create(zip_path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(BufWriter::new(zip_file));
+    let mut processed = 0u64;
+    emit_progress(ctx, "zip", 0, total_size, 0, 0, tr!(PackingDir));
+    let buffer_size = ctx.memory().buffer_size();
+    for file in files {
+        let source = root.join(&file.path);
+        let metadata = fs::metadata(&source).map_err(|e| e.to_string())?;
+        let size = metadata.len();
+        zip.start_file(
+            format!("{}/{}", root_name, file.path),
+            build_file_options(
+                password,
+                ctx.aes_mode(),
+                CompressionMethod::Deflated,
+                compression_level,
+                size,
+                metadata.modified().ok(),
+            ),
+        )
+        .map_err(|e| e.to_string())?;
+        let mut reader = BufReader::new(File::open(&source).map_err(|e| e.to_string())?);
+        copy_n_buffered(&mut reader, &mut zip, size, buffer_size, ctx.cancel_token(), |delta| {
+            processed += delta;
+            emit_progress(ctx, "zip", processed, total_size, 0, 0, tr!(PackingDir));
+        })
+        .map_err(|e| e.to_string())?;
+    }
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+/// 按清单依次合并每一批分片；自动解压时全部解压到同一目录，后一批覆盖前一批。
+pub(crate) fn restore_generations(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    manifest: &AppendManifest,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let target_dir = output_dir.join(&manifest.base_name);
+    let mut output_files = Vec::new();
+    let mut merged_file = None;
+    for generation in &manifest.generations {
+        let first = generation
+            .parts
+            .first()
+            .ok_or_else(|| tr!(NoPartsFound))?;
+        let part_group = collect_part_group(&parts_dir.join(first))?;
+        let layout = index::part_layout(&part_group, "zip-then-split")?;
+        let zip_name = generation.prefix.clone();
+        let temp_path = output_dir.join(format!("{}.merge.tmp", zip_name));
+        merge_raw_parts(ctx, &part_group, &temp_path)?;
+        let merged_path = output_dir.join(&zip_name);
+        if merged_path.exists() {
+            fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+        }
+        fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+        if auto_extract {
+            extract::extract_archive(ctx, &merged_path, &target_dir, password, Some(&layout))?;
+        }
+        merged_file.get_or_insert_with(|| merged_path.to_string_lossy().to_string());
+        output_files.push(merged_path.to_string_lossy().to_string());
+    }
+    let extracted_dir = auto_extract.then(|| target_dir.to_string_lossy().to_string());
+    if let Some(dir) = &extracted_dir {
+        output_files.push(dir.clone());
+    }
+    Ok(RestoreResult {
+        merged_file,
+        extracted_dir,
+        output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
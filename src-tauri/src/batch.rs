@@ -0,0 +1,119 @@
+//! 批量合并：一个目录中有多组分片（例如下载到同一文件夹的多套分片）时，逐组合并到同一输出目录。
+//! 各组以其第一份分片为输入，完整性清单、恢复分片等按该组查找；合并方式、密码与其他参数各组相同。
+//!
+//! 某一组失败时记录原因后继续合并其余各组；取消时只删除正在合并的一组的输出，已完成的组保留。
+//! 进度阶段为 `batch`（第几组），各组合并时照常发送其自身的进度。
+
+use serde::Serialize;
+use std::{path::PathBuf, time::Instant};
+
+use crate::{
+    diagnostics, download, emit_progress, find_part_groups, i18n::tr, jobs::JobContext, logging,
+    merge_parts, remove_temp, secrets, RestoreOptions, RestoreResult,
+};
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct BatchRestoreResult {
+    /// 各组的结果，按分片名排列。
+    pub(crate) groups: Vec<GroupRestoreResult>,
+    pub(crate) succeeded: usize,
+    pub(crate) failed: usize,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GroupRestoreResult {
+    /// 分片名去掉序号后的名称。
+    pub(crate) name: String,
+    pub(crate) first_part: String,
+    pub(crate) part_count: usize,
+    /// 合并成功时的结果。
+    pub(crate) result: Option<RestoreResult>,
+    /// 合并失败的原因。
+    pub(crate) error: Option<String>,
+}
+
+pub(crate) fn restore_all_groups_blocking(
+    ctx: &JobContext,
+    mut options: RestoreOptions,
+) -> Result<BatchRestoreResult, String> {
+    log::info!(
+        "开始批量合并：{}（{}）",
+        options.input_path,
+        options.merge_mode
+    );
+    diagnostics::record_job("restoreAll", &options);
+    let started = Instant::now();
+    let result = restore_all_groups(ctx, &mut options);
+    if result.is_err() && ctx.is_cancelled() {
+        ctx.remove_outputs();
+    }
+    logging::log_result("批量合并", started, &result);
+    result
+}
+
+fn restore_all_groups(
+    ctx: &JobContext,
+    options: &mut RestoreOptions,
+) -> Result<BatchRestoreResult, String> {
+    // 登记的密码只能取用一次，先取出供各组共用
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    // 给出网址时先下载全部分片，各组再从下载目录合并
+    let output_dir = PathBuf::from(&options.output_dir);
+    let downloads = download::prepare(ctx, options, &output_dir)?;
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.is_dir() {
+        return Err(tr!(BatchNeedsDir));
+    }
+    let groups = find_part_groups(&input_path)?;
+    let total = groups.len();
+    let mut results = Vec::with_capacity(total);
+
+    for (idx, group) in groups.iter().enumerate() {
+        ctx.check_cancelled()?;
+        let name = group.prefix.trim_end_matches('.').to_string();
+        let first_part = group.parts[0].path.to_string_lossy().to_string();
+        emit_progress(
+            ctx,
+            "batch",
+            idx as u64,
+            total as u64,
+            idx + 1,
+            total,
+            tr!(RestoringGroup, idx + 1, total, name),
+        );
+        let mut group_options = options.clone();
+        group_options.input_path = first_part.clone();
+        let (result, error) = match merge_parts(ctx, group_options) {
+            Ok(result) => (Some(result), None),
+            Err(err) if ctx.is_cancelled() => return Err(err),
+            Err(err) => {
+                log::warn!("第 {} 组 {} 合并失败：{}", idx + 1, name, err);
+                (None, Some(err))
+            }
+        };
+        // 本组已结束，其输出不再随之后的取消删除
+        ctx.forget_outputs();
+        results.push(GroupRestoreResult {
+            name,
+            first_part,
+            part_count: group.parts.len(),
+            result,
+            error,
+        });
+    }
+
+    let failed = results.iter().filter(|group| group.error.is_some()).count();
+    // 有失败的组时保留下载的分片，重新合并时无需再下载
+    let keep_downloads = failed > 0 || options.keep_downloads.unwrap_or(false);
+    if let Some(dir) = downloads.filter(|_| !keep_downloads) {
+        remove_temp(ctx, &dir, false);
+    }
+    log::info!("批量合并完成：{} 组成功，{} 组失败", total - failed, failed);
+    Ok(BatchRestoreResult {
+        groups: results,
+        succeeded: total - failed,
+        failed,
+    })
+}
@@ -0,0 +1,599 @@
+//! 供 Rust 调用方使用的构建器接口：切分方式、打包方式等用枚举表示，
+//! 代替命令参数中的字符串，配置错误在编译期即可发现。
+//!
+//! ```no_run
+//! use tauri_app_lib::{PackMode, SplitJob};
+//!
+//! let output = SplitJob::builder()
+//!     .input("/data/backup.tar")
+//!     .output_dir("/data/out")
+//!     .by_size(1 << 30)
+//!     .pack_mode(PackMode::ZstdThenSplit)
+//!     .password("secret")
+//!     .run(|progress| eprintln!("{}/{:?}", progress.processed_bytes, progress.total_bytes))?;
+//! println!("{} 份", output.parts);
+//! # Ok::<(), String>(())
+//! ```
+
+use std::{
+    path::{Path, PathBuf},
+    sync::Arc,
+};
+
+use crate::{
+    i18n::tr, jobs::JobContext, process_file_blocking, secrets::SecretString, ProgressPayload,
+    SplitOptions,
+};
+
+/// 切分方式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SplitBy {
+    /// 每份的最大字节数。
+    Size(u64),
+    /// 总份数。
+    Count(u64),
+}
+
+/// 打包方式。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum PackMode {
+    /// 先切分，再把每份分别打包为 zip。
+    SplitThenZip,
+    /// 先整体打包为 zip，再切分。
+    #[default]
+    ZipThenSplit,
+    /// 先整体打包为 7z，再切分。
+    SevenZThenSplit,
+    /// 先以 seekable 格式 zstd 压缩，再切分。
+    ZstdThenSplit,
+    /// 先打包为 tar.gz（保留 Unix 权限与符号链接），再切分。
+    TarGzThenSplit,
+    /// 先打包为 tar.zst（多线程 zstd 压缩），再切分。
+    TarZstThenSplit,
+    /// 不压缩，直接切成普通的二进制分片，拼接即得原文件；只用于单个文件。
+    RawSplit,
+    /// 与 `RawSplit` 一样直接切分，每份分片各自以 age 加密：口令为密码，
+    /// 或用 [`SplitJobBuilder::age_recipient`] 加密给公钥。只用于单个文件。
+    AgeSplit,
+    /// 以密码经 Argon2id 派生密钥，加密为本程序的容器后直接切分；目录先打成 zip。必须设置密码。
+    ContainerThenSplit,
+    /// 先压缩为 zip，再写成标准分卷 zip（`.z01`……`.zip`），只能按大小分卷。
+    SpannedZip,
+}
+
+impl PackMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            PackMode::SplitThenZip => "split-then-zip",
+            PackMode::ZipThenSplit => "zip-then-split",
+            PackMode::SevenZThenSplit => "7z-then-split",
+            PackMode::ZstdThenSplit => "zstd-then-split",
+            PackMode::TarGzThenSplit => "tar-gz-then-split",
+            PackMode::TarZstThenSplit => "tar-zst-then-split",
+            PackMode::RawSplit => "raw-split",
+            PackMode::AgeSplit => "age-split",
+            PackMode::ContainerThenSplit => "container-then-split",
+            PackMode::SpannedZip => "spanned-zip",
+        }
+    }
+}
+
+/// 目录输入在 `SplitThenZip` 下的压缩位置。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirSplitMode {
+    /// 整体打包时仅存储，切分后每份再压缩。
+    StoreSplitCompress,
+    /// 整体打包时压缩，切分后每份仅存储。
+    CompressSplitStore,
+}
+
+impl DirSplitMode {
+    fn as_str(self) -> &'static str {
+        match self {
+            DirSplitMode::StoreSplitCompress => "store-split-compress",
+            DirSplitMode::CompressSplitStore => "compress-split-store",
+        }
+    }
+}
+
+/// `SplitThenZip` 与 `ZipThenSplit` 中需要压缩的 zip 条目使用的压缩方法。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ZipCompression {
+    #[default]
+    Deflate,
+    /// 与只认 bzip2 条目的工具或流水线配合使用，压缩比略高、速度较慢。
+    Bzip2,
+    /// LZMA2（xz），压缩比最高，适合日志、数据库等高度可压缩的数据；
+    /// 解压需要 7-Zip 等较新的工具。
+    Xz,
+}
+
+impl ZipCompression {
+    fn as_str(self) -> &'static str {
+        match self {
+            ZipCompression::Deflate => "deflate",
+            ZipCompression::Bzip2 => "bzip2",
+            ZipCompression::Xz => "xz",
+        }
+    }
+}
+
+/// 设置密码时 zip 条目（`SplitThenZip`、`ZipThenSplit` 与 `SpannedZip`）的 AES 强度。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AesStrength {
+    /// 很大的压缩包上明显更快，对多数用途已足够。
+    Aes128,
+    Aes192,
+    #[default]
+    Aes256,
+}
+
+impl AesStrength {
+    fn bits(self) -> u16 {
+        match self {
+            AesStrength::Aes128 => 128,
+            AesStrength::Aes192 => 192,
+            AesStrength::Aes256 => 256,
+        }
+    }
+}
+
+/// 完整性清单（`SplitThenZip` 与 `ZipThenSplit`）的校验算法。
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HashAlgorithm {
+    #[default]
+    Sha256,
+    /// 多线程计算，适合很大的输入；此时 [`SplitOutput::part_sha256s`] 为空。
+    Blake3,
+}
+
+impl HashAlgorithm {
+    fn as_str(self) -> &'static str {
+        match self {
+            HashAlgorithm::Sha256 => "sha256",
+            HashAlgorithm::Blake3 => "blake3",
+        }
+    }
+}
+
+/// 进度回调收到的信息。
+#[derive(Debug, Clone)]
+pub struct Progress {
+    pub phase: String,
+    pub processed_bytes: u64,
+    /// 为 `None` 时总量未知（如管道输入）。
+    pub total_bytes: Option<u64>,
+    pub part_index: usize,
+    pub part_total: usize,
+    pub message: String,
+}
+
+impl From<&ProgressPayload> for Progress {
+    fn from(payload: &ProgressPayload) -> Self {
+        Progress {
+            phase: payload.phase.clone(),
+            processed_bytes: payload.processed_bytes,
+            total_bytes: payload.total_bytes,
+            part_index: payload.part_index,
+            part_total: payload.part_total,
+            message: payload.message.clone(),
+        }
+    }
+}
+
+/// 切分完成后的结果。
+#[derive(Debug, Clone)]
+pub struct SplitOutput {
+    pub parts: usize,
+    pub output_files: Vec<PathBuf>,
+    pub is_dir: bool,
+    pub base_name: String,
+    /// 每份分片的路径与 SHA-256（十六进制）。
+    pub part_sha256s: Vec<(PathBuf, String)>,
+    /// 生成的 PAR2 恢复文件、恢复分片及其记录，未开启时为空。
+    pub recovery_files: Vec<PathBuf>,
+    /// 分片目录中的自解压程序、合并脚本及其设置，未开启时为空。
+    pub restore_files: Vec<PathBuf>,
+}
+
+/// 一个已通过必填项检查的切分任务。
+#[derive(Debug)]
+pub struct SplitJob {
+    options: SplitOptions,
+}
+
+impl SplitJob {
+    pub fn builder() -> SplitJobBuilder {
+        SplitJobBuilder::default()
+    }
+
+    /// 在当前线程执行任务，进度通过 `progress` 回调汇报（可能从多个工作线程调用）。
+    pub fn run(
+        self,
+        progress: impl Fn(&Progress) + Send + Sync + 'static,
+    ) -> Result<SplitOutput, String> {
+        let ctx = JobContext::detached().with_progress(Arc::new(move |payload| {
+            progress(&Progress::from(payload))
+        }));
+        let result = process_file_blocking(&ctx, self.options)?;
+        Ok(SplitOutput {
+            parts: result.parts,
+            output_files: result.output_files.into_iter().map(PathBuf::from).collect(),
+            is_dir: result.is_dir,
+            base_name: result.base_name,
+            part_sha256s: result
+                .part_sha256s
+                .into_iter()
+                .map(|part| (PathBuf::from(part.path), part.sha256))
+                .collect(),
+            recovery_files: result.recovery_files.into_iter().map(PathBuf::from).collect(),
+            restore_files: result.restore_files.into_iter().map(PathBuf::from).collect(),
+        })
+    }
+}
+
+/// [`SplitJob`] 的构建器。`input`、`output_dir` 与切分方式（`by_size` / `by_count`）必填，
+/// 打包方式默认 [`PackMode::ZipThenSplit`]。
+#[derive(Debug, Clone)]
+pub struct SplitJobBuilder {
+    input: Option<PathBuf>,
+    output_dir: Option<PathBuf>,
+    split_by: Option<SplitBy>,
+    pack_mode: PackMode,
+    password: Option<SecretString>,
+    dir_split_mode: Option<DirSplitMode>,
+    overwrite_parts: bool,
+    compression_level: Option<i64>,
+    post_processors: Vec<String>,
+    script: Option<PathBuf>,
+    filter_command: Option<String>,
+    gpg_recipients: Vec<String>,
+    max_memory: Option<u64>,
+    adaptive_io: bool,
+    resume: bool,
+    background_priority: bool,
+    hash_algorithm: HashAlgorithm,
+    par2_redundancy: Option<u32>,
+    redundancy_parts: Option<u32>,
+    xor_parity: bool,
+    signing_key: Option<PathBuf>,
+    verify_after_split: bool,
+    destroy_source: bool,
+    secure_delete: bool,
+    seven_zip_volumes: bool,
+    per_part_keys: bool,
+    numbered_volumes: bool,
+    self_extracting: bool,
+    rejoin_scripts: bool,
+    zstd_dictionary: bool,
+    aes_strength: AesStrength,
+    encrypt_names: bool,
+    keyfile: Option<PathBuf>,
+    age_recipients: Vec<String>,
+    compression_method: ZipCompression,
+}
+
+impl Default for SplitJobBuilder {
+    fn default() -> Self {
+        SplitJobBuilder {
+            input: None,
+            output_dir: None,
+            split_by: None,
+            pack_mode: PackMode::default(),
+            password: None,
+            dir_split_mode: None,
+            overwrite_parts: false,
+            compression_level: None,
+            post_processors: Vec::new(),
+            script: None,
+            filter_command: None,
+            gpg_recipients: Vec::new(),
+            max_memory: None,
+            adaptive_io: true,
+            resume: true,
+            background_priority: false,
+            hash_algorithm: HashAlgorithm::default(),
+            par2_redundancy: None,
+            redundancy_parts: None,
+            xor_parity: false,
+            signing_key: None,
+            verify_after_split: false,
+            destroy_source: false,
+            secure_delete: false,
+            seven_zip_volumes: false,
+            per_part_keys: false,
+            numbered_volumes: false,
+            self_extracting: false,
+            rejoin_scripts: false,
+            zstd_dictionary: false,
+            aes_strength: AesStrength::default(),
+            encrypt_names: false,
+            keyfile: None,
+            age_recipients: Vec::new(),
+            compression_method: ZipCompression::default(),
+        }
+    }
+}
+
+impl SplitJobBuilder {
+    /// 输入文件或目录，也可以是命名管道或套接字（仅 `ZstdThenSplit`）。
+    pub fn input(mut self, path: impl AsRef<Path>) -> Self {
+        self.input = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// 输出目录，分片写入其中的 `<名称>.parts` 子目录。
+    pub fn output_dir(mut self, path: impl AsRef<Path>) -> Self {
+        self.output_dir = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn split_by(mut self, split_by: SplitBy) -> Self {
+        self.split_by = Some(split_by);
+        self
+    }
+
+    pub fn by_size(self, bytes: u64) -> Self {
+        self.split_by(SplitBy::Size(bytes))
+    }
+
+    pub fn by_count(self, count: u64) -> Self {
+        self.split_by(SplitBy::Count(count))
+    }
+
+    pub fn pack_mode(mut self, mode: PackMode) -> Self {
+        self.pack_mode = mode;
+        self
+    }
+
+    pub fn password(mut self, password: impl Into<String>) -> Self {
+        self.password = Some(SecretString::from(password.into()));
+        self
+    }
+
+    /// 密钥文件，其 SHA-256 接在密码之后作为实际密码；未设置密码时单独作为密码。
+    pub fn keyfile(mut self, path: impl AsRef<Path>) -> Self {
+        self.keyfile = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    /// `AgeSplit` 加密给的 X25519 接收方：`age1…` 公钥或每行一个公钥的文件，可多次调用。
+    /// 设置后不能再设置密码。
+    pub fn age_recipient(mut self, recipient: impl Into<String>) -> Self {
+        self.age_recipients.push(recipient.into());
+        self
+    }
+
+    pub fn dir_split_mode(mut self, mode: DirSplitMode) -> Self {
+        self.dir_split_mode = Some(mode);
+        self
+    }
+
+    /// 分片目录已存在时是否覆盖，默认不覆盖。
+    pub fn overwrite_parts(mut self, overwrite: bool) -> Self {
+        self.overwrite_parts = overwrite;
+        self
+    }
+
+    pub fn compression_level(mut self, level: i64) -> Self {
+        self.compression_level = Some(level);
+        self
+    }
+
+    /// zip 条目的压缩方法，默认 Deflate。
+    pub fn compression_method(mut self, method: ZipCompression) -> Self {
+        self.compression_method = method;
+        self
+    }
+
+    /// 设置密码时 zip 条目的 AES 强度，默认 AES-256；`SevenZThenSplit` 固定为 AES-256。
+    pub fn aes_strength(mut self, strength: AesStrength) -> Self {
+        self.aes_strength = strength;
+        self
+    }
+
+    /// 设置密码时连同文件名与目录结构一起加密，不知道密码时看不到任何条目名；
+    /// 只用于 `ZipThenSplit` 与 `SevenZThenSplit`，默认关闭。
+    pub fn encrypt_names(mut self, enabled: bool) -> Self {
+        self.encrypt_names = enabled;
+        self
+    }
+
+    /// 追加一个后处理插件，按添加顺序运行。
+    pub fn post_processor(mut self, name: impl Into<String>) -> Self {
+        self.post_processors.push(name.into());
+        self
+    }
+
+    /// rhai 脚本路径。
+    pub fn script(mut self, path: impl AsRef<Path>) -> Self {
+        self.script = Some(path.as_ref().to_path_buf());
+        self
+    }
+
+    pub fn filter_command(mut self, command: impl Into<String>) -> Self {
+        self.filter_command = Some(command.into());
+        self
+    }
+
+    /// 打包后把每份分片加密给该 GPG 公钥（指纹、密钥 ID 或邮箱），可多次调用；需要本机安装 GnuPG。
+    pub fn gpg_recipient(mut self, recipient: impl Into<String>) -> Self {
+        self.gpg_recipients.push(recipient.into());
+        self
+    }
+
+    /// 缓冲区与并发线程的内存上限（字节）。
+    pub fn max_memory(mut self, bytes: u64) -> Self {
+        self.max_memory = Some(bytes);
+        self
+    }
+
+    /// 是否按实测设备速度选择缓冲区与并发，默认开启。
+    pub fn adaptive_io(mut self, enabled: bool) -> Self {
+        self.adaptive_io = enabled;
+        self
+    }
+
+    /// 分片目录中有上次中断留下的检查点且参数一致时是否从中断处继续，默认开启。
+    pub fn resume(mut self, enabled: bool) -> Self {
+        self.resume = enabled;
+        self
+    }
+
+    /// 以较低的 CPU 与磁盘 I/O 优先级运行，减少对其他程序的影响，默认关闭。
+    pub fn background_priority(mut self, enabled: bool) -> Self {
+        self.background_priority = enabled;
+        self
+    }
+
+    pub fn hash_algorithm(mut self, algorithm: HashAlgorithm) -> Self {
+        self.hash_algorithm = algorithm;
+        self
+    }
+
+    /// 同时生成 PAR2 恢复文件，`percent` 为恢复数据占分片的百分比（1–100）。
+    pub fn par2_redundancy(mut self, percent: u32) -> Self {
+        self.par2_redundancy = Some(percent);
+        self
+    }
+
+    /// 另外生成 `count` 份恢复分片，合并时可重建不超过该份数的缺失或损坏分片。
+    pub fn redundancy_parts(mut self, count: u32) -> Self {
+        self.redundancy_parts = Some(count);
+        self
+    }
+
+    /// 另外生成一份异或校验分片，合并时可重建一份缺失或损坏的分片，默认关闭。
+    pub fn xor_parity(mut self, enabled: bool) -> Self {
+        self.xor_parity = enabled;
+        self
+    }
+
+    /// 用 Ed25519 私钥文件对完整性清单签名（仅 `SplitThenZip` 与 `ZipThenSplit`）。
+    pub fn signing_key(mut self, path: impl Into<PathBuf>) -> Self {
+        self.signing_key = Some(path.into());
+        self
+    }
+
+    /// 分割完成后重新读取每份分片，解密、解压后与源比对，默认关闭。
+    pub fn verify_after_split(mut self, enabled: bool) -> Self {
+        self.verify_after_split = enabled;
+        self
+    }
+
+    /// 打包完成且分片核对通过后覆写并删除源文件或目录，默认关闭。开启时总会分割后核对。
+    pub fn destroy_source(mut self, enabled: bool) -> Self {
+        self.destroy_source = enabled;
+        self
+    }
+
+    /// 中间文件与取消后的输出先覆写再删除，默认关闭；加密时生成的明文中间文件总会覆写。
+    pub fn secure_delete(mut self, enabled: bool) -> Self {
+        self.secure_delete = enabled;
+        self
+    }
+
+    /// `SevenZThenSplit` 的分片按 7-Zip 分卷命名为 `<名称>.7z.001`，默认关闭。
+    pub fn seven_zip_volumes(mut self, enabled: bool) -> Self {
+        self.seven_zip_volumes = enabled;
+        self
+    }
+
+    /// `ContainerThenSplit` 的每份分片以按序号派生的子密钥各自加密为独立的容器，默认关闭。
+    pub fn per_part_keys(mut self, enabled: bool) -> Self {
+        self.per_part_keys = enabled;
+        self
+    }
+
+    /// 按字节切分的分片改用经典分卷命名 `<名称>.001`，HJSplit、FFSJ 与 7-Zip 都能直接合并；
+    /// 不用于 `SplitThenZip` 与 `SpannedZip`，默认关闭。
+    pub fn numbered_volumes(mut self, enabled: bool) -> Self {
+        self.numbered_volumes = enabled;
+        self
+    }
+
+    /// 在分片目录中放入自解压程序 `restore`，接收方运行即可合并解压；只能在与本机相同的系统上运行，
+    /// 不能与过滤命令同时使用，默认关闭。
+    pub fn self_extracting(mut self, enabled: bool) -> Self {
+        self.self_extracting = enabled;
+        self
+    }
+
+    /// 在分片目录中写出 `rejoin.bat` 与 `rejoin.sh`，接收方只用系统自带工具即可拼接并解压；
+    /// 不用于 `SplitThenZip` 与 `SpannedZip`，默认关闭。
+    pub fn rejoin_scripts(mut self, enabled: bool) -> Self {
+        self.rejoin_scripts = enabled;
+        self
+    }
+
+    /// `ZstdThenSplit` 打包目录时训练 zstd 字典并用它压缩，适合大量相似的小文件，默认关闭。
+    pub fn zstd_dictionary(mut self, enabled: bool) -> Self {
+        self.zstd_dictionary = enabled;
+        self
+    }
+
+    pub fn build(self) -> Result<SplitJob, String> {
+        let input = self.input.ok_or_else(|| tr!(BuilderMissingField, "input"))?;
+        let output_dir = self
+            .output_dir
+            .ok_or_else(|| tr!(BuilderMissingField, "output_dir"))?;
+        let split_by = self
+            .split_by
+            .ok_or_else(|| tr!(BuilderMissingField, "by_size / by_count"))?;
+        let (split_by, size_bytes, count) = match split_by {
+            SplitBy::Size(bytes) => ("size", Some(bytes), None),
+            SplitBy::Count(count) => ("count", None, Some(count)),
+        };
+        Ok(SplitJob {
+            options: SplitOptions {
+                input_path: input.to_string_lossy().to_string(),
+                output_dir: output_dir.to_string_lossy().to_string(),
+                split_by: split_by.to_string(),
+                size_bytes,
+                count,
+                pack_mode: self.pack_mode.as_str().to_string(),
+                password: self.password,
+                dir_split_mode: self.dir_split_mode.map(|mode| mode.as_str().to_string()),
+                overwrite_parts: Some(self.overwrite_parts),
+                compression_level: self.compression_level,
+                post_processors: Some(self.post_processors).filter(|names| !names.is_empty()),
+                script_path: self.script.map(|path| path.to_string_lossy().to_string()),
+                filter_command: self.filter_command,
+                gpg_recipients: Some(self.gpg_recipients).filter(|values| !values.is_empty()),
+                secret_job_id: None,
+                max_memory: self.max_memory,
+                adaptive_io: Some(self.adaptive_io),
+                resume: Some(self.resume),
+                power_action: None,
+                background_priority: Some(self.background_priority),
+                hash_algorithm: Some(self.hash_algorithm.as_str().to_string()),
+                par2_redundancy: self.par2_redundancy,
+                redundancy_parts: self.redundancy_parts,
+                xor_parity: Some(self.xor_parity),
+                signing_key: self.signing_key.map(|path| path.to_string_lossy().to_string()),
+                verify_after_split: Some(self.verify_after_split),
+                destroy_source: Some(self.destroy_source),
+                secure_delete: Some(self.secure_delete),
+                seven_zip_volumes: Some(self.seven_zip_volumes),
+                per_part_keys: Some(self.per_part_keys),
+                numbered_volumes: Some(self.numbered_volumes),
+                self_extracting: Some(self.self_extracting),
+                rejoin_scripts: Some(self.rejoin_scripts),
+                zstd_dictionary: Some(self.zstd_dictionary),
+                aes_strength: Some(self.aes_strength.bits()),
+                encrypt_names: Some(self.encrypt_names),
+                keyfile: self.keyfile.map(|path| path.to_string_lossy().to_string()),
+                age_recipients: Some(self.age_recipients).filter(|values| !values.is_empty()),
+                compression_method: Some(self.compression_method.as_str().to_string()),
+            },
+        })
+    }
+
+    /// 相当于 `build()?.run(progress)`。
+    pub fn run(
+        self,
+        progress: impl Fn(&Progress) + Send + Sync + 'static,
+    ) -> Result<SplitOutput, String> {
+        self.build()?.run(progress)
+    }
+}
@@ -0,0 +1,263 @@
+//! 分割检查点：打包时在分片目录写入 `.checkpoint.json`，记录已完整写出的中间压缩包与分片。
+//! 程序崩溃或机器重启后以相同参数再次打包，检查点的指纹（输入路径、大小、修改时间与影响输出的参数）
+//! 一致时保留已写出的部分，从第一份未完成的分片继续；打包成功后删除检查点。
+//!
+//! 记为完成之前先把文件同步到磁盘，续传时再核对文件大小，断电时只写了一半的分片会重新写出。
+//! 分片很多时检查点文件每秒最多重写一次，中断时最后一秒内写完的分片会重新写出。
+//! 密码不写入检查点。指纹中含有以其余参数为盐、经 Argon2id 派生的密码校验值，换了密码或密钥文件后
+//! 指纹不同，不会把两个密码加密的分片续在一起。
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    collections::BTreeMap,
+    fs::{self, OpenOptions},
+    path::{Path, PathBuf},
+    sync::Mutex,
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use crate::{source, SplitOptions};
+
+const CHECKPOINT_FILE: &str = ".checkpoint.json";
+const CHECKPOINT_VERSION: u32 = 2;
+/// 记录分片时重写检查点文件的最短间隔。每次都重写时，上万份分片的总写入量随分片数平方增长。
+const PART_SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CheckpointState {
+    version: u32,
+    fingerprint: String,
+    /// 已完整写出的中间压缩包（先整体打包再切分时）。
+    archive: Option<CompletedFile>,
+    /// 已完整写出的分片，按序号（从 1 开始）。
+    parts: BTreeMap<usize, CompletedFile>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct CompletedFile {
+    path: String,
+    size: u64,
+}
+
+impl CompletedFile {
+    /// 记录的文件仍在原处且大小一致。
+    fn matches(&self, path: &Path) -> bool {
+        Path::new(&self.path) == path
+            && fs::metadata(path)
+                .map(|meta| meta.is_file() && meta.len() == self.size)
+                .unwrap_or(false)
+    }
+}
+
+/// 一个分片目录的检查点，可以在并行写分片的线程间共享。
+#[derive(Debug)]
+pub(crate) struct Checkpoint {
+    path: PathBuf,
+    state: Mutex<CheckpointState>,
+    /// 上次重写检查点文件的时间。
+    saved_at: Mutex<Option<Instant>>,
+}
+
+impl Checkpoint {
+    /// 读取分片目录中指纹一致的检查点；没有、无法解析或参数不同时返回 `None`。
+    pub(crate) fn load(parts_dir: &Path, fingerprint: &str) -> Option<Checkpoint> {
+        let path = parts_dir.join(CHECKPOINT_FILE);
+        let text = fs::read_to_string(&path).ok()?;
+        let state: CheckpointState = match serde_json::from_str(&text) {
+            Ok(state) => state,
+            Err(err) => {
+                log::warn!("检查点无法解析，忽略：{}（{}）", path.display(), err);
+                return None;
+            }
+        };
+        if state.version != CHECKPOINT_VERSION || state.fingerprint != fingerprint {
+            log::info!("检查点与本次参数或输入不一致，不续传：{}", path.display());
+            return None;
+        }
+        Some(Checkpoint {
+            path,
+            state: Mutex::new(state),
+            saved_at: Mutex::new(None),
+        })
+    }
+
+    /// 在分片目录中新建空的检查点。
+    pub(crate) fn create(parts_dir: &Path, fingerprint: &str) -> Result<Checkpoint, String> {
+        let checkpoint = Checkpoint {
+            path: parts_dir.join(CHECKPOINT_FILE),
+            state: Mutex::new(CheckpointState {
+                version: CHECKPOINT_VERSION,
+                fingerprint: fingerprint.to_string(),
+                archive: None,
+                parts: BTreeMap::new(),
+            }),
+            saved_at: Mutex::new(None),
+        };
+        checkpoint.save()?;
+        Ok(checkpoint)
+    }
+
+    /// 已完成的分片数。
+    pub(crate) fn completed_parts(&self) -> usize {
+        self.lock().parts.len()
+    }
+
+    pub(crate) fn archive_done(&self, path: &Path) -> bool {
+        self.lock()
+            .archive
+            .as_ref()
+            .is_some_and(|archive| archive.matches(path))
+    }
+
+    pub(crate) fn part_done(&self, index: usize, path: &Path) -> bool {
+        self.lock()
+            .parts
+            .get(&index)
+            .is_some_and(|part| part.matches(path))
+    }
+
+    /// 中间压缩包写完后调用。分片由压缩包切出，重新生成压缩包后之前记录的分片一并作废。
+    pub(crate) fn mark_archive(&self, path: &Path) -> Result<(), String> {
+        let archive = completed(path)?;
+        {
+            let mut state = self.lock();
+            state.archive = Some(archive);
+            state.parts.clear();
+        }
+        self.save()
+    }
+
+    /// 分片写完后调用。距上次重写检查点文件不足 [`PART_SAVE_INTERVAL`] 时只记在内存中。
+    pub(crate) fn mark_part(&self, index: usize, path: &Path) -> Result<(), String> {
+        let part = completed(path)?;
+        self.lock().parts.insert(index, part);
+        let recently_saved = self
+            .saved_at
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .is_some_and(|saved_at| saved_at.elapsed() < PART_SAVE_INTERVAL);
+        if recently_saved {
+            return Ok(());
+        }
+        self.save()
+    }
+
+    /// 打包成功后删除检查点。
+    pub(crate) fn remove(&self) {
+        let _ = fs::remove_file(&self.path);
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, CheckpointState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// 先写临时文件再改名，崩溃时检查点要么是旧的、要么是新的。
+    fn save(&self) -> Result<(), String> {
+        let text = {
+            let state = self.lock();
+            serde_json::to_string_pretty(&*state).map_err(|e| e.to_string())?
+        };
+        let temp = self.path.with_extension("json.tmp");
+        fs::write(&temp, text).map_err(|e| e.to_string())?;
+        fs::rename(&temp, &self.path).map_err(|e| e.to_string())?;
+        *self.saved_at.lock().unwrap_or_else(|e| e.into_inner()) = Some(Instant::now());
+        Ok(())
+    }
+}
+
+/// 把文件同步到磁盘并记下大小。
+fn completed(path: &Path) -> Result<CompletedFile, String> {
+    let file = OpenOptions::new()
+        .write(true)
+        .open(path)
+        .map_err(|e| e.to_string())?;
+    file.sync_all().map_err(|e| e.to_string())?;
+    let size = file.metadata().map_err(|e| e.to_string())?.len();
+    Ok(CompletedFile {
+        path: path.to_string_lossy().to_string(),
+        size,
+    })
+}
+
+/// 打包参数与输入状态的指纹。`options.password` 须为合并密钥文件后的实际密码。
+/// 管道与套接字输入无法重读，返回 `None`（不续传）。
+pub(crate) fn fingerprint(options: &SplitOptions) -> Option<String> {
+    let input_path = Path::new(&options.input_path);
+    if source::is_stream_source(input_path) {
+        return None;
+    }
+    let (size, modified, files) = input_stats(input_path)?;
+    let mut value = json!({
+        "inputPath": options.input_path,
+        "inputSize": size,
+        "inputModified": modified,
+        "inputFiles": files,
+        "packMode": options.pack_mode,
+        "splitBy": options.split_by,
+        "sizeBytes": options.size_bytes,
+        "count": options.count,
+        "dirSplitMode": options.dir_split_mode,
+        "compressionLevel": options.compression_level,
+        "sevenZipVolumes": options.seven_zip_volumes,
+        "numberedVolumes": options.numbered_volumes,
+        "compressionMethod": options.compression_method,
+        "aesStrength": options.aes_strength,
+        "encryptNames": options.encrypt_names,
+        "ageRecipients": options.age_recipients,
+        "zstdDictionary": options.zstd_dictionary,
+        "scriptPath": options.script_path,
+        "filterCommand": options.filter_command,
+        "postProcessors": options.post_processors,
+        "gpgRecipients": options.gpg_recipients,
+        "perPartKeys": options.per_part_keys,
+        "hashAlgorithm": options.hash_algorithm,
+        "par2Redundancy": options.par2_redundancy,
+        "redundancyParts": options.redundancy_parts,
+        "xorParity": options.xor_parity,
+    });
+    if let Some(password) = options
+        .password
+        .as_deref()
+        .filter(|value| !value.is_empty())
+    {
+        let salt = Sha256::digest(value.to_string().as_bytes());
+        value["passwordVerifier"] = json!(password_verifier(password, &salt)?);
+    }
+    Some(format!("{:x}", Sha256::digest(value.to_string().as_bytes())))
+}
+
+/// 密码的校验值：Argon2id 派生，盐取其余参数的摘要，同一组参数与密码每次得到相同的值。
+/// 检查点文件可能被别人读到，校验值不能让人低成本地逐个尝试密码。
+fn password_verifier(password: &str, salt: &[u8]) -> Option<String> {
+    let mut output = [0u8; 32];
+    Argon2::new(Algorithm::Argon2id, Version::V0x13, Params::default())
+        .hash_password_into(password.as_bytes(), salt, &mut output)
+        .map_err(|err| log::warn!("无法计算密码校验值，不续传：{}", err))
+        .ok()?;
+    Some(output.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+/// 输入的总大小、最近修改时间（Unix 毫秒）与文件数；目录递归统计。
+pub(crate) fn input_stats(path: &Path) -> Option<(u64, u64, u64)> {
+    let meta = fs::metadata(path).ok()?;
+    let modified = meta
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    if !meta.is_dir() {
+        return Some((meta.len(), modified, 1));
+    }
+    let mut totals = (0u64, modified, 0u64);
+    for entry in fs::read_dir(path).ok()? {
+        let (size, modified, files) = input_stats(&entry.ok()?.path())?;
+        totals = (totals.0 + size, totals.1.max(modified), totals.2 + files);
+    }
+    Some(totals)
+}
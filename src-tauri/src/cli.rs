@@ -0,0 +1,697 @@
+//! 命令行模式：`split` / `merge` 子命令，输入输出可以是标准输入输出（`-`），
+//! 便于接入数据库导出等管道，数据无需先完整落盘。
+//!
+//! 输入也可以是命名管道（FIFO）或套接字路径。流式处理（输入为 `-`、管道或套接字，
+//! 或输出为 `-`）只支持 `raw`（直接按大小切分、不压缩）与 `zstd-then-split`，
+//! 其他打包方式需要随机读写，输入输出必须是文件或目录。
+
+use std::{
+    cmp,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    append,
+    batch::{restore_all_groups_blocking, BatchRestoreResult},
+    collect_part_group,
+    conflicts::ConflictAction,
+    decrypt::{decrypt_parts_blocking, DecryptOptions},
+    ensure_parts_dir,
+    i18n::{self, tr, Msg},
+    index,
+    integrity::{self, HashAlgorithm},
+    jobs::JobContext,
+    part_reader::PartReader,
+    memory_budget, part_stem, process_file_blocking, raw,
+    reencrypt::{reencrypt_parts_blocking, ReencryptOptions},
+    rejoin, restore_parts_blocking,
+    secrets::SecretString,
+    seekable, sfx, signing, source,
+    stream_split::PartWriter,
+    test_archive::{test_archive_blocking, TestArchiveOptions},
+    tuning,
+    RestoreOptions, SplitOptions,
+};
+
+#[derive(Default)]
+struct CliArgs {
+    positional: Vec<String>,
+    mode: Option<String>,
+    size: Option<u64>,
+    count: Option<u64>,
+    name: Option<String>,
+    output: Option<String>,
+    part: Option<usize>,
+    part_to: Option<String>,
+    password: Option<String>,
+    level: Option<i64>,
+    method: Option<String>,
+    aes: Option<u16>,
+    encrypt_names: bool,
+    keyfile: Option<String>,
+    new_password: Option<String>,
+    new_keyfile: Option<String>,
+    age_recipients: Vec<String>,
+    age_identities: Vec<String>,
+    gpg_recipients: Vec<String>,
+    gpg: bool,
+    max_memory: Option<u64>,
+    no_adaptive_io: bool,
+    no_resume: bool,
+    power_action: Option<String>,
+    background: bool,
+    hash: Option<String>,
+    par2: Option<u32>,
+    recovery_parts: Option<u32>,
+    parity: bool,
+    sign: Option<String>,
+    trusted_keys: Vec<String>,
+    include: Vec<String>,
+    exclude: Vec<String>,
+    extract_from_parts: bool,
+    extract_only: bool,
+    all_groups: bool,
+    missing_parts: Option<String>,
+    on_conflict: Option<String>,
+    filename_encoding: Option<String>,
+    urls: Vec<String>,
+    keep_downloads: bool,
+    require_signature: bool,
+    no_verify: bool,
+    extract: bool,
+    salvage: bool,
+    verify_after_split: bool,
+    destroy_source: bool,
+    secure_delete: bool,
+    seven_zip_volumes: bool,
+    per_part_keys: bool,
+    numbered_volumes: bool,
+    self_extracting: bool,
+    rejoin_scripts: bool,
+    zstd_dictionary: bool,
+}
+
+fn parse_args(args: &[String]) -> Result<CliArgs, String> {
+    let mut parsed = CliArgs::default();
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| tr!(MissingFlagValue, flag))
+        };
+        match arg.as_str() {
+            "--mode" => parsed.mode = Some(value(arg)?),
+            "--size" => parsed.size = Some(parse_size(&value(arg)?)?),
+            "--count" => {
+                let raw = value(arg)?;
+                parsed.count = Some(raw.parse().map_err(|_| tr!(InvalidCount, raw))?);
+            }
+            "--name" => parsed.name = Some(value(arg)?),
+            "--output" | "-o" => parsed.output = Some(value(arg)?),
+            "--part" => {
+                let raw = value(arg)?;
+                parsed.part = Some(raw.parse().map_err(|_| tr!(InvalidPartIndex, raw))?);
+            }
+            "--part-to" => parsed.part_to = Some(value(arg)?),
+            "--password" => parsed.password = Some(value(arg)?),
+            "--keyfile" => parsed.keyfile = Some(value(arg)?),
+            "--new-password" => parsed.new_password = Some(value(arg)?),
+            "--new-keyfile" => parsed.new_keyfile = Some(value(arg)?),
+            "--age-recipient" => parsed.age_recipients.push(value(arg)?),
+            "--age-identity" => parsed.age_identities.push(value(arg)?),
+            "--gpg-recipient" => parsed.gpg_recipients.push(value(arg)?),
+            "--gpg" => parsed.gpg = true,
+            "--level" => {
+                let raw = value(arg)?;
+                parsed.level = Some(raw.parse().map_err(|_| tr!(InvalidLevel, raw))?);
+            }
+            "--max-memory" => parsed.max_memory = Some(parse_size(&value(arg)?)?),
+            "--no-adaptive-io" => parsed.no_adaptive_io = true,
+            "--no-resume" => parsed.no_resume = true,
+            "--power-action" => parsed.power_action = Some(value(arg)?),
+            "--background" => parsed.background = true,
+            "--hash" => parsed.hash = Some(value(arg)?),
+            "--method" => parsed.method = Some(value(arg)?),
+            "--aes" => {
+                let raw = value(arg)?;
+                parsed.aes = Some(raw.parse().map_err(|_| tr!(UnknownAesStrength, raw))?);
+            }
+            "--encrypt-names" => parsed.encrypt_names = true,
+            "--par2" => {
+                let raw = value(arg)?;
+                parsed.par2 = Some(raw.parse().map_err(|_| tr!(InvalidPar2Redundancy, raw))?);
+            }
+            "--recovery-parts" => {
+                let raw = value(arg)?;
+                parsed.recovery_parts =
+                    Some(raw.parse().map_err(|_| tr!(InvalidRecoveryParts, raw))?);
+            }
+            "--parity" => parsed.parity = true,
+            "--sign" => parsed.sign = Some(value(arg)?),
+            "--trusted-key" => parsed.trusted_keys.push(value(arg)?),
+            "--include" => parsed.include.push(value(arg)?),
+            "--exclude" => parsed.exclude.push(value(arg)?),
+            "--extract-from-parts" => parsed.extract_from_parts = true,
+            "--all-groups" => parsed.all_groups = true,
+            "--missing-parts" => parsed.missing_parts = Some(value(arg)?),
+            "--on-conflict" => parsed.on_conflict = Some(value(arg)?),
+            "--filename-encoding" => parsed.filename_encoding = Some(value(arg)?),
+            "--url" => parsed.urls.push(value(arg)?),
+            "--keep-downloads" => parsed.keep_downloads = true,
+            "--extract-only" => {
+                parsed.extract = true;
+                parsed.extract_only = true;
+            }
+            "--require-signature" => parsed.require_signature = true,
+            "--no-verify" => parsed.no_verify = true,
+            "--extract" => parsed.extract = true,
+            "--salvage" => parsed.salvage = true,
+            "--verify-after-split" => parsed.verify_after_split = true,
+            "--destroy-source" => parsed.destroy_source = true,
+            "--secure-delete" => parsed.secure_delete = true,
+            "--7z-volumes" => parsed.seven_zip_volumes = true,
+            "--per-part-keys" => parsed.per_part_keys = true,
+            "--numbered-volumes" => parsed.numbered_volumes = true,
+            "--self-extracting" => parsed.self_extracting = true,
+            "--rejoin-scripts" => parsed.rejoin_scripts = true,
+            "--zstd-dictionary" => parsed.zstd_dictionary = true,
+            // 已在 `run` 中提前处理
+            "--lang" => {
+                value(arg)?;
+            }
+            "-" => parsed.positional.push(arg.clone()),
+            flag if flag.starts_with('-') => return Err(tr!(UnknownArgument, flag)),
+            _ => parsed.positional.push(arg.clone()),
+        }
+    }
+    Ok(parsed)
+}
+
+/// 解析 `100`、`64K`、`1.5G` 等大小写法。
+fn parse_size(value: &str) -> Result<u64, String> {
+    let trimmed = value.trim();
+    let (number, unit) = match trimmed.char_indices().find(|(_, c)| c.is_ascii_alphabetic()) {
+        Some((pos, _)) => trimmed.split_at(pos),
+        None => (trimmed, ""),
+    };
+    let multiplier: u64 = match unit.to_ascii_uppercase().trim_end_matches(['B', 'I']) {
+        "" => 1,
+        "K" => 1 << 10,
+        "M" => 1 << 20,
+        "G" => 1 << 30,
+        "T" => 1 << 40,
+        _ => return Err(tr!(InvalidSize, value)),
+    };
+    let number: f64 = number
+        .trim()
+        .parse()
+        .map_err(|_| tr!(InvalidSize, value))?;
+    let bytes = (number * multiplier as f64) as u64;
+    if bytes == 0 {
+        return Err(tr!(SizeNotPositive));
+    }
+    Ok(bytes)
+}
+
+/// 执行 `split` / `merge` 子命令，结果摘要输出到标准错误（标准输出可能用于数据）。
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let Some((command, rest)) = args.split_first() else {
+        return Err(tr!(CliUsage));
+    };
+    if let Some(pos) = rest.iter().position(|arg| arg == "--lang") {
+        let tag = rest
+            .get(pos + 1)
+            .ok_or_else(|| tr!(MissingFlagValue, "--lang"))?;
+        i18n::set_language_tag(tag)?;
+    }
+    if rest.iter().any(|arg| arg == "--help" || arg == "-h") {
+        println!("{}", Msg::CliUsage);
+        return Ok(());
+    }
+    let parsed = parse_args(rest)?;
+    match command.as_str() {
+        "split" => run_split(parsed),
+        "merge" => run_merge(parsed),
+        "test" => run_test(parsed),
+        "reencrypt" => run_reencrypt(parsed),
+        "decrypt" => run_decrypt(parsed),
+        "keygen" => run_keygen(parsed),
+        _ => Err(tr!(CliUsage)),
+    }
+}
+
+/// 测试分片组或压缩包，有文件出错时以错误退出。
+fn run_test(parsed: CliArgs) -> Result<(), String> {
+    let input = single_input(&parsed)?;
+    let options = TestArchiveOptions {
+        input_path: input,
+        merge_mode: parsed.mode,
+        password: parsed.password.map(SecretString::from),
+        keyfile: parsed.keyfile,
+    };
+    let report = test_archive_blocking(&JobContext::detached(), options)?;
+    for failure in &report.failures {
+        if failure.locations.is_empty() {
+            eprintln!("{}", tr!(EntryCorrupt, failure.entry, failure.error));
+        } else {
+            let locations = index::describe_ranges(&failure.locations);
+            eprintln!("{}", tr!(EntryCorruptAt, failure.entry, locations, failure.error));
+        }
+    }
+    if !report.ok {
+        return Err(tr!(TestFailedSummary, report.entries_tested, report.failures.len()));
+    }
+    eprintln!("{}", tr!(TestPassed, report.entries_tested));
+    Ok(())
+}
+
+/// 以新密码重新加密分片组，未给新密码时去掉加密。
+fn run_reencrypt(parsed: CliArgs) -> Result<(), String> {
+    let input = single_input(&parsed)?;
+    let options = ReencryptOptions {
+        input_path: input,
+        merge_mode: parsed.mode,
+        old_password: parsed.password.map(SecretString::from),
+        old_keyfile: parsed.keyfile,
+        new_password: parsed.new_password.map(SecretString::from),
+        new_keyfile: parsed.new_keyfile,
+    };
+    let report = reencrypt_parts_blocking(&JobContext::detached(), options)?;
+    if report.encrypted {
+        eprintln!("{}", tr!(ReencryptDone, report.output_files.len()));
+    } else {
+        eprintln!("{}", tr!(ReencryptDecrypted, report.output_files.len()));
+    }
+    Ok(())
+}
+
+/// 去掉分片组的加密，未给 `--output` 时原地替换。
+fn run_decrypt(parsed: CliArgs) -> Result<(), String> {
+    let input = single_input(&parsed)?;
+    let options = DecryptOptions {
+        input_path: input,
+        merge_mode: parsed.mode,
+        password: parsed.password.map(SecretString::from),
+        keyfile: parsed.keyfile,
+        age_identities: Some(parsed.age_identities),
+        output_dir: parsed.output,
+    };
+    let report = decrypt_parts_blocking(&JobContext::detached(), options)?;
+    eprintln!(
+        "{}",
+        tr!(DecryptDone, report.output_files.len(), report.pack_mode, report.parts_dir)
+    );
+    Ok(())
+}
+
+/// 生成签名密钥对：私钥写到给定路径，公钥写到 `<路径>.pub`。
+fn run_keygen(parsed: CliArgs) -> Result<(), String> {
+    let key_path = single_input(&parsed)?;
+    let info = signing::generate_key_file(Path::new(&key_path), false)?;
+    eprintln!("{}", tr!(SigningKeyGenerated, info.key_path, info.public_key_path, info.fingerprint));
+    Ok(())
+}
+
+fn single_input(parsed: &CliArgs) -> Result<String, String> {
+    match parsed.positional.as_slice() {
+        [input] => Ok(input.clone()),
+        [] => Err(tr!(MissingInput)),
+        _ => Err(tr!(TooManyInputs)),
+    }
+}
+
+fn run_split(parsed: CliArgs) -> Result<(), String> {
+    let input = single_input(&parsed)?;
+    let from_stdin = input == "-";
+    let to_stdout = match parsed.part_to.as_deref() {
+        None => false,
+        Some("stdout") | Some("-") => true,
+        Some(other) => return Err(tr!(PartToStdoutOnly, other)),
+    };
+    if to_stdout && parsed.part.is_none() {
+        return Err(tr!(PartToNeedsPart));
+    }
+    let streaming = from_stdin || to_stdout || source::is_stream_source(Path::new(&input));
+    let mode = parsed
+        .mode
+        .clone()
+        .unwrap_or_else(|| if streaming { "raw" } else { "zip-then-split" }.to_string());
+
+    if mode != "raw" && mode != "zstd-then-split" {
+        if streaming {
+            return Err(tr!(ModeNoStdio, mode));
+        }
+        let result = process_file_blocking(
+            &JobContext::detached(),
+            SplitOptions {
+                input_path: input,
+                output_dir: parsed.output.unwrap_or_else(|| ".".to_string()),
+                split_by: if parsed.count.is_some() { "count" } else { "size" }.to_string(),
+                size_bytes: parsed.size,
+                count: parsed.count,
+                pack_mode: mode,
+                password: parsed.password.map(SecretString::from),
+                dir_split_mode: None,
+                overwrite_parts: Some(true),
+                compression_level: parsed.level,
+                post_processors: None,
+                script_path: None,
+                filter_command: None,
+                gpg_recipients: Some(parsed.gpg_recipients).filter(|values| !values.is_empty()),
+                secret_job_id: None,
+                max_memory: parsed.max_memory,
+                adaptive_io: Some(!parsed.no_adaptive_io),
+                resume: Some(!parsed.no_resume),
+                power_action: parsed.power_action,
+                background_priority: Some(parsed.background),
+                hash_algorithm: parsed.hash,
+                par2_redundancy: parsed.par2,
+                redundancy_parts: parsed.recovery_parts,
+                xor_parity: Some(parsed.parity),
+                signing_key: parsed.sign,
+                verify_after_split: Some(parsed.verify_after_split),
+                destroy_source: Some(parsed.destroy_source),
+                secure_delete: Some(parsed.secure_delete),
+                seven_zip_volumes: Some(parsed.seven_zip_volumes),
+                per_part_keys: Some(parsed.per_part_keys),
+                numbered_volumes: Some(parsed.numbered_volumes),
+                self_extracting: Some(parsed.self_extracting),
+                rejoin_scripts: Some(parsed.rejoin_scripts),
+                zstd_dictionary: Some(parsed.zstd_dictionary),
+                aes_strength: parsed.aes,
+                encrypt_names: Some(parsed.encrypt_names),
+                keyfile: parsed.keyfile,
+                age_recipients: Some(parsed.age_recipients).filter(|values| !values.is_empty()),
+                compression_method: parsed.method,
+            },
+        )?;
+        for file in result.output_files.iter().chain(&result.restore_files) {
+            eprintln!("{}", file);
+        }
+        return Ok(());
+    }
+
+    if parsed.password.is_some() || parsed.keyfile.is_some() {
+        return Err(tr!(ModeNoPassword, mode));
+    }
+    // 流式 zstd 写入分片目录时同样写清单，可以签名
+    if parsed.sign.is_some() && (mode != "zstd-then-split" || to_stdout) {
+        return Err(tr!(SigningNeedsManifest));
+    }
+    let mut memory = memory_budget(parsed.max_memory)?;
+    let size = match (parsed.size, parsed.count) {
+        (Some(size), _) => size,
+        (None, Some(count)) if !from_stdin && !source::is_stream_source(Path::new(&input)) => {
+            if mode == "zstd-then-split" {
+                return Err(tr!(ZstdStreamSizeOnly));
+            }
+            let len = fs::metadata(&input).map_err(|e| e.to_string())?.len();
+            cmp::max(1, len.div_ceil(count.max(1)))
+        }
+        (None, Some(_)) => {
+            return Err(tr!(StreamNoCount))
+        }
+        (None, None) => return Err(tr!(SizeOrCountRequired)),
+    };
+
+    let name = match parsed.name.clone() {
+        Some(name) => name,
+        None if from_stdin => "stdin".to_string(),
+        None => Path::new(&input)
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .ok_or_else(|| tr!(CannotParseFileName))?,
+    };
+    let prefix = if mode == "zstd-then-split" {
+        format!("{}.zst", name)
+    } else {
+        name.clone()
+    };
+
+    let parts_dir = (!to_stdout).then(|| {
+        let output = PathBuf::from(parsed.output.clone().unwrap_or_else(|| ".".to_string()));
+        output.join(format!("{}.parts", name))
+    });
+    let mut writer = match &parts_dir {
+        None => PartWriter::to_stdout(size, parsed.part.unwrap_or(1)),
+        Some(parts_dir) => {
+            ensure_parts_dir(parts_dir, true)?;
+            if !parsed.no_adaptive_io {
+                let source = (!from_stdin).then(|| Path::new(&input));
+                memory = tuning::tune(memory, source, parts_dir);
+            }
+            PartWriter::to_dir(size, parts_dir.clone(), part_stem(&prefix, parsed.numbered_volumes))
+        }
+    };
+
+    let reader: Box<dyn Read> = if from_stdin {
+        Box::new(io::stdin().lock())
+    } else {
+        source::open_source(Path::new(&input)).map_err(|e| e.to_string())?
+    };
+    let mut reader = BufReader::new(reader);
+    let mut frame_table = None;
+    if mode == "zstd-then-split" {
+        let level = parsed.level.unwrap_or(3).clamp(1, 19) as i32;
+        let table = seekable::compress_seekable(
+            &mut reader,
+            &mut writer,
+            level,
+            seekable::DEFAULT_FRAME_SIZE,
+            seekable::max_workers(memory, seekable::DEFAULT_FRAME_SIZE),
+            None,
+            parsed.background,
+            |_| {},
+        )
+        .map_err(|e| e.to_string())?;
+        frame_table = Some(table);
+    } else {
+        io::copy(&mut reader, &mut writer).map_err(|e| e.to_string())?;
+    }
+    let files = writer.finish().map_err(|e| e.to_string())?;
+    for file in &files {
+        eprintln!("{}", file.display());
+    }
+    let Some(parts_dir) = &parts_dir else {
+        return Ok(());
+    };
+    let ctx = JobContext::detached();
+    if let Some(table) = &frame_table {
+        let hash = parsed.hash.as_deref().unwrap_or_default();
+        let algorithm =
+            HashAlgorithm::parse(hash).ok_or_else(|| tr!(UnknownHashAlgorithm, hash))?;
+        let parts: Vec<String> = files
+            .iter()
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+        integrity::write_part_manifest(&ctx, algorithm, parts_dir, &name, &mode, &parts)?;
+        seekable::record_frame_index(parts_dir, &name, table)?;
+        if let Some(key_path) = parsed.sign.as_deref() {
+            signing::sign_manifest(
+                &append::manifest_path(parts_dir, &name),
+                Path::new(key_path),
+            )?;
+        }
+    }
+    let mut restore_files = Vec::new();
+    if let (true, Some(first_part)) = (parsed.self_extracting, files.first()) {
+        restore_files.extend(sfx::write_restorer(&ctx, parts_dir, &mode, first_part, false, false)?);
+    }
+    if parsed.rejoin_scripts && !files.is_empty() {
+        let parts: Vec<String> = files
+            .iter()
+            .map(|file| file.to_string_lossy().to_string())
+            .collect();
+        restore_files.extend(rejoin::write_scripts(&ctx, parts_dir, &mode, &parts, true)?);
+    }
+    for file in &restore_files {
+        eprintln!("{}", file);
+    }
+    Ok(())
+}
+
+fn run_merge(parsed: CliArgs) -> Result<(), String> {
+    // 从网址合并时输入是下载目录，不需要输入路径
+    let input = if parsed.urls.is_empty() || !parsed.positional.is_empty() {
+        single_input(&parsed)?
+    } else {
+        String::new()
+    };
+    let from_stdin = input == "-";
+    let mode = parsed.mode.clone().unwrap_or_else(|| "raw".to_string());
+    let output = parsed
+        .output
+        .clone()
+        .unwrap_or_else(|| if from_stdin { "-" } else { "." }.to_string());
+    let stdio = from_stdin || output == "-";
+    let through_restore = match mode.as_str() {
+        "zstd-then-split" => false,
+        // 输出到目录时走完整的合并流程，可按清单或校验文件核对
+        "raw" => !stdio && Path::new(&output).is_dir(),
+        _ => true,
+    };
+
+    // 缺片合并要按清单与其余分片推算缺失的范围，只在完整的合并流程中进行
+    if parsed.missing_parts.is_some() && (!through_restore || stdio) {
+        return Err(tr!(MissingPartsNeedsRestore));
+    }
+    if !parsed.urls.is_empty() && (!through_restore || stdio) {
+        return Err(tr!(UrlsNeedRestore));
+    }
+    if through_restore {
+        if stdio {
+            return Err(tr!(ModeNoStdio, mode));
+        }
+        let options = RestoreOptions {
+            input_path: input,
+            output_dir: output,
+            merge_mode: mode,
+            password: parsed.password.map(SecretString::from),
+            keyfile: parsed.keyfile,
+            age_identities: Some(parsed.age_identities),
+            auto_extract: Some(parsed.extract),
+            filter_command: None,
+            gpg_decrypt: Some(parsed.gpg),
+            secret_job_id: None,
+            max_memory: parsed.max_memory,
+            adaptive_io: Some(!parsed.no_adaptive_io),
+            verify: Some(!parsed.no_verify),
+            trusted_keys: Some(parsed.trusted_keys),
+            require_signature: Some(parsed.require_signature),
+            salvage: Some(parsed.salvage),
+            secure_delete: Some(parsed.secure_delete),
+            include: Some(parsed.include),
+            exclude: Some(parsed.exclude),
+            extract_from_parts: Some(parsed.extract_from_parts),
+            keep_archive: Some(!parsed.extract_only),
+            missing_parts: parsed.missing_parts,
+            on_conflict: parsed.on_conflict,
+            filename_encoding: parsed.filename_encoding,
+            resume: Some(!parsed.no_resume),
+            urls: Some(parsed.urls),
+            keep_downloads: Some(parsed.keep_downloads),
+        };
+        if parsed.all_groups {
+            return print_batch(restore_all_groups_blocking(&JobContext::detached(), options)?);
+        }
+        let result = restore_parts_blocking(&JobContext::detached(), options)?;
+        for file in &result.output_files {
+            eprintln!("{}", file);
+        }
+        if let Some(archive_type) = &result.archive_type {
+            eprintln!("{}", tr!(DetectedArchiveType, archive_type));
+        }
+        if let Some(signature) = result.signature.as_ref().filter(|status| !status.trusted) {
+            eprintln!("{}", tr!(SignatureUntrustedWarning, signature.fingerprint));
+        }
+        if !result.lost_entries.is_empty() {
+            eprintln!("{}", tr!(SalvageLostEntries, result.lost_entries.len()));
+            for entry in &result.lost_entries {
+                eprintln!("  {}", entry);
+            }
+        }
+        for decision in &result.conflicts {
+            match (&decision.action, &decision.renamed_to) {
+                (ConflictAction::Skip, _) => eprintln!("{}", tr!(ConflictSkipped, decision.path)),
+                (ConflictAction::Rename, Some(renamed)) => {
+                    eprintln!("{}", tr!(ConflictRenamed, decision.path, renamed))
+                }
+                _ => {}
+            }
+        }
+        if !result.missing_ranges.is_empty() {
+            eprintln!("{}", tr!(MissingRanges, result.missing_ranges.len()));
+            for range in &result.missing_ranges {
+                let estimated = if range.estimated {
+                    tr!(MissingRangeEstimated)
+                } else {
+                    String::new()
+                };
+                eprintln!(
+                    "  {}{}",
+                    tr!(MissingRangeLine, range.part, range.start, range.end),
+                    estimated
+                );
+            }
+        }
+        if result.verified == Some(false) {
+            return Err(tr!(RestoreVerifyFailed, result.mismatches.join(", ")));
+        }
+        return Ok(());
+    }
+
+    // 流式合并不读清单，也就无法核对签名
+    if parsed.require_signature {
+        return Err(tr!(SignatureRequired));
+    }
+    let mut writer: Box<dyn Write> = if output == "-" {
+        Box::new(BufWriter::new(io::stdout().lock()))
+    } else {
+        let target = merge_target(&input, &output, &mode)?;
+        eprintln!("{}", target.display());
+        Box::new(BufWriter::new(File::create(&target).map_err(|e| e.to_string())?))
+    };
+
+    if from_stdin {
+        let mut reader = BufReader::new(io::stdin().lock());
+        if mode == "zstd-then-split" {
+            // 帧索引是可跳过帧，普通 zstd 解码器会直接忽略
+            zstd::stream::copy_decode(&mut reader, &mut writer).map_err(|e| e.to_string())?;
+        } else {
+            io::copy(&mut reader, &mut writer).map_err(|e| e.to_string())?;
+        }
+    } else {
+        let part_group = if mode == "raw" {
+            raw::collect_raw_group(Path::new(&input))?
+        } else {
+            collect_part_group(Path::new(&input))?
+        };
+        let paths: Vec<PathBuf> = part_group.parts.iter().map(|part| part.path.clone()).collect();
+        let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+        if mode == "zstd-then-split" {
+            let mut reader = seekable::SeekableReader::open_parts(&paths, reader).map_err(|e| e.to_string())?;
+            io::copy(&mut reader, &mut writer).map_err(|e| e.to_string())?;
+        } else {
+            io::copy(&mut BufReader::new(reader), &mut writer).map_err(|e| e.to_string())?;
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
+
+/// 列出批量合并中各组的输出或失败原因，有失败的组时报错。
+fn print_batch(batch: BatchRestoreResult) -> Result<(), String> {
+    for group in &batch.groups {
+        match (&group.result, &group.error) {
+            (Some(result), _) => {
+                for file in &result.output_files {
+                    eprintln!("{}", file);
+                }
+            }
+            (None, Some(err)) => eprintln!("{}", tr!(BatchGroupFailed, group.name, err)),
+            (None, None) => {}
+        }
+    }
+    if batch.failed > 0 {
+        return Err(tr!(BatchRestoreFailed, batch.failed, batch.groups.len()));
+    }
+    Ok(())
+}
+
+/// `--output` 为目录时按分片名推出合并后的文件名，否则直接作为文件路径。
+fn merge_target(input: &str, output: &str, mode: &str) -> Result<PathBuf, String> {
+    let output = PathBuf::from(output);
+    if !output.is_dir() {
+        return Ok(output);
+    }
+    let part_group = collect_part_group(Path::new(input))?;
+    let prefix = part_group.prefix.trim_end_matches('.');
+    let name = if mode == "zstd-then-split" {
+        prefix.strip_suffix(".zst").unwrap_or(prefix)
+    } else {
+        prefix
+    };
+    Ok(output.join(name))
+}
@@ -0,0 +1,186 @@
+//! 解压时的同名文件：输出目录中已有与条目同名的文件时，按 [`ConflictPolicy`] 覆盖、跳过、
+//! 改名（`名称 (1).扩展名`）或逐个询问界面（参见 [`crate::prompts`]）。每次的处理记入合并结果的
+//! `conflicts`。未设置时照旧直接覆盖，也不记录。
+//!
+//! 询问时事件的 `kind` 为 `extractConflict`，附带 `path`（已有文件）、`entry`（zip 中的条目名）、
+//! `size` 与 `existingSize`；回答为 `{ action: "overwrite" | "skip" | "rename", applyToAll }`，
+//! `applyToAll` 为 `true` 时其余同名文件不再询问，按同样的方式处理。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs,
+    path::{Path, PathBuf},
+    sync::Mutex,
+};
+
+use crate::{i18n::tr, jobs::JobContext, prompts};
+
+/// 如何处理输出目录中已有的同名文件。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ConflictPolicy {
+    Overwrite,
+    Skip,
+    Rename,
+    Ask,
+}
+
+impl ConflictPolicy {
+    /// 解析 `onConflict` 参数：`overwrite`、`skip`、`rename` 或 `ask`，未设置时为 `None`。
+    pub(crate) fn parse(value: Option<&str>) -> Result<Option<ConflictPolicy>, String> {
+        match value.unwrap_or_default() {
+            "" => Ok(None),
+            "overwrite" => Ok(Some(ConflictPolicy::Overwrite)),
+            "skip" => Ok(Some(ConflictPolicy::Skip)),
+            "rename" => Ok(Some(ConflictPolicy::Rename)),
+            "ask" => Ok(Some(ConflictPolicy::Ask)),
+            other => Err(tr!(UnknownConflictPolicy, other)),
+        }
+    }
+}
+
+/// 对一个同名文件实际采取的处理。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum ConflictAction {
+    Overwrite,
+    Skip,
+    Rename,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ConflictDecision {
+    /// 已有的同名文件。
+    pub(crate) path: String,
+    pub(crate) action: ConflictAction,
+    /// 改名时条目实际写入的路径。
+    pub(crate) renamed_to: Option<String>,
+}
+
+/// 一次操作中的处理方式与已做的决定；询问时选了“全部”后处理方式随之改变。
+#[derive(Debug)]
+pub(crate) struct Conflicts {
+    policy: Mutex<ConflictPolicy>,
+    decisions: Mutex<Vec<ConflictDecision>>,
+}
+
+impl Conflicts {
+    pub(crate) fn new(policy: ConflictPolicy) -> Self {
+        Conflicts {
+            policy: Mutex::new(policy),
+            decisions: Mutex::default(),
+        }
+    }
+
+    pub(crate) fn take_decisions(&self) -> Vec<ConflictDecision> {
+        std::mem::take(&mut *self.decisions.lock().unwrap_or_else(|e| e.into_inner()))
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ConflictPrompt<'a> {
+    path: String,
+    entry: &'a str,
+    size: u64,
+    existing_size: u64,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ConflictAnswer {
+    action: ConflictAction,
+    #[serde(default)]
+    apply_to_all: bool,
+}
+
+/// 条目 `entry` 将写入 `out_path`：返回实际写入的路径，跳过时返回 `None`。
+/// `out_path` 不存在或未设置处理方式时原样返回。
+pub(crate) fn resolve(
+    ctx: &JobContext,
+    out_path: &Path,
+    entry: &str,
+    size: u64,
+) -> Result<Option<PathBuf>, String> {
+    let Some(conflicts) = ctx.conflicts() else {
+        return Ok(Some(out_path.to_path_buf()));
+    };
+    let Ok(existing) = fs::metadata(out_path) else {
+        return Ok(Some(out_path.to_path_buf()));
+    };
+    let policy = *conflicts.policy.lock().unwrap_or_else(|e| e.into_inner());
+    let action = match policy {
+        // 同名的是目录时无法覆盖，改名写入
+        ConflictPolicy::Overwrite if existing.is_dir() => ConflictAction::Rename,
+        ConflictPolicy::Overwrite => ConflictAction::Overwrite,
+        ConflictPolicy::Skip => ConflictAction::Skip,
+        ConflictPolicy::Rename => ConflictAction::Rename,
+        ConflictPolicy::Ask => {
+            let answer = prompts::ask(
+                ctx,
+                "extractConflict",
+                ConflictPrompt {
+                    path: out_path.to_string_lossy().to_string(),
+                    entry,
+                    size,
+                    existing_size: existing.len(),
+                },
+            )?;
+            let answer: ConflictAnswer =
+                serde_json::from_value(answer).map_err(|e| tr!(InvalidPromptAnswer, e))?;
+            if answer.apply_to_all {
+                *conflicts.policy.lock().unwrap_or_else(|e| e.into_inner()) = match answer.action {
+                    ConflictAction::Overwrite => ConflictPolicy::Overwrite,
+                    ConflictAction::Skip => ConflictPolicy::Skip,
+                    ConflictAction::Rename => ConflictPolicy::Rename,
+                };
+            }
+            match answer.action {
+                ConflictAction::Overwrite if existing.is_dir() => ConflictAction::Rename,
+                action => action,
+            }
+        }
+    };
+
+    let renamed = (action == ConflictAction::Rename).then(|| free_name(out_path));
+    match &renamed {
+        Some(path) => log::info!(
+            "已有同名文件 {}，改名为 {}",
+            out_path.display(),
+            path.display()
+        ),
+        None => log::info!("已有同名文件 {}：{:?}", out_path.display(), action),
+    }
+    conflicts
+        .decisions
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .push(ConflictDecision {
+            path: out_path.to_string_lossy().to_string(),
+            action,
+            renamed_to: renamed
+                .as_ref()
+                .map(|path| path.to_string_lossy().to_string()),
+        });
+    Ok(match action {
+        ConflictAction::Overwrite => Some(out_path.to_path_buf()),
+        ConflictAction::Skip => None,
+        ConflictAction::Rename => renamed,
+    })
+}
+
+/// 在同一目录中找一个不存在的名称：`名称 (1).扩展名`、`名称 (2).扩展名`……
+fn free_name(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    (1u32..)
+        .map(|n| path.with_file_name(format!("{} ({}){}", stem, n, extension)))
+        .find(|candidate| !candidate.exists())
+        .unwrap_or_else(|| path.to_path_buf())
+}
@@ -0,0 +1,1205 @@
+//! 加密容器（`container-then-split`）：zip 的 AES 加密用 PBKDF2 派生密钥，迭代次数固定为 1000，
+//! 弱密码很容易被离线穷举。此方式改用本程序自有的容器格式：Argon2id 派生密钥，内容按块以
+//! XChaCha20-Poly1305 加密认证，再按字节切分为 `<名称>.fspc.part-001`……目录先打成不加密的 zip 再装入容器。
+//!
+//! 容器布局（整数均为小端序）：
+//!
+//! | 偏移 | 长度 | 内容                                                  |
+//! |------|------|-------------------------------------------------------|
+//! | 0    | 4    | 魔数 `FSPC`                                           |
+//! | 4    | 1    | 格式版本，目前为 1                                    |
+//! | 5    | 1    | 密钥派生算法，1 = Argon2id（v0x13）                   |
+//! | 6    | 1    | 加密算法，1 = XChaCha20-Poly1305                      |
+//! | 7    | 1    | 保留，为 0                                            |
+//! | 8    | 4    | Argon2 内存（KiB）                                    |
+//! | 12   | 4    | Argon2 迭代次数                                       |
+//! | 16   | 4    | Argon2 并行度                                         |
+//! | 20   | 4    | 每块明文字节数                                        |
+//! | 24   | 16   | 盐                                                    |
+//! | 40   | 16   | nonce 前缀                                            |
+//!
+//! 文件头之后依次是各块的密文与 16 字节认证标签，最后一块可以不满。第 `i` 块（从 0 起）的 nonce 为
+//! `nonce 前缀 || i（u64 大端序）`，附加认证数据为 `文件头 56 字节 || 是否最后一块（u8）`，
+//! 块被调换、删除或末尾被截断都无法通过认证。参数都写在文件头中，以后调整默认值不影响旧容器。
+//! 完整性清单的 `container` 字段记录同样的布局与本次使用的参数。
+//!
+//! 开启 `perPartKeys` 时改为每份分片各是一个独立的容器（版本 2）：文件头后多 8 字节，记录分片序号
+//! （从 1 起）与总份数（均为 u32 小端序）。各分片共用盐与 Argon2 参数，Argon2id 的输出作为主密钥，
+//! 第 `n` 份的密钥为 `HKDF-SHA256(主密钥, info = "FSPC part" || n（u32 大端序）)`，nonce 前缀各自随机。
+//! 任何一份都能单独解密、核对，拿到一份分片的子密钥也无助于解密其他分片。
+
+use argon2::{Algorithm, Argon2, Params, Version};
+use chacha20poly1305::{
+    aead::{Aead, KeyInit, Payload},
+    Key, XChaCha20Poly1305, XNonce,
+};
+use hkdf::Hkdf;
+use rand::RngCore;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+use zeroize::Zeroizing;
+
+use crate::password_prompt::{self, PasswordError};
+use crate::{
+    archive_from_checkpoint, collect_part_group, compute_parts, emit_progress, extract,
+    finish_checkpoint, format_part_index, i18n::tr, integrity, jobs::JobContext, mark_archive_done,
+    mark_part_done, output_base_name, part_from_checkpoint, part_reader::PartReader, part_stem,
+    prepare_parts_dir, remove_temp, split_raw_file, zip_directory, RestoreResult, SplitResult,
+};
+
+pub(crate) const EXTENSION: &str = ".fspc";
+
+const MAGIC: &[u8; 4] = b"FSPC";
+const VERSION: u8 = 1;
+/// 每份分片各自独立加密的版本。
+const PART_VERSION: u8 = 2;
+const KDF_ARGON2ID: u8 = 1;
+const AEAD_XCHACHA20_POLY1305: u8 = 1;
+const HEADER_LEN: usize = 56;
+/// 版本 2 的文件头另有分片序号与总份数。
+const PART_HEADER_LEN: usize = HEADER_LEN + 8;
+/// 派生分片密钥时 HKDF 的 info 前缀。
+const PART_KEY_INFO: &[u8] = b"FSPC part";
+const SALT_LEN: usize = 16;
+const NONCE_PREFIX_LEN: usize = 16;
+const TAG_LEN: usize = 16;
+const KEY_LEN: usize = 32;
+
+const CHUNK_SIZE: u32 = 1024 * 1024;
+const MEMORY_KIB: u32 = 64 * 1024;
+const ITERATIONS: u32 = 3;
+const PARALLELISM: u32 = 4;
+
+/// Argon2id 的内存（KiB）、迭代次数、并行度与盐。
+type KdfParams = (u32, u32, u32, [u8; SALT_LEN]);
+
+/// 打开容器时接受的参数上限，避免被改过的文件头让派生密钥耗尽内存或长时间卡住。
+const MAX_MEMORY_KIB: u32 = 4 * 1024 * 1024;
+const MAX_ITERATIONS: u32 = 64;
+const MAX_CHUNK_SIZE: u32 = 64 * 1024 * 1024;
+
+#[derive(Clone, Copy)]
+struct Header {
+    memory_kib: u32,
+    iterations: u32,
+    parallelism: u32,
+    chunk_size: u32,
+    salt: [u8; SALT_LEN],
+    nonce_prefix: [u8; NONCE_PREFIX_LEN],
+    /// 独立加密的分片（版本 2）所属的序号与总份数。
+    part: Option<PartInfo>,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+struct PartInfo {
+    index: u32,
+    count: u32,
+}
+
+impl Header {
+    /// 默认参数与新生成的随机盐、nonce 前缀。
+    fn generate() -> Header {
+        let mut rng = rand::thread_rng();
+        let mut salt = [0u8; SALT_LEN];
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        rng.fill_bytes(&mut salt);
+        rng.fill_bytes(&mut nonce_prefix);
+        Header {
+            memory_kib: MEMORY_KIB,
+            iterations: ITERATIONS,
+            parallelism: PARALLELISM,
+            chunk_size: CHUNK_SIZE,
+            salt,
+            nonce_prefix,
+            part: None,
+        }
+    }
+
+    /// 同一组分片中的第 `index` 份：沿用盐与参数，另取随机的 nonce 前缀。
+    fn for_part(&self, index: u32, count: u32) -> Header {
+        let mut nonce_prefix = [0u8; NONCE_PREFIX_LEN];
+        rand::thread_rng().fill_bytes(&mut nonce_prefix);
+        Header {
+            nonce_prefix,
+            part: Some(PartInfo { index, count }),
+            ..*self
+        }
+    }
+
+    fn to_bytes(self) -> Vec<u8> {
+        let mut bytes = vec![0u8; HEADER_LEN];
+        bytes[..4].copy_from_slice(MAGIC);
+        bytes[4] = if self.part.is_some() {
+            PART_VERSION
+        } else {
+            VERSION
+        };
+        bytes[5] = KDF_ARGON2ID;
+        bytes[6] = AEAD_XCHACHA20_POLY1305;
+        bytes[8..12].copy_from_slice(&self.memory_kib.to_le_bytes());
+        bytes[12..16].copy_from_slice(&self.iterations.to_le_bytes());
+        bytes[16..20].copy_from_slice(&self.parallelism.to_le_bytes());
+        bytes[20..24].copy_from_slice(&self.chunk_size.to_le_bytes());
+        bytes[24..40].copy_from_slice(&self.salt);
+        bytes[40..56].copy_from_slice(&self.nonce_prefix);
+        if let Some(part) = self.part {
+            bytes.extend_from_slice(&part.index.to_le_bytes());
+            bytes.extend_from_slice(&part.count.to_le_bytes());
+        }
+        bytes
+    }
+
+    /// 读取并解析文件头，返回文件头及其原始字节（用作附加认证数据）。
+    fn read_from(reader: &mut impl Read) -> Result<(Header, Vec<u8>), String> {
+        let mut bytes = vec![0u8; HEADER_LEN];
+        reader
+            .read_exact(&mut bytes)
+            .map_err(|_| tr!(ContainerInvalid))?;
+        if &bytes[..4] == MAGIC && bytes[4] == PART_VERSION {
+            bytes.resize(PART_HEADER_LEN, 0);
+            reader
+                .read_exact(&mut bytes[HEADER_LEN..])
+                .map_err(|_| tr!(ContainerInvalid))?;
+        }
+        let header = Header::parse(&bytes)?;
+        Ok((header, bytes))
+    }
+
+    fn parse(bytes: &[u8]) -> Result<Header, String> {
+        if &bytes[..4] != MAGIC {
+            return Err(tr!(ContainerInvalid));
+        }
+        let version = bytes[4];
+        if !matches!(version, VERSION | PART_VERSION)
+            || bytes[5] != KDF_ARGON2ID
+            || bytes[6] != AEAD_XCHACHA20_POLY1305
+        {
+            return Err(tr!(ContainerUnsupported, version));
+        }
+        let u32_at = |offset: usize| {
+            u32::from_le_bytes([
+                bytes[offset],
+                bytes[offset + 1],
+                bytes[offset + 2],
+                bytes[offset + 3],
+            ])
+        };
+        let header = Header {
+            memory_kib: u32_at(8),
+            iterations: u32_at(12),
+            parallelism: u32_at(16),
+            chunk_size: u32_at(20),
+            salt: bytes[24..40].try_into().unwrap_or_default(),
+            nonce_prefix: bytes[40..56].try_into().unwrap_or_default(),
+            part: (version == PART_VERSION).then(|| PartInfo {
+                index: u32_at(56),
+                count: u32_at(60),
+            }),
+        };
+        if header.memory_kib > MAX_MEMORY_KIB
+            || header.iterations > MAX_ITERATIONS
+            || header.chunk_size == 0
+            || header.chunk_size > MAX_CHUNK_SIZE
+            || header
+                .part
+                .is_some_and(|part| part.index == 0 || part.index > part.count)
+        {
+            return Err(tr!(ContainerUnsupported, version));
+        }
+        Ok(header)
+    }
+
+    /// Argon2id 派生主密钥。
+    fn master_key(&self, password: &str) -> Result<Zeroizing<[u8; KEY_LEN]>, String> {
+        let params = Params::new(
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            Some(KEY_LEN),
+        )
+        .map_err(|e| tr!(ContainerKdfFailed, e))?;
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(password.as_bytes(), &self.salt, key.as_mut())
+            .map_err(|e| tr!(ContainerKdfFailed, e))?;
+        Ok(key)
+    }
+
+    /// 整个容器直接用主密钥；独立加密的分片用按序号派生的子密钥。
+    fn cipher(&self, master: &[u8; KEY_LEN]) -> Result<XChaCha20Poly1305, String> {
+        let Some(part) = self.part else {
+            return Ok(XChaCha20Poly1305::new(Key::from_slice(master)));
+        };
+        let mut info = PART_KEY_INFO.to_vec();
+        info.extend_from_slice(&part.index.to_be_bytes());
+        let mut key = Zeroizing::new([0u8; KEY_LEN]);
+        Hkdf::<Sha256>::new(None, master)
+            .expand(&info, key.as_mut())
+            .map_err(|e| tr!(ContainerKdfFailed, e))?;
+        Ok(XChaCha20Poly1305::new(Key::from_slice(key.as_ref())))
+    }
+
+    /// 派生主密钥所用的参数；相同时主密钥相同。
+    fn kdf_params(&self) -> KdfParams {
+        (
+            self.memory_kib,
+            self.iterations,
+            self.parallelism,
+            self.salt,
+        )
+    }
+
+    fn nonce(&self, index: u64) -> XNonce {
+        let mut nonce = [0u8; NONCE_PREFIX_LEN + 8];
+        nonce[..NONCE_PREFIX_LEN].copy_from_slice(&self.nonce_prefix);
+        nonce[NONCE_PREFIX_LEN..].copy_from_slice(&index.to_be_bytes());
+        *XNonce::from_slice(&nonce)
+    }
+}
+
+/// 按密码与文件头派生密钥；一组分片共用盐与参数，主密钥只派生一次。
+pub(crate) struct Keyring {
+    password: Zeroizing<String>,
+    master: Option<(KdfParams, Zeroizing<[u8; KEY_LEN]>)>,
+}
+
+impl Keyring {
+    pub(crate) fn new(password: &str) -> Keyring {
+        Keyring {
+            password: Zeroizing::new(password.to_string()),
+            master: None,
+        }
+    }
+
+    fn cipher(&mut self, header: &Header) -> Result<XChaCha20Poly1305, String> {
+        let params = header.kdf_params();
+        let cached = self.master.as_ref().filter(|(cached, _)| *cached == params);
+        let master = match cached {
+            Some((_, master)) => master.clone(),
+            None => {
+                let master = header.master_key(&self.password)?;
+                self.master = Some((params, master.clone()));
+                master
+            }
+        };
+        header.cipher(&master)
+    }
+}
+
+/// 每块的附加认证数据：文件头加上是否最后一块。
+fn associated_data(header_bytes: &[u8], last: bool) -> Vec<u8> {
+    let mut aad = header_bytes.to_vec();
+    aad.push(u8::from(last));
+    aad
+}
+
+/// 写入完整性清单 `container` 字段的格式说明。
+fn describe(header: &Header) -> Value {
+    let mut description = json!({
+        "format": "FSPC",
+        "version": VERSION,
+        "headerBytes": HEADER_LEN,
+        "header": "magic \"FSPC\" [4] | version u8 | kdf u8 | aead u8 | reserved u8 | memoryKib u32le | iterations u32le | parallelism u32le | chunkSize u32le | salt [16] | noncePrefix [16]",
+        "kdf": {
+            "algorithm": "argon2id",
+            "version": 0x13,
+            "memoryKib": header.memory_kib,
+            "iterations": header.iterations,
+            "parallelism": header.parallelism,
+            "saltBytes": SALT_LEN,
+            "keyBytes": KEY_LEN,
+            "input": "UTF-8 password",
+        },
+        "aead": {
+            "algorithm": "xchacha20-poly1305",
+            "nonce": "noncePrefix || chunkIndex u64be",
+            "associatedData": "header || lastChunk u8",
+            "tagBytes": TAG_LEN,
+        },
+        "chunkSize": header.chunk_size,
+        "chunks": "ciphertext || tag per chunk of chunkSize plaintext bytes; the last chunk may be shorter",
+    });
+    if header.part.is_some() {
+        description["version"] = json!(PART_VERSION);
+        description["headerBytes"] = json!(PART_HEADER_LEN);
+        description["header"] = json!("magic \"FSPC\" [4] | version u8 | kdf u8 | aead u8 | reserved u8 | memoryKib u32le | iterations u32le | parallelism u32le | chunkSize u32le | salt [16] | noncePrefix [16] | partIndex u32le | partCount u32le");
+        description["perPartKeys"] = json!(true);
+        description["parts"] = json!("every part is a standalone container; all parts share the salt and Argon2 parameters, each has its own noncePrefix");
+        description["partKey"] = json!({
+            "algorithm": "hkdf-sha256",
+            "ikm": "argon2id output",
+            "salt": "none",
+            "info": "\"FSPC part\" || partIndex u32be",
+            "keyBytes": KEY_LEN,
+        });
+    }
+    description
+}
+
+/// 把 `source` 加密为容器写到 `target`，返回所用的文件头。
+fn seal(ctx: &JobContext, source: &Path, target: &Path, password: &str) -> Result<Header, String> {
+    let header = Header::generate();
+    let cipher = Keyring::new(password).cipher(&header)?;
+    let total = fs::metadata(source).map_err(|e| e.to_string())?.len();
+    let mut reader = BufReader::new(File::open(source).map_err(|e| e.to_string())?);
+    let mut writer = BufWriter::new(File::create(target).map_err(|e| e.to_string())?);
+    emit_progress(ctx, "encrypt", 0, total, 0, 0, tr!(EncryptingContainer));
+    let mut processed = 0u64;
+    seal_stream(
+        ctx,
+        &mut reader,
+        total,
+        &mut writer,
+        &header,
+        &cipher,
+        |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "encrypt",
+                processed,
+                total,
+                0,
+                0,
+                tr!(EncryptingContainer),
+            );
+        },
+    )?;
+    writer.flush().map_err(|e| e.to_string())?;
+    Ok(header)
+}
+
+/// 写入文件头，再把 `reader` 中的 `total` 字节逐块加密写入 `writer`；`on_progress` 收到每块的明文字节数。
+fn seal_stream(
+    ctx: &JobContext,
+    reader: &mut impl Read,
+    total: u64,
+    writer: &mut impl Write,
+    header: &Header,
+    cipher: &XChaCha20Poly1305,
+    mut on_progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let header_bytes = header.to_bytes();
+    let chunk_size = header.chunk_size as u64;
+    let chunks = total.div_ceil(chunk_size).max(1);
+    writer.write_all(&header_bytes).map_err(|e| e.to_string())?;
+    let mut buffer = Zeroizing::new(vec![0u8; header.chunk_size as usize]);
+    let mut processed = 0u64;
+    for index in 0..chunks {
+        ctx.check_cancelled()?;
+        let len = chunk_size.min(total - processed) as usize;
+        reader
+            .read_exact(&mut buffer[..len])
+            .map_err(|e| e.to_string())?;
+        let aad = associated_data(&header_bytes, index + 1 == chunks);
+        let sealed = cipher
+            .encrypt(
+                &header.nonce(index),
+                Payload {
+                    msg: &buffer[..len],
+                    aad: &aad,
+                },
+            )
+            .map_err(|e| e.to_string())?;
+        writer.write_all(&sealed).map_err(|e| e.to_string())?;
+        processed += len as u64;
+        on_progress(len as u64);
+    }
+    Ok(())
+}
+
+/// 每份分片写满 `part_size` 字节时最多能装入的明文字节数。
+fn part_capacity(part_size: u64, chunk_size: u64) -> Result<u64, String> {
+    let min_size = (PART_HEADER_LEN + TAG_LEN) as u64 + 1;
+    if part_size < min_size {
+        return Err(tr!(ContainerPartTooSmall, min_size));
+    }
+    let body = part_size - PART_HEADER_LEN as u64;
+    let sealed_chunk = chunk_size + TAG_LEN as u64;
+    let rest = body % sealed_chunk;
+    Ok(body / sealed_chunk * chunk_size + rest.saturating_sub(TAG_LEN as u64))
+}
+
+/// 把 `source` 按明文切分，每份以各自派生的子密钥加密为独立的容器，分片命名为 `<part_stem><序号>`。
+/// 返回分片数、分片路径与第一份的文件头。
+#[allow(clippy::too_many_arguments)]
+fn seal_parts(
+    ctx: &JobContext,
+    source: &Path,
+    parts_dir: &Path,
+    part_stem: &str,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    password: &str,
+) -> Result<(usize, Vec<String>, Header), String> {
+    let total = fs::metadata(source).map_err(|e| e.to_string())?.len();
+    let capacity = match (split_by, size_bytes) {
+        ("size", Some(size)) => Some(part_capacity(size, CHUNK_SIZE as u64)?),
+        _ => size_bytes,
+    };
+    let (plain_size, parts) = compute_parts(total, split_by, capacity, count)?;
+    let count = u32::try_from(parts).map_err(|e| e.to_string())?;
+    let width = parts.to_string().len().max(3);
+    let part_path =
+        |index: usize| parts_dir.join(format!("{}{}", part_stem, format_part_index(index, width)));
+
+    // 续传时沿用已完成分片的盐与参数，各分片才能共用同一个主密钥
+    let first_path = part_path(1);
+    let base = if part_from_checkpoint(ctx, 1, &first_path) {
+        let mut file = File::open(&first_path).map_err(|e| e.to_string())?;
+        Header::read_from(&mut file)?.0
+    } else {
+        Header::generate()
+    };
+    let mut keyring = Keyring::new(password);
+    let mut reader = BufReader::new(File::open(source).map_err(|e| e.to_string())?);
+    let mut output_files = Vec::with_capacity(parts);
+    let mut processed = 0u64;
+    for part_index in 1..=parts {
+        let part_size = plain_size.min(total.saturating_sub(processed));
+        if part_size == 0 && split_by != "count" {
+            break;
+        }
+        let path = part_path(part_index);
+        if part_from_checkpoint(ctx, part_index, &path) {
+            reader
+                .seek_relative(part_size as i64)
+                .map_err(|e| e.to_string())?;
+            processed += part_size;
+            output_files.push(path.to_string_lossy().to_string());
+            continue;
+        }
+        emit_progress(
+            ctx,
+            "encrypt",
+            processed,
+            total,
+            part_index,
+            parts,
+            tr!(PreparePart, part_index),
+        );
+
+        let header = base.for_part(part_index as u32, count);
+        let cipher = keyring.cipher(&header)?;
+        ctx.track_output(&path);
+        let mut writer = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        seal_stream(
+            ctx,
+            &mut reader,
+            part_size,
+            &mut writer,
+            &header,
+            &cipher,
+            |delta| {
+                processed += delta;
+                emit_progress(
+                    ctx,
+                    "encrypt",
+                    processed,
+                    total,
+                    part_index,
+                    parts,
+                    tr!(EncryptingPart, part_index),
+                );
+            },
+        )?;
+        writer.flush().map_err(|e| e.to_string())?;
+        mark_part_done(ctx, part_index, &path)?;
+        output_files.push(path.to_string_lossy().to_string());
+    }
+    Ok((parts, output_files, base.for_part(1, count)))
+}
+
+/// 逐块解密容器的读取器；块认证失败时读取出错。
+struct Opened<R> {
+    inner: R,
+    cipher: XChaCha20Poly1305,
+    header: Header,
+    header_bytes: Vec<u8>,
+    index: u64,
+    sealed: Vec<u8>,
+    plain: Zeroizing<Vec<u8>>,
+    pos: usize,
+    /// 判断是否最后一块时多读的一个字节，属于下一块。
+    pending: Option<u8>,
+    done: bool,
+}
+
+/// 读取文件头、派生密钥并解密第一块；第一块无法认证时为 [`PasswordError::Wrong`]。
+fn open<R: Read>(mut inner: R, keyring: &mut Keyring) -> Result<Opened<R>, PasswordError> {
+    let (header, header_bytes) = Header::read_from(&mut inner)?;
+    let cipher = keyring.cipher(&header)?;
+    let sealed = vec![0u8; header.chunk_size as usize + TAG_LEN];
+    let mut opened = Opened {
+        inner,
+        cipher,
+        header,
+        header_bytes,
+        index: 0,
+        sealed,
+        plain: Zeroizing::new(Vec::new()),
+        pos: 0,
+        pending: None,
+        done: false,
+    };
+    opened.next_chunk().map_err(|_| PasswordError::Wrong)?;
+    Ok(opened)
+}
+
+impl<R: Read> Opened<R> {
+    fn next_chunk(&mut self) -> io::Result<()> {
+        let want = self.sealed.len();
+        let mut filled = 0;
+        if let Some(byte) = self.pending.take() {
+            self.sealed[0] = byte;
+            filled = 1;
+        }
+        filled += read_full(&mut self.inner, &mut self.sealed[filled..want])?;
+        let last = if filled < want {
+            true
+        } else {
+            let mut byte = [0u8; 1];
+            match read_full(&mut self.inner, &mut byte)? {
+                0 => true,
+                _ => {
+                    self.pending = Some(byte[0]);
+                    false
+                }
+            }
+        };
+        let corrupt = || {
+            io::Error::new(
+                io::ErrorKind::InvalidData,
+                tr!(ContainerCorrupt, self.index + 1),
+            )
+        };
+        if filled < TAG_LEN {
+            return Err(corrupt());
+        }
+        let aad = associated_data(&self.header_bytes, last);
+        let plain = self
+            .cipher
+            .decrypt(
+                &self.header.nonce(self.index),
+                Payload {
+                    msg: &self.sealed[..filled],
+                    aad: &aad,
+                },
+            )
+            .map_err(|_| corrupt())?;
+        self.plain = Zeroizing::new(plain);
+        self.pos = 0;
+        self.index += 1;
+        self.done = last;
+        Ok(())
+    }
+}
+
+impl<R: Read> Read for Opened<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        while self.pos == self.plain.len() {
+            if self.done {
+                return Ok(0);
+            }
+            self.next_chunk()?;
+        }
+        let len = buf.len().min(self.plain.len() - self.pos);
+        buf[..len].copy_from_slice(&self.plain[self.pos..self.pos + len]);
+        self.pos += len;
+        Ok(len)
+    }
+}
+
+/// 按顺序打开一组分片的解密读取器：整个容器切分的分片直接拼接后解密，独立加密的分片逐份解密后拼接。
+/// 密码错误时在这里报错。
+pub(crate) fn open_parts(
+    paths: &[PathBuf],
+    password: &str,
+) -> Result<Box<dyn Read>, PasswordError> {
+    let mut keyring = Keyring::new(password);
+    let mut reader = PartReader::from_files(paths).map_err(|e| e.to_string())?;
+    let mut version = [0u8; 5];
+    reader
+        .read_exact(&mut version)
+        .map_err(|_| tr!(ContainerInvalid))?;
+    if version[4] != PART_VERSION {
+        let reader = PartReader::from_files(paths).map_err(|e| e.to_string())?;
+        return Ok(Box::new(open(BufReader::new(reader), &mut keyring)?));
+    }
+    let mut parts = SeparateParts {
+        paths: paths.to_vec(),
+        next: 0,
+        keyring,
+        current: None,
+    };
+    parts.open_next()?;
+    Ok(Box::new(parts))
+}
+
+/// 逐份解密独立加密的分片。
+struct SeparateParts {
+    paths: Vec<PathBuf>,
+    next: usize,
+    keyring: Keyring,
+    current: Option<Opened<BufReader<File>>>,
+}
+
+impl SeparateParts {
+    /// 打开下一份分片并确认其序号与总份数，没有更多分片时返回 `false`。
+    fn open_next(&mut self) -> Result<bool, PasswordError> {
+        let Some(path) = self.paths.get(self.next) else {
+            self.current = None;
+            return Ok(false);
+        };
+        let expected = PartInfo {
+            index: self.next as u32 + 1,
+            count: self.paths.len() as u32,
+        };
+        self.current = Some(open_part(path, expected, &mut self.keyring)?);
+        self.next += 1;
+        Ok(true)
+    }
+}
+
+/// 打开一份独立加密的分片并确认其序号与总份数与 `expected` 一致。
+fn open_part(
+    path: &Path,
+    expected: PartInfo,
+    keyring: &mut Keyring,
+) -> Result<Opened<BufReader<File>>, PasswordError> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy();
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let opened = match open(BufReader::new(file), keyring) {
+        Ok(opened) => opened,
+        // 第一份解密失败多半是密码错误，原样报告
+        Err(err) if expected.index == 1 => return Err(err),
+        Err(err) => return Err(tr!(ContainerPartFailed, name, String::from(err)).into()),
+    };
+    match opened.header.part {
+        Some(part) if part == expected => Ok(opened),
+        Some(part) => Err(tr!(ContainerPartMismatch, name, part.index, part.count).into()),
+        None => Err(tr!(ContainerInvalid).into()),
+    }
+}
+
+impl Read for SeparateParts {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        loop {
+            let Some(current) = self.current.as_mut() else {
+                return Ok(0);
+            };
+            let read_len = current.read(buf).map_err(|err| {
+                let name = self.paths[self.next - 1].file_name().unwrap_or_default();
+                io::Error::new(
+                    err.kind(),
+                    tr!(ContainerPartFailed, name.to_string_lossy(), err),
+                )
+            })?;
+            if read_len > 0 || buf.is_empty() {
+                return Ok(read_len);
+            }
+            self.open_next()
+                .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, String::from(err)))?;
+        }
+    }
+}
+
+/// 更换独立加密的分片的密码：逐份以原密码解密、以新密码重新加密。新的一组分片共用新的盐。
+pub(crate) struct Resealer {
+    old: Keyring,
+    new: Keyring,
+    base: Header,
+    count: u32,
+}
+
+impl Resealer {
+    pub(crate) fn new(
+        old_password: &str,
+        new_password: &str,
+        count: usize,
+    ) -> Result<Resealer, String> {
+        Ok(Resealer {
+            old: Keyring::new(old_password),
+            new: Keyring::new(new_password),
+            base: Header::generate(),
+            count: u32::try_from(count).map_err(|e| e.to_string())?,
+        })
+    }
+
+    /// 把第 `index` 份分片 `source` 重新加密写到 `target`；`on_progress` 收到每块的明文字节数。
+    pub(crate) fn reseal(
+        &mut self,
+        ctx: &JobContext,
+        index: usize,
+        source: &Path,
+        target: &Path,
+        on_progress: impl FnMut(u64),
+    ) -> Result<(), String> {
+        let mut file = File::open(source).map_err(|e| e.to_string())?;
+        if Header::read_from(&mut file)?.0.part.is_none() {
+            return Err(tr!(ReencryptNeedsPerPartKeys));
+        }
+        let expected = PartInfo {
+            index: index as u32,
+            count: self.count,
+        };
+        let mut reader = open_part(source, expected, &mut self.old)?;
+        let sealed_len = fs::metadata(source).map_err(|e| e.to_string())?.len();
+        let total = plain_len(sealed_len, reader.header.chunk_size as u64)
+            .ok_or_else(|| tr!(ContainerCorrupt, 1))?;
+        let header = self.base.for_part(expected.index, expected.count);
+        let cipher = self.new.cipher(&header)?;
+        let mut writer = BufWriter::new(File::create(target).map_err(|e| e.to_string())?);
+        seal_stream(
+            ctx,
+            &mut reader,
+            total,
+            &mut writer,
+            &header,
+            &cipher,
+            on_progress,
+        )?;
+        writer.flush().map_err(|e| e.to_string())
+    }
+}
+
+/// 由独立加密的分片大小推算其中的明文字节数；大小不符合格式时返回 `None`。
+fn plain_len(sealed_len: u64, chunk_size: u64) -> Option<u64> {
+    let body = sealed_len.checked_sub(PART_HEADER_LEN as u64)?;
+    let sealed_chunk = chunk_size + TAG_LEN as u64;
+    let rest = body % sealed_chunk;
+    if rest > 0 && rest < TAG_LEN as u64 {
+        return None;
+    }
+    Some(body / sealed_chunk * chunk_size + rest.saturating_sub(TAG_LEN as u64))
+}
+
+/// 尽量读满 `buf`，返回读到的字节数，只有到达末尾时才少于 `buf` 的长度。
+fn read_full(reader: &mut impl Read, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(len) => filled += len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+pub(crate) fn container_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    password: Option<&str>,
+    overwrite_parts: bool,
+    compression_level: Option<i64>,
+    hash_algorithm: integrity::HashAlgorithm,
+    numbered_volumes: bool,
+    per_part_keys: bool,
+) -> Result<SplitResult, String> {
+    let password = password.ok_or_else(|| tr!(ContainerNeedsPassword))?;
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    let is_dir = metadata.is_dir();
+    if !is_dir && metadata.len() == 0 {
+        return Err(tr!(EmptyInputFile));
+    }
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+    // 目录先打成不加密的 zip，装入容器的就是这个 zip，合并后按扩展名识别并解压
+    let payload_name = if is_dir {
+        format!("{}.zip", base_name)
+    } else {
+        base_name.clone()
+    };
+    let payload_path = if is_dir {
+        output_dir.join(&payload_name)
+    } else {
+        input_path.to_path_buf()
+    };
+    let container_name = format!("{}{}", payload_name, EXTENSION);
+    let container_path = output_dir.join(&container_name);
+    let stem = part_stem(&container_name, numbered_volumes);
+    if is_dir {
+        ctx.track_output(&payload_path);
+    }
+    let mut header = None;
+    let (parts, output_files) = if per_part_keys {
+        // 各分片直接由明文加密而成，不生成整个容器
+        if is_dir && !archive_from_checkpoint(ctx, &payload_path) {
+            zip_directory(
+                ctx,
+                input_path,
+                &payload_path,
+                None,
+                ctx.compression(),
+                compression_level,
+                "zip",
+            )?;
+            mark_archive_done(ctx, &payload_path)?;
+        }
+        let (parts, output_files, first) = seal_parts(
+            ctx,
+            &payload_path,
+            &parts_dir,
+            &stem,
+            split_by,
+            size_bytes,
+            count,
+            password,
+        )?;
+        header = Some(first);
+        (parts, output_files)
+    } else {
+        ctx.track_output(&container_path);
+        if !archive_from_checkpoint(ctx, &container_path) {
+            if is_dir {
+                zip_directory(
+                    ctx,
+                    input_path,
+                    &payload_path,
+                    None,
+                    ctx.compression(),
+                    compression_level,
+                    "zip",
+                )?;
+            }
+            header = Some(seal(ctx, &payload_path, &container_path, password)?);
+            mark_archive_done(ctx, &container_path)?;
+        }
+        split_raw_file(
+            ctx,
+            &container_path,
+            &parts_dir,
+            &stem,
+            split_by,
+            size_bytes,
+            count,
+        )?
+    };
+    finish_checkpoint(ctx);
+
+    // 沿用检查点中的容器时重新读取其文件头
+    let header = match header {
+        Some(header) => Ok(header),
+        None => read_header(&container_path),
+    };
+    let manifest = header.and_then(|header| {
+        let part_sha256s = integrity::write_manifest(
+            ctx,
+            hash_algorithm,
+            &parts_dir,
+            &base_name,
+            "container-then-split",
+            &payload_path,
+            is_dir.then_some(input_path),
+            &output_files,
+        )?;
+        integrity::record_field(&parts_dir, &base_name, "container", describe(&header))?;
+        Ok(part_sha256s)
+    });
+    if !per_part_keys {
+        remove_temp(ctx, &container_path, false);
+    }
+    if is_dir {
+        // 先压缩出的 zip 是明文
+        remove_temp(ctx, &payload_path, true);
+    }
+    let part_sha256s = manifest?;
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir,
+        base_name,
+        part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+fn read_header(path: &Path) -> Result<Header, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    Ok(Header::read_from(&mut file)?.0)
+}
+
+/// 按顺序读取分片并直接解密，不落地合并后的容器；`auto_extract` 时解压装入的 zip 等压缩包。
+/// 整个容器切分的分片与独立加密的分片都能合并。缺少密码或密码不对时可向界面重新要密码。
+pub(crate) fn restore(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let part_group = collect_part_group(input_path)?;
+    let container_name = part_group.prefix.trim_end_matches('.');
+    let merged_name = container_name
+        .strip_suffix(EXTENSION)
+        .unwrap_or(container_name)
+        .to_string();
+    let paths: Vec<_> = part_group
+        .parts
+        .iter()
+        .map(|part| part.path.clone())
+        .collect();
+    let total_bytes: u64 = paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum();
+    emit_progress(ctx, "merge", 0, total_bytes, 0, 0, tr!(DecryptingContainer));
+    let mut reader = password_prompt::retry(ctx, input_path, password, |password| {
+        open_parts(&paths, password.ok_or(PasswordError::Required)?)
+    })?;
+
+    let temp_path = output_dir.join(format!("{}.merge.tmp", merged_name));
+    ctx.track_output(&temp_path);
+    let mut writer = BufWriter::new(File::create(&temp_path).map_err(|e| e.to_string())?);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut processed = 0u64;
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err.to_string()),
+        };
+        writer
+            .write_all(&buffer[..read_len])
+            .map_err(|e| e.to_string())?;
+        // 明文略小于分片总大小，进度按分片大小估算
+        processed = (processed + read_len as u64).min(total_bytes);
+        emit_progress(
+            ctx,
+            "merge",
+            processed,
+            total_bytes,
+            0,
+            0,
+            tr!(DecryptingContainer),
+        );
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    drop(writer);
+
+    let merged_path = output_dir.join(&merged_name);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let mut output_files = vec![merged_path.to_string_lossy().to_string()];
+    let mut extracted_dir = None;
+    if auto_extract && extract::ArchiveKind::detect(&merged_path)?.is_some() {
+        let target_dir = output_dir.join(extract::strip_extension(&merged_name));
+        extract::extract_archive(ctx, &merged_path, &target_dir, None, None)?;
+        extracted_dir = Some(target_dir.to_string_lossy().to_string());
+        output_files.push(target_dir.to_string_lossy().to_string());
+    }
+
+    Ok(RestoreResult {
+        merged_file: Some(merged_path.to_string_lossy().to_string()),
+        extracted_dir,
+        output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    const PASSWORD: &str = "correct horse";
+    /// 测试用的小块，几十字节即可跨越多块。
+    const TEST_CHUNK: usize = 64;
+    const SEALED_CHUNK: usize = TEST_CHUNK + TAG_LEN;
+
+    /// 默认盐与 nonce 前缀，Argon2id 改用最小参数，测试不必等待派生密钥。
+    fn test_header() -> Header {
+        Header {
+            memory_kib: 8,
+            iterations: 1,
+            parallelism: 1,
+            chunk_size: TEST_CHUNK as u32,
+            ..Header::generate()
+        }
+    }
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 7 % 251) as u8).collect()
+    }
+
+    fn seal_bytes(header: &Header, password: &str, plain: &[u8]) -> Vec<u8> {
+        let cipher = Keyring::new(password).cipher(header).unwrap();
+        let mut sealed = Vec::new();
+        seal_stream(
+            &JobContext::detached(),
+            &mut &plain[..],
+            plain.len() as u64,
+            &mut sealed,
+            header,
+            &cipher,
+            |_| {},
+        )
+        .unwrap();
+        sealed
+    }
+
+    fn read_all(reader: &mut impl Read) -> Result<Vec<u8>, PasswordError> {
+        let mut plain = Vec::new();
+        reader
+            .read_to_end(&mut plain)
+            .map_err(|e| PasswordError::Other(e.to_string()))?;
+        Ok(plain)
+    }
+
+    fn open_bytes(sealed: &[u8], password: &str) -> Result<Vec<u8>, PasswordError> {
+        read_all(&mut open(Cursor::new(sealed), &mut Keyring::new(password))?)
+    }
+
+    /// 把各份明文以 `base` 的盐与参数独立加密，写到临时目录中，返回按序号排列的分片路径。
+    fn write_parts(name: &str, base: &Header, plains: &[Vec<u8>]) -> Vec<PathBuf> {
+        let dir = std::env::temp_dir().join(format!("fspc-{}-{}", name, std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let count = plains.len() as u32;
+        plains
+            .iter()
+            .enumerate()
+            .map(|(idx, plain)| {
+                let header = base.for_part(idx as u32 + 1, count);
+                let path = dir.join(format!("a.bin.fspc.part-{:03}", idx + 1));
+                fs::write(&path, seal_bytes(&header, PASSWORD, plain)).unwrap();
+                path
+            })
+            .collect()
+    }
+
+    fn remove_parts(paths: &[PathBuf]) {
+        if let Some(dir) = paths.first().and_then(|path| path.parent()) {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let header = test_header();
+        for len in [0, 1, TEST_CHUNK, 3 * TEST_CHUNK + 10] {
+            let plain = sample(len);
+            let sealed = seal_bytes(&header, PASSWORD, &plain);
+            assert_eq!(
+                open_bytes(&sealed, PASSWORD).unwrap(),
+                plain,
+                "length {}",
+                len
+            );
+        }
+
+        let plains = vec![sample(2 * TEST_CHUNK), sample(TEST_CHUNK + 5), sample(7)];
+        let paths = write_parts("round-trip", &test_header(), &plains);
+        let result = open_parts(&paths, PASSWORD).and_then(|mut reader| read_all(&mut reader));
+        remove_parts(&paths);
+        assert_eq!(result.unwrap(), plains.concat());
+    }
+
+    #[test]
+    fn wrong_password_is_typed() {
+        let sealed = seal_bytes(&test_header(), PASSWORD, &sample(3 * TEST_CHUNK));
+        assert!(matches!(
+            open_bytes(&sealed, "wrong"),
+            Err(PasswordError::Wrong)
+        ));
+
+        let paths = write_parts("wrong-password", &test_header(), &[sample(10), sample(10)]);
+        let result = open_parts(&paths, "wrong").map(|_| ());
+        remove_parts(&paths);
+        assert!(matches!(result, Err(PasswordError::Wrong)));
+    }
+
+    #[test]
+    fn truncation_at_chunk_boundary_is_rejected() {
+        let sealed = seal_bytes(&test_header(), PASSWORD, &sample(3 * TEST_CHUNK));
+        assert_eq!(sealed.len(), HEADER_LEN + 3 * SEALED_CHUNK);
+        // 去掉整块后剩下的最后一块当初不是按最后一块认证的
+        let truncated = &sealed[..HEADER_LEN + 2 * SEALED_CHUNK];
+        assert!(matches!(
+            open_bytes(truncated, PASSWORD),
+            Err(PasswordError::Other(_))
+        ));
+    }
+
+    #[test]
+    fn reordered_chunks_and_parts_are_rejected() {
+        let mut sealed = seal_bytes(&test_header(), PASSWORD, &sample(4 * TEST_CHUNK));
+        let second = HEADER_LEN + SEALED_CHUNK;
+        let (left, right) = sealed[second..].split_at_mut(SEALED_CHUNK);
+        left.swap_with_slice(&mut right[..SEALED_CHUNK]);
+        assert!(matches!(
+            open_bytes(&sealed, PASSWORD),
+            Err(PasswordError::Other(_))
+        ));
+
+        let plains = vec![sample(10), sample(20), sample(30)];
+        let paths = write_parts("reordered", &test_header(), &plains);
+        let swapped = [paths[1].clone(), paths[0].clone(), paths[2].clone()];
+        let first_swapped = open_parts(&swapped, PASSWORD).map(|_| ());
+        let swapped = [paths[0].clone(), paths[2].clone(), paths[1].clone()];
+        let tail_swapped =
+            open_parts(&swapped, PASSWORD).and_then(|mut reader| read_all(&mut reader));
+        remove_parts(&paths);
+        assert!(matches!(first_swapped, Err(PasswordError::Other(_))));
+        assert!(matches!(tail_swapped, Err(PasswordError::Other(_))));
+    }
+
+    #[test]
+    fn per_part_keys_differ() {
+        let base = test_header();
+        let first = base.for_part(1, 2);
+        // 沿用第一份的 nonce 前缀，密文只可能因密钥不同而不同
+        let second = Header {
+            nonce_prefix: first.nonce_prefix,
+            ..base.for_part(2, 2)
+        };
+        let plain = sample(TEST_CHUNK);
+        let sealed_first = seal_bytes(&first, PASSWORD, &plain);
+        let sealed_second = seal_bytes(&second, PASSWORD, &plain);
+        let body = PART_HEADER_LEN..PART_HEADER_LEN + TEST_CHUNK;
+        assert_ne!(sealed_first[body.clone()], sealed_second[body]);
+
+        // 第一份的密钥解不开第二份
+        let mut keyring = Keyring::new(PASSWORD);
+        let cipher = keyring.cipher(&first).unwrap();
+        let aad = associated_data(&second.to_bytes(), true);
+        let opened = cipher.decrypt(
+            &second.nonce(0),
+            Payload {
+                msg: &sealed_second[PART_HEADER_LEN..],
+                aad: &aad,
+            },
+        );
+        assert!(opened.is_err());
+    }
+}
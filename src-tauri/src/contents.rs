@@ -0,0 +1,199 @@
+//! 预览压缩包内容：不解压，读取合并后的 zip、分卷 zip 或完整分片组的中央目录，按目录结构
+//! 返回条目树（名称、大小、压缩后大小、修改时间与是否加密），供界面在合并前展示。
+//!
+//! 分片组经 [`crate::partial`] 直接在分片上定位读取，不拼接；`split-then-zip` 的分片需要是
+//! 仅存储且不加密的，其余情况与 [`crate::index`] 一样无法列出。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fs::File,
+    io::{BufReader, Read, Seek},
+    path::PathBuf,
+    time::SystemTime,
+};
+use zip::ZipArchive;
+
+use crate::{
+    collect_part_group, detect, entry_times::EntryTimes, i18n::tr, index, parse_part_name,
+    partial::open_archive_from_layout, spanned,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ListContentsOptions {
+    /// 合并后的 zip、分卷 zip 中的一卷，或分片文件、分片目录。
+    pub(crate) input_path: String,
+    /// 分片的打包方式：`zip-then-split`、`split-then-zip`、`zstd-then-split` 或 `spanned-zip`；
+    /// 输入为合并后的 zip 时不需要。
+    pub(crate) merge_mode: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ContentsReport {
+    /// 文件条目数（不含目录）。
+    pub(crate) files: usize,
+    /// 全部文件解压后与压缩后的总大小。
+    pub(crate) total_size: u64,
+    pub(crate) compressed_size: u64,
+    /// 是否有加密的条目，合并后解压需要密码。
+    pub(crate) encrypted: bool,
+    /// 顶层的条目，目录在前，同级按名称排序。
+    pub(crate) entries: Vec<ContentNode>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ContentNode {
+    pub(crate) name: String,
+    /// zip 中的完整路径，`/` 分隔。
+    pub(crate) path: String,
+    pub(crate) is_dir: bool,
+    /// 目录为其中全部文件之和。
+    pub(crate) size: u64,
+    pub(crate) compressed_size: u64,
+    /// 本地时间的修改时间 `YYYY-MM-DD HH:MM:SS`，优先取扩展时间戳；没有记录或只是推断出的目录时为 `None`。
+    pub(crate) modified: Option<String>,
+    /// 目录为其中是否有加密的文件。
+    pub(crate) encrypted: bool,
+    pub(crate) children: Vec<ContentNode>,
+}
+
+pub(crate) fn list_contents_blocking(
+    options: ListContentsOptions,
+) -> Result<ContentsReport, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let merge_mode = detect::resolve(options.merge_mode.as_deref(), &input_path)?;
+    let merge_mode = merge_mode.as_deref().unwrap_or_default();
+    let is_part = input_path.is_dir()
+        || input_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_part_name)
+            .is_some();
+    if merge_mode == "spanned-zip"
+        || (merge_mode == "zip-then-split" && spanned::is_spanned_set(&input_path))
+    {
+        let volumes = spanned::find_volumes(&input_path)?;
+        let reader = spanned::SpannedReader::open(&volumes)?;
+        list_zip(ZipArchive::new(BufReader::new(reader)).map_err(|e| e.to_string())?)
+    } else if is_part {
+        if merge_mode.is_empty() {
+            return Err(tr!(ContentsNeedsMode));
+        }
+        let part_group = collect_part_group(&input_path)?;
+        let layout = index::part_layout(&part_group, merge_mode)?;
+        list_zip(open_archive_from_layout(&part_group, merge_mode, &layout)?)
+    } else {
+        let file = File::open(&input_path).map_err(|e| e.to_string())?;
+        list_zip(ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?)
+    }
+}
+
+/// 扁平记录的节点，子节点为下标；全部条目读完后再转为嵌套的 [`ContentNode`]。
+struct FlatNode {
+    node: ContentNode,
+    children: Vec<usize>,
+}
+
+fn list_zip<R: Read + Seek>(mut archive: ZipArchive<R>) -> Result<ContentsReport, String> {
+    let mut nodes: Vec<FlatNode> = Vec::new();
+    let mut roots: Vec<usize> = Vec::new();
+    let mut lookup: HashMap<String, usize> = HashMap::new();
+    let mut files = 0usize;
+
+    for index in 0..archive.len() {
+        let raw = archive.by_index_raw(index).map_err(|e| e.to_string())?;
+        let components: Vec<&str> = raw
+            .name()
+            .split('/')
+            .filter(|part| !part.is_empty() && *part != ".")
+            .collect();
+        if components.is_empty() || components.contains(&"..") {
+            continue;
+        }
+        let is_dir = raw.is_dir();
+        if !is_dir {
+            files += 1;
+        }
+        let mut parent: Option<usize> = None;
+        let mut path = String::new();
+        for (depth, name) in components.iter().enumerate() {
+            if !path.is_empty() {
+                path.push('/');
+            }
+            path.push_str(name);
+            let is_leaf = depth + 1 == components.len();
+            let idx = match lookup.get(&path) {
+                Some(&idx) => idx,
+                None => {
+                    nodes.push(FlatNode {
+                        node: ContentNode {
+                            name: name.to_string(),
+                            path: path.clone(),
+                            is_dir: !is_leaf || is_dir,
+                            size: 0,
+                            compressed_size: 0,
+                            modified: None,
+                            encrypted: false,
+                            children: Vec::new(),
+                        },
+                        children: Vec::new(),
+                    });
+                    let idx = nodes.len() - 1;
+                    match parent {
+                        Some(parent) => nodes[parent].children.push(idx),
+                        None => roots.push(idx),
+                    }
+                    lookup.insert(path.clone(), idx);
+                    idx
+                }
+            };
+            let node = &mut nodes[idx].node;
+            if is_leaf {
+                node.modified = EntryTimes::from_zip(&raw).modified().map(format_time);
+            }
+            if !is_dir {
+                // 文件的大小与加密标记累计到各级目录
+                node.size += raw.size();
+                node.compressed_size += raw.compressed_size();
+                node.encrypted |= raw.encrypted();
+            }
+            parent = Some(idx);
+        }
+    }
+
+    let mut slots: Vec<Option<FlatNode>> = nodes.into_iter().map(Some).collect();
+    let entries = build_tree(&mut slots, &roots);
+    Ok(ContentsReport {
+        files,
+        total_size: entries.iter().map(|node| node.size).sum(),
+        compressed_size: entries.iter().map(|node| node.compressed_size).sum(),
+        encrypted: entries.iter().any(|node| node.encrypted),
+        entries,
+    })
+}
+
+fn build_tree(slots: &mut [Option<FlatNode>], indices: &[usize]) -> Vec<ContentNode> {
+    let mut result = Vec::with_capacity(indices.len());
+    for &idx in indices {
+        let Some(flat) = slots[idx].take() else {
+            continue;
+        };
+        let mut node = flat.node;
+        node.children = build_tree(slots, &flat.children);
+        result.push(node);
+    }
+    result.sort_by(|a, b| b.is_dir.cmp(&a.is_dir).then_with(|| a.name.cmp(&b.name)));
+    result
+}
+
+fn format_time(value: SystemTime) -> String {
+    chrono::DateTime::<chrono::Local>::from(value)
+        .format("%Y-%m-%d %H:%M:%S")
+        .to_string()
+}
@@ -0,0 +1,311 @@
+//! 去掉分片的加密：把加密的分片组转为不加密的分片组，交给不便处理密码的接收方。可以原地替换，
+//! 也可以写到另一个目录、保留原分片。支持的打包方式：
+//! - `split-then-zip`：去掉每份分片 zip 中条目的加密，仍是 `split-then-zip` 分片；
+//! - `age-split`：逐份解密为 `<名称>.part-001`……，即 `raw-split` 分片；
+//! - `container-then-split`（两种布局）：按原分片大小切分解密出的明文，分片名去掉 `.fspc`，
+//!   同样成为 `raw-split` 分片，目录打包时合并得到的是其中的 zip。
+//!
+//! 其余方式的加密在整个 zip 或 7z 之中，需要合并后重新打包。有完整性清单时按新分片改写清单，
+//! 打包方式随之改变，原有签名失效；恢复分片、异或校验分片与 PAR2 文件需要重新生成。
+//! 进度阶段为 `decrypt`。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    age_split::{self, Unseal},
+    collect_part_group, container, emit_progress,
+    i18n::tr,
+    integrity,
+    jobs::JobContext,
+    keyfile,
+    reencrypt::{self, manifest_base_name},
+    secrets::SecretString,
+    PartSha256,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DecryptOptions {
+    /// 分片文件或分片目录。
+    pub(crate) input_path: String,
+    /// 分片的打包方式；未设置时按完整性清单中记录的打包方式。
+    pub(crate) merge_mode: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    pub(crate) keyfile: Option<String>,
+    /// 解密 `age-split` 分片的 age 身份文件。
+    pub(crate) age_identities: Option<Vec<String>>,
+    /// 写出不加密分片的目录；未设置时原地替换原分片。
+    pub(crate) output_dir: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DecryptReport {
+    pub(crate) parts_dir: String,
+    pub(crate) output_files: Vec<String>,
+    /// 不加密分片的打包方式：`split-then-zip` 或 `raw-split`。
+    pub(crate) pack_mode: String,
+    /// 按新分片改写清单后的校验值；没有完整性清单时为空。
+    pub(crate) part_sha256s: Vec<PartSha256>,
+}
+
+pub(crate) fn decrypt_parts_blocking(
+    ctx: &JobContext,
+    options: DecryptOptions,
+) -> Result<DecryptReport, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let manifest = integrity::find(&input_path)?;
+    let merge_mode = reencrypt::pack_mode(options.merge_mode.as_deref(), &input_path, &manifest)?;
+
+    let part_group = collect_part_group(&input_path)?;
+    let paths: Vec<PathBuf> = part_group
+        .parts
+        .iter()
+        .map(|part| part.path.clone())
+        .collect();
+    let parts_dir = paths
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| tr!(NoPartsFound))?;
+    let target_dir = match options.output_dir.as_deref().filter(|dir| !dir.is_empty()) {
+        Some(dir) => {
+            fs::create_dir_all(dir).map_err(|e| e.to_string())?;
+            PathBuf::from(dir)
+        }
+        None => parts_dir.clone(),
+    };
+    let in_place = same_dir(&target_dir, &parts_dir);
+    let sizes: Vec<u64> = paths
+        .iter()
+        .map(|path| fs::metadata(path).map(|meta| meta.len()).unwrap_or(0))
+        .collect();
+    let total_bytes: u64 = sizes.iter().sum();
+
+    let (pack_mode, targets) = match merge_mode.as_str() {
+        "split-then-zip" => {
+            let targets = target_paths(&paths, &target_dir, |name| Some(name.to_string()))?;
+            // 原地替换时先写临时文件，全部成功后再替换
+            let written: Vec<PathBuf> = if in_place {
+                targets
+                    .iter()
+                    .map(|path| reencrypt::temp_path(path))
+                    .collect()
+            } else {
+                targets.clone()
+            };
+            let mut processed = 0u64;
+            for (idx, (source, target)) in paths.iter().zip(&written).enumerate() {
+                ctx.check_cancelled()?;
+                emit_progress(
+                    ctx,
+                    "decrypt",
+                    processed,
+                    total_bytes,
+                    idx + 1,
+                    paths.len(),
+                    tr!(DecryptingPart, idx + 1),
+                );
+                ctx.track_output(target);
+                reencrypt::reencrypt_zip_part(
+                    ctx,
+                    source,
+                    target,
+                    password.as_deref(),
+                    None,
+                    |delta| {
+                        processed = (processed + delta).min(total_bytes);
+                        emit_progress(
+                            ctx,
+                            "decrypt",
+                            processed,
+                            total_bytes,
+                            idx + 1,
+                            paths.len(),
+                            tr!(DecryptingPart, idx + 1),
+                        );
+                    },
+                )?;
+            }
+            if in_place {
+                for (temp, target) in written.iter().zip(&targets) {
+                    fs::rename(temp, target).map_err(|e| e.to_string())?;
+                }
+            }
+            ("split-then-zip", targets)
+        }
+        "age-split" => {
+            let unseal = Unseal::new(
+                password.as_deref(),
+                options.age_identities.as_deref().unwrap_or_default(),
+            )?;
+            let targets = target_paths(&paths, &target_dir, |name| {
+                name.strip_suffix(age_split::PART_SUFFIX)
+                    .map(str::to_string)
+            })?;
+            let mut processed = 0u64;
+            for (idx, (source, target)) in paths.iter().zip(&targets).enumerate() {
+                ctx.check_cancelled()?;
+                let reader = age_split::open_part(source, &unseal)?;
+                write_plain(
+                    ctx,
+                    reader,
+                    target,
+                    |delta| {
+                        processed = (processed + delta).min(total_bytes);
+                        emit_progress(
+                            ctx,
+                            "decrypt",
+                            processed,
+                            total_bytes,
+                            idx + 1,
+                            paths.len(),
+                            tr!(DecryptingPart, idx + 1),
+                        );
+                    },
+                    |err| tr!(AgeDecryptFailed, source.display(), err),
+                )?;
+            }
+            ("raw-split", targets)
+        }
+        "container-then-split" => {
+            let password = password
+                .as_deref()
+                .ok_or_else(|| tr!(ContainerNeedsPassword))?;
+            let targets = target_paths(&paths, &target_dir, |name| {
+                let marker = format!("{}.", container::EXTENSION);
+                name.contains(&marker)
+                    .then(|| name.replacen(&marker, ".", 1))
+            })?;
+            let mut reader = container::open_parts(&paths, password)?;
+            let mut processed = 0u64;
+            for (idx, target) in targets.iter().enumerate() {
+                ctx.check_cancelled()?;
+                // 明文比密文略短，末份写入剩余的全部明文
+                let limit = if idx + 1 == targets.len() {
+                    u64::MAX
+                } else {
+                    sizes[idx]
+                };
+                write_plain(
+                    ctx,
+                    (&mut reader).take(limit),
+                    target,
+                    |delta| {
+                        processed = (processed + delta).min(total_bytes);
+                        emit_progress(
+                            ctx,
+                            "decrypt",
+                            processed,
+                            total_bytes,
+                            idx + 1,
+                            paths.len(),
+                            tr!(DecryptingPart, idx + 1),
+                        );
+                    },
+                    |err| err.to_string(),
+                )?;
+            }
+            ("raw-split", targets)
+        }
+        _ => return Err(tr!(DecryptUnsupportedMode, merge_mode)),
+    };
+
+    // 名称改变的分片在新分片都写好后才删除原分片
+    if in_place && pack_mode != merge_mode {
+        for path in &paths {
+            fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+    }
+    let output_files: Vec<String> = targets
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let part_sha256s = match &manifest {
+        Some((_, integrity)) => integrity::record_decrypted(
+            ctx,
+            integrity,
+            &target_dir,
+            &manifest_base_name(integrity),
+            pack_mode,
+            &output_files,
+        )?,
+        None => Vec::new(),
+    };
+    log::info!(
+        "已去掉 {} 份分片的加密：{} -> {}（{}）",
+        paths.len(),
+        parts_dir.display(),
+        target_dir.display(),
+        pack_mode
+    );
+    Ok(DecryptReport {
+        parts_dir: target_dir.to_string_lossy().to_string(),
+        output_files,
+        pack_mode: pack_mode.to_string(),
+        part_sha256s,
+    })
+}
+
+fn same_dir(a: &Path, b: &Path) -> bool {
+    match (fs::canonicalize(a), fs::canonicalize(b)) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => a == b,
+    }
+}
+
+/// 不加密分片的路径：`rename` 由原分片名得出新名称，无法得出时报错。
+fn target_paths(
+    paths: &[PathBuf],
+    target_dir: &Path,
+    rename: impl Fn(&str) -> Option<String>,
+) -> Result<Vec<PathBuf>, String> {
+    paths
+        .iter()
+        .map(|path| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(&rename)
+                .map(|name| target_dir.join(name))
+                .ok_or_else(|| tr!(CannotParsePartFileName))
+        })
+        .collect()
+}
+
+/// 把解密出的明文读到结束，写入 `target`；`on_progress` 收到每次读到的字节数，
+/// 解密出错（认证失败等）时以 `read_error` 说明原因。
+fn write_plain(
+    ctx: &JobContext,
+    mut reader: impl Read,
+    target: &Path,
+    mut on_progress: impl FnMut(u64),
+    read_error: impl Fn(io::Error) -> String,
+) -> Result<(), String> {
+    ctx.track_output(target);
+    let mut writer = BufWriter::new(File::create(target).map_err(|e| e.to_string())?);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(read_error(err)),
+        };
+        writer
+            .write_all(&buffer[..read_len])
+            .map_err(|e| e.to_string())?;
+        on_progress(read_len as u64);
+    }
+    writer.flush().map_err(|e| e.to_string())
+}
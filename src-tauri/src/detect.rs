@@ -0,0 +1,99 @@
+//! 识别合并方式（`mergeMode: "auto"`）：收件人往往不记得分片是“先分割后压缩”还是“先压缩后分割”。
+//! 依次按以下线索判断，先命中者为准：
+//! - 分卷 zip（`.z01`……`.zip`）为 `spanned-zip`，分卷 RAR 与追加打包的分片目录为 `zip-then-split`；
+//! - 完整性清单中记录的打包方式；
+//! - 分片命名：`.age` 结尾为 `age-split`，名称中有 `.fspc.` 为 `container-then-split`；
+//! - 第一份分片的内容：只含一个 `*.part-NNN` 条目的 zip 为 `split-then-zip`，其余 zip 片段与 RAR
+//!   为 `zip-then-split`，7z 为 `7z-then-split`，tar.gz / tar.zst 为对应的 tar 方式，
+//!   其他 zstd 数据为 `zstd-then-split`，认不出的为 `raw`。
+//!
+//! 分片经过 GPG 加密或过滤命令时内容无法识别，需要有完整性清单。
+
+use std::{fs::File, io::BufReader, path::Path};
+use zip::ZipArchive;
+
+use crate::{
+    age_split, append, collect_part_group, container, extract::ArchiveKind, i18n::tr, integrity,
+    parse_part_name, rar, sfx, spanned, tar::TarDecoding,
+};
+
+/// 合并参数中表示自动识别的合并方式。
+pub(crate) const AUTO: &str = "auto";
+
+/// 识别 `input_path` 对应分片的合并方式；`transformed` 表示分片经过 GPG 加密或过滤命令。
+pub(crate) fn detect_merge_mode(input_path: &Path, transformed: bool) -> Result<String, String> {
+    if spanned::is_spanned_set(input_path) {
+        return Ok("spanned-zip".to_string());
+    }
+    if rar::is_volume_set(input_path) {
+        return Ok("zip-then-split".to_string());
+    }
+    if input_path.is_dir() && append::load_manifest(input_path)?.is_some() {
+        return Ok("zip-then-split".to_string());
+    }
+    if let Some((_, manifest)) = integrity::find(input_path)? {
+        return Ok(sfx::merge_mode(manifest.pack_mode()).to_string());
+    }
+    if transformed {
+        return Err(tr!(AutoModeNeedsManifest));
+    }
+
+    let part_group = collect_part_group(input_path)?;
+    let first_part = &part_group.parts[0].path;
+    let name = first_part
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| tr!(CannotParsePartFileName))?;
+    if name.ends_with(age_split::PART_SUFFIX) {
+        return Ok("age-split".to_string());
+    }
+    if name.contains(&format!("{}.", container::EXTENSION)) {
+        return Ok("container-then-split".to_string());
+    }
+    let mode = match ArchiveKind::detect(first_part)? {
+        Some(ArchiveKind::Zip) if is_wrapped_part(first_part) => "split-then-zip",
+        Some(ArchiveKind::Zip) | Some(ArchiveKind::Rar) => "zip-then-split",
+        Some(ArchiveKind::SevenZ) => "7z-then-split",
+        Some(ArchiveKind::Tar(TarDecoding::Gzip)) => "tar-gz-then-split",
+        Some(ArchiveKind::Tar(TarDecoding::Zstd)) => "tar-zst-then-split",
+        Some(ArchiveKind::Compressed(TarDecoding::Zstd)) => "zstd-then-split",
+        _ => "raw",
+    };
+    Ok(mode.to_string())
+}
+
+/// 参数给出的合并方式，为 `auto` 时识别；输入不是分片（如合并后的 zip）时不需要合并方式，返回 `None`。
+pub(crate) fn resolve(
+    merge_mode: Option<&str>,
+    input_path: &Path,
+) -> Result<Option<String>, String> {
+    if merge_mode != Some(AUTO) {
+        return Ok(merge_mode.map(str::to_string));
+    }
+    let is_part = input_path.is_dir()
+        || spanned::is_spanned_set(input_path)
+        || input_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_part_name)
+            .is_some();
+    if !is_part {
+        return Ok(None);
+    }
+    detect_merge_mode(input_path, false).map(Some)
+}
+
+/// 分片本身是只含一个分片条目的完整 zip；先压缩后分割的第一份只是 zip 的开头，读不出中央目录。
+fn is_wrapped_part(path: &Path) -> bool {
+    let Ok(file) = File::open(path) else {
+        return false;
+    };
+    let Ok(archive) = ZipArchive::new(BufReader::new(file)) else {
+        return false;
+    };
+    archive.len() == 1
+        && archive
+            .name_for_index(0)
+            .and_then(parse_part_name)
+            .is_some()
+}
@@ -0,0 +1,218 @@
+//! 诊断包导出：把最近的日志、上一次任务的参数（密码等敏感字段已隐去）与运行环境信息
+//! 打包成一个 zip，方便用户附在问题反馈里。
+
+use serde::Serialize;
+use serde_json::{json, Value};
+use std::{
+    cmp::Reverse,
+    env,
+    fs::{self, File},
+    io::{self, BufWriter, Write},
+    path::{Path, PathBuf},
+    sync::{Mutex, OnceLock},
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use zip::{write::FileOptions, CompressionMethod, ZipWriter};
+
+use crate::{i18n, logging};
+
+/// 导出时隐去取值的参数名（忽略大小写，包含即匹配）。过滤命令中可能带有密钥，一并隐去；
+/// 密钥文件的路径等于指明了密钥所在，也隐去，签名私钥与 age 身份文件同理；GPG 收件人会暴露
+/// 通信对象，下载网址的查询参数中常有访问令牌，同样隐去。
+const REDACTED_KEYS: [&str; 8] = [
+    "password",
+    "token",
+    "filtercommand",
+    "keyfile",
+    "urls",
+    "signingkey",
+    "ageidentities",
+    "gpgrecipients",
+];
+const REDACTED: &str = "***";
+
+static LAST_JOB: OnceLock<Mutex<Option<Value>>> = OnceLock::new();
+
+fn last_job() -> &'static Mutex<Option<Value>> {
+    LAST_JOB.get_or_init(|| Mutex::new(None))
+}
+
+/// 记录最近一次任务的类型与参数，敏感字段在记录时即被隐去，不会留在内存中。
+pub(crate) fn record_job<T: Serialize>(kind: &str, options: &T) {
+    let mut options = serde_json::to_value(options).unwrap_or(Value::Null);
+    redact(&mut options);
+    let record = json!({
+        "kind": kind,
+        "startedAt": unix_millis(),
+        "options": options,
+    });
+    if let Ok(mut guard) = last_job().lock() {
+        *guard = Some(record);
+    }
+}
+
+fn redact(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                let key = key.to_ascii_lowercase();
+                if REDACTED_KEYS.iter().any(|name| key.contains(name)) {
+                    if !item.is_null() && item.as_str() != Some("") {
+                        *item = Value::String(REDACTED.to_string());
+                    }
+                } else {
+                    redact(item);
+                }
+            }
+        }
+        Value::Array(items) => items.iter_mut().for_each(redact),
+        _ => {}
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct DiskInfo {
+    name: String,
+    /// `ssd`、`hdd` 或 `unknown`。
+    kind: &'static str,
+    size_bytes: Option<u64>,
+    model: Option<String>,
+}
+
+/// 块设备列表（目前仅 Linux 可读取介质类型，其他系统返回空列表）。
+fn disks() -> Vec<DiskInfo> {
+    let Ok(entries) = fs::read_dir("/sys/block") else {
+        return Vec::new();
+    };
+    let read = |path: &Path| {
+        fs::read_to_string(path)
+            .ok()
+            .map(|text| text.trim().to_string())
+    };
+    let mut disks: Vec<DiskInfo> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            if ["loop", "ram", "zram", "dm-"]
+                .iter()
+                .any(|prefix| name.starts_with(prefix))
+            {
+                return None;
+            }
+            let dir = entry.path();
+            let kind = match read(&dir.join("queue/rotational")).as_deref() {
+                Some("0") => "ssd",
+                Some("1") => "hdd",
+                _ => "unknown",
+            };
+            let size_bytes = read(&dir.join("size"))
+                .and_then(|sectors| sectors.parse::<u64>().ok())
+                .map(|sectors| sectors * 512);
+            let model = read(&dir.join("device/model")).filter(|model| !model.is_empty());
+            Some(DiskInfo {
+                name,
+                kind,
+                size_bytes,
+                model,
+            })
+        })
+        .collect();
+    disks.sort_by(|a, b| a.name.cmp(&b.name));
+    disks
+}
+
+fn os_release() -> Option<String> {
+    let text = fs::read_to_string("/etc/os-release").ok()?;
+    text.lines()
+        .find_map(|line| line.strip_prefix("PRETTY_NAME="))
+        .map(|name| name.trim_matches('"').to_string())
+}
+
+fn environment() -> Value {
+    json!({
+        "appVersion": env!("CARGO_PKG_VERSION"),
+        "os": env::consts::OS,
+        "osRelease": os_release(),
+        "arch": env::consts::ARCH,
+        "cpuCores": thread::available_parallelism().map(|n| n.get()).unwrap_or(1),
+        "language": i18n::current().code(),
+        "logLevel": logging::level().to_string().to_lowercase(),
+        "logDir": logging::log_dir().map(|dir| dir.to_string_lossy().to_string()),
+        "disks": disks(),
+        "createdAt": unix_millis(),
+    })
+}
+
+fn unix_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+/// 日志目录中的日志文件，按修改时间从新到旧排列。
+fn log_files() -> Vec<(String, PathBuf)> {
+    let Some(dir) = logging::log_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Vec::new();
+    };
+    let mut files: Vec<(SystemTime, String, PathBuf)> = entries
+        .flatten()
+        .filter_map(|entry| {
+            let path = entry.path();
+            let name = entry.file_name().to_string_lossy().to_string();
+            if !path.is_file() || !name.ends_with(".log") {
+                return None;
+            }
+            let modified = entry.metadata().and_then(|meta| meta.modified()).ok()?;
+            Some((modified, name, path))
+        })
+        .collect();
+    files.sort_by_key(|(modified, _, _)| Reverse(*modified));
+    files.into_iter().map(|(_, name, path)| (name, path)).collect()
+}
+
+/// 生成诊断包，返回包内的文件名列表。
+pub(crate) fn export(target_path: &Path) -> Result<Vec<String>, String> {
+    log::logger().flush();
+    if let Some(parent) = target_path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    let file = File::create(target_path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(BufWriter::new(file));
+    let options: FileOptions<'_, ()> =
+        FileOptions::default().compression_method(CompressionMethod::Deflated);
+    let mut entries = Vec::new();
+
+    let mut add_json = |zip: &mut ZipWriter<BufWriter<File>>, name: &str, value: &Value| {
+        zip.start_file(name, options).map_err(|e| e.to_string())?;
+        let text = serde_json::to_string_pretty(value).map_err(|e| e.to_string())?;
+        zip.write_all(text.as_bytes()).map_err(|e| e.to_string())?;
+        entries.push(name.to_string());
+        Ok::<(), String>(())
+    };
+    add_json(&mut zip, "environment.json", &environment())?;
+    let last = last_job().lock().ok().and_then(|guard| guard.clone());
+    add_json(&mut zip, "last-job.json", &last.unwrap_or(Value::Null))?;
+
+    for (name, path) in log_files() {
+        let entry_name = format!("logs/{}", name);
+        let Ok(mut source) = File::open(&path) else {
+            continue;
+        };
+        zip.start_file(entry_name.as_str(), options)
+            .map_err(|e| e.to_string())?;
+        io::copy(&mut source, &mut zip).map_err(|e| e.to_string())?;
+        entries.push(entry_name);
+    }
+
+    zip.finish()
+        .and_then(|mut writer| writer.flush().map_err(Into::into))
+        .map_err(|e| e.to_string())?;
+    log::info!("已导出诊断包：{}", target_path.display());
+    Ok(entries)
+}
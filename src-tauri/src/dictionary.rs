@@ -0,0 +1,112 @@
+//! zstd 字典：`zstd-then-split` 打包大量相似小文件的目录时，先从目录中抽样训练字典，再用它压缩每一帧。
+//! 相似文件共有的文件头、字段名与模板进入字典，每帧不必各自重复一遍，帧可以切得更小而压缩率更高。
+//!
+//! 字典写入分片目录的 `<名称>.zst.dict`，合并与随机读取时从同一位置读取。
+//! 用 zstd 命令行解压时需要 `-D <名称>.zst.dict`。
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+};
+
+use crate::{include_dir_entry, jobs::JobContext};
+
+pub(crate) const DICTIONARY_SUFFIX: &str = ".dict";
+/// 使用字典时的帧大小。字典补上了帧之间的共有内容，小帧的压缩率损失不大，随机读取也更快。
+pub(crate) const DICTIONARY_FRAME_SIZE: usize = 256 * 1024;
+/// zstd 命令行 `--train` 的默认字典大小。
+const MAX_DICTIONARY_SIZE: usize = 112 * 1024;
+/// 大于此大小的文件不作为样本，大文件自身的压缩率字典帮不上忙。
+const MAX_SAMPLE_SIZE: u64 = 128 * 1024;
+/// 样本总大小上限，超过时按间隔抽取。
+const MAX_SAMPLES_TOTAL: u64 = 32 * 1024 * 1024;
+/// 样本太少时训练不出有用的字典。
+const MIN_SAMPLES: usize = 16;
+
+fn dictionary_path(parts_dir: &Path, archive_name: &str) -> PathBuf {
+    parts_dir.join(format!("{}{}", archive_name, DICTIONARY_SUFFIX))
+}
+
+/// 从目录中抽样训练字典；小文件太少或训练失败时返回 `None`，照常不用字典压缩。
+pub(crate) fn train(ctx: &JobContext, root: &Path) -> Result<Option<Vec<u8>>, String> {
+    let mut candidates = Vec::new();
+    collect_samples(ctx, root, root, &mut candidates)?;
+    if candidates.len() < MIN_SAMPLES {
+        log::info!(
+            "可作为样本的小文件只有 {} 个，不训练字典：{}",
+            candidates.len(),
+            root.display()
+        );
+        return Ok(None);
+    }
+    // 样本过多时等间隔抽取，避免只抽到前几个子目录
+    let total: u64 = candidates.iter().map(|(_, size)| size).sum();
+    let step = total.div_ceil(MAX_SAMPLES_TOTAL).max(1) as usize;
+    let mut samples = Vec::new();
+    for (path, _) in candidates.iter().step_by(step) {
+        ctx.check_cancelled()?;
+        match fs::read(path) {
+            Ok(data) => samples.push(data),
+            Err(err) => log::warn!("读取字典样本失败，跳过：{}（{}）", path.display(), err),
+        }
+    }
+    match zstd::dict::from_samples(&samples, MAX_DICTIONARY_SIZE) {
+        Ok(dictionary) => {
+            log::info!(
+                "已用 {} 个样本训练 zstd 字典（{} 字节）",
+                samples.len(),
+                dictionary.len()
+            );
+            Ok(Some(dictionary))
+        }
+        Err(err) => {
+            log::warn!("训练 zstd 字典失败，不使用字典：{}", err);
+            Ok(None)
+        }
+    }
+}
+
+fn collect_samples(
+    ctx: &JobContext,
+    root: &Path,
+    current: &Path,
+    samples: &mut Vec<(PathBuf, u64)>,
+) -> Result<(), String> {
+    for entry in fs::read_dir(current).map_err(|e| e.to_string())? {
+        ctx.check_cancelled()?;
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        let meta = entry.metadata().map_err(|e| e.to_string())?;
+        if !include_dir_entry(ctx, root, &path, meta.is_dir(), meta.len())? {
+            continue;
+        }
+        if meta.is_dir() {
+            collect_samples(ctx, root, &path, samples)?;
+        } else if meta.is_file() && meta.len() > 0 && meta.len() <= MAX_SAMPLE_SIZE {
+            samples.push((path, meta.len()));
+        }
+    }
+    Ok(())
+}
+
+/// 把字典写入分片目录的 `<archive_name>.dict`，返回写出的路径。
+pub(crate) fn write(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    archive_name: &str,
+    dictionary: &[u8],
+) -> Result<String, String> {
+    let path = dictionary_path(parts_dir, archive_name);
+    ctx.track_output(&path);
+    fs::write(&path, dictionary).map_err(|e| e.to_string())?;
+    Ok(path.to_string_lossy().to_string())
+}
+
+/// 读取分片旁的字典，没有字典文件时返回 `None`。
+pub(crate) fn load(parts_dir: &Path, archive_name: &str) -> io::Result<Option<Vec<u8>>> {
+    match fs::read(dictionary_path(parts_dir, archive_name)) {
+        Ok(dictionary) => Ok(Some(dictionary)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(err) => Err(err),
+    }
+}
@@ -0,0 +1,416 @@
+//! 从网址合并：合并参数的 `urls` 给出各份分片的 HTTP(S) 地址，或一份清单（`*.manifest.json`）的地址。
+//! 清单中列出的分片按相对清单的地址下载，有完整性清单时下载后核对大小，清单的签名文件一并下载。
+//!
+//! 分片下载到输出目录中的 `.<第一个文件名>.download` 目录，随后照常从该目录合并。连接中断、超时或
+//! 服务器临时出错时按 [`RETRIES`] 次重试，以 `Range` 请求从已下载的位置续传；合并失败时保留已下载的
+//! 文件，以同样的网址重新合并时只下载缺少的部分。合并成功后删除下载目录，除非设置了 `keepDownloads`。
+//! 进度阶段为 `download`。
+
+use std::{
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    thread,
+    time::{Duration, Instant},
+};
+use ureq::Agent;
+
+use crate::{
+    append, emit_progress, i18n::tr, integrity, jobs::JobContext, signing, RestoreOptions,
+};
+
+/// 每个文件失败后重试的次数，间隔从 1 秒起逐次加倍。
+const RETRIES: u32 = 5;
+/// 未下载完的文件名后缀。
+const PARTIAL_SUFFIX: &str = ".partial";
+const CONNECT_TIMEOUT: Duration = Duration::from_secs(30);
+const RESPONSE_TIMEOUT: Duration = Duration::from_secs(60);
+/// 等待重试时检查取消的间隔。
+const RETRY_POLL: Duration = Duration::from_millis(200);
+
+/// 一个要下载的文件。
+struct Source {
+    url: String,
+    name: String,
+    /// 清单记录的大小，下载后核对。
+    size: Option<u64>,
+}
+
+impl Source {
+    fn parse(url: &str) -> Result<Source, String> {
+        let lower = url.to_ascii_lowercase();
+        if !lower.starts_with("http://") && !lower.starts_with("https://") {
+            return Err(tr!(DownloadUrlUnsupported, url));
+        }
+        let path = url.split(['?', '#']).next().unwrap_or_default();
+        let name = path
+            .split_once("://")
+            .and_then(|(_, rest)| rest.split_once('/'))
+            .and_then(|(_, path)| path.rsplit('/').next())
+            .map(percent_decode)
+            .filter(|name| valid_name(name))
+            .ok_or_else(|| tr!(DownloadUrlNoFileName, url))?;
+        Ok(Source {
+            url: url.to_string(),
+            name,
+            size: None,
+        })
+    }
+
+    fn is_manifest(&self) -> bool {
+        self.name.ends_with(append::MANIFEST_SUFFIX)
+    }
+}
+
+/// 下载失败的原因：`Retry` 可以稍后续传，`Fatal` 重试也无济于事。
+enum Failure {
+    Retry(String),
+    Fatal(String),
+}
+
+/// 合并参数给出了网址时下载全部文件，把 `input_path` 改为下载目录并返回该目录；未给出网址时返回 `None`。
+pub(crate) fn prepare(
+    ctx: &JobContext,
+    options: &mut RestoreOptions,
+    output_dir: &Path,
+) -> Result<Option<PathBuf>, String> {
+    let urls: Vec<String> = options
+        .urls
+        .take()
+        .unwrap_or_default()
+        .iter()
+        .map(|url| url.trim().to_string())
+        .filter(|url| !url.is_empty())
+        .collect();
+    if urls.is_empty() {
+        return Ok(None);
+    }
+    let sources = urls
+        .iter()
+        .map(|url| Source::parse(url))
+        .collect::<Result<Vec<_>, _>>()?;
+    let dir = output_dir.join(format!(".{}.download", sources[0].name));
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+        ctx.track_output(&dir);
+    }
+    let agent = agent();
+
+    // 先下载清单，再按清单补上没有直接给出的分片
+    let (manifests, mut parts): (Vec<Source>, Vec<Source>) =
+        sources.into_iter().partition(Source::is_manifest);
+    for manifest in &manifests {
+        ctx.check_cancelled()?;
+        fetch(
+            ctx,
+            &agent,
+            &manifest.url,
+            &dir.join(&manifest.name),
+            |_, _| {},
+        )?;
+        let signature = Source {
+            url: format!("{}{}", manifest.url, signing::SIGNATURE_SUFFIX),
+            name: format!("{}{}", manifest.name, signing::SIGNATURE_SUFFIX),
+            size: None,
+        };
+        // 签名文件可有可无，服务器上没有时不算错误
+        match fetch(
+            ctx,
+            &agent,
+            &signature.url,
+            &dir.join(&signature.name),
+            |_, _| {},
+        ) {
+            Ok(_) => {}
+            Err(err) if ctx.is_cancelled() => return Err(err),
+            Err(err) => log::info!("清单没有可下载的签名文件：{}", err),
+        }
+    }
+    if let Some(manifest) = manifests.first() {
+        for source in listed_parts(&dir, &manifest.url, &manifest.name)? {
+            match parts.iter_mut().find(|part| part.name == source.name) {
+                Some(part) => part.size = source.size,
+                None => parts.push(source),
+            }
+        }
+    }
+
+    let mut total_bytes: u64 = parts.iter().filter_map(|part| part.size).sum();
+    let mut processed = 0u64;
+    let count = parts.len();
+    for (idx, part) in parts.iter().enumerate() {
+        ctx.check_cancelled()?;
+        let message = || tr!(DownloadingPart, idx + 1, count, part.name);
+        emit_progress(
+            ctx,
+            "download",
+            processed,
+            total_bytes,
+            idx + 1,
+            count,
+            message(),
+        );
+        let mut counted_total = part.size.is_some();
+        let target = dir.join(&part.name);
+        let size = fetch(ctx, &agent, &part.url, &target, |position, length| {
+            if !counted_total {
+                if let Some(length) = length {
+                    total_bytes += length;
+                    counted_total = true;
+                }
+            }
+            emit_progress(
+                ctx,
+                "download",
+                processed + position,
+                total_bytes.max(processed + position),
+                idx + 1,
+                count,
+                message(),
+            );
+        })?;
+        if let Some(expected) = part.size.filter(|expected| *expected != size) {
+            // 删除大小不对的文件，重新合并时重新下载
+            let _ = fs::remove_file(&target);
+            return Err(tr!(DownloadSizeMismatch, part.name, expected, size));
+        }
+        processed += size;
+    }
+    log::info!(
+        "已下载 {} 个文件（{} 字节）到 {}",
+        count + manifests.len(),
+        processed,
+        dir.display()
+    );
+    options.input_path = dir.to_string_lossy().to_string();
+    Ok(Some(dir))
+}
+
+fn agent() -> Agent {
+    Agent::config_builder()
+        .timeout_connect(Some(CONNECT_TIMEOUT))
+        .timeout_recv_response(Some(RESPONSE_TIMEOUT))
+        .http_status_as_error(false)
+        .build()
+        .into()
+}
+
+/// 清单中列出的分片，地址相对清单的地址；有完整性清单时带上分片大小。
+fn listed_parts(
+    dir: &Path,
+    manifest_url: &str,
+    manifest_name: &str,
+) -> Result<Vec<Source>, String> {
+    let base = manifest_url
+        .split(['?', '#'])
+        .next()
+        .and_then(|path| path.rsplit_once('/'))
+        .map(|(base, _)| base)
+        .unwrap_or_default();
+    let source = |name: &str, size: Option<u64>| Source {
+        url: format!("{}/{}", base, percent_encode(name)),
+        name: name.to_string(),
+        size,
+    };
+    let parts: Vec<Source> = match integrity::find(dir)? {
+        Some((_, manifest)) => manifest
+            .part_sizes()
+            .into_iter()
+            .map(|(name, size)| source(name, Some(size)))
+            .collect(),
+        None => match append::load_manifest(dir)? {
+            Some((_, manifest)) => manifest
+                .part_names()
+                .into_iter()
+                .map(|name| source(name, None))
+                .collect(),
+            None => Vec::new(),
+        },
+    };
+    if parts.is_empty() {
+        return Err(tr!(DownloadManifestNoParts, manifest_name));
+    }
+    if let Some(part) = parts.iter().find(|part| !valid_name(&part.name)) {
+        return Err(tr!(DownloadUrlNoFileName, part.name));
+    }
+    Ok(parts)
+}
+
+/// 下载 `url` 到 `target`，`target` 已存在时视为已下载；返回文件大小。
+/// `on_progress` 收到本文件已下载的字节数与响应给出的总大小。
+fn fetch(
+    ctx: &JobContext,
+    agent: &Agent,
+    url: &str,
+    target: &Path,
+    mut on_progress: impl FnMut(u64, Option<u64>),
+) -> Result<u64, String> {
+    if let Ok(meta) = fs::metadata(target) {
+        log::info!("已下载过，跳过：{}", target.display());
+        return Ok(meta.len());
+    }
+    let mut partial = target.as_os_str().to_owned();
+    partial.push(PARTIAL_SUFFIX);
+    let partial = PathBuf::from(partial);
+    let mut attempt = 0;
+    loop {
+        ctx.check_cancelled()?;
+        match fetch_once(ctx, agent, url, &partial, &mut on_progress) {
+            Ok(()) => break,
+            Err(Failure::Fatal(err)) => return Err(tr!(DownloadFailed, display_url(url), err)),
+            Err(Failure::Retry(err)) if attempt < RETRIES => {
+                let delay = Duration::from_secs(1 << attempt);
+                attempt += 1;
+                log::warn!(
+                    "下载 {} 中断，{} 秒后第 {} 次重试：{}",
+                    display_url(url),
+                    delay.as_secs(),
+                    attempt,
+                    err
+                );
+                wait(ctx, delay)?;
+            }
+            Err(Failure::Retry(err)) => return Err(tr!(DownloadFailed, display_url(url), err)),
+        }
+    }
+    fs::rename(&partial, target).map_err(|e| e.to_string())?;
+    fs::metadata(target)
+        .map(|meta| meta.len())
+        .map_err(|e| e.to_string())
+}
+
+/// 请求一次，从 `partial` 已有的长度续传到结束。
+fn fetch_once(
+    ctx: &JobContext,
+    agent: &Agent,
+    url: &str,
+    partial: &Path,
+    on_progress: &mut impl FnMut(u64, Option<u64>),
+) -> Result<(), Failure> {
+    let offset = fs::metadata(partial).map(|meta| meta.len()).unwrap_or(0);
+    let mut request = agent.get(url);
+    if offset > 0 {
+        request = request.header("Range", format!("bytes={}-", offset));
+    }
+    let mut response = request.call().map_err(|err| match err {
+        ureq::Error::Io(_)
+        | ureq::Error::Timeout(_)
+        | ureq::Error::HostNotFound
+        | ureq::Error::ConnectionFailed
+        | ureq::Error::Protocol(_)
+        | ureq::Error::BodyStalled => Failure::Retry(err.to_string()),
+        err => Failure::Fatal(err.to_string()),
+    })?;
+    let status = response.status().as_u16();
+    let header = |name: &str| {
+        response
+            .headers()
+            .get(name)
+            .and_then(|value| value.to_str().ok())
+            .map(str::to_string)
+    };
+    let length = header("content-length").and_then(|value| value.parse::<u64>().ok());
+    let (file, start) = match status {
+        // 续传的数据须从请求的位置开始，不符时丢弃已下载的部分从头下载
+        206 if header("content-range")
+            .is_some_and(|range| range.starts_with(&format!("bytes {}-", offset))) =>
+        {
+            (OpenOptions::new().append(true).open(partial), offset)
+        }
+        206 => {
+            let _ = fs::remove_file(partial);
+            return Err(Failure::Retry(tr!(DownloadRangeIgnored)));
+        }
+        200 => (File::create(partial), 0),
+        // 已下载的部分就是完整的文件
+        416 if offset > 0 => return Ok(()),
+        408 | 429 | 500..=599 => return Err(Failure::Retry(format!("HTTP {}", status))),
+        _ => return Err(Failure::Fatal(format!("HTTP {}", status))),
+    };
+    let mut file = file.map_err(|e| Failure::Fatal(e.to_string()))?;
+    let total = length.map(|length| start + length);
+    let mut position = start;
+    let mut reader = response.body_mut().as_reader();
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    on_progress(position, total);
+    loop {
+        ctx.check_cancelled().map_err(Failure::Fatal)?;
+        let read_len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            // 已写入的部分保留，下次从这里续传
+            Err(err) => return Err(Failure::Retry(err.to_string())),
+        };
+        file.write_all(&buffer[..read_len])
+            .map_err(|e| Failure::Fatal(e.to_string()))?;
+        position += read_len as u64;
+        on_progress(position, total);
+    }
+    file.flush().map_err(|e| Failure::Fatal(e.to_string()))?;
+    match total {
+        Some(total) if position < total => {
+            Err(Failure::Retry(tr!(DownloadTruncated, position, total)))
+        }
+        _ => Ok(()),
+    }
+}
+
+/// 等待 `delay`，期间任务被取消时返回取消错误。
+fn wait(ctx: &JobContext, delay: Duration) -> Result<(), String> {
+    let deadline = Instant::now() + delay;
+    while Instant::now() < deadline {
+        ctx.check_cancelled()?;
+        thread::sleep(RETRY_POLL.min(deadline.saturating_duration_since(Instant::now())));
+    }
+    ctx.check_cancelled()
+}
+
+/// 日志与错误中不显示网址的查询参数，其中常有访问令牌。
+fn display_url(url: &str) -> &str {
+    url.split(['?', '#']).next().unwrap_or(url)
+}
+
+/// 下载的文件名只能是下载目录中的一个文件。
+fn valid_name(name: &str) -> bool {
+    !name.is_empty()
+        && name != "."
+        && name != ".."
+        && !name.contains(['/', '\\', '\0'])
+        && !name.ends_with(PARTIAL_SUFFIX)
+}
+
+fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        let hex = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[i], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                i += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                i += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).to_string()
+}
+
+fn percent_encode(value: &str) -> String {
+    value
+        .bytes()
+        .map(|byte| match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                (byte as char).to_string()
+            }
+            _ => format!("%{:02X}", byte),
+        })
+        .collect()
+}
@@ -0,0 +1,56 @@
+//! 选择性解压：合并参数 `include`/`exclude` 为通配符列表，自动解压 zip 时只解压名称匹配
+//! `include`（为空时视为全部）且不匹配 `exclude` 的文件条目。
+//!
+//! 通配符按条目在 zip 中的完整路径（`/` 分隔）或其中的文件名匹配，不区分大小写，`*` 可以跨越
+//! 目录，如 `*.psd` 匹配任意目录下的 PSD 文件，`project/assets/*` 匹配该目录下的全部文件。
+
+use glob::{MatchOptions, Pattern};
+
+use crate::i18n::tr;
+
+const MATCH_OPTIONS: MatchOptions = MatchOptions {
+    case_sensitive: false,
+    require_literal_separator: false,
+    require_literal_leading_dot: false,
+};
+
+#[derive(Debug)]
+pub(crate) struct EntryFilter {
+    include: Vec<Pattern>,
+    exclude: Vec<Pattern>,
+}
+
+impl EntryFilter {
+    /// 两个列表都为空时返回 `None`，即解压全部条目。
+    pub(crate) fn new(
+        include: &[String],
+        exclude: &[String],
+    ) -> Result<Option<EntryFilter>, String> {
+        let include = compile(include)?;
+        let exclude = compile(exclude)?;
+        if include.is_empty() && exclude.is_empty() {
+            return Ok(None);
+        }
+        Ok(Some(EntryFilter { include, exclude }))
+    }
+
+    /// 条目 `name`（zip 中的路径）是否需要解压。
+    pub(crate) fn matches(&self, name: &str) -> bool {
+        let name = name.trim_end_matches('/');
+        let file_name = name.rsplit('/').next().unwrap_or(name);
+        let hit = |pattern: &Pattern| {
+            pattern.matches_with(name, MATCH_OPTIONS)
+                || pattern.matches_with(file_name, MATCH_OPTIONS)
+        };
+        (self.include.is_empty() || self.include.iter().any(hit)) && !self.exclude.iter().any(hit)
+    }
+}
+
+fn compile(patterns: &[String]) -> Result<Vec<Pattern>, String> {
+    patterns
+        .iter()
+        .map(|pattern| pattern.trim().replace('\\', "/"))
+        .filter(|pattern| !pattern.is_empty())
+        .map(|pattern| Pattern::new(&pattern).map_err(|e| tr!(InvalidGlob, e)))
+        .collect()
+}
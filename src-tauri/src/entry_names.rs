@@ -0,0 +1,185 @@
+//! 旧版中文、日文 Windows 工具打的 zip 以 GBK、Shift-JIS 等本地代码页记录条目名，又不设 UTF-8 标志，
+//! 按规范以 CP437 解读后全是乱码。合并参数 `filenameEncoding` 指定这类名称的代码页（`gbk`、
+//! `shift_jis`、`big5`、`euc-kr` 等 WHATWG 编码名），或为 `auto` 时按整个压缩包的名称推测。
+//!
+//! 只影响不是合法 UTF-8 的名称：设了 UTF-8 标志、带 Unicode 路径扩展字段或纯 ASCII 的名称照旧。
+//! 未设置或为 `cp437` 时与以前一样按 CP437 解读。
+
+use encoding_rs::{Encoding, BIG5, EUC_KR, GBK, SHIFT_JIS};
+use std::{
+    io::{Read, Seek},
+    path::PathBuf,
+};
+use zip::{read::ZipFile, ZipArchive};
+
+use crate::i18n::tr;
+
+/// `filenameEncoding` 参数。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum NameEncoding {
+    Auto,
+    Fixed(&'static Encoding),
+}
+
+impl NameEncoding {
+    /// 解析 `filenameEncoding`：`auto` 或编码名；未设置、为空或为 `cp437` 时为 `None`。
+    pub(crate) fn parse(value: Option<&str>) -> Result<Option<NameEncoding>, String> {
+        let value = value.unwrap_or_default().trim();
+        if value.is_empty() || value.eq_ignore_ascii_case("cp437") {
+            return Ok(None);
+        }
+        if value.eq_ignore_ascii_case("auto") {
+            return Ok(Some(NameEncoding::Auto));
+        }
+        Encoding::for_label(value.as_bytes())
+            .map(|encoding| Some(NameEncoding::Fixed(encoding)))
+            .ok_or_else(|| tr!(UnknownFilenameEncoding, value))
+    }
+}
+
+/// 一个压缩包中非 UTF-8 条目名的解码方式；为 `None` 时沿用 zip 库按 CP437 解读的名称。
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct NameDecoder(Option<&'static Encoding>);
+
+impl NameDecoder {
+    /// 为 `Auto` 时读取全部条目名推测代码页。
+    pub(crate) fn for_archive<R: Read + Seek>(
+        encoding: Option<NameEncoding>,
+        archive: &mut ZipArchive<R>,
+    ) -> NameDecoder {
+        match encoding {
+            None => NameDecoder(None),
+            Some(NameEncoding::Fixed(encoding)) => NameDecoder(Some(encoding)),
+            Some(NameEncoding::Auto) => {
+                let mut names = Vec::new();
+                for index in 0..archive.len() {
+                    if let Ok(entry) = archive.by_index_raw(index) {
+                        if std::str::from_utf8(entry.name_raw()).is_err() {
+                            names.push(entry.name_raw().to_vec());
+                        }
+                    }
+                }
+                let detected = detect(&names);
+                if let Some(encoding) = detected {
+                    log::info!("按 {} 解读条目名", encoding.name());
+                }
+                NameDecoder(detected)
+            }
+        }
+    }
+
+    /// 原始字节 `raw` 的名称；是合法 UTF-8 或未指定代码页时返回 `None`。
+    pub(crate) fn decode(&self, raw: &[u8]) -> Option<String> {
+        let encoding = self.0?;
+        if std::str::from_utf8(raw).is_ok() {
+            return None;
+        }
+        Some(encoding.decode_without_bom_handling(raw).0.into_owned())
+    }
+
+    /// 条目在压缩包中的名称。
+    pub(crate) fn name_for_index<R: Read + Seek>(
+        &self,
+        archive: &mut ZipArchive<R>,
+        index: usize,
+    ) -> String {
+        if self.0.is_some() {
+            if let Ok(entry) = archive.by_index_raw(index) {
+                return self.name(&entry);
+            }
+        }
+        archive
+            .name_for_index(index)
+            .unwrap_or_default()
+            .to_string()
+    }
+
+    pub(crate) fn name<R: Read>(&self, entry: &ZipFile<'_, R>) -> String {
+        self.decode(entry.name_raw())
+            .unwrap_or_else(|| entry.name().to_string())
+    }
+
+    /// 解压到的相对路径，见 [`enclosed`]。
+    pub(crate) fn enclosed_name<R: Read>(&self, entry: &ZipFile<'_, R>) -> Option<PathBuf> {
+        match self.decode(entry.name_raw()) {
+            Some(name) => enclosed(&name),
+            None => entry.enclosed_name(),
+        }
+    }
+}
+
+/// 扫描文件头时（尽力解压）单个非 UTF-8 名称的解码：按指定或推测的代码页，有无法解码的字节时
+/// 返回 `None`，以便把压缩数据中碰巧出现的签名与真正的文件头区分开。
+pub(crate) fn decode_exact(encoding: Option<NameEncoding>, raw: &[u8]) -> Option<String> {
+    let encoding = match encoding? {
+        NameEncoding::Fixed(encoding) => encoding,
+        NameEncoding::Auto => detect(&[raw.to_vec()])?,
+    };
+    encoding
+        .decode_without_bom_handling_and_without_replacement(raw)
+        .map(|name| name.into_owned())
+}
+
+/// 与 zip 库的 `enclosed_name` 相同的规则：`/` 与 `\` 都是分隔符，去掉开头的根与盘符，
+/// `..` 超出解压目录或名称中有 NUL 时返回 `None`。
+pub(crate) fn enclosed(name: &str) -> Option<PathBuf> {
+    if name.contains('\0') {
+        return None;
+    }
+    let mut depth = 0usize;
+    let mut path = PathBuf::new();
+    for (position, component) in name.split(['/', '\\']).enumerate() {
+        match component {
+            "" | "." => {}
+            ".." => {
+                depth = depth.checked_sub(1)?;
+                path.pop();
+            }
+            drive if position == 0 && drive.len() == 2 && drive.ends_with(':') => {}
+            normal => {
+                depth += 1;
+                path.push(normal);
+            }
+        }
+    }
+    Some(path)
+}
+
+/// 推测代码页：选出能无错解码全部名称、且解出的字符最像该语言文字的一个。都不能解码时返回 `None`。
+fn detect(names: &[Vec<u8>]) -> Option<&'static Encoding> {
+    if names.is_empty() {
+        return None;
+    }
+    let mut best: Option<(&'static Encoding, i64)> = None;
+    // 得分相同时靠前的优先
+    for encoding in [GBK, SHIFT_JIS, BIG5, EUC_KR] {
+        let mut score = 0i64;
+        let mut valid = true;
+        for raw in names {
+            match encoding.decode_without_bom_handling_and_without_replacement(raw) {
+                Some(text) => score += text.chars().map(|ch| char_score(encoding, ch)).sum::<i64>(),
+                None => {
+                    valid = false;
+                    break;
+                }
+            }
+        }
+        if valid && best.is_none_or(|(_, best_score)| score > best_score) {
+            best = Some((encoding, score));
+        }
+    }
+    best.map(|(encoding, _)| encoding)
+}
+
+/// 常用汉字、假名与谚文加分，全角符号略加分；其余非 ASCII 字符（如半角片假名）减分，
+/// 按错误的代码页解码时往往解出这类字符。
+fn char_score(encoding: &Encoding, ch: char) -> i64 {
+    match ch {
+        ch if ch.is_ascii() => 0,
+        '\u{3040}'..='\u{30ff}' if encoding == SHIFT_JIS => 3,
+        '\u{ac00}'..='\u{d7a3}' if encoding == EUC_KR => 3,
+        '\u{4e00}'..='\u{9fff}' => 2,
+        '\u{3000}'..='\u{303f}' | '\u{ff01}'..='\u{ff5e}' => 1,
+        _ => -2,
+    }
+}
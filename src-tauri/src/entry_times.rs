@@ -0,0 +1,236 @@
+//! 解压时恢复条目的时间：修改时间，以及压缩包记录了创建时间时的创建时间（只有 Windows 与 macOS
+//! 能设置）。zip 依次取 NTFS 时间（`0x000a`）、扩展时间戳（`0x5455`，UTC 秒）与 MS-DOS 修改时间，
+//! 后者不带时区，与其他压缩工具一样按本机的本地时间处理；7z 取条目记录的修改与创建时间。
+//!
+//! 打包时由 [`stamp_zip`] 写入源文件的修改时间：MS-DOS 时间按本地时间记录，另加扩展时间戳，
+//! 在其他时区解压也能得到准确的时间。
+//!
+//! 目录的时间在全部条目写完后由深到浅设置，否则之后写入的文件会改掉目录的修改时间。
+//! 设置失败（如文件系统不支持）时只记日志，不影响解压。
+//!
+//! 条目记录了 Unix 权限（zip 的外部属性高 16 位，7z 属性中带 `0x8000` 标志时同样取高 16 位）时，
+//! 在 Linux 与 macOS 上一并恢复，脚本与可执行文件解压后仍可执行；Windows 上不做处理。
+//! 不恢复 setuid、setgid 位。目录的权限与时间一起最后设置，只读目录不会挡住其中文件的写入。
+
+use chrono::{Datelike, Local, NaiveDate, TimeZone, Timelike};
+use sevenz_rust::SevenZArchiveEntry;
+use std::{
+    fs::{self, File, FileTimes},
+    io::Read,
+    path::{Path, PathBuf},
+    time::{Duration, SystemTime, UNIX_EPOCH},
+};
+use zip::{read::ZipFile, write::FullFileOptions, DateTime, ExtraField};
+
+/// Windows FILETIME（1601 年起的 100 纳秒数）与 Unix 纪元相差的秒数。
+const FILETIME_UNIX_OFFSET: u64 = 11_644_473_600;
+
+/// 扩展时间戳的头部 ID。
+const EXTENDED_TIMESTAMP: u16 = 0x5455;
+
+/// 7z 属性中表示高 16 位为 Unix 权限的标志（p7zip 的约定）。
+const SEVENZ_UNIX_EXTENSION: u32 = 0x8000;
+
+/// 一个条目记录的时间与 Unix 权限。
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct EntryTimes {
+    modified: Option<SystemTime>,
+    created: Option<SystemTime>,
+    mode: Option<u32>,
+}
+
+impl EntryTimes {
+    pub(crate) fn from_zip<R: Read>(entry: &ZipFile<'_, R>) -> EntryTimes {
+        let mut times = EntryTimes::default();
+        for field in entry.extra_data_fields() {
+            match field {
+                ExtraField::Ntfs(ntfs) => {
+                    times.modified = times.modified.or(filetime(ntfs.mtime()));
+                    times.created = times.created.or(filetime(ntfs.ctime()));
+                }
+                ExtraField::ExtendedTimestamp(stamp) => {
+                    let unix = |secs: u32| UNIX_EPOCH + Duration::from_secs(secs as u64);
+                    times.modified = times.modified.or(stamp.mod_time().map(unix));
+                    times.created = times.created.or(stamp.cr_time().map(unix));
+                }
+            }
+        }
+        times.modified = times.modified.or(entry.last_modified().map(zip_time));
+        times.mode = entry.unix_mode();
+        times
+    }
+
+    /// 修改时间；条目没有记录时为 `None`。
+    pub(crate) fn modified(&self) -> Option<SystemTime> {
+        self.modified
+    }
+
+    pub(crate) fn from_sevenz(entry: &SevenZArchiveEntry) -> EntryTimes {
+        EntryTimes {
+            modified: entry
+                .has_last_modified_date
+                .then(|| filetime(entry.last_modified_date.to_raw()))
+                .flatten(),
+            created: entry
+                .has_creation_date
+                .then(|| filetime(entry.creation_date.to_raw()))
+                .flatten(),
+            mode: (entry.has_windows_attributes
+                && entry.windows_attributes & SEVENZ_UNIX_EXTENSION != 0)
+                .then_some(entry.windows_attributes >> 16),
+        }
+    }
+
+    /// 设置到刚写完的文件上。
+    pub(crate) fn apply_to_file(&self, path: &Path, file: &File) {
+        if let Some(times) = self.file_times() {
+            if let Err(err) = file.set_times(times) {
+                log::debug!("无法设置文件时间 {}：{}", path.display(), err);
+            }
+        }
+        if let Some(mode) = self.mode {
+            set_mode(path, mode);
+        }
+    }
+
+    fn apply_to_dir(&self, path: &Path) {
+        if let Some(times) = self.file_times() {
+            if let Err(err) = File::open(path).and_then(|dir| dir.set_times(times)) {
+                log::debug!("无法设置目录时间 {}：{}", path.display(), err);
+            }
+        }
+        if let Some(mode) = self.mode {
+            set_mode(path, mode);
+        }
+    }
+
+    fn file_times(&self) -> Option<FileTimes> {
+        let mut times = FileTimes::new();
+        let mut any = false;
+        if let Some(modified) = self.modified {
+            times = times.set_modified(modified);
+            any = true;
+        }
+        if let Some(created) = self.created {
+            if let Some(with_created) = set_created(times, created) {
+                times = with_created;
+                any = true;
+            }
+        }
+        any.then_some(times)
+    }
+}
+
+/// 解压出的目录及其时间与权限，全部条目写完后调用 [`DirTimes::apply`]。
+#[derive(Default)]
+pub(crate) struct DirTimes(Vec<(PathBuf, EntryTimes)>);
+
+impl DirTimes {
+    pub(crate) fn push(&mut self, path: PathBuf, times: EntryTimes) {
+        self.0.push((path, times));
+    }
+
+    /// 由深到浅设置，设置子目录不会再改动已设置过的上级目录。
+    pub(crate) fn apply(mut self) {
+        self.0
+            .sort_by_key(|(path, _)| std::cmp::Reverse(path.components().count()));
+        for (path, times) in &self.0 {
+            times.apply_to_dir(path);
+        }
+    }
+}
+
+/// 设置 Unix 权限，不恢复 setuid、setgid 位；失败时只记日志。
+#[cfg(unix)]
+pub(crate) fn set_mode(path: &Path, mode: u32) {
+    use std::os::unix::fs::PermissionsExt;
+    if let Err(err) = fs::set_permissions(path, fs::Permissions::from_mode(mode & 0o1777)) {
+        log::debug!("无法设置权限 {}：{}", path.display(), err);
+    }
+}
+
+#[cfg(not(unix))]
+pub(crate) fn set_mode(_path: &Path, _mode: u32) {}
+
+#[cfg(windows)]
+fn set_created(times: FileTimes, created: SystemTime) -> Option<FileTimes> {
+    use std::os::windows::fs::FileTimesExt;
+    Some(times.set_created(created))
+}
+
+#[cfg(target_os = "macos")]
+fn set_created(times: FileTimes, created: SystemTime) -> Option<FileTimes> {
+    use std::os::macos::fs::FileTimesExt;
+    Some(times.set_created(created))
+}
+
+#[cfg(not(any(windows, target_os = "macos")))]
+fn set_created(_times: FileTimes, _created: SystemTime) -> Option<FileTimes> {
+    None
+}
+
+/// Windows FILETIME 对应的时间；为 0（未记录）或早于 1970 年时返回 `None`。
+fn filetime(raw: u64) -> Option<SystemTime> {
+    let secs = (raw / 10_000_000).checked_sub(FILETIME_UNIX_OFFSET)?;
+    let nanos = (raw % 10_000_000) * 100;
+    Some(UNIX_EPOCH + Duration::new(secs, nanos as u32))
+}
+
+/// MS-DOS 时间不带时区，按本地时间处理；夏令时切换时不存在的时刻按 UTC 处理。
+fn zip_time(value: DateTime) -> SystemTime {
+    let naive = NaiveDate::from_ymd_opt(
+        value.year() as i32,
+        value.month() as u32,
+        value.day() as u32,
+    )
+    .and_then(|date| {
+        date.and_hms_opt(
+            value.hour() as u32,
+            value.minute() as u32,
+            value.second() as u32,
+        )
+    });
+    let Some(naive) = naive else {
+        return UNIX_EPOCH;
+    };
+    match Local.from_local_datetime(&naive).earliest() {
+        Some(local) => local.into(),
+        None => naive.and_utc().into(),
+    }
+}
+
+/// 为打包的条目记录修改时间：MS-DOS 时间按本地时间写入（早于 1980 年时记为 1980-01-01），
+/// 并加上扩展时间戳（只含修改时间，本地头与中央目录各 9 字节）；时间超出其 32 位范围时不加。
+pub(crate) fn stamp_zip(
+    mut options: FullFileOptions<'_>,
+    modified: SystemTime,
+) -> FullFileOptions<'_> {
+    let local = chrono::DateTime::<Local>::from(modified);
+    let dos = u16::try_from(local.year())
+        .ok()
+        .and_then(|year| {
+            DateTime::from_date_and_time(
+                year,
+                local.month() as u8,
+                local.day() as u8,
+                local.hour() as u8,
+                local.minute() as u8,
+                local.second() as u8,
+            )
+            .ok()
+        })
+        .unwrap_or_default();
+    options = options.last_modified_time(dos);
+    let secs = modified
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .and_then(|elapsed| u32::try_from(elapsed.as_secs()).ok());
+    if let Some(secs) = secs {
+        // 标志位 1 表示只带修改时间
+        let mut body = [1u8; 5];
+        body[1..].copy_from_slice(&secs.to_le_bytes());
+        // 选项中只有这一个扩展字段，不会超出长度上限
+        let _ = options.add_extra_data(EXTENDED_TIMESTAMP, body, false);
+    }
+    options
+}
@@ -0,0 +1,488 @@
+//! 恢复分片（默认关闭）：设置 `redundancyParts`（M）后，切分完成时另外生成 M 份恢复分片
+//! `<名称>.recovery-1` …，并在分片目录写入 `<名称>.recovery.json`，记录每份数据分片与恢复分片的
+//! 大小和 SHA-256。合并前发现缺失或损坏的数据分片不超过可用的恢复分片时，先重建并写回分片目录，
+//! 再照常合并。
+//!
+//! 编码为 GF(2^16) 上的 Cauchy Reed–Solomon：数据分片补零到最大一份的长度后逐 16 位字计算，
+//! 任意 M 份分片（数据或恢复）丢失都能恢复；每份恢复分片与最大一份数据分片一样大。
+//! 与 PAR2（参见 [`crate::parity`]）相比不需要外部工具，但只能整份重建。
+//!
+//! 只需防一份丢失时可改用 `xorParity`：只生成一份 `<名称>.parity`，内容为全部数据分片的异或，
+//! 计算几乎不占 CPU，合并时能重建恰好一份缺失或损坏的分片。
+//!
+//! 不合并、只修复分片时使用 [`crate::repair`]。
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    emit_progress,
+    i18n::tr,
+    jobs::JobContext,
+    parity::{self, Field},
+    repair::Outcome,
+};
+
+/// 恢复分片数上限。
+pub(crate) const MAX_RECOVERY_PARTS: u32 = 256;
+/// 数据分片与恢复分片合计的上限（GF(2^16) 中可用的不同取值）。
+const MAX_TOTAL_PARTS: usize = 1 << 16;
+const SET_SUFFIX: &str = ".recovery.json";
+const SET_VERSION: u32 = 1;
+/// 未设置内存上限时，每轮读入的数据段与恢复段合计的大小。
+const ENCODE_MEMORY: u64 = 256 * 1024 * 1024;
+/// 每轮段长的下限。
+const MIN_WINDOW: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+enum Scheme {
+    ReedSolomon,
+    /// 单份校验分片，全部系数为 1。
+    Xor,
+}
+
+/// 一份数据分片或恢复分片。
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Shard {
+    name: String,
+    size: u64,
+    sha256: String,
+}
+
+/// `<名称>.recovery.json` 的内容。
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RecoverySet {
+    version: u32,
+    scheme: Scheme,
+    /// 参与编码的长度：最大一份数据分片补齐到偶数字节。
+    shard_size: u64,
+    data: Vec<Shard>,
+    recovery: Vec<Shard>,
+}
+
+/// 第 `row` 份恢复分片中第 `column` 份数据分片的系数。Reed–Solomon 为 1 / (x_row + y_column)，
+/// x 取 0..M、y 取 M..M+N，互不相同，任意方阵都可逆。
+fn coefficient(
+    field: &Field,
+    scheme: Scheme,
+    recovery_count: usize,
+    row: usize,
+    column: usize,
+) -> u16 {
+    match scheme {
+        Scheme::ReedSolomon => field.inv((row ^ (recovery_count + column)) as u16),
+        Scheme::Xor => 1,
+    }
+}
+
+/// 每轮的段长：数据段与恢复段合计不超过内存预算，是 4 的倍数且不超过 `shard_size`。
+fn window_size(ctx: &JobContext, shard_size: u64, blocks: usize) -> usize {
+    let budget = ctx
+        .memory()
+        .limit()
+        .unwrap_or(ENCODE_MEMORY)
+        .min(ENCODE_MEMORY);
+    ((budget / blocks.max(1) as u64).max(MIN_WINDOW) / 4 * 4).min(shard_size) as usize
+}
+
+/// 依次读入每个文件 `[offset, offset + len)` 这一段，超出文件末尾的部分补零；
+/// 提供了 `hashers` 时把实际读到的数据计入对应文件的校验值。
+fn read_window(
+    paths: &[PathBuf],
+    offset: u64,
+    len: usize,
+    target: &mut [u8],
+    hashers: &mut [Sha256],
+) -> Result<(), String> {
+    for (idx, (path, chunk)) in paths.iter().zip(target.chunks_exact_mut(len)).enumerate() {
+        let fail = |e: io::Error| tr!(RecoveryFailed, path.display(), e);
+        let mut file = File::open(path).map_err(fail)?;
+        let size = file.metadata().map_err(fail)?.len();
+        let available = (size.saturating_sub(offset) as usize).min(len);
+        if available > 0 {
+            file.seek(SeekFrom::Start(offset)).map_err(fail)?;
+            file.read_exact(&mut chunk[..available]).map_err(fail)?;
+        }
+        chunk[available..].fill(0);
+        if let Some(hasher) = hashers.get_mut(idx) {
+            hasher.update(&chunk[..available]);
+        }
+    }
+    Ok(())
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+fn save(path: &Path, set: &RecoverySet) -> Result<(), String> {
+    let text = serde_json::to_string_pretty(set).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, text).map_err(|e| tr!(RecoveryFailed, temp.display(), e))?;
+    fs::rename(&temp, path).map_err(|e| tr!(RecoveryFailed, path.display(), e))
+}
+
+/// 删除上次生成的同名恢复分片与校验分片，份数或方式变化时旧的不会被覆盖。
+fn remove_stale(dir: &Path, base_name: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let prefix = format!("{}.recovery-", base_name);
+    let parity = format!("{}.parity", base_name);
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        if name.starts_with(&prefix) || name == parity {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// 为分片生成 `count` 份恢复分片与 `<名称>.recovery.json`，写在分片所在目录，
+/// 返回生成的文件；进度阶段为 `parity`。
+pub(crate) fn create_recovery_parts(
+    ctx: &JobContext,
+    base_name: &str,
+    parts: &[String],
+    count: u32,
+) -> Result<Vec<String>, String> {
+    create(ctx, Scheme::ReedSolomon, base_name, parts, count as usize)
+}
+
+/// 为分片生成一份异或校验分片 `<名称>.parity` 与 `<名称>.recovery.json`。
+pub(crate) fn create_parity_part(
+    ctx: &JobContext,
+    base_name: &str,
+    parts: &[String],
+) -> Result<Vec<String>, String> {
+    create(ctx, Scheme::Xor, base_name, parts, 1)
+}
+
+fn create(
+    ctx: &JobContext,
+    scheme: Scheme,
+    base_name: &str,
+    parts: &[String],
+    count: usize,
+) -> Result<Vec<String>, String> {
+    let Some(dir) = parts.first().and_then(|path| Path::new(path).parent()) else {
+        return Ok(Vec::new());
+    };
+    if parts.len() + count > MAX_TOTAL_PARTS {
+        return Err(tr!(RecoveryTooManyParts, parts.len(), MAX_TOTAL_PARTS - count));
+    }
+    let paths: Vec<PathBuf> = parts.iter().map(PathBuf::from).collect();
+    let sizes = paths
+        .iter()
+        .map(|path| fs::metadata(path).map(|meta| meta.len()))
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|e| tr!(RecoveryFailed, dir.display(), e))?;
+    let total: u64 = sizes.iter().sum();
+    let shard_size = sizes.iter().copied().max().unwrap_or(0).div_ceil(2) * 2;
+
+    remove_stale(dir, base_name);
+    let width = count.to_string().len();
+    let mut outputs = Vec::with_capacity(count);
+    for idx in 0..count {
+        let path = match scheme {
+            Scheme::ReedSolomon => dir.join(format!("{}.recovery-{:0width$}", base_name, idx + 1)),
+            Scheme::Xor => dir.join(format!("{}.parity", base_name)),
+        };
+        ctx.track_output(&path);
+        let file = File::create(&path).map_err(|e| tr!(RecoveryFailed, path.display(), e))?;
+        outputs.push((path, file, Sha256::new()));
+    }
+
+    let field = parity::field();
+    let matrix: Vec<Vec<u16>> = (0..count)
+        .map(|row| {
+            (0..paths.len())
+                .map(|col| coefficient(field, scheme, count, row, col))
+                .collect()
+        })
+        .collect();
+    let mut data_hashers = vec![Sha256::new(); paths.len()];
+    let window = window_size(ctx, shard_size, paths.len() + count);
+    let mut sources = vec![0u8; paths.len() * window];
+    let mut recovery = vec![0u8; count * window];
+    let mut offset = 0u64;
+    while offset < shard_size {
+        ctx.check_cancelled()?;
+        let len = (window as u64).min(shard_size - offset) as usize;
+        let sources = &mut sources[..paths.len() * len];
+        read_window(&paths, offset, len, sources, &mut data_hashers)?;
+        let recovery = &mut recovery[..count * len];
+        parity::combine(ctx, &matrix, sources, recovery, len);
+        for ((path, file, hasher), data) in outputs.iter_mut().zip(recovery.chunks_exact(len)) {
+            file.write_all(data)
+                .map_err(|e| tr!(RecoveryFailed, path.display(), e))?;
+            hasher.update(data);
+        }
+        offset += len as u64;
+        let encoded = (total as u128 * offset as u128 / shard_size as u128) as u64;
+        emit_progress(ctx, "parity", encoded, total, parts.len(), parts.len(), tr!(RecoveryEncoding));
+    }
+
+    let data = paths
+        .iter()
+        .zip(sizes)
+        .zip(data_hashers)
+        .map(|((path, size), hasher)| Shard {
+            name: file_name(path),
+            size,
+            sha256: format!("{:x}", hasher.finalize()),
+        })
+        .collect();
+    let mut recovery_shards = Vec::with_capacity(count);
+    let mut files = Vec::with_capacity(count + 1);
+    for (path, mut file, hasher) in outputs {
+        file.flush().map_err(|e| tr!(RecoveryFailed, path.display(), e))?;
+        recovery_shards.push(Shard {
+            name: file_name(&path),
+            size: shard_size,
+            sha256: format!("{:x}", hasher.finalize()),
+        });
+        files.push(path.to_string_lossy().to_string());
+    }
+    let set_path = dir.join(format!("{}{}", base_name, SET_SUFFIX));
+    ctx.track_output(&set_path);
+    save(
+        &set_path,
+        &RecoverySet {
+            version: SET_VERSION,
+            scheme,
+            shard_size,
+            data,
+            recovery: recovery_shards,
+        },
+    )?;
+    files.insert(0, set_path.to_string_lossy().to_string());
+    Ok(files)
+}
+
+/// 找出输入对应的恢复分片记录；输入为分片文件时只取包含该分片的记录。
+fn find(input_path: &Path) -> Result<Option<(PathBuf, RecoverySet)>, String> {
+    let (dir, part_name) = if input_path.is_dir() {
+        (input_path.to_path_buf(), None)
+    } else {
+        match input_path.parent() {
+            Some(dir) => (dir.to_path_buf(), Some(file_name(input_path))),
+            None => return Ok(None),
+        }
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(None);
+    };
+    let mut found = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        if !path.is_file() || !file_name(&path).ends_with(SET_SUFFIX) {
+            continue;
+        }
+        let text = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+        let set: RecoverySet = serde_json::from_str(&text).map_err(|e| tr!(ManifestInvalid, e))?;
+        if set.version > SET_VERSION {
+            return Err(tr!(ManifestVersionUnsupported, set.version));
+        }
+        if part_name
+            .as_ref()
+            .is_none_or(|name| set.data.iter().any(|shard| &shard.name == name))
+        {
+            found.push(set);
+        }
+    }
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(found.pop().map(|set| (dir, set))),
+        _ => Err(tr!(MultipleRecoverySets)),
+    }
+}
+
+/// 分片存在且大小与 SHA-256 都与记录一致。
+fn shard_intact(
+    ctx: &JobContext,
+    path: &Path,
+    shard: &Shard,
+    mut progress: impl FnMut(u64),
+) -> Result<bool, String> {
+    if fs::metadata(path).map(|meta| meta.len()).ok() != Some(shard.size) {
+        return Ok(false);
+    }
+    let Ok(mut file) = File::open(path) else {
+        return Ok(false);
+    };
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    loop {
+        ctx.check_cancelled()?;
+        let Ok(read_len) = file.read(&mut buffer) else {
+            return Ok(false);
+        };
+        if read_len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read_len]);
+        progress(read_len as u64);
+    }
+    Ok(format!("{:x}", hasher.finalize()) == shard.sha256)
+}
+
+/// 合并前调用：输入对应的分片有恢复分片记录时逐份核对，缺失或损坏的数据分片不超过
+/// 可用的恢复分片时重建并写回分片目录，返回重建的分片；进度阶段为 `repair`。
+pub(crate) fn reconstruct(ctx: &JobContext, input_path: &Path) -> Result<Vec<String>, String> {
+    match repair(ctx, input_path)? {
+        Some(Outcome {
+            error: Some(error), ..
+        }) => Err(error),
+        Some(outcome) => Ok(outcome.repaired),
+        None => Ok(Vec::new()),
+    }
+}
+
+/// 逐份核对输入对应的数据分片与恢复分片，能重建时重建并写回分片目录；
+/// 没有恢复分片记录时返回 `None`，恢复分片不够时由 `error` 说明。
+pub(crate) fn repair(ctx: &JobContext, input_path: &Path) -> Result<Option<Outcome>, String> {
+    let Some((dir, set)) = find(input_path)? else {
+        return Ok(None);
+    };
+    let shards: Vec<&Shard> = set.data.iter().chain(&set.recovery).collect();
+    let total: u64 = shards.iter().map(|shard| shard.size).sum();
+    let mut processed = 0u64;
+    let mut intact = Vec::with_capacity(shards.len());
+    for (idx, shard) in shards.iter().enumerate() {
+        intact.push(shard_intact(ctx, &dir.join(&shard.name), shard, |delta| {
+            processed += delta;
+            emit_progress(ctx, "repair", processed, total, idx + 1, shards.len(), tr!(VerifyingPart, idx + 1));
+        })?);
+    }
+    let (data_intact, recovery_intact) = intact.split_at(set.data.len());
+    let damaged: Vec<usize> = (0..set.data.len()).filter(|&idx| !data_intact[idx]).collect();
+    let mut outcome = Outcome {
+        method: match set.scheme {
+            Scheme::ReedSolomon => "reedSolomon",
+            Scheme::Xor => "xor",
+        },
+        damaged: damaged.iter().map(|&idx| set.data[idx].name.clone()).collect(),
+        repaired: Vec::new(),
+        error: None,
+    };
+    if damaged.is_empty() {
+        return Ok(Some(outcome));
+    }
+    let available: Vec<usize> = (0..set.recovery.len())
+        .filter(|&idx| recovery_intact[idx])
+        .collect();
+    if available.len() < damaged.len() {
+        outcome.error = Some(tr!(RecoveryNotEnough, damaged.len(), available.len()));
+        return Ok(Some(outcome));
+    }
+    let rows = &available[..damaged.len()];
+    let present: Vec<usize> = (0..set.data.len()).filter(|&idx| data_intact[idx]).collect();
+    log::info!(
+        "用恢复分片重建 {} 份数据分片：{}",
+        damaged.len(),
+        outcome.damaged.join(", ")
+    );
+
+    // 恢复分片减去完好数据分片的贡献，剩下的是缺失分片的线性组合，乘以其系数方阵的逆即可解出
+    let field = parity::field();
+    let (scheme, count) = (set.scheme, set.recovery.len());
+    let syndrome_matrix: Vec<Vec<u16>> = rows
+        .iter()
+        .enumerate()
+        .map(|(pos, &row)| {
+            present
+                .iter()
+                .map(|&col| coefficient(field, scheme, count, row, col))
+                .chain((0..rows.len()).map(|idx| u16::from(idx == pos)))
+                .collect()
+        })
+        .collect();
+    let missing_matrix: Vec<Vec<u16>> = rows
+        .iter()
+        .map(|&row| {
+            damaged
+                .iter()
+                .map(|&col| coefficient(field, scheme, count, row, col))
+                .collect()
+        })
+        .collect();
+    let solve = field
+        .invert(missing_matrix)
+        .ok_or_else(|| tr!(RecoveryNotEnough, damaged.len(), available.len()))?;
+
+    let inputs: Vec<PathBuf> = present
+        .iter()
+        .map(|&idx| dir.join(&set.data[idx].name))
+        .chain(rows.iter().map(|&idx| dir.join(&set.recovery[idx].name)))
+        .collect();
+    let mut outputs = Vec::with_capacity(damaged.len());
+    for &idx in &damaged {
+        let path = dir.join(format!("{}.repair.tmp", set.data[idx].name));
+        let file = File::create(&path).map_err(|e| tr!(RecoveryFailed, path.display(), e))?;
+        outputs.push((path, file, Sha256::new(), &set.data[idx]));
+    }
+    let result = rebuild(ctx, &set, &inputs, &syndrome_matrix, &solve, &mut outputs);
+    let mut repaired = Vec::with_capacity(outputs.len());
+    for (temp, file, hasher, shard) in outputs {
+        drop(file);
+        let target = dir.join(&shard.name);
+        if result.is_err() {
+            let _ = fs::remove_file(&temp);
+            continue;
+        }
+        if format!("{:x}", hasher.finalize()) != shard.sha256 {
+            let _ = fs::remove_file(&temp);
+            return Err(tr!(RecoveryRebuildMismatch, shard.name));
+        }
+        let _ = fs::remove_file(&target);
+        fs::rename(&temp, &target).map_err(|e| tr!(RecoveryFailed, target.display(), e))?;
+        repaired.push(target.to_string_lossy().to_string());
+    }
+    result?;
+    outcome.repaired = repaired;
+    Ok(Some(outcome))
+}
+
+/// 逐段解出缺失分片写入临时文件，长度截到记录的大小。
+fn rebuild(
+    ctx: &JobContext,
+    set: &RecoverySet,
+    inputs: &[PathBuf],
+    syndrome_matrix: &[Vec<u16>],
+    solve: &[Vec<u16>],
+    outputs: &mut [(PathBuf, File, Sha256, &Shard)],
+) -> Result<(), String> {
+    let window = window_size(ctx, set.shard_size, inputs.len() + 2 * outputs.len());
+    let mut sources = vec![0u8; inputs.len() * window];
+    let mut syndromes = vec![0u8; outputs.len() * window];
+    let mut missing = vec![0u8; outputs.len() * window];
+    let mut offset = 0u64;
+    while offset < set.shard_size {
+        ctx.check_cancelled()?;
+        let len = (window as u64).min(set.shard_size - offset) as usize;
+        let sources = &mut sources[..inputs.len() * len];
+        read_window(inputs, offset, len, sources, &mut [])?;
+        let syndromes = &mut syndromes[..outputs.len() * len];
+        parity::combine(ctx, syndrome_matrix, sources, syndromes, len);
+        let missing = &mut missing[..outputs.len() * len];
+        parity::combine(ctx, solve, syndromes, missing, len);
+        for ((path, file, hasher, shard), data) in outputs.iter_mut().zip(missing.chunks_exact(len)) {
+            let keep = (shard.size.saturating_sub(offset) as usize).min(len);
+            file.write_all(&data[..keep])
+                .map_err(|e| tr!(RecoveryFailed, path.display(), e))?;
+            hasher.update(&data[..keep]);
+        }
+        offset += len as u64;
+        emit_progress(ctx, "repair", offset, set.shard_size, outputs.len(), outputs.len(), tr!(RepairingParts));
+    }
+    Ok(())
+}
@@ -0,0 +1,368 @@
+//! 合并前预估：只读取分片头部与压缩包目录，给出合并后的文件大小、解压后的总大小、条目数
+//! 以及所需的磁盘空间，避免在空间不足的磁盘上合并到一半才失败。
+//!
+//! [`plan_restore_blocking`] 在此之上另外识别合并方式、列出分片并判断是否需要密码，
+//! 供界面与脚本在写入任何数据之前确认能否合并。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+};
+use zip::ZipArchive;
+
+use crate::{
+    append, collect_part_group, detect, i18n::tr, index, part_reader::PartReader, partial,
+    secrets::SecretString, seekable::SeekableReader, sevenz, spanned, PartGroup, PartInfo,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EstimateRestoreOptions {
+    input_path: String,
+    merge_mode: String,
+    /// 合并输出目录，用于查询可用空间；未指定时不检查。
+    output_dir: Option<String>,
+    /// 7z 头部加密时需要。
+    password: Option<SecretString>,
+    /// 是否计入自动解压所需的空间，默认是。
+    auto_extract: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlanRestoreOptions {
+    input_path: String,
+    /// 合并方式，未指定或为 `auto` 时识别。
+    merge_mode: Option<String>,
+    /// 合并输出目录，用于查询可用空间；未指定时不检查。
+    output_dir: Option<String>,
+    /// 7z 头部加密时用于读取条目。
+    password: Option<SecretString>,
+    /// 是否计入自动解压所需的空间，默认是。
+    auto_extract: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct EstimateRestoreResult {
+    /// 合并后的文件大小。
+    merged_bytes: u64,
+    /// 解压后的文件总大小；合并结果不是压缩包或无法不解压读取目录时为 `None`。
+    extracted_bytes: Option<u64>,
+    /// 压缩包内的文件条目数（不含目录）。
+    entry_count: Option<usize>,
+    /// 合并（及解压）需要的磁盘空间。
+    required_bytes: u64,
+    /// 输出目录所在磁盘的可用空间。
+    available_bytes: Option<u64>,
+    /// 可用空间是否足够；未指定输出目录时为 `None`。
+    enough_space: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlanRestoreResult {
+    /// 指定或识别出的合并方式。
+    merge_mode: String,
+    /// 分片组的名称（分片文件名中序号之前的部分）。
+    group: String,
+    /// 追加打包过的分片目录中的批次数，否则为 1。
+    batches: usize,
+    /// 全部分片，按合并顺序排列。
+    parts: Vec<PlannedPart>,
+    part_count: usize,
+    /// 全部分片的大小之和。
+    parts_bytes: u64,
+    /// 合并或解压是否需要密码；分片经过压缩等无法不解压判断时为 `None`。
+    password_required: Option<bool>,
+    #[serde(flatten)]
+    estimate: EstimateRestoreResult,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PlannedPart {
+    path: String,
+    size: u64,
+}
+
+/// 一组分片的预估。
+struct GroupEstimate {
+    merged_bytes: u64,
+    /// 条目数与解压后的总大小。
+    contents: Option<(usize, u64)>,
+    /// 是否需要密码。
+    encrypted: Option<bool>,
+}
+
+/// 全部批次合计的预估。
+struct Totals {
+    merged_bytes: u64,
+    contents: Option<(usize, u64)>,
+    encrypted: Option<bool>,
+}
+
+pub(crate) fn estimate_restore_blocking(
+    options: EstimateRestoreOptions,
+) -> Result<EstimateRestoreResult, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let merge_mode = options.merge_mode.as_str();
+    let password = options.password.as_deref().filter(|value| !value.is_empty());
+    let groups = restore_groups(&input_path, merge_mode)?;
+    let totals = estimate_groups(&groups, merge_mode, password)?;
+    Ok(space_estimate(
+        &totals,
+        options.auto_extract.unwrap_or(true),
+        options.output_dir.as_deref(),
+    ))
+}
+
+/// 合并前的检查：识别合并方式与分片组，列出分片，判断是否需要密码并预估所需空间。不写入任何数据。
+pub(crate) fn plan_restore_blocking(
+    options: PlanRestoreOptions,
+) -> Result<PlanRestoreResult, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let merge_mode = match options.merge_mode.as_deref().filter(|value| !value.is_empty()) {
+        None | Some(detect::AUTO) => detect::detect_merge_mode(&input_path, false)?,
+        Some(mode) => mode.to_string(),
+    };
+    let password = options.password.as_deref().filter(|value| !value.is_empty());
+    let groups = restore_groups(&input_path, &merge_mode)?;
+    let totals = estimate_groups(&groups, &merge_mode, password)?;
+
+    let mut parts = Vec::new();
+    for part in groups.iter().flat_map(|group| &group.parts) {
+        parts.push(PlannedPart {
+            path: part.path.to_string_lossy().to_string(),
+            size: part_size(part)?,
+        });
+    }
+    Ok(PlanRestoreResult {
+        group: groups[0].prefix.clone(),
+        batches: groups.len(),
+        part_count: parts.len(),
+        parts_bytes: parts.iter().map(|part| part.size).sum(),
+        parts,
+        password_required: totals.encrypted,
+        estimate: space_estimate(
+            &totals,
+            options.auto_extract.unwrap_or(true),
+            options.output_dir.as_deref(),
+        ),
+        merge_mode,
+    })
+}
+
+/// 合并时依次处理的分片组：追加过的分片目录按批次分别合并、解压到同一目录，分卷 zip 的各卷为一组。
+fn restore_groups(input_path: &Path, merge_mode: &str) -> Result<Vec<PartGroup>, String> {
+    if merge_mode == "spanned-zip" {
+        let volumes = spanned::find_volumes(input_path)?;
+        let prefix = volumes
+            .last()
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let parts = volumes
+            .into_iter()
+            .enumerate()
+            .map(|(index, path)| PartInfo {
+                index: index + 1,
+                path,
+            })
+            .collect();
+        return Ok(vec![PartGroup { prefix, parts }]);
+    }
+    match append::load_manifest(input_path)? {
+        Some((_, manifest)) if input_path.is_dir() && merge_mode == "zip-then-split" => manifest
+            .first_parts(input_path)
+            .iter()
+            .map(|first| collect_part_group(first))
+            .collect(),
+        _ => Ok(vec![collect_part_group(input_path)?]),
+    }
+}
+
+fn estimate_groups(
+    groups: &[PartGroup],
+    merge_mode: &str,
+    password: Option<&str>,
+) -> Result<Totals, String> {
+    let mut totals = Totals {
+        merged_bytes: 0,
+        contents: Some((0, 0)),
+        encrypted: Some(false),
+    };
+    for group in groups {
+        let estimate = estimate_group(group, merge_mode, password)?;
+        totals.merged_bytes += estimate.merged_bytes;
+        totals.contents = match (totals.contents, estimate.contents) {
+            (Some((count, size)), Some((more, bytes))) => Some((count + more, size + bytes)),
+            _ => None,
+        };
+        // 任何一批需要密码即需要；否则有一批无法判断时整体无法判断
+        totals.encrypted = match (totals.encrypted, estimate.encrypted) {
+            (Some(true), _) | (_, Some(true)) => Some(true),
+            (Some(false), Some(false)) => Some(false),
+            _ => None,
+        };
+    }
+    Ok(totals)
+}
+
+fn space_estimate(
+    totals: &Totals,
+    auto_extract: bool,
+    output_dir: Option<&str>,
+) -> EstimateRestoreResult {
+    let extracted_bytes = totals.contents.map(|(_, size)| size);
+    let required_bytes = totals.merged_bytes
+        + if auto_extract {
+            extracted_bytes.unwrap_or(0)
+        } else {
+            0
+        };
+    let available_bytes = output_dir
+        .filter(|value| !value.is_empty())
+        .and_then(|dir| available_space(Path::new(dir)));
+    EstimateRestoreResult {
+        merged_bytes: totals.merged_bytes,
+        extracted_bytes,
+        entry_count: totals.contents.map(|(count, _)| count),
+        required_bytes,
+        available_bytes,
+        enough_space: available_bytes.map(|available| available >= required_bytes),
+    }
+}
+
+fn estimate_group(
+    part_group: &PartGroup,
+    merge_mode: &str,
+    password: Option<&str>,
+) -> Result<GroupEstimate, String> {
+    let paths: Vec<PathBuf> = part_group.parts.iter().map(|part| part.path.clone()).collect();
+    match merge_mode {
+        // 直接拼接，或解密后去掉的开销很小，按分片大小估计
+        "raw" | "age-split" | "container-then-split" => {
+            let mut merged_bytes = 0;
+            for part in &part_group.parts {
+                merged_bytes += part_size(part)?;
+            }
+            return Ok(GroupEstimate {
+                merged_bytes,
+                contents: None,
+                encrypted: Some(merge_mode != "raw"),
+            });
+        }
+        "spanned-zip" => {
+            let reader = spanned::SpannedReader::open(&paths)?;
+            let merged_bytes = reader.len();
+            let summary = ZipArchive::new(BufReader::new(reader))
+                .ok()
+                .and_then(|mut archive| zip_summary(&mut archive));
+            return Ok(GroupEstimate {
+                merged_bytes,
+                contents: summary.map(|(count, size, _)| (count, size)),
+                encrypted: summary.map(|(_, _, encrypted)| encrypted),
+            });
+        }
+        _ => {}
+    }
+    let layout = index::part_layout(part_group, merge_mode)?;
+    let data_bytes: u64 = layout.iter().map(|part| part.len).sum();
+    match merge_mode {
+        "7z-then-split" => {
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            let (contents, encrypted) = sevenz::entry_totals(reader, data_bytes, password)?;
+            Ok(GroupEstimate {
+                merged_bytes: data_bytes,
+                contents,
+                encrypted: Some(encrypted),
+            })
+        }
+        "zstd-then-split" => {
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            let merged_bytes = SeekableReader::open_parts(&paths, reader)
+                .map_err(|e| e.to_string())?
+                .len();
+            let summary = zip_contents(part_group, merge_mode, &layout);
+            Ok(GroupEstimate {
+                merged_bytes,
+                contents: summary.map(|(count, size, _)| (count, size)),
+                encrypted: summary.map(|(_, _, encrypted)| encrypted),
+            })
+        }
+        // 不解压就无法知道 tar 中的条目；tar 方式不支持密码
+        "tar-gz-then-split" | "tar-zst-then-split" => Ok(GroupEstimate {
+            merged_bytes: data_bytes,
+            contents: None,
+            encrypted: Some(false),
+        }),
+        "split-then-zip" | "zip-then-split" => {
+            let summary = zip_contents(part_group, merge_mode, &layout);
+            let encrypted = summary.map(|(_, _, encrypted)| encrypted).or_else(|| {
+                (merge_mode == "split-then-zip")
+                    .then(|| wrapped_encrypted(&part_group.parts[0].path))
+                    .flatten()
+            });
+            Ok(GroupEstimate {
+                merged_bytes: data_bytes,
+                contents: summary.map(|(count, size, _)| (count, size)),
+                encrypted,
+            })
+        }
+        _ => Err(tr!(UnknownMergeMode)),
+    }
+}
+
+/// 合并结果是 zip 时从目录中统计条目数、解压后的总大小与是否有加密条目；
+/// 分片内压缩过（先分割后压缩且非 Store）时无法直接读取。
+fn zip_contents(
+    part_group: &PartGroup,
+    merge_mode: &str,
+    layout: &[index::IndexedPart],
+) -> Option<(usize, u64, bool)> {
+    let mut archive = partial::open_archive_from_layout(part_group, merge_mode, layout).ok()?;
+    zip_summary(&mut archive)
+}
+
+fn zip_summary<R: std::io::Read + std::io::Seek>(
+    archive: &mut ZipArchive<R>,
+) -> Option<(usize, u64, bool)> {
+    let mut totals = (0usize, 0u64, false);
+    for index in 0..archive.len() {
+        let entry = archive.by_index_raw(index).ok()?;
+        totals.2 |= entry.encrypted();
+        if !entry.is_dir() {
+            totals = (totals.0 + 1, totals.1 + entry.size(), totals.2);
+        }
+    }
+    Some(totals)
+}
+
+/// 先分割后压缩的分片本身是 zip，其中的分片条目加密时合并需要密码。
+fn wrapped_encrypted(first_part: &Path) -> Option<bool> {
+    let file = File::open(first_part).ok()?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).ok()?;
+    let entry = archive.by_index_raw(0).ok()?;
+    Some(entry.encrypted())
+}
+
+fn part_size(part: &PartInfo) -> Result<u64, String> {
+    fs::metadata(&part.path)
+        .map(|meta| meta.len())
+        .map_err(|e| e.to_string())
+}
+
+/// 目录尚未创建时取最近一个已存在的上级目录。
+fn available_space(dir: &Path) -> Option<u64> {
+    let existing = dir.ancestors().find(|path| path.exists())?;
+    fs4::available_space(existing).ok()
+}
@@ -0,0 +1,250 @@
+//! 自动解压合并结果：按文件开头的签名识别 zip、7z、RAR、tar（可带 gzip、Zstandard 或 xz 外层压缩）
+//! 以及单个文件的 gzip、Zstandard 与 xz 压缩流，分派给对应的解压方式，不看扩展名。
+
+use std::{
+    cell::Cell,
+    fs::{self, File},
+    io::{BufReader, BufWriter, Read, Seek, Write},
+    path::Path,
+    rc::Rc,
+};
+
+use crate::{
+    check_zip_password, emit_progress,
+    i18n::tr,
+    hidden_names, index, is_zip_file,
+    jobs::JobContext,
+    password_prompt, rar, sevenz,
+    tar::{self, CountingReader, TarDecoding},
+    unzip_file,
+};
+
+const GZIP_SIGNATURE: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_SIGNATURE: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+const XZ_SIGNATURE: [u8; 6] = [0xfd, 0x37, 0x7a, 0x58, 0x5a, 0x00];
+
+/// 能自动解压的扩展名，长的在前，去掉扩展名时先匹配 `.tar.gz` 再匹配 `.gz`。
+const EXTENSIONS: [&str; 10] = [
+    ".tar.gz", ".tar.zst", ".tar.xz", ".tar", ".zip", ".7z", ".rar", ".gz", ".zst", ".xz",
+];
+
+/// 合并结果的格式。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum ArchiveKind {
+    Zip,
+    SevenZ,
+    Rar,
+    /// tar 包及其外层压缩。
+    Tar(TarDecoding),
+    /// 压缩的单个文件（不是 tar）。
+    Compressed(TarDecoding),
+}
+
+impl ArchiveKind {
+    /// 按文件开头的签名识别；外层为 gzip、Zstandard 或 xz 时解压开头的一个块，判断其中是否为 tar。
+    pub(crate) fn detect(path: &Path) -> Result<Option<ArchiveKind>, String> {
+        if is_zip_file(path)? {
+            return Ok(Some(ArchiveKind::Zip));
+        }
+        if sevenz::is_sevenz_file(path)? {
+            return Ok(Some(ArchiveKind::SevenZ));
+        }
+        if rar::is_rar_file(path)? {
+            return Ok(Some(ArchiveKind::Rar));
+        }
+
+        let mut file = File::open(path).map_err(|e| e.to_string())?;
+        let mut signature = [0u8; 6];
+        let read_len = read_up_to(&mut file, &mut signature).map_err(|e| e.to_string())?;
+        let signature = &signature[..read_len];
+        let decoding = if signature.starts_with(&GZIP_SIGNATURE) {
+            TarDecoding::Gzip
+        } else if signature.starts_with(&ZSTD_SIGNATURE) {
+            TarDecoding::Zstd
+        } else if signature.starts_with(&XZ_SIGNATURE) {
+            TarDecoding::Xz
+        } else {
+            TarDecoding::None
+        };
+
+        file.rewind().map_err(|e| e.to_string())?;
+        let mut block = [0u8; 512];
+        // 开头解不出来时不当作 tar，解压单个文件时再报告错误
+        let is_tar = tar::open_decoder(BufReader::new(file), decoding)
+            .and_then(|mut decoder| read_up_to(&mut decoder, &mut block))
+            .is_ok_and(|len| tar::is_tar_header(&block[..len]));
+        Ok(match decoding {
+            _ if is_tar => Some(ArchiveKind::Tar(decoding)),
+            TarDecoding::None => None,
+            _ => Some(ArchiveKind::Compressed(decoding)),
+        })
+    }
+
+    /// 格式名称，写入合并结果的 `archiveType`。
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            ArchiveKind::Zip => "zip",
+            ArchiveKind::SevenZ => "7z",
+            ArchiveKind::Rar => "rar",
+            ArchiveKind::Tar(TarDecoding::None) => "tar",
+            ArchiveKind::Tar(TarDecoding::Gzip) => "tar.gz",
+            ArchiveKind::Tar(TarDecoding::Zstd) => "tar.zst",
+            ArchiveKind::Tar(TarDecoding::Xz) => "tar.xz",
+            ArchiveKind::Compressed(TarDecoding::Gzip) => "gzip",
+            ArchiveKind::Compressed(TarDecoding::Zstd) => "zstd",
+            ArchiveKind::Compressed(TarDecoding::Xz) => "xz",
+            ArchiveKind::Compressed(TarDecoding::None) => "none",
+        }
+    }
+
+    /// 合并结果应有的扩展名。
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            ArchiveKind::Zip => ".zip",
+            ArchiveKind::SevenZ => ".7z",
+            ArchiveKind::Rar => ".rar",
+            ArchiveKind::Tar(TarDecoding::None) => ".tar",
+            ArchiveKind::Tar(TarDecoding::Gzip) => ".tar.gz",
+            ArchiveKind::Tar(TarDecoding::Zstd) => ".tar.zst",
+            ArchiveKind::Tar(TarDecoding::Xz) => ".tar.xz",
+            ArchiveKind::Compressed(TarDecoding::Gzip) => ".gz",
+            ArchiveKind::Compressed(TarDecoding::Zstd) => ".zst",
+            ArchiveKind::Compressed(TarDecoding::Xz) => ".xz",
+            ArchiveKind::Compressed(TarDecoding::None) => "",
+        }
+    }
+}
+
+/// 去掉能自动解压的扩展名，得到解压目录或解压出的文件名。
+pub(crate) fn strip_extension(name: &str) -> &str {
+    EXTENSIONS
+        .iter()
+        .find_map(|extension| name.strip_suffix(extension))
+        .filter(|stem| !stem.is_empty())
+        .unwrap_or(name)
+}
+
+/// 解压合并结果到 `output_dir`。`layout` 为合并结果由哪些分片直接拼接而成，zip 条目损坏时据此
+/// 指出数据所在的分片与字节区间。认不出的数据按 zip 处理，以便尽力解压时扫描文件头。
+pub(crate) fn extract_archive(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    layout: Option<&[index::IndexedPart]>,
+) -> Result<(), String> {
+    let kind = ArchiveKind::detect(archive_path)?;
+    if ctx.filters_entries() && !matches!(kind, Some(ArchiveKind::Zip) | None) {
+        log::warn!("选择性解压只用于 zip，{} 将全部解压", archive_path.display());
+    }
+    // 密码不对或缺少密码时向界面要新密码，重试解压而不必重新合并。先核对密码再解压，
+    // 解压开始后的错误不再重试
+    password_prompt::retry(ctx, archive_path, password, |password| match kind {
+        Some(ArchiveKind::SevenZ) => {
+            sevenz::check_password(archive_path, password)?;
+            Ok(sevenz::extract_sevenz(
+                ctx,
+                archive_path,
+                output_dir,
+                password,
+            )?)
+        }
+        Some(ArchiveKind::Rar) => {
+            create_output_dir(ctx, output_dir)?;
+            rar::extract(ctx, archive_path, output_dir, password)
+        }
+        Some(ArchiveKind::Tar(decoding)) => {
+            Ok(tar::extract_tar(ctx, archive_path, output_dir, decoding)?)
+        }
+        Some(ArchiveKind::Compressed(decoding)) => {
+            Ok(decompress_file(ctx, archive_path, output_dir, decoding)?)
+        }
+        Some(ArchiveKind::Zip) if hidden_names::is_wrapped(archive_path) => {
+            check_zip_password(archive_path, password)?;
+            Ok(hidden_names::extract(
+                ctx,
+                archive_path,
+                output_dir,
+                password,
+            )?)
+        }
+        Some(ArchiveKind::Zip) | None => {
+            check_zip_password(archive_path, password)?;
+            Ok(unzip_file(ctx, archive_path, output_dir, password, layout)?)
+        }
+    })
+}
+
+fn create_output_dir(ctx: &JobContext, output_dir: &Path) -> Result<(), String> {
+    if !output_dir.exists() {
+        ctx.track_output(output_dir);
+    }
+    fs::create_dir_all(output_dir).map_err(|e| e.to_string())
+}
+
+/// 解压单个文件的压缩流，输出文件名为压缩文件名去掉扩展名；出错时删掉写了一半的文件。
+fn decompress_file(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    decoding: TarDecoding,
+) -> Result<(), String> {
+    create_output_dir(ctx, output_dir)?;
+    let archive_name = archive_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| tr!(CannotParseFileName))?;
+    let name = strip_extension(archive_name);
+    let out_path = output_dir.join(name);
+
+    let file = File::open(archive_path).map_err(|e| e.to_string())?;
+    let total_bytes = file.metadata().map_err(|e| e.to_string())?.len();
+    let read_bytes = Rc::new(Cell::new(0u64));
+    let counter = CountingReader {
+        inner: BufReader::new(file),
+        count: Rc::clone(&read_bytes),
+    };
+    let mut decoder = tar::open_decoder(counter, decoding).map_err(|e| e.to_string())?;
+    let mut writer = BufWriter::new(File::create(&out_path).map_err(|e| e.to_string())?);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    emit_progress(ctx, "unzip", 0, total_bytes, 1, 1, tr!(Extracting));
+    let copied = loop {
+        if let Err(err) = ctx.check_cancelled() {
+            break Err(err);
+        }
+        let read_len = match decoder.read(&mut buffer) {
+            Ok(0) => break writer.flush().map_err(|e| e.to_string()),
+            Ok(read_len) => read_len,
+            Err(err) => break Err(tr!(EntryCorrupt, name, err)),
+        };
+        if let Err(err) = writer.write_all(&buffer[..read_len]) {
+            break Err(err.to_string());
+        }
+        emit_progress(
+            ctx,
+            "unzip",
+            read_bytes.get(),
+            total_bytes,
+            1,
+            1,
+            tr!(Extracting),
+        );
+    };
+    if copied.is_err() {
+        drop(writer);
+        let _ = fs::remove_file(&out_path);
+    }
+    copied
+}
+
+/// 尽量读满 `buffer`，数据不足时返回实际读到的长度。
+fn read_up_to(reader: &mut impl Read, buffer: &mut [u8]) -> std::io::Result<usize> {
+    let mut filled = 0;
+    while filled < buffer.len() {
+        match reader.read(&mut buffer[filled..])? {
+            0 => break,
+            len => filled += len,
+        }
+    }
+    Ok(filled)
+}
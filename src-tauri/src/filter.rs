@@ -0,0 +1,127 @@
+//! 外部过滤命令：打包后把每份分片的内容经用户指定的命令转换（如 `openssl enc`），
+//! 恢复前用逆向命令还原。命令从标准输入读取原内容，向标准输出写出转换结果，
+//! 通过系统 shell 执行，可使用环境变量 `FSP_PART_INDEX`、`FSP_PART_NAME`。
+
+use std::{
+    fs::{self, File},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+};
+
+use crate::{emit_progress, i18n::tr, jobs::JobContext, PartGroup};
+
+fn shell_command(command: &str) -> Command {
+    if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.args(["/C", command]);
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.args(["-c", command]);
+        cmd
+    }
+}
+
+/// 以 `input` 为标准输入、`output` 为标准输出运行一次过滤命令。
+fn run_filter(command: &str, input: &Path, output: &Path, index: usize) -> Result<(), String> {
+    let stdin = File::open(input).map_err(|e| e.to_string())?;
+    let stdout = File::create(output).map_err(|e| e.to_string())?;
+    let name = input
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let result = shell_command(command)
+        .env("FSP_PART_INDEX", index.to_string())
+        .env("FSP_PART_NAME", name)
+        .stdin(Stdio::from(stdin))
+        .stdout(Stdio::from(stdout))
+        .stderr(Stdio::piped())
+        .output()
+        .map_err(|e| tr!(FilterStartFailed, e))?;
+    if !result.status.success() {
+        let stderr = String::from_utf8_lossy(&result.stderr);
+        let reason = stderr
+            .lines()
+            .rev()
+            .find(|line| !line.trim().is_empty())
+            .map(|line| line.trim().to_string())
+            .unwrap_or_else(|| result.status.to_string());
+        return Err(tr!(FilterFailed, reason));
+    }
+    Ok(())
+}
+
+/// 原地转换打包生成的分片：先写入临时文件，成功后替换原分片。
+pub(crate) fn filter_parts_in_place(
+    ctx: &JobContext,
+    command: &str,
+    paths: &[String],
+) -> Result<(), String> {
+    let total_bytes = total_size(paths.iter().map(Path::new));
+    let mut processed = 0u64;
+    for (idx, path) in paths.iter().enumerate() {
+        let path = Path::new(path);
+        emit_progress(
+            ctx,
+            "filter",
+            processed,
+            total_bytes,
+            idx + 1,
+            paths.len(),
+            tr!(FilteringParts),
+        );
+        let size = fs::metadata(path).map(|meta| meta.len()).unwrap_or(0);
+        let temp_path = temp_sibling(path);
+        if let Err(err) = run_filter(command, path, &temp_path, idx + 1) {
+            let _ = fs::remove_file(&temp_path);
+            return Err(err);
+        }
+        fs::rename(&temp_path, path).map_err(|e| e.to_string())?;
+        processed += size;
+    }
+    Ok(())
+}
+
+/// 用逆向命令把分片还原到 `target_dir`，文件名保持不变。
+pub(crate) fn unfilter_parts(
+    ctx: &JobContext,
+    command: &str,
+    part_group: &PartGroup,
+    target_dir: &Path,
+) -> Result<(), String> {
+    fs::create_dir_all(target_dir).map_err(|e| e.to_string())?;
+    let total_bytes = total_size(part_group.parts.iter().map(|part| part.path.as_path()));
+    let mut processed = 0u64;
+    for (idx, part) in part_group.parts.iter().enumerate() {
+        emit_progress(
+            ctx,
+            "filter",
+            processed,
+            total_bytes,
+            idx + 1,
+            part_group.parts.len(),
+            tr!(UnfilteringParts),
+        );
+        let file_name = part
+            .path
+            .file_name()
+            .ok_or_else(|| tr!(CannotParsePartFileName))?;
+        let target = target_dir.join(file_name);
+        run_filter(command, &part.path, &target, part.index)?;
+        processed += fs::metadata(&part.path).map(|meta| meta.len()).unwrap_or(0);
+    }
+    Ok(())
+}
+
+fn temp_sibling(path: &Path) -> PathBuf {
+    let mut name = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".filtering");
+    path.with_file_name(name)
+}
+
+fn total_size<'a>(paths: impl Iterator<Item = &'a Path>) -> u64 {
+    paths
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum()
+}
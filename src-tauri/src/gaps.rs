@@ -0,0 +1,235 @@
+//! 缺片合并：部分分片丢失、又无法用恢复分片重建时，合并现有的分片，抢救还能用的数据。
+//! 缺失的分片按 [`GapPolicy`] 补零或从第一处缺失截断，缺失的字节范围记入合并结果的
+//! `missingRanges`。视频、音频等容忍数据缺损的文件常能照常播放；压缩包可配合尽力解压找回完好的条目。
+//!
+//! 只适用于直接拼接分片的合并方式：`raw`、`zip-then-split`、`7z-then-split` 与 tar 方式。
+//! 缺失分片的大小取自完整性清单；没有清单时按现有分片推算（除末份外各分片大小相同），
+//! 末尾缺了几份也只有清单能给出，推算出的范围标记为估计值。
+
+use serde::Serialize;
+use std::{
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::Path,
+};
+
+use crate::{
+    copy_n_buffered, emit_progress, find_part_group, i18n::tr, integrity, jobs::JobContext,
+    PartGroup, PartInfo,
+};
+
+/// 如何处理缺失的分片。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum GapPolicy {
+    /// 按缺失分片的大小补零，其后的数据仍在原来的位置。
+    Fill,
+    /// 只保留第一处缺失之前的数据。
+    Truncate,
+}
+
+impl GapPolicy {
+    /// 解析 `missingParts` 参数：`fill`、`truncate`，未设置或 `error` 时缺少分片照常报错。
+    pub(crate) fn parse(value: Option<&str>) -> Result<Option<GapPolicy>, String> {
+        match value.unwrap_or_default() {
+            "" | "error" => Ok(None),
+            "fill" => Ok(Some(GapPolicy::Fill)),
+            "truncate" => Ok(Some(GapPolicy::Truncate)),
+            other => Err(tr!(UnknownMissingPartsMode, other)),
+        }
+    }
+}
+
+/// 一份缺失分片在原文件中的字节范围。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct MissingRange {
+    /// 分片序号，从 1 开始。
+    pub(crate) part: usize,
+    pub(crate) start: u64,
+    /// 不含。
+    pub(crate) end: u64,
+    /// 大小按现有分片推算，没有清单核实。
+    pub(crate) estimated: bool,
+}
+
+/// 拼接顺序中的一个位置：现有的分片，或缺失分片推算出的大小。
+enum Slot<'a> {
+    Present(&'a PartInfo, u64),
+    Missing {
+        index: usize,
+        size: u64,
+        estimated: bool,
+    },
+}
+
+/// 找出合并用的分片组：缺片合并时允许序号不连续，否则按 `strict` 查找并检查序号。
+pub(crate) fn collect_group(
+    ctx: &JobContext,
+    input_path: &Path,
+    strict: fn(&Path) -> Result<PartGroup, String>,
+) -> Result<PartGroup, String> {
+    if ctx.gap_policy().is_none() {
+        return strict(input_path);
+    }
+    // split(1) 命名的分片按文件名逐个往后找，找不出缺口，交给 `strict`
+    find_part_group(input_path).or_else(|_| strict(input_path))
+}
+
+/// 按顺序拼接现有的分片，缺失的分片按 `policy` 补零或截断，缺失范围用 [`JobContext::record_missing`] 记录。
+pub(crate) fn merge(
+    ctx: &JobContext,
+    part_group: &PartGroup,
+    target_path: &Path,
+    policy: GapPolicy,
+) -> Result<(), String> {
+    let slots = plan(part_group)?;
+    let count = slots.len();
+    let total_bytes: u64 = slots
+        .iter()
+        .map(|slot| match slot {
+            Slot::Present(_, size) => *size,
+            Slot::Missing { size, .. } => *size,
+        })
+        .sum();
+
+    ctx.track_output(target_path);
+    let mut writer = BufWriter::new(File::create(target_path).map_err(|e| e.to_string())?);
+    let mut processed = 0u64;
+    let mut truncated_at: Option<u64> = None;
+    for (idx, slot) in slots.iter().enumerate() {
+        emit_progress(
+            ctx,
+            "merge",
+            processed,
+            total_bytes,
+            idx + 1,
+            count,
+            tr!(MergingPart, idx + 1),
+        );
+        match slot {
+            Slot::Present(part, size) => {
+                if truncated_at.is_some() {
+                    processed += size;
+                    continue;
+                }
+                let mut reader = BufReader::new(File::open(&part.path).map_err(|e| e.to_string())?);
+                let buffer_size = ctx.memory().buffer_size();
+                copy_n_buffered(
+                    &mut reader,
+                    &mut writer,
+                    *size,
+                    buffer_size,
+                    ctx.cancel_token(),
+                    |delta| {
+                        processed += delta;
+                        emit_progress(
+                            ctx,
+                            "merge",
+                            processed,
+                            total_bytes,
+                            idx + 1,
+                            count,
+                            tr!(Merging),
+                        );
+                    },
+                )
+                .map_err(|e| e.to_string())?;
+            }
+            Slot::Missing {
+                index,
+                size,
+                estimated,
+            } => {
+                ctx.check_cancelled()?;
+                ctx.record_missing(MissingRange {
+                    part: *index,
+                    start: processed,
+                    end: processed + size,
+                    estimated: *estimated,
+                });
+                log::warn!(
+                    "缺少第 {} 份分片，字节 {}..{}{}",
+                    index,
+                    processed,
+                    processed + size,
+                    if *estimated { "（推算）" } else { "" }
+                );
+                if policy == GapPolicy::Fill {
+                    io::copy(&mut io::repeat(0).take(*size), &mut writer)
+                        .map_err(|e| e.to_string())?;
+                } else if truncated_at.is_none() {
+                    truncated_at = Some(processed);
+                }
+                processed += size;
+            }
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    if let Some(offset) = truncated_at {
+        log::warn!("缺片合并：合并结果截断在第一处缺失的字节 {} 处", offset);
+    }
+    Ok(())
+}
+
+/// 排出拼接顺序：总份数取现有分片的最大序号与清单记录的份数中较大者。
+fn plan(part_group: &PartGroup) -> Result<Vec<Slot<'_>>, String> {
+    let sizes = part_group
+        .parts
+        .iter()
+        .map(|part| fs::metadata(&part.path).map(|meta| meta.len()))
+        .collect::<io::Result<Vec<u64>>>()
+        .map_err(|e| e.to_string())?;
+    // 清单按顺序记录了每份分片的大小；现有分片都在清单中相应的位置上时才采用，
+    // 以免误用同目录其他组（如追加打包的其他批次）的记录
+    let listed: Vec<u64> = match part_group.parts.first() {
+        Some(first) => match integrity::find(&first.path).ok().flatten() {
+            Some((_, manifest)) => {
+                let listed = manifest.part_sizes();
+                let matches = part_group.parts.iter().all(|part| {
+                    let name = part.path.file_name().and_then(|name| name.to_str());
+                    listed.get(part.index - 1).map(|(listed, _)| *listed) == name
+                });
+                if matches {
+                    listed.into_iter().map(|(_, size)| size).collect()
+                } else {
+                    Vec::new()
+                }
+            }
+            None => Vec::new(),
+        },
+        None => Vec::new(),
+    };
+    let last_index = part_group.parts.last().map_or(0, |part| part.index);
+    let total = last_index.max(listed.len());
+    // 没有清单时以现有非末份分片的大小为准；只剩末份时只能以它的大小估计
+    let regular = part_group
+        .parts
+        .iter()
+        .zip(&sizes)
+        .filter(|(part, _)| part.index < total)
+        .map(|(_, size)| *size)
+        .max()
+        .or_else(|| sizes.last().copied())
+        .unwrap_or(0);
+
+    let mut present = part_group.parts.iter().zip(sizes).peekable();
+    let mut slots = Vec::with_capacity(total);
+    for index in 1..=total {
+        match present.next_if(|(part, _)| part.index == index) {
+            Some((part, size)) => slots.push(Slot::Present(part, size)),
+            None => slots.push(match listed.get(index - 1) {
+                Some(size) => Slot::Missing {
+                    index,
+                    size: *size,
+                    estimated: false,
+                },
+                None => Slot::Missing {
+                    index,
+                    size: regular,
+                    estimated: true,
+                },
+            }),
+        }
+    }
+    Ok(slots)
+}
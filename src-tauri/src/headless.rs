@@ -0,0 +1,80 @@
+//! 无窗口运行（`--headless`）：不创建任何窗口，仅启动本机 HTTP 接口（可选 IPC 接口），
+//! 便于在 NAS 或服务器上由脚本驱动分割与合并。
+
+use std::{env, thread};
+
+use crate::{
+    api,
+    i18n::{self, tr, Msg},
+    ipc, DEFAULT_API_PORT,
+};
+
+/// 未通过参数指定令牌时读取的环境变量。
+const TOKEN_ENV: &str = "FILE_SPLIT_PACKER_TOKEN";
+
+struct HeadlessOptions {
+    port: u16,
+    token: Option<String>,
+    /// `Some(None)` 表示使用默认名称启动 IPC 接口。
+    ipc: Option<Option<String>>,
+}
+
+fn parse_args(args: &[String]) -> Result<Option<HeadlessOptions>, String> {
+    let mut options = HeadlessOptions {
+        port: DEFAULT_API_PORT,
+        token: None,
+        ipc: None,
+    };
+    let mut help = false;
+    let mut iter = args.iter().peekable();
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--headless" => {}
+            "--help" | "-h" => help = true,
+            "--port" => {
+                let value = iter.next().ok_or_else(|| tr!(MissingFlagValue, "--port"))?;
+                options.port = value
+                    .parse()
+                    .map_err(|_| tr!(InvalidPort, value))?;
+            }
+            "--token" => {
+                let value = iter.next().ok_or_else(|| tr!(MissingFlagValue, "--token"))?;
+                options.token = Some(value.clone());
+            }
+            "--lang" => {
+                let value = iter.next().ok_or_else(|| tr!(MissingFlagValue, "--lang"))?;
+                i18n::set_language_tag(value)?;
+            }
+            "--ipc" => {
+                let name = iter.next_if(|value| !value.starts_with("--")).cloned();
+                options.ipc = Some(name);
+            }
+            other => return Err(tr!(UnknownArgument, other)),
+        }
+    }
+    if help {
+        return Ok(None);
+    }
+    if options.token.is_none() {
+        options.token = env::var(TOKEN_ENV).ok().filter(|value| !value.is_empty());
+    }
+    Ok(Some(options))
+}
+
+/// 启动接口并一直阻塞，直到进程被终止。
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let Some(options) = parse_args(args)? else {
+        println!("{}", Msg::HeadlessUsage);
+        return Ok(());
+    };
+    let (url, token) = api::start(None, options.port, options.token)?;
+    println!("{}", tr!(ApiStarted, url));
+    println!("{}", tr!(TokenLine, token));
+    if let Some(name) = options.ipc {
+        let name = ipc::start(None, name)?;
+        println!("{}", tr!(IpcStarted, name));
+    }
+    loop {
+        thread::park();
+    }
+}
@@ -0,0 +1,114 @@
+//! 加密文件名：zip 的 AES 加密只加密条目内容，条目名与目录结构仍以明文列出。开启 `encryptNames` 时
+//! 先把输入打成不加密的内层 zip，再把它作为唯一的条目 [`WRAPPED_ENTRY`] 加密后放进外层 zip，
+//! 不知道密码时只能看到这一个条目名。
+//!
+//! 合并后自动解压时识别外层 zip，先解密出内层 zip 再照常解压。
+
+use std::{
+    fs::File,
+    io::{BufReader, BufWriter, Write},
+    path::Path,
+};
+
+use zip::{CompressionMethod, ZipArchive, ZipWriter};
+
+use crate::{
+    build_file_options, copy_n_buffered, emit_progress, extract, i18n::tr, jobs::JobContext,
+    open_zip_file, remove_temp,
+};
+
+/// 外层 zip 中唯一条目的名称，只用于识别，不透露任何信息。
+pub(crate) const WRAPPED_ENTRY: &str = ".file-split-packer-names.zip";
+
+/// 把内层 zip 加密后作为唯一条目写入 `zip_path`。内层已经压缩过，外层仅存储。
+pub(crate) fn wrap(
+    ctx: &JobContext,
+    inner_path: &Path,
+    zip_path: &Path,
+    password: &str,
+) -> Result<(), String> {
+    let inner = File::open(inner_path).map_err(|e| e.to_string())?;
+    let size = inner.metadata().map_err(|e| e.to_string())?.len();
+    let mut reader = BufReader::new(inner);
+    let mut zip = ZipWriter::new(BufWriter::new(
+        File::create(zip_path).map_err(|e| e.to_string())?,
+    ));
+    zip.start_file(
+        WRAPPED_ENTRY,
+        build_file_options(
+            Some(password),
+            ctx.aes_mode(),
+            CompressionMethod::Stored,
+            None,
+            size,
+            None,
+        ),
+    )
+    .map_err(|e| e.to_string())?;
+    let mut processed = 0u64;
+    emit_progress(ctx, "zip", 0, size, 0, 0, tr!(EncryptingNames));
+    copy_n_buffered(
+        &mut reader,
+        &mut zip,
+        size,
+        ctx.memory().buffer_size(),
+        ctx.cancel_token(),
+        |delta| {
+            processed += delta;
+            emit_progress(ctx, "zip", processed, size, 0, 0, tr!(EncryptingNames));
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    zip.finish()
+        .map_err(|e| e.to_string())?
+        .flush()
+        .map_err(|e| e.to_string())
+}
+
+/// `zip_path` 是否是加密了文件名的外层 zip：只有一个条目，且名为 [`WRAPPED_ENTRY`]。
+pub(crate) fn is_wrapped(zip_path: &Path) -> bool {
+    let Ok(file) = File::open(zip_path) else {
+        return false;
+    };
+    let Ok(archive) = ZipArchive::new(BufReader::new(file)) else {
+        return false;
+    };
+    archive.len() == 1 && archive.name_for_index(0) == Some(WRAPPED_ENTRY)
+}
+
+/// 解密出内层 zip 并解压到 `output_dir`，内层 zip 用完即删。
+pub(crate) fn extract(
+    ctx: &JobContext,
+    zip_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<(), String> {
+    let file = File::open(zip_path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let mut entry = open_zip_file(&mut archive, 0, password)?;
+    let size = entry.size();
+    let inner_path = zip_path.with_extension("names.tmp");
+    ctx.track_output(&inner_path);
+    // 解密出的内层 zip 含明文文件名，写出或解压失败时同样覆写删除
+    let decrypted = (|| {
+        let mut writer = BufWriter::new(File::create(&inner_path).map_err(|e| e.to_string())?);
+        let mut processed = 0u64;
+        copy_n_buffered(
+            &mut entry,
+            &mut writer,
+            size,
+            ctx.memory().buffer_size(),
+            ctx.cancel_token(),
+            |delta| {
+                processed += delta;
+                emit_progress(ctx, "unzip", processed, size, 0, 0, tr!(DecryptingNames));
+            },
+        )
+        .map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    })();
+    let result =
+        decrypted.and_then(|()| extract::extract_archive(ctx, &inner_path, output_dir, None, None));
+    remove_temp(ctx, &inner_path, true);
+    result
+}
@@ -0,0 +1,791 @@
+//! 后端面向用户的文本（进度消息、错误信息、帮助信息）集中在此维护，
+//! 按当前语言输出，界面、HTTP/IPC 接口与命令行看到的文本保持一致。
+//!
+//! 默认语言为简体中文，可通过 `set_language` 命令、命令行 `--lang` 参数或环境变量
+//! `FILE_SPLIT_PACKER_LANG` 切换。新增语言时在 [`Lang`] 中加一项，并为每条文本补充译文。
+
+use std::{
+    env, fmt,
+    sync::atomic::{AtomicU8, Ordering},
+};
+
+/// 启动时读取的默认语言。
+const LANG_ENV: &str = "FILE_SPLIT_PACKER_LANG";
+
+/// 0 表示尚未初始化，其余为 `Lang as u8 + 1`。
+static CURRENT: AtomicU8 = AtomicU8::new(0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Lang {
+    ZhCn,
+    En,
+}
+
+impl Lang {
+    const ALL: [Lang; 2] = [Lang::ZhCn, Lang::En];
+
+    /// 解析语言标签，忽略大小写与地区后缀（如 `en-US`、`zh_CN.UTF-8`）。
+    pub(crate) fn parse(tag: &str) -> Option<Lang> {
+        let tag = tag.trim().to_ascii_lowercase();
+        let primary = tag.split(['-', '_', '.']).next().unwrap_or_default();
+        match primary {
+            "zh" => Some(Lang::ZhCn),
+            "en" => Some(Lang::En),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn code(self) -> &'static str {
+        match self {
+            Lang::ZhCn => "zh-CN",
+            Lang::En => "en",
+        }
+    }
+}
+
+pub(crate) fn current() -> Lang {
+    match CURRENT.load(Ordering::Relaxed) {
+        0 => {
+            let lang = env::var(LANG_ENV)
+                .ok()
+                .and_then(|value| Lang::parse(&value))
+                .unwrap_or(Lang::ZhCn);
+            // 已被显式设置时保留设置值
+            let _ = CURRENT.compare_exchange(0, lang as u8 + 1, Ordering::Relaxed, Ordering::Relaxed);
+            Lang::ALL[CURRENT.load(Ordering::Relaxed) as usize - 1]
+        }
+        value => Lang::ALL[value as usize - 1],
+    }
+}
+
+pub(crate) fn set_language(lang: Lang) {
+    CURRENT.store(lang as u8 + 1, Ordering::Relaxed);
+}
+
+/// 按标签切换语言，不支持的语言返回错误。
+pub(crate) fn set_language_tag(tag: &str) -> Result<Lang, String> {
+    let lang = Lang::parse(tag).ok_or_else(|| fill(Msg::InvalidLanguage.text(), &[&tag]))?;
+    set_language(lang);
+    Ok(lang)
+}
+
+/// 依次用参数替换文本中的 `{}`。
+pub(crate) fn fill(template: &str, args: &[&dyn fmt::Display]) -> String {
+    let mut out = String::with_capacity(template.len());
+    let mut args = args.iter();
+    let mut rest = template;
+    while let Some(pos) = rest.find("{}") {
+        out.push_str(&rest[..pos]);
+        match args.next() {
+            Some(arg) => out.push_str(&arg.to_string()),
+            None => out.push_str("{}"),
+        }
+        rest = &rest[pos + 2..];
+    }
+    out.push_str(rest);
+    out
+}
+
+/// 取当前语言的文本：`tr!(Key)`，带参数时 `tr!(Key, a, b)` 依次填入 `{}`。
+macro_rules! tr {
+    ($key:ident) => {
+        $crate::i18n::Msg::$key.text().to_string()
+    };
+    ($key:ident, $($arg:expr),+ $(,)?) => {
+        $crate::i18n::fill(
+            $crate::i18n::Msg::$key.text(),
+            &[$(&$arg as &dyn ::std::fmt::Display),+],
+        )
+    };
+}
+pub(crate) use tr;
+
+macro_rules! messages {
+    ($($key:ident => $zh:expr, $en:expr;)*) => {
+        // 部分文本只在启用对应特性（如 `mount`）时使用
+        #[allow(dead_code)]
+        #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+        pub(crate) enum Msg {
+            $($key,)*
+        }
+
+        impl Msg {
+            pub(crate) fn text(self) -> &'static str {
+                self.text_in(current())
+            }
+
+            pub(crate) fn text_in(self, lang: Lang) -> &'static str {
+                match self {
+                    $(Msg::$key => match lang {
+                        Lang::ZhCn => $zh,
+                        Lang::En => $en,
+                    },)*
+                }
+            }
+        }
+    };
+}
+
+messages! {
+    // 通用
+    InputPartsNotFound => "输入分片不存在", "Input parts do not exist";
+    InputFileNotFound => "输入文件不存在", "Input file does not exist";
+    CannotParseFileName => "无法解析文件名", "Cannot determine the file name";
+    CannotParseDirName => "无法解析目录名", "Cannot determine the directory name";
+    CannotParsePartFileName => "无法解析分片文件名", "Cannot determine the part file name";
+    CannotParsePartsDir => "无法解析分片目录", "Cannot determine the parts directory";
+    UnrecognizedPartFileName => "无法识别分片文件名", "Unrecognized part file name";
+    UnknownPackMode => "未知的打包方式", "Unknown pack mode";
+    UnknownMergeMode => "未知的合并方式", "Unknown merge mode";
+    UnknownSplitBy => "未知的切分方式", "Unknown split method";
+    UnknownArgument => "未知参数：{}", "Unknown argument: {}";
+    JobNotFound => "任务不存在", "Job not found";
+    JobCancelled => "任务已取消", "Job cancelled";
+    UnknownJobKind => "未知的任务类型", "Unknown job kind";
+    InvalidJobOptions => "任务参数格式错误：{}", "Invalid job options: {}";
+    QueueItemNotFound => "队列中没有该任务", "Queued job not found";
+    QueueItemRunning => "任务正在运行，请使用取消任务", "Job is running, cancel it instead";
+    MaxAttemptsZero => "最多尝试次数必须大于 0", "Max attempts must be greater than 0";
+    UnknownError => "未知错误", "unknown error";
+    // 进度
+    Done => "完成", "Done";
+    DoneWithParts => "完成，共 {} 份", "Done, {} parts in total";
+    PreparePart => "准备写入第 {} 份", "Preparing part {}";
+    Writing => "写入中", "Writing";
+    ParallelCompressing => "并行压缩中", "Compressing in parallel";
+    CompressStarted => "开始压缩", "Starting compression";
+    Compressing => "压缩中", "Compressing";
+    CompressingToParts => "压缩并写入分片中", "Compressing and writing parts";
+    TrainingDictionary => "训练 zstd 字典中", "Training the zstd dictionary";
+    EncryptingNames => "加密文件名中", "Encrypting file names";
+    DecryptingNames => "解密文件名中", "Decrypting file names";
+    EncryptingPart => "加密第 {} 份", "Encrypting part {}";
+    DecryptingPart => "解密第 {} 份", "Decrypting part {}";
+    EncryptingContainer => "加密为容器中", "Encrypting into the container";
+    DecryptingContainer => "解密容器中", "Decrypting the container";
+    GpgEncryptingPart => "用 GPG 加密第 {} 份", "Encrypting part {} with GPG";
+    GpgDecryptingPart => "用 GPG 解密第 {} 份", "Decrypting part {} with GPG";
+    PackingDir => "打包目录中", "Packing directory";
+    MergingPart => "合并第 {} 份", "Merging part {}";
+    Merging => "合并中", "Merging";
+    ExtractMerging => "解压合并中", "Extracting and merging";
+    Extracting => "解压中", "Extracting";
+    TestingArchive => "测试中", "Testing";
+    ReadingPart => "读取第 {} 份", "Reading part {}";
+    Restoring => "恢复中", "Restoring";
+    Reading => "读取中", "Reading";
+    FilteringParts => "过滤分片中", "Filtering parts";
+    UnfilteringParts => "还原过滤分片中", "Reversing part filter";
+    PluginRunning => "插件 {} 运行中", "Plugin {} running";
+    RunningPlugin => "正在运行插件 {}", "Running plugin {}";
+    HashingPart => "计算第 {} 份的校验值", "Hashing part {}";
+    HashingPayload => "计算整体校验值", "Hashing packed content";
+    VerifyingPart => "校验第 {} 份", "Verifying part {}";
+    VerifyingSplit => "核对分片还原出的内容", "Verifying the content restored from the parts";
+    VerifyingMerged => "校验合并结果", "Verifying merged file";
+    HashingEntries => "计算压缩包内文件的校验值", "Hashing archive entries";
+    VerifyingExtracted => "校验解压出的文件", "Verifying extracted files";
+    ParityHashing => "计算第 {} 份的 PAR2 校验信息", "Computing PAR2 checksums for part {}";
+    ParityEncoding => "生成 PAR2 恢复数据", "Generating PAR2 recovery data";
+    RecoveryEncoding => "生成恢复分片", "Generating recovery parts";
+    RepairingParts => "重建缺失或损坏的分片", "Rebuilding missing or damaged parts";
+    ReencryptingPart => "重新加密第 {} 份分片", "Re-encrypting part {}";
+    RestoringGroup => "合并第 {}/{} 组：{}", "Restoring group {}/{}: {}";
+    ParityVerifying => "核对第 {} 份的数据块", "Checking slices of part {}";
+    // 分割
+    StreamRequiresZstd => "管道或套接字输入只支持 zstd-then-split 打包方式", "Pipe or socket input only supports the zstd-then-split pack mode";
+    MaxSizeRequiresStore => "按每份最大时，请选择“先压缩 → 切分 → Store 打包”", "When splitting by maximum part size, choose \"compress → split → store\"";
+    EmptyInputFile => "输入文件大小为 0，无法切分", "Input file is empty and cannot be split";
+    EmptyInput => "输入为空，无法切分", "Input is empty and cannot be split";
+    ZstdNoPassword => "zstd 打包方式不支持密码", "The zstd pack mode does not support passwords";
+    TarNoPassword => "tar 打包方式不支持密码", "The tar pack modes do not support passwords";
+    RawSplitNoPassword => "直接分割（raw-split）不加密，不支持密码", "raw-split does not encrypt and does not support passwords";
+    RawFirstPartRequired => "无法识别分片文件名；split 生成的分片请选择第一份（如 xaa 或 x00）", "Unrecognized part file name; for parts made by split, choose the first one (such as xaa or x00)";
+    SpannedZipNeedsSize => "标准分卷 zip 只能按大小分卷", "spanned-zip can only split by size";
+    SpannedZipSegmentSize => "标准分卷 zip 的每卷大小须在 64 KiB 与 4 GiB 之间", "spanned-zip volume size must be between 64 KiB and 4 GiB";
+    SpannedZipNoFilter => "标准分卷 zip 不能使用过滤命令，否则其他软件无法打开", "spanned-zip cannot use a filter command, other programs could not open the volumes";
+    SpannedZipTooLarge => "分卷 zip 的卷数或偏移超出格式上限，请增大每卷大小", "Too many volumes or offsets too large for a spanned zip; use a larger volume size";
+    SpannedZipInvalid => "分卷 zip 已损坏：{}", "Spanned zip is corrupt: {}";
+    SpannedZipNotFound => "未找到分卷 zip，请选择 .zip 或 .z01 卷，或其所在目录", "No spanned zip found; choose the .zip or .z01 volume or its directory";
+    SpannedZipMultiple => "目录中有多组分卷 zip，请直接选择其中一卷", "Multiple spanned zips found in the directory; choose one of the volumes";
+    RarVolumesNotFound => "未找到分卷 RAR，请选择其中一卷或其所在目录", "No RAR volumes found; choose one of the volumes or its directory";
+    RarVolumesMultiple => "目录中有多组分卷 RAR，请直接选择其中一卷", "Multiple RAR volume sets found in the directory; choose one of the volumes";
+    RarVolumeMissing => "缺少 RAR 分卷，请确认各卷都在同一目录中", "A RAR volume is missing; make sure all volumes are in the same directory";
+    RarInvalid => "RAR 已损坏：{}", "RAR archive is corrupt: {}";
+    RarUnavailable => "当前版本未启用 RAR 解压（需以 rar 特性构建）", "RAR extraction is not enabled in this build (requires building with the rar feature)";
+    RawSplitNeedsFile => "直接分割（raw-split）只能分割单个文件，目录请选择其他打包方式", "raw-split only splits a single file; choose another pack mode for directories";
+    TarHeaderCorrupt => "tar 文件头已损坏", "The tar header is corrupted";
+    MissingPartSize => "缺少每份大小参数", "Missing part size";
+    PartSizeNotPositive => "每份大小必须大于 0", "Part size must be greater than 0";
+    MissingPartCount => "缺少份数参数", "Missing part count";
+    PartCountNotPositive => "份数必须大于 0", "Part count must be greater than 0";
+    PartSizeTooSmall => "每份大小过小，至少需要 {} 字节", "Part size is too small, at least {} bytes are required";
+    MaxMemoryTooSmall => "内存上限过小，至少需要 {} 字节", "Memory limit is too small, at least {} bytes are required";
+    OutputPathNotDir => "分片输出路径已存在且不是目录", "Parts output path exists and is not a directory";
+    OutputDirExists => "分片输出目录已存在，请确认是否覆盖", "Parts output directory already exists, please confirm overwriting";
+    OutputListLocked => "输出列表被锁定", "Output list is locked";
+    OutputListUnavailable => "输出列表无法回收", "Output list cannot be reclaimed";
+    HashFailed => "计算校验值失败: {} ({})", "Failed to compute checksum: {} ({})";
+    UnknownHashAlgorithm => "未知的校验算法：{}", "Unknown hash algorithm: {}";
+    ParityFailed => "生成 PAR2 恢复文件失败: {} ({})", "Failed to create PAR2 recovery files: {} ({})";
+    ParityTooManyParts => "分片有 {} 份，超过 PAR2 支持的 {} 份上限", "There are {} parts, more than the {} supported by PAR2";
+    Par2RedundancyTooHigh => "PAR2 冗余比例不能超过 {}%", "PAR2 redundancy cannot exceed {}%";
+    Par2ReadFailed => "读取 PAR2 恢复文件失败: {} ({})", "Failed to read PAR2 recovery files: {} ({})";
+    RepairWriteFailed => "写入修复的分片失败: {} ({})", "Failed to write the repaired part: {} ({})";
+    RecoveryFailed => "读写恢复分片失败: {} ({})", "Failed to read or write recovery parts: {} ({})";
+    RecoveryTooManyParts => "分片有 {} 份，加上恢复分片后超过上限，最多 {} 份", "There are {} parts; with the recovery parts this exceeds the limit of {} parts";
+    RecoveryPartsTooMany => "恢复分片不能超过 {} 份", "Recovery parts cannot exceed {}";
+    XorParityWithRecoveryParts => "异或校验分片与恢复分片只能选一种", "Choose either the XOR parity part or recovery parts, not both";
+    UnknownCompressionMethod => "未知的压缩方法：{}（可选 deflate、bzip2、xz）", "Unknown compression method: {} (choose deflate, bzip2 or xz)";
+    UnknownAesStrength => "未知的 AES 强度：{}（可选 128、192、256）", "Unknown AES strength: {} (choose 128, 192 or 256)";
+    AesStrengthNeedsZip => "只有“先分割后压缩”、“先压缩然后分割”与“标准分卷 zip”可以选择 AES 强度，7z 固定为 AES-256", "Only split-then-zip, zip-then-split and spanned-zip can choose the AES strength; 7z always uses AES-256";
+    EncryptNamesNeedsPassword => "加密文件名需要设置密码", "Encrypting file names requires a password";
+    EncryptNamesNeedsArchive => "只有“先压缩然后分割”与“7z 然后分割”可以加密文件名", "Only zip-then-split and 7z-then-split can encrypt file names";
+    CompressionMethodNeedsZip => "只有“先分割后压缩”、“先压缩然后分割”与“标准分卷 zip”可以选择 zip 压缩方法", "Only split-then-zip, zip-then-split and spanned-zip can choose a zip compression method";
+    NumberedVolumesNeedsByteSplit => "“先分割后压缩”、“age 加密分割”与“标准分卷 zip”不能使用 .001 分卷命名", "split-then-zip, age-split and spanned-zip cannot use .001 volume naming";
+    SelfExtractingNoFilter => "自解压程序无法还原过滤命令处理过的分片，不能与过滤命令同时使用", "The self-extracting restorer cannot undo a filter command, so the two cannot be combined";
+    RejoinScriptsNeedsByteSplit => "“先分割后压缩”、“age 加密分割”与“标准分卷 zip”的分片不能直接拼接，不能生成合并脚本", "split-then-zip, age-split and spanned-zip parts cannot be joined by concatenation, so no rejoin scripts can be written";
+    RejoinScriptsNoFilter => "合并脚本无法还原过滤命令处理过的分片，不能与过滤命令同时使用", "Rejoin scripts cannot undo a filter command, so the two cannot be combined";
+    ZstdDictionaryNeedsZstd => "只有“zstd 然后分割”可以使用 zstd 字典", "Only zstd-then-split can use a zstd dictionary";
+    SevenZipVolumesNeeds7z => "只有“7z 然后分割”能按 7-Zip 分卷命名分片", "Only 7z-then-split can name parts as 7-Zip volumes";
+    PerPartKeysNeedsContainer => "只有“加密容器然后分割”能逐份独立加密分片", "Only container-then-split can encrypt each part independently";
+    SigningNeedsManifest => "只有“先分割后压缩”、“先压缩然后分割”、“直接分割”、“age 加密分割”、“加密容器然后分割”、“标准分卷 zip”与“zstd 然后分割”会写清单，才能签名", "Only split-then-zip, zip-then-split, raw-split, age-split, container-then-split, spanned-zip and zstd-then-split write a manifest that can be signed";
+    WipingSource => "覆写并删除源文件中", "Overwriting and deleting the source";
+    WipeFailed => "覆写删除失败：{}（{}）", "Failed to overwrite and delete: {} ({})";
+    WipingTemp => "覆写并删除中间文件中", "Overwriting and deleting temporary files";
+    DestroySourceNeedsPath => "管道与套接字输入没有可销毁的源文件", "Pipe and socket inputs have no source file to destroy";
+    DestroySourceContainsOutput => "输出目录位于要销毁的源目录中，分片会被一起删除", "The output directory is inside the source directory to be destroyed, so the parts would be deleted too";
+    DestroySourceFiltered => "脚本定义了 filter 钩子，源目录中未打包的文件也会被销毁", "The script defines a filter hook, so files it leaves out of the pack would be destroyed too";
+    DestroySourceFilterCommand => "filterCommand 改写后的分片本机无法核对，不能销毁源文件", "Parts rewritten by filterCommand cannot be verified here, so the source cannot be destroyed";
+    DestroySourceNeedsVerify => "加密给接收方公钥的分片本机无法核对，不能销毁源文件", "Parts encrypted to recipient public keys cannot be verified here, so the source cannot be destroyed";
+    KeyfileReadFailed => "读取密钥文件失败：{}（{}）", "Failed to read the keyfile: {} ({})";
+    KeyfileNotFound => "密钥文件不存在：{}", "Keyfile not found: {}";
+    KeyfileEmpty => "密钥文件是空的：{}", "The keyfile is empty: {}";
+    JobSecretIdEmpty => "登记任务密码需要 jobId", "Registering a job secret requires a jobId";
+    JobSecretNotFound => "找不到任务 {} 登记的密码，可能已被使用或已过期，请重新登记", "No secret is registered for job {}; it may have been used or expired, please register it again";
+    AgeSplitNeedsFile => "age 加密分割只能分割单个文件，目录请先打包或选择其他打包方式", "age-split only splits a single file; pack directories first or choose another pack mode";
+    AgeSplitNeedsKey => "age 加密需要口令（密码或密钥文件）或接收方公钥", "age encryption requires a passphrase (password or keyfile) or recipient public keys";
+    AgeSplitKeyConflict => "age 不能同时用口令与接收方公钥加密，请只选一种", "age cannot encrypt to a passphrase and recipient public keys at once; choose one";
+    AgeRecipientInvalid => "不是有效的 age 公钥或接收方文件：{}", "Not a valid age public key or recipients file: {}";
+    AgeRecipientsNeedAgeSplit => "只有“age 加密分割”使用接收方公钥", "Only age-split uses recipient public keys";
+    AgeVerifyNeedsPassphrase => "加密给接收方公钥的分片本机无法解密，不能在分割后核对", "Parts encrypted to recipient public keys cannot be decrypted here, so they cannot be verified after splitting";
+    AgeIdentityReadFailed => "读取 age 身份文件失败：{}（{}）", "Failed to read the age identity file: {} ({})";
+    AgeNoMatchingKey => "口令或身份文件无法解密分片：{}", "The passphrase or identity files cannot decrypt the part: {}";
+    AgeDecryptFailed => "解密 age 分片失败：{}（{}）", "Failed to decrypt the age part: {} ({})";
+    ContainerNeedsPassword => "加密容器需要密码或密钥文件", "The encrypted container requires a password or keyfile";
+    ContainerInvalid => "不是本程序的加密容器，或文件头已损坏", "Not an encrypted container of this program, or its header is damaged";
+    ContainerUnsupported => "不支持的加密容器版本或参数（版本 {}）", "Unsupported encrypted container version or parameters (version {})";
+    ContainerKdfFailed => "派生密钥失败：{}", "Failed to derive the key: {}";
+    ContainerCorrupt => "加密容器的第 {} 块未通过认证，分片被截断、篡改或损坏", "Chunk {} of the encrypted container failed authentication; the parts are truncated, tampered with or damaged";
+    ContainerPartFailed => "分片 {} 解密失败：{}", "Failed to decrypt part {}: {}";
+    ContainerPartMismatch => "分片 {} 的文件头记录的是第 {} 份（共 {} 份），分片被改名、缺失或混入了其他分片组", "The header of part {} says it is part {} of {}; parts were renamed, are missing or come from another set";
+    ReencryptNeedsMode => "没有完整性清单，请指定分片的打包方式", "No integrity manifest found; specify the pack mode of the parts";
+    ReencryptUnsupportedMode => "{} 的分片不能逐份更换密码，只支持 split-then-zip、age-split 与逐份独立加密的 container-then-split；其他方式请合并后重新打包", "The password of {} parts cannot be changed part by part; only split-then-zip, age-split and independently encrypted container-then-split are supported; merge and pack again otherwise";
+    ReencryptNeedsNewPassword => "age 加密分割与加密容器的分片不能去掉加密，请设置新密码或密钥文件", "age-split and container parts cannot have their encryption removed; set a new password or keyfile";
+    DecryptUnsupportedMode => "{} 的分片不能逐份去掉加密，只支持 split-then-zip、age-split 与 container-then-split；其他方式请合并后重新打包", "The encryption of {} parts cannot be removed part by part; only split-then-zip, age-split and container-then-split are supported; merge and pack again otherwise";
+    ReencryptNeedsPerPartKeys => "整个容器切分的分片不能逐份更换密码，只支持逐份独立加密（perPartKeys）的分片", "Parts cut from a whole container cannot be re-encrypted part by part; only independently encrypted parts (perPartKeys) can";
+    ContainerPartTooSmall => "独立加密的分片大小至少为 {} 字节", "Independently encrypted parts must be at least {} bytes";
+    PasswordEmpty => "未设置密码", "No password is set";
+    PasswordCommon => "这是常见密码或其简单变形，字典攻击会最先尝试", "This is a common password or a simple variation of one; dictionary attacks try these first";
+    PasswordRepeats => "重复的字符（如 aaa）几乎不增加强度", "Repeated characters such as aaa add almost no strength";
+    PasswordSequence => "连续的字母、数字或相邻按键（如 abc、123、qwerty）很容易猜到", "Sequences of letters, digits or adjacent keys such as abc, 123 or qwerty are easy to guess";
+    PasswordTooShort => "密码少于 12 个字符，加长比加复杂更有效", "The password is shorter than 12 characters; length helps more than complexity";
+    PasswordSingleClass => "只用了一类字符，可混用大小写字母、数字与符号", "Only one kind of character is used; mix upper and lower case letters, digits and symbols";
+    GpgStartFailed => "无法运行 gpg，请确认已安装 GnuPG：{}", "Cannot run gpg; make sure GnuPG is installed: {}";
+    GpgFailed => "gpg 执行失败：{}", "gpg failed: {}";
+    GpgRecipientNotFound => "本机密钥环中找不到 GPG 公钥：{}", "GPG public key not found in the local keyring: {}";
+    SpannedZipNoGpg => "标准分卷 zip 不能加密给 GPG 公钥", "spanned-zip parts cannot be encrypted to GPG keys";
+    RejoinScriptsNoGpg => "合并脚本无法解密加密给 GPG 公钥的分片，不能同时使用", "Rejoin scripts cannot decrypt parts encrypted to GPG keys, so the two cannot be combined";
+    SigningKeyReadFailed => "读取密钥文件失败: {} ({})", "Failed to read the key file: {} ({})";
+    SigningKeyInvalid => "不是有效的 Ed25519 私钥：{}", "Not a valid Ed25519 private key: {}";
+    SigningKeyExists => "密钥文件已存在：{}", "Key file already exists: {}";
+    SigningKeyWriteFailed => "写入密钥或签名文件失败: {} ({})", "Failed to write the key or signature file: {} ({})";
+    UnexpectedEof => "读取到意外的文件结束", "Unexpected end of file";
+    // 合并
+    NoPartsFound => "未找到分片文件", "No part files found";
+    InputNotFileOrDir => "输入路径不是文件或目录", "Input path is not a file or directory";
+    MultiplePartGroups => "检测到多组分片，请选择具体的分片文件，或批量合并全部分片组", "Multiple part groups found, please choose a specific part file or restore all groups in a batch";
+    BatchNeedsDir => "批量合并需要选择分片所在的目录", "Batch restore needs the directory that holds the parts";
+    BatchGroupFailed => "{} 合并失败：{}", "Failed to restore {}: {}";
+    BatchRestoreFailed => "{} 组分片合并失败（共 {} 组）", "{} of {} part groups failed to restore";
+    MissingPart => "分片序号不连续，缺少第 {} 份", "Part numbers are not contiguous, part {} is missing";
+    PartCommentIndexMismatch => "{} 的注释记录它是第 {} 份分片，与文件名不符，分片可能被改过名", "{} records itself as part {} in its comment, which does not match the file name; the part may have been renamed";
+    IntegrityPartMissing => "清单中的分片不存在：{}", "Part listed in the manifest is missing: {}";
+    IntegrityPartMismatch => "分片与清单中的校验值不一致，可能已损坏：{}", "Part does not match the checksum in the manifest and may be corrupted: {}";
+    RecoveryNotEnough => "有 {} 份分片缺失或损坏，只有 {} 份完好的恢复分片，无法重建", "{} parts are missing or damaged but only {} intact recovery parts are available";
+    RecoveryRebuildMismatch => "重建的分片与记录的校验值不一致：{}", "Rebuilt part does not match the recorded checksum: {}";
+    MultipleRecoverySets => "分片目录中有多组恢复分片记录，请选择具体的分片文件", "The parts directory contains more than one recovery set, please choose a specific part file";
+    ParityNotEnough => "有 {} 个数据块缺失或损坏，只有 {} 个完好的恢复块，无法修复", "{} slices are missing or damaged but only {} intact recovery slices are available";
+    Par2SetIncomplete => "PAR2 恢复文件中缺少 {} 的校验信息", "The PAR2 recovery files lack the checksums for {}";
+    NoRecoveryData => "{} 中没有恢复分片、校验分片或 PAR2 恢复文件", "{} contains no recovery parts, parity part or PAR2 recovery files";
+    TrustedKeyInvalid => "无法识别信任的公钥：{}", "Unrecognized trusted key: {}";
+    SignatureRequired => "分片清单没有签名", "The parts manifest is not signed";
+    SignatureMissing => "清单记录了签名者，但签名文件缺失：{}", "The manifest names a signer but its signature file is missing: {}";
+    SignatureInvalid => "清单签名无效，清单可能被改动：{}", "Invalid manifest signature, the manifest may have been tampered with: {}";
+    TestBlockBroken => "同一数据块中前面的数据已损坏，无法读取", "Earlier data in the same solid block is damaged, this file cannot be read";
+    TestUnsupportedArchive => "无法识别的压缩包：{}（只支持 zip、7z、tar.gz 与 tar.zst）", "Unrecognized archive: {} (only zip, 7z, tar.gz and tar.zst are supported)";
+    TestTarStream => "（tar 数据流）", "(tar stream)";
+    PartByteRange => "{} 第 {}–{} 字节", "{} bytes {}-{}";
+    EntryCorrupt => "{} 已损坏：{}", "{} is corrupted: {}";
+    EntryCorruptAt => "{} 已损坏（位于 {}）：{}", "{} is corrupted (in {}): {}";
+    SplitVerifyMismatch => "分割后核对失败：分片还原出的内容与源不一致，请检查输出磁盘", "Post-split verification failed: the content restored from the parts does not match the source, please check the output drive";
+    SplitVerifyFailed => "分割后核对失败：{}", "Post-split verification failed: {}";
+    SalvageLostEntries => "尽力解压跳过了 {} 个无法恢复的条目：", "Salvage skipped {} unrecoverable entries:";
+    UnknownMissingPartsMode => "未知的缺失分片处理方式：{}（可选 fill、truncate）", "Unknown missing parts handling: {} (use fill or truncate)";
+    MissingPartsNeedsRestore => "处理缺失分片需要合并到目录，且不支持 zstd-then-split", "Handling missing parts needs an output directory and is not supported for zstd-then-split";
+    MissingRanges => "缺少 {} 份分片，以下字节范围的数据缺失：", "{} parts are missing; data in these byte ranges is lost:";
+    MissingRangeLine => "第 {} 份：字节 {}..{}", "part {}: bytes {}..{}";
+    MissingRangeEstimated => "（大小为推算）", " (estimated size)";
+    UnknownConflictPolicy => "未知的同名文件处理方式：{}（可选 overwrite、skip、rename、ask）", "Unknown conflict policy: {} (use overwrite, skip, rename or ask)";
+    UnknownFilenameEncoding => "未知的文件名编码：{}（可选 auto、gbk、shift_jis、big5、euc-kr 等）", "Unknown filename encoding: {} (use auto, gbk, shift_jis, big5, euc-kr, ...)";
+    ConflictSkipped => "已有同名文件，已跳过：{}", "Skipped existing file: {}";
+    ConflictRenamed => "已有同名文件 {}，解压为 {}", "{} already exists, extracted as {}";
+    PromptNeedsUi => "只有在界面中运行的任务才能询问，请改用其他处理方式", "Only jobs running in the app can ask questions; choose another option";
+    PromptNotFound => "找不到等待回答的提问：{}", "No pending question: {}";
+    InvalidPromptAnswer => "无法解析提问的回答：{}", "Cannot parse the answer: {}";
+    DownloadUrlUnsupported => "只支持 HTTP 或 HTTPS 网址：{}", "Only HTTP and HTTPS URLs are supported: {}";
+    DownloadUrlNoFileName => "无法从网址得出文件名：{}", "Cannot tell the file name from the URL: {}";
+    DownloadManifestNoParts => "清单中没有列出分片：{}", "The manifest does not list any parts: {}";
+    DownloadingPart => "下载第 {}/{} 份：{}", "Downloading {}/{}: {}";
+    DownloadFailed => "下载 {} 失败：{}", "Failed to download {}: {}";
+    DownloadTruncated => "连接在 {} 字节处中断（共 {} 字节）", "Connection dropped at byte {} of {}";
+    DownloadRangeIgnored => "服务器没有从请求的位置续传", "The server did not resume from the requested offset";
+    DownloadSizeMismatch => "下载的 {} 大小与清单不符：应为 {} 字节，实为 {} 字节", "Downloaded {} does not match the manifest: expected {} bytes, got {}";
+    UrlsNeedRestore => "从网址合并需要输出到目录，且不支持 zstd-then-split", "Merging from URLs needs an output directory and is not supported for zstd-then-split";
+    DetectedArchiveType => "合并结果格式：{}", "Merged payload format: {}";
+    TestFailedSummary => "共测试 {} 个文件，{} 个出错", "{} files tested, {} failed";
+    SignatureUntrustedWarning => "警告：清单签名有效，但签名者不在信任的公钥中（指纹 {}）", "Warning: the manifest signature is valid but the signer is not among the trusted keys (fingerprint {})";
+    SignatureUntrusted => "清单的签名者不在信任的公钥中（指纹 {}）", "The manifest was signed by an untrusted key (fingerprint {})";
+    RestoreVerifyFailed => "合并结果与清单中的校验值不一致：{}", "Restored files do not match the checksums in the manifest: {}";
+    WrongPassword => "解密失败，请确认密码", "Decryption failed, please check the password";
+    PasswordRequired => "需要密码才能解包", "A password is required to unpack";
+    PartArchiveEmpty => "分片压缩包为空", "Part archive is empty";
+    PartArchiveMultipleFiles => "分片压缩包内包含多个文件", "Part archive contains more than one file";
+    PartArchiveInvalid => "分片压缩包内容异常", "Part archive content is invalid";
+    PartLengthMismatch => "分片长度与预期不一致", "Part length does not match the expected size";
+    InvalidSeekPosition => "无效的读取位置", "Invalid read position";
+    // 查看与挂载
+    MountUnavailable => "当前版本未启用挂载功能", "Mounting is not enabled in this build";
+    MountUnavailableHint => "当前版本未启用挂载功能（需在 Linux/macOS 上以 mount 特性构建）", "Mounting is not enabled in this build (requires building with the mount feature on Linux/macOS)";
+    CannotListPacked => "无法读取分片内的文件列表（分片经过压缩或加密），请先合并", "Cannot list files inside the parts (they are compressed or encrypted), please merge first";
+    MountStateError => "挂载状态异常", "Mount state is corrupted";
+    AlreadyMounted => "该目录已挂载", "This directory is already mounted";
+    NotMounted => "该目录未挂载", "This directory is not mounted";
+    AutoModeNeedsManifest => "分片经过 GPG 加密或过滤命令，需要完整性清单才能自动识别合并方式，请手动指定", "The parts are GPG-encrypted or filtered; automatic mode detection needs the integrity manifest, please choose the merge mode";
+    ContentsNeedsMode => "预览分片组的内容需要指定打包方式", "Specify the pack mode to preview the contents of a part set";
+    SearchEmpty => "请填写搜索内容", "Please enter a search query";
+    InvalidGlob => "通配符格式错误：{}", "Invalid wildcard pattern: {}";
+    // 部分恢复
+    RangeLengthNotPositive => "恢复长度必须大于 0", "Restore length must be greater than 0";
+    SevenZipNoRange => "7z 分片不支持按范围恢复", "7z parts do not support range restore";
+    TarNoRange => "tar 分片不支持按范围恢复", "tar parts do not support range restore";
+    OffsetOutOfRange => "起始位置超出文件大小（共 {} 字节）", "Start offset is beyond the file size ({} bytes in total)";
+    ArchiveMultipleFilesNoRange => "压缩包内包含多个文件，无法按字节范围恢复", "The archive contains more than one file, byte range restore is not possible";
+    ArchiveInvalid => "压缩包内容异常", "Archive content is invalid";
+    SevenZipNoEntry => "7z 分片不支持直接读取其中的文件，请先合并", "Files cannot be read directly from 7z parts, please merge first";
+    TarNoEntry => "tar 分片不支持直接读取其中的文件，请先合并", "Files cannot be read directly from tar parts, please merge first";
+    PackedNoEntry => "分片经过压缩或加密，无法直接读取其中的文件，请先合并", "The parts are compressed or encrypted, files cannot be read directly, please merge first";
+    NotZipNoEntry => "分片内容不是 zip 压缩包，无法直接读取其中的文件", "The parts do not contain a zip archive, files cannot be read directly";
+    EntryNameEmpty => "请填写要提取的文件名", "Please enter the file name to extract";
+    EntryAmbiguous => "匹配到多个文件，请填写完整路径", "Multiple files match, please enter the full path";
+    EntryNotFound => "未找到文件：{}", "File not found: {}";
+    EntryIsDir => "目标是目录，请填写文件路径", "The target is a directory, please enter a file path";
+    EntryIncomplete => "文件内容不完整", "File content is incomplete";
+    // zstd
+    NotSeekableZstd => "不是可随机读取的 zstd 数据", "Not seekable zstd data";
+    ZstdSeekTableCorrupt => "zstd 索引表已损坏", "The zstd seek table is corrupted";
+    ZstdSeekTableMismatch => "zstd 索引表与数据长度不一致", "The zstd seek table does not match the data length";
+    ZstdFrameSizeMismatch => "zstd 帧解压后的长度与索引不一致", "Decompressed zstd frame length does not match the seek table";
+    ZstdDictionaryMissing => "数据用 zstd 字典压缩，但分片旁没有字典文件（.zst.dict）", "The data was compressed with a zstd dictionary, but the dictionary file (.zst.dict) is missing next to the parts";
+    // HTTP / IPC
+    ApiStateError => "接口状态异常", "API state is corrupted";
+    ApiAlreadyRunning => "HTTP 接口已在运行：{}", "HTTP API is already running: {}";
+    ApiStartFailed => "无法启动 HTTP 接口：{}", "Cannot start the HTTP API: {}";
+    ApiNotRunning => "HTTP 接口未运行", "HTTP API is not running";
+    InvalidToken => "令牌无效", "Invalid token";
+    RouteNotFound => "接口不存在", "Endpoint not found";
+    InvalidRequestBody => "请求内容格式错误：{}", "Invalid request body: {}";
+    IpcStateError => "IPC 接口状态异常", "IPC server state is corrupted";
+    IpcAlreadyRunning => "IPC 接口已在运行：{}", "IPC server is already running: {}";
+    IpcStartFailed => "无法启动 IPC 接口：{}", "Cannot start the IPC server: {}";
+    IpcNotRunning => "IPC 接口未运行", "IPC server is not running";
+    WatchAlreadyRunning => "已在监视目录：{}", "Already watching a folder: {}";
+    WatchNotRunning => "未在监视目录", "No folder is being watched";
+    WatchDirNotFound => "监视目录不存在：{}", "Watch folder not found: {}";
+    UnknownMethod => "未知的方法：{}", "Unknown method: {}";
+    InvalidParams => "参数格式错误：{}", "Invalid params: {}";
+    // 插件、脚本、过滤
+    PluginStartFailed => "无法启动插件 {}：{}", "Cannot start plugin {}: {}";
+    PluginFailed => "插件 {} 执行失败：{}", "Plugin {} failed: {}";
+    PluginNotFound => "未找到插件：{}", "Plugin not found: {}";
+    ScriptLoadFailed => "脚本加载失败：{}", "Failed to load script: {}";
+    ScriptFnFailed => "脚本函数 {} 执行失败：{}", "Script function {} failed: {}";
+    ScriptNameNotString => "脚本函数 name 应返回字符串", "Script function name must return a string";
+    ScriptNameInvalid => "脚本返回的名称无效：{}", "Script returned an invalid name: {}";
+    ScriptFilterNotBool => "脚本函数 filter 应返回 true 或 false", "Script function filter must return true or false";
+    FilterStartFailed => "无法运行过滤命令：{}", "Cannot run the filter command: {}";
+    FilterFailed => "过滤命令执行失败：{}", "Filter command failed: {}";
+    // 命令行与无窗口模式
+    MissingFlagValue => "{} 缺少参数", "{} requires a value";
+    InvalidCount => "份数格式错误：{}", "Invalid part count: {}";
+    InvalidPartIndex => "分片序号格式错误：{}", "Invalid part index: {}";
+    InvalidLevel => "压缩级别格式错误：{}", "Invalid compression level: {}";
+    InvalidPar2Redundancy => "PAR2 冗余比例格式错误：{}", "Invalid PAR2 redundancy: {}";
+    InvalidRecoveryParts => "恢复分片份数格式错误：{}", "Invalid recovery part count: {}";
+    InvalidSize => "大小格式错误：{}", "Invalid size: {}";
+    SizeNotPositive => "大小必须大于 0", "Size must be greater than 0";
+    MissingInput => "缺少输入", "Missing input";
+    TooManyInputs => "只能指定一个输入", "Only one input can be given";
+    PartToStdoutOnly => "--part-to 只支持 stdout：{}", "--part-to only supports stdout: {}";
+    PartToNeedsPart => "--part-to stdout 需要同时指定 --part", "--part-to stdout requires --part";
+    ModeNoStdio => "{} 方式不支持标准输入输出", "The {} mode does not support standard input/output";
+    ModeNoPassword => "{} 方式不支持密码", "The {} mode does not support passwords";
+    ZstdStreamSizeOnly => "zstd-then-split 流式切分只支持 --size", "Streaming zstd-then-split only supports --size";
+    StreamNoCount => "从标准输入、管道或套接字读取时无法按份数切分，请使用 --size", "Cannot split by count when reading from standard input, a pipe or a socket, use --size";
+    SizeOrCountRequired => "请指定 --size 或 --count", "Please specify --size or --count";
+    RestorerCopyFailed => "复制自解压程序失败：{}", "Failed to copy the self-extracting restorer: {}";
+    RestorerConfigInvalid => "无法读取合并设置 restore.json：{}", "Cannot read the restore settings in restore.json: {}";
+    RestorerPasswordPrompt => "分片已加密，请输入密码：", "The parts are encrypted, enter the password: ";
+    RestorerPasswordRequired => "分片已加密，需要密码", "The parts are encrypted and need a password";
+    RestorerDone => "已还原到 {}", "Restored to {}";
+    RejoinScriptHeader => "由 file-split-packer 生成：按顺序拼接本目录中的分片，结果写到上一级目录", "Generated by file-split-packer: joins the parts in this directory in order into the parent directory";
+    RejoinScriptExtractHint => "把 EXTRACT 改为 0 时只拼接、不解压", "Set EXTRACT to 0 to join without extracting";
+    RejoinScriptTargetExists => "{} 已存在，请先移走", "{} already exists, move it away first";
+    RejoinScriptDone => "已合并到 {}", "Joined into {}";
+    InvalidPort => "端口格式错误：{}", "Invalid port: {}";
+    InvalidLogLevel => "不支持的日志级别：{}", "Unsupported log level: {}";
+    BenchmarkPathNotFound => "测速路径不存在：{}", "Benchmark path does not exist: {}";
+    InvalidOptions => "参数无效：{}", "Invalid options: {}";
+    FieldRequired => "必填", "Required";
+    OutputDirNotDir => "输出路径已存在且不是目录", "Output path exists and is not a directory";
+    UnknownDirSplitMode => "未知的目录切分策略：{}", "Unknown directory split mode: {}";
+    UnknownPowerAction => "未知的完成后操作：{}", "Unknown power action: {}";
+    PowerActionUnsupported => "当前系统不支持该电源操作：{}", "Power action not supported on this system: {}";
+    PowerActionFailed => "执行电源操作失败：{}", "Power action failed: {}";
+    ScriptNotFound => "脚本文件不存在：{}", "Script file does not exist: {}";
+    ManifestMissing => "分片目录中没有清单，只有以“先压缩然后分割”打包的目录才能追加", "The parts directory has no manifest; only directories packed with zip-then-split can be appended to";
+    ManifestInvalid => "清单格式无效：{}", "Invalid manifest: {}";
+    ManifestVersionUnsupported => "不支持的清单版本：{}", "Unsupported manifest version: {}";
+    MultipleManifests => "分片目录中有多个清单", "The parts directory contains more than one manifest";
+    AppendInputNotDir => "追加的源路径必须是目录", "The source to append from must be a directory";
+    BuilderMissingField => "缺少必填参数：{}", "Missing required option: {}";
+    BenchmarkStreamPath => "无法对管道或套接字测速：{}", "Cannot benchmark a pipe or socket: {}";
+    InvalidLanguage => "不支持的语言：{}", "Unsupported language: {}";
+    ApiStarted => "HTTP 接口已启动：{}", "HTTP API started: {}";
+    TestPassed => "共测试 {} 个文件，全部正常", "{} files tested, no errors";
+    ReencryptDone => "已以新密码重新加密 {} 份分片；恢复分片、异或校验分片与 PAR2 文件需重新生成", "Re-encrypted {} parts with the new password; recovery parts, XOR parity parts and PAR2 files must be regenerated";
+    DecryptDone => "已把 {} 份分片解密为不加密的 {} 分片：{}", "Decrypted {} parts into unencrypted {} parts: {}";
+    ReencryptDecrypted => "已去掉 {} 份分片的加密；恢复分片、异或校验分片与 PAR2 文件需重新生成", "Removed the encryption from {} parts; recovery parts, XOR parity parts and PAR2 files must be regenerated";
+    SigningKeyGenerated => "私钥：{}\n公钥：{}\n指纹：{}", "Private key: {}\nPublic key: {}\nFingerprint: {}";
+    TokenLine => "令牌：{}", "Token: {}";
+    IpcStarted => "IPC 接口已启动：{}", "IPC server started: {}";
+    // 帮助信息
+    CliUsage =>
+        "用法：
+  file-split-packer split <输入|-> (--size <大小> | --count <份数>) [选项]
+      --mode <方式>       raw、split-then-zip、zip-then-split、7z-then-split、zstd-then-split、
+                          tar-gz-then-split、tar-zst-then-split、raw-split、age-split、
+                          container-then-split、spanned-zip，
+                          输入为 -、命名管道或套接字时默认 raw；
+                          raw-split 与 raw 一样直接切分，另写完整性清单并支持续传；
+                          age-split 直接切分后每份以 age 加密（名称.part-001.age），
+                          口令为 --password，或用 --age-recipient 加密给公钥；
+                          container-then-split 以 --password 经 Argon2id 派生密钥，
+                          加密为本程序的容器（名称.fspc）后直接切分，目录先打成 zip；
+                          spanned-zip 输出标准分卷 zip（名称.z01……名称.zip），只能按 --size，
+                          每卷 64K 至 4G，WinZip、7-Zip 可直接打开
+      --name <名称>       输出名称，输入为 - 时默认 stdin
+      --output <目录>     输出目录，默认当前目录
+      --part <序号> --part-to stdout
+                          只把第几份（从 1 开始）写到标准输出，不写文件
+      --password <密码>   --level <压缩级别>
+      --method <方法>     zip 条目的压缩方法：deflate（默认）、bzip2 或 xz
+      --aes <位数>        设置密码时 zip 条目的 AES 强度：128、192 或 256（默认）
+      --encrypt-names     连同文件名与目录结构一起加密（zip-then-split、7z-then-split）
+      --keyfile <文件>    以文件的 SHA-256 代替或补充密码（接在密码之后）
+      --age-recipient <公钥|文件>
+                          age-split 加密给 age1… 公钥或接收方文件中的公钥，可重复
+      --gpg-recipient <公钥>
+                          打包后把每份分片加密给该 GPG 公钥（指纹、密钥 ID 或邮箱），可重复；
+                          需要本机安装 GnuPG
+      --no-resume         忽略上次中断留下的检查点，重新切分
+      --hash <算法>       清单中的校验算法：sha256（默认）或 blake3（多线程，适合大文件）
+      --par2 <百分比>     同时生成 PAR2 恢复文件，恢复数据占分片的百分比（1–100）
+      --recovery-parts <份数>
+                          另外生成恢复分片，合并时可重建不超过该份数的缺失或损坏分片
+      --parity            另外生成一份异或校验分片，合并时可重建一份缺失或损坏的分片
+      --sign <私钥文件>   用 Ed25519 私钥对完整性清单签名（split-then-zip、zip-then-split、raw-split、
+                          spanned-zip）
+      --verify-after-split
+                          分割完成后重新读取分片，解密、解压后与源比对
+      --destroy-source    打包完成且核对通过后覆写并删除源文件或目录（隐含 --verify-after-split）
+      --secure-delete     中间文件与取消后的输出先覆写再删除（加密时的明文中间文件总会覆写）
+      --7z-volumes        7z-then-split 的分片按 7-Zip 分卷命名（名称.7z.001），可直接用 7-Zip 打开
+      --per-part-keys     container-then-split 的每份分片以按序号派生的子密钥各自加密，可单独解密与核对
+      --numbered-volumes  分片按经典分卷命名（名称.001），HJSplit、FFSJ 与 7-Zip 可直接合并；
+                          不用于 split-then-zip 与 spanned-zip
+      --self-extracting   在分片目录中放入自解压程序 restore，接收方运行即可合并解压，
+                          只能在与本机相同的系统上运行
+      --rejoin-scripts    在分片目录中写出 rejoin.bat 与 rejoin.sh，只用系统自带工具拼接并解压；
+                          不用于 split-then-zip 与 spanned-zip
+      --zstd-dictionary   zstd-then-split 打包目录时训练 zstd 字典并用它压缩，适合大量相似的小文件
+      --power-action <操作>
+                          成功完成后 shutdown（关机）、sleep（睡眠）或 hibernate（休眠）
+
+  file-split-packer merge <分片目录|分片文件|-> [选项]
+      --mode <方式>       同上，默认 raw；auto 按清单、分片命名与第一份分片的内容自动识别
+      --output <目录|文件|->
+                          raw 与 zstd-then-split 可输出到文件或标准输出（-，输入为 - 时默认），
+                          其他方式为输出目录；raw 输出到目录时按清单或 SHA256SUMS 核对，
+                          也能合并 split 生成的分片（选第一份，如 xaa 或 x00）；
+                          zip-then-split 也能还原其他软件生成的 .z01/.zip 与 .zip.001 分卷，
+                          并直接解压分卷 RAR（名称.part1.rar 或 名称.rar/名称.r00）；
+                          --extract 按签名识别 zip、7z、RAR、tar（.gz/.zst/.xz）与单个 .gz/.xz 文件
+      --password <密码>   --extract（合并后自动解压）
+      --keyfile <文件>    打包时使用的密钥文件
+      --age-identity <文件>
+                          解密 age-split 分片的 age 身份文件，可重复
+      --gpg               分片加密给了 GPG 公钥，合并前先用本机私钥解密
+      --no-verify         合并后不再与完整性清单比对（合并前仍核对分片）
+      --trusted-key <公钥>
+                          只接受该公钥的清单签名，可重复；值为公钥文件、十六进制公钥或指纹
+      --require-signature 清单没有签名时拒绝合并
+      --salvage           尽力解压：跳过损坏的条目继续解压，最后列出跳过的条目（zip）
+      --include <通配符>  --extract 解压 zip 时只解压匹配的文件（如 *.psd），可重复
+      --exclude <通配符>  --extract 解压 zip 时跳过匹配的文件，可重复
+      --extract-from-parts
+                          --extract 解压 zip-then-split 分片时直接从分片读取，不生成合并后的 zip
+      --extract-only      合并后自动解压，只保留解压结果，删除合并得到的压缩包
+      --all-groups        输入目录中有多组分片时逐组合并，某组失败时继续合并其余各组
+      --missing-parts <fill|truncate>
+                          缺少分片且无法重建时，fill 补零、truncate 截断到第一处缺失，
+                          最后列出缺失的字节范围（raw、zip/7z/tar 先压缩后分割）
+      --on-conflict <overwrite|skip|rename>
+                          --extract 解压 zip 时输出目录中已有同名文件的处理，默认覆盖；
+                          rename 改名为 名称 (1).扩展名
+      --filename-encoding <auto|gbk|shift_jis|big5|euc-kr|...>
+                          --extract 解压 zip 时按该代码页解读非 UTF-8 的条目名，
+                          auto 自动推测（旧版中文、日文 Windows 工具打的 zip）
+      --url <网址>        从 HTTP(S) 网址下载分片后合并，可重复，此时不需要输入路径；
+                          网址为 .manifest.json 清单时按清单下载其中列出的分片，中断时自动续传
+      --keep-downloads    合并成功后保留下载的分片，默认删除
+      --no-resume         忽略上次中断留下的合并进度，从头合并（默认从中断处继续）
+      --secure-delete     临时目录与取消后的输出先覆写再删除
+
+  file-split-packer test <分片目录|分片文件|压缩包> [--mode <方式>] [--password <密码>]
+                          [--keyfile <文件>]
+                          不写出文件，逐个解压并核对 CRC；输入为分片时需指定 --mode
+
+  file-split-packer reencrypt <分片目录|分片文件> [--mode <方式>] [--password <原密码>]
+                          [--keyfile <原密钥文件>] [--new-password <新密码>] [--new-keyfile <文件>]
+                          不合并，逐份以新密码重新加密分片并更新清单；支持 split-then-zip、
+                          age-split 与 --per-part-keys 的 container-then-split，
+                          有清单时可省略 --mode；split-then-zip 不给新密码时去掉加密
+
+  file-split-packer decrypt <分片目录|分片文件> [--mode <方式>] [--password <密码>]
+                          [--keyfile <文件>] [--age-identity <文件>] [--output <目录>]
+                          把加密的分片转为不加密的分片，未给 --output 时原地替换；
+                          split-then-zip 仍为 split-then-zip，age-split 与 container-then-split
+                          转为 raw-split
+
+  file-split-packer keygen <私钥文件>
+                          生成 Ed25519 密钥对，公钥写在 <私钥文件>.pub
+
+  --max-memory <大小>     缓冲区与并发线程的内存上限，默认不限制
+  --no-adaptive-io        不测速，使用默认缓冲区大小与并发数
+  --background            以较低的 CPU 与磁盘 I/O 优先级运行（仅 split）
+  --lang <语言>           提示信息的语言：zh-CN（默认）或 en
+
+大小支持 K、M、G 后缀（1024 进制）。",
+        "Usage:
+  file-split-packer split <input|-> (--size <size> | --count <parts>) [options]
+      --mode <mode>       raw, split-then-zip, zip-then-split, 7z-then-split, zstd-then-split,
+                          tar-gz-then-split, tar-zst-then-split, raw-split, age-split,
+                          container-then-split, spanned-zip;
+                          defaults to raw when the input is -, a named pipe or a socket;
+                          raw-split splits like raw but also writes a manifest and can resume;
+                          age-split splits like raw-split and encrypts each part with age
+                          (name.part-001.age) to --password or to --age-recipient keys;
+                          container-then-split encrypts into this program's container
+                          (name.fspc) with an Argon2id key derived from --password and splits
+                          it as is; directories are zipped first;
+                          spanned-zip writes a standard split zip (name.z01 ... name.zip) that
+                          WinZip and 7-Zip open directly; --size only, 64K to 4G per volume
+      --name <name>       output name, defaults to stdin when the input is -
+      --output <dir>      output directory, defaults to the current directory
+      --part <index> --part-to stdout
+                          write only the given part (starting at 1) to standard output
+      --password <password>   --level <compression level>
+      --method <method>   compression method for zip entries: deflate (default), bzip2 or xz
+      --aes <bits>        AES strength for password-protected zip entries: 128, 192 or 256
+                          (default)
+      --encrypt-names     encrypt file names and directory structure as well
+                          (zip-then-split, 7z-then-split)
+      --keyfile <file>    use the SHA-256 of a file instead of or after the password
+      --age-recipient <key|file>
+                          age-split: encrypt to an age1... public key or the keys in a
+                          recipients file, repeatable
+      --gpg-recipient <key>
+                          encrypt every part to this GPG public key (fingerprint, key ID or
+                          email) after packing, repeatable; requires GnuPG
+      --no-resume         ignore the checkpoint left by an interrupted run and start over
+      --hash <algorithm>  checksum algorithm for the manifest: sha256 (default) or blake3
+                          (multithreaded, faster for large inputs)
+      --par2 <percent>    also write PAR2 recovery files with the given redundancy (1-100)
+      --recovery-parts <count>
+                          also write recovery parts; merging rebuilds up to that many
+                          missing or damaged parts
+      --parity            also write one XOR parity part; merging rebuilds one missing
+                          or damaged part
+      --sign <key file>   sign the integrity manifest with an Ed25519 private key
+                          (split-then-zip, zip-then-split, raw-split, spanned-zip)
+      --verify-after-split
+                          re-read the parts after splitting and compare the decrypted,
+                          decompressed content with the source
+      --destroy-source    overwrite and delete the source file or directory once packing
+                          has finished and the parts are verified (implies
+                          --verify-after-split)
+      --secure-delete     overwrite temporary files and outputs of a cancelled job before
+                          deleting them (plaintext temporaries are always overwritten when
+                          encrypting)
+      --7z-volumes        name 7z-then-split parts as 7-Zip volumes (name.7z.001) so
+                          7-Zip can open them directly
+      --per-part-keys     encrypt each container-then-split part on its own with a key
+                          derived from its index, so every part can be decrypted and
+                          checked alone
+      --numbered-volumes  name parts as classic volumes (name.001) that HJSplit, FFSJ and
+                          7-Zip can join; not for split-then-zip or spanned-zip
+      --self-extracting   put a self-extracting restorer (restore) into the parts directory;
+                          recipients run it to merge and extract, same OS as this machine only
+      --rejoin-scripts    write rejoin.bat and rejoin.sh into the parts directory that join and
+                          extract with built-in tools only; not for split-then-zip or spanned-zip
+      --zstd-dictionary   train a zstd dictionary when packing a directory with zstd-then-split
+                          and compress with it; suits many small similar files
+      --power-action <action>
+                          shutdown, sleep or hibernate after finishing successfully
+
+  file-split-packer merge <parts dir|part file|-> [options]
+      --mode <mode>       as above, defaults to raw; auto detects the mode from the manifest,
+                          the part names and the content of the first part
+      --output <dir|file|->
+                          raw and zstd-then-split can write to a file or standard output
+                          (-, the default when the input is -); other modes take an output directory;
+                          raw into a directory checks the manifest or SHA256SUMS and also merges
+                          parts made by split (choose the first one, such as xaa or x00);
+                          zip-then-split also restores .z01/.zip and .zip.001 volumes made by
+                          other tools and extracts RAR volumes (name.part1.rar or
+                          name.rar/name.r00) directly; --extract detects zip, 7z, RAR, tar (.gz/.zst/.xz)
+                          and single .gz/.xz files by signature
+      --password <password>   --extract (extract after merging)
+      --keyfile <file>    the keyfile used when packing
+      --age-identity <file>
+                          age identity file that decrypts age-split parts, repeatable
+      --gpg               the parts are encrypted to GPG keys; decrypt them with the local
+                          private key before merging
+      --no-verify         skip comparing the result with the integrity manifest after merging
+                          (parts are still checked before merging)
+      --trusted-key <key> only accept manifest signatures from this key, repeatable; a public key
+                          file, a hex public key or a fingerprint
+      --require-signature refuse to merge when the manifest is not signed
+      --salvage           best-effort extraction: skip damaged entries, extract the rest and
+                          list what was lost (zip)
+      --include <glob>    with --extract, only extract zip entries matching the pattern
+                          (such as *.psd), repeatable
+      --exclude <glob>    with --extract, skip zip entries matching the pattern, repeatable
+      --extract-from-parts
+                          with --extract, read zip-then-split parts directly instead of
+                          writing the merged zip first
+      --extract-only      extract after merging and keep only the extracted files, deleting
+                          the merged archive
+      --all-groups        restore every part group in the input directory one after another,
+                          continuing past groups that fail
+      --missing-parts <fill|truncate>
+                          when parts are missing and cannot be rebuilt, fill the gaps with zeros
+                          or truncate at the first gap, then list the missing byte ranges
+                          (raw and zip/7z/tar-then-split)
+      --on-conflict <overwrite|skip|rename>
+                          with --extract, what to do when a zip entry's file already exists in
+                          the output directory (default overwrite); rename writes name (1).ext
+      --filename-encoding <auto|gbk|shift_jis|big5|euc-kr|...>
+                          with --extract, decode non-UTF-8 zip entry names with this code page;
+                          auto guesses it (zips made by old Chinese/Japanese Windows tools)
+      --url <url>         download the parts from an HTTP(S) URL before merging, repeatable; no
+                          input path is needed then; a .manifest.json URL downloads every part it
+                          lists; interrupted downloads resume automatically
+      --keep-downloads    keep the downloaded parts after a successful merge (deleted by default)
+      --no-resume         ignore the progress left by an interrupted merge and start over
+                          (by default the merge continues where it stopped)
+      --secure-delete     overwrite temporary directories and outputs of a cancelled job
+                          before deleting them
+
+  file-split-packer test <parts dir|part file|archive> [--mode <mode>] [--password <password>]
+                          [--keyfile <file>]
+                          decompress every file and check CRCs without writing anything;
+                          --mode is required for parts
+
+  file-split-packer reencrypt <parts dir|part file> [--mode <mode>] [--password <old password>]
+                          [--keyfile <old keyfile>] [--new-password <password>] [--new-keyfile <file>]
+                          re-encrypt every part with the new password without merging and update
+                          the manifest; supports split-then-zip, age-split and container-then-split
+                          with --per-part-keys; --mode may be omitted when there is a manifest;
+                          split-then-zip without a new password removes the encryption
+
+  file-split-packer decrypt <parts dir|part file> [--mode <mode>] [--password <password>]
+                          [--keyfile <file>] [--age-identity <file>] [--output <dir>]
+                          turn encrypted parts into unencrypted ones, in place unless --output
+                          is given; split-then-zip stays split-then-zip, age-split and
+                          container-then-split become raw-split
+
+  file-split-packer keygen <key file>
+                          generate an Ed25519 key pair, the public key goes to <key file>.pub
+
+  --max-memory <size>     memory limit for buffers and worker threads, unlimited by default
+  --no-adaptive-io        skip the device speed probe and use default buffer sizes and workers
+  --background            run with lower CPU and disk I/O priority (split only)
+  --lang <language>       message language: zh-CN (default) or en
+
+Sizes accept K, M, G suffixes (powers of 1024).";
+    HeadlessUsage =>
+        "用法：file-split-packer --headless [--port <端口>] [--token <令牌>] [--ipc [<名称>]]
+
+  --port <端口>    HTTP 接口端口，默认 38517
+  --token <令牌>   接口令牌，也可通过环境变量 FILE_SPLIT_PACKER_TOKEN 指定；
+                   均未指定时随机生成
+  --ipc [<名称>]   同时启动 IPC 接口，可指定套接字路径（Unix）或管道名（Windows）
+  --lang <语言>    提示信息的语言：zh-CN（默认）或 en",
+        "Usage: file-split-packer --headless [--port <port>] [--token <token>] [--ipc [<name>]]
+
+  --port <port>    HTTP API port, defaults to 38517
+  --token <token>  API token, can also be given via the FILE_SPLIT_PACKER_TOKEN environment variable;
+                   generated randomly when neither is given
+  --ipc [<name>]   also start the IPC server, optionally with a socket path (Unix) or pipe name (Windows)
+  --lang <lang>    message language: zh-CN (default) or en";
+    RestorerUsage =>
+        "用法：restore [--output <目录>] [--password <密码>] [--keyfile <文件>]
+               [--age-identity <文件>]
+
+合并并解压本程序所在目录中的分片，默认输出到上一级目录。
+
+  --output <目录>     输出目录
+  --password <密码>   分片的密码，加密时未指定则运行后询问
+  --keyfile <文件>    打包时使用的密钥文件
+  --age-identity <文件>
+                      分片加密给 age 公钥时，用于解密的身份文件，可重复
+  --lang <语言>       提示信息的语言：zh-CN（默认）或 en",
+        "Usage: restore [--output <dir>] [--password <password>] [--keyfile <file>]
+               [--age-identity <file>]
+
+Merges and extracts the parts in the directory of this program, into its parent directory by default.
+
+  --output <dir>          output directory
+  --password <password>   password of the parts, asked for when they are encrypted and none is given
+  --keyfile <file>        the keyfile used when packing
+  --age-identity <file>   identity file that decrypts parts encrypted to age public keys, repeatable
+  --lang <lang>           message language: zh-CN (default) or en";
+}
+
+impl fmt::Display for Msg {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.text())
+    }
+}
@@ -0,0 +1,359 @@
+//! 分片索引：记录每份分片在拼接数据中的位置，以及压缩包内每个条目落在哪几份分片上。
+//! 索引缓存为分片目录下的 `<名称>.index.json`，分片大小或修改时间变化后自动失效。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    fs::{self, File},
+    io::BufReader,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::{
+    i18n::tr,
+    partial::{open_archive_from_layout, PartSource},
+    PartGroup,
+};
+
+const INDEX_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartIndex {
+    pub(crate) version: u32,
+    pub(crate) merge_mode: String,
+    /// 拼接后数据的总长度（先分割后压缩为各分片条目解压后的长度之和）。
+    pub(crate) total_size: u64,
+    pub(crate) parts: Vec<IndexedPart>,
+    pub(crate) entries: Vec<IndexedEntry>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexedPart {
+    pub(crate) index: usize,
+    pub(crate) file_name: String,
+    pub(crate) file_size: u64,
+    pub(crate) modified: u64,
+    /// 本分片数据在拼接数据中的起始位置与长度。
+    pub(crate) offset: u64,
+    pub(crate) len: u64,
+    /// 数据在分片文件内的起始位置；仅 `seekable` 为 true 时可直接定位读取。
+    pub(crate) data_start: u64,
+    pub(crate) seekable: bool,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct IndexedEntry {
+    pub(crate) name: String,
+    pub(crate) is_dir: bool,
+    pub(crate) size: u64,
+    pub(crate) compressed_size: u64,
+    /// 条目（含本地文件头）在压缩包中的字节区间。
+    pub(crate) offset: u64,
+    pub(crate) end: u64,
+    pub(crate) parts: Vec<usize>,
+}
+
+/// 损坏数据所在的分片文件与其中的字节区间 `[start, end)`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartRange {
+    pub(crate) part: String,
+    pub(crate) start: u64,
+    pub(crate) end: u64,
+}
+
+pub(crate) fn index_path(part_group: &PartGroup) -> Result<PathBuf, String> {
+    let dir = part_group
+        .parts
+        .first()
+        .and_then(|part| part.path.parent())
+        .ok_or_else(|| tr!(CannotParsePartsDir))?;
+    Ok(dir.join(format!(
+        "{}.index.json",
+        part_group.prefix.trim_end_matches('.')
+    )))
+}
+
+/// 读取已缓存且仍然有效的索引。
+pub(crate) fn cached_index(part_group: &PartGroup, merge_mode: &str) -> Option<PartIndex> {
+    let path = index_path(part_group).ok()?;
+    let content = fs::read_to_string(path).ok()?;
+    let index: PartIndex = serde_json::from_str(&content).ok()?;
+    if index.version != INDEX_VERSION
+        || index.merge_mode != merge_mode
+        || index.parts.len() != part_group.parts.len()
+    {
+        return None;
+    }
+    for (indexed, part) in index.parts.iter().zip(&part_group.parts) {
+        let (file_size, modified) = file_stamp(&part.path).ok()?;
+        if indexed.index != part.index
+            || indexed.file_size != file_size
+            || indexed.modified != modified
+        {
+            return None;
+        }
+    }
+    Some(index)
+}
+
+/// 返回有效的缓存索引，否则重新生成并尽量写入缓存；分片在只读介质上等写不了缓存时只记日志。
+pub(crate) fn load_or_build_index(
+    part_group: &PartGroup,
+    merge_mode: &str,
+    rebuild: bool,
+) -> Result<(PartIndex, bool), String> {
+    if !rebuild {
+        if let Some(index) = cached_index(part_group, merge_mode) {
+            return Ok((index, true));
+        }
+    }
+    let index = build_index(part_group, merge_mode)?;
+    let written = serde_json::to_string_pretty(&index)
+        .map_err(|e| e.to_string())
+        .and_then(|content| {
+            let path = index_path(part_group)?;
+            fs::write(&path, content).map_err(|e| format!("{}：{}", path.display(), e))
+        });
+    if let Err(err) = written {
+        log::warn!("无法写入索引缓存 {}", err);
+    }
+    Ok((index, false))
+}
+
+/// 有缓存时直接使用缓存中的分片布局，否则现场计算（不写缓存）。
+pub(crate) fn part_layout(
+    part_group: &PartGroup,
+    merge_mode: &str,
+) -> Result<Vec<IndexedPart>, String> {
+    match cached_index(part_group, merge_mode) {
+        Some(index) => Ok(index.parts),
+        None => compute_layout(part_group, merge_mode),
+    }
+}
+
+fn build_index(part_group: &PartGroup, merge_mode: &str) -> Result<PartIndex, String> {
+    let parts = compute_layout(part_group, merge_mode)?;
+    let total_size = parts.iter().map(|part| part.len).sum();
+
+    let entries = match open_archive_from_layout(part_group, merge_mode, &parts) {
+        Ok(archive) => list_entries(archive, &parts)?,
+        // 单个文件直接分割时，拼接结果就是原文件本身
+        Err(_)
+            if parts.iter().all(|part| part.seekable)
+                && !matches!(
+                    merge_mode,
+                    "7z-then-split" | "tar-gz-then-split" | "tar-zst-then-split"
+                ) =>
+        {
+            vec![IndexedEntry {
+                name: part_group.prefix.trim_end_matches('.').to_string(),
+                is_dir: false,
+                size: total_size,
+                compressed_size: total_size,
+                offset: 0,
+                end: total_size,
+                parts: parts.iter().map(|part| part.index).collect(),
+            }]
+        }
+        Err(_) => Vec::new(),
+    };
+
+    Ok(PartIndex {
+        version: INDEX_VERSION,
+        merge_mode: merge_mode.to_string(),
+        total_size,
+        parts,
+        entries,
+    })
+}
+
+fn compute_layout(part_group: &PartGroup, merge_mode: &str) -> Result<Vec<IndexedPart>, String> {
+    let mut layout = Vec::with_capacity(part_group.parts.len());
+    let mut offset = 0u64;
+    for part in &part_group.parts {
+        let (file_size, modified) = file_stamp(&part.path)?;
+        let file_name = part
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        let (data_start, len, seekable) = if merge_mode == "split-then-zip" {
+            let file = File::open(&part.path).map_err(|e| e.to_string())?;
+            let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+            if archive.len() != 1 {
+                return Err(tr!(PartArchiveInvalid));
+            }
+            let raw = archive.by_index_raw(0).map_err(|e| e.to_string())?;
+            (
+                raw.data_start(),
+                raw.size(),
+                matches!(raw.compression(), CompressionMethod::Stored) && !raw.encrypted(),
+            )
+        } else {
+            (0, file_size, true)
+        };
+        layout.push(IndexedPart {
+            index: part.index,
+            file_name,
+            file_size,
+            modified,
+            offset,
+            len,
+            data_start,
+            seekable,
+        });
+        offset += len;
+    }
+    Ok(layout)
+}
+
+fn list_entries(
+    mut archive: ZipArchive<BufReader<PartSource>>,
+    parts: &[IndexedPart],
+) -> Result<Vec<IndexedEntry>, String> {
+    let mut entries = Vec::with_capacity(archive.len());
+    for index in 0..archive.len() {
+        let raw = archive.by_index_raw(index).map_err(|e| e.to_string())?;
+        entries.push(IndexedEntry {
+            name: raw.name().to_string(),
+            is_dir: raw.is_dir(),
+            size: raw.size(),
+            compressed_size: raw.compressed_size(),
+            offset: raw.header_start(),
+            end: raw.data_start() + raw.compressed_size(),
+            parts: Vec::new(),
+        });
+    }
+
+    // zstd 分片中的区间是解压后的位置，需要先换算回压缩数据中的位置
+    let source = archive.into_inner().into_inner();
+    for entry in &mut entries {
+        let (start, end) = match &source {
+            PartSource::Zstd(reader) => reader.compressed_range(entry.offset, entry.end),
+            PartSource::Plain(_) => (entry.offset, entry.end),
+        };
+        entry.parts = parts_for_range(parts, start, end);
+    }
+    Ok(entries)
+}
+
+/// 按名称搜索条目：包含 `*`、`?`、`[` 时按通配符匹配完整路径，否则按子串匹配。
+pub(crate) fn search_entries<'a>(
+    index: &'a PartIndex,
+    pattern: &str,
+    case_sensitive: bool,
+) -> Result<Vec<&'a IndexedEntry>, String> {
+    let pattern = pattern.trim().replace('\\', "/");
+    if pattern.is_empty() {
+        return Err(tr!(SearchEmpty));
+    }
+    if pattern.contains(['*', '?', '[']) {
+        let glob = glob::Pattern::new(&pattern).map_err(|e| tr!(InvalidGlob, e))?;
+        let options = glob::MatchOptions {
+            case_sensitive,
+            require_literal_separator: false,
+            require_literal_leading_dot: false,
+        };
+        return Ok(index
+            .entries
+            .iter()
+            .filter(|entry| {
+                let file_name = entry.name.trim_end_matches('/').rsplit('/').next().unwrap_or("");
+                glob.matches_with(&entry.name, options) || glob.matches_with(file_name, options)
+            })
+            .collect());
+    }
+
+    let needle = if case_sensitive {
+        pattern
+    } else {
+        pattern.to_lowercase()
+    };
+    Ok(index
+        .entries
+        .iter()
+        .filter(|entry| {
+            if case_sensitive {
+                entry.name.contains(&needle)
+            } else {
+                entry.name.to_lowercase().contains(&needle)
+            }
+        })
+        .collect())
+}
+
+/// 拼接数据中 `[start, end)` 区间所覆盖的分片序号。
+pub(crate) fn parts_for_range(parts: &[IndexedPart], start: u64, end: u64) -> Vec<usize> {
+    parts
+        .iter()
+        .filter(|part| part.len > 0 && part.offset < end && start < part.offset + part.len)
+        .map(|part| part.index)
+        .collect()
+}
+
+/// 把拼接数据中的 `[start, end)` 换算为各分片文件内的字节区间；分片经过压缩或加密时
+/// 无法换算，给出该分片的整个数据区。
+pub(crate) fn locate_range(parts: &[IndexedPart], start: u64, end: u64) -> Vec<PartRange> {
+    parts
+        .iter()
+        .filter(|part| part.len > 0 && part.offset < end && start < part.offset + part.len)
+        .map(|part| {
+            let (from, to) = if part.seekable {
+                (
+                    part.data_start + start.max(part.offset) - part.offset,
+                    part.data_start + end.min(part.offset + part.len) - part.offset,
+                )
+            } else {
+                (part.data_start, part.file_size)
+            };
+            PartRange {
+                part: part.file_name.clone(),
+                start: from,
+                end: to,
+            }
+        })
+        .collect()
+}
+
+/// 用于错误信息：`名称.part-002 第 100–200 字节, ...`。
+pub(crate) fn describe_ranges(ranges: &[PartRange]) -> String {
+    ranges
+        .iter()
+        .map(|range| tr!(PartByteRange, range.part, range.start, range.end))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+/// 把单个文件（合并后的压缩包或一份分片）当作只有一份的布局，区间即文件内的位置。
+pub(crate) fn whole_file(path: &Path) -> Result<IndexedPart, String> {
+    let (file_size, modified) = file_stamp(path)?;
+    Ok(IndexedPart {
+        index: 1,
+        file_name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        file_size,
+        modified,
+        offset: 0,
+        len: file_size,
+        data_start: 0,
+        seekable: true,
+    })
+}
+
+fn file_stamp(path: &Path) -> Result<(u64, u64), String> {
+    let metadata = fs::metadata(path).map_err(|e| e.to_string())?;
+    let modified = metadata
+        .modified()
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0);
+    Ok((metadata.len(), modified))
+}
@@ -0,0 +1,828 @@
+//! 完整性清单：以 `split-then-zip`、`zip-then-split` 或 `raw-split` 打包后，计算每一份分片以及
+//! 被切分内容（单个文件本身，或目录打成的 zip）的校验值，写入分片目录的 `<名称>.manifest.json`。
+//! 目录以 `zip-then-split` 打包时分片目录中已有追加清单（参见 [`crate::append`]），校验值写入同一个文件。
+//!
+//! 被切分内容是本程序打成的 zip 时，清单还记录其中每个文件条目的源文件校验值。
+//!
+//! `zstd-then-split` 的清单只记录分片（压缩前的内容可能来自管道，没有 `payload`），另在 `frames`
+//! 字段记录帧索引（参见 [`crate::seekable`]），随分片校验值一起签名。
+//!
+//! 合并时若分片目录中有清单，先逐份核对大小与校验值，全部一致才开始合并；合并完成后
+//! （默认开启）再把合并得到的文件与解压出的文件与清单比对，结果写入 `verified` 与 `mismatches`。
+//! 没有清单的分片照常合并，不做校验。
+//!
+//! 校验算法默认为 SHA-256；数十 GB 的输入可改用 BLAKE3，借助 rayon 多线程计算，
+//! 校验不再占去大部分耗时。界面生成的合并脚本只支持 SHA-256。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    append::{self, MANIFEST_SUFFIX, MANIFEST_VERSION},
+    emit_progress, find_part_group,
+    i18n::tr,
+    jobs::JobContext,
+    parse_part_name, part_comment, priority, signing, PartSha256, RestoreResult,
+};
+
+/// 校验算法。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub(crate) enum HashAlgorithm {
+    #[default]
+    Sha256,
+    Blake3,
+}
+
+impl HashAlgorithm {
+    /// 解析 `sha256`、`blake3`，忽略大小写；空字符串视为 `sha256`。
+    pub(crate) fn parse(value: &str) -> Option<HashAlgorithm> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "" | "sha256" | "sha-256" => Some(HashAlgorithm::Sha256),
+            "blake3" => Some(HashAlgorithm::Blake3),
+            _ => None,
+        }
+    }
+}
+
+/// 计算中的校验值。BLAKE3 把每一块数据分给 rayon 线程池并行计算；
+/// 以后台优先级运行时改用降低了优先级的线程池，参见 [`priority::background_pool`]。
+pub(crate) enum Hasher {
+    Sha256(Sha256),
+    Blake3 {
+        hasher: Box<blake3::Hasher>,
+        background: bool,
+    },
+}
+
+impl Hasher {
+    pub(crate) fn new(ctx: &JobContext, algorithm: HashAlgorithm) -> Hasher {
+        match algorithm {
+            HashAlgorithm::Sha256 => Hasher::Sha256(Sha256::new()),
+            HashAlgorithm::Blake3 => Hasher::Blake3 {
+                hasher: Box::new(blake3::Hasher::new()),
+                background: ctx.background_priority(),
+            },
+        }
+    }
+
+    pub(crate) fn update(&mut self, data: &[u8]) {
+        match self {
+            Hasher::Sha256(hasher) => hasher.update(data),
+            Hasher::Blake3 { hasher, background } => {
+                priority::install(*background, || {
+                    hasher.update_rayon(data);
+                });
+            }
+        }
+    }
+
+    pub(crate) fn finish(self) -> String {
+        match self {
+            Hasher::Sha256(hasher) => format!("{:x}", hasher.finalize()),
+            Hasher::Blake3 { hasher, .. } => hasher.finalize().to_hex().to_string(),
+        }
+    }
+}
+
+/// 一个文件的大小与校验值。
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FileDigest {
+    /// 分片与整体为文件名（不含目录），解压条目为 zip 内的路径（`/` 分隔）。
+    name: String,
+    size: u64,
+    /// 十六进制校验值，算法见 [`Integrity::algorithm`]。早期清单的字段名为 `sha256`。
+    #[serde(alias = "sha256")]
+    hash: String,
+}
+
+/// 清单中与校验有关的字段，与追加清单的字段并列存放。
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct Integrity {
+    pack_mode: String,
+    #[serde(default)]
+    algorithm: HashAlgorithm,
+    /// 被切分的内容：单个文件本身，或目录打成的 zip。
+    payload: Option<FileDigest>,
+    parts: Vec<FileDigest>,
+    /// `payload` 为 zip 时其中的文件条目，用于核对解压结果。
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    entries: Vec<FileDigest>,
+    /// 读取时所在的清单文件。
+    #[serde(skip)]
+    path: PathBuf,
+}
+
+impl Integrity {
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub(crate) fn algorithm(&self) -> HashAlgorithm {
+        self.algorithm
+    }
+
+    pub(crate) fn pack_mode(&self) -> &str {
+        &self.pack_mode
+    }
+
+    /// 被切分内容的大小与校验值。
+    pub(crate) fn payload(&self) -> Option<(u64, &str)> {
+        self.payload
+            .as_ref()
+            .map(|digest| (digest.size, digest.hash.as_str()))
+    }
+
+    /// 清单中校验之外的说明字段（如 `frames`），读不出或没有时返回 `None`。
+    pub(crate) fn field(&self, key: &str) -> Option<Value> {
+        read_object(&self.path).ok()?.remove(key)
+    }
+
+    /// 按顺序排列的各份分片的文件名与大小。
+    pub(crate) fn part_sizes(&self) -> Vec<(&str, u64)> {
+        self.parts
+            .iter()
+            .map(|part| (part.name.as_str(), part.size))
+            .collect()
+    }
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default()
+}
+
+/// 计算文件的大小与校验值，每读一块回调一次已读字节数。
+fn digest_file(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    path: &Path,
+    mut progress: impl FnMut(u64),
+) -> Result<FileDigest, String> {
+    let mut file = File::open(path).map_err(|e| tr!(HashFailed, path.display(), e))?;
+    let mut hasher = Hasher::new(ctx, algorithm);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut size = 0u64;
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = file
+            .read(&mut buffer)
+            .map_err(|e| tr!(HashFailed, path.display(), e))?;
+        if read_len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read_len]);
+        size += read_len as u64;
+        progress(read_len as u64);
+    }
+    Ok(FileDigest {
+        name: file_name(path),
+        size,
+        hash: hasher.finish(),
+    })
+}
+
+/// 依次计算每份分片的校验值，进度阶段为 `hash`。
+fn digest_parts(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    paths: &[PathBuf],
+) -> Result<Vec<FileDigest>, String> {
+    let total: u64 = paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum();
+    let mut processed = 0u64;
+    let mut digests = Vec::with_capacity(paths.len());
+    for (idx, path) in paths.iter().enumerate() {
+        digests.push(digest_file(ctx, algorithm, path, |delta| {
+            processed += delta;
+            emit_progress(ctx, "hash", processed, total, idx + 1, paths.len(), tr!(HashingPart, idx + 1));
+        })?);
+    }
+    Ok(digests)
+}
+
+/// 结果中的 `partSha256s`；使用其他算法时为空。
+fn part_sha256s(
+    parts_dir: &Path,
+    algorithm: HashAlgorithm,
+    digests: &[FileDigest],
+) -> Vec<PartSha256> {
+    if algorithm != HashAlgorithm::Sha256 {
+        return Vec::new();
+    }
+    digests
+        .iter()
+        .map(|digest| PartSha256 {
+            path: parts_dir.join(&digest.name).to_string_lossy().to_string(),
+            sha256: digest.hash.clone(),
+        })
+        .collect()
+}
+
+fn read_object(path: &Path) -> Result<Map<String, Value>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&text).map_err(|e| tr!(ManifestInvalid, e))? {
+        Value::Object(object) => Ok(object),
+        _ => Err(tr!(ManifestInvalid, path.display())),
+    }
+}
+
+/// 把校验字段写入清单；清单已存在时保留其他字段。
+fn save(path: &Path, base_name: &str, integrity: &Integrity) -> Result<(), String> {
+    let mut object = if path.is_file() {
+        read_object(path)?
+    } else {
+        Map::new()
+    };
+    object
+        .entry("version")
+        .or_insert_with(|| Value::from(MANIFEST_VERSION));
+    object
+        .entry("baseName")
+        .or_insert_with(|| Value::from(base_name));
+    if let Value::Object(fields) = serde_json::to_value(integrity).map_err(|e| e.to_string())? {
+        object.extend(fields);
+    }
+    write_object(path, object)
+}
+
+fn write_object(path: &Path, mut object: Map<String, Value>) -> Result<(), String> {
+    signing::discard(path, &mut object);
+    let text = serde_json::to_string_pretty(&object).map_err(|e| e.to_string())?;
+    let temp = path.with_extension("json.tmp");
+    fs::write(&temp, text).map_err(|e| e.to_string())?;
+    fs::rename(&temp, path).map_err(|e| e.to_string())
+}
+
+fn parse(path: &Path) -> Result<Option<Integrity>, String> {
+    let object = read_object(path)?;
+    if !object.contains_key("parts") {
+        return Ok(None);
+    }
+    let version = object.get("version").and_then(Value::as_u64).unwrap_or(0);
+    if version > MANIFEST_VERSION as u64 {
+        return Err(tr!(ManifestVersionUnsupported, version));
+    }
+    serde_json::from_value(Value::Object(object))
+        .map(Some)
+        .map_err(|e| tr!(ManifestInvalid, e))
+}
+
+/// zip 中的文件条目及其源文件：`source` 为目录时条目名的第一段是目录名，
+/// 为单个文件时 zip 中只有这一个文件。
+fn zip_entry_sources(zip_path: &Path, source: &Path) -> Result<Vec<(String, PathBuf)>, String> {
+    let file = File::open(zip_path).map_err(|e| e.to_string())?;
+    let archive = ZipArchive::new(file).map_err(|e| e.to_string())?;
+    let is_dir = source.is_dir();
+    Ok(archive
+        .file_names()
+        .filter(|name| !name.ends_with('/'))
+        .filter_map(|name| {
+            let path = if is_dir {
+                source.join(name.split_once('/')?.1)
+            } else {
+                source.to_path_buf()
+            };
+            Some((name.to_string(), path))
+        })
+        .collect())
+}
+
+/// 依次计算源文件的校验值，记录为对应 zip 条目名，进度阶段为 `hash`。
+fn digest_entries(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    entries: &[(String, PathBuf)],
+) -> Result<Vec<FileDigest>, String> {
+    let total: u64 = entries
+        .iter()
+        .filter_map(|(_, path)| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum();
+    let mut processed = 0u64;
+    let mut digests = Vec::with_capacity(entries.len());
+    for (name, path) in entries {
+        let mut digest = digest_file(ctx, algorithm, path, |delta| {
+            processed += delta;
+            emit_progress(ctx, "hash", processed, total, 0, 0, tr!(HashingEntries));
+        })?;
+        digest.name = name.clone();
+        digests.push(digest);
+    }
+    Ok(digests)
+}
+
+/// 打包完成后计算分片与 `payload` 的校验值并写入清单，返回每份分片的 SHA-256。
+/// `payload` 是由 `source` 打成的 zip 时，同时记录其中每个文件条目的校验值。
+pub(crate) fn write_manifest(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    parts_dir: &Path,
+    base_name: &str,
+    pack_mode: &str,
+    payload: &Path,
+    source: Option<&Path>,
+    output_files: &[String],
+) -> Result<Vec<PartSha256>, String> {
+    let entries = match source {
+        Some(source) => digest_entries(ctx, algorithm, &zip_entry_sources(payload, source)?)?,
+        None => Vec::new(),
+    };
+    let paths: Vec<PathBuf> = output_files.iter().map(PathBuf::from).collect();
+    let parts = digest_parts(ctx, algorithm, &paths)?;
+    let total = fs::metadata(payload).map(|meta| meta.len()).unwrap_or(0);
+    let mut processed = 0u64;
+    let payload = digest_file(ctx, algorithm, payload, |delta| {
+        processed += delta;
+        emit_progress(ctx, "hash", processed, total, 0, 0, tr!(HashingPayload));
+    })?;
+    let sha256s = part_sha256s(parts_dir, algorithm, &parts);
+    let integrity = Integrity {
+        pack_mode: pack_mode.to_string(),
+        algorithm,
+        payload: Some(payload),
+        parts,
+        entries,
+        path: PathBuf::new(),
+    };
+    save(&append::manifest_path(parts_dir, base_name), base_name, &integrity)?;
+    Ok(sha256s)
+}
+
+/// 只计算分片的校验值并写入清单，用于没有完整 `payload` 可记录的打包方式，返回每份分片的 SHA-256。
+pub(crate) fn write_part_manifest(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    parts_dir: &Path,
+    base_name: &str,
+    pack_mode: &str,
+    output_files: &[String],
+) -> Result<Vec<PartSha256>, String> {
+    let paths: Vec<PathBuf> = output_files.iter().map(PathBuf::from).collect();
+    let parts = digest_parts(ctx, algorithm, &paths)?;
+    let sha256s = part_sha256s(parts_dir, algorithm, &parts);
+    let integrity = Integrity {
+        pack_mode: pack_mode.to_string(),
+        algorithm,
+        payload: None,
+        parts,
+        entries: Vec::new(),
+        path: PathBuf::new(),
+    };
+    save(&append::manifest_path(parts_dir, base_name), base_name, &integrity)?;
+    Ok(sha256s)
+}
+
+/// 分片内容改变（如经过过滤命令）或新增分片（追加打包）后按清单的算法重新计算这些分片的
+/// 校验值，同名的记录被替换。分片目录中没有清单时什么也不做，返回空列表。
+pub(crate) fn record_parts(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    base_name: &str,
+    output_files: &[String],
+) -> Result<Vec<PartSha256>, String> {
+    let path = append::manifest_path(parts_dir, base_name);
+    if !path.is_file() {
+        return Ok(Vec::new());
+    }
+    let Some(mut integrity) = parse(&path)? else {
+        return Ok(Vec::new());
+    };
+    let paths: Vec<PathBuf> = output_files.iter().map(PathBuf::from).collect();
+    let digests = digest_parts(ctx, integrity.algorithm, &paths)?;
+    let sha256s = part_sha256s(parts_dir, integrity.algorithm, &digests);
+    for digest in digests {
+        match integrity.parts.iter_mut().find(|part| part.name == digest.name) {
+            Some(part) => *part = digest,
+            None => integrity.parts.push(digest),
+        }
+    }
+    save(&path, base_name, &integrity)?;
+    Ok(sha256s)
+}
+
+/// 分片解密后改写清单（参见 [`crate::decrypt`]）：以 `source` 为底写到 `parts_dir`，打包方式与
+/// 分片记录整体替换，描述加密格式的 `container` 字段与签名随之删除，其余字段保留。
+pub(crate) fn record_decrypted(
+    ctx: &JobContext,
+    source: &Integrity,
+    parts_dir: &Path,
+    base_name: &str,
+    pack_mode: &str,
+    output_files: &[String],
+) -> Result<Vec<PartSha256>, String> {
+    let mut object = read_object(&source.path)?;
+    object.remove("container");
+    let paths: Vec<PathBuf> = output_files.iter().map(PathBuf::from).collect();
+    let parts = digest_parts(ctx, source.algorithm, &paths)?;
+    let sha256s = part_sha256s(parts_dir, source.algorithm, &parts);
+    object.insert("packMode".to_string(), Value::from(pack_mode));
+    object.insert(
+        "parts".to_string(),
+        serde_json::to_value(&parts).map_err(|e| e.to_string())?,
+    );
+    write_object(&append::manifest_path(parts_dir, base_name), object)?;
+    Ok(sha256s)
+}
+
+/// 在清单中写入校验之外的说明字段（如加密容器的格式），同名字段被替换。
+pub(crate) fn record_field(
+    parts_dir: &Path,
+    base_name: &str,
+    key: &str,
+    value: Value,
+) -> Result<(), String> {
+    let path = append::manifest_path(parts_dir, base_name);
+    let mut object = read_object(&path)?;
+    object.insert(key.to_string(), value);
+    write_object(&path, object)
+}
+
+/// 追加打包后记录新增或修改的文件条目，同名的记录被替换。分片目录中没有清单时什么也不做。
+pub(crate) fn record_entries(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    base_name: &str,
+    entries: &[(String, PathBuf)],
+) -> Result<(), String> {
+    let path = append::manifest_path(parts_dir, base_name);
+    if !path.is_file() {
+        return Ok(());
+    }
+    let Some(mut integrity) = parse(&path)? else {
+        return Ok(());
+    };
+    for digest in digest_entries(ctx, integrity.algorithm, entries)? {
+        match integrity.entries.iter_mut().find(|entry| entry.name == digest.name) {
+            Some(entry) => *entry = digest,
+            None => integrity.entries.push(digest),
+        }
+    }
+    save(&path, base_name, &integrity)
+}
+
+/// 查找合并输入对应的清单：输入为分片文件时只认列有该文件的清单。没有清单时返回 `None`。
+pub(crate) fn find(input_path: &Path) -> Result<Option<(PathBuf, Integrity)>, String> {
+    let (parts_dir, part_name) = if input_path.is_dir() {
+        (input_path.to_path_buf(), None)
+    } else {
+        match input_path.parent() {
+            Some(dir) => (dir.to_path_buf(), Some(file_name(input_path))),
+            None => return Ok(None),
+        }
+    };
+    let Ok(entries) = fs::read_dir(&parts_dir) else {
+        return Ok(None);
+    };
+    let mut found = Vec::new();
+    for path in entries.flatten().map(|entry| entry.path()) {
+        let is_manifest = path.is_file()
+            && path
+                .file_name()
+                .and_then(|name| name.to_str())
+                .is_some_and(|name| name.ends_with(MANIFEST_SUFFIX));
+        if !is_manifest {
+            continue;
+        }
+        let Some(mut integrity) = parse(&path)? else {
+            continue;
+        };
+        integrity.path = path;
+        if part_name
+            .as_ref()
+            .is_none_or(|name| integrity.parts.iter().any(|part| &part.name == name))
+        {
+            found.push(integrity);
+        }
+    }
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(found.pop().map(|integrity| (parts_dir, integrity))),
+        _ => Err(tr!(MultipleManifests)),
+    }
+}
+
+/// 一份分片的核对结果。
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum PartStatus {
+    /// 与清单一致（快速检查时只比较大小）。
+    Ok,
+    /// 没有清单，只检查了序号。
+    Unchecked,
+    Missing,
+    SizeMismatch,
+    HashMismatch,
+    /// 分片目录中有同组的分片，但清单中没有记录，合并时会被一并合入。
+    Unlisted,
+}
+
+/// 对照清单核对一份分片；`hash` 为 `false` 时只比较大小。
+fn check_part(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    path: &Path,
+    expected: &FileDigest,
+    hash: bool,
+    progress: impl FnMut(u64),
+) -> Result<PartStatus, String> {
+    let Ok(metadata) = fs::metadata(path) else {
+        return Ok(PartStatus::Missing);
+    };
+    if metadata.len() != expected.size {
+        return Ok(PartStatus::SizeMismatch);
+    }
+    if hash && digest_file(ctx, algorithm, path, progress)?.hash != expected.hash {
+        return Ok(PartStatus::HashMismatch);
+    }
+    Ok(PartStatus::Ok)
+}
+
+/// 合并前核对清单中的每一份分片，缺失或大小、校验值不一致时报错，进度阶段为 `verify`。
+pub(crate) fn verify_parts(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    integrity: &Integrity,
+) -> Result<(), String> {
+    let total: u64 = integrity.parts.iter().map(|part| part.size).sum();
+    let count = integrity.parts.len();
+    let mut processed = 0u64;
+    for (idx, expected) in integrity.parts.iter().enumerate() {
+        let path = parts_dir.join(&expected.name);
+        let status = check_part(ctx, integrity.algorithm, &path, expected, true, |delta| {
+            processed += delta;
+            emit_progress(ctx, "verify", processed, total, idx + 1, count, tr!(VerifyingPart, idx + 1));
+        })?;
+        match status {
+            PartStatus::Missing => return Err(tr!(IntegrityPartMissing, expected.name)),
+            PartStatus::Ok => {}
+            _ => return Err(tr!(IntegrityPartMismatch, expected.name)),
+        }
+    }
+    log::info!("已核对 {} 份分片的校验值（{:?}）", count, integrity.algorithm);
+    Ok(())
+}
+
+/// 按清单核对指定的几份分片，返回缺失或与清单不一致的分片名；清单中没有记录的分片不计入。
+pub(crate) fn damaged_parts(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    integrity: &Integrity,
+    names: &[String],
+) -> Result<Vec<String>, String> {
+    let mut damaged = Vec::new();
+    for expected in integrity.parts.iter().filter(|part| names.contains(&part.name)) {
+        let path = parts_dir.join(&expected.name);
+        if check_part(ctx, integrity.algorithm, &path, expected, true, |_| {})? != PartStatus::Ok {
+            damaged.push(expected.name.clone());
+        }
+    }
+    Ok(damaged)
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VerifyOptions {
+    /// 分片文件或分片目录。
+    input_path: String,
+    /// 只检查分片是否齐全与大小，不计算校验值，默认关闭。
+    quick: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PartReport {
+    name: String,
+    status: PartStatus,
+    /// 实际大小，分片不存在时为 `None`。
+    size: Option<u64>,
+    /// 清单中记录的大小。
+    expected_size: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct VerifyReport {
+    parts_dir: String,
+    /// 是否找到完整性清单；没有清单时只检查序号是否连续。
+    has_manifest: bool,
+    algorithm: Option<HashAlgorithm>,
+    /// 是否计算并比较了校验值。
+    hashes_checked: bool,
+    /// 清单中记录的分片数。
+    expected_parts: Option<usize>,
+    found_parts: usize,
+    /// 缺失的分片序号（从 1 开始）。
+    missing_indices: Vec<usize>,
+    parts: Vec<PartReport>,
+    /// 分片齐全且全部与清单一致，可以开始合并。
+    ok: bool,
+}
+
+/// 合并前的预检：检查分片是否齐全、序号是否连续，有清单时逐份比较大小与校验值，
+/// 不合并也不写出任何文件。发现问题时照常返回报告，由 `ok` 与各分片的 `status` 说明。
+pub(crate) fn verify_parts_blocking(
+    ctx: &JobContext,
+    options: VerifyOptions,
+) -> Result<VerifyReport, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let quick = options.quick.unwrap_or(false);
+    let report = match find(&input_path)? {
+        Some((parts_dir, integrity)) => report_with_manifest(ctx, &parts_dir, &integrity, !quick)?,
+        None => report_sequence(ctx, &input_path, !quick)?,
+    };
+    log::info!(
+        "预检分片：{}（{} 份，{}）",
+        report.parts_dir,
+        report.found_parts,
+        if report.ok { "通过" } else { "未通过" }
+    );
+    Ok(report)
+}
+
+fn report_with_manifest(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    integrity: &Integrity,
+    hash: bool,
+) -> Result<VerifyReport, String> {
+    let total: u64 = integrity.parts.iter().map(|part| part.size).sum();
+    let count = integrity.parts.len();
+    let mut processed = 0u64;
+    let mut parts = Vec::with_capacity(count);
+    let mut missing_indices = Vec::new();
+    for (idx, expected) in integrity.parts.iter().enumerate() {
+        let path = parts_dir.join(&expected.name);
+        let status = check_part(ctx, integrity.algorithm, &path, expected, hash, |delta| {
+            processed += delta;
+            emit_progress(ctx, "verify", processed, total, idx + 1, count, tr!(VerifyingPart, idx + 1));
+        })?;
+        if status == PartStatus::Missing {
+            if let Some((_, index, _)) = parse_part_name(&expected.name) {
+                missing_indices.push(index);
+            }
+        }
+        parts.push(PartReport {
+            name: expected.name.clone(),
+            status,
+            size: fs::metadata(&path).ok().map(|meta| meta.len()),
+            expected_size: Some(expected.size),
+        });
+    }
+
+    // 与清单中的分片同组、却不在清单里的文件
+    let groups: HashSet<(String, String)> = integrity
+        .parts
+        .iter()
+        .filter_map(|part| parse_part_name(&part.name))
+        .map(|(prefix, _, suffix)| (prefix, suffix))
+        .collect();
+    let listed: HashSet<&str> = integrity.parts.iter().map(|part| part.name.as_str()).collect();
+    let mut unlisted: Vec<(String, u64)> = fs::read_dir(parts_dir)
+        .map_err(|e| e.to_string())?
+        .flatten()
+        .filter_map(|entry| {
+            let name = entry.file_name().to_string_lossy().to_string();
+            let (prefix, _, suffix) = parse_part_name(&name)?;
+            let size = entry.metadata().ok().filter(|meta| meta.is_file())?.len();
+            (groups.contains(&(prefix, suffix)) && !listed.contains(name.as_str()))
+                .then_some((name, size))
+        })
+        .collect();
+    unlisted.sort();
+    for (name, size) in unlisted {
+        parts.push(PartReport {
+            name,
+            status: PartStatus::Unlisted,
+            size: Some(size),
+            expected_size: None,
+        });
+    }
+
+    missing_indices.sort_unstable();
+    Ok(VerifyReport {
+        parts_dir: parts_dir.to_string_lossy().to_string(),
+        has_manifest: true,
+        algorithm: Some(integrity.algorithm),
+        hashes_checked: hash,
+        expected_parts: Some(count),
+        found_parts: parts.iter().filter(|part| part.size.is_some()).count(),
+        missing_indices,
+        ok: parts.iter().all(|part| part.status == PartStatus::Ok),
+        parts,
+    })
+}
+
+/// 没有清单时检查序号：从第 1 份到最大序号之间不能有缺口。分片 zip 带有分片注释时
+/// （参见 [`crate::part_comment`]）再按注释补上缺少的末尾分片，并核对每份分片的内容。
+fn report_sequence(ctx: &JobContext, input_path: &Path, hash: bool) -> Result<VerifyReport, String> {
+    let group = find_part_group(input_path)?;
+    let parts_dir = group
+        .parts
+        .first()
+        .and_then(|part| part.path.parent())
+        .map(|dir| dir.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let present: HashSet<usize> = group.parts.iter().map(|part| part.index).collect();
+    let mut expected_parts = None;
+    let mut hashes_checked = false;
+    let mut parts = Vec::with_capacity(group.parts.len());
+    for (idx, part) in group.parts.iter().enumerate() {
+        let mut status = PartStatus::Unchecked;
+        if let Some(comment) = part_comment::read(&part.path) {
+            expected_parts = expected_parts.max(Some(comment.total));
+            emit_progress(ctx, "verify", idx as u64, group.parts.len() as u64, idx + 1, group.parts.len(), tr!(VerifyingPart, idx + 1));
+            status = part_comment::verify(ctx, &part.path, &comment, hash)?;
+            hashes_checked |= hash && comment.sha256.is_some();
+        }
+        parts.push(PartReport {
+            name: file_name(&part.path),
+            status,
+            size: fs::metadata(&part.path).ok().map(|meta| meta.len()),
+            expected_size: None,
+        });
+    }
+    let last = group.parts.last().map(|part| part.index).unwrap_or(0).max(expected_parts.unwrap_or(0));
+    let missing_indices: Vec<usize> = (1..=last).filter(|index| !present.contains(index)).collect();
+    Ok(VerifyReport {
+        parts_dir,
+        has_manifest: false,
+        algorithm: hashes_checked.then_some(HashAlgorithm::Sha256),
+        hashes_checked,
+        expected_parts,
+        found_parts: parts.len(),
+        ok: missing_indices.is_empty()
+            && parts
+                .iter()
+                .all(|part| matches!(part.status, PartStatus::Ok | PartStatus::Unchecked)),
+        missing_indices,
+        parts,
+    })
+}
+
+/// 合并后重新计算合并得到的文件（追加打包时为第 1 批的 zip）与解压出的文件的校验值，
+/// 与清单比对，不一致或缺失的路径记入 `mismatches`。清单没有对应记录的部分跳过。
+pub(crate) fn verify_restored(
+    ctx: &JobContext,
+    integrity: &Integrity,
+    result: &mut RestoreResult,
+) -> Result<(), String> {
+    let mut mismatches = Vec::new();
+    if let (Some(expected), Some(merged_file)) = (&integrity.payload, &result.merged_file) {
+        let mut processed = 0u64;
+        let actual = digest_file(ctx, integrity.algorithm, Path::new(merged_file), |delta| {
+            processed += delta;
+            emit_progress(ctx, "verify", processed, expected.size, 0, 0, tr!(VerifyingMerged));
+        })?;
+        if actual.size != expected.size || actual.hash != expected.hash {
+            mismatches.push(merged_file.clone());
+        }
+    }
+    if let Some(dir) = &result.extracted_dir {
+        // 选择性解压时只核对解压出的条目
+        let entries: Vec<&FileDigest> = integrity
+            .entries
+            .iter()
+            .filter(|entry| ctx.extracts(&entry.name))
+            .collect();
+        let total: u64 = entries.iter().map(|entry| entry.size).sum();
+        let count = entries.len();
+        let mut processed = 0u64;
+        for (idx, expected) in entries.into_iter().enumerate() {
+            let path = Path::new(dir).join(&expected.name);
+            let status = check_part(ctx, integrity.algorithm, &path, expected, true, |delta| {
+                processed += delta;
+                emit_progress(ctx, "verify", processed, total, idx + 1, count, tr!(VerifyingExtracted));
+            })?;
+            if status != PartStatus::Ok {
+                mismatches.push(path.to_string_lossy().to_string());
+            }
+        }
+    }
+    if mismatches.is_empty() {
+        log::info!("合并结果与清单一致");
+    } else {
+        log::warn!("合并结果与清单不一致：{}", mismatches.join(", "));
+    }
+    result.verified = Some(mismatches.is_empty());
+    result.mismatches = mismatches;
+    Ok(())
+}
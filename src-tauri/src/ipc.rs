@@ -0,0 +1,293 @@
+//! 本机 IPC 接口（默认关闭）：Unix 上为 Unix 域套接字，Windows 上为命名管道。
+//! 协议为按行分隔的 JSON-RPC 2.0，每行一条消息。
+//!
+//! - `submit`：`{"kind": ..., "options": {...}}`，与 HTTP 接口的 `POST /api/jobs` 相同，返回 `{"jobId"}`
+//! - `list`：任务列表（含历史）
+//! - `status`：`{"jobId"}`，返回任务状态、最新进度与结果
+//! - `cancel`：`{"jobId"}`，取消任务
+//! - `subscribe`：`{"jobId"}`，之后通过 `progress`（`{"jobId", "progress"}`）与
+//!   `finished`（`{"jobId", "job"}`）通知推送该任务的事件
+//!
+//! Unix 套接字文件权限为 0600，仅当前用户可连接。
+
+use interprocess::local_socket::{prelude::*, ListenerOptions, Name, SendHalf, Stream};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use std::{
+    io::{self, BufRead, BufReader, Write},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        mpsc::Receiver,
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+};
+use tauri::AppHandle;
+
+use crate::{
+    i18n::tr,
+    jobs::{self, JobEvent},
+};
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+/// 任务本身出错（参数内容不合法、任务不存在等）。
+const JOB_ERROR: i64 = -32000;
+
+/// 订阅的任务 ID 与事件通道，在 `subscribe` 的响应写出之后才开始转发，保证响应先于通知。
+type Subscription = (String, Receiver<JobEvent>);
+
+struct RunningIpc {
+    name: String,
+    stopped: Arc<AtomicBool>,
+}
+
+fn running() -> &'static Mutex<Option<RunningIpc>> {
+    static RUNNING: OnceLock<Mutex<Option<RunningIpc>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(None))
+}
+
+#[derive(Debug, Deserialize)]
+struct RpcRequest {
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SubmitParams {
+    kind: String,
+    options: Value,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct JobParams {
+    job_id: String,
+}
+
+/// 默认的套接字路径（Unix）或管道名（Windows）。
+pub(crate) fn default_name() -> String {
+    #[cfg(unix)]
+    {
+        let dir = std::env::var_os("XDG_RUNTIME_DIR")
+            .map(std::path::PathBuf::from)
+            .unwrap_or_else(std::env::temp_dir);
+        dir.join("file-split-packer.sock")
+            .to_string_lossy()
+            .to_string()
+    }
+    #[cfg(not(unix))]
+    {
+        "file-split-packer".to_string()
+    }
+}
+
+fn socket_name(name: &str) -> io::Result<Name<'_>> {
+    #[cfg(unix)]
+    {
+        use interprocess::local_socket::GenericFilePath;
+        name.to_fs_name::<GenericFilePath>()
+    }
+    #[cfg(not(unix))]
+    {
+        use interprocess::local_socket::GenericNamespaced;
+        name.to_ns_name::<GenericNamespaced>()
+    }
+}
+
+/// 启动 IPC 接口，返回实际使用的套接字路径或管道名。
+pub(crate) fn start(app: Option<AppHandle>, name: Option<String>) -> Result<String, String> {
+    let mut guard = running().lock().map_err(|_| tr!(IpcStateError))?;
+    if let Some(current) = guard.as_ref() {
+        return Err(tr!(IpcAlreadyRunning, current.name));
+    }
+    let name = name
+        .filter(|value| !value.is_empty())
+        .unwrap_or_else(default_name);
+    let listener = ListenerOptions::new()
+        .name(socket_name(&name).map_err(|e| e.to_string())?)
+        .try_overwrite(true)
+        .create_sync()
+        .map_err(|e| tr!(IpcStartFailed, e))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&name, std::fs::Permissions::from_mode(0o600))
+            .map_err(|e| e.to_string())?;
+    }
+
+    let stopped = Arc::new(AtomicBool::new(false));
+    let flag = stopped.clone();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            if flag.load(Ordering::Relaxed) {
+                break;
+            }
+            if let Ok(stream) = stream {
+                let app = app.clone();
+                thread::spawn(move || handle_connection(app, stream));
+            }
+        }
+    });
+
+    *guard = Some(RunningIpc {
+        name: name.clone(),
+        stopped,
+    });
+    Ok(name)
+}
+
+pub(crate) fn stop() -> Result<(), String> {
+    let current = running()
+        .lock()
+        .map_err(|_| tr!(IpcStateError))?
+        .take()
+        .ok_or_else(|| tr!(IpcNotRunning))?;
+    current.stopped.store(true, Ordering::Relaxed);
+    // 监听线程阻塞在 accept 上，连接一次使其退出
+    if let Ok(name) = socket_name(&current.name) {
+        let _ = Stream::connect(name);
+    }
+    Ok(())
+}
+
+/// 当前运行中的套接字路径或管道名。
+pub(crate) fn status() -> Option<String> {
+    let guard = running().lock().ok()?;
+    guard.as_ref().map(|current| current.name.clone())
+}
+
+fn handle_connection(app: Option<AppHandle>, stream: Stream) {
+    let (recv, send) = stream.split();
+    let writer = Arc::new(Mutex::new(send));
+    let reader = BufReader::new(recv);
+    for line in reader.lines() {
+        let Ok(line) = line else {
+            break;
+        };
+        if line.trim().is_empty() {
+            continue;
+        }
+        let mut subscription = None;
+        if let Some(reply) = handle_line(app.clone(), &line, &mut subscription) {
+            if send_message(&writer, &reply).is_err() {
+                break;
+            }
+        }
+        if let Some((job_id, events)) = subscription {
+            let writer = writer.clone();
+            thread::spawn(move || forward_events(&writer, &job_id, events));
+        }
+    }
+}
+
+/// 处理一行请求，返回需要写回的响应；通知（无 `id`）不回复。
+fn handle_line(
+    app: Option<AppHandle>,
+    line: &str,
+    subscription: &mut Option<Subscription>,
+) -> Option<Value> {
+    let value: Value = match serde_json::from_str(line) {
+        Ok(value) => value,
+        Err(err) => return Some(error_reply(Value::Null, PARSE_ERROR, &err.to_string())),
+    };
+    let request: RpcRequest = match serde_json::from_value(value) {
+        Ok(request) => request,
+        Err(err) => return Some(error_reply(Value::Null, INVALID_REQUEST, &err.to_string())),
+    };
+    let is_notification = request.id.is_null();
+    let outcome = dispatch(app, &request.method, request.params, subscription);
+    if is_notification {
+        return None;
+    }
+    Some(match outcome {
+        Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+        Err((code, message)) => error_reply(request.id, code, &message),
+    })
+}
+
+fn forward_events(writer: &Mutex<SendHalf>, job_id: &str, events: Receiver<JobEvent>) {
+    for event in events {
+        let notification = match event {
+            JobEvent::Progress(payload) => json!({
+                "jsonrpc": "2.0",
+                "method": "progress",
+                "params": { "jobId": job_id, "progress": payload },
+            }),
+            JobEvent::Finished(snapshot) => json!({
+                "jsonrpc": "2.0",
+                "method": "finished",
+                "params": { "jobId": job_id, "job": snapshot },
+            }),
+        };
+        if send_message(writer, &notification).is_err() {
+            break;
+        }
+    }
+}
+
+fn dispatch(
+    app: Option<AppHandle>,
+    method: &str,
+    params: Value,
+    subscription: &mut Option<Subscription>,
+) -> Result<Value, (i64, String)> {
+    match method {
+        "submit" => {
+            let params: SubmitParams = parse_params(params)?;
+            let job =
+                jobs::submit(app, &params.kind, params.options).map_err(|err| (JOB_ERROR, err))?;
+            Ok(json!({ "jobId": job.id() }))
+        }
+        "list" => Ok(json!(jobs::list_jobs())),
+        "status" => {
+            let params: JobParams = parse_params(params)?;
+            let job = find_job(&params.job_id)?;
+            Ok(json!(job.snapshot()))
+        }
+        "cancel" => {
+            let params: JobParams = parse_params(params)?;
+            let job = find_job(&params.job_id)?;
+            job.request_cancel();
+            Ok(json!(job.snapshot()))
+        }
+        "subscribe" => {
+            let params: JobParams = parse_params(params)?;
+            let job = find_job(&params.job_id)?;
+            let result = json!({ "jobId": params.job_id });
+            *subscription = Some((params.job_id, job.subscribe()));
+            Ok(result)
+        }
+        _ => Err((METHOD_NOT_FOUND, tr!(UnknownMethod, method))),
+    }
+}
+
+fn find_job(id: &str) -> Result<Arc<jobs::Job>, (i64, String)> {
+    jobs::find_job(id).ok_or_else(|| (JOB_ERROR, tr!(JobNotFound)))
+}
+
+fn parse_params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, (i64, String)> {
+    serde_json::from_value(params).map_err(|e| (INVALID_PARAMS, tr!(InvalidParams, e)))
+}
+
+fn error_reply(id: Value, code: i64, message: &str) -> Value {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": code, "message": message },
+    })
+}
+
+fn send_message(writer: &Mutex<SendHalf>, message: &Value) -> io::Result<()> {
+    let mut line = message.to_string();
+    line.push('\n');
+    let mut writer = writer.lock().unwrap_or_else(|e| e.into_inner());
+    writer.write_all(line.as_bytes())?;
+    writer.flush()
+}
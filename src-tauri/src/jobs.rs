@@ -0,0 +1,799 @@
+//! 任务上下文与任务记录。进度既通过 `split-progress` 事件发给前端，
+//! 也记录在任务上，供 HTTP 接口等外部调用方查询。
+//!
+//! 界面发起的命令同样登记为任务，可以同时运行多个；进度事件带有 `jobId`，
+//! 任务结束时发送 `job-finished` 事件，前端按任务 ID 分别显示进度。
+//!
+//! 取消是协作式的：`cancel_job` 或外部接口只设置任务的 [`CancelToken`]，复制循环、目录打包
+//! 与并行分片任务在每一步之前检查，随后以取消错误返回，并删除本次已写出的不完整输出。
+
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{HashSet, VecDeque},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, AtomicU64, Ordering},
+        mpsc::{self, Receiver, Sender},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{SystemTime, UNIX_EPOCH},
+};
+use tauri::{AppHandle, Emitter};
+use zip::{AesMode, CompressionMethod};
+
+use crate::{
+    checkpoint::Checkpoint,
+    conflicts::{ConflictDecision, ConflictPolicy, Conflicts},
+    entry_filter::EntryFilter,
+    entry_names::NameEncoding,
+    gaps::{GapPolicy, MissingRange},
+    i18n::tr,
+    memory::MemoryBudget,
+    partial, process_file_blocking, restore_parts_blocking,
+    script::ScriptHooks,
+    secrets, wipe, ExtractEntryOptions, ProgressPayload, RangeRestoreOptions, RestoreOptions,
+    SplitOptions,
+};
+
+/// 保留的历史任务数量上限，超出后丢弃最早完成的任务。
+const HISTORY_LIMIT: usize = 200;
+
+/// 进度回调，供以库的方式调用时接收进度。
+pub(crate) type ProgressCallback = Arc<dyn Fn(&ProgressPayload) + Send + Sync>;
+
+/// 取消标记，在任务记录与其运行上下文之间共享。
+#[derive(Debug, Clone, Default)]
+pub(crate) struct CancelToken(Arc<AtomicBool>);
+
+impl CancelToken {
+    pub(crate) fn cancel(&self) {
+        self.0.store(true, Ordering::Relaxed);
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.0.load(Ordering::Relaxed)
+    }
+
+    /// 已请求取消时返回取消错误。
+    pub(crate) fn check(&self) -> Result<(), String> {
+        if self.is_cancelled() {
+            Err(tr!(JobCancelled))
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// 新建的输出按记录顺序保存，另用集合去重，分片上万份时记录仍是常数时间。
+#[derive(Default)]
+struct TrackedOutputs {
+    order: Vec<PathBuf>,
+    seen: HashSet<PathBuf>,
+}
+
+/// 一次操作的运行上下文：进度发往哪里、所属的任务、用户脚本钩子、内存上限与取消标记。
+#[derive(Clone)]
+pub(crate) struct JobContext {
+    app: Option<AppHandle>,
+    job: Option<Arc<Job>>,
+    script: Option<Arc<ScriptHooks>>,
+    memory: MemoryBudget,
+    progress: Option<ProgressCallback>,
+    cancel: CancelToken,
+    /// 本次操作新建的文件与目录，取消后删除。
+    outputs: Arc<Mutex<TrackedOutputs>>,
+    /// 打包或合并参数指纹，设置时打包在分片目录中写检查点、合并时记录合并进度，并尝试续传。
+    resume: Option<Arc<str>>,
+    checkpoint: Option<Arc<Checkpoint>>,
+    /// 新建的工作线程是否降低 CPU 与 I/O 优先级。
+    background_priority: bool,
+    /// 尽力解压时记录跳过的条目；未设置时遇到损坏的条目直接出错。
+    lost: Option<Arc<Mutex<Vec<String>>>>,
+    /// 需要压缩的 zip 条目使用的压缩方法，默认 Deflate。
+    compression: CompressionMethod,
+    /// 设置密码时 zip 条目的 AES 强度，默认 AES-256。
+    aes_mode: AesMode,
+    /// 中间文件与取消后的输出先覆写再删除。
+    secure_delete: bool,
+    /// 选择性解压时只解压匹配的条目；未设置时解压全部条目。
+    entry_filter: Option<Arc<EntryFilter>>,
+    /// 缺片合并时如何处理缺失的分片，及记录下的缺失范围；未设置时缺少分片直接出错。
+    gaps: Option<(GapPolicy, Arc<Mutex<Vec<MissingRange>>>)>,
+    /// 解压时如何处理已有的同名文件；未设置时直接覆盖。
+    conflicts: Option<Arc<Conflicts>>,
+    /// 解压 zip 时非 UTF-8 条目名的代码页；未设置时按 CP437 解读。
+    name_encoding: Option<NameEncoding>,
+    /// 合并时使用的密钥文件，询问到的新密码同样要拼上它的校验值。
+    keyfile: Option<Arc<str>>,
+}
+
+impl JobContext {
+    /// 不汇报进度的上下文，用于命令行等没有前端的场景。
+    pub(crate) fn detached() -> Self {
+        JobContext {
+            app: None,
+            job: None,
+            script: None,
+            memory: MemoryBudget::default(),
+            progress: None,
+            cancel: CancelToken::default(),
+            outputs: Arc::default(),
+            resume: None,
+            checkpoint: None,
+            background_priority: false,
+            lost: None,
+            compression: CompressionMethod::Deflated,
+            aes_mode: AesMode::Aes256,
+            secure_delete: false,
+            entry_filter: None,
+            gaps: None,
+            conflicts: None,
+            name_encoding: None,
+            keyfile: None,
+        }
+    }
+
+    pub(crate) fn for_job(app: Option<AppHandle>, job: Arc<Job>) -> Self {
+        JobContext {
+            app,
+            cancel: job.cancel.clone(),
+            job: Some(job),
+            script: None,
+            memory: MemoryBudget::default(),
+            progress: None,
+            outputs: Arc::default(),
+            resume: None,
+            checkpoint: None,
+            background_priority: false,
+            lost: None,
+            compression: CompressionMethod::Deflated,
+            aes_mode: AesMode::Aes256,
+            secure_delete: false,
+            entry_filter: None,
+            gaps: None,
+            conflicts: None,
+            name_encoding: None,
+            keyfile: None,
+        }
+    }
+
+    /// 在界面中运行时的应用句柄。
+    pub(crate) fn app(&self) -> Option<&AppHandle> {
+        self.app.as_ref()
+    }
+
+    /// 所属任务的 ID；不属于任务时为 `None`。
+    pub(crate) fn job_id(&self) -> Option<&str> {
+        self.job.as_ref().map(|job| job.id())
+    }
+
+    pub(crate) fn with_script(&self, script: ScriptHooks) -> Self {
+        JobContext {
+            script: Some(Arc::new(script)),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn script(&self) -> Option<&ScriptHooks> {
+        self.script.as_deref()
+    }
+
+    pub(crate) fn with_memory(&self, memory: MemoryBudget) -> Self {
+        JobContext {
+            memory,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn memory(&self) -> MemoryBudget {
+        self.memory
+    }
+
+    pub(crate) fn with_progress(&self, progress: ProgressCallback) -> Self {
+        JobContext {
+            progress: Some(progress),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn with_resume(&self, fingerprint: String) -> Self {
+        JobContext {
+            resume: Some(fingerprint.into()),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn resume_fingerprint(&self) -> Option<&str> {
+        self.resume.as_deref()
+    }
+
+    pub(crate) fn with_checkpoint(&self, checkpoint: Checkpoint) -> Self {
+        JobContext {
+            checkpoint: Some(Arc::new(checkpoint)),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn checkpoint(&self) -> Option<&Checkpoint> {
+        self.checkpoint.as_deref()
+    }
+
+    pub(crate) fn with_background_priority(&self) -> Self {
+        JobContext {
+            background_priority: true,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn background_priority(&self) -> bool {
+        self.background_priority
+    }
+
+    /// 尽力解压：跳过读不出或损坏的条目继续解压，用 [`Self::record_lost`] 记录跳过的条目。
+    pub(crate) fn with_salvage(&self) -> Self {
+        JobContext {
+            lost: Some(Arc::default()),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn salvage(&self) -> bool {
+        self.lost.is_some()
+    }
+
+    pub(crate) fn record_lost(&self, entry: String) {
+        if let Some(lost) = &self.lost {
+            lost.lock().unwrap_or_else(|e| e.into_inner()).push(entry);
+        }
+    }
+
+    pub(crate) fn take_lost(&self) -> Vec<String> {
+        self.lost
+            .as_ref()
+            .map(|lost| std::mem::take(&mut *lost.lock().unwrap_or_else(|e| e.into_inner())))
+            .unwrap_or_default()
+    }
+
+    /// 缺片合并：缺失的分片按 `policy` 补零或截断，用 [`Self::record_missing`] 记录缺失范围。
+    pub(crate) fn with_gaps(&self, policy: GapPolicy) -> Self {
+        JobContext {
+            gaps: Some((policy, Arc::default())),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn gap_policy(&self) -> Option<GapPolicy> {
+        self.gaps.as_ref().map(|(policy, _)| *policy)
+    }
+
+    pub(crate) fn record_missing(&self, range: MissingRange) {
+        if let Some((_, missing)) = &self.gaps {
+            missing
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(range);
+        }
+    }
+
+    pub(crate) fn take_missing(&self) -> Vec<MissingRange> {
+        self.gaps
+            .as_ref()
+            .map(|(_, missing)| {
+                std::mem::take(&mut *missing.lock().unwrap_or_else(|e| e.into_inner()))
+            })
+            .unwrap_or_default()
+    }
+
+    /// 解压时已有同名文件按 `policy` 处理，参见 [`crate::conflicts`]。
+    pub(crate) fn with_conflicts(&self, policy: ConflictPolicy) -> Self {
+        JobContext {
+            conflicts: Some(Arc::new(Conflicts::new(policy))),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn conflicts(&self) -> Option<&Conflicts> {
+        self.conflicts.as_deref()
+    }
+
+    pub(crate) fn take_conflicts(&self) -> Vec<ConflictDecision> {
+        self.conflicts
+            .as_ref()
+            .map(|conflicts| conflicts.take_decisions())
+            .unwrap_or_default()
+    }
+
+    /// 解压 zip 时按 `encoding` 解读非 UTF-8 的条目名，参见 [`crate::entry_names`]。
+    pub(crate) fn with_name_encoding(&self, encoding: NameEncoding) -> Self {
+        JobContext {
+            name_encoding: Some(encoding),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn name_encoding(&self) -> Option<NameEncoding> {
+        self.name_encoding
+    }
+
+    /// 合并参数中的密钥文件，参见 [`crate::keyfile`]。
+    pub(crate) fn with_keyfile(&self, keyfile: &str) -> Self {
+        JobContext {
+            keyfile: Some(Arc::from(keyfile)),
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn keyfile(&self) -> Option<&str> {
+        self.keyfile.as_deref()
+    }
+
+    pub(crate) fn with_compression(&self, compression: CompressionMethod) -> Self {
+        JobContext {
+            compression,
+            ..self.clone()
+        }
+    }
+
+    /// 需要压缩的 zip 条目使用的压缩方法；仅存储的条目不受影响。
+    pub(crate) fn compression(&self) -> CompressionMethod {
+        self.compression
+    }
+
+    pub(crate) fn with_aes_mode(&self, aes_mode: AesMode) -> Self {
+        JobContext {
+            aes_mode,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn aes_mode(&self) -> AesMode {
+        self.aes_mode
+    }
+
+    pub(crate) fn with_secure_delete(&self) -> Self {
+        JobContext {
+            secure_delete: true,
+            ..self.clone()
+        }
+    }
+
+    pub(crate) fn secure_delete(&self) -> bool {
+        self.secure_delete
+    }
+
+    pub(crate) fn with_entry_filter(&self, filter: EntryFilter) -> Self {
+        JobContext {
+            entry_filter: Some(Arc::new(filter)),
+            ..self.clone()
+        }
+    }
+
+    /// 解压时是否需要条目 `name`，参见 [`crate::entry_filter`]。
+    pub(crate) fn extracts(&self, name: &str) -> bool {
+        match &self.entry_filter {
+            Some(filter) => filter.matches(name),
+            None => true,
+        }
+    }
+
+    /// 是否只解压部分条目。
+    pub(crate) fn filters_entries(&self) -> bool {
+        self.entry_filter.is_some()
+    }
+
+    pub(crate) fn cancel_token(&self) -> &CancelToken {
+        &self.cancel
+    }
+
+    pub(crate) fn is_cancelled(&self) -> bool {
+        self.cancel.is_cancelled()
+    }
+
+    pub(crate) fn check_cancelled(&self) -> Result<(), String> {
+        self.cancel.check()
+    }
+
+    /// 记录本次操作新建的文件或目录。已存在的用户目录不要记录，取消时会整个删除。
+    pub(crate) fn track_output(&self, path: &Path) {
+        let mut outputs = self.outputs.lock().unwrap_or_else(|e| e.into_inner());
+        if outputs.seen.insert(path.to_path_buf()) {
+            outputs.order.push(path.to_path_buf());
+        }
+    }
+
+    /// 不再记录已有的输出：已完成的部分此后不随取消删除。
+    pub(crate) fn forget_outputs(&self) {
+        *self.outputs.lock().unwrap_or_else(|e| e.into_inner()) = Default::default();
+    }
+
+    /// 取消后删除已记录的输出，后记录的先删除；开启安全删除时先覆写。
+    pub(crate) fn remove_outputs(&self) {
+        let outputs = std::mem::take(&mut *self.outputs.lock().unwrap_or_else(|e| e.into_inner()));
+        for path in outputs.order.iter().rev() {
+            let removed = if self.secure_delete && path.exists() {
+                wipe::wipe_path(self, path, tr!(WipingTemp))
+            } else if path.is_dir() {
+                fs::remove_dir_all(path).map_err(|e| e.to_string())
+            } else {
+                fs::remove_file(path).map_err(|e| e.to_string())
+            };
+            match removed {
+                Ok(()) => log::info!("已删除未完成的输出：{}", path.display()),
+                Err(err) if path.exists() => {
+                    log::warn!("删除未完成的输出失败：{}（{}）", path.display(), err)
+                }
+                Err(_) => {}
+            }
+        }
+    }
+
+    pub(crate) fn report(&self, mut payload: ProgressPayload) {
+        if let Some(job) = &self.job {
+            payload.job_id = Some(job.id.clone());
+            job.record_progress(payload.clone());
+        }
+        if let Some(progress) = &self.progress {
+            progress(&payload);
+        }
+        if let Some(app) = &self.app {
+            let _ = app.emit("split-progress", payload);
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum JobState {
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobSnapshot {
+    pub(crate) id: String,
+    pub(crate) kind: String,
+    pub(crate) state: JobState,
+    pub(crate) cancel_requested: bool,
+    pub(crate) progress: Option<ProgressPayload>,
+    pub(crate) result: Option<Value>,
+    pub(crate) error: Option<String>,
+    pub(crate) created_at: u64,
+    pub(crate) finished_at: Option<u64>,
+}
+
+/// 任务的当前状态与进度，展开为平铺的字段，供界面轮询。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct JobStatus {
+    pub(crate) job_id: String,
+    pub(crate) kind: String,
+    pub(crate) state: JobState,
+    /// 尚未汇报进度时为 `None`，下同。
+    pub(crate) phase: Option<String>,
+    pub(crate) processed_bytes: u64,
+    pub(crate) total_bytes: Option<u64>,
+    pub(crate) part_index: usize,
+    pub(crate) part_total: usize,
+    pub(crate) message: Option<String>,
+    pub(crate) error: Option<String>,
+}
+
+/// 推送给订阅方（如 SSE 连接）的任务事件。
+#[derive(Debug, Clone)]
+pub(crate) enum JobEvent {
+    Progress(ProgressPayload),
+    Finished(JobSnapshot),
+}
+
+pub(crate) struct Job {
+    id: String,
+    kind: String,
+    created_at: u64,
+    cancel: CancelToken,
+    record: Mutex<JobRecord>,
+    subscribers: Mutex<Vec<Sender<JobEvent>>>,
+}
+
+struct JobRecord {
+    state: JobState,
+    progress: Option<ProgressPayload>,
+    result: Option<Value>,
+    error: Option<String>,
+    finished_at: Option<u64>,
+}
+
+impl Job {
+    pub(crate) fn id(&self) -> &str {
+        &self.id
+    }
+
+    pub(crate) fn snapshot(&self) -> JobSnapshot {
+        let record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+        JobSnapshot {
+            id: self.id.clone(),
+            kind: self.kind.clone(),
+            state: record.state,
+            cancel_requested: self.cancel.is_cancelled(),
+            progress: record.progress.clone(),
+            result: record.result.clone(),
+            error: record.error.clone(),
+            created_at: self.created_at,
+            finished_at: record.finished_at,
+        }
+    }
+
+    pub(crate) fn status(&self) -> JobStatus {
+        let record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+        let progress = record.progress.as_ref();
+        JobStatus {
+            job_id: self.id.clone(),
+            kind: self.kind.clone(),
+            state: record.state,
+            phase: progress.map(|progress| progress.phase.clone()),
+            processed_bytes: progress.map_or(0, |progress| progress.processed_bytes),
+            total_bytes: progress.and_then(|progress| progress.total_bytes),
+            part_index: progress.map_or(0, |progress| progress.part_index),
+            part_total: progress.map_or(0, |progress| progress.part_total),
+            message: progress.map(|progress| progress.message.clone()),
+            error: record.error.clone(),
+        }
+    }
+
+    pub(crate) fn request_cancel(&self) {
+        self.cancel.cancel();
+    }
+
+    /// 订阅任务事件；任务结束后发送 `Finished` 并关闭通道。
+    pub(crate) fn subscribe(&self) -> Receiver<JobEvent> {
+        let (sender, receiver) = mpsc::channel();
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        if self.is_finished() {
+            let _ = sender.send(JobEvent::Finished(self.snapshot()));
+        } else {
+            if let Some(progress) = self.snapshot().progress {
+                let _ = sender.send(JobEvent::Progress(progress));
+            }
+            subscribers.push(sender);
+        }
+        receiver
+    }
+
+    fn record_progress(&self, payload: ProgressPayload) {
+        {
+            let mut record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+            record.progress = Some(payload.clone());
+        }
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        subscribers.retain(|sender| sender.send(JobEvent::Progress(payload.clone())).is_ok());
+    }
+
+    fn finish(&self, outcome: Result<Value, String>) {
+        self.update_finished(outcome);
+        let snapshot = self.snapshot();
+        let mut subscribers = self.subscribers.lock().unwrap_or_else(|e| e.into_inner());
+        for sender in subscribers.drain(..) {
+            let _ = sender.send(JobEvent::Finished(snapshot.clone()));
+        }
+    }
+
+    fn update_finished(&self, outcome: Result<Value, String>) {
+        let mut record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+        match outcome {
+            Ok(value) => {
+                record.state = JobState::Succeeded;
+                record.result = Some(value);
+            }
+            Err(err) => {
+                record.state = if self.cancel.is_cancelled() {
+                    JobState::Cancelled
+                } else {
+                    JobState::Failed
+                };
+                record.error = Some(err);
+            }
+        }
+        record.finished_at = Some(now_millis());
+    }
+
+    fn is_finished(&self) -> bool {
+        let record = self.record.lock().unwrap_or_else(|e| e.into_inner());
+        record.state != JobState::Running
+    }
+}
+
+fn jobs() -> &'static Mutex<VecDeque<Arc<Job>>> {
+    static JOBS: OnceLock<Mutex<VecDeque<Arc<Job>>>> = OnceLock::new();
+    JOBS.get_or_init(|| Mutex::new(VecDeque::new()))
+}
+
+pub(crate) fn now_millis() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0)
+}
+
+pub(crate) fn next_job_id() -> String {
+    static COUNTER: AtomicU64 = AtomicU64::new(0);
+    let seq = COUNTER.fetch_add(1, Ordering::Relaxed) + 1;
+    format!("{:x}-{}", now_millis(), seq)
+}
+
+/// 新建一条运行中的任务记录并加入任务列表。
+fn register_job(kind: &str) -> Arc<Job> {
+    let job = Arc::new(Job {
+        id: next_job_id(),
+        kind: kind.to_string(),
+        created_at: now_millis(),
+        cancel: CancelToken::default(),
+        record: Mutex::new(JobRecord {
+            state: JobState::Running,
+            progress: None,
+            result: None,
+            error: None,
+            finished_at: None,
+        }),
+        subscribers: Mutex::new(Vec::new()),
+    });
+    {
+        let mut list = jobs().lock().unwrap_or_else(|e| e.into_inner());
+        list.push_back(job.clone());
+        while list.len() > HISTORY_LIMIT {
+            match list.iter().position(|job| job.is_finished()) {
+                Some(pos) => {
+                    list.remove(pos);
+                }
+                None => break,
+            }
+        }
+    }
+    job
+}
+
+/// 在后台线程中运行任务，立即返回任务记录。
+pub(crate) fn spawn_job<T, F>(app: Option<AppHandle>, kind: &str, run: F) -> Arc<Job>
+where
+    T: Serialize,
+    F: FnOnce(&JobContext) -> Result<T, String> + Send + 'static,
+{
+    let job = register_job(kind);
+    let ctx = JobContext::for_job(app.clone(), job.clone());
+    let worker = job.clone();
+    thread::spawn(move || {
+        let outcome = run(&ctx)
+            .and_then(|value| serde_json::to_value(value).map_err(|e| e.to_string()));
+        worker.finish(outcome);
+        emit_finished(app.as_ref(), &worker);
+    });
+    job
+}
+
+/// 在当前线程运行任务并返回结果，任务同样记录在任务列表中，可以通过 `cancel_job` 取消。
+/// 供界面直接调用的 `process_file`、`restore_parts` 等命令使用。
+pub(crate) fn run_job<T, F>(app: Option<AppHandle>, kind: &str, run: F) -> Result<T, String>
+where
+    T: Serialize,
+    F: FnOnce(&JobContext) -> Result<T, String>,
+{
+    let job = register_job(kind);
+    let ctx = JobContext::for_job(app.clone(), job.clone());
+    let result = run(&ctx);
+    job.finish(match &result {
+        Ok(value) => serde_json::to_value(value).map_err(|e| e.to_string()),
+        Err(err) => Err(err.clone()),
+    });
+    emit_finished(app.as_ref(), &job);
+    result
+}
+
+/// 任务结束时发送 `job-finished` 事件（任务快照），界面据此移除该任务的进度。
+fn emit_finished(app: Option<&AppHandle>, job: &Job) {
+    if let Some(app) = app {
+        let _ = app.emit("job-finished", job.snapshot());
+    }
+}
+
+/// 已解析参数、尚未开始运行的任务，供任务队列先校验参数、稍后再运行（失败重试时再次运行）。
+#[derive(Debug, Clone)]
+pub(crate) enum JobRequest {
+    Pack(Box<SplitOptions>),
+    Restore(Box<RestoreOptions>),
+    RestoreRange(RangeRestoreOptions),
+    ExtractEntry(ExtractEntryOptions),
+}
+
+impl JobRequest {
+    /// `kind` 为 `pack`、`restore`、`restoreRange` 或 `extractEntry`，`options` 与对应命令的参数一致。
+    pub(crate) fn parse(kind: &str, options: Value) -> Result<Self, String> {
+        match kind {
+            // 队列中的任务失败后会重试，登记的密码在提交时就取出
+            "pack" => {
+                let mut options: Box<SplitOptions> = parse_options(options)?;
+                secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+                Ok(JobRequest::Pack(options))
+            }
+            "restore" => {
+                let mut options: Box<RestoreOptions> = parse_options(options)?;
+                secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+                Ok(JobRequest::Restore(options))
+            }
+            "restoreRange" => {
+                let mut options: RangeRestoreOptions = parse_options(options)?;
+                secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+                Ok(JobRequest::RestoreRange(options))
+            }
+            "extractEntry" => {
+                let mut options: ExtractEntryOptions = parse_options(options)?;
+                secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+                Ok(JobRequest::ExtractEntry(options))
+            }
+            _ => Err(tr!(UnknownJobKind)),
+        }
+    }
+
+    pub(crate) fn kind(&self) -> &'static str {
+        match self {
+            JobRequest::Pack(_) => "pack",
+            JobRequest::Restore(_) => "restore",
+            JobRequest::RestoreRange(_) => "restoreRange",
+            JobRequest::ExtractEntry(_) => "extractEntry",
+        }
+    }
+
+    /// 在后台线程中开始运行。
+    pub(crate) fn spawn(self, app: Option<AppHandle>) -> Arc<Job> {
+        let kind = self.kind();
+        match self {
+            JobRequest::Pack(options) => {
+                spawn_job(app, kind, move |ctx| process_file_blocking(ctx, *options))
+            }
+            JobRequest::Restore(options) => {
+                spawn_job(app, kind, move |ctx| restore_parts_blocking(ctx, *options))
+            }
+            JobRequest::RestoreRange(options) => spawn_job(app, kind, move |ctx| {
+                partial::restore_range_blocking(ctx, options)
+            }),
+            JobRequest::ExtractEntry(options) => spawn_job(app, kind, move |ctx| {
+                partial::extract_entry_blocking(ctx, options)
+            }),
+        }
+    }
+}
+
+/// 按任务类型提交任务，供 HTTP 接口与 IPC 接口共用。
+pub(crate) fn submit(app: Option<AppHandle>, kind: &str, options: Value) -> Result<Arc<Job>, String> {
+    Ok(JobRequest::parse(kind, options)?.spawn(app))
+}
+
+fn parse_options<T: for<'de> Deserialize<'de>>(value: Value) -> Result<T, String> {
+    serde_json::from_value(value).map_err(|e| tr!(InvalidJobOptions, e))
+}
+
+pub(crate) fn find_job(id: &str) -> Option<Arc<Job>> {
+    let list = jobs().lock().unwrap_or_else(|e| e.into_inner());
+    list.iter().find(|job| job.id == id).cloned()
+}
+
+/// 取消所有运行中的任务，返回被取消的任务 ID。
+pub(crate) fn cancel_running() -> Vec<String> {
+    let list = jobs().lock().unwrap_or_else(|e| e.into_inner());
+    list.iter()
+        .filter(|job| !job.is_finished())
+        .map(|job| {
+            job.request_cancel();
+            job.id.clone()
+        })
+        .collect()
+}
+
+/// 所有任务（含历史），最新的在前。
+pub(crate) fn list_jobs() -> Vec<JobSnapshot> {
+    let list = jobs().lock().unwrap_or_else(|e| e.into_inner());
+    list.iter().rev().map(|job| job.snapshot()).collect()
+}
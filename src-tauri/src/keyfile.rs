@@ -0,0 +1,54 @@
+//! 密钥文件：打包、合并与测试时可以选一个文件代替或补充密码，适合无人值守的脚本与自动传输。
+//!
+//! 实际使用的密码为 `<密码><密钥文件的 SHA-256（小写十六进制）>`，只选密钥文件时即为其 SHA-256 本身。
+//! 用 7-Zip 等其他软件解压时输入同样拼接的密码即可，不依赖本程序。
+
+use sha2::{Digest, Sha256};
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+use zeroize::Zeroizing;
+
+use crate::{i18n::tr, secrets::SecretString};
+
+/// 由密码与密钥文件得出实际使用的密码；两者都未设置时返回 `None`。
+pub(crate) fn effective_password(
+    password: Option<&str>,
+    keyfile: Option<&str>,
+) -> Result<Option<SecretString>, String> {
+    let password = password.filter(|value| !value.is_empty());
+    let Some(keyfile) = keyfile.filter(|value| !value.is_empty()) else {
+        return Ok(password.map(SecretString::from));
+    };
+    let digest = Zeroizing::new(digest(Path::new(keyfile))?);
+    Ok(Some(SecretString::from(format!(
+        "{}{}",
+        password.unwrap_or_default(),
+        digest.as_str()
+    ))))
+}
+
+fn digest(path: &Path) -> Result<String, String> {
+    let mut file = File::open(path).map_err(|e| tr!(KeyfileReadFailed, path.display(), e))?;
+    let mut hasher = Sha256::new();
+    let mut buffer = vec![0u8; 64 * 1024];
+    let mut total = 0u64;
+    loop {
+        let read_len = match file.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(tr!(KeyfileReadFailed, path.display(), err)),
+        };
+        hasher.update(&buffer[..read_len]);
+        total += read_len as u64;
+    }
+    // 空文件的校验值人人皆知，等于没有密钥
+    if total == 0 {
+        return Err(tr!(KeyfileEmpty, path.display()));
+    }
+    Ok(format!("{:x}", hasher.finalize()))
+}
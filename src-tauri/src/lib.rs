@@ -1,8 +1,8 @@
 use serde::{Deserialize, Serialize};
-use sha2::{Digest, Sha256};
 use std::{
     cmp,
     collections::HashMap,
+    env,
     fs::{self, File},
     io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
     path::{Path, PathBuf},
@@ -11,13 +11,103 @@ use std::{
         Arc, Mutex,
     },
     thread,
-    time::{Duration, Instant},
+    time::{Duration, Instant, SystemTime},
+};
+use i18n::{tr, Msg};
+use jobs::{CancelToken, JobContext};
+use logging::{IoTimer, PartTiming, TimedRead, TimedWrite};
+use memory::MemoryBudget;
+use password_prompt::PasswordError;
+use secrets::SecretString;
+use tauri::{
+    ipc::{Channel, InvokeResponseBody},
+    AppHandle, Manager,
 };
-use tauri::{AppHandle, Emitter};
 use rayon::prelude::*;
-use zip::{result::ZipError, write::FileOptions, AesMode, CompressionMethod, ZipArchive, ZipWriter};
+use zip::{result::ZipError, write::FullFileOptions, AesMode, CompressionMethod, ZipArchive, ZipWriter};
+use zeroize::Zeroizing;
 
-#[derive(Debug, Deserialize)]
+mod age_split;
+mod api;
+mod append;
+mod batch;
+mod builder;
+mod checkpoint;
+mod cli;
+mod conflicts;
+mod container;
+mod contents;
+mod decrypt;
+mod detect;
+mod diagnostics;
+mod dictionary;
+mod download;
+mod entry_filter;
+mod entry_names;
+mod entry_times;
+mod erasure;
+mod estimate;
+mod extract;
+mod filter;
+mod gaps;
+mod gpg;
+mod headless;
+mod hidden_names;
+mod i18n;
+mod index;
+mod integrity;
+mod ipc;
+mod jobs;
+mod keyfile;
+mod logging;
+mod memory;
+mod merge_resume;
+#[cfg(all(feature = "mount", unix))]
+mod mount;
+mod parity;
+mod part_comment;
+mod partial;
+mod password_prompt;
+mod password_strength;
+mod part_reader;
+mod plugins;
+mod power;
+mod priority;
+mod prompts;
+mod queue;
+mod raw;
+mod reencrypt;
+mod rejoin;
+mod rar;
+mod repair;
+mod salvage;
+mod script;
+mod secrets;
+mod seekable;
+mod sevenz;
+mod sfx;
+mod signing;
+mod source;
+mod spanned;
+mod split_verify;
+mod stream_split;
+mod tar;
+mod test_archive;
+mod tuning;
+mod validation;
+mod watch;
+mod wipe;
+
+pub use builder::{
+    AesStrength, DirSplitMode, HashAlgorithm, PackMode, Progress, SplitBy, SplitJob,
+    SplitJobBuilder, SplitOutput, ZipCompression,
+};
+
+const DEFAULT_API_PORT: u16 = 38517;
+/// 合并前试解密的字节数，参见 [`check_part_password`]。
+const PASSWORD_PROBE_SIZE: u64 = 64 * 1024;
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SplitOptions {
     input_path: String,
@@ -26,10 +116,77 @@ struct SplitOptions {
     size_bytes: Option<u64>,
     count: Option<u64>,
     pack_mode: String,
-    password: Option<String>,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
     dir_split_mode: Option<String>,
     overwrite_parts: Option<bool>,
     compression_level: Option<i64>,
+    /// 分割完成后依次运行的后处理插件名称。
+    post_processors: Option<Vec<String>>,
+    /// rhai 脚本路径，可自定义输出名称、过滤目录条目、处理每份分片。
+    script_path: Option<String>,
+    /// 打包后逐份转换分片内容的外部命令（标准输入 → 标准输出）。
+    filter_command: Option<String>,
+    /// 打包后把每份分片原地加密给这些 GPG 公钥（指纹、密钥 ID 或邮箱），未设置时不加密。
+    gpg_recipients: Option<Vec<String>>,
+    /// 缓冲区与并发线程的内存上限（字节），未设置时不限制。
+    max_memory: Option<u64>,
+    /// 按实测的源与目标读写速度选择缓冲区大小与并发数，默认开启。
+    adaptive_io: Option<bool>,
+    /// 分片目录中有相同参数留下的检查点时从中断处继续，默认开启。
+    resume: Option<bool>,
+    /// 成功完成后的电源操作：`none`（默认）、`shutdown`、`sleep` 或 `hibernate`。
+    power_action: Option<String>,
+    /// 以较低的 CPU 与磁盘 I/O 优先级运行，默认关闭。
+    background_priority: Option<bool>,
+    /// 完整性清单的校验算法：`sha256`（默认）或 `blake3`。
+    hash_algorithm: Option<String>,
+    /// 生成 PAR2 恢复文件，值为恢复数据占分片的百分比（1–100），未设置或为 0 时不生成。
+    par2_redundancy: Option<u32>,
+    /// 另外生成的恢复分片份数，任意不超过该份数的分片缺失或损坏时可在合并前重建，默认不生成。
+    redundancy_parts: Option<u32>,
+    /// 另外生成一份异或校验分片 `<名称>.parity`，可重建一份缺失或损坏的分片，默认关闭。
+    xor_parity: Option<bool>,
+    /// Ed25519 私钥文件，设置后对完整性清单签名（仅 `split-then-zip` 与 `zip-then-split`）。
+    signing_key: Option<String>,
+    /// 分割完成后重新读取每份分片，解密、解压后与源比对，不一致时报错，默认关闭。
+    verify_after_split: Option<bool>,
+    /// 打包全部完成、分片核对通过后覆写并删除源文件（目录则为其中全部文件与目录本身），默认关闭；
+    /// 开启时总会做分割后核对。
+    destroy_source: Option<bool>,
+    /// 中间文件（先压缩再分割的 zip、先打包的 tar 等）与取消后的输出先覆写再删除，默认关闭。
+    /// 加密打包时生成的明文中间文件总会覆写。
+    secure_delete: Option<bool>,
+    /// `7z-then-split` 的分片按 7-Zip 分卷的习惯命名为 `<名称>.7z.001`，可直接用 7-Zip 打开，默认关闭。
+    seven_zip_volumes: Option<bool>,
+    /// `container-then-split` 的每份分片各自以按序号派生的子密钥加密为独立的容器，可以单独解密与核对，
+    /// 默认关闭（整个容器加密后再切分）。
+    per_part_keys: Option<bool>,
+    /// 按字节切分的分片改用经典分卷命名 `<名称>.001`、`<名称>.002`……，HJSplit、FFSJ 与 7-Zip 都能
+    /// 直接合并；不用于 `split-then-zip` 与 `spanned-zip`，默认关闭。
+    numbered_volumes: Option<bool>,
+    /// 在分片目录中放入自解压程序 `restore` 与合并设置，接收方无需安装本程序即可合并，默认关闭。
+    self_extracting: Option<bool>,
+    /// 在分片目录中写出只用系统自带工具拼接分片的 `rejoin.bat` 与 `rejoin.sh`；不用于 `split-then-zip`
+    /// 与 `spanned-zip`，默认关闭。
+    rejoin_scripts: Option<bool>,
+    /// `zstd-then-split` 打包目录时从中抽样训练 zstd 字典并用它压缩，大量相似小文件时压缩率明显更高；
+    /// 字典写入分片目录，默认关闭。
+    zstd_dictionary: Option<bool>,
+    /// 设置密码时 zip 条目的 AES 强度：`128`、`192` 或 `256`（默认），只用于 `split-then-zip`、
+    /// `zip-then-split` 与 `spanned-zip`。AES-128 在很大的压缩包上明显更快。
+    aes_strength: Option<u16>,
+    /// 设置密码时连同文件名与目录结构一起加密：`zip-then-split` 把整个 zip 作为唯一的加密条目
+    /// 再包一层，`7z-then-split` 本就加密文件头。默认关闭。
+    encrypt_names: Option<bool>,
+    /// 密钥文件，其 SHA-256 接在密码之后作为实际密码；未设置密码时单独作为密码。
+    keyfile: Option<String>,
+    /// `age-split` 的 X25519 接收方：`age1…` 公钥或每行一个公钥的文件；未设置时以密码为口令加密。
+    age_recipients: Option<Vec<String>>,
+    /// zip 条目的压缩方法：`deflate`（默认）、`bzip2` 或 `xz`，只用于 `split-then-zip` 与 `zip-then-split`
+    /// 中需要压缩的条目，仅存储的条目不变。
+    compression_method: Option<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -40,6 +197,10 @@ struct SplitResult {
     is_dir: bool,
     base_name: String,
     part_sha256s: Vec<PartSha256>,
+    /// 生成的 PAR2 恢复文件、恢复分片及其记录。
+    recovery_files: Vec<String>,
+    /// 分片目录中供接收方合并用的自解压程序、合并脚本及其设置。
+    restore_files: Vec<String>,
 }
 
 #[derive(Debug, Serialize)]
@@ -49,14 +210,62 @@ struct PartSha256 {
     sha256: String,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct RestoreOptions {
     input_path: String,
     output_dir: String,
+    /// 合并方式；为 `auto` 时按清单、分片命名与第一份分片的内容识别，参见 [`detect`]。
     merge_mode: String,
-    password: Option<String>,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
+    /// 打包时使用的密钥文件。
+    keyfile: Option<String>,
+    /// `age-split` 加密给公钥时用于解密的 age 身份文件。
+    age_identities: Option<Vec<String>>,
     auto_extract: Option<bool>,
+    /// 打包时使用了过滤命令时，对应的逆向命令。
+    filter_command: Option<String>,
+    /// 分片打包时加密给了 GPG 公钥，合并前先用本机私钥解密，默认关闭。
+    gpg_decrypt: Option<bool>,
+    /// 缓冲区的内存上限（字节），未设置时不限制。
+    max_memory: Option<u64>,
+    /// 按实测的分片与目标读写速度选择缓冲区大小，默认开启。
+    adaptive_io: Option<bool>,
+    /// 有完整性清单时，合并后重新计算合并结果与解压出的文件的校验值并与清单比对，默认开启。
+    verify: Option<bool>,
+    /// 信任的签名公钥（公钥文件、十六进制公钥或指纹），提供时只接受其中公钥签名的清单。
+    trusted_keys: Option<Vec<String>>,
+    /// 清单必须带有效签名才合并，默认关闭。
+    require_signature: Option<bool>,
+    /// 尽力解压：跳过读不出或损坏的条目，解压其余条目，默认关闭。
+    salvage: Option<bool>,
+    /// 临时目录与取消后的输出先覆写再删除，默认关闭。GPG 解密出的临时分片总会覆写。
+    secure_delete: Option<bool>,
+    /// 自动解压 zip 时只解压匹配这些通配符的文件条目，未设置时解压全部条目。
+    include: Option<Vec<String>>,
+    /// 自动解压 zip 时跳过匹配这些通配符的文件条目。
+    exclude: Option<Vec<String>>,
+    /// 自动解压 `zip-then-split` 分片时直接从分片读取 zip，不生成合并后的 zip，默认关闭。
+    extract_from_parts: Option<bool>,
+    /// 自动解压后是否保留合并得到的压缩包，默认保留；不保留时 `zip-then-split` 直接从分片解压。
+    keep_archive: Option<bool>,
+    /// 缺少分片且无法重建时的处理：`fill` 补零、`truncate` 截断，未设置时报错，参见 [`gaps`]。
+    missing_parts: Option<String>,
+    /// 直接拼接分片时记录合并进度，中断后以相同参数再次合并时从上次完成的分片继续，默认开启，
+    /// 参见 [`merge_resume`]。
+    resume: Option<bool>,
+    /// 解压 zip 时输出目录中已有同名文件的处理：`overwrite`、`skip`、`rename` 或 `ask`，
+    /// 未设置时直接覆盖，参见 [`conflicts`]。
+    on_conflict: Option<String>,
+    /// 解压 zip 时非 UTF-8 条目名的代码页，如 `gbk`、`shift_jis`，`auto` 时自动推测；
+    /// 未设置时按 CP437 解读，参见 [`entry_names`]。
+    filename_encoding: Option<String>,
+    /// 分片或清单的 HTTP(S) 网址：先下载到输出目录再合并，此时忽略 `input_path`，参见 [`download`]。
+    urls: Option<Vec<String>>,
+    /// 合并成功后保留下载的分片，默认删除。
+    keep_downloads: Option<bool>,
 }
 
 #[derive(Debug, Serialize)]
@@ -65,6 +274,198 @@ struct RestoreResult {
     merged_file: Option<String>,
     extracted_dir: Option<String>,
     output_files: Vec<String>,
+    /// 合并结果是否与清单一致；没有清单或关闭了校验时为 `None`。
+    verified: Option<bool>,
+    /// 与清单不一致或缺失的文件。
+    mismatches: Vec<String>,
+    /// 合并前用恢复分片重建的分片。
+    repaired_parts: Vec<String>,
+    /// 清单签名的核对结果，清单没有签名时为 `None`。
+    signature: Option<signing::SignatureStatus>,
+    /// 尽力解压时跳过的条目。
+    lost_entries: Vec<String>,
+    /// 按签名识别出的合并结果格式（如 `zip`、`7z`、`tar.gz`、`gzip`），认不出时为 `None`。
+    archive_type: Option<String>,
+    /// `mergeMode` 为 `auto` 时识别出的合并方式。
+    detected_mode: Option<String>,
+    /// 缺片合并时缺失分片在合并结果中的字节范围。
+    missing_ranges: Vec<gaps::MissingRange>,
+    /// 解压时遇到的同名文件及其处理。
+    conflicts: Vec<conflicts::ConflictDecision>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RangeRestoreOptions {
+    input_path: String,
+    output_path: String,
+    merge_mode: String,
+    offset: u64,
+    length: u64,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
+    /// 打包时使用的密钥文件。
+    keyfile: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct RangeRestoreResult {
+    output_file: String,
+    bytes_written: u64,
+    parts_read: Vec<usize>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtractEntryOptions {
+    input_path: String,
+    output_dir: String,
+    merge_mode: String,
+    entry_name: String,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
+    /// 打包时使用的密钥文件。
+    keyfile: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExtractEntryResult {
+    entry_name: String,
+    output_file: String,
+    parts_read: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamEntryOptions {
+    input_path: String,
+    merge_mode: String,
+    entry_name: String,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
+    /// 打包时使用的密钥文件。
+    keyfile: Option<String>,
+    target_path: Option<String>,
+    offset: Option<u64>,
+    length: Option<u64>,
+}
+
+/// `stream_entry` 通道中的 JSON 消息；每个 `Chunk` 之后紧跟一条原始字节消息（`ArrayBuffer`），
+/// 即该块的内容，不经 JSON 数组编码。
+#[derive(Debug, Serialize, Clone)]
+#[serde(rename_all = "camelCase", tag = "event", content = "data")]
+enum EntryStreamEvent {
+    #[serde(rename_all = "camelCase")]
+    Started { entry_name: String, size: u64 },
+    #[serde(rename_all = "camelCase")]
+    Chunk { offset: u64, length: u64 },
+    #[serde(rename_all = "camelCase")]
+    Finished { bytes_sent: u64 },
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct StreamEntryResult {
+    entry_name: String,
+    size: u64,
+    bytes_sent: u64,
+    target_path: Option<String>,
+    parts_read: Vec<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexPartsOptions {
+    input_path: String,
+    merge_mode: String,
+    rebuild: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IndexPartsResult {
+    index_file: String,
+    cached: bool,
+    index: index::PartIndex,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchEntriesOptions {
+    input_path: String,
+    merge_mode: String,
+    pattern: String,
+    case_sensitive: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SearchEntriesResult {
+    total_entries: usize,
+    matches: Vec<index::IndexedEntry>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(all(feature = "mount", unix)), allow(dead_code))]
+struct MountOptions {
+    input_path: String,
+    merge_mode: String,
+    mount_point: String,
+    password: Option<SecretString>,
+    /// 以 `set_job_secret` 登记的密码代替 `password`，值为登记时的 `jobId`。
+    secret_job_id: Option<String>,
+    /// 打包时使用的密钥文件。
+    keyfile: Option<String>,
+    /// 非 UTF-8 条目名的代码页，同 [`RestoreOptions`] 的 `filename_encoding`。
+    filename_encoding: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+#[cfg_attr(not(all(feature = "mount", unix)), allow(dead_code))]
+struct MountResult {
+    mount_point: String,
+    entries: usize,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiServerOptions {
+    port: Option<u16>,
+    token: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ApiServerInfo {
+    url: String,
+    token: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct IpcServerOptions {
+    /// Unix 上为套接字路径，Windows 上为命名管道名；为空时使用默认值。
+    name: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct IpcServerInfo {
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct LogSettings {
+    level: String,
+    dir: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -74,54 +475,687 @@ struct SaveTextOptions {
     content: String,
 }
 
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportDiagnosticsOptions {
+    target_path: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ExportDiagnosticsResult {
+    path: String,
+    /// 诊断包内的文件名。
+    entries: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct BenchmarkDiskOptions {
+    source_path: Option<String>,
+    destination_path: Option<String>,
+    /// 每项读写的数据量，默认 256 MiB；每项最多测 5 秒。
+    size_bytes: Option<u64>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct BenchmarkDiskResult {
+    source: Option<tuning::DiskSpeed>,
+    destination: Option<tuning::DiskSpeed>,
+}
+
 #[derive(Debug, Serialize, Clone)]
 #[serde(rename_all = "camelCase")]
 struct ProgressPayload {
     phase: String,
     processed_bytes: u64,
-    total_bytes: u64,
+    /// 为 `None` 时总量未知（如管道输入），只汇报已处理的字节数。
+    total_bytes: Option<u64>,
     part_index: usize,
     part_total: usize,
     message: String,
+    /// 所属任务，多个任务同时运行时据此区分进度；由 [`JobContext::report`] 填写。
+    #[serde(skip_serializing_if = "Option::is_none")]
+    job_id: Option<String>,
 }
 
 #[tauri::command]
 async fn process_file(app: AppHandle, options: SplitOptions) -> Result<SplitResult, String> {
-    let app = app.clone();
-    tauri::async_runtime::spawn_blocking(move || process_file_blocking(&app, options))
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "pack", |ctx| process_file_blocking(ctx, options))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 把源目录中新增或修改的文件追加为一批新分片，并更新分片目录中的清单。
+#[tauri::command]
+async fn append_parts(
+    app: AppHandle,
+    options: append::AppendOptions,
+) -> Result<append::AppendResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "append", |ctx| append::append_parts_blocking(ctx, options))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 检查打包参数而不执行，返回全部字段错误，界面据此标注对应的输入框。
+#[tauri::command]
+fn validate_split_options(options: SplitOptions) -> Vec<validation::FieldError> {
+    validation::validate_split(&options)
+}
+
+#[tauri::command]
+async fn restore_parts(app: AppHandle, options: RestoreOptions) -> Result<RestoreResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "restore", |ctx| restore_parts_blocking(ctx, options))
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 批量合并目录中的每一组分片，返回各组的结果，参见 [`batch`]。
+#[tauri::command]
+async fn restore_all_groups(
+    app: AppHandle,
+    options: RestoreOptions,
+) -> Result<batch::BatchRestoreResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "restoreAll", |ctx| {
+            batch::restore_all_groups_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 以 `jobId` 登记任务密码，打包与合并参数中以 `secretJobId` 引用，密码不再出现在参数 JSON 中。
+#[tauri::command]
+fn set_job_secret(job_id: String, secret: String) -> Result<(), String> {
+    secrets::set(&job_id, secret)
+}
+
+/// 回答任务中途的提问（`job-prompt` 事件），参见 [`prompts`]。
+#[tauri::command]
+fn answer_prompt(prompt_id: String, answer: serde_json::Value) -> Result<(), String> {
+    prompts::answer(&prompt_id, answer)
+}
+
+/// 取消任务：指定 `jobId` 时只取消该任务，否则取消所有运行中的任务。返回被取消的任务 ID。
+/// 任务在下一次检查时以取消错误结束，并删除已写出的不完整输出。
+#[tauri::command]
+fn cancel_job(job_id: Option<String>) -> Result<Vec<String>, String> {
+    match job_id.as_deref().filter(|value| !value.is_empty()) {
+        Some(id) => {
+            let job = jobs::find_job(id).ok_or_else(|| tr!(JobNotFound))?;
+            job.request_cancel();
+            Ok(vec![job.id().to_string()])
+        }
+        None => Ok(jobs::cancel_running()),
+    }
+}
+
+/// 查询任务的状态与最新进度，界面可以轮询而不只依赖事件。
+#[tauri::command]
+fn get_job_status(job_id: String) -> Result<jobs::JobStatus, String> {
+    jobs::find_job(&job_id)
+        .map(|job| job.status())
+        .ok_or_else(|| tr!(JobNotFound))
+}
+
+/// 把任务加入后台队列，按加入顺序逐个运行。`kind` 为 `pack`、`restore`、`restoreRange`
+/// 或 `extractEntry`，`options` 与对应命令的参数一致。
+#[tauri::command]
+fn enqueue_job(
+    app: AppHandle,
+    kind: String,
+    options: serde_json::Value,
+) -> Result<queue::QueueItem, String> {
+    queue::enqueue(Some(app), &kind, options)
+}
+
+#[tauri::command]
+fn list_queue() -> Vec<queue::QueueItem> {
+    queue::list()
+}
+
+/// 移出尚未开始或等待重试的任务，返回剩余队列。
+#[tauri::command]
+fn remove_queued_job(id: String) -> Result<Vec<queue::QueueItem>, String> {
+    queue::remove(&id)
+}
+
+/// 移出所有尚未开始或等待重试的任务，返回被移出的 ID。
+#[tauri::command]
+fn clear_queue() -> Vec<String> {
+    queue::clear()
+}
+
+/// 队列设置：失败重试的次数与等待时间，以及队列完成后的电源操作。
+#[tauri::command]
+fn get_queue_settings() -> queue::QueueSettings {
+    queue::settings()
+}
+
+/// 更新队列设置，未提供的字段保持不变。
+#[tauri::command]
+fn set_queue_settings(
+    settings: queue::QueueSettingsOptions,
+) -> Result<queue::QueueSettings, String> {
+    queue::update_settings(settings)
+}
+
+/// 不合并，只读取分片头部与压缩包目录，预估合并与解压所需的空间。
+#[tauri::command]
+async fn estimate_restore(
+    options: estimate::EstimateRestoreOptions,
+) -> Result<estimate::EstimateRestoreResult, String> {
+    tauri::async_runtime::spawn_blocking(move || estimate::estimate_restore_blocking(options))
         .await
         .map_err(|e| e.to_string())?
 }
 
+/// 合并前的检查：识别合并方式与分片组，列出分片，判断是否需要密码并预估所需空间，不写入任何数据。
 #[tauri::command]
-async fn restore_parts(app: AppHandle, options: RestoreOptions) -> Result<RestoreResult, String> {
-    let app = app.clone();
-    tauri::async_runtime::spawn_blocking(move || restore_parts_blocking(&app, options))
+async fn plan_restore(
+    options: estimate::PlanRestoreOptions,
+) -> Result<estimate::PlanRestoreResult, String> {
+    tauri::async_runtime::spawn_blocking(move || estimate::plan_restore_blocking(options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn restore_range(
+    app: AppHandle,
+    options: RangeRestoreOptions,
+) -> Result<RangeRestoreResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "restoreRange", |ctx| {
+            partial::restore_range_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn verify_parts(
+    app: AppHandle,
+    options: integrity::VerifyOptions,
+) -> Result<integrity::VerifyReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "verifyParts", |ctx| {
+            integrity::verify_parts_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 估算密码强度，界面据此在开始加密打包前拦下过弱的密码。
+#[tauri::command]
+fn estimate_password_strength(password: String) -> password_strength::PasswordStrength {
+    password_strength::estimate(&password)
+}
+
+/// 生成 Ed25519 签名密钥对，私钥用于打包参数 `signingKey`，公钥交给收件方。
+#[tauri::command]
+fn generate_signing_key(
+    options: signing::GenerateKeyOptions,
+) -> Result<signing::KeyInfo, String> {
+    signing::generate_key(options)
+}
+
+/// 导入已有的 Ed25519 私钥（如 OpenSSL 生成的 PKCS#8），转存并写出公钥。
+#[tauri::command]
+fn import_signing_key(options: signing::ImportKeyOptions) -> Result<signing::KeyInfo, String> {
+    signing::import_key(options)
+}
+
+/// 不合并，只用恢复分片、异或校验分片或 PAR2 恢复文件修复缺失或损坏的分片。
+#[tauri::command]
+async fn repair_parts(
+    app: AppHandle,
+    options: repair::RepairOptions,
+) -> Result<repair::RepairReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "repairParts", |ctx| {
+            repair::repair_parts_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 不合并，逐份解密分片再以新密码加密（新密码留空时去掉加密），并更新完整性清单。
+#[tauri::command]
+async fn reencrypt_parts(
+    app: AppHandle,
+    options: reencrypt::ReencryptOptions,
+) -> Result<reencrypt::ReencryptReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "reencryptParts", |ctx| {
+            reencrypt::reencrypt_parts_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 去掉分片组的加密，原地替换或写到另一个目录，并改写完整性清单。
+#[tauri::command]
+async fn decrypt_parts(
+    app: AppHandle,
+    options: decrypt::DecryptOptions,
+) -> Result<decrypt::DecryptReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "decryptParts", |ctx| {
+            decrypt::decrypt_parts_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 不写出文件，逐个解压分片组或合并后的压缩包中的文件并核对 CRC（相当于 `7z t`）。
+#[tauri::command]
+async fn test_archive(
+    app: AppHandle,
+    options: test_archive::TestArchiveOptions,
+) -> Result<test_archive::TestReport, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "testArchive", |ctx| {
+            test_archive::test_archive_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn extract_entry(
+    app: AppHandle,
+    options: ExtractEntryOptions,
+) -> Result<ExtractEntryResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "extractEntry", |ctx| {
+            partial::extract_entry_blocking(ctx, options)
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn stream_entry(
+    app: AppHandle,
+    options: StreamEntryOptions,
+    on_event: Channel<InvokeResponseBody>,
+) -> Result<StreamEntryResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        jobs::run_job(Some(app), "streamEntry", |ctx| {
+            partial::stream_entry_blocking(
+                ctx,
+                options,
+                |event| {
+                    let body = serde_json::to_string(&event).map_err(|e| e.to_string())?;
+                    on_event
+                        .send(InvokeResponseBody::Json(body))
+                        .map_err(|e| e.to_string())
+                },
+                |data| {
+                    on_event
+                        .send(InvokeResponseBody::Raw(data.to_vec()))
+                        .map_err(|e| e.to_string())
+                },
+            )
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn index_parts(options: IndexPartsOptions) -> Result<IndexPartsResult, String> {
+    tauri::async_runtime::spawn_blocking(move || index_parts_blocking(options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+/// 不解压，列出合并后的 zip 或完整分片组中的条目树，供合并前预览。
+#[tauri::command]
+async fn list_archive_contents(
+    options: contents::ListContentsOptions,
+) -> Result<contents::ContentsReport, String> {
+    tauri::async_runtime::spawn_blocking(move || contents::list_contents_blocking(options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn search_entries(options: SearchEntriesOptions) -> Result<SearchEntriesResult, String> {
+    tauri::async_runtime::spawn_blocking(move || search_entries_blocking(options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+async fn mount_parts(options: MountOptions) -> Result<MountResult, String> {
+    tauri::async_runtime::spawn_blocking(move || mount_parts_blocking(options))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+fn unmount_parts(mount_point: String) -> Result<(), String> {
+    #[cfg(all(feature = "mount", unix))]
+    return mount::unmount(Path::new(&mount_point));
+    #[cfg(not(all(feature = "mount", unix)))]
+    {
+        let _ = mount_point;
+        Err(tr!(MountUnavailable))
+    }
+}
+
+#[tauri::command]
+fn start_api_server(app: AppHandle, options: ApiServerOptions) -> Result<ApiServerInfo, String> {
+    let (url, token) = api::start(
+        Some(app),
+        options.port.unwrap_or(DEFAULT_API_PORT),
+        options.token,
+    )?;
+    Ok(ApiServerInfo { url, token })
+}
+
+#[tauri::command]
+fn stop_api_server() -> Result<(), String> {
+    api::stop()
+}
+
+#[tauri::command]
+fn get_api_server() -> Option<ApiServerInfo> {
+    api::status().map(|(url, token)| ApiServerInfo { url, token })
+}
+
+/// 开始监视目录：出现新的文件或文件夹时按保存的打包参数加入任务队列。
+#[tauri::command]
+fn start_watch_folder(
+    app: AppHandle,
+    options: watch::WatchOptions,
+) -> Result<watch::WatchInfo, String> {
+    watch::start(Some(app), options)
+}
+
+#[tauri::command]
+fn stop_watch_folder() -> Result<(), String> {
+    watch::stop()
+}
+
+#[tauri::command]
+fn get_watch_folder() -> Option<watch::WatchInfo> {
+    watch::status()
+}
+
+#[tauri::command]
+fn start_ipc_server(app: AppHandle, options: IpcServerOptions) -> Result<IpcServerInfo, String> {
+    let name = ipc::start(Some(app), options.name)?;
+    Ok(IpcServerInfo { name })
+}
+
+#[tauri::command]
+fn stop_ipc_server() -> Result<(), String> {
+    ipc::stop()
+}
+
+#[tauri::command]
+fn get_ipc_server() -> Option<IpcServerInfo> {
+    ipc::status().map(|name| IpcServerInfo { name })
+}
+
+#[tauri::command]
+fn list_plugins() -> Vec<plugins::PluginInfo> {
+    plugins::list_plugins()
+}
+
+/// 当前提示信息的语言代码（`zh-CN`、`en`）。
+#[tauri::command]
+fn get_language() -> String {
+    i18n::current().code().to_string()
+}
+
+/// 切换进度消息与错误信息的语言，返回规范化后的语言代码。
+#[tauri::command]
+fn set_language(language: String) -> Result<String, String> {
+    i18n::set_language_tag(&language).map(|lang| lang.code().to_string())
+}
+
+fn log_settings() -> LogSettings {
+    LogSettings {
+        level: logging::level().to_string().to_lowercase(),
+        dir: logging::log_dir().map(|dir| dir.to_string_lossy().to_string()),
+    }
+}
+
+/// 当前日志级别与日志目录（未启用文件日志时目录为空）。
+#[tauri::command]
+fn get_log_settings() -> LogSettings {
+    log_settings()
+}
+
+/// 调整日志详细程度：`off`、`error`、`warn`、`info`、`debug`、`trace`。
+#[tauri::command]
+fn set_log_level(level: String) -> Result<LogSettings, String> {
+    logging::set_level(&level)?;
+    Ok(log_settings())
+}
+
+/// 导出诊断包（日志、上一次任务参数、运行环境），用于附在问题反馈里。
+#[tauri::command]
+async fn export_diagnostics(
+    options: ExportDiagnosticsOptions,
+) -> Result<ExportDiagnosticsResult, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let entries = diagnostics::export(Path::new(&options.target_path))?;
+        Ok(ExportDiagnosticsResult {
+            path: options.target_path,
+            entries,
+        })
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
+
+/// 测量源与目标路径的顺序读写速度，结果供界面预估耗时，也会被之后的任务用来选择缓冲区与并发。
+#[tauri::command]
+async fn benchmark_disk(options: BenchmarkDiskOptions) -> Result<BenchmarkDiskResult, String> {
+    tauri::async_runtime::spawn_blocking(move || benchmark_disk_blocking(options))
         .await
         .map_err(|e| e.to_string())?
 }
 
+fn benchmark_disk_blocking(options: BenchmarkDiskOptions) -> Result<BenchmarkDiskResult, String> {
+    let size = options
+        .size_bytes
+        .unwrap_or(tuning::DEFAULT_BENCHMARK_SIZE);
+    let measure = |path: Option<String>| {
+        path.filter(|value| !value.is_empty())
+            .map(|path| tuning::benchmark(Path::new(&path), size))
+            .transpose()
+    };
+    Ok(BenchmarkDiskResult {
+        source: measure(options.source_path)?,
+        destination: measure(options.destination_path)?,
+    })
+}
+
 #[tauri::command]
 fn save_text_file(options: SaveTextOptions) -> Result<(), String> {
     fs::write(&options.target_path, options.content).map_err(|e| e.to_string())
 }
 
-fn process_file_blocking(app: &AppHandle, options: SplitOptions) -> Result<SplitResult, String> {
+fn index_parts_blocking(options: IndexPartsOptions) -> Result<IndexPartsResult, String> {
+    let input_path = PathBuf::from(options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let part_group = collect_part_group(&input_path)?;
+    let (index, cached) = index::load_or_build_index(
+        &part_group,
+        options.merge_mode.as_str(),
+        options.rebuild.unwrap_or(false),
+    )?;
+    Ok(IndexPartsResult {
+        index_file: index::index_path(&part_group)?
+            .to_string_lossy()
+            .to_string(),
+        cached,
+        index,
+    })
+}
+
+fn search_entries_blocking(options: SearchEntriesOptions) -> Result<SearchEntriesResult, String> {
+    let input_path = PathBuf::from(options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let part_group = collect_part_group(&input_path)?;
+    let (index, _) = index::load_or_build_index(&part_group, options.merge_mode.as_str(), false)?;
+    if index.entries.is_empty() {
+        return Err(tr!(CannotListPacked));
+    }
+    let matches = index::search_entries(
+        &index,
+        options.pattern.as_str(),
+        options.case_sensitive.unwrap_or(false),
+    )?
+    .into_iter()
+    .cloned()
+    .collect();
+    Ok(SearchEntriesResult {
+        total_entries: index.entries.len(),
+        matches,
+    })
+}
+
+#[cfg(all(feature = "mount", unix))]
+fn mount_parts_blocking(mut options: MountOptions) -> Result<MountResult, String> {
+    let input_path = PathBuf::from(options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let name_encoding = entry_names::NameEncoding::parse(options.filename_encoding.as_deref())?;
+    let mount_point = PathBuf::from(options.mount_point);
+    let part_group = collect_part_group(&input_path)?;
+    let entries = mount::mount_part_group(
+        part_group,
+        options.merge_mode.as_str(),
+        &mount_point,
+        password.as_deref(),
+        name_encoding,
+    )?;
+    Ok(MountResult {
+        mount_point: mount_point.to_string_lossy().to_string(),
+        entries,
+    })
+}
+
+#[cfg(not(all(feature = "mount", unix)))]
+fn mount_parts_blocking(options: MountOptions) -> Result<MountResult, String> {
+    let _ = options;
+    Err(tr!(MountUnavailableHint))
+}
+
+fn process_file_blocking(ctx: &JobContext, options: SplitOptions) -> Result<SplitResult, String> {
+    log::info!(
+        "开始打包：{}（打包方式 {}，切分方式 {}）",
+        options.input_path,
+        options.pack_mode,
+        options.split_by
+    );
+    diagnostics::record_job("pack", &options);
+    let started = Instant::now();
+    let power_action = options.power_action.clone();
+    let result = if options.background_priority.unwrap_or(false) {
+        let ctx = ctx.with_background_priority();
+        priority::run_in_background(|| pack_file(&ctx, options))
+    } else {
+        pack_file(ctx, options)
+    };
+    if result.is_err() && ctx.is_cancelled() {
+        ctx.remove_outputs();
+    }
+    logging::log_result("打包", started, &result);
+    if result.is_ok() {
+        power::after_success(power_action.as_deref());
+    }
+    result
+}
+
+fn pack_file(ctx: &JobContext, mut options: SplitOptions) -> Result<SplitResult, String> {
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    validation::check_split(&options)?;
+    // 之后各打包方式只看实际密码
+    options.password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let fingerprint = if options.resume.unwrap_or(true) {
+        checkpoint::fingerprint(&options)
+    } else {
+        None
+    };
+    let mut memory = memory_budget(options.max_memory)?;
+    let script = match options.script_path.as_deref().filter(|value| !value.is_empty()) {
+        Some(path) => Some(script::ScriptHooks::load(Path::new(path))?),
+        None => None,
+    };
     let input_path = PathBuf::from(options.input_path);
     let output_dir = PathBuf::from(options.output_dir);
     let overwrite_parts = options.overwrite_parts.unwrap_or(false);
     let compression_level = options.compression_level;
+    let hash_algorithm = options
+        .hash_algorithm
+        .as_deref()
+        .and_then(integrity::HashAlgorithm::parse)
+        .unwrap_or_default();
 
-    if !input_path.exists() {
-        return Err("输入文件不存在".to_string());
-    }
     if !output_dir.exists() {
         fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
+        ctx.track_output(&output_dir);
     }
+    if options.adaptive_io.unwrap_or(true) {
+        memory = tuning::tune(memory, Some(&input_path), &output_dir);
+    }
+    let mut ctx = ctx.with_memory(memory);
+    if let Some(method) = options.compression_method.as_deref().and_then(parse_compression_method) {
+        if method == CompressionMethod::Xz {
+            log::warn!("xz 压缩的 zip 条目只能用 7-Zip 或本程序解压，系统自带的解压功能与 unzip 都打不开");
+        }
+        ctx = ctx.with_compression(method);
+    }
+    if let Some(mode) = options.aes_strength.and_then(parse_aes_strength) {
+        ctx = ctx.with_aes_mode(mode);
+    }
+    if options.secure_delete.unwrap_or(false) {
+        ctx = ctx.with_secure_delete();
+    }
+    if let Some(script) = script {
+        ctx = ctx.with_script(script);
+    }
+    if let Some(fingerprint) = fingerprint {
+        ctx = ctx.with_resume(fingerprint);
+    }
+    let ctx = &ctx;
+    let numbered_volumes = options.numbered_volumes.unwrap_or(false);
 
-    match options.pack_mode.as_str() {
+    let mut result = match options.pack_mode.as_str() {
         "split-then-zip" => split_then_zip(
-            app,
+            ctx,
             &input_path,
             &output_dir,
             options.split_by.as_str(),
@@ -134,9 +1168,10 @@ fn process_file_blocking(app: &AppHandle, options: SplitOptions) -> Result<Split
             options.dir_split_mode.as_deref(),
             overwrite_parts,
             compression_level,
+            hash_algorithm,
         ),
         "zip-then-split" => zip_then_split(
-            app,
+            ctx,
             &input_path,
             &output_dir,
             options.split_by.as_str(),
@@ -148,37 +1183,494 @@ fn process_file_blocking(app: &AppHandle, options: SplitOptions) -> Result<Split
                 .filter(|value| !value.is_empty()),
             overwrite_parts,
             compression_level,
+            hash_algorithm,
+            numbered_volumes,
+            options.encrypt_names.unwrap_or(false),
         ),
-        _ => Err("未知的打包方式".to_string()),
+        "7z-then-split" => sevenz_then_split(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.split_by.as_str(),
+            options.size_bytes,
+            options.count,
+            options
+                .password
+                .as_deref()
+                .filter(|value| !value.is_empty()),
+            overwrite_parts,
+            compression_level,
+            options.seven_zip_volumes.unwrap_or(false) || numbered_volumes,
+        ),
+        "zstd-then-split" => zstd_then_split(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.split_by.as_str(),
+            options.size_bytes,
+            options.count,
+            options
+                .password
+                .as_deref()
+                .filter(|value| !value.is_empty()),
+            overwrite_parts,
+            compression_level,
+            hash_algorithm,
+            numbered_volumes,
+            options.zstd_dictionary.unwrap_or(false),
+        ),
+        "tar-gz-then-split" => tar_then_split(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.split_by.as_str(),
+            options.size_bytes,
+            options.count,
+            overwrite_parts,
+            tar::TarCompression::Gzip(compression_level.unwrap_or(6).clamp(0, 9) as u32),
+            numbered_volumes,
+        ),
+        "tar-zst-then-split" => tar_then_split(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.split_by.as_str(),
+            options.size_bytes,
+            options.count,
+            overwrite_parts,
+            tar::TarCompression::Zstd {
+                level: compression_level.unwrap_or(3).clamp(1, 19) as i32,
+                workers: ctx.memory().workers(
+                    thread::available_parallelism().map_or(1, |n| n.get()),
+                    memory::ZSTD_MT_WORKER_MEMORY,
+                ) as u32,
+            },
+            numbered_volumes,
+        ),
+        "spanned-zip" => spanned_zip(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.size_bytes,
+            options
+                .password
+                .as_deref()
+                .filter(|value| !value.is_empty()),
+            overwrite_parts,
+            compression_level,
+            hash_algorithm,
+        ),
+        "raw-split" => raw_split(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.split_by.as_str(),
+            options.size_bytes,
+            options.count,
+            overwrite_parts,
+            hash_algorithm,
+            numbered_volumes,
+        ),
+        "age-split" => {
+            let recipients =
+                age_split::parse_recipients(options.age_recipients.as_deref().unwrap_or_default())?;
+            let password = options.password.as_deref().filter(|value| !value.is_empty());
+            let seal = if !recipients.is_empty() {
+                age_split::Seal::Recipients(recipients)
+            } else if let Some(password) = password {
+                age_split::Seal::Passphrase(SecretString::from(password))
+            } else {
+                return Err(tr!(AgeSplitNeedsKey));
+            };
+            age_split::split(
+                ctx,
+                &input_path,
+                &output_dir,
+                options.split_by.as_str(),
+                options.size_bytes,
+                options.count,
+                &seal,
+                overwrite_parts,
+                hash_algorithm,
+            )
+        }
+        "container-then-split" => container::container_then_split(
+            ctx,
+            &input_path,
+            &output_dir,
+            options.split_by.as_str(),
+            options.size_bytes,
+            options.count,
+            options
+                .password
+                .as_deref()
+                .filter(|value| !value.is_empty()),
+            overwrite_parts,
+            compression_level,
+            hash_algorithm,
+            numbered_volumes,
+            options.per_part_keys.unwrap_or(false),
+        ),
+        _ => Err(tr!(UnknownPackMode)),
+    }?;
+
+    let destroy_source = options.destroy_source.unwrap_or(false);
+    if options.verify_after_split.unwrap_or(false) || destroy_source {
+        split_verify::verify_split(
+            ctx,
+            &options.pack_mode,
+            &input_path,
+            &output_dir.join(format!("{}.parts", result.base_name)),
+            &result.output_files,
+            options
+                .password
+                .as_deref()
+                .filter(|value| !value.is_empty()),
+        )?;
+    }
+    let filter_command = options.filter_command.as_deref().filter(|value| !value.trim().is_empty());
+    if let Some(command) = filter_command {
+        filter::filter_parts_in_place(ctx, command, &result.output_files)?;
+    }
+    let gpg_recipients = options.gpg_recipients.as_deref().filter(|values| !values.is_empty());
+    if let Some(recipients) = gpg_recipients {
+        gpg::encrypt_parts_in_place(ctx, recipients, &result.output_files)?;
+    }
+    if (filter_command.is_some() || gpg_recipients.is_some())
+        && matches!(
+            options.pack_mode.as_str(),
+            "split-then-zip"
+                | "zip-then-split"
+                | "raw-split"
+                | "age-split"
+                | "container-then-split"
+        )
+    {
+        let parts_dir = output_dir.join(format!("{}.parts", result.base_name));
+        result.part_sha256s =
+            integrity::record_parts(ctx, &parts_dir, &result.base_name, &result.output_files)?;
+    }
+    if let Some(count) = options.redundancy_parts.filter(|value| *value > 0) {
+        result.recovery_files =
+            erasure::create_recovery_parts(ctx, &result.base_name, &result.output_files, count)?;
+    } else if options.xor_parity.unwrap_or(false) {
+        result.recovery_files =
+            erasure::create_parity_part(ctx, &result.base_name, &result.output_files)?;
+    }
+    if let Some(redundancy) = options.par2_redundancy.filter(|value| *value > 0) {
+        result.recovery_files.extend(parity::create_recovery_files(
+            ctx,
+            &result.base_name,
+            &result.output_files,
+            redundancy,
+        )?);
+    }
+    if let Some(key_path) = options.signing_key.as_deref().filter(|value| !value.is_empty()) {
+        let parts_dir = output_dir.join(format!("{}.parts", result.base_name));
+        signing::sign_manifest(
+            &append::manifest_path(&parts_dir, &result.base_name),
+            Path::new(key_path),
+        )?;
+    }
+    if options.self_extracting.unwrap_or(false) {
+        let parts_dir = output_dir.join(format!("{}.parts", result.base_name));
+        let first_part = result.output_files.first().ok_or_else(|| tr!(NoPartsFound))?;
+        result.restore_files = sfx::write_restorer(
+            ctx,
+            &parts_dir,
+            &options.pack_mode,
+            Path::new(first_part),
+            options.password.as_deref().is_some_and(|value| !value.is_empty()),
+            gpg_recipients.is_some(),
+        )?;
+    }
+    if options.rejoin_scripts.unwrap_or(false) {
+        let parts_dir = output_dir.join(format!("{}.parts", result.base_name));
+        // 系统自带的 unzip 与 tar 解不开 AES 加密与 xz 压缩的条目，此时只拼接
+        let extractable = options.password.as_deref().is_none_or(str::is_empty)
+            && options.compression_method.as_deref() != Some("xz");
+        result.restore_files.extend(rejoin::write_scripts(
+            ctx,
+            &parts_dir,
+            sfx::merge_mode(&options.pack_mode),
+            &result.output_files,
+            extractable,
+        )?);
+    }
+    if let Some(script) = ctx.script() {
+        let total = result.output_files.len();
+        for (idx, path) in result.output_files.iter().enumerate() {
+            script.on_part(path, idx + 1, total)?;
+        }
+    }
+    if let Some(names) = options.post_processors.as_deref() {
+        let manifest = serde_json::to_value(&result).map_err(|e| e.to_string())?;
+        plugins::run_post_processors(ctx, names, &result.output_files, &manifest)?;
     }
+    // 其余步骤都成功后才销毁源文件
+    if destroy_source {
+        wipe::wipe_path(ctx, &input_path, tr!(WipingSource))?;
+    }
+    Ok(result)
 }
 
 fn restore_parts_blocking(
-    app: &AppHandle,
+    ctx: &JobContext,
     options: RestoreOptions,
 ) -> Result<RestoreResult, String> {
-    let input_path = PathBuf::from(options.input_path);
-    let output_dir = PathBuf::from(options.output_dir);
-
-    if !input_path.exists() {
-        return Err("输入分片不存在".to_string());
+    log::info!("开始合并：{}（{}）", options.input_path, options.merge_mode);
+    diagnostics::record_job("restore", &options);
+    let started = Instant::now();
+    let result = merge_parts(ctx, options);
+    if result.is_err() && ctx.is_cancelled() {
+        ctx.remove_outputs();
     }
+    logging::log_result("合并", started, &result);
+    result
+}
+
+fn merge_parts(ctx: &JobContext, mut options: RestoreOptions) -> Result<RestoreResult, String> {
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    let mut memory = memory_budget(options.max_memory)?;
+    let output_dir = PathBuf::from(&options.output_dir);
     if !output_dir.exists() {
         fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
+        ctx.track_output(&output_dir);
+    }
+    // 给出网址时先下载，之后从下载目录合并
+    let downloads = download::prepare(ctx, &mut options, &output_dir)?;
+    let input_path = PathBuf::from(&options.input_path);
+
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    if options.adaptive_io.unwrap_or(true) {
+        memory = tuning::tune(memory, Some(&input_path), &output_dir);
+    }
+    let mut ctx = ctx.with_memory(memory);
+    if options.salvage.unwrap_or(false) {
+        ctx = ctx.with_salvage();
+    }
+    if options.secure_delete.unwrap_or(false) {
+        ctx = ctx.with_secure_delete();
+    }
+    if let Some(policy) = gaps::GapPolicy::parse(options.missing_parts.as_deref())? {
+        ctx = ctx.with_gaps(policy);
+    }
+    if let Some(policy) = conflicts::ConflictPolicy::parse(options.on_conflict.as_deref())? {
+        ctx = ctx.with_conflicts(policy);
+    }
+    if let Some(encoding) =
+        entry_names::NameEncoding::parse(options.filename_encoding.as_deref())?
+    {
+        ctx = ctx.with_name_encoding(encoding);
+    }
+    if let Some(keyfile) = options.keyfile.as_deref().filter(|value| !value.is_empty()) {
+        ctx = ctx.with_keyfile(keyfile);
+    }
+    if options.resume.unwrap_or(true) {
+        ctx = ctx.with_resume(merge_resume::fingerprint(&options));
+    }
+    if let Some(filter) = entry_filter::EntryFilter::new(
+        options.include.as_deref().unwrap_or_default(),
+        options.exclude.as_deref().unwrap_or_default(),
+    )? {
+        ctx = ctx.with_entry_filter(filter);
     }
+    let ctx = &ctx;
 
-    let password = options.password.as_deref().filter(|value| !value.is_empty());
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let password = password.as_deref();
     let auto_extract = options.auto_extract.unwrap_or(false);
 
-    match options.merge_mode.as_str() {
-        "split-then-zip" => restore_split_then_zip(app, &input_path, &output_dir, password, auto_extract),
-        "zip-then-split" => restore_zip_then_split(app, &input_path, &output_dir, password, auto_extract),
-        _ => Err("未知的合并方式".to_string()),
+    // 有恢复分片时先重建缺失或损坏的分片
+    let repaired_parts = salvage_step(ctx, erasure::reconstruct(ctx, &input_path))?
+        .unwrap_or_default();
+    // 有完整性清单时先核对全部分片，合并后再核对合并与解压结果
+    let manifest = integrity::find(&input_path)?;
+    // 清单的签名先于分片核对：签名通过后清单中的校验值才可信
+    let signature = signing::verify_manifest(
+        manifest.as_ref().map(|(_, manifest)| manifest.path()),
+        options.trusted_keys.as_deref().unwrap_or_default(),
+        options.require_signature.unwrap_or(false),
+    )?;
+    if let Some((parts_dir, manifest)) = &manifest {
+        salvage_step(ctx, integrity::verify_parts(ctx, parts_dir, manifest))?;
+    }
+    // 自动识别在重建缺失分片之后进行，第一份分片此时已在
+    let detected_mode = if options.merge_mode == detect::AUTO {
+        let transformed = options.gpg_decrypt.unwrap_or(false)
+            || options
+                .filter_command
+                .as_deref()
+                .is_some_and(|value| !value.trim().is_empty());
+        options.merge_mode = detect::detect_merge_mode(&input_path, transformed)?;
+        log::info!("识别出的合并方式：{}", options.merge_mode);
+        Some(options.merge_mode.clone())
+    } else {
+        None
+    };
+    let mut result =
+        restore_filtered(ctx, &options, &input_path, &output_dir, password, auto_extract)?;
+    if options.verify.unwrap_or(true) {
+        match &manifest {
+            Some((_, manifest)) => integrity::verify_restored(ctx, manifest, &mut result)?,
+            None if options.merge_mode == "raw" => {
+                raw::verify_checksum_file(ctx, &input_path, &mut result)?
+            }
+            None => {}
+        }
+    }
+    result.repaired_parts = repaired_parts;
+    result.signature = signature;
+    result.lost_entries = ctx.take_lost();
+    result.detected_mode = detected_mode;
+    result.missing_ranges = ctx.take_missing();
+    result.conflicts = ctx.take_conflicts();
+    if result.archive_type.is_none() {
+        if let Some(merged_file) = &result.merged_file {
+            result.archive_type = extract::ArchiveKind::detect(Path::new(merged_file))?
+                .map(|kind| kind.name().to_string());
+        }
+    }
+    // 只要解压结果时，核对完成后再删除合并得到的压缩包
+    if !options.keep_archive.unwrap_or(true) && result.extracted_dir.is_some() {
+        discard_merged(ctx, &mut result);
+    }
+    if let Some(dir) = downloads.filter(|_| !options.keep_downloads.unwrap_or(false)) {
+        remove_temp(ctx, &dir, false);
+    }
+    Ok(result)
+}
+
+/// 删除合并得到的压缩包并从结果中去掉，只留下解压目录。
+fn discard_merged(ctx: &JobContext, result: &mut RestoreResult) {
+    let Some(merged_file) = result.merged_file.take() else {
+        return;
+    };
+    remove_temp(ctx, Path::new(&merged_file), false);
+    result.output_files.retain(|file| *file != merged_file);
+    log::info!("已删除合并得到的压缩包：{}", merged_file);
+}
+
+/// 尽力解压或缺片合并时，合并前的修复与核对失败只记录警告，继续合并能读出的部分。
+fn salvage_step<T>(ctx: &JobContext, result: Result<T, String>) -> Result<Option<T>, String> {
+    match result {
+        Ok(value) => Ok(Some(value)),
+        Err(err) if ctx.salvage() || ctx.gap_policy().is_some() => {
+            ctx.check_cancelled()?;
+            log::warn!("忽略合并前的错误，继续合并能读出的部分：{}", err);
+            Ok(None)
+        }
+        Err(err) => Err(err),
+    }
+}
+
+/// 打包时加密给了 GPG 公钥或设置了过滤命令时，先把分片解密、还原到临时目录再合并，否则直接合并。
+fn restore_filtered(
+    ctx: &JobContext,
+    options: &RestoreOptions,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let command = options.filter_command.as_deref().filter(|value| !value.trim().is_empty());
+    let gpg_decrypt = options.gpg_decrypt.unwrap_or(false);
+    if command.is_none() && !gpg_decrypt {
+        return restore_by_mode(ctx, options, input_path, output_dir, password, auto_extract);
+    }
+    let mut temp_dirs = Vec::new();
+    let result = restore_transformed(
+        ctx,
+        options,
+        input_path,
+        output_dir,
+        password,
+        auto_extract,
+        &mut temp_dirs,
+    );
+    // GPG 解密出的分片是明文
+    for dir in temp_dirs {
+        remove_temp(ctx, &dir, gpg_decrypt);
+    }
+    result
+}
+
+/// 按打包时的逆序还原分片：先 GPG 解密，再用逆向命令还原，每一步写入一个新的临时目录。
+fn restore_transformed(
+    ctx: &JobContext,
+    options: &RestoreOptions,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+    temp_dirs: &mut Vec<PathBuf>,
+) -> Result<RestoreResult, String> {
+    let mut part_group = collect_part_group(input_path)?;
+    let stem = part_group.prefix.trim_end_matches('.').to_string();
+    let mut source = input_path.to_path_buf();
+    if options.gpg_decrypt.unwrap_or(false) {
+        let temp_dir = output_dir.join(format!(".{}.decrypted", stem));
+        temp_dirs.push(temp_dir.clone());
+        gpg::decrypt_parts(ctx, &part_group, &temp_dir)?;
+        part_group = collect_part_group(&temp_dir)?;
+        source = temp_dir;
+    }
+    if let Some(command) = options.filter_command.as_deref().filter(|value| !value.trim().is_empty()) {
+        let temp_dir = output_dir.join(format!(".{}.unfiltered", stem));
+        temp_dirs.push(temp_dir.clone());
+        filter::unfilter_parts(ctx, command, &part_group, &temp_dir)?;
+        source = temp_dir;
+    }
+    restore_by_mode(ctx, options, &source, output_dir, password, auto_extract)
+}
+
+fn restore_by_mode(
+    ctx: &JobContext,
+    options: &RestoreOptions,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let merge_mode = options.merge_mode.as_str();
+    match merge_mode {
+        "split-then-zip" => restore_split_then_zip(ctx, input_path, output_dir, password, auto_extract),
+        "zip-then-split" => restore_zip_then_split(
+            ctx,
+            input_path,
+            output_dir,
+            password,
+            auto_extract,
+            options.extract_from_parts.unwrap_or(false) || !options.keep_archive.unwrap_or(true),
+        ),
+        "7z-then-split" => restore_sevenz_then_split(ctx, input_path, output_dir, password, auto_extract),
+        "zstd-then-split" => restore_zstd_then_split(ctx, input_path, output_dir, password, auto_extract),
+        "raw" => raw::restore_raw(ctx, input_path, output_dir),
+        "spanned-zip" => spanned::restore(ctx, input_path, output_dir, password, auto_extract),
+        "age-split" => age_split::restore(
+            ctx,
+            input_path,
+            output_dir,
+            password,
+            options.age_identities.as_deref().unwrap_or_default(),
+        ),
+        "container-then-split" => {
+            container::restore(ctx, input_path, output_dir, password, auto_extract)
+        }
+        _ => match tar::TarCompression::for_merge_mode(merge_mode) {
+            Some(compression) => {
+                restore_tar_then_split(ctx, input_path, output_dir, compression, auto_extract)
+            }
+            None => Err(tr!(UnknownMergeMode)),
+        },
     }
 }
 
 fn split_then_zip(
-    app: &AppHandle,
+    ctx: &JobContext,
     input_path: &Path,
     output_dir: &Path,
     split_by: &str,
@@ -188,42 +1680,47 @@ fn split_then_zip(
     dir_split_mode: Option<&str>,
     overwrite_parts: bool,
     compression_level: Option<i64>,
+    hash_algorithm: integrity::HashAlgorithm,
 ) -> Result<SplitResult, String> {
     let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
     let is_dir = metadata.is_dir();
 
-    let base_name = file_base_name(input_path)?;
+    let base_name = output_base_name(ctx, input_path)?;
     let parts_dir = output_dir.join(format!("{}.parts", base_name));
-    ensure_parts_dir(&parts_dir, overwrite_parts)?;
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
 
+    let compressed = ctx.compression();
     let (dir_zip_compression, dir_part_compression) = match dir_split_mode.unwrap_or("") {
-        "store-split-compress" => (CompressionMethod::Stored, CompressionMethod::Deflated),
-        "compress-split-store" => (CompressionMethod::Deflated, CompressionMethod::Stored),
-        _ => (CompressionMethod::Deflated, CompressionMethod::Stored),
+        "store-split-compress" => (CompressionMethod::Stored, compressed),
+        "compress-split-store" => (compressed, CompressionMethod::Stored),
+        _ => (compressed, CompressionMethod::Stored),
     };
     let strict_size = split_by == "size";
     if strict_size && is_dir && !matches!(dir_part_compression, CompressionMethod::Stored) {
-        return Err("按每份最大时，请选择“先压缩 → 切分 → Store 打包”".to_string());
+        return Err(tr!(MaxSizeRequiresStore));
     }
     let part_compression = if strict_size {
         CompressionMethod::Stored
     } else if is_dir {
         dir_part_compression
     } else {
-        CompressionMethod::Deflated
+        compressed
     };
 
     let temp_zip_path = if is_dir {
         let zip_path = parts_dir.join(format!("{}.zip", base_name));
-        zip_directory(
-            app,
-            input_path,
-            &zip_path,
-            None,
-            dir_zip_compression,
-            compression_level,
-            "pack-dir",
-        )?;
+        if !archive_from_checkpoint(ctx, &zip_path) {
+            zip_directory(
+                ctx,
+                input_path,
+                &zip_path,
+                None,
+                dir_zip_compression,
+                compression_level,
+                "pack-dir",
+            )?;
+            mark_archive_done(ctx, &zip_path)?;
+        }
         Some(zip_path)
     } else {
         None
@@ -236,7 +1733,7 @@ fn split_then_zip(
     let input_file = File::open(source_path).map_err(|e| e.to_string())?;
     let total_size = input_file.metadata().map_err(|e| e.to_string())?.len();
     if total_size == 0 {
-        return Err("输入文件大小为 0，无法切分".to_string());
+        return Err(tr!(EmptyInputFile));
     }
 
     let (chunk_size, parts) = if strict_size && matches!(part_compression, CompressionMethod::Stored)
@@ -252,12 +1749,19 @@ fn split_then_zip(
     };
     let width = cmp::max(3, parts.to_string().len());
     let max_threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
-    let use_parallel = matches!(part_compression, CompressionMethod::Deflated)
+    let (max_workers, _) = ctx
+        .memory()
+        .plan(
+            max_threads,
+            memory::zip_worker_overhead(part_compression, compression_level),
+            1,
+        );
+    let use_parallel = !matches!(part_compression, CompressionMethod::Stored)
         && parts > 1
-        && max_threads > 1;
+        && max_workers > 1;
     let output_files = if use_parallel {
         split_file_parts_parallel(
-            app,
+            ctx,
             source_path,
             &parts_dir,
             base_name.as_str(),
@@ -272,7 +1776,7 @@ fn split_then_zip(
         )?
     } else {
         split_file_parts_sequential(
-            app,
+            ctx,
             input_file,
             &parts_dir,
             base_name.as_str(),
@@ -287,11 +1791,21 @@ fn split_then_zip(
         )?
     };
 
+    finish_checkpoint(ctx);
+    let part_sha256s = integrity::write_manifest(
+        ctx,
+        hash_algorithm,
+        &parts_dir,
+        &base_name,
+        "split-then-zip",
+        source_path,
+        is_dir.then_some(input_path),
+        &output_files,
+    );
     if let Some(path) = temp_zip_path {
-        let _ = fs::remove_file(path);
+        remove_temp(ctx, &path, password.is_some());
     }
-
-    let part_sha256s = compute_part_sha256s(&output_files)?;
+    let part_sha256s = part_sha256s?;
 
     Ok(SplitResult {
         parts,
@@ -299,6 +1813,8 @@ fn split_then_zip(
         is_dir,
         base_name,
         part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
     })
 }
 
@@ -312,7 +1828,7 @@ struct PartTask {
 }
 
 fn split_file_parts_sequential(
-    app: &AppHandle,
+    ctx: &JobContext,
     input_file: File,
     parts_dir: &Path,
     base_name: &str,
@@ -325,9 +1841,12 @@ fn split_file_parts_sequential(
     compression_level: Option<i64>,
     width: usize,
 ) -> Result<Vec<String>, String> {
-    let mut reader = BufReader::new(input_file);
+    let modified = input_file.metadata().and_then(|meta| meta.modified()).ok();
+    let (read_timer, write_timer) = (IoTimer::default(), IoTimer::default());
+    let mut reader = BufReader::new(TimedRead::new(input_file, read_timer.clone()));
     let mut output_files = Vec::with_capacity(parts);
     let mut processed = 0u64;
+    let group_id = part_comment::group_id(ctx);
 
     for part_index in 1..=parts {
         let remaining = total_size.saturating_sub(processed);
@@ -339,43 +1858,77 @@ fn split_file_parts_sequential(
         let zip_name = format!("{}.part-{}.zip", base_name, part_label);
         let entry_name = format!("{}.part-{}", base_name, part_label);
         let zip_path = parts_dir.join(&zip_name);
+        if part_from_checkpoint(ctx, part_index, &zip_path) {
+            reader
+                .seek_relative(part_size as i64)
+                .map_err(|e| e.to_string())?;
+            processed += part_size;
+            output_files.push(zip_path.to_string_lossy().to_string());
+            continue;
+        }
 
         emit_progress(
-            app,
+            ctx,
             "split-zip",
             processed,
             total_size,
             part_index,
             parts,
-            format!("准备写入第 {} 份", part_index),
+            tr!(PreparePart, part_index),
         );
 
+        let timing = PartTiming::start(&read_timer, &write_timer);
         let zip_file = File::create(&zip_path).map_err(|e| e.to_string())?;
-        let mut zip = ZipWriter::new(BufWriter::new(zip_file));
-        let options = build_file_options(password, part_compression, compression_level);
+        let mut zip = ZipWriter::new(BufWriter::new(TimedWrite::new(zip_file, write_timer.clone())));
+        let options = build_file_options(
+            password,
+            ctx.aes_mode(),
+            part_compression,
+            compression_level,
+            part_size,
+            modified,
+        );
         zip.start_file(entry_name, options)
             .map_err(|e| e.to_string())?;
 
-        copy_n_with_progress(
+        let mut payload = part_comment::HashingWriter::new(&mut zip);
+        copy_n_buffered(
             &mut reader,
-            &mut zip,
+            &mut payload,
             part_size,
+            ctx.memory().buffer_size(),
+            ctx.cancel_token(),
             |delta| {
                 processed += delta;
                 emit_progress(
-                    app,
+                    ctx,
                     "split-zip",
                     processed,
                     total_size,
                     part_index,
                     parts,
-                    "写入中".to_string(),
+                    tr!(Writing),
                 );
             },
         )
         .map_err(|e| e.to_string())?;
+        let sha256 = payload.finish();
+        zip.set_comment(
+            part_comment::PartComment {
+                index: part_index,
+                total: parts,
+                group: Some(group_id.clone()),
+                size: part_size,
+                sha256: password.is_none().then_some(sha256),
+            }
+            .to_text(),
+        );
 
-        zip.finish().map_err(|e| e.to_string())?;
+        zip.finish()
+            .and_then(|mut writer| writer.flush().map_err(ZipError::from))
+            .map_err(|e| e.to_string())?;
+        timing.finish("split-zip", part_index, parts, part_size, &read_timer, &write_timer);
+        mark_part_done(ctx, part_index, &zip_path)?;
         output_files.push(zip_path.to_string_lossy().to_string());
     }
 
@@ -383,7 +1936,7 @@ fn split_file_parts_sequential(
 }
 
 fn split_file_parts_parallel(
-    app: &AppHandle,
+    ctx: &JobContext,
     source_path: &Path,
     parts_dir: &Path,
     base_name: &str,
@@ -417,70 +1970,135 @@ fn split_file_parts_parallel(
         });
     }
 
-    let output_files = Arc::new(Mutex::new(vec![String::new(); parts]));
-    let processed_total = Arc::new(AtomicU64::new(0));
+    // 检查点中已完成的分片不再重写
+    let mut output_files = vec![String::new(); parts];
+    let mut resumed = 0u64;
+    tasks.retain(|task| {
+        if !part_from_checkpoint(ctx, task.index, &task.zip_path) {
+            return true;
+        }
+        output_files[task.index - 1] = task.zip_path.to_string_lossy().to_string();
+        resumed += task.size;
+        false
+    });
+    let output_files = Arc::new(Mutex::new(output_files));
+    let processed_total = Arc::new(AtomicU64::new(resumed));
+    let modified = fs::metadata(source_path).and_then(|meta| meta.modified()).ok();
     let last_emit = Arc::new(Mutex::new(Instant::now()));
 
     emit_progress(
-        app,
+        ctx,
         "split-zip",
-        0,
+        resumed,
         total_size,
         if parts > 0 { 1 } else { 0 },
         parts,
-        "并行压缩中".to_string(),
+        tr!(ParallelCompressing),
     );
 
     let max_threads = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
-    let concurrency = cmp::min(max_threads, tasks.len().max(1));
+    let (concurrency, buffer_size) = ctx.memory().plan(
+        cmp::min(max_threads, tasks.len().max(1)),
+        memory::zip_worker_overhead(part_compression, compression_level),
+        1,
+    );
+    log::debug!("并行压缩：{} 个线程，缓冲区 {} 字节", concurrency, buffer_size);
+    let background = ctx.background_priority();
     let pool = rayon::ThreadPoolBuilder::new()
         .num_threads(concurrency)
+        .start_handler(move |_| {
+            if background {
+                priority::lower_current_thread();
+            }
+        })
         .build()
         .map_err(|e| e.to_string())?;
 
+    let group_id = part_comment::group_id(ctx);
     let result = pool.install(|| {
         tasks.par_iter().try_for_each(|task| {
+            ctx.check_cancelled()?;
+            let (read_timer, write_timer) = (IoTimer::default(), IoTimer::default());
+            let timing = PartTiming::start(&read_timer, &write_timer);
             let mut source = File::open(source_path).map_err(|e| e.to_string())?;
             source
                 .seek(SeekFrom::Start(task.offset))
                 .map_err(|e| e.to_string())?;
-            let mut reader = BufReader::new(source);
+            let mut reader = BufReader::new(TimedRead::new(source, read_timer.clone()));
 
             let zip_file = File::create(&task.zip_path).map_err(|e| e.to_string())?;
-            let mut zip = ZipWriter::new(BufWriter::new(zip_file));
-            let options = build_file_options(password, part_compression, compression_level);
+            let mut zip =
+                ZipWriter::new(BufWriter::new(TimedWrite::new(zip_file, write_timer.clone())));
+            let options = build_file_options(
+                password,
+                ctx.aes_mode(),
+                part_compression,
+                compression_level,
+                task.size,
+                modified,
+            );
             zip.start_file(task.entry_name.as_str(), options)
                 .map_err(|e| e.to_string())?;
 
             let processed_total = processed_total.clone();
             let last_emit = last_emit.clone();
-            let app = app.clone();
-            copy_n_with_progress(&mut reader, &mut zip, task.size, |delta| {
-                let current = processed_total.fetch_add(delta, Ordering::Relaxed) + delta;
-                if let Ok(mut last) = last_emit.lock() {
-                    let now = Instant::now();
-                    if current >= total_size
-                        || now.duration_since(*last) >= Duration::from_millis(120)
-                    {
-                        *last = now;
-                        emit_progress(
-                            &app,
-                            "split-zip",
-                            current,
-                            total_size,
-                            task.index,
-                            parts,
-                            "并行压缩中".to_string(),
-                        );
+            let ctx = ctx.clone();
+            let mut payload = part_comment::HashingWriter::new(&mut zip);
+            copy_n_buffered(
+                &mut reader,
+                &mut payload,
+                task.size,
+                buffer_size,
+                ctx.cancel_token(),
+                |delta| {
+                    let current = processed_total.fetch_add(delta, Ordering::Relaxed) + delta;
+                    if let Ok(mut last) = last_emit.lock() {
+                        let now = Instant::now();
+                        if current >= total_size
+                            || now.duration_since(*last) >= Duration::from_millis(120)
+                        {
+                            *last = now;
+                            emit_progress(
+                                &ctx,
+                                "split-zip",
+                                current,
+                                total_size,
+                                task.index,
+                                parts,
+                                tr!(ParallelCompressing),
+                            );
+                        }
                     }
-                }
-            })
+                },
+            )
             .map_err(|e| e.to_string())?;
+            let sha256 = payload.finish();
+            zip.set_comment(
+                part_comment::PartComment {
+                    index: task.index,
+                    total: parts,
+                    group: Some(group_id.clone()),
+                    size: task.size,
+                    sha256: password.is_none().then_some(sha256),
+                }
+                .to_text(),
+            );
 
-            zip.finish().map_err(|e| e.to_string())?;
+            zip.finish()
+                .and_then(|mut writer| writer.flush().map_err(ZipError::from))
+                .map_err(|e| e.to_string())?;
+            timing.finish(
+                "split-zip",
+                task.index,
+                parts,
+                task.size,
+                &read_timer,
+                &write_timer,
+            );
+            mark_part_done(&ctx, task.index, &task.zip_path)?;
 
             {
-                let mut guard = output_files.lock().map_err(|_| "输出列表被锁定".to_string())?;
+                let mut guard = output_files.lock().map_err(|_| tr!(OutputListLocked))?;
                 guard[task.index - 1] = task.zip_path.to_string_lossy().to_string();
             }
             Ok::<(), String>(())
@@ -490,23 +2108,23 @@ fn split_file_parts_parallel(
     result?;
 
     emit_progress(
-        app,
+        ctx,
         "split-zip",
         total_size,
         total_size,
         parts,
         parts,
-        "完成".to_string(),
+        tr!(Done),
     );
 
     Arc::try_unwrap(output_files)
-        .map_err(|_| "输出列表无法回收".to_string())?
+        .map_err(|_| tr!(OutputListUnavailable))?
         .into_inner()
-        .map_err(|_| "输出列表被锁定".to_string())
+        .map_err(|_| tr!(OutputListLocked))
 }
 
 fn zip_then_split(
-    app: &AppHandle,
+    ctx: &JobContext,
     input_path: &Path,
     output_dir: &Path,
     split_by: &str,
@@ -515,153 +2133,759 @@ fn zip_then_split(
     password: Option<&str>,
     overwrite_parts: bool,
     compression_level: Option<i64>,
+    hash_algorithm: integrity::HashAlgorithm,
+    numbered_volumes: bool,
+    encrypt_names: bool,
 ) -> Result<SplitResult, String> {
     let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
     let is_dir = metadata.is_dir();
+    let encrypt_names = encrypt_names && password.is_some();
 
-    let base_name = file_base_name(input_path)?;
+    let base_name = output_base_name(ctx, input_path)?;
     let parts_dir = output_dir.join(format!("{}.parts", base_name));
-    ensure_parts_dir(&parts_dir, overwrite_parts)?;
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
     let zip_path = output_dir.join(format!("{}.zip", base_name));
-    if is_dir {
-        zip_directory(
-            app,
+    let archive = TempFile::new(ctx, zip_path.clone(), false);
+    if !archive_from_checkpoint(ctx, &zip_path) {
+        // 加密文件名时先打成不加密的内层 zip，整个加密后再放进外层 zip
+        let inner_path = output_dir.join(format!("{}.names.zip", base_name));
+        let inner = encrypt_names.then(|| TempFile::new(ctx, inner_path, true));
+        let (packed_path, entry_password) = match &inner {
+            Some(inner) => (inner.path().to_path_buf(), None),
+            None => (zip_path.clone(), password),
+        };
+        if is_dir {
+            zip_directory(
+                ctx,
+                input_path,
+                &packed_path,
+                entry_password,
+                ctx.compression(),
+                compression_level,
+                "zip",
+            )?;
+        } else {
+            zip_single_file(
+                ctx,
+                input_path,
+                &packed_path,
+                &base_name,
+                entry_password,
+                compression_level,
+            )?;
+        }
+        if let (Some(inner), Some(password)) = (inner, password) {
+            hidden_names::wrap(ctx, inner.path(), &zip_path, password)?;
+            inner.remove();
+        }
+        mark_archive_done(ctx, &zip_path)?;
+    }
+
+    let (parts, output_files) = split_raw_file(
+        ctx,
+        &zip_path,
+        &parts_dir,
+        part_stem(&format!("{}.zip", base_name), numbered_volumes).as_str(),
+        split_by,
+        size_bytes,
+        count,
+    )?;
+
+    finish_checkpoint(ctx);
+    // 追加清单会重写整个文件，先写追加清单再写入校验值
+    let part_size = if split_by == "size" { size_bytes } else { None };
+    // 追加清单与条目校验值会列出文件名，加密文件名时都不写
+    let manifest = if is_dir && !encrypt_names {
+        append::write_initial_manifest(
+            ctx,
             input_path,
+            &parts_dir,
+            &base_name,
+            part_size,
+            &output_files,
+        )
+    } else {
+        Ok(())
+    };
+    let part_sha256s = manifest.and_then(|_| {
+        integrity::write_manifest(
+            ctx,
+            hash_algorithm,
+            &parts_dir,
+            &base_name,
+            "zip-then-split",
             &zip_path,
+            (!encrypt_names).then_some(input_path),
+            &output_files,
+        )
+    });
+    archive.remove();
+    let part_sha256s = part_sha256s?;
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir,
+        base_name,
+        part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+/// 先压缩为 zip，再按 APPNOTE 的分卷格式写成 `<名称>.z01`、`<名称>.z02`……与 `<名称>.zip`，
+/// 常见的解压软件打开 `.zip` 即可直接解压。完整性清单记录的 `payload` 为分卷前的 zip。
+fn spanned_zip(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    size_bytes: Option<u64>,
+    password: Option<&str>,
+    overwrite_parts: bool,
+    compression_level: Option<i64>,
+    hash_algorithm: integrity::HashAlgorithm,
+) -> Result<SplitResult, String> {
+    let segment_size = size_bytes.ok_or_else(|| tr!(SpannedZipNeedsSize))?;
+    let is_dir = fs::metadata(input_path).map_err(|e| e.to_string())?.is_dir();
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+    let zip_path = output_dir.join(format!("{}.zip", base_name));
+    let archive = TempFile::new(ctx, zip_path.clone(), false);
+    if !archive_from_checkpoint(ctx, &zip_path) {
+        if is_dir {
+            zip_directory(
+                ctx,
+                input_path,
+                &zip_path,
+                password,
+                ctx.compression(),
+                compression_level,
+                "zip",
+            )?;
+        } else {
+            zip_single_file(ctx, input_path, &zip_path, &base_name, password, compression_level)?;
+        }
+        mark_archive_done(ctx, &zip_path)?;
+    }
+
+    let output_files = spanned::span_zip(ctx, &zip_path, &parts_dir, &base_name, segment_size)?;
+    finish_checkpoint(ctx);
+    let part_sha256s = integrity::write_manifest(
+        ctx,
+        hash_algorithm,
+        &parts_dir,
+        &base_name,
+        "spanned-zip",
+        &zip_path,
+        Some(input_path),
+        &output_files,
+    );
+    archive.remove();
+    let part_sha256s = part_sha256s?;
+
+    Ok(SplitResult {
+        parts: output_files.len(),
+        output_files,
+        is_dir,
+        base_name,
+        part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+/// 把单个文件压缩为只含一个条目 `entry_name` 的 zip，进度阶段为 `zip`。
+fn zip_single_file(
+    ctx: &JobContext,
+    input_path: &Path,
+    zip_path: &Path,
+    entry_name: &str,
+    password: Option<&str>,
+    compression_level: Option<i64>,
+) -> Result<(), String> {
+    let input_file = File::open(input_path).map_err(|e| e.to_string())?;
+    let metadata = input_file.metadata().map_err(|e| e.to_string())?;
+    let total_size = metadata.len();
+    let modified = metadata.modified().ok();
+    if total_size == 0 {
+        return Err(tr!(EmptyInputFile));
+    }
+    emit_progress(
+        ctx,
+        "zip",
+        0,
+        total_size,
+        0,
+        0,
+        tr!(CompressStarted),
+    );
+
+    let mut reader = BufReader::new(input_file);
+    let zip_file = File::create(zip_path).map_err(|e| e.to_string())?;
+    let mut zip = ZipWriter::new(BufWriter::new(zip_file));
+    let options = build_file_options(
+        password,
+        ctx.aes_mode(),
+        ctx.compression(),
+        compression_level,
+        total_size,
+        modified,
+    );
+    zip.start_file(entry_name, options)
+        .map_err(|e| e.to_string())?;
+
+    let mut processed = 0u64;
+    let buffer_size = ctx.memory().buffer_size();
+    copy_n_buffered(
+        &mut reader,
+        &mut zip,
+        total_size,
+        buffer_size,
+        ctx.cancel_token(),
+        |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "zip",
+                processed,
+                total_size,
+                0,
+                0,
+                tr!(Compressing),
+            );
+        },
+    )
+    .map_err(|e| e.to_string())?;
+
+    zip.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn sevenz_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    password: Option<&str>,
+    overwrite_parts: bool,
+    compression_level: Option<i64>,
+    seven_zip_volumes: bool,
+) -> Result<SplitResult, String> {
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    let is_dir = metadata.is_dir();
+    if !is_dir && metadata.len() == 0 {
+        return Err(tr!(EmptyInputFile));
+    }
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+    let archive_path = output_dir.join(format!("{}.7z", base_name));
+    let archive = TempFile::new(ctx, archive_path.clone(), false);
+    if !archive_from_checkpoint(ctx, &archive_path) {
+        sevenz::sevenz_path(
+            ctx,
+            input_path,
+            &archive_path,
             password,
-            CompressionMethod::Deflated,
             compression_level,
-            "zip",
+            "7z",
         )?;
+        mark_archive_done(ctx, &archive_path)?;
+    }
+
+    // 7-Zip 分卷的命名：`<名称>.7z.001`
+    let (parts, output_files) = split_raw_file(
+        ctx,
+        &archive_path,
+        &parts_dir,
+        part_stem(&format!("{}.7z", base_name), seven_zip_volumes).as_str(),
+        split_by,
+        size_bytes,
+        count,
+    )?;
+
+    finish_checkpoint(ctx);
+    archive.remove();
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir,
+        base_name,
+        part_sha256s: Vec::new(),
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+/// 先打包为 tar 并压缩（保留 Unix 权限、属主与符号链接），再切分。不支持密码。
+fn tar_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    overwrite_parts: bool,
+    compression: tar::TarCompression,
+    numbered_volumes: bool,
+) -> Result<SplitResult, String> {
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    let is_dir = metadata.is_dir();
+    if !is_dir && metadata.len() == 0 {
+        return Err(tr!(EmptyInputFile));
+    }
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+    let archive_name = format!("{}.{}", base_name, compression.extension());
+    let archive_path = output_dir.join(&archive_name);
+    let archive = TempFile::new(ctx, archive_path.clone(), false);
+    if !archive_from_checkpoint(ctx, &archive_path) {
+        tar::tar_path(ctx, input_path, &archive_path, compression, "tar")?;
+        mark_archive_done(ctx, &archive_path)?;
+    }
+
+    let (parts, output_files) = split_raw_file(
+        ctx,
+        &archive_path,
+        &parts_dir,
+        part_stem(&archive_name, numbered_volumes).as_str(),
+        split_by,
+        size_bytes,
+        count,
+    )?;
+
+    finish_checkpoint(ctx);
+    archive.remove();
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir,
+        base_name,
+        part_sha256s: Vec::new(),
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+/// 不压缩、不加容器，直接把文件切成 `<名称>.part-001`、`part-002`……，
+/// 拼接即得原文件（`cat`、`copy /b` 均可）。完整性清单记录原文件与每份分片的校验值。
+fn raw_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    overwrite_parts: bool,
+    hash_algorithm: integrity::HashAlgorithm,
+    numbered_volumes: bool,
+) -> Result<SplitResult, String> {
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    if metadata.is_dir() {
+        return Err(tr!(RawSplitNeedsFile));
+    }
+    if metadata.len() == 0 {
+        return Err(tr!(EmptyInputFile));
+    }
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+    let (parts, output_files) = split_raw_file(
+        ctx,
+        input_path,
+        &parts_dir,
+        part_stem(&base_name, numbered_volumes).as_str(),
+        split_by,
+        size_bytes,
+        count,
+    )?;
+    let part_sha256s = integrity::write_manifest(
+        ctx,
+        hash_algorithm,
+        &parts_dir,
+        &base_name,
+        "raw-split",
+        input_path,
+        None,
+        &output_files,
+    )?;
+    finish_checkpoint(ctx);
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir: false,
+        base_name,
+        part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
+
+fn zstd_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+    password: Option<&str>,
+    overwrite_parts: bool,
+    compression_level: Option<i64>,
+    hash_algorithm: integrity::HashAlgorithm,
+    numbered_volumes: bool,
+    use_dictionary: bool,
+) -> Result<SplitResult, String> {
+    if password.is_some() {
+        return Err(tr!(ZstdNoPassword));
+    }
+    // 管道或套接字只能顺序读取一次，长度未知，边读边压缩
+    let is_stream = source::is_stream_source(input_path);
+    let is_dir = !is_stream && fs::metadata(input_path).map_err(|e| e.to_string())?.is_dir();
+
+    let base_name = output_base_name(ctx, input_path)?;
+    let parts_dir = output_dir.join(format!("{}.parts", base_name));
+    let ctx = &prepare_parts_dir(ctx, &parts_dir, overwrite_parts)?;
+
+    let payload_name = if is_dir {
+        format!("{}.zip", base_name)
     } else {
-        let input_file = File::open(input_path).map_err(|e| e.to_string())?;
-        let total_size = input_file.metadata().map_err(|e| e.to_string())?.len();
-        if total_size == 0 {
-            return Err("输入文件大小为 0，无法切分".to_string());
+        base_name.clone()
+    };
+    let archive_name = format!("{}.zst", payload_name);
+    let archive_path = output_dir.join(&archive_name);
+    let archive = TempFile::new(ctx, archive_path.clone(), false);
+    let level = compression_level.unwrap_or(3).clamp(1, 19) as i32;
+    if !archive_from_checkpoint(ctx, &archive_path) {
+        let dictionary = if use_dictionary && is_dir {
+            emit_progress(ctx, "zstd", 0, 0, 0, 0, tr!(TrainingDictionary));
+            let dictionary = dictionary::train(ctx, input_path)?;
+            if let Some(dictionary) = &dictionary {
+                dictionary::write(ctx, &parts_dir, &archive_name, dictionary)?;
+            }
+            dictionary
+        } else {
+            if use_dictionary {
+                log::info!("输入不是目录，不训练 zstd 字典：{}", input_path.display());
+            }
+            None
+        };
+        let frame_size = if dictionary.is_some() {
+            dictionary::DICTIONARY_FRAME_SIZE
+        } else {
+            seekable::DEFAULT_FRAME_SIZE
+        };
+
+        // 目录先以 Store 方式打包成 zip，压缩交给 zstd，解压后仍可按 zip 随机读取
+        let payload_zip = is_dir.then(|| TempFile::new(ctx, output_dir.join(&payload_name), false));
+        let source_path = match &payload_zip {
+            Some(zip) => {
+                zip_directory(
+                    ctx,
+                    input_path,
+                    zip.path(),
+                    None,
+                    CompressionMethod::Stored,
+                    None,
+                    "pack-dir",
+                )?;
+                zip.path().to_path_buf()
+            }
+            None => input_path.to_path_buf(),
+        };
+
+        let total_size = if is_stream {
+            0
+        } else {
+            fs::metadata(&source_path)
+                .map_err(|e| e.to_string())?
+                .len()
+        };
+        if total_size == 0 && !is_stream {
+            return Err(tr!(EmptyInputFile));
         }
-        emit_progress(
-            app,
-            "zip",
-            0,
-            total_size,
-            0,
-            0,
-            "开始压缩".to_string(),
-        );
 
-        let mut reader = BufReader::new(input_file);
-        let zip_file = File::create(&zip_path).map_err(|e| e.to_string())?;
-        let mut zip = ZipWriter::new(BufWriter::new(zip_file));
-        let options =
-            build_file_options(password, CompressionMethod::Deflated, compression_level);
-        zip.start_file(base_name.clone(), options)
+        if is_stream && split_by == "size" {
+            // 按大小切分时无需知道总长度，压缩结果直接写入分片
+            let (parts, output_files, table) = zstd_stream_to_parts(
+                ctx,
+                &source_path,
+                &parts_dir,
+                &part_stem(&archive_name, numbered_volumes),
+                size_bytes,
+                level,
+            )?;
+            let part_sha256s = integrity::write_part_manifest(
+                ctx,
+                hash_algorithm,
+                &parts_dir,
+                &base_name,
+                "zstd-then-split",
+                &output_files,
+            )?;
+            seekable::record_frame_index(&parts_dir, &base_name, &table)?;
+            return Ok(SplitResult {
+                parts,
+                output_files,
+                is_dir,
+                base_name,
+                part_sha256s,
+                recovery_files: Vec::new(),
+                restore_files: Vec::new(),
+            });
+        }
+
+        {
+            let mut reader =
+                BufReader::new(source::open_source(&source_path).map_err(|e| e.to_string())?);
+            let mut writer =
+                BufWriter::new(File::create(&archive_path).map_err(|e| e.to_string())?);
+            let mut processed = 0u64;
+            emit_progress(ctx, "zstd", 0, total_size, 0, 0, tr!(CompressStarted));
+            seekable::compress_seekable(
+                &mut reader,
+                &mut writer,
+                level,
+                frame_size,
+                seekable::max_workers(ctx.memory(), frame_size),
+                dictionary.as_deref(),
+                ctx.background_priority(),
+                |delta| {
+                    processed += delta;
+                    if is_stream {
+                        emit_stream_progress(ctx, "zstd", processed, 0, tr!(Compressing));
+                    } else {
+                        emit_progress(
+                            ctx,
+                            "zstd",
+                            processed,
+                            total_size,
+                            0,
+                            0,
+                            tr!(Compressing),
+                        );
+                    }
+                },
+            )
             .map_err(|e| e.to_string())?;
+            writer.flush().map_err(|e| e.to_string())?;
+            if processed == 0 {
+                return Err(tr!(EmptyInput));
+            }
+        }
+        if let Some(zip) = payload_zip {
+            zip.remove();
+        }
+        mark_archive_done(ctx, &archive_path)?;
+    }
+
+    let (parts, output_files) = split_raw_file(
+        ctx,
+        &archive_path,
+        &parts_dir,
+        part_stem(&archive_name, numbered_volumes).as_str(),
+        split_by,
+        size_bytes,
+        count,
+    )?;
+
+    let table = seekable::SeekTable::read_from(
+        &mut File::open(&archive_path).map_err(|e| e.to_string())?,
+    )
+    .map_err(|e| e.to_string())?;
+
+    finish_checkpoint(ctx);
+    let part_sha256s = integrity::write_part_manifest(
+        ctx,
+        hash_algorithm,
+        &parts_dir,
+        &base_name,
+        "zstd-then-split",
+        &output_files,
+    )
+    .and_then(|sha256s| {
+        seekable::record_frame_index(&parts_dir, &base_name, &table).map(|()| sha256s)
+    });
+    archive.remove();
+    let part_sha256s = part_sha256s?;
+
+    Ok(SplitResult {
+        parts,
+        output_files,
+        is_dir,
+        base_name,
+        part_sha256s,
+        recovery_files: Vec::new(),
+        restore_files: Vec::new(),
+    })
+}
 
-        let mut processed = 0u64;
-        copy_n_with_progress(&mut reader, &mut zip, total_size, |delta| {
+/// 长度未知的输入边压缩边切分，写满一份即开始下一份，返回实际份数、分片路径与帧索引。
+fn zstd_stream_to_parts(
+    ctx: &JobContext,
+    source_path: &Path,
+    parts_dir: &Path,
+    part_stem: &str,
+    size_bytes: Option<u64>,
+    level: i32,
+) -> Result<(usize, Vec<String>, seekable::SeekTable), String> {
+    let part_size = size_bytes.ok_or(Msg::MissingPartSize.text())?;
+    if part_size == 0 {
+        return Err(tr!(PartSizeNotPositive));
+    }
+    let mut reader = BufReader::new(source::open_source(source_path).map_err(|e| e.to_string())?);
+    let mut writer =
+        stream_split::PartWriter::to_dir(part_size, parts_dir.to_path_buf(), part_stem.to_string());
+    let current_part = writer.current_part();
+    let mut processed = 0u64;
+    emit_stream_progress(ctx, "zstd", 0, 1, tr!(CompressStarted));
+    let table = seekable::compress_seekable(
+        &mut reader,
+        &mut writer,
+        level,
+        seekable::DEFAULT_FRAME_SIZE,
+        seekable::max_workers(ctx.memory(), seekable::DEFAULT_FRAME_SIZE),
+        None,
+        ctx.background_priority(),
+        |delta| {
             processed += delta;
-            emit_progress(
-                app,
-                "zip",
+            emit_stream_progress(
+                ctx,
+                "zstd",
                 processed,
-                total_size,
-                0,
-                0,
-                "压缩中".to_string(),
+                current_part.load(Ordering::Relaxed),
+                tr!(CompressingToParts),
             );
-        })
-        .map_err(|e| e.to_string())?;
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    let files = writer.finish().map_err(|e| e.to_string())?;
+    if processed == 0 {
+        for file in &files {
+            let _ = fs::remove_file(file);
+        }
+        return Err(tr!(EmptyInput));
+    }
+    let output_files: Vec<String> = files
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    emit_progress(
+        ctx,
+        "zstd",
+        processed,
+        processed,
+        output_files.len(),
+        output_files.len(),
+        tr!(DoneWithParts, output_files.len()),
+    );
+    Ok((output_files.len(), output_files, table))
+}
 
-        zip.finish().map_err(|e| e.to_string())?;
+/// 按字节切分的分片名前缀：默认为 `<name>.part-`，经典分卷命名时为 `<name>.`（得到 `<name>.001`）。
+fn part_stem(name: &str, numbered_volumes: bool) -> String {
+    if numbered_volumes {
+        format!("{}.", name)
+    } else {
+        format!("{}.part-", name)
     }
+}
 
-    let zip_size = fs::metadata(&zip_path)
+/// 把 `source_path` 按字节切成分片，分片命名为 `<part_stem><序号>`，
+/// 如 `name.zip.part-` 得到 `name.zip.part-001`，经典分卷命名为 `name.zip.` 得到 `name.zip.001`。
+fn split_raw_file(
+    ctx: &JobContext,
+    source_path: &Path,
+    parts_dir: &Path,
+    part_stem: &str,
+    split_by: &str,
+    size_bytes: Option<u64>,
+    count: Option<u64>,
+) -> Result<(usize, Vec<String>), String> {
+    let source_size = fs::metadata(source_path)
         .map_err(|e| e.to_string())?
         .len();
-    let (chunk_size, parts) = compute_parts(zip_size, split_by, size_bytes, count)?;
+    let (chunk_size, parts) = compute_parts(source_size, split_by, size_bytes, count)?;
     let width = cmp::max(3, parts.to_string().len());
 
-    let mut zip_reader = BufReader::new(File::open(&zip_path).map_err(|e| e.to_string())?);
+    let (read_timer, write_timer) = (IoTimer::default(), IoTimer::default());
+    let source_file = File::open(source_path).map_err(|e| e.to_string())?;
+    let mut source_reader = BufReader::new(TimedRead::new(source_file, read_timer.clone()));
     let mut output_files = Vec::with_capacity(parts);
     let mut split_processed = 0u64;
 
     for part_index in 1..=parts {
-        let remaining = zip_size.saturating_sub(split_processed);
+        let remaining = source_size.saturating_sub(split_processed);
         let part_size = cmp::min(chunk_size, remaining);
         if part_size == 0 && split_by != "count" {
             break;
         }
         let part_label = format_part_index(part_index, width);
-        let part_name = format!("{}.zip.part-{}", base_name, part_label);
+        let part_name = format!("{}{}", part_stem, part_label);
         let part_path = parts_dir.join(&part_name);
+        if part_from_checkpoint(ctx, part_index, &part_path) {
+            source_reader
+                .seek_relative(part_size as i64)
+                .map_err(|e| e.to_string())?;
+            split_processed += part_size;
+            output_files.push(part_path.to_string_lossy().to_string());
+            continue;
+        }
 
         emit_progress(
-            app,
+            ctx,
             "split",
             split_processed,
-            zip_size,
+            source_size,
             part_index,
             parts,
-            format!("准备写入第 {} 份", part_index),
+            tr!(PreparePart, part_index),
         );
 
+        let timing = PartTiming::start(&read_timer, &write_timer);
+        ctx.track_output(&part_path);
         let part_file = File::create(&part_path).map_err(|e| e.to_string())?;
-        let mut writer = BufWriter::new(part_file);
+        let mut writer = BufWriter::new(TimedWrite::new(part_file, write_timer.clone()));
 
-        copy_n_with_progress(&mut zip_reader, &mut writer, part_size, |delta| {
-            split_processed += delta;
-            emit_progress(
-                app,
-                "split",
-                split_processed,
-                zip_size,
-                part_index,
-                parts,
-                "写入中".to_string(),
-            );
-        })
+        let buffer_size = ctx.memory().buffer_size();
+        copy_n_buffered(
+            &mut source_reader,
+            &mut writer,
+            part_size,
+            buffer_size,
+            ctx.cancel_token(),
+            |delta| {
+                split_processed += delta;
+                emit_progress(
+                    ctx,
+                    "split",
+                    split_processed,
+                    source_size,
+                    part_index,
+                    parts,
+                    tr!(Writing),
+                );
+            },
+        )
         .map_err(|e| e.to_string())?;
 
         writer.flush().map_err(|e| e.to_string())?;
+        timing.finish("split", part_index, parts, part_size, &read_timer, &write_timer);
+        mark_part_done(ctx, part_index, &part_path)?;
         output_files.push(part_path.to_string_lossy().to_string());
     }
 
-    let _ = fs::remove_file(&zip_path);
-
-    Ok(SplitResult {
-        parts,
-        output_files,
-        is_dir,
-        base_name,
-        part_sha256s: Vec::new(),
-    })
-}
-
-fn compute_part_sha256s(paths: &[String]) -> Result<Vec<PartSha256>, String> {
-    let mut results = Vec::with_capacity(paths.len());
-    for path in paths {
-        let sha256 = compute_file_sha256(Path::new(path))
-            .map_err(|err| format!("计算 SHA256 失败: {} ({})", path, err))?;
-        results.push(PartSha256 {
-            path: path.clone(),
-            sha256,
-        });
-    }
-    Ok(results)
-}
-
-fn compute_file_sha256(path: &Path) -> Result<String, String> {
-    let mut file = File::open(path).map_err(|e| e.to_string())?;
-    let mut hasher = Sha256::new();
-    let mut buffer = vec![0u8; 8 * 1024 * 1024];
-    loop {
-        let read_len = file.read(&mut buffer).map_err(|e| e.to_string())?;
-        if read_len == 0 {
-            break;
-        }
-        hasher.update(&buffer[..read_len]);
-    }
-    Ok(format!("{:x}", hasher.finalize()))
+    Ok((parts, output_files))
 }
 
 fn compute_parts(
@@ -672,23 +2896,85 @@ fn compute_parts(
 ) -> Result<(u64, usize), String> {
     match split_by {
         "size" => {
-            let size = size_bytes.ok_or("缺少每份大小参数")?;
+            let size = size_bytes.ok_or(Msg::MissingPartSize.text())?;
             if size == 0 {
-                return Err("每份大小必须大于 0".to_string());
+                return Err(tr!(PartSizeNotPositive));
             }
             let parts = div_ceil(total_size, size) as usize;
             Ok((size, parts))
         }
         "count" => {
-            let count = count.ok_or("缺少份数参数")?;
+            let count = count.ok_or(Msg::MissingPartCount.text())?;
             if count == 0 {
-                return Err("份数必须大于 0".to_string());
+                return Err(tr!(PartCountNotPositive));
             }
             let chunk_size = cmp::max(1, div_ceil(total_size, count));
             let parts = count as usize;
             Ok((chunk_size, parts))
         }
-        _ => Err("未知的切分方式".to_string()),
+        _ => Err(tr!(UnknownSplitBy)),
+    }
+}
+
+/// 准备分片目录，返回写分片时使用的上下文。开启续传且目录中有相同参数留下的检查点时保留已写出的
+/// 分片；否则按 `overwrite` 清空目录或报错，开启续传时再新建检查点。
+fn prepare_parts_dir(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    overwrite: bool,
+) -> Result<JobContext, String> {
+    let Some(fingerprint) = ctx.resume_fingerprint() else {
+        ensure_parts_dir(parts_dir, overwrite)?;
+        ctx.track_output(parts_dir);
+        return Ok(ctx.clone());
+    };
+    if let Some(checkpoint) = checkpoint::Checkpoint::load(parts_dir, fingerprint) {
+        log::info!(
+            "从检查点继续打包：{}（已完成 {} 份）",
+            parts_dir.display(),
+            checkpoint.completed_parts()
+        );
+        return Ok(ctx.with_checkpoint(checkpoint));
+    }
+    ensure_parts_dir(parts_dir, overwrite)?;
+    ctx.track_output(parts_dir);
+    Ok(ctx.with_checkpoint(checkpoint::Checkpoint::create(parts_dir, fingerprint)?))
+}
+
+/// 检查点中记录的中间压缩包仍完整时沿用，不再重新打包。
+fn archive_from_checkpoint(ctx: &JobContext, path: &Path) -> bool {
+    let done = ctx
+        .checkpoint()
+        .is_some_and(|checkpoint| checkpoint.archive_done(path));
+    if done {
+        log::info!("沿用检查点中已完成的压缩包：{}", path.display());
+    }
+    done
+}
+
+fn part_from_checkpoint(ctx: &JobContext, index: usize, path: &Path) -> bool {
+    ctx.checkpoint()
+        .is_some_and(|checkpoint| checkpoint.part_done(index, path))
+}
+
+fn mark_archive_done(ctx: &JobContext, path: &Path) -> Result<(), String> {
+    match ctx.checkpoint() {
+        Some(checkpoint) => checkpoint.mark_archive(path),
+        None => Ok(()),
+    }
+}
+
+fn mark_part_done(ctx: &JobContext, index: usize, path: &Path) -> Result<(), String> {
+    match ctx.checkpoint() {
+        Some(checkpoint) => checkpoint.mark_part(index, path),
+        None => Ok(()),
+    }
+}
+
+/// 全部分片写完后删除检查点。
+fn finish_checkpoint(ctx: &JobContext) {
+    if let Some(checkpoint) = ctx.checkpoint() {
+        checkpoint.remove();
     }
 }
 
@@ -696,11 +2982,11 @@ fn ensure_parts_dir(parts_dir: &Path, overwrite: bool) -> Result<(), String> {
     if parts_dir.exists() {
         let metadata = fs::metadata(parts_dir).map_err(|e| e.to_string())?;
         if !metadata.is_dir() {
-            return Err("分片输出路径已存在且不是目录".to_string());
+            return Err(tr!(OutputPathNotDir));
         }
         let is_empty = dir_is_empty(parts_dir)?;
         if !is_empty && !overwrite {
-            return Err("分片输出目录已存在，请确认是否覆盖".to_string());
+            return Err(tr!(OutputDirExists));
         }
         if overwrite && !is_empty {
             fs::remove_dir_all(parts_dir).map_err(|e| e.to_string())?;
@@ -723,49 +3009,114 @@ fn file_base_name(path: &Path) -> Result<String, String> {
     path.file_name()
         .and_then(|name| name.to_str())
         .map(|name| name.to_string())
-        .ok_or_else(|| "无法解析文件名".to_string())
+        .ok_or_else(|| tr!(CannotParseFileName))
+}
+
+/// 输出名称：默认为输入文件名，脚本定义了 `name` 钩子时由脚本决定。
+fn output_base_name(ctx: &JobContext, input_path: &Path) -> Result<String, String> {
+    let base_name = file_base_name(input_path)?;
+    match ctx.script() {
+        Some(script) => script.base_name(&base_name, input_path),
+        None => Ok(base_name),
+    }
+}
+
+/// 打包目录时是否包含该条目（由脚本的 `filter` 钩子决定，默认全部包含）。
+fn include_dir_entry(
+    ctx: &JobContext,
+    root: &Path,
+    path: &Path,
+    is_dir: bool,
+    size: u64,
+) -> Result<bool, String> {
+    let Some(script) = ctx.script() else {
+        return Ok(true);
+    };
+    let rel = path.strip_prefix(root).map_err(|e| e.to_string())?;
+    script.include(&rel.to_string_lossy().replace('\\', "/"), is_dir, size)
 }
 
 fn format_part_index(index: usize, width: usize) -> String {
     format!("{:0width$}", index, width = width)
 }
 
+/// 条目达到此大小时写 Zip64 扩展字段。压缩或加密后的数据可能略大于原始大小，因此比 4 GiB 留出余量。
+const ZIP64_THRESHOLD: u64 = 0xF000_0000;
+
+/// `size` 为条目的原始大小，用于决定是否写 Zip64 扩展字段；目录传 0。`modified` 为源文件的
+/// 修改时间，见 [`entry_times::stamp_zip`]；为 `None` 时记录当前时间。
 fn build_file_options<'a>(
     password: Option<&'a str>,
+    aes_mode: AesMode,
     compression: CompressionMethod,
     compression_level: Option<i64>,
-) -> FileOptions<'a, ()> {
-    let mut options = FileOptions::default().compression_method(compression);
+    size: u64,
+    modified: Option<SystemTime>,
+) -> FullFileOptions<'a> {
+    let mut options = FullFileOptions::default()
+        .compression_method(compression)
+        .large_file(size >= ZIP64_THRESHOLD);
+    if let Some(modified) = modified {
+        options = entry_times::stamp_zip(options, modified);
+    }
     if let Some(level) = compression_level {
-        if matches!(compression, CompressionMethod::Deflated) {
-            options = options.compression_level(Some(level));
+        match compression {
+            CompressionMethod::Deflated => options = options.compression_level(Some(level)),
+            // bzip2 的等级为块大小（100 KiB 的倍数），0 不可用
+            CompressionMethod::Bzip2 => options = options.compression_level(Some(level.clamp(1, 9))),
+            // xz 的等级即 LZMA2 预设 0–9
+            CompressionMethod::Xz => options = options.compression_level(Some(level.clamp(0, 9))),
+            _ => {}
         }
     }
     if let Some(password) = password {
-        options.with_aes_encryption(AesMode::Aes256, password)
+        options.with_aes_encryption(aes_mode, password)
     } else {
         options
     }
 }
 
+/// 打包参数 `aesStrength`（位数）对应的 AES 强度。
+fn parse_aes_strength(bits: u16) -> Option<AesMode> {
+    match bits {
+        128 => Some(AesMode::Aes128),
+        192 => Some(AesMode::Aes192),
+        256 => Some(AesMode::Aes256),
+        _ => None,
+    }
+}
+
+/// 打包参数 `compressionMethod` 对应的 zip 压缩方法。
+fn parse_compression_method(name: &str) -> Option<CompressionMethod> {
+    match name {
+        "deflate" => Some(CompressionMethod::Deflated),
+        "bzip2" => Some(CompressionMethod::Bzip2),
+        "xz" => Some(CompressionMethod::Xz),
+        _ => None,
+    }
+}
+
 fn compute_parts_with_overhead(
     total_size: u64,
     size_bytes: Option<u64>,
     base_name: &str,
     encrypted: bool,
 ) -> Result<(u64, usize), String> {
-    let size = size_bytes.ok_or("缺少每份大小参数")?;
+    let size = size_bytes.ok_or(Msg::MissingPartSize.text())?;
     let mut parts = div_ceil(total_size, size) as usize;
-
-    for _ in 0..5 {
+    let zip64 = size >= ZIP64_THRESHOLD;
+    // 分片数决定序号位数，进而决定条目名与注释的长度
+    let overhead_for = |parts: usize| {
         let width = cmp::max(3, parts.to_string().len());
         let entry_len = base_name.len() + ".part-".len() + width;
-        let overhead = zip_stored_overhead(entry_len, encrypted);
+        let comment_len = part_comment::max_len(parts, size, encrypted);
+        zip_stored_overhead(entry_len, comment_len, encrypted, zip64)
+    };
+
+    for _ in 0..5 {
+        let overhead = overhead_for(parts);
         if size <= overhead {
-            return Err(format!(
-                "每份大小过小，至少需要 {} 字节",
-                overhead + 1
-            ));
+            return Err(tr!(PartSizeTooSmall, overhead + 1));
         }
         let payload = size - overhead;
         let next_parts = div_ceil(total_size, payload) as usize;
@@ -775,36 +3126,53 @@ fn compute_parts_with_overhead(
         parts = next_parts;
     }
 
-    let width = cmp::max(3, parts.to_string().len());
-    let entry_len = base_name.len() + ".part-".len() + width;
-    let overhead = zip_stored_overhead(entry_len, encrypted);
+    let overhead = overhead_for(parts);
     if size <= overhead {
-        return Err(format!(
-            "每份大小过小，至少需要 {} 字节",
-            overhead + 1
-        ));
+        return Err(tr!(PartSizeTooSmall, overhead + 1));
     }
     Ok((size - overhead, parts))
 }
 
-fn zip_stored_overhead(entry_name_len: usize, encrypted: bool) -> u64 {
+/// 一份仅存储的分片 zip 除数据外的最大字节数，`comment_len` 为分片注释的长度。
+fn zip_stored_overhead(
+    entry_name_len: usize,
+    comment_len: usize,
+    encrypted: bool,
+    zip64: bool,
+) -> u64 {
     let name_len = entry_name_len as u64;
     let local_header = 30u64;
     let central_header = 46u64;
     let end_of_central = 22u64;
     let data_descriptor = 16u64;
+    // 本地头与中央目录各一个只含修改时间的扩展时间戳
+    let extended_timestamp = 2 * 9u64;
     let safety = 32u64;
-    let mut overhead =
-        local_header + central_header + end_of_central + data_descriptor + safety + (2 * name_len);
+    let mut overhead = local_header
+        + central_header
+        + end_of_central
+        + data_descriptor
+        + extended_timestamp
+        + safety
+        + (2 * name_len)
+        + comment_len as u64;
     if encrypted {
         // AES 加密的保守额外开销估算，避免分片超过最大大小
         overhead += 64u64;
     }
+    if zip64 {
+        // 本地与中央目录的 Zip64 扩展字段、Zip64 目录结束记录及其定位记录，数据描述符的大小也改为 8 字节
+        let local_extra = 20u64;
+        let central_extra = 28u64;
+        let end_records = 56u64 + 20u64;
+        let data_descriptor = 8u64;
+        overhead += local_extra + central_extra + end_records + data_descriptor;
+    }
     overhead
 }
 
 fn zip_directory(
-    app: &AppHandle,
+    ctx: &JobContext,
     dir_path: &Path,
     zip_path: &Path,
     password: Option<&str>,
@@ -812,7 +3180,7 @@ fn zip_directory(
     compression_level: Option<i64>,
     phase: &str,
 ) -> Result<(), String> {
-    let total_size = dir_total_size(dir_path)?;
+    let total_size = dir_total_size(ctx, dir_path, dir_path)?;
     let zip_file = File::create(zip_path).map_err(|e| e.to_string())?;
     let mut zip = ZipWriter::new(BufWriter::new(zip_file));
     let mut processed = 0u64;
@@ -820,17 +3188,17 @@ fn zip_directory(
     let root_name = dir_path
         .file_name()
         .and_then(|name| name.to_str())
-        .ok_or_else(|| "无法解析目录名".to_string())?
+        .ok_or_else(|| tr!(CannotParseDirName))?
         .to_string();
 
     emit_progress(
-        app,
+        ctx,
         phase,
         0,
         total_size,
         0,
         0,
-        "打包目录中".to_string(),
+        tr!(PackingDir),
     );
 
     add_dir_entries(
@@ -840,7 +3208,7 @@ fn zip_directory(
         password,
         compression,
         compression_level,
-        app,
+        ctx,
         phase,
         &mut processed,
         total_size,
@@ -858,7 +3226,7 @@ fn add_dir_entries(
     password: Option<&str>,
     compression: CompressionMethod,
     compression_level: Option<i64>,
-    app: &AppHandle,
+    ctx: &JobContext,
     phase: &str,
     processed: &mut u64,
     total_size: u64,
@@ -867,6 +3235,7 @@ fn add_dir_entries(
     let mut has_entry = false;
     let entries = fs::read_dir(current).map_err(|e| e.to_string())?;
     for entry in entries {
+        ctx.check_cancelled()?;
         let entry = entry.map_err(|e| e.to_string())?;
         let path = entry.path();
         let rel = path
@@ -881,13 +3250,27 @@ fn add_dir_entries(
                 rel.to_string_lossy().replace('\\', "/")
             )
         };
+        let is_dir = path.is_dir();
+        let metadata = entry.metadata().map_err(|e| e.to_string())?;
+        let size = if is_dir { 0 } else { metadata.len() };
+        let modified = metadata.modified().ok();
+        if !include_dir_entry(ctx, root, &path, is_dir, size)? {
+            continue;
+        }
         has_entry = true;
 
-        if path.is_dir() {
+        if is_dir {
             let dir_name = format!("{}/", rel_path.trim_end_matches('/'));
             zip.add_directory(
                 dir_name,
-                build_file_options(password, compression, compression_level),
+                build_file_options(
+                    password,
+                    ctx.aes_mode(),
+                    compression,
+                    compression_level,
+                    0,
+                    modified,
+                ),
             )
                 .map_err(|e| e.to_string())?;
             add_dir_entries(
@@ -897,7 +3280,7 @@ fn add_dir_entries(
                 password,
                 compression,
                 compression_level,
-                app,
+                ctx,
                 phase,
                 processed,
                 total_size,
@@ -906,21 +3289,29 @@ fn add_dir_entries(
         } else if path.is_file() {
             zip.start_file(
                 rel_path,
-                build_file_options(password, compression, compression_level),
+                build_file_options(
+                    password,
+                    ctx.aes_mode(),
+                    compression,
+                    compression_level,
+                    size,
+                    modified,
+                ),
             )
                 .map_err(|e| e.to_string())?;
-            let file_size = fs::metadata(&path).map_err(|e| e.to_string())?.len();
+            let file_size = size;
             let mut file = BufReader::new(File::open(&path).map_err(|e| e.to_string())?);
-            copy_n_with_progress(&mut file, zip, file_size, |delta| {
+            let buffer_size = ctx.memory().buffer_size();
+            copy_n_buffered(&mut file, zip, file_size, buffer_size, ctx.cancel_token(), |delta| {
                 *processed += delta;
                 emit_progress(
-                    app,
+                    ctx,
                     phase,
                     *processed,
                     total_size,
                     0,
                     0,
-                    "打包目录中".to_string(),
+                    tr!(PackingDir),
                 );
             })
             .map_err(|e| e.to_string())?;
@@ -929,9 +3320,17 @@ fn add_dir_entries(
 
     if !has_entry {
         let dir_name = format!("{}/", root_name.trim_end_matches('/'));
+        let modified = fs::metadata(current).and_then(|meta| meta.modified()).ok();
         zip.add_directory(
             dir_name,
-            build_file_options(password, compression, compression_level),
+            build_file_options(
+                password,
+                ctx.aes_mode(),
+                compression,
+                compression_level,
+                0,
+                modified,
+            ),
         )
             .map_err(|e| e.to_string())?;
     }
@@ -939,15 +3338,18 @@ fn add_dir_entries(
     Ok(())
 }
 
-fn dir_total_size(path: &Path) -> Result<u64, String> {
+fn dir_total_size(ctx: &JobContext, root: &Path, path: &Path) -> Result<u64, String> {
     let mut total = 0u64;
     let entries = fs::read_dir(path).map_err(|e| e.to_string())?;
     for entry in entries {
         let entry = entry.map_err(|e| e.to_string())?;
         let entry_path = entry.path();
         let meta = entry.metadata().map_err(|e| e.to_string())?;
+        if !include_dir_entry(ctx, root, &entry_path, meta.is_dir(), meta.len())? {
+            continue;
+        }
         if meta.is_dir() {
-            total += dir_total_size(&entry_path)?;
+            total += dir_total_size(ctx, root, &entry_path)?;
         } else if meta.is_file() {
             total += meta.len();
         }
@@ -955,21 +3357,47 @@ fn dir_total_size(path: &Path) -> Result<u64, String> {
     Ok(total)
 }
 
+fn memory_budget(max_memory: Option<u64>) -> Result<MemoryBudget, String> {
+    if let Some(max) = max_memory {
+        if max < memory::MIN_MEMORY {
+            return Err(tr!(MaxMemoryTooSmall, memory::MIN_MEMORY));
+        }
+    }
+    Ok(MemoryBudget::new(max_memory))
+}
+
+/// 复制 `remaining` 字节，每读一块之前检查 `cancel`，已取消时返回取消错误。
 fn copy_n_with_progress<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    remaining: u64,
+    cancel: &CancelToken,
+    progress: impl FnMut(u64),
+) -> io::Result<()> {
+    copy_n_buffered(reader, writer, remaining, memory::DEFAULT_BUFFER_SIZE, cancel, progress)
+}
+
+/// 与 [`copy_n_with_progress`] 相同，但使用指定大小的缓冲区（受内存上限约束）。
+fn copy_n_buffered<R: Read, W: Write>(
     reader: &mut R,
     writer: &mut W,
     mut remaining: u64,
+    buffer_size: usize,
+    cancel: &CancelToken,
     mut progress: impl FnMut(u64),
 ) -> io::Result<()> {
-    let mut buffer = vec![0u8; 8 * 1024 * 1024];
+    let mut buffer = vec![0u8; buffer_size];
     while remaining > 0 {
+        if cancel.is_cancelled() {
+            return Err(io::Error::other(Msg::JobCancelled.text()));
+        }
         let buffer_len = buffer.len() as u64;
         let read_len =
             reader.read(&mut buffer[..cmp::min(remaining, buffer_len) as usize])?;
         if read_len == 0 {
             return Err(io::Error::new(
                 io::ErrorKind::UnexpectedEof,
-                "读取到意外的文件结束",
+                Msg::UnexpectedEof.text(),
             ));
         }
         writer.write_all(&buffer[..read_len])?;
@@ -979,8 +3407,74 @@ fn copy_n_with_progress<R: Read, W: Write>(
     Ok(())
 }
 
+/// 删除中间文件或临时目录。开启 `secureDelete` 或 `sensitive`（加密时生成的明文中间文件）时
+/// 先覆写再删除；删除失败只记录日志。
+fn remove_temp(ctx: &JobContext, path: &Path, sensitive: bool) {
+    let removed = if ctx.secure_delete() || sensitive {
+        wipe::wipe_path(ctx, path, tr!(WipingTemp))
+    } else if path.is_dir() {
+        fs::remove_dir_all(path).map_err(|e| e.to_string())
+    } else {
+        fs::remove_file(path).map_err(|e| e.to_string())
+    };
+    match removed {
+        Ok(()) => {}
+        Err(err) if path.exists() => {
+            log::warn!("删除中间文件失败：{}（{}）", path.display(), err)
+        }
+        Err(_) => {}
+    }
+}
+
+/// 打包时的中间文件：出错返回时删除，成功后以 [`TempFile::remove`] 删除。检查点已记为完成的
+/// 中间压缩包出错时保留，以相同参数再次打包时从它继续切分。
+struct TempFile<'a> {
+    ctx: &'a JobContext,
+    path: PathBuf,
+    sensitive: bool,
+    armed: bool,
+}
+
+impl<'a> TempFile<'a> {
+    fn new(ctx: &'a JobContext, path: PathBuf, sensitive: bool) -> Self {
+        ctx.track_output(&path);
+        TempFile {
+            ctx,
+            path,
+            sensitive,
+            armed: true,
+        }
+    }
+
+    fn path(&self) -> &Path {
+        &self.path
+    }
+
+    fn remove(mut self) {
+        self.armed = false;
+        remove_temp(self.ctx, &self.path, self.sensitive);
+    }
+}
+
+impl Drop for TempFile<'_> {
+    fn drop(&mut self) {
+        if !self.armed || fs::symlink_metadata(&self.path).is_err() {
+            return;
+        }
+        if self
+            .ctx
+            .checkpoint()
+            .is_some_and(|checkpoint| checkpoint.archive_done(&self.path))
+        {
+            log::info!("保留已完成的中间压缩包以便续传：{}", self.path.display());
+            return;
+        }
+        remove_temp(self.ctx, &self.path, self.sensitive);
+    }
+}
+
 fn emit_progress(
-    app: &AppHandle,
+    ctx: &JobContext,
     phase: &str,
     processed_bytes: u64,
     total_bytes: u64,
@@ -991,12 +3485,32 @@ fn emit_progress(
     let payload = ProgressPayload {
         phase: phase.to_string(),
         processed_bytes,
-        total_bytes,
+        total_bytes: Some(total_bytes),
         part_index,
         part_total,
         message,
+        job_id: None,
     };
-    let _ = app.emit("split-progress", payload);
+    ctx.report(payload);
+}
+
+/// 总量与总份数都未知时的进度（流式切分）。
+fn emit_stream_progress(
+    ctx: &JobContext,
+    phase: &str,
+    processed_bytes: u64,
+    part_index: usize,
+    message: String,
+) {
+    ctx.report(ProgressPayload {
+        phase: phase.to_string(),
+        processed_bytes,
+        total_bytes: None,
+        part_index,
+        part_total: 0,
+        message,
+        job_id: None,
+    });
 }
 
 #[derive(Debug, Clone)]
@@ -1005,14 +3519,16 @@ struct PartInfo {
     path: PathBuf,
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 struct PartGroup {
     prefix: String,
     parts: Vec<PartInfo>,
 }
 
 fn parse_part_name(name: &str) -> Option<(String, usize, String)> {
-    let part_pos = name.rfind("part-")?;
+    let Some(part_pos) = name.rfind("part-") else {
+        return parse_volume_name(name);
+    };
     let digits_start = part_pos + "part-".len();
     let mut digits_end = digits_start;
     for (offset, ch) in name[digits_start..].char_indices() {
@@ -1032,17 +3548,41 @@ fn parse_part_name(name: &str) -> Option<(String, usize, String)> {
     Some((prefix, index, suffix))
 }
 
+/// 经典分卷命名 `<名称>.001`：7-Zip 分卷 `<名称>.7z.001`、7-Zip 或 WinZip 把 zip 按字节切分的
+/// `<名称>.zip.001`，以及 HJSplit、FFSJ 切分的 `<名称>.<扩展名>.001`。前缀为 `<名称>.`，没有后缀；
+/// 只认三位以上、从 1 开始的序号。
+fn parse_volume_name(name: &str) -> Option<(String, usize, String)> {
+    let (stem, digits) = name.rsplit_once('.')?;
+    if stem.is_empty() || digits.len() < 3 || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return None;
+    }
+    let index = digits.parse::<usize>().ok().filter(|index| *index > 0)?;
+    Some((format!("{}.", stem), index, String::new()))
+}
+
 fn collect_part_group(input_path: &Path) -> Result<PartGroup, String> {
+    // 注释中带分片组 ID 的分片按注释归组排序，改过名也能找到
+    if let Some(group) = part_comment::find_group(input_path)? {
+        return Ok(group);
+    }
+    let group = find_part_group(input_path)?;
+    validate_part_sequence(&group.parts)?;
+    part_comment::check_group(&group.parts)?;
+    Ok(group)
+}
+
+/// 找出输入对应的分片组，按序号排列，不检查序号是否连续。
+fn find_part_group(input_path: &Path) -> Result<PartGroup, String> {
     if input_path.is_file() {
         let name = input_path
             .file_name()
             .and_then(|value| value.to_str())
-            .ok_or_else(|| "无法解析分片文件名".to_string())?;
+            .ok_or_else(|| tr!(CannotParsePartFileName))?;
         let (prefix, _, suffix) =
-            parse_part_name(name).ok_or_else(|| "无法识别分片文件名".to_string())?;
+            parse_part_name(name).ok_or_else(|| tr!(UnrecognizedPartFileName))?;
         let dir = input_path
             .parent()
-            .ok_or_else(|| "无法解析分片目录".to_string())?;
+            .ok_or_else(|| tr!(CannotParsePartsDir))?;
         let parts = collect_part_group_from_dir(dir, Some((&prefix, &suffix)))?;
         return Ok(PartGroup { prefix, parts });
     }
@@ -1050,7 +3590,7 @@ fn collect_part_group(input_path: &Path) -> Result<PartGroup, String> {
     if input_path.is_dir() {
         let parts = collect_part_group_from_dir(input_path, None)?;
         if parts.is_empty() {
-            return Err("未找到分片文件".to_string());
+            return Err(tr!(NoPartsFound));
         }
         let (prefix, _) = parts
             .first()
@@ -1060,17 +3600,67 @@ fn collect_part_group(input_path: &Path) -> Result<PartGroup, String> {
                     .and_then(|value| value.to_str())
                     .and_then(|name| parse_part_name(name).map(|(p, _, s)| (p, s)))
             })
-            .ok_or_else(|| "无法识别分片文件名".to_string())?;
+            .ok_or_else(|| tr!(UnrecognizedPartFileName))?;
         return Ok(PartGroup { prefix, parts });
     }
 
-    Err("输入路径不是文件或目录".to_string())
+    Err(tr!(InputNotFileOrDir))
+}
+
+/// 找出目录中的全部分片组，按名称排列，各组内按序号排列；不检查序号是否连续。
+fn find_part_groups(dir: &Path) -> Result<Vec<PartGroup>, String> {
+    let mut groups: Vec<PartGroup> = scan_part_groups(dir, None)?
+        .into_iter()
+        .map(|((prefix, _), mut parts)| {
+            parts.sort_by_key(|part| part.index);
+            PartGroup { prefix, parts }
+        })
+        .collect();
+    if groups.is_empty() {
+        return Err(tr!(NoPartsFound));
+    }
+    groups.sort_by(|a, b| {
+        a.prefix
+            .cmp(&b.prefix)
+            .then_with(|| a.parts[0].path.cmp(&b.parts[0].path))
+    });
+    Ok(groups)
 }
 
 fn collect_part_group_from_dir(
     dir: &Path,
     filter: Option<(&String, &String)>,
 ) -> Result<Vec<PartInfo>, String> {
+    let mut groups = scan_part_groups(dir, filter)?;
+
+    if groups.is_empty() {
+        return Err(tr!(NoPartsFound));
+    }
+    if groups.len() > 1 && filter.is_none() {
+        return Err(tr!(MultiplePartGroups));
+    }
+
+    let mut parts = if let Some((filter_prefix, filter_suffix)) = filter {
+        groups
+            .remove(&(filter_prefix.clone(), filter_suffix.clone()))
+            .unwrap_or_default()
+    } else {
+        groups.into_values().next().unwrap_or_default()
+    };
+
+    if parts.is_empty() {
+        return Err(tr!(NoPartsFound));
+    }
+
+    parts.sort_by_key(|part| part.index);
+    Ok(parts)
+}
+
+/// 按（前缀，后缀）归组目录中的分片；`filter` 只保留指定的一组。
+fn scan_part_groups(
+    dir: &Path,
+    filter: Option<(&String, &String)>,
+) -> Result<HashMap<(String, String), Vec<PartInfo>>, String> {
     let entries = fs::read_dir(dir).map_err(|e| e.to_string())?;
     let mut groups: HashMap<(String, String), Vec<PartInfo>> = HashMap::new();
 
@@ -1099,67 +3689,118 @@ fn collect_part_group_from_dir(
             .or_default()
             .push(PartInfo { index, path });
     }
-
-    if groups.is_empty() {
-        return Err("未找到分片文件".to_string());
-    }
-    if groups.len() > 1 && filter.is_none() {
-        return Err("检测到多组分片，请选择具体的分片文件".to_string());
-    }
-
-    let mut parts = if let Some((filter_prefix, filter_suffix)) = filter {
-        groups
-            .remove(&(filter_prefix.clone(), filter_suffix.clone()))
-            .unwrap_or_default()
-    } else {
-        groups.into_values().next().unwrap_or_default()
-    };
-
-    if parts.is_empty() {
-        return Err("未找到分片文件".to_string());
-    }
-
-    parts.sort_by_key(|part| part.index);
-    validate_part_sequence(&parts)?;
-    Ok(parts)
+    Ok(groups)
 }
 
 fn validate_part_sequence(parts: &[PartInfo]) -> Result<(), String> {
     for (idx, part) in parts.iter().enumerate() {
         let expected = idx + 1;
         if part.index != expected {
-            return Err(format!("分片序号不连续，缺少第 {} 份", expected));
+            return Err(tr!(MissingPart, expected));
         }
     }
     Ok(())
 }
 
-fn open_zip_file<'a>(
-    archive: &'a mut ZipArchive<BufReader<File>>,
+fn open_zip_file<'a, R: Read + Seek>(
+    archive: &'a mut ZipArchive<R>,
+    index: usize,
+    password: Option<&str>,
+) -> Result<zip::read::ZipFile<'a, R>, String> {
+    decrypt_zip_file(archive, index, password).map_err(String::from)
+}
+
+/// 同 [`open_zip_file`]，区分密码不对与缺少密码。
+fn decrypt_zip_file<'a, R: Read + Seek>(
+    archive: &'a mut ZipArchive<R>,
     index: usize,
     password: Option<&str>,
-) -> Result<zip::read::ZipFile<'a, BufReader<File>>, String> {
+) -> Result<zip::read::ZipFile<'a, R>, PasswordError> {
     if let Some(password) = password {
         return archive
             .by_index_decrypt(index, password.as_bytes())
-            .map_err(|decrypt_err| map_zip_error(decrypt_err, true));
+            .map_err(|decrypt_err| zip_password_error(decrypt_err, true));
     }
-    archive.by_index(index).map_err(|err| map_zip_error(err, false))
+    archive
+        .by_index(index)
+        .map_err(|err| zip_password_error(err, false))
 }
 
-fn map_zip_error(err: ZipError, had_password: bool) -> String {
+/// 开始合并前先试着解密第一份分片开头的数据。ZipCrypto 只用一个字节、AES 只用两个字节核对密码，
+/// 错误的密码仍有机会通过，若不先试读，要到合并完整份分片、解压出错或核对 CRC 时才发现。
+/// 条目不超过试读大小时读到末尾，连同 CRC 与认证码一起核对。分片加密而没有给出密码时同样在此发现。
+fn check_part_password(path: &Path, password: Option<&str>) -> Result<(), PasswordError> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let entry = decrypt_zip_file(&mut archive, 0, password)?;
+    probe_password(entry)
+}
+
+/// 解压 zip 前先试解密第一个加密的文件条目，密码不对或缺少密码时在写出任何文件之前发现。
+/// 读不出目录时不检查，交给解压（及尽力解压）处理。
+fn check_zip_password(path: &Path, password: Option<&str>) -> Result<(), PasswordError> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let Ok(mut archive) = ZipArchive::new(BufReader::new(file)) else {
+        return Ok(());
+    };
+    check_archive_password(&mut archive, password).map(|_| ())
+}
+
+/// 试解密压缩包中第一个加密的文件条目；没有加密的条目时返回 `false`。
+fn check_archive_password<R: Read + Seek>(
+    archive: &mut ZipArchive<R>,
+    password: Option<&str>,
+) -> Result<bool, PasswordError> {
+    let first_encrypted = (0..archive.len()).find(|index| {
+        archive
+            .by_index_raw(*index)
+            .is_ok_and(|entry| entry.encrypted() && !entry.is_dir())
+    });
+    match first_encrypted {
+        Some(index) => probe_password(decrypt_zip_file(archive, index, password)?).map(|()| true),
+        None => Ok(false),
+    }
+}
+
+fn probe_password<R: Read>(entry: zip::read::ZipFile<'_, R>) -> Result<(), PasswordError> {
+    if !entry.encrypted() {
+        return Ok(());
+    }
+    let probe = if entry.size() <= PASSWORD_PROBE_SIZE {
+        // 多读一个字节才会核对 CRC
+        entry.size() + 1
+    } else {
+        PASSWORD_PROBE_SIZE
+    };
+    // 解密出的数据无法解压、CRC 或 AES 认证码不符时是密码不对，读取分片本身出错时原样返回
+    let Err(err) = io::copy(&mut entry.take(probe), &mut io::sink()) else {
+        return Ok(());
+    };
+    match err.kind() {
+        io::ErrorKind::InvalidData | io::ErrorKind::InvalidInput => Err(PasswordError::Wrong),
+        _ => Err(PasswordError::Other(err.to_string())),
+    }
+}
+
+fn zip_password_error(err: ZipError, had_password: bool) -> PasswordError {
     match err {
+        ZipError::UnsupportedArchive(ZipError::PASSWORD_REQUIRED) => PasswordError::Required,
         ZipError::InvalidPassword => {
             if had_password {
-                "解密失败，请确认密码".to_string()
+                PasswordError::Wrong
             } else {
-                "需要密码才能解包".to_string()
+                PasswordError::Required
             }
         }
-        _ => err.to_string(),
+        _ => PasswordError::Other(err.to_string()),
     }
 }
 
+/// 合并结果为可自动解压的压缩包时返回其扩展名。
+fn archive_extension(path: &Path) -> Result<Option<&'static str>, String> {
+    Ok(extract::ArchiveKind::detect(path)?.map(extract::ArchiveKind::extension))
+}
+
 fn is_zip_file(path: &Path) -> Result<bool, String> {
     let mut file = File::open(path).map_err(|e| e.to_string())?;
     let mut signature = [0u8; 4];
@@ -1174,7 +3815,7 @@ fn is_zip_file(path: &Path) -> Result<bool, String> {
 }
 
 fn restore_split_then_zip(
-    app: &AppHandle,
+    ctx: &JobContext,
     input_path: &Path,
     output_dir: &Path,
     password: Option<&str>,
@@ -1182,74 +3823,103 @@ fn restore_split_then_zip(
 ) -> Result<RestoreResult, String> {
     let part_group = collect_part_group(input_path)?;
     let base_name = part_group.prefix.trim_end_matches('.').to_string();
+    let layout = index::part_layout(&part_group, "split-then-zip")?;
+    let password = match part_group.parts.first() {
+        Some(first) => password_prompt::retry(ctx, &first.path, password, |password| {
+            check_part_password(&first.path, password)?;
+            Ok(password.map(|value| Zeroizing::new(value.to_string())))
+        })?,
+        None => password.map(|value| Zeroizing::new(value.to_string())),
+    };
+    let password = password.as_deref().map(String::as_str);
 
     let mut parts_with_size = Vec::with_capacity(part_group.parts.len());
     for part in &part_group.parts {
         let file = File::open(&part.path).map_err(|e| e.to_string())?;
         let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
         if archive.len() == 0 {
-            return Err("分片压缩包为空".to_string());
+            return Err(tr!(PartArchiveEmpty));
         }
         if archive.len() > 1 {
-            return Err("分片压缩包内包含多个文件".to_string());
+            return Err(tr!(PartArchiveMultipleFiles));
         }
         let entry = open_zip_file(&mut archive, 0, password)?;
         if entry.is_dir() {
-            return Err("分片压缩包内容异常".to_string());
+            return Err(tr!(PartArchiveInvalid));
         }
         parts_with_size.push((part.clone(), entry.size()));
     }
 
     let total_bytes: u64 = parts_with_size.iter().map(|(_, size)| *size).sum();
     let temp_path = output_dir.join(format!("{}.merge.tmp", base_name));
+    ctx.track_output(&temp_path);
     let mut writer = BufWriter::new(File::create(&temp_path).map_err(|e| e.to_string())?);
     let mut processed = 0u64;
 
     for (idx, (part, size)) in parts_with_size.iter().enumerate() {
         emit_progress(
-            app,
+            ctx,
             "restore",
             processed,
             total_bytes,
             idx + 1,
             parts_with_size.len(),
-            format!("合并第 {} 份", idx + 1),
+            tr!(MergingPart, idx + 1),
         );
         let file = File::open(&part.path).map_err(|e| e.to_string())?;
         let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
         let mut entry = open_zip_file(&mut archive, 0, password)?;
-        copy_n_with_progress(&mut entry, &mut writer, *size, |delta| {
+        let buffer_size = ctx.memory().buffer_size();
+        let part_start = processed;
+        let copied = copy_n_buffered(&mut entry, &mut writer, *size, buffer_size, ctx.cancel_token(), |delta| {
             processed += delta;
             emit_progress(
-                app,
+                ctx,
                 "restore",
                 processed,
                 total_bytes,
                 idx + 1,
                 parts_with_size.len(),
-                "合并中".to_string(),
+                tr!(Merging),
             );
         })
-        .map_err(|e| e.to_string())?;
+        // 读到条目末尾才会核对 CRC，损坏时指出是哪一份分片
+        .and_then(|()| entry.read(&mut [0u8; 1]).map(|_| ()));
+        if let Err(err) = copied {
+            ctx.check_cancelled()?;
+            let name = part.path.file_name().unwrap_or_default().to_string_lossy().to_string();
+            if !ctx.salvage() {
+                return Err(tr!(EntryCorrupt, name, err));
+            }
+            // 尽力解压：读不出的部分补零，后面分片的内容仍在原来的位置
+            let missing = size.saturating_sub(processed - part_start);
+            io::copy(&mut io::repeat(0).take(missing), &mut writer).map_err(|e| e.to_string())?;
+            processed += missing;
+            log::warn!("尽力解压：{}", tr!(EntryCorrupt, name, err));
+            ctx.record_lost(name);
+        }
     }
     writer.flush().map_err(|e| e.to_string())?;
 
     let mut merged_name = base_name.clone();
-    if is_zip_file(&temp_path)? && !merged_name.ends_with(".zip") {
-        merged_name = format!("{}.zip", merged_name);
+    if let Some(extension) = archive_extension(&temp_path)? {
+        if !merged_name.ends_with(extension) {
+            merged_name.push_str(extension);
+        }
     }
     let merged_path = output_dir.join(&merged_name);
     if merged_path.exists() {
         fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
     }
     fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
 
     let mut output_files = vec![merged_path.to_string_lossy().to_string()];
     let mut extracted_dir = None;
 
-    if auto_extract && is_zip_file(&merged_path)? {
-        let target_dir = output_dir.join(strip_zip_extension(&merged_name));
-        unzip_file(app, &merged_path, &target_dir, password)?;
+    if auto_extract && archive_extension(&merged_path)?.is_some() {
+        let target_dir = output_dir.join(extract::strip_extension(&merged_name));
+        extract::extract_archive(ctx, &merged_path, &target_dir, password, Some(&layout))?;
         extracted_dir = Some(target_dir.to_string_lossy().to_string());
         output_files.push(target_dir.to_string_lossy().to_string());
     }
@@ -1258,71 +3928,320 @@ fn restore_split_then_zip(
         merged_file: Some(merged_path.to_string_lossy().to_string()),
         extracted_dir,
         output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
     })
 }
 
 fn restore_zip_then_split(
-    app: &AppHandle,
+    ctx: &JobContext,
     input_path: &Path,
     output_dir: &Path,
     password: Option<&str>,
     auto_extract: bool,
+    extract_from_parts: bool,
 ) -> Result<RestoreResult, String> {
-    let part_group = collect_part_group(input_path)?;
+    // 其他软件生成的 `.z01`……`.zip` 分卷同样是 zip，按分卷格式还原
+    if spanned::is_spanned_set(input_path) {
+        return spanned::restore(ctx, input_path, output_dir, password, auto_extract);
+    }
+    // 分卷 RAR 无法合并成单个文件，直接解压
+    if rar::is_volume_set(input_path) {
+        return rar::restore(ctx, input_path, output_dir, password);
+    }
+    if input_path.is_dir() {
+        if let Some((_, manifest)) = append::load_manifest(input_path)? {
+            return append::restore_generations(
+                ctx,
+                input_path,
+                &manifest,
+                output_dir,
+                password,
+                auto_extract,
+            );
+        }
+    }
+    let part_group = gaps::collect_group(ctx, input_path, collect_part_group)?;
+    let layout = index::part_layout(&part_group, "zip-then-split")?;
+    // 缺片合并时分片拼接不出完整的 zip，先合并再解压
+    if auto_extract && extract_from_parts && ctx.gap_policy().is_none() {
+        if let Some(result) =
+            extract_zip_from_parts(ctx, &part_group, &layout, output_dir, password)?
+        {
+            return Ok(result);
+        }
+    }
+    let mut zip_name = part_group.prefix.trim_end_matches('.').to_string();
+    let temp_path = output_dir.join(format!("{}.merge.tmp", zip_name));
+    merge_raw_parts(ctx, &part_group, &temp_path)?;
+    // 切分的原文件是 7z 或 RAR 时保留原扩展名，其余按 zip 命名
+    let extension = archive_extension(&temp_path)?.unwrap_or(".zip");
+    if !zip_name.ends_with(extension) {
+        zip_name.push_str(extension);
+    }
+
+    let merged_path = output_dir.join(&zip_name);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let mut output_files = vec![merged_path.to_string_lossy().to_string()];
+    let mut extracted_dir = None;
+
+    if auto_extract {
+        let target_dir = output_dir.join(extract::strip_extension(&zip_name));
+        extract::extract_archive(ctx, &merged_path, &target_dir, password, Some(&layout))?;
+        extracted_dir = Some(target_dir.to_string_lossy().to_string());
+        output_files.push(target_dir.to_string_lossy().to_string());
+    }
+
+    Ok(RestoreResult {
+        merged_file: Some(merged_path.to_string_lossy().to_string()),
+        extracted_dir,
+        output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
+
+/// 不合并，直接在分片上读取拼接出的 zip 并解压，省去合并后的 zip 占用的空间。
+/// 拼接出的不是普通 zip（7z、RAR 或加密了文件名的 zip）时返回 `None`，照常先合并。
+fn extract_zip_from_parts(
+    ctx: &JobContext,
+    part_group: &PartGroup,
+    layout: &[index::IndexedPart],
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<Option<RestoreResult>, String> {
+    let mut archive = match partial::open_archive_from_layout(part_group, "zip-then-split", layout)
+    {
+        Ok(archive) => archive,
+        Err(err) => {
+            log::info!("分片拼接出的不是 zip，改为先合并：{}", err);
+            return Ok(None);
+        }
+    };
+    if archive.len() == 1 && archive.name_for_index(0) == Some(hidden_names::WRAPPED_ENTRY) {
+        log::info!("压缩包加密了文件名，改为先合并");
+        return Ok(None);
+    }
     let mut zip_name = part_group.prefix.trim_end_matches('.').to_string();
     if !zip_name.ends_with(".zip") {
-        zip_name = format!("{}.zip", zip_name);
+        zip_name.push_str(".zip");
     }
-    let temp_path = output_dir.join(format!("{}.merge.tmp", zip_name));
-    let mut writer = BufWriter::new(File::create(&temp_path).map_err(|e| e.to_string())?);
-    let mut processed = 0u64;
-    let mut total_bytes = 0u64;
+    let target_dir = output_dir.join(extract::strip_extension(&zip_name));
+    if !target_dir.exists() {
+        ctx.track_output(&target_dir);
+    }
+    fs::create_dir_all(&target_dir).map_err(|e| e.to_string())?;
+    unzip_archive(ctx, &mut archive, &target_dir, password, Some(layout))?;
+    log::info!("已直接从分片解压：{}", target_dir.display());
 
-    for part in &part_group.parts {
-        let size = fs::metadata(&part.path).map_err(|e| e.to_string())?.len();
-        total_bytes += size;
+    let extracted_dir = target_dir.to_string_lossy().to_string();
+    Ok(Some(RestoreResult {
+        merged_file: None,
+        extracted_dir: Some(extracted_dir.clone()),
+        output_files: vec![extracted_dir],
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: Some("zip".to_string()),
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    }))
+}
+
+fn restore_sevenz_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let part_group = gaps::collect_group(ctx, input_path, collect_part_group)?;
+    let mut archive_name = part_group.prefix.trim_end_matches('.').to_string();
+    if !archive_name.ends_with(".7z") {
+        archive_name = format!("{}.7z", archive_name);
     }
+    let temp_path = output_dir.join(format!("{}.merge.tmp", archive_name));
+    merge_raw_parts(ctx, &part_group, &temp_path)?;
 
-    for (idx, part) in part_group.parts.iter().enumerate() {
-        emit_progress(
-            app,
-            "merge",
-            processed,
-            total_bytes,
-            idx + 1,
-            part_group.parts.len(),
-            format!("合并第 {} 份", idx + 1),
-        );
-        let mut reader = BufReader::new(File::open(&part.path).map_err(|e| e.to_string())?);
-        let size = fs::metadata(&part.path).map_err(|e| e.to_string())?.len();
-        copy_n_with_progress(&mut reader, &mut writer, size, |delta| {
+    let merged_path = output_dir.join(&archive_name);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let mut output_files = vec![merged_path.to_string_lossy().to_string()];
+    let mut extracted_dir = None;
+
+    if auto_extract {
+        let target_dir = output_dir.join(extract::strip_extension(&archive_name));
+        extract::extract_archive(ctx, &merged_path, &target_dir, password, None)?;
+        extracted_dir = Some(target_dir.to_string_lossy().to_string());
+        output_files.push(target_dir.to_string_lossy().to_string());
+    }
+
+    Ok(RestoreResult {
+        merged_file: Some(merged_path.to_string_lossy().to_string()),
+        extracted_dir,
+        output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
+
+fn restore_tar_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    compression: tar::TarCompression,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let part_group = gaps::collect_group(ctx, input_path, collect_part_group)?;
+    let extension = format!(".{}", compression.extension());
+    let mut archive_name = part_group.prefix.trim_end_matches('.').to_string();
+    if !archive_name.ends_with(&extension) {
+        archive_name.push_str(&extension);
+    }
+    let temp_path = output_dir.join(format!("{}.merge.tmp", archive_name));
+    merge_raw_parts(ctx, &part_group, &temp_path)?;
+
+    let merged_path = output_dir.join(&archive_name);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let mut output_files = vec![merged_path.to_string_lossy().to_string()];
+    let mut extracted_dir = None;
+
+    if auto_extract {
+        let target_dir = output_dir.join(extract::strip_extension(&archive_name));
+        extract::extract_archive(ctx, &merged_path, &target_dir, None, None)?;
+        extracted_dir = Some(target_dir.to_string_lossy().to_string());
+        output_files.push(target_dir.to_string_lossy().to_string());
+    }
+
+    Ok(RestoreResult {
+        merged_file: Some(merged_path.to_string_lossy().to_string()),
+        extracted_dir,
+        output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
+
+fn restore_zstd_then_split(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let part_group = collect_part_group(input_path)?;
+    let archive_name = part_group.prefix.trim_end_matches('.').to_string();
+    let mut merged_name = archive_name
+        .strip_suffix(".zst")
+        .unwrap_or(archive_name.as_str())
+        .to_string();
+
+    let part_paths: Vec<PathBuf> = part_group.parts.iter().map(|part| part.path.clone()).collect();
+    let part_reader = part_reader::PartReader::from_files(&part_paths).map_err(|e| e.to_string())?;
+    let mut reader = seekable::SeekableReader::open_parts(&part_paths, part_reader)
+        .map_err(|e| e.to_string())?;
+    let total_bytes = reader.len();
+
+    let temp_path = output_dir.join(format!("{}.merge.tmp", merged_name));
+    ctx.track_output(&temp_path);
+    let mut writer = BufWriter::new(File::create(&temp_path).map_err(|e| e.to_string())?);
+    let mut processed = 0u64;
+    emit_progress(
+        ctx,
+        "restore",
+        0,
+        total_bytes,
+        0,
+        0,
+        tr!(ExtractMerging),
+    );
+    let buffer_size = ctx.memory().buffer_size();
+    copy_n_buffered(
+        &mut reader,
+        &mut writer,
+        total_bytes,
+        buffer_size,
+        ctx.cancel_token(),
+        |delta| {
             processed += delta;
             emit_progress(
-                app,
-                "merge",
+                ctx,
+                "restore",
                 processed,
                 total_bytes,
-                idx + 1,
-                part_group.parts.len(),
-                "合并中".to_string(),
+                0,
+                0,
+                tr!(ExtractMerging),
             );
-        })
-        .map_err(|e| e.to_string())?;
-    }
+        },
+    )
+    .map_err(|e| e.to_string())?;
     writer.flush().map_err(|e| e.to_string())?;
+    drop(writer);
 
-    let merged_path = output_dir.join(&zip_name);
+    if let Some(extension) = archive_extension(&temp_path)? {
+        if !merged_name.ends_with(extension) {
+            merged_name.push_str(extension);
+        }
+    }
+    let merged_path = output_dir.join(&merged_name);
     if merged_path.exists() {
         fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
     }
     fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
 
     let mut output_files = vec![merged_path.to_string_lossy().to_string()];
     let mut extracted_dir = None;
 
-    if auto_extract {
-        let target_dir = output_dir.join(strip_zip_extension(&zip_name));
-        unzip_file(app, &merged_path, &target_dir, password)?;
+    if auto_extract && archive_extension(&merged_path)?.is_some() {
+        let target_dir = output_dir.join(extract::strip_extension(&merged_name));
+        extract::extract_archive(ctx, &merged_path, &target_dir, password, None)?;
         extracted_dir = Some(target_dir.to_string_lossy().to_string());
         output_files.push(target_dir.to_string_lossy().to_string());
     }
@@ -1331,91 +4250,412 @@ fn restore_zip_then_split(
         merged_file: Some(merged_path.to_string_lossy().to_string()),
         extracted_dir,
         output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
     })
 }
 
-fn strip_zip_extension(name: &str) -> String {
-    name.strip_suffix(".zip").unwrap_or(name).to_string()
+fn merge_raw_parts(ctx: &JobContext, part_group: &PartGroup, target_path: &Path) -> Result<(), String> {
+    if let Some(policy) = ctx.gap_policy() {
+        return gaps::merge(ctx, part_group, target_path, policy);
+    }
+    ctx.track_output(target_path);
+    let sizes = part_group
+        .parts
+        .iter()
+        .map(|part| fs::metadata(&part.path).map(|meta| meta.len()))
+        .collect::<io::Result<Vec<u64>>>()
+        .map_err(|e| e.to_string())?;
+    let total_bytes: u64 = sizes.iter().sum();
+    // 开启续传时跳过上次中断前已完整写入的分片
+    let (file, mut progress) = merge_resume::open(ctx, &part_group.parts, &sizes, target_path)?;
+    let skipped = progress.as_ref().map_or(0, |progress| progress.completed());
+    let mut processed: u64 = sizes[..skipped].iter().sum();
+    let mut writer = BufWriter::new(file);
+
+    for (idx, part) in part_group.parts.iter().enumerate().skip(skipped) {
+        emit_progress(
+            ctx,
+            "merge",
+            processed,
+            total_bytes,
+            idx + 1,
+            part_group.parts.len(),
+            tr!(MergingPart, idx + 1),
+        );
+        let mut reader = BufReader::new(File::open(&part.path).map_err(|e| e.to_string())?);
+        let buffer_size = ctx.memory().buffer_size();
+        copy_n_buffered(&mut reader, &mut writer, sizes[idx], buffer_size, ctx.cancel_token(), |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "merge",
+                processed,
+                total_bytes,
+                idx + 1,
+                part_group.parts.len(),
+                tr!(Merging),
+            );
+        })
+        .map_err(|e| e.to_string())?;
+        if let Some(progress) = &mut progress {
+            progress.mark(&mut writer, idx + 1, processed)?;
+        }
+    }
+    writer.flush().map_err(|e| e.to_string())?;
+    if let Some(progress) = progress {
+        progress.finish();
+    }
+    Ok(())
 }
 
+/// 解压合并得到的 zip。`layout` 为该 zip 由哪些分片直接拼接而成，文件损坏时据此
+/// 指出数据所在的分片与字节区间。
 fn unzip_file(
-    app: &AppHandle,
+    ctx: &JobContext,
     zip_path: &Path,
     output_dir: &Path,
     password: Option<&str>,
+    layout: Option<&[index::IndexedPart]>,
 ) -> Result<(), String> {
+    if !output_dir.exists() {
+        ctx.track_output(output_dir);
+    }
     fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
     let file = File::open(zip_path).map_err(|e| e.to_string())?;
-    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let mut archive = match ZipArchive::new(BufReader::new(file)) {
+        Ok(archive) => archive,
+        // 尽力解压时中央目录损坏，改为扫描本地文件头
+        Err(err) if ctx.salvage() => {
+            log::warn!("无法读取 {} 的条目列表，改为扫描文件头：{}", zip_path.display(), err);
+            return salvage::extract_local_entries(ctx, zip_path, output_dir);
+        }
+        Err(err) => return Err(err.to_string()),
+    };
+    unzip_archive(ctx, &mut archive, output_dir, password, layout)
+}
+
+/// 解压已打开的 zip，可以是合并后的文件，也可以是直接在分片上读取的拼接流。
+fn unzip_archive<R: Read + Seek>(
+    ctx: &JobContext,
+    archive: &mut ZipArchive<R>,
+    output_dir: &Path,
+    password: Option<&str>,
+    layout: Option<&[index::IndexedPart]>,
+) -> Result<(), String> {
     let total_entries = archive.len();
     let mut total_bytes = 0u64;
+    let names = entry_names::NameDecoder::for_archive(ctx.name_encoding(), archive);
 
     for index in 0..total_entries {
-        let entry = open_zip_file(&mut archive, index, password)?;
-        total_bytes += entry.size();
+        if !ctx.extracts(&names.name_for_index(archive, index)) {
+            continue;
+        }
+        match open_zip_file(archive, index, password) {
+            Ok(entry) => total_bytes += entry.size(),
+            Err(_) if ctx.salvage() => {}
+            Err(err) => return Err(err),
+        }
     }
 
-    let file = File::open(zip_path).map_err(|e| e.to_string())?;
-    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
     let mut processed = 0u64;
-    let total_entries = archive.len();
+    let mut dir_times = entry_times::DirTimes::default();
 
     for index in 0..total_entries {
-        let mut entry = open_zip_file(&mut archive, index, password)?;
-        let Some(name) = entry.enclosed_name().map(|value| value.to_path_buf()) else {
+        let entry_name = names.name_for_index(archive, index);
+        // 选择性解压时跳过不匹配的条目，目录随其中的文件创建
+        if ctx.filters_entries() && (entry_name.ends_with('/') || !ctx.extracts(&entry_name)) {
+            continue;
+        }
+        let mut entry = match decrypt_zip_file(archive, index, password) {
+            Ok(entry) => entry,
+            // 密码不对时每个条目都读不出，不算作条目损坏
+            Err(PasswordError::Other(err)) if ctx.salvage() => {
+                let name = entry_name;
+                log::warn!("尽力解压：{}", tr!(EntryCorrupt, name, err));
+                ctx.record_lost(name);
+                continue;
+            }
+            Err(err) => return Err(err.into()),
+        };
+        let Some(name) = names.enclosed_name(&entry) else {
             continue;
         };
         let out_path = output_dir.join(name);
+        let times = entry_times::EntryTimes::from_zip(&entry);
         if entry.is_dir() || entry.name().ends_with('/') {
             fs::create_dir_all(&out_path).map_err(|e| e.to_string())?;
+            dir_times.push(out_path, times);
             continue;
         }
         if let Some(parent) = out_path.parent() {
             fs::create_dir_all(parent).map_err(|e| e.to_string())?;
         }
+        let Some(out_path) = conflicts::resolve(ctx, &out_path, &entry_name, entry.size())? else {
+            processed += entry.size();
+            continue;
+        };
 
         emit_progress(
-            app,
+            ctx,
             "unzip",
             processed,
             total_bytes,
             index + 1,
             total_entries,
-            "解压中".to_string(),
+            tr!(Extracting),
         );
 
         let mut writer = BufWriter::new(File::create(&out_path).map_err(|e| e.to_string())?);
         let size = entry.size();
-        copy_n_with_progress(&mut entry, &mut writer, size, |delta| {
+        let range = (entry.data_start(), entry.data_start() + entry.compressed_size());
+        let buffer_size = ctx.memory().buffer_size();
+        let copied = copy_n_buffered(&mut entry, &mut writer, size, buffer_size, ctx.cancel_token(), |delta| {
             processed += delta;
             emit_progress(
-                app,
+                ctx,
                 "unzip",
                 processed,
                 total_bytes,
                 index + 1,
                 total_entries,
-                "解压中".to_string(),
+                tr!(Extracting),
             );
         })
-        .map_err(|e| e.to_string())?;
+        // 读到条目末尾才会核对 CRC
+        .and_then(|()| entry.read(&mut [0u8; 1]).map(|_| ()));
+        if let Err(err) = copied {
+            ctx.check_cancelled()?;
+            let name = entry_name.clone();
+            let ranges = layout
+                .map(|parts| index::locate_range(parts, range.0, range.1))
+                .unwrap_or_default();
+            let message = if ranges.is_empty() {
+                tr!(EntryCorrupt, name, err)
+            } else {
+                tr!(EntryCorruptAt, name, index::describe_ranges(&ranges), err)
+            };
+            if !ctx.salvage() {
+                return Err(message);
+            }
+            // 尽力解压：删掉写了一半的文件，记下条目后继续
+            drop(writer);
+            fs::remove_file(&out_path).map_err(|e| e.to_string())?;
+            log::warn!("尽力解压：{}", message);
+            ctx.record_lost(name);
+            continue;
+        }
         writer.flush().map_err(|e| e.to_string())?;
+        times.apply_to_file(&out_path, writer.get_ref());
     }
 
+    dir_times.apply();
     Ok(())
 }
 
+/// 无窗口运行，参见 [`headless`]。出错时打印原因并以非零状态退出。
+pub fn run_headless(args: Vec<String>) {
+    logging::init(Some(env::temp_dir().join("file-split-packer").join("logs")));
+    if let Err(err) = headless::run(&args) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+/// 是否作为分片目录中的自解压程序运行，参见 [`sfx`]。
+pub fn is_restorer() -> bool {
+    sfx::is_restorer()
+}
+
+/// 自解压程序：合并所在目录中的分片，参见 [`sfx`]。出错时打印原因并以非零状态退出。
+pub fn run_restorer(args: Vec<String>) {
+    logging::init(None);
+    if let Err(err) = sfx::run(&args) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
+/// 命令行模式（`split` / `merge`），参见 [`cli`]。出错时打印原因并以非零状态退出。
+pub fn run_cli(args: Vec<String>) {
+    logging::init(None);
+    if let Err(err) = cli::run(&args) {
+        eprintln!("{}", err);
+        std::process::exit(1);
+    }
+}
+
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
     tauri::Builder::default()
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_opener::init())
+        .setup(|app| {
+            logging::init(app.path().app_log_dir().ok());
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             process_file,
+            validate_split_options,
+            estimate_password_strength,
+            append_parts,
             restore_parts,
+            restore_all_groups,
+            set_job_secret,
+            answer_prompt,
+            cancel_job,
+            get_job_status,
+            enqueue_job,
+            list_queue,
+            remove_queued_job,
+            clear_queue,
+            get_queue_settings,
+            set_queue_settings,
+            estimate_restore,
+            plan_restore,
+            verify_parts,
+            repair_parts,
+            reencrypt_parts,
+            decrypt_parts,
+            test_archive,
+            generate_signing_key,
+            import_signing_key,
+            restore_range,
+            extract_entry,
+            stream_entry,
+            index_parts,
+            list_archive_contents,
+            search_entries,
+            mount_parts,
+            unmount_parts,
+            start_api_server,
+            stop_api_server,
+            get_api_server,
+            start_ipc_server,
+            stop_ipc_server,
+            get_ipc_server,
+            start_watch_folder,
+            stop_watch_folder,
+            get_watch_folder,
+            list_plugins,
+            get_language,
+            set_language,
+            get_log_settings,
+            set_log_level,
+            export_diagnostics,
+            benchmark_disk,
             save_text_file
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    /// 按严格分片大小算出的分片写成仅存储的 zip，返回文件大小。
+    fn stored_part_len(base_name: &str, parts: usize, payload: u64, password: Option<&str>) -> u64 {
+        let width = cmp::max(3, parts.to_string().len());
+        let entry_name = format!("{}.part-{}", base_name, format_part_index(parts, width));
+        let options = build_file_options(
+            password,
+            AesMode::Aes256,
+            CompressionMethod::Stored,
+            None,
+            payload,
+            Some(SystemTime::now()),
+        );
+        let mut zip = ZipWriter::new(Cursor::new(Vec::new()));
+        zip.start_file(entry_name, options).unwrap();
+        zip.write_all(&vec![0x5a; payload as usize]).unwrap();
+        zip.set_comment(
+            part_comment::PartComment {
+                index: parts,
+                total: parts,
+                group: Some("0123456789abcdef".to_string()),
+                size: payload,
+                sha256: password.is_none().then(|| "f".repeat(64)),
+            }
+            .to_text(),
+        );
+        zip.finish().unwrap().into_inner().len() as u64
+    }
+
+    #[test]
+    fn strict_parts_never_exceed_size() {
+        for size_bytes in [700u64, 1024, 4096, 65536] {
+            for password in [None, Some("secret")] {
+                let (payload, _) = compute_parts_with_overhead(
+                    size_bytes * 5,
+                    Some(size_bytes),
+                    "movie.mkv",
+                    password.is_some(),
+                )
+                .unwrap();
+                let parts = div_ceil(size_bytes * 5, payload) as usize;
+                let len = stored_part_len("movie.mkv", parts, payload, password);
+                assert!(
+                    len <= size_bytes,
+                    "size {} encrypted {}: part is {} bytes",
+                    size_bytes,
+                    password.is_some(),
+                    len
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn zip64_overhead_applies_from_threshold() {
+        let plain = zip_stored_overhead(20, 0, false, false);
+        let zip64 = zip_stored_overhead(20, 0, false, true);
+        // 两个 Zip64 扩展字段、Zip64 目录结束记录与定位记录、加长的数据描述符
+        assert_eq!(zip64 - plain, 20 + 28 + 56 + 20 + 8);
+
+        let total = ZIP64_THRESHOLD * 3;
+        let (below, _) =
+            compute_parts_with_overhead(total, Some(ZIP64_THRESHOLD - 1), "disk.img", false)
+                .unwrap();
+        let (at, parts) =
+            compute_parts_with_overhead(total, Some(ZIP64_THRESHOLD), "disk.img", false).unwrap();
+        let entry_len = "disk.img.part-".len() + 3;
+        let comment_len = part_comment::max_len(parts, ZIP64_THRESHOLD, false);
+        assert_eq!(
+            at,
+            ZIP64_THRESHOLD - zip_stored_overhead(entry_len, comment_len, false, true)
+        );
+        // 低于阈值时不写 Zip64，开销更小
+        assert!((ZIP64_THRESHOLD - 1) - below < ZIP64_THRESHOLD - at);
+    }
+
+    #[test]
+    fn part_names_widen_beyond_65535_parts() {
+        let size_bytes = 1024;
+        let (payload, parts) =
+            compute_parts_with_overhead(size_bytes * 100_000, Some(size_bytes), "a.bin", false)
+                .unwrap();
+        assert!(parts > 65_535);
+        let width = cmp::max(3, parts.to_string().len());
+        assert_eq!(width, 6);
+        assert!(stored_part_len("a.bin", parts, payload, None) <= size_bytes);
+
+        let name = format!("a.bin.part-{}.zip", format_part_index(parts, width));
+        assert_eq!(
+            parse_part_name(&name),
+            Some(("a.bin.".to_string(), parts, ".zip".to_string()))
+        );
+        let first = format!("a.bin.part-{}.zip", format_part_index(1, width));
+        assert_eq!(first, "a.bin.part-000001.zip");
+        assert_eq!(parse_part_name(&first).map(|(_, index, _)| index), Some(1));
+    }
+}
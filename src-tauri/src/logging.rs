@@ -0,0 +1,355 @@
+//! 诊断日志：写入日志目录下的 `file-split-packer.log`，超过大小或写满一天后轮转，
+//! 只保留最近几份并清理过期文件，方便用户把日志发回来分析性能问题。
+//!
+//! 级别可通过 `set_log_level` 命令或环境变量 `FILE_SPLIT_PACKER_LOG` 调整，
+//! 日志目录可通过环境变量 `FILE_SPLIT_PACKER_LOG_DIR` 覆盖。
+
+use log::{LevelFilter, Log, Metadata, Record};
+use std::{
+    env,
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+use crate::i18n::tr;
+
+const LOG_FILE_NAME: &str = "file-split-packer";
+const LEVEL_ENV: &str = "FILE_SPLIT_PACKER_LOG";
+const DIR_ENV: &str = "FILE_SPLIT_PACKER_LOG_DIR";
+/// 单个日志文件的大小上限。
+const MAX_FILE_SIZE: u64 = 5 * 1024 * 1024;
+/// 单个日志文件最多写入的时长，超过后轮转。
+const MAX_FILE_AGE: Duration = Duration::from_secs(24 * 60 * 60);
+/// 轮转后保留的历史文件数（`.1.log` 最新）。
+const KEEP_FILES: usize = 5;
+/// 历史文件超过该时长即删除，即使未达到保留份数。
+const MAX_KEEP_AGE: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+static LOGGER: OnceLock<FileLogger> = OnceLock::new();
+
+struct FileLogger {
+    dir: PathBuf,
+    state: Mutex<Option<LogFile>>,
+}
+
+struct LogFile {
+    file: File,
+    size: u64,
+    opened_at: SystemTime,
+}
+
+impl FileLogger {
+    fn current_path(&self) -> PathBuf {
+        self.dir.join(format!("{}.log", LOG_FILE_NAME))
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{}.{}.log", LOG_FILE_NAME, index))
+    }
+
+    fn open(&self) -> io::Result<LogFile> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.current_path();
+        let file = OpenOptions::new().create(true).append(true).open(&path)?;
+        let meta = file.metadata()?;
+        let opened_at = meta
+            .created()
+            .or_else(|_| meta.modified())
+            .unwrap_or_else(|_| SystemTime::now());
+        Ok(LogFile {
+            file,
+            size: meta.len(),
+            opened_at,
+        })
+    }
+
+    /// 当前文件改名为 `.1.log`，已有的历史文件依次后移，超出保留份数或过期的删除。
+    fn rotate(&self) -> io::Result<()> {
+        let _ = fs::remove_file(self.rotated_path(KEEP_FILES));
+        for index in (1..KEEP_FILES).rev() {
+            let from = self.rotated_path(index);
+            if from.exists() {
+                fs::rename(&from, self.rotated_path(index + 1))?;
+            }
+        }
+        fs::rename(self.current_path(), self.rotated_path(1))?;
+        let now = SystemTime::now();
+        for index in 2..=KEEP_FILES {
+            let path = self.rotated_path(index);
+            let expired = fs::metadata(&path)
+                .and_then(|meta| meta.modified())
+                .map(|modified| now.duration_since(modified).unwrap_or_default() > MAX_KEEP_AGE)
+                .unwrap_or(false);
+            if expired {
+                let _ = fs::remove_file(path);
+            }
+        }
+        Ok(())
+    }
+
+    fn write_line(&self, line: &str) -> io::Result<()> {
+        let mut state = self
+            .state
+            .lock()
+            .map_err(|_| io::Error::other("log state poisoned"))?;
+        if let Some(current) = state.as_ref() {
+            let age = SystemTime::now()
+                .duration_since(current.opened_at)
+                .unwrap_or_default();
+            if current.size > 0
+                && (current.size + line.len() as u64 > MAX_FILE_SIZE || age > MAX_FILE_AGE)
+            {
+                *state = None;
+                self.rotate()?;
+            }
+        }
+        if state.is_none() {
+            *state = Some(self.open()?);
+        }
+        let current = state.as_mut().expect("log file opened above");
+        current.file.write_all(line.as_bytes())?;
+        current.size += line.len() as u64;
+        Ok(())
+    }
+}
+
+impl Log for FileLogger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= log::max_level()
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let line = format!(
+            "{} {:<5} [{}] {}\n",
+            format_timestamp(SystemTime::now()),
+            record.level(),
+            record.target(),
+            record.args()
+        );
+        // 日志写入失败不能影响任务本身
+        let _ = self.write_line(&line);
+    }
+
+    fn flush(&self) {
+        if let Ok(mut state) = self.state.lock() {
+            if let Some(current) = state.as_mut() {
+                let _ = current.file.flush();
+            }
+        }
+    }
+}
+
+/// 安装文件日志。`default_dir` 为未通过环境变量指定目录时使用的日志目录，
+/// 两者都没有时不记录日志。重复调用只有第一次生效。
+pub(crate) fn init(default_dir: Option<PathBuf>) {
+    let dir = env::var_os(DIR_ENV)
+        .filter(|value| !value.is_empty())
+        .map(PathBuf::from)
+        .or(default_dir);
+    let Some(dir) = dir else {
+        return;
+    };
+    let level = env::var(LEVEL_ENV)
+        .ok()
+        .and_then(|value| value.parse().ok())
+        .unwrap_or(LevelFilter::Info);
+    let logger = LOGGER.get_or_init(|| FileLogger {
+        dir,
+        state: Mutex::new(None),
+    });
+    if log::set_logger(logger).is_ok() {
+        log::set_max_level(level);
+        log::info!(
+            "file-split-packer {} 启动，系统 {}/{}",
+            env!("CARGO_PKG_VERSION"),
+            env::consts::OS,
+            env::consts::ARCH
+        );
+    }
+}
+
+/// 当前使用的日志目录；未启用文件日志时为 `None`。
+pub(crate) fn log_dir() -> Option<&'static Path> {
+    LOGGER.get().map(|logger| logger.dir.as_path())
+}
+
+pub(crate) fn level() -> LevelFilter {
+    log::max_level()
+}
+
+/// 按名称（`off`、`error`、`warn`、`info`、`debug`、`trace`，忽略大小写）设置日志级别。
+pub(crate) fn set_level(name: &str) -> Result<LevelFilter, String> {
+    let level: LevelFilter = name
+        .trim()
+        .parse()
+        .map_err(|_| tr!(InvalidLogLevel, name))?;
+    log::set_max_level(level);
+    log::info!("日志级别调整为 {}", level);
+    Ok(level)
+}
+
+/// 记录任务结果与总用时。
+pub(crate) fn log_result<T>(label: &str, started: Instant, result: &Result<T, String>) {
+    match result {
+        Ok(_) => log::info!("{}完成，用时 {:.2?}", label, started.elapsed()),
+        Err(err) => log::error!("{}失败（用时 {:.2?}）：{}", label, started.elapsed(), err),
+    }
+}
+
+/// 累计 I/O 耗时（纳秒），可在多个读写器之间共享。
+#[derive(Clone, Default)]
+pub(crate) struct IoTimer(Arc<AtomicU64>);
+
+impl IoTimer {
+    pub(crate) fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.0.load(Ordering::Relaxed))
+    }
+
+    fn measure<T>(&self, f: impl FnOnce() -> T) -> T {
+        let started = Instant::now();
+        let value = f();
+        self.0
+            .fetch_add(started.elapsed().as_nanos() as u64, Ordering::Relaxed);
+        value
+    }
+}
+
+/// 统计 `read` 耗时的读取器。
+pub(crate) struct TimedRead<R> {
+    inner: R,
+    timer: IoTimer,
+}
+
+impl<R> TimedRead<R> {
+    pub(crate) fn new(inner: R, timer: IoTimer) -> Self {
+        TimedRead { inner, timer }
+    }
+}
+
+impl<R: Read> Read for TimedRead<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let inner = &mut self.inner;
+        self.timer.measure(|| inner.read(buf))
+    }
+}
+
+/// 从检查点续传时跳过已写出的分片。
+impl<R: Seek> Seek for TimedRead<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let inner = &mut self.inner;
+        self.timer.measure(|| inner.seek(pos))
+    }
+}
+
+/// 统计 `write` / `flush` / `seek` 耗时的写入器。
+pub(crate) struct TimedWrite<W> {
+    inner: W,
+    timer: IoTimer,
+}
+
+impl<W> TimedWrite<W> {
+    pub(crate) fn new(inner: W, timer: IoTimer) -> Self {
+        TimedWrite { inner, timer }
+    }
+}
+
+impl<W: Write> Write for TimedWrite<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let inner = &mut self.inner;
+        self.timer.measure(|| inner.write(buf))
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let inner = &mut self.inner;
+        self.timer.measure(|| inner.flush())
+    }
+}
+
+/// zip 写入结束时会回写本地文件头，定位时间一并计入写入。
+impl<W: Seek> Seek for TimedWrite<W> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let inner = &mut self.inner;
+        self.timer.measure(|| inner.seek(pos))
+    }
+}
+
+/// 单份分片的耗时拆分：读取、写入直接计时，其余时间计为压缩（含加密与打包）。
+pub(crate) struct PartTiming {
+    started: Instant,
+    read_start: Duration,
+    write_start: Duration,
+}
+
+impl PartTiming {
+    pub(crate) fn start(read: &IoTimer, write: &IoTimer) -> Self {
+        PartTiming {
+            started: Instant::now(),
+            read_start: read.elapsed(),
+            write_start: write.elapsed(),
+        }
+    }
+
+    pub(crate) fn finish(
+        self,
+        phase: &str,
+        part_index: usize,
+        part_total: usize,
+        bytes: u64,
+        read: &IoTimer,
+        write: &IoTimer,
+    ) {
+        let total = self.started.elapsed();
+        let read = read.elapsed().saturating_sub(self.read_start);
+        let write = write.elapsed().saturating_sub(self.write_start);
+        let compress = total.saturating_sub(read).saturating_sub(write);
+        let throughput = bytes as f64 / total.as_secs_f64().max(1e-9) / (1024.0 * 1024.0);
+        log::info!(
+            target: phase,
+            "第 {}/{} 份 {} 字节：读取 {:.2?}，压缩及其他 {:.2?}，写入 {:.2?}，共 {:.2?}（{:.1} MiB/s）",
+            part_index,
+            part_total,
+            bytes,
+            read,
+            compress,
+            write,
+            total,
+            throughput
+        );
+    }
+}
+
+/// 格式化为 UTC 时间 `YYYY-MM-DD HH:MM:SS.mmmZ`。
+fn format_timestamp(time: SystemTime) -> String {
+    let since_epoch = time.duration_since(UNIX_EPOCH).unwrap_or_default();
+    let secs = since_epoch.as_secs();
+    let (days, rem) = (secs / 86_400, secs % 86_400);
+    // 公历日期换算（Howard Hinnant 的 civil_from_days）
+    let z = days as i64 + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}:{:02}.{:03}Z",
+        year,
+        month,
+        day,
+        rem / 3_600,
+        rem % 3_600 / 60,
+        rem % 60,
+        since_epoch.subsec_millis()
+    )
+}
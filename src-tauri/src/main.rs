@@ -2,5 +2,13 @@
 #![cfg_attr(not(debug_assertions), windows_subsystem = "windows")]
 
 fn main() {
-    tauri_app_lib::run()
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if tauri_app_lib::is_restorer() {
+        return tauri_app_lib::run_restorer(args);
+    }
+    match args.first().map(String::as_str) {
+        Some("split") | Some("merge") | Some("test") | Some("keygen") => tauri_app_lib::run_cli(args),
+        _ if args.iter().any(|arg| arg == "--headless") => tauri_app_lib::run_headless(args),
+        _ => tauri_app_lib::run(),
+    }
 }
@@ -0,0 +1,130 @@
+//! 内存上限（`maxMemory`）：按 并发数 × 缓冲区大小 × 流水线深度 估算占用，
+//! 超出时先缩小缓冲区、再减少并发，避免在内存较小的机器上把多线程打包推进交换区。
+//! 按设备测速（见 `tuning`）得到的缓冲区大小与并发上限也在这里一并生效。
+
+use zip::CompressionMethod;
+
+/// 未设上限且未测速时每个复制缓冲区的大小。
+pub(crate) const DEFAULT_BUFFER_SIZE: usize = 8 * 1024 * 1024;
+/// 优先保持并发，缓冲区缩到这个大小以下时才减少并发数。
+const PREFERRED_MIN_BUFFER_SIZE: usize = 1024 * 1024;
+/// 单线程时缓冲区最小缩到的大小。
+const MIN_BUFFER_SIZE: usize = 64 * 1024;
+/// 允许设置的最小内存上限。
+pub(crate) const MIN_MEMORY: u64 = 4 * 1024 * 1024;
+/// 每个 zip 压缩线程除复制缓冲区外的占用估算（deflate 状态、写缓冲、zip 元数据等）。
+const ZIP_WORKER_OVERHEAD: u64 = 1024 * 1024;
+/// LZMA2 预设 0–9 的字典大小（与 xz 工具相同）。
+const XZ_PRESET_DICT_SIZES: [u64; 10] = [
+    256 << 10,
+    1 << 20,
+    2 << 20,
+    4 << 20,
+    4 << 20,
+    8 << 20,
+    8 << 20,
+    16 << 20,
+    32 << 20,
+    64 << 20,
+];
+/// 每个 zstd 压缩线程除帧缓冲外的占用估算（压缩上下文与哈希表）。
+pub(crate) const ZSTD_WORKER_OVERHEAD: u64 = 16 * 1024 * 1024;
+/// 每个 zstd 多线程压缩工作线程的占用估算（压缩上下文加上待压缩与已压缩的任务块）。
+pub(crate) const ZSTD_MT_WORKER_MEMORY: u64 = 64 * 1024 * 1024;
+
+/// 每个 zip 压缩线程除复制缓冲区外的占用估算。xz 编码器的匹配查找表约为字典的 10 倍以上，
+/// 预设 9 时每个线程接近 700 MiB，需要按等级估算才能让 `maxMemory` 有效。
+pub(crate) fn zip_worker_overhead(compression: CompressionMethod, level: Option<i64>) -> u64 {
+    match compression {
+        CompressionMethod::Xz => {
+            let preset = level.unwrap_or(6).clamp(0, 9) as usize;
+            XZ_PRESET_DICT_SIZES[preset] * 11 + ZIP_WORKER_OVERHEAD
+        }
+        _ => ZIP_WORKER_OVERHEAD,
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct MemoryBudget {
+    max: Option<u64>,
+    /// 缓冲区大小上限，默认 `DEFAULT_BUFFER_SIZE`，可按设备速度调整。
+    buffer_cap: usize,
+    /// 按设备速度限制的并发数。
+    worker_cap: Option<usize>,
+}
+
+impl Default for MemoryBudget {
+    fn default() -> Self {
+        MemoryBudget::new(None)
+    }
+}
+
+impl MemoryBudget {
+    pub(crate) fn new(max: Option<u64>) -> Self {
+        MemoryBudget {
+            max,
+            buffer_cap: DEFAULT_BUFFER_SIZE,
+            worker_cap: None,
+        }
+    }
+
+    /// 叠加按设备速度选择的缓冲区大小与并发上限，内存上限仍然优先。
+    pub(crate) fn with_io_limits(self, buffer_size: usize, max_workers: Option<usize>) -> Self {
+        MemoryBudget {
+            buffer_cap: buffer_size.max(MIN_BUFFER_SIZE),
+            worker_cap: max_workers.map(|workers| workers.max(1)),
+            ..self
+        }
+    }
+
+    fn capped_workers(&self, desired_workers: usize) -> usize {
+        desired_workers
+            .max(1)
+            .min(self.worker_cap.unwrap_or(usize::MAX))
+    }
+
+    /// 设置的内存上限（字节）。
+    pub(crate) fn limit(&self) -> Option<u64> {
+        self.max
+    }
+
+    /// 单线程顺序复制时使用的缓冲区大小。
+    pub(crate) fn buffer_size(&self) -> usize {
+        self.plan(1, 0, 1).1
+    }
+
+    /// 计算并发数与每个缓冲区的大小：每个线程占用 `overhead` 字节加上
+    /// `buffers_per_worker` 个缓冲区（流水线深度）。
+    pub(crate) fn plan(
+        &self,
+        desired_workers: usize,
+        overhead: u64,
+        buffers_per_worker: usize,
+    ) -> (usize, usize) {
+        let desired_workers = self.capped_workers(desired_workers);
+        let Some(max) = self.max else {
+            return (desired_workers, self.buffer_cap);
+        };
+        let buffers_per_worker = buffers_per_worker.max(1) as u64;
+        let buffer_for = |workers: usize| {
+            (max / workers as u64).saturating_sub(overhead) / buffers_per_worker
+        };
+        for workers in (1..=desired_workers).rev() {
+            let buffer = buffer_for(workers);
+            if buffer >= PREFERRED_MIN_BUFFER_SIZE as u64 {
+                return (workers, buffer.min(self.buffer_cap as u64) as usize);
+            }
+        }
+        let buffer = buffer_for(1).clamp(MIN_BUFFER_SIZE as u64, self.buffer_cap as u64);
+        (1, buffer as usize)
+    }
+
+    /// 缓冲区大小固定（如 zstd 帧）时，上限内能同时运行的线程数。
+    pub(crate) fn workers(&self, desired_workers: usize, per_worker: u64) -> usize {
+        let desired_workers = self.capped_workers(desired_workers);
+        match self.max {
+            Some(max) => ((max / per_worker.max(1)) as usize).clamp(1, desired_workers),
+            None => desired_workers,
+        }
+    }
+}
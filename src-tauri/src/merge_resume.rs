@@ -0,0 +1,208 @@
+//! 合并续传：直接拼接分片时（`raw`、`zip-then-split`、`7z-then-split` 与 tar 方式）先写入输出目录中的
+//! `<名称>.merge.tmp`，同时在旁边的 `<名称>.merge.tmp.progress` 中记录已完整写入并同步到磁盘的分片数
+//! 与字节数。合并因崩溃、断电或出错中断后，以相同参数再次合并时把临时文件截到记录的位置，从下一份
+//! 分片继续，不再重新复制已合并的数据；合并完成后删除记录。
+//!
+//! 记录中保存合并参数的指纹与各份分片的名称、大小与修改时间，任何一项不同时从头合并。分片很多时
+//! 记录每秒最多更新一次，中断时最后一秒内写完的分片会重新复制。取消任务时照常删除临时文件与记录。
+
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use sha2::{Digest, Sha256};
+use std::{
+    ffi::OsString,
+    fs::{self, File, OpenOptions},
+    io::{BufWriter, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    time::{Duration, Instant, UNIX_EPOCH},
+};
+
+use crate::{jobs::JobContext, PartInfo, RestoreOptions};
+
+const PROGRESS_SUFFIX: &str = ".progress";
+const PROGRESS_VERSION: u32 = 1;
+/// 更新记录的最短间隔，每次都同步到磁盘时大量小分片的合并会明显变慢。
+const SAVE_INTERVAL: Duration = Duration::from_secs(1);
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ProgressState {
+    version: u32,
+    fingerprint: String,
+    parts: Vec<PartStamp>,
+    /// 已完整写入的分片数。
+    completed: usize,
+    /// 这些分片在临时文件中占的字节数。
+    bytes: u64,
+}
+
+#[derive(Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PartStamp {
+    name: String,
+    size: u64,
+    /// 修改时间（Unix 毫秒）。
+    modified: u64,
+}
+
+/// 一次合并的续传记录。
+#[derive(Debug)]
+pub(crate) struct MergeProgress {
+    path: PathBuf,
+    state: ProgressState,
+    saved_at: Option<Instant>,
+}
+
+/// 合并参数的指纹；合并结果只取决于分片，分片另外逐份记录。
+pub(crate) fn fingerprint(options: &RestoreOptions) -> String {
+    let value = json!({
+        "mergeMode": options.merge_mode,
+        "gpgDecrypt": options.gpg_decrypt,
+        "filterCommand": options.filter_command,
+    });
+    format!("{:x}", Sha256::digest(value.to_string().as_bytes()))
+}
+
+/// 打开合并目标：开启续传且有相同参数与分片留下的记录时截到已完成的位置继续写，否则新建。
+/// 返回目标文件与续传记录；未开启续传时记录为 `None`。
+pub(crate) fn open(
+    ctx: &JobContext,
+    parts: &[PartInfo],
+    sizes: &[u64],
+    target: &Path,
+) -> Result<(File, Option<MergeProgress>), String> {
+    let path = progress_path(target);
+    let Some(fingerprint) = ctx.resume_fingerprint() else {
+        let _ = fs::remove_file(&path);
+        return Ok((File::create(target).map_err(|e| e.to_string())?, None));
+    };
+    let stamps = parts
+        .iter()
+        .zip(sizes)
+        .map(|(part, size)| stamp(&part.path, *size))
+        .collect::<Vec<_>>();
+
+    let resumed = load(&path)
+        .filter(|state| state.fingerprint == fingerprint && state.parts == stamps)
+        .filter(|state| {
+            fs::metadata(target).is_ok_and(|meta| meta.is_file() && meta.len() >= state.bytes)
+        });
+    let (file, state) = match resumed {
+        Some(state) => {
+            let mut file = OpenOptions::new()
+                .write(true)
+                .open(target)
+                .map_err(|e| e.to_string())?;
+            file.set_len(state.bytes).map_err(|e| e.to_string())?;
+            file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+            log::info!(
+                "从上次中断处继续合并：{}（已完成 {}/{} 份，{} 字节）",
+                target.display(),
+                state.completed,
+                parts.len(),
+                state.bytes
+            );
+            (file, state)
+        }
+        None => (
+            File::create(target).map_err(|e| e.to_string())?,
+            ProgressState {
+                version: PROGRESS_VERSION,
+                fingerprint: fingerprint.to_string(),
+                parts: stamps,
+                completed: 0,
+                bytes: 0,
+            },
+        ),
+    };
+    ctx.track_output(&path);
+    let progress = MergeProgress {
+        path,
+        state,
+        saved_at: None,
+    };
+    progress.save()?;
+    Ok((file, Some(progress)))
+}
+
+impl MergeProgress {
+    /// 已完整写入的分片数，续传时从其后的一份开始。
+    pub(crate) fn completed(&self) -> usize {
+        self.state.completed
+    }
+
+    /// 前 `completed` 份分片（共 `bytes` 字节）已写入 `writer` 后调用。距上次更新不足
+    /// [`SAVE_INTERVAL`] 时不做任何事；否则先把数据同步到磁盘再更新记录。
+    pub(crate) fn mark(
+        &mut self,
+        writer: &mut BufWriter<File>,
+        completed: usize,
+        bytes: u64,
+    ) -> Result<(), String> {
+        if self
+            .saved_at
+            .is_some_and(|saved_at| saved_at.elapsed() < SAVE_INTERVAL)
+        {
+            return Ok(());
+        }
+        writer.flush().map_err(|e| e.to_string())?;
+        writer.get_ref().sync_data().map_err(|e| e.to_string())?;
+        self.state.completed = completed;
+        self.state.bytes = bytes;
+        self.save()?;
+        self.saved_at = Some(Instant::now());
+        Ok(())
+    }
+
+    /// 全部分片写完后删除记录。
+    pub(crate) fn finish(self) {
+        let _ = fs::remove_file(&self.path);
+    }
+
+    /// 先写临时文件再改名，崩溃时记录要么是旧的、要么是新的。
+    fn save(&self) -> Result<(), String> {
+        let text = serde_json::to_string_pretty(&self.state).map_err(|e| e.to_string())?;
+        let temp = self.path.with_extension("progress.tmp");
+        fs::write(&temp, text).map_err(|e| e.to_string())?;
+        fs::rename(&temp, &self.path).map_err(|e| e.to_string())
+    }
+}
+
+fn progress_path(target: &Path) -> PathBuf {
+    let mut path = OsString::from(target.as_os_str());
+    path.push(PROGRESS_SUFFIX);
+    PathBuf::from(path)
+}
+
+fn load(path: &Path) -> Option<ProgressState> {
+    let text = fs::read_to_string(path).ok()?;
+    match serde_json::from_str::<ProgressState>(&text) {
+        Ok(state) if state.version == PROGRESS_VERSION => Some(state),
+        Ok(_) => None,
+        Err(err) => {
+            log::warn!(
+                "合并续传记录无法解析，从头合并：{}（{}）",
+                path.display(),
+                err
+            );
+            None
+        }
+    }
+}
+
+fn stamp(path: &Path, size: u64) -> PartStamp {
+    let modified = fs::metadata(path)
+        .and_then(|meta| meta.modified())
+        .ok()
+        .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+        .map(|duration| duration.as_millis() as u64)
+        .unwrap_or(0);
+    PartStamp {
+        name: path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default(),
+        size,
+        modified,
+    }
+}
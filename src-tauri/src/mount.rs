@@ -0,0 +1,458 @@
+//! 只读挂载分片组（FUSE，仅 Linux/macOS，需启用 `mount` 特性；Windows 上的 Dokan 暂不支持）。
+//! 按分片索引（有缓存时直接使用，否则生成并写入缓存）定位各分片的数据区，文件内容按需读取：
+//! Store 条目直接定位；较小的压缩条目整个解压到内存；较大的压缩条目每个打开的文件句柄各有
+//! 一个顺序解压的线程，往后跳读时丢弃中间数据，跳回已读过的位置时才从头重新解压。
+
+use fuser::{
+    BackgroundSession, FileAttr, FileType, Filesystem, MountOption, ReplyAttr, ReplyData,
+    ReplyDirectory, ReplyEmpty, ReplyEntry, ReplyOpen, Request,
+};
+use std::{
+    collections::HashMap,
+    ffi::OsStr,
+    fs,
+    io::{BufReader, Read, Seek, SeekFrom},
+    path::Path,
+    sync::{
+        mpsc::{self, Receiver},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, SystemTime},
+};
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::{
+    entry_names::{NameDecoder, NameEncoding},
+    entry_times::EntryTimes,
+    i18n::tr,
+    index::{self, IndexedPart},
+    open_zip_file,
+    partial::{open_archive_from_layout, PartSource},
+    secrets::SecretString,
+    PartGroup,
+};
+
+const TTL: Duration = Duration::from_secs(60);
+const ROOT_INO: u64 = 1;
+/// 压缩条目不超过该大小时整个解压到内存缓存，避免反复从头解压。
+const CACHE_LIMIT: u64 = 64 * 1024 * 1024;
+/// 解压线程每次送出的数据量。
+const STREAM_CHUNK: usize = 1024 * 1024;
+/// 解压线程最多领先读取位置的块数。
+const STREAM_AHEAD: usize = 4;
+
+static MOUNTS: OnceLock<Mutex<HashMap<String, BackgroundSession>>> = OnceLock::new();
+
+fn mounts() -> &'static Mutex<HashMap<String, BackgroundSession>> {
+    MOUNTS.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+struct Node {
+    name: String,
+    parent: u64,
+    is_dir: bool,
+    size: u64,
+    mtime: SystemTime,
+    entry_index: Option<usize>,
+    children: Vec<u64>,
+}
+
+/// 重新打开分片组所需的信息，供解压线程各自打开一份压缩包。
+struct PartSet {
+    part_group: PartGroup,
+    merge_mode: String,
+    layout: Vec<IndexedPart>,
+}
+
+impl PartSet {
+    fn open(&self) -> Result<ZipArchive<BufReader<PartSource>>, String> {
+        open_archive_from_layout(&self.part_group, &self.merge_mode, &self.layout)
+    }
+}
+
+/// 一个文件句柄上的顺序解压：后台线程从头解压条目并按块送出，`position` 为已取走的字节数。
+struct EntryStream {
+    index: usize,
+    position: u64,
+    chunks: Receiver<Result<Vec<u8>, String>>,
+    pending: Vec<u8>,
+    pending_pos: usize,
+}
+
+impl EntryStream {
+    fn start(set: &Arc<PartSet>, index: usize, password: Option<SecretString>) -> Self {
+        let (sender, chunks) = mpsc::sync_channel(STREAM_AHEAD);
+        let set = Arc::clone(set);
+        thread::spawn(move || {
+            let result = set.open().and_then(|mut archive| {
+                let mut entry = open_zip_file(&mut archive, index, password.as_deref())?;
+                loop {
+                    let mut chunk = vec![0u8; STREAM_CHUNK];
+                    let read = entry.read(&mut chunk).map_err(|e| e.to_string())?;
+                    if read == 0 {
+                        return Ok(());
+                    }
+                    chunk.truncate(read);
+                    // 句柄已关闭或已改为重新解压
+                    if sender.send(Ok(chunk)).is_err() {
+                        return Ok(());
+                    }
+                }
+            });
+            if let Err(err) = result {
+                let _ = sender.send(Err(err));
+            }
+        });
+        EntryStream {
+            index,
+            position: 0,
+            chunks,
+            pending: Vec::new(),
+            pending_pos: 0,
+        }
+    }
+
+    /// 确保有未取走的数据；解压结束时返回 `false`。
+    fn fill(&mut self) -> Result<bool, String> {
+        if self.pending_pos < self.pending.len() {
+            return Ok(true);
+        }
+        match self.chunks.recv() {
+            Ok(Ok(chunk)) => {
+                self.pending = chunk;
+                self.pending_pos = 0;
+                Ok(true)
+            }
+            Ok(Err(err)) => Err(err),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// 读取 `offset` 起的最多 `want` 字节，`offset` 不能小于 `position`。
+    fn read_at(&mut self, offset: u64, want: u64) -> Result<Vec<u8>, String> {
+        let mut buf = Vec::with_capacity(want as usize);
+        while (buf.len() as u64) < want && self.fill()? {
+            let available = (self.pending.len() - self.pending_pos) as u64;
+            let skip = offset.saturating_sub(self.position).min(available);
+            let take = if skip > 0 {
+                0
+            } else {
+                (want - buf.len() as u64).min(available)
+            };
+            let start = self.pending_pos + skip as usize;
+            buf.extend_from_slice(&self.pending[start..start + take as usize]);
+            self.pending_pos += (skip + take) as usize;
+            self.position += skip + take;
+        }
+        Ok(buf)
+    }
+}
+
+struct PartSetFs {
+    set: Arc<PartSet>,
+    archive: ZipArchive<BufReader<PartSource>>,
+    password: Option<SecretString>,
+    nodes: Vec<Node>,
+    cache: Option<(usize, Vec<u8>)>,
+    streams: HashMap<u64, EntryStream>,
+    next_fh: u64,
+}
+
+impl PartSetFs {
+    fn new(
+        set: PartSet,
+        password: Option<SecretString>,
+        name_encoding: Option<NameEncoding>,
+    ) -> Result<Self, String> {
+        let mut archive = set.open()?;
+        let names = NameDecoder::for_archive(name_encoding, &mut archive);
+        let now = SystemTime::now();
+        let mut nodes = vec![Node {
+            name: String::new(),
+            parent: ROOT_INO,
+            is_dir: true,
+            size: 0,
+            mtime: now,
+            entry_index: None,
+            children: Vec::new(),
+        }];
+        let mut lookup: HashMap<String, u64> = HashMap::new();
+
+        for index in 0..archive.len() {
+            let raw = archive.by_index_raw(index).map_err(|e| e.to_string())?;
+            let Some(path) = names.enclosed_name(&raw) else {
+                continue;
+            };
+            let mtime = EntryTimes::from_zip(&raw).modified().unwrap_or(now);
+            let components: Vec<String> = path
+                .components()
+                .map(|part| part.as_os_str().to_string_lossy().to_string())
+                .collect();
+            let mut parent = ROOT_INO;
+            let mut key = String::new();
+            for (depth, name) in components.iter().enumerate() {
+                key.push('/');
+                key.push_str(name);
+                let is_leaf = depth + 1 == components.len();
+                if let Some(ino) = lookup.get(&key) {
+                    parent = *ino;
+                    continue;
+                }
+                let is_dir = !is_leaf || raw.is_dir();
+                nodes.push(Node {
+                    name: name.clone(),
+                    parent,
+                    is_dir,
+                    size: if is_dir { 0 } else { raw.size() },
+                    mtime,
+                    entry_index: if is_dir { None } else { Some(index) },
+                    children: Vec::new(),
+                });
+                let ino = nodes.len() as u64;
+                nodes[(parent - 1) as usize].children.push(ino);
+                lookup.insert(key.clone(), ino);
+                parent = ino;
+            }
+        }
+
+        Ok(PartSetFs {
+            set: Arc::new(set),
+            archive,
+            password,
+            nodes,
+            cache: None,
+            streams: HashMap::new(),
+            next_fh: 1,
+        })
+    }
+
+    fn node(&self, ino: u64) -> Option<&Node> {
+        ino.checked_sub(1)
+            .and_then(|idx| self.nodes.get(idx as usize))
+    }
+
+    fn attr(&self, ino: u64) -> Option<FileAttr> {
+        let node = self.node(ino)?;
+        Some(FileAttr {
+            ino,
+            size: node.size,
+            blocks: node.size.div_ceil(512),
+            atime: node.mtime,
+            mtime: node.mtime,
+            ctime: node.mtime,
+            crtime: node.mtime,
+            kind: if node.is_dir {
+                FileType::Directory
+            } else {
+                FileType::RegularFile
+            },
+            perm: if node.is_dir { 0o555 } else { 0o444 },
+            nlink: if node.is_dir { 2 } else { 1 },
+            uid: unsafe { libc::getuid() },
+            gid: unsafe { libc::getgid() },
+            rdev: 0,
+            blksize: 512,
+            flags: 0,
+        })
+    }
+
+    fn read_entry(
+        &mut self,
+        fh: u64,
+        index: usize,
+        offset: u64,
+        size: u64,
+    ) -> Result<Vec<u8>, String> {
+        let (direct, entry_size) = {
+            let raw = self.archive.by_index_raw(index).map_err(|e| e.to_string())?;
+            (
+                matches!(raw.compression(), CompressionMethod::Stored) && !raw.encrypted(),
+                raw.size(),
+            )
+        };
+        if offset >= entry_size {
+            return Ok(Vec::new());
+        }
+        let want = size.min(entry_size - offset);
+
+        if direct {
+            let mut entry = self.archive.by_index_seek(index).map_err(|e| e.to_string())?;
+            entry
+                .seek(SeekFrom::Start(offset))
+                .map_err(|e| e.to_string())?;
+            let mut buf = vec![0u8; want as usize];
+            entry.read_exact(&mut buf).map_err(|e| e.to_string())?;
+            return Ok(buf);
+        }
+
+        if entry_size <= CACHE_LIMIT {
+            if !matches!(&self.cache, Some((cached, _)) if *cached == index) {
+                let mut entry = open_zip_file(&mut self.archive, index, self.password.as_deref())?;
+                let mut data = Vec::with_capacity(entry_size as usize);
+                entry.read_to_end(&mut data).map_err(|e| e.to_string())?;
+                self.cache = Some((index, data));
+            }
+            let (_, data) = self.cache.as_ref().expect("cached entry");
+            let start = offset as usize;
+            return Ok(data[start..start + want as usize].to_vec());
+        }
+
+        let restart = !matches!(
+            self.streams.get(&fh),
+            Some(stream) if stream.index == index && stream.position <= offset
+        );
+        if restart {
+            let stream = EntryStream::start(&self.set, index, self.password.clone());
+            self.streams.insert(fh, stream);
+        }
+        let stream = self.streams.get_mut(&fh).expect("entry stream");
+        stream.read_at(offset, want)
+    }
+}
+
+impl Filesystem for PartSetFs {
+    fn lookup(&mut self, _req: &Request<'_>, parent: u64, name: &OsStr, reply: ReplyEntry) {
+        let name = name.to_string_lossy();
+        let found = self.node(parent).and_then(|node| {
+            node.children
+                .iter()
+                .copied()
+                .find(|child| self.node(*child).map(|c| c.name == name).unwrap_or(false))
+        });
+        match found.and_then(|ino| self.attr(ino)) {
+            Some(attr) => reply.entry(&TTL, &attr, 0),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn getattr(&mut self, _req: &Request<'_>, ino: u64, _fh: Option<u64>, reply: ReplyAttr) {
+        match self.attr(ino) {
+            Some(attr) => reply.attr(&TTL, &attr),
+            None => reply.error(libc::ENOENT),
+        }
+    }
+
+    fn open(&mut self, _req: &Request<'_>, ino: u64, _flags: i32, reply: ReplyOpen) {
+        if self.node(ino).is_none() {
+            reply.error(libc::ENOENT);
+            return;
+        }
+        let fh = self.next_fh;
+        self.next_fh += 1;
+        reply.opened(fh, 0);
+    }
+
+    fn release(
+        &mut self,
+        _req: &Request<'_>,
+        _ino: u64,
+        fh: u64,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        _flush: bool,
+        reply: ReplyEmpty,
+    ) {
+        // 丢弃接收端后解压线程在下一次送出时退出
+        self.streams.remove(&fh);
+        reply.ok();
+    }
+
+    fn read(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        fh: u64,
+        offset: i64,
+        size: u32,
+        _flags: i32,
+        _lock_owner: Option<u64>,
+        reply: ReplyData,
+    ) {
+        let Some(index) = self.node(ino).and_then(|node| node.entry_index) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        match self.read_entry(fh, index, offset.max(0) as u64, size as u64) {
+            Ok(data) => reply.data(&data),
+            Err(_) => reply.error(libc::EIO),
+        }
+    }
+
+    fn readdir(
+        &mut self,
+        _req: &Request<'_>,
+        ino: u64,
+        _fh: u64,
+        offset: i64,
+        mut reply: ReplyDirectory,
+    ) {
+        let Some(node) = self.node(ino) else {
+            reply.error(libc::ENOENT);
+            return;
+        };
+        let mut listing = vec![
+            (ino, FileType::Directory, ".".to_string()),
+            (node.parent, FileType::Directory, "..".to_string()),
+        ];
+        for child in &node.children {
+            if let Some(child_node) = self.node(*child) {
+                let kind = if child_node.is_dir {
+                    FileType::Directory
+                } else {
+                    FileType::RegularFile
+                };
+                listing.push((*child, kind, child_node.name.clone()));
+            }
+        }
+        for (idx, (child, kind, name)) in listing.into_iter().enumerate().skip(offset as usize) {
+            if reply.add(child, (idx + 1) as i64, kind, name) {
+                break;
+            }
+        }
+        reply.ok();
+    }
+}
+
+/// 以只读方式把分片组挂载到 `mount_point`，返回条目数量。
+pub(crate) fn mount_part_group(
+    part_group: PartGroup,
+    merge_mode: &str,
+    mount_point: &Path,
+    password: Option<&str>,
+    name_encoding: Option<NameEncoding>,
+) -> Result<usize, String> {
+    let key = mount_point.to_string_lossy().to_string();
+    let mut sessions = mounts().lock().map_err(|_| tr!(MountStateError))?;
+    if sessions.contains_key(&key) {
+        return Err(tr!(AlreadyMounted));
+    }
+    fs::create_dir_all(mount_point).map_err(|e| e.to_string())?;
+
+    let (index, _) = index::load_or_build_index(&part_group, merge_mode, false)?;
+    let fs_name = part_group.prefix.trim_end_matches('.').to_string();
+    let set = PartSet {
+        part_group,
+        merge_mode: merge_mode.to_string(),
+        layout: index.parts,
+    };
+    let filesystem = PartSetFs::new(set, password.map(SecretString::from), name_encoding)?;
+    let entries = filesystem.archive.len();
+    let options = [MountOption::RO, MountOption::FSName(fs_name)];
+    let session =
+        fuser::spawn_mount2(filesystem, mount_point, &options).map_err(|e| e.to_string())?;
+    sessions.insert(key, session);
+    Ok(entries)
+}
+
+pub(crate) fn unmount(mount_point: &Path) -> Result<(), String> {
+    let key = mount_point.to_string_lossy().to_string();
+    let session = mounts()
+        .lock()
+        .map_err(|_| tr!(MountStateError))?
+        .remove(&key)
+        .ok_or_else(|| tr!(NotMounted))?;
+    // 会话释放时自动卸载
+    drop(session);
+    Ok(())
+}
@@ -0,0 +1,1072 @@
+//! PAR2 恢复文件（默认关闭）：设置 `par2Redundancy`（百分比）后，切分完成时按 PAR 2.0 规范
+//! 为全部分片生成恢复卷，收件方可用 par2cmdline、MultiPar 等通用工具校验；分片出现坏块
+//! 或被截断时，只要损坏的数据块不多于恢复块就能修复。
+//!
+//! 恢复文件与分片放在同一目录：`<名称>.par2` 只含校验信息，`<名称>.volNN+MM.par2`
+//! 依次含 1、2、4……个恢复块，收件方按损坏程度取其中几个即可。
+//!
+//! 恢复块按规范在 GF(2^16) 上做 Reed–Solomon 编码。每一轮只读入各数据块的同一段，
+//! 内存占用约为（数据块数 + 恢复块数）× 段长；各恢复块由 rayon 线程并行计算，
+//! 以后台优先级运行时只用当前线程。
+//!
+//! 修复分片（参见 [`crate::repair`]）时读出目录中全部 `.par2` 文件的完好包，逐块核对分片，
+//! 用恢复块解出缺失或损坏的数据块；不认识的包与损坏的包直接跳过。
+
+use md5::{Digest, Md5};
+use rayon::prelude::*;
+use std::{
+    collections::{BTreeMap, HashMap},
+    fs::{self, File, OpenOptions},
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    sync::OnceLock,
+};
+
+use crate::{emit_progress, i18n::tr, jobs::JobContext, repair::Outcome};
+
+/// 冗余比例（恢复块数占数据块数的百分比）上限。
+pub(crate) const MAX_REDUNDANCY: u32 = 100;
+/// PAR 2.0 允许的数据块数上限。
+const MAX_SOURCE_SLICES: u64 = 32768;
+/// 按总大小选择块大小时期望的数据块数，与 par2cmdline 的默认值相同。
+const TARGET_SOURCE_SLICES: u64 = 2000;
+/// 块大小下限，分片很小时避免校验包比数据还大。
+const MIN_SLICE_SIZE: u64 = 4 * 1024;
+/// 未设置内存上限时，每轮读入的数据段与恢复段合计的大小。
+const ENCODE_MEMORY: u64 = 256 * 1024 * 1024;
+/// 每轮段长的下限，数据块很多时避免读得过碎。
+const MIN_WINDOW: u64 = 64 * 1024;
+/// 文件描述包中单独计算 MD5 的开头长度。
+const HASH_16K_LEN: u64 = 16 * 1024;
+const CREATOR: &str = concat!("file-split-packer ", env!("CARGO_PKG_VERSION"));
+
+const MAGIC: &[u8; 8] = b"PAR2\0PKT";
+const HEADER_LEN: u64 = 64;
+const TYPE_MAIN: &[u8; 16] = b"PAR 2.0\0Main\0\0\0\0";
+const TYPE_FILE_DESC: &[u8; 16] = b"PAR 2.0\0FileDesc";
+const TYPE_SLICE_CHECKSUMS: &[u8; 16] = b"PAR 2.0\0IFSC\0\0\0\0";
+const TYPE_RECOVERY: &[u8; 16] = b"PAR 2.0\0RecvSlic";
+const TYPE_CREATOR: &[u8; 16] = b"PAR 2.0\0Creator\0";
+
+/// GF(2^16) 乘法群的阶。
+const FIELD_ORDER: u32 = 65535;
+/// 生成多项式 x^16 + x^12 + x^3 + x + 1。
+const FIELD_POLYNOMIAL: u32 = 0x1100B;
+
+/// GF(2^16) 的对数表与反对数表，恢复分片（参见 [`crate::erasure`]）也用它计算。
+pub(crate) struct Field {
+    log: Vec<u32>,
+    /// 存两个周期，两个对数相加后不必取模。
+    exp: Vec<u16>,
+}
+
+pub(crate) fn field() -> &'static Field {
+    static FIELD: OnceLock<Field> = OnceLock::new();
+    FIELD.get_or_init(|| {
+        let mut log = vec![0u32; 1 << 16];
+        let mut exp = vec![0u16; 2 * FIELD_ORDER as usize];
+        let mut value = 1u32;
+        for power in 0..FIELD_ORDER {
+            exp[power as usize] = value as u16;
+            exp[(power + FIELD_ORDER) as usize] = value as u16;
+            log[value as usize] = power;
+            value <<= 1;
+            if value & 0x10000 != 0 {
+                value ^= FIELD_POLYNOMIAL;
+            }
+        }
+        Field { log, exp }
+    })
+}
+
+impl Field {
+    /// 非零元素的对数。
+    pub(crate) fn log(&self, value: u16) -> u32 {
+        self.log[value as usize]
+    }
+
+    pub(crate) fn mul(&self, a: u16, b: u16) -> u16 {
+        if a == 0 || b == 0 {
+            return 0;
+        }
+        self.exp[(self.log[a as usize] + self.log[b as usize]) as usize]
+    }
+
+    /// 2 的 `exponent` 次幂。
+    fn power(&self, exponent: u64) -> u16 {
+        self.exp[(exponent % FIELD_ORDER as u64) as usize]
+    }
+
+    /// 非零元素的乘法逆元。
+    pub(crate) fn inv(&self, value: u16) -> u16 {
+        self.exp[((FIELD_ORDER - self.log[value as usize]) % FIELD_ORDER) as usize]
+    }
+
+    /// `target ^= factor × source`，数据按 16 位小端字计算，`factor` 以对数给出。
+    pub(crate) fn mul_add(&self, target: &mut [u8], source: &[u8], factor_log: u32) {
+        for (out, word) in target.chunks_exact_mut(2).zip(source.chunks_exact(2)) {
+            let word = u16::from_le_bytes([word[0], word[1]]);
+            if word == 0 {
+                continue;
+            }
+            let product = self.exp[(self.log[word as usize] + factor_log) as usize];
+            let current = u16::from_le_bytes([out[0], out[1]]);
+            out.copy_from_slice(&(current ^ product).to_le_bytes());
+        }
+    }
+
+    /// 方阵求逆（Gauss–Jordan），不可逆时返回 `None`。
+    pub(crate) fn invert(&self, mut matrix: Vec<Vec<u16>>) -> Option<Vec<Vec<u16>>> {
+        let size = matrix.len();
+        let mut inverse: Vec<Vec<u16>> = (0..size)
+            .map(|row| (0..size).map(|col| u16::from(row == col)).collect())
+            .collect();
+        for col in 0..size {
+            let pivot = (col..size).find(|&row| matrix[row][col] != 0)?;
+            matrix.swap(col, pivot);
+            inverse.swap(col, pivot);
+            let scale = self.inv(matrix[col][col]);
+            for value in matrix[col].iter_mut().chain(inverse[col].iter_mut()) {
+                *value = self.mul(*value, scale);
+            }
+            for row in 0..size {
+                let factor = matrix[row][col];
+                if row == col || factor == 0 {
+                    continue;
+                }
+                for idx in 0..size {
+                    matrix[row][idx] ^= self.mul(factor, matrix[col][idx]);
+                    inverse[row][idx] ^= self.mul(factor, inverse[col][idx]);
+                }
+            }
+        }
+        Some(inverse)
+    }
+}
+
+/// `outputs` 的第 r 段 = Σ `matrix[r][i]` × `inputs` 的第 i 段，每段长 `len`。
+pub(crate) fn combine(
+    ctx: &JobContext,
+    matrix: &[Vec<u16>],
+    inputs: &[u8],
+    outputs: &mut [u8],
+    len: usize,
+) {
+    let field = field();
+    let encode = |(row, target): (usize, &mut [u8])| {
+        target.fill(0);
+        for (source, &factor) in inputs.chunks_exact(len).zip(&matrix[row]) {
+            match factor {
+                0 => {}
+                1 => target.iter_mut().zip(source).for_each(|(out, byte)| *out ^= byte),
+                _ => field.mul_add(target, source, field.log(factor)),
+            }
+        }
+    };
+    if ctx.background_priority() {
+        outputs.chunks_exact_mut(len).enumerate().for_each(encode);
+    } else {
+        outputs.par_chunks_exact_mut(len).enumerate().for_each(encode);
+    }
+}
+
+/// 各数据块系数的对数：第 i 块的系数为 2^n，n 依次取与 65535 互质的数。
+fn slice_bases(count: usize) -> Vec<u32> {
+    (1..FIELD_ORDER)
+        .filter(|n| n % 3 != 0 && n % 5 != 0 && n % 17 != 0 && n % 257 != 0)
+        .take(count)
+        .collect()
+}
+
+fn md5(data: &[u8]) -> [u8; 16] {
+    Md5::digest(data).into()
+}
+
+/// 恢复集中的一个分片。
+struct SourceFile {
+    path: PathBuf,
+    name: String,
+    size: u64,
+    id: [u8; 16],
+    hash: [u8; 16],
+    hash_16k: [u8; 16],
+    /// 每个数据块的 MD5 与 CRC32，末块补零到块大小后计算。
+    slices: Vec<([u8; 16], u32)>,
+}
+
+/// 逐段计算数据块的 MD5 与 CRC32。
+struct SliceHasher {
+    slice_size: u64,
+    filled: u64,
+    md5: Md5,
+    crc: crc32fast::Hasher,
+    slices: Vec<([u8; 16], u32)>,
+}
+
+impl SliceHasher {
+    fn new(slice_size: u64) -> Self {
+        SliceHasher {
+            slice_size,
+            filled: 0,
+            md5: Md5::new(),
+            crc: crc32fast::Hasher::new(),
+            slices: Vec::new(),
+        }
+    }
+
+    fn update(&mut self, mut data: &[u8]) {
+        while !data.is_empty() {
+            let take = ((self.slice_size - self.filled) as usize).min(data.len());
+            self.md5.update(&data[..take]);
+            self.crc.update(&data[..take]);
+            self.filled += take as u64;
+            data = &data[take..];
+            if self.filled == self.slice_size {
+                let md5 = std::mem::take(&mut self.md5).finalize().into();
+                let crc = std::mem::take(&mut self.crc).finalize();
+                self.slices.push((md5, crc));
+                self.filled = 0;
+            }
+        }
+    }
+
+    /// 末块不足块大小时补零，`zeros` 为任意长度的全零缓冲区。
+    fn finish(mut self, zeros: &[u8]) -> Vec<([u8; 16], u32)> {
+        while self.filled > 0 {
+            let take = ((self.slice_size - self.filled) as usize).min(zeros.len());
+            self.update(&zeros[..take]);
+        }
+        self.slices
+    }
+}
+
+/// 读一遍分片，计算文件描述包与数据块校验包需要的全部校验值。
+fn describe_file(
+    ctx: &JobContext,
+    path: &Path,
+    slice_size: u64,
+    mut progress: impl FnMut(u64),
+) -> Result<SourceFile, String> {
+    let fail = |e: io::Error| tr!(ParityFailed, path.display(), e);
+    let mut file = File::open(path).map_err(fail)?;
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut whole = Md5::new();
+    let mut first = Md5::new();
+    let mut slices = SliceHasher::new(slice_size);
+    let mut size = 0u64;
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = file.read(&mut buffer).map_err(fail)?;
+        if read_len == 0 {
+            break;
+        }
+        let data = &buffer[..read_len];
+        whole.update(data);
+        if size < HASH_16K_LEN {
+            first.update(&data[..((HASH_16K_LEN - size) as usize).min(read_len)]);
+        }
+        slices.update(data);
+        size += read_len as u64;
+        progress(read_len as u64);
+    }
+    buffer.fill(0);
+
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let hash_16k: [u8; 16] = first.finalize().into();
+    let mut id_source = hash_16k.to_vec();
+    id_source.extend_from_slice(&size.to_le_bytes());
+    id_source.extend_from_slice(name.as_bytes());
+    Ok(SourceFile {
+        path: path.to_path_buf(),
+        id: md5(&id_source),
+        name,
+        size,
+        hash: whole.finalize().into(),
+        hash_16k,
+        slices: slices.finish(&buffer),
+    })
+}
+
+/// 块大小：数据块约为 `TARGET_SOURCE_SLICES` 个，是 4 的倍数，总数不超过规范上限。
+fn choose_slice_size(sizes: &[u64]) -> u64 {
+    let total: u64 = sizes.iter().sum();
+    let mut slice_size = (total.div_ceil(TARGET_SOURCE_SLICES).div_ceil(4) * 4).max(MIN_SLICE_SIZE);
+    while slice_count(sizes, slice_size) > MAX_SOURCE_SLICES {
+        slice_size *= 2;
+    }
+    slice_size
+}
+
+fn slice_count(sizes: &[u64], slice_size: u64) -> u64 {
+    sizes.iter().map(|size| size.div_ceil(slice_size)).sum()
+}
+
+/// 长度补零到 4 的倍数的文本。
+fn padded(text: &str) -> Vec<u8> {
+    let mut bytes = text.as_bytes().to_vec();
+    bytes.resize(bytes.len().div_ceil(4) * 4, 0);
+    bytes
+}
+
+fn packet(set_id: &[u8; 16], kind: &[u8; 16], body: &[u8]) -> Vec<u8> {
+    let mut hashed = Vec::with_capacity(32 + body.len());
+    hashed.extend_from_slice(set_id);
+    hashed.extend_from_slice(kind);
+    hashed.extend_from_slice(body);
+    let mut packet = Vec::with_capacity(HEADER_LEN as usize + body.len());
+    packet.extend_from_slice(MAGIC);
+    packet.extend_from_slice(&(HEADER_LEN + body.len() as u64).to_le_bytes());
+    packet.extend_from_slice(&md5(&hashed));
+    packet.extend_from_slice(&hashed);
+    packet
+}
+
+/// 恢复集 ID 与每个 PAR2 文件都要包含的主包、文件描述包、数据块校验包和创建者包。
+fn critical_packets(slice_size: u64, files: &[SourceFile]) -> ([u8; 16], Vec<u8>) {
+    let mut main = Vec::new();
+    main.extend_from_slice(&slice_size.to_le_bytes());
+    main.extend_from_slice(&(files.len() as u32).to_le_bytes());
+    for file in files {
+        main.extend_from_slice(&file.id);
+    }
+    let set_id = md5(&main);
+    let mut packets = packet(&set_id, TYPE_MAIN, &main);
+    for file in files {
+        let mut body = Vec::new();
+        body.extend_from_slice(&file.id);
+        body.extend_from_slice(&file.hash);
+        body.extend_from_slice(&file.hash_16k);
+        body.extend_from_slice(&file.size.to_le_bytes());
+        body.extend_from_slice(&padded(&file.name));
+        packets.extend_from_slice(&packet(&set_id, TYPE_FILE_DESC, &body));
+
+        let mut body = file.id.to_vec();
+        for (hash, crc) in &file.slices {
+            body.extend_from_slice(hash);
+            body.extend_from_slice(&crc.to_le_bytes());
+        }
+        packets.extend_from_slice(&packet(&set_id, TYPE_SLICE_CHECKSUMS, &body));
+    }
+    packets.extend_from_slice(&packet(&set_id, TYPE_CREATOR, &padded(CREATOR)));
+    (set_id, packets)
+}
+
+/// 每轮的段长：读入与算出的各段合计不超过内存预算，是 4 的倍数且不超过块大小。
+fn window_size(ctx: &JobContext, slice_size: u64, blocks: usize) -> usize {
+    let budget = ctx
+        .memory()
+        .limit()
+        .unwrap_or(ENCODE_MEMORY)
+        .min(ENCODE_MEMORY);
+    ((budget / blocks.max(1) as u64).max(MIN_WINDOW) / 4 * 4).min(slice_size) as usize
+}
+
+/// 恢复卷的（首个指数, 块数）：依次含 1、2、4……块，最后一卷取剩余的块。
+fn volume_layout(count: u32) -> Vec<(u32, u32)> {
+    let mut volumes = Vec::new();
+    let mut first = 0;
+    let mut len = 1;
+    while first < count {
+        let take = len.min(count - first);
+        volumes.push((first, take));
+        first += take;
+        len *= 2;
+    }
+    volumes
+}
+
+/// 删除上次生成的同名恢复文件，恢复块数变化时旧卷不会被覆盖。
+fn remove_stale(dir: &Path, base_name: &str) {
+    let Ok(entries) = fs::read_dir(dir) else {
+        return;
+    };
+    let volume_prefix = format!("{}.vol", base_name);
+    for entry in entries.flatten() {
+        let name = entry.file_name().to_string_lossy().to_string();
+        let stale = name == format!("{}.par2", base_name)
+            || (name.starts_with(&volume_prefix) && name.ends_with(".par2"));
+        if stale {
+            let _ = fs::remove_file(entry.path());
+        }
+    }
+}
+
+/// 一个恢复卷文件及其中的恢复包。
+struct Volume {
+    file: File,
+    path: PathBuf,
+    first: u32,
+    count: u32,
+}
+
+/// 为分片生成 PAR2 恢复文件，写在分片所在目录，返回生成的文件；进度阶段为 `parity`。
+/// `redundancy` 为恢复块数占数据块数的百分比（1–100）。
+pub(crate) fn create_recovery_files(
+    ctx: &JobContext,
+    base_name: &str,
+    parts: &[String],
+    redundancy: u32,
+) -> Result<Vec<String>, String> {
+    let Some(dir) = parts.first().and_then(|path| Path::new(path).parent()) else {
+        return Ok(Vec::new());
+    };
+    let paths: Vec<PathBuf> = parts
+        .iter()
+        .map(PathBuf::from)
+        .filter(|path| fs::metadata(path).is_ok_and(|meta| meta.len() > 0))
+        .collect();
+    if paths.len() as u64 > MAX_SOURCE_SLICES {
+        return Err(tr!(ParityTooManyParts, paths.len(), MAX_SOURCE_SLICES));
+    }
+    let sizes: Vec<u64> = paths
+        .iter()
+        .map(|path| fs::metadata(path).map(|meta| meta.len()).unwrap_or(0))
+        .collect();
+    let total: u64 = sizes.iter().sum();
+    let slice_size = choose_slice_size(&sizes);
+    let source_count = slice_count(&sizes, slice_size) as usize;
+    let recovery_count =
+        (source_count * redundancy.clamp(1, MAX_REDUNDANCY) as usize).div_ceil(100) as u32;
+
+    let mut processed = 0u64;
+    let mut files = Vec::with_capacity(paths.len());
+    for (idx, path) in paths.iter().enumerate() {
+        files.push(describe_file(ctx, path, slice_size, |delta| {
+            processed += delta;
+            emit_progress(ctx, "parity", processed, total * 2, idx + 1, paths.len(), tr!(ParityHashing, idx + 1));
+        })?);
+    }
+    // 主包中的文件按 ID 排序（ID 视为小端整数），数据块按同一顺序编号
+    files.sort_by(|a, b| a.id.iter().rev().cmp(b.id.iter().rev()));
+    let (set_id, critical) = critical_packets(slice_size, &files);
+
+    remove_stale(dir, base_name);
+    let index_path = dir.join(format!("{}.par2", base_name));
+    ctx.track_output(&index_path);
+    fs::write(&index_path, &critical).map_err(|e| tr!(ParityFailed, index_path.display(), e))?;
+
+    let packet_len = HEADER_LEN + 4 + slice_size;
+    let width = recovery_count.to_string().len();
+    let mut volumes = Vec::new();
+    for (first, count) in volume_layout(recovery_count) {
+        let path = dir.join(format!(
+            "{}.vol{:0width$}+{:0width$}.par2",
+            base_name, first, count
+        ));
+        ctx.track_output(&path);
+        let fail = |e: io::Error| tr!(ParityFailed, path.display(), e);
+        let mut file = File::create(&path).map_err(fail)?;
+        for idx in 0..count {
+            // 包的 MD5 在全部数据写完后补上
+            let mut header = Vec::with_capacity(HEADER_LEN as usize + 4);
+            header.extend_from_slice(MAGIC);
+            header.extend_from_slice(&packet_len.to_le_bytes());
+            header.extend_from_slice(&[0u8; 16]);
+            header.extend_from_slice(&set_id);
+            header.extend_from_slice(TYPE_RECOVERY);
+            header.extend_from_slice(&(first + idx).to_le_bytes());
+            file.seek(SeekFrom::Start(idx as u64 * packet_len)).map_err(fail)?;
+            file.write_all(&header).map_err(fail)?;
+        }
+        file.seek(SeekFrom::Start(count as u64 * packet_len)).map_err(fail)?;
+        file.write_all(&critical).map_err(fail)?;
+        volumes.push(Volume { file, path, first, count });
+    }
+
+    let mut hashers: Vec<Md5> = (0..recovery_count)
+        .map(|exponent| {
+            let mut hasher = Md5::new();
+            hasher.update(set_id);
+            hasher.update(TYPE_RECOVERY);
+            hasher.update(exponent.to_le_bytes());
+            hasher
+        })
+        .collect();
+    let window = window_size(ctx, slice_size, source_count + recovery_count as usize);
+    let mut sources = vec![0u8; source_count * window];
+    let mut recovery = vec![0u8; recovery_count as usize * window];
+    let bases = slice_bases(source_count);
+    let field = field();
+
+    let mut offset = 0u64;
+    while offset < slice_size {
+        ctx.check_cancelled()?;
+        let len = (window as u64).min(slice_size - offset) as usize;
+        read_window(&files, slice_size, offset, len, &mut sources[..source_count * len])?;
+        let sources = &sources[..source_count * len];
+        let encode = |(exponent, target): (usize, &mut [u8])| {
+            target.fill(0);
+            for (source, base) in sources.chunks_exact(len).zip(&bases) {
+                let factor = (*base as u64 * exponent as u64 % FIELD_ORDER as u64) as u32;
+                field.mul_add(target, source, factor);
+            }
+        };
+        let recovery = &mut recovery[..recovery_count as usize * len];
+        if ctx.background_priority() {
+            recovery.chunks_exact_mut(len).enumerate().for_each(encode);
+        } else {
+            recovery.par_chunks_exact_mut(len).enumerate().for_each(encode);
+        }
+
+        let mut blocks = recovery.chunks_exact(len);
+        for volume in &mut volumes {
+            let fail = |e: io::Error| tr!(ParityFailed, volume.path.display(), e);
+            for idx in 0..volume.count {
+                let data = blocks.next().unwrap_or_default();
+                hashers[(volume.first + idx) as usize].update(data);
+                let position = idx as u64 * packet_len + HEADER_LEN + 4 + offset;
+                volume.file.seek(SeekFrom::Start(position)).map_err(fail)?;
+                volume.file.write_all(data).map_err(fail)?;
+            }
+        }
+        offset += len as u64;
+        let encoded = (total as u128 * offset as u128 / slice_size as u128) as u64;
+        emit_progress(ctx, "parity", total + encoded, total * 2, paths.len(), paths.len(), tr!(ParityEncoding));
+    }
+
+    let mut hashers = hashers.into_iter();
+    let mut outputs = vec![index_path.to_string_lossy().to_string()];
+    for mut volume in volumes {
+        let fail = |e: io::Error| tr!(ParityFailed, volume.path.display(), e);
+        for idx in 0..volume.count {
+            let hash: [u8; 16] = hashers.next().map(|hasher| hasher.finalize().into()).unwrap_or_default();
+            volume.file.seek(SeekFrom::Start(idx as u64 * packet_len + 16)).map_err(fail)?;
+            volume.file.write_all(&hash).map_err(fail)?;
+        }
+        volume.file.flush().map_err(fail)?;
+        outputs.push(volume.path.to_string_lossy().to_string());
+    }
+    log::info!(
+        "已生成 PAR2 恢复文件：{} 个数据块，{} 个恢复块，块大小 {} 字节",
+        source_count,
+        recovery_count,
+        slice_size
+    );
+    Ok(outputs)
+}
+
+/// 读入每个数据块 `[offset, offset + len)` 这一段，依次放入 `target`，超出文件末尾的部分补零。
+fn read_window(
+    files: &[SourceFile],
+    slice_size: u64,
+    offset: u64,
+    len: usize,
+    target: &mut [u8],
+) -> Result<(), String> {
+    let mut chunks = target.chunks_exact_mut(len);
+    for source in files {
+        let fail = |e: io::Error| tr!(ParityFailed, source.path.display(), e);
+        let mut file = File::open(&source.path).map_err(fail)?;
+        for idx in 0..source.slices.len() as u64 {
+            let Some(chunk) = chunks.next() else {
+                return Ok(());
+            };
+            let position = idx * slice_size + offset;
+            let available = (source.size.saturating_sub(position) as usize).min(len);
+            if available > 0 {
+                file.seek(SeekFrom::Start(position)).map_err(fail)?;
+                file.read_exact(&mut chunk[..available]).map_err(fail)?;
+            }
+            chunk[available..].fill(0);
+        }
+    }
+    Ok(())
+}
+
+fn le_u64(bytes: &[u8]) -> u64 {
+    let mut value = [0u8; 8];
+    value.copy_from_slice(&bytes[..8]);
+    u64::from_le_bytes(value)
+}
+
+fn packet_id(bytes: &[u8]) -> [u8; 16] {
+    let mut value = [0u8; 16];
+    value.copy_from_slice(&bytes[..16]);
+    value
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 恢复块所在的文件、数据的位置与长度。
+type RecoverySlice = (PathBuf, u64, u64);
+
+/// 从 PAR2 文件中读到的一个恢复集的包。
+#[derive(Default)]
+struct ParsedSet {
+    main: Option<Vec<u8>>,
+    descriptions: HashMap<[u8; 16], Vec<u8>>,
+    checksums: HashMap<[u8; 16], Vec<u8>>,
+    /// 按指数排列，同一指数只取先读到的一个。
+    recovery: BTreeMap<u32, RecoverySlice>,
+}
+
+/// 从 `position` 起按 4 字节对齐找下一个包头，找不到时返回文件长度。
+fn next_magic(file: &mut File, mut position: u64, len: u64, buffer: &mut [u8]) -> io::Result<u64> {
+    while position + HEADER_LEN <= len {
+        let take = (len - position).min(buffer.len() as u64) as usize;
+        file.seek(SeekFrom::Start(position))?;
+        file.read_exact(&mut buffer[..take])?;
+        let scanned = take - (MAGIC.len() - 1);
+        if let Some(found) = (0..scanned)
+            .step_by(4)
+            .find(|&idx| &buffer[idx..idx + MAGIC.len()] == MAGIC)
+        {
+            return Ok(position + found as u64);
+        }
+        position += (scanned as u64).div_ceil(4) * 4;
+    }
+    Ok(len)
+}
+
+/// 读出 PAR2 文件中完好的包，按恢复集归类；恢复包只记下位置，不读入内存。
+fn read_packets(
+    ctx: &JobContext,
+    path: &Path,
+    sets: &mut HashMap<[u8; 16], ParsedSet>,
+) -> Result<(), String> {
+    let fail = |e: io::Error| tr!(Par2ReadFailed, path.display(), e);
+    let mut file = File::open(path).map_err(fail)?;
+    let len = file.metadata().map_err(fail)?.len();
+    let mut buffer = vec![0u8; ctx.memory().buffer_size().max(HEADER_LEN as usize)];
+    let mut header = [0u8; HEADER_LEN as usize];
+    let mut position = 0u64;
+    while position + HEADER_LEN <= len {
+        ctx.check_cancelled()?;
+        file.seek(SeekFrom::Start(position)).map_err(fail)?;
+        file.read_exact(&mut header).map_err(fail)?;
+        let packet_len = le_u64(&header[8..16]);
+        let plausible = &header[..8] == MAGIC
+            && packet_len >= HEADER_LEN
+            && packet_len.is_multiple_of(4)
+            && packet_len <= len - position;
+        if !plausible {
+            position = next_magic(&mut file, position + 4, len, &mut buffer).map_err(fail)?;
+            continue;
+        }
+        let kind = packet_id(&header[48..64]);
+        let keep = [TYPE_MAIN, TYPE_FILE_DESC, TYPE_SLICE_CHECKSUMS].contains(&&kind);
+        let mut hasher = Md5::new();
+        hasher.update(&header[32..]);
+        let mut body = Vec::new();
+        let mut remaining = packet_len - HEADER_LEN;
+        while remaining > 0 {
+            let take = remaining.min(buffer.len() as u64) as usize;
+            file.read_exact(&mut buffer[..take]).map_err(fail)?;
+            hasher.update(&buffer[..take]);
+            // 其余的包只留下开头，恢复包用到其中的指数
+            let wanted = if keep { take } else { take.min(4usize.saturating_sub(body.len())) };
+            body.extend_from_slice(&buffer[..wanted]);
+            remaining -= take as u64;
+        }
+        let hash: [u8; 16] = hasher.finalize().into();
+        if hash[..] != header[16..32] {
+            position = next_magic(&mut file, position + 4, len, &mut buffer).map_err(fail)?;
+            continue;
+        }
+        let set = sets.entry(packet_id(&header[32..48])).or_default();
+        if &kind == TYPE_MAIN {
+            set.main = Some(body);
+        } else if &kind == TYPE_FILE_DESC && body.len() >= 56 {
+            set.descriptions.insert(packet_id(&body), body);
+        } else if &kind == TYPE_SLICE_CHECKSUMS && body.len() >= 16 {
+            set.checksums.insert(packet_id(&body), body);
+        } else if &kind == TYPE_RECOVERY && body.len() == 4 {
+            let exponent = u32::from_le_bytes([body[0], body[1], body[2], body[3]]);
+            set.recovery.entry(exponent).or_insert((
+                path.to_path_buf(),
+                position + HEADER_LEN + 4,
+                packet_len - HEADER_LEN - 4,
+            ));
+        }
+        position += packet_len;
+    }
+    Ok(())
+}
+
+/// PAR2 文件中记录的一个分片。
+struct RecordedFile {
+    name: String,
+    path: PathBuf,
+    size: u64,
+    hash: [u8; 16],
+    /// 每个数据块的 MD5。
+    slices: Vec<[u8; 16]>,
+}
+
+struct RecordedSet {
+    slice_size: u64,
+    /// 按主包中的顺序排列，数据块按同一顺序编号。
+    files: Vec<RecordedFile>,
+    recovery: BTreeMap<u32, RecoverySlice>,
+}
+
+/// 由主包、文件描述包与数据块校验包还原恢复集；主包无效或文件名不是单纯的文件名时返回 `None`。
+fn recorded_set(dir: &Path, set: ParsedSet) -> Result<Option<RecordedSet>, String> {
+    let Some(main) = set.main.filter(|main| main.len() >= 12) else {
+        return Ok(None);
+    };
+    let slice_size = le_u64(&main[..8]);
+    let count = u32::from_le_bytes([main[8], main[9], main[10], main[11]]) as usize;
+    if slice_size == 0 || !slice_size.is_multiple_of(4) || main.len() < 12 + 16 * count {
+        return Ok(None);
+    }
+    let mut files = Vec::with_capacity(count);
+    for file_id in main[12..12 + 16 * count].chunks_exact(16) {
+        let file_id = packet_id(file_id);
+        let Some(description) = set.descriptions.get(&file_id) else {
+            return Err(tr!(Par2SetIncomplete, hex(&file_id)));
+        };
+        let name = String::from_utf8_lossy(&description[56..])
+            .trim_end_matches('\0')
+            .to_string();
+        if Path::new(&name).file_name().and_then(|value| value.to_str()) != Some(name.as_str()) {
+            return Ok(None);
+        }
+        let size = le_u64(&description[48..56]);
+        let slice_count = size.div_ceil(slice_size) as usize;
+        let slices: Vec<[u8; 16]> = set
+            .checksums
+            .get(&file_id)
+            .map(|body| body[16..].chunks_exact(20).map(packet_id).collect())
+            .unwrap_or_default();
+        if slices.len() < slice_count {
+            return Err(tr!(Par2SetIncomplete, name));
+        }
+        files.push(RecordedFile {
+            path: dir.join(&name),
+            name,
+            size,
+            hash: packet_id(&description[16..32]),
+            slices: slices[..slice_count].to_vec(),
+        });
+    }
+    Ok(Some(RecordedSet {
+        slice_size,
+        files,
+        recovery: set.recovery,
+    }))
+}
+
+/// 找出输入对应的 PAR2 恢复集：读分片目录中的全部 `.par2` 文件，输入为分片文件时只取包含该分片的恢复集。
+fn find_set(ctx: &JobContext, input_path: &Path) -> Result<Option<RecordedSet>, String> {
+    let (dir, part_name) = if input_path.is_dir() {
+        (input_path.to_path_buf(), None)
+    } else {
+        match (input_path.parent(), input_path.file_name()) {
+            (Some(dir), Some(name)) => (dir.to_path_buf(), Some(name.to_string_lossy().to_string())),
+            _ => return Ok(None),
+        }
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Ok(None);
+    };
+    let mut paths: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| {
+            path.is_file()
+                && path
+                    .extension()
+                    .is_some_and(|ext| ext.eq_ignore_ascii_case("par2"))
+        })
+        .collect();
+    paths.sort();
+    let mut parsed = HashMap::new();
+    for path in &paths {
+        read_packets(ctx, path, &mut parsed)?;
+    }
+    let mut found = Vec::new();
+    for set in parsed.into_values() {
+        let Some(set) = recorded_set(&dir, set)? else {
+            continue;
+        };
+        if part_name
+            .as_ref()
+            .is_none_or(|name| set.files.iter().any(|file| &file.name == name))
+        {
+            found.push(set);
+        }
+    }
+    match found.len() {
+        0 => Ok(None),
+        1 => Ok(found.pop()),
+        _ => Err(tr!(MultipleRecoverySets)),
+    }
+}
+
+/// 按记录的大小读分片，返回每个数据块是否完好；分片不存在或读不出的部分视为损坏。
+fn check_slices(
+    ctx: &JobContext,
+    file: &RecordedFile,
+    slice_size: u64,
+    mut progress: impl FnMut(u64),
+) -> Result<Vec<bool>, String> {
+    let Ok(source) = File::open(&file.path) else {
+        return Ok(vec![false; file.slices.len()]);
+    };
+    let mut reader = source.take(file.size);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut hasher = SliceHasher::new(slice_size);
+    loop {
+        ctx.check_cancelled()?;
+        let Ok(read_len) = reader.read(&mut buffer) else {
+            break;
+        };
+        if read_len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read_len]);
+        progress(read_len as u64);
+    }
+    buffer.fill(0);
+    let computed = hasher.finish(&buffer);
+    Ok(file
+        .slices
+        .iter()
+        .enumerate()
+        .map(|(idx, expected)| computed.get(idx).is_some_and(|(md5, _)| md5 == expected))
+        .collect())
+}
+
+fn file_md5(ctx: &JobContext, path: &Path) -> Result<[u8; 16], String> {
+    let fail = |e: io::Error| tr!(Par2ReadFailed, path.display(), e);
+    let mut file = File::open(path).map_err(fail)?;
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut hasher = Md5::new();
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = file.read(&mut buffer).map_err(fail)?;
+        if read_len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read_len]);
+    }
+    Ok(hasher.finalize().into())
+}
+
+/// 一个数据块或恢复块在文件中的位置与长度，超出长度的部分按零计算。
+struct Block {
+    path: PathBuf,
+    start: u64,
+    len: u64,
+}
+
+/// 读入每个块 `[offset, offset + len)` 这一段，依次放入 `target`。
+fn read_blocks(blocks: &[Block], offset: u64, len: usize, target: &mut [u8]) -> Result<(), String> {
+    for (block, chunk) in blocks.iter().zip(target.chunks_exact_mut(len)) {
+        let fail = |e: io::Error| tr!(Par2ReadFailed, block.path.display(), e);
+        let available = (block.len.saturating_sub(offset) as usize).min(len);
+        if available > 0 {
+            let mut file = File::open(&block.path).map_err(fail)?;
+            file.seek(SeekFrom::Start(block.start + offset)).map_err(fail)?;
+            file.read_exact(&mut chunk[..available]).map_err(fail)?;
+        }
+        chunk[available..].fill(0);
+    }
+    Ok(())
+}
+
+/// 一个损坏分片的临时文件，修复完成并核对 MD5 后替换原分片。
+struct RepairTarget<'a> {
+    file: &'a RecordedFile,
+    temp: PathBuf,
+    output: File,
+}
+
+/// 用分片目录中的 PAR2 恢复文件逐块核对分片，缺失或损坏的数据块不多于完好的恢复块时
+/// 解出这些数据块并写回分片目录；没有 PAR2 恢复文件时返回 `None`。进度阶段为 `repair`。
+pub(crate) fn repair(ctx: &JobContext, input_path: &Path) -> Result<Option<Outcome>, String> {
+    let Some(set) = find_set(ctx, input_path)? else {
+        return Ok(None);
+    };
+    let files = &set.files;
+    let total: u64 = files.iter().map(|file| file.size).sum();
+    let mut processed = 0u64;
+    let mut intact = Vec::with_capacity(files.len());
+    for (idx, file) in files.iter().enumerate() {
+        intact.push(check_slices(ctx, file, set.slice_size, |delta| {
+            processed += delta;
+            emit_progress(ctx, "repair", processed, total, idx + 1, files.len(), tr!(ParityVerifying, idx + 1));
+        })?);
+    }
+    // 大小不对但数据块都完好的分片（多出尾部或末块截掉的恰好是零）只需调整长度
+    let damaged: Vec<usize> = (0..files.len())
+        .filter(|&idx| {
+            intact[idx].contains(&false)
+                || fs::metadata(&files[idx].path).map(|meta| meta.len()).ok() != Some(files[idx].size)
+        })
+        .collect();
+    let mut outcome = Outcome {
+        method: "par2",
+        damaged: damaged.iter().map(|&idx| files[idx].name.clone()).collect(),
+        repaired: Vec::new(),
+        error: None,
+    };
+    if damaged.is_empty() {
+        return Ok(Some(outcome));
+    }
+
+    // 数据块按文件顺序统一编号：（所在的修复目标, 块在文件中的位置, 块的实际长度, 编号）
+    let mut present = Vec::new();
+    let mut lost = Vec::new();
+    let mut number = 0;
+    for (file_idx, (file, slices)) in files.iter().zip(&intact).enumerate() {
+        for (slice_idx, &ok) in slices.iter().enumerate() {
+            let start = slice_idx as u64 * set.slice_size;
+            let len = (file.size - start).min(set.slice_size);
+            if ok {
+                present.push((file_idx, start, len, number));
+            } else {
+                let target = damaged.iter().position(|&idx| idx == file_idx).unwrap_or_default();
+                lost.push((target, start, len, number));
+            }
+            number += 1;
+        }
+    }
+    let available: Vec<(u32, &RecoverySlice)> = set
+        .recovery
+        .iter()
+        .filter(|(_, (_, _, len))| *len == set.slice_size)
+        .map(|(&exponent, slice)| (exponent, slice))
+        .collect();
+    if available.len() < lost.len() {
+        outcome.error = Some(tr!(ParityNotEnough, lost.len(), available.len()));
+        return Ok(Some(outcome));
+    }
+    let rows = &available[..lost.len()];
+    let field = field();
+    let bases = slice_bases(number);
+    let coefficient = |exponent: u32, slice: usize| field.power(bases[slice] as u64 * exponent as u64);
+    let solve = field.invert(
+        rows.iter()
+            .map(|&(exponent, _)| lost.iter().map(|slice| coefficient(exponent, slice.3)).collect())
+            .collect(),
+    );
+    let Some(solve) = solve else {
+        outcome.error = Some(tr!(ParityNotEnough, lost.len(), available.len()));
+        return Ok(Some(outcome));
+    };
+    // 恢复块减去完好数据块的贡献，剩下的是缺失数据块的线性组合
+    let syndrome_matrix: Vec<Vec<u16>> = rows
+        .iter()
+        .enumerate()
+        .map(|(pos, &(exponent, _))| {
+            present
+                .iter()
+                .map(|slice| coefficient(exponent, slice.3))
+                .chain((0..rows.len()).map(|idx| u16::from(idx == pos)))
+                .collect()
+        })
+        .collect();
+    let inputs: Vec<Block> = present
+        .iter()
+        .map(|&(file_idx, start, len, _)| Block {
+            path: files[file_idx].path.clone(),
+            start,
+            len,
+        })
+        .chain(rows.iter().map(|(_, (path, start, len))| Block {
+            path: path.clone(),
+            start: *start,
+            len: *len,
+        }))
+        .collect();
+    log::info!(
+        "用 PAR2 恢复块修复 {} 个数据块，涉及分片：{}",
+        lost.len(),
+        outcome.damaged.join(", ")
+    );
+
+    // 损坏的分片先复制到临时文件并调整为记录的长度，再写入解出的数据块
+    let mut targets = Vec::with_capacity(damaged.len());
+    for &idx in &damaged {
+        let file = &files[idx];
+        let temp = file.path.with_file_name(format!("{}.repair.tmp", file.name));
+        let fail = |e: io::Error| tr!(RepairWriteFailed, temp.display(), e);
+        if file.path.is_file() && fs::copy(&file.path, &temp).is_err() {
+            let _ = fs::remove_file(&temp);
+        }
+        let output = OpenOptions::new()
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(&temp)
+            .map_err(fail)?;
+        output.set_len(file.size).map_err(fail)?;
+        targets.push(RepairTarget { file, temp, output });
+    }
+    let result = if lost.is_empty() {
+        Ok(())
+    } else {
+        rebuild_slices(ctx, set.slice_size, &inputs, &syndrome_matrix, &solve, &lost, &mut targets)
+    };
+    let mut repaired = Vec::with_capacity(targets.len());
+    let mut mismatch = None;
+    for target in targets {
+        drop(target.output);
+        if result.is_err() || mismatch.is_some() {
+            let _ = fs::remove_file(&target.temp);
+            continue;
+        }
+        if file_md5(ctx, &target.temp)? != target.file.hash {
+            let _ = fs::remove_file(&target.temp);
+            mismatch = Some(tr!(RecoveryRebuildMismatch, target.file.name));
+            continue;
+        }
+        let _ = fs::remove_file(&target.file.path);
+        fs::rename(&target.temp, &target.file.path)
+            .map_err(|e| tr!(RepairWriteFailed, target.file.path.display(), e))?;
+        repaired.push(target.file.path.to_string_lossy().to_string());
+    }
+    result?;
+    if let Some(error) = mismatch {
+        return Err(error);
+    }
+    outcome.repaired = repaired;
+    Ok(Some(outcome))
+}
+
+/// 逐段解出缺失的数据块，写入所在分片的临时文件。
+fn rebuild_slices(
+    ctx: &JobContext,
+    slice_size: u64,
+    inputs: &[Block],
+    syndrome_matrix: &[Vec<u16>],
+    solve: &[Vec<u16>],
+    lost: &[(usize, u64, u64, usize)],
+    targets: &mut [RepairTarget],
+) -> Result<(), String> {
+    let window = window_size(ctx, slice_size, inputs.len() + 2 * lost.len());
+    let mut sources = vec![0u8; inputs.len() * window];
+    let mut syndromes = vec![0u8; lost.len() * window];
+    let mut missing = vec![0u8; lost.len() * window];
+    let mut offset = 0u64;
+    while offset < slice_size {
+        ctx.check_cancelled()?;
+        let len = (window as u64).min(slice_size - offset) as usize;
+        let sources = &mut sources[..inputs.len() * len];
+        read_blocks(inputs, offset, len, sources)?;
+        let syndromes = &mut syndromes[..lost.len() * len];
+        combine(ctx, syndrome_matrix, sources, syndromes, len);
+        let missing = &mut missing[..lost.len() * len];
+        combine(ctx, solve, syndromes, missing, len);
+        for (&(target, start, size, _), data) in lost.iter().zip(missing.chunks_exact(len)) {
+            let keep = (size.saturating_sub(offset) as usize).min(len);
+            if keep == 0 {
+                continue;
+            }
+            let target = &mut targets[target];
+            let fail = |e: io::Error| tr!(RepairWriteFailed, target.temp.display(), e);
+            target.output.seek(SeekFrom::Start(start + offset)).map_err(fail)?;
+            target.output.write_all(&data[..keep]).map_err(fail)?;
+        }
+        offset += len as u64;
+        emit_progress(ctx, "repair", offset, slice_size, targets.len(), targets.len(), tr!(RepairingParts));
+    }
+    Ok(())
+}
@@ -0,0 +1,314 @@
+//! 分片注释：`split-then-zip` 的每份分片 zip 在 zip 注释中写明分片序号、总份数、分片组 ID、
+//! 分片内容的大小与 SHA-256，形如 `file-split-packer part=3/10 group=… size=1048576 sha256=…`。
+//! 没有完整性清单时也能单独核对一份分片，合并时据此发现缺少末尾的分片。分片加密时不写 SHA-256，
+//! 以免注释泄露明文的校验值。
+//!
+//! 带分片组 ID 的分片合并时按注释归组、排序，不依赖文件名：分片被改名，或序号被下载工具改乱
+//! （如 `part-001 (1).zip`、`download.zip`）也能按原顺序合并。旧版本写的注释没有分片组 ID，
+//! 仍按文件名查找，序号与文件名不符时报错。
+
+use sha2::{Digest, Sha256};
+use std::{
+    collections::HashSet,
+    fs::{self, File},
+    io::{self, BufReader, Read, Write},
+    path::{Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    i18n::tr, integrity::PartStatus, jobs::JobContext, parse_part_name, PartGroup, PartInfo,
+};
+
+const MARKER: &str = "file-split-packer";
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct PartComment {
+    /// 分片序号，从 1 开始。
+    pub(crate) index: usize,
+    pub(crate) total: usize,
+    /// 同一次切分写出的分片共用的分片组 ID（十六进制），旧版本写的注释没有。
+    pub(crate) group: Option<String>,
+    /// 分片内容（解压后）的字节数。
+    pub(crate) size: u64,
+    /// 分片内容的十六进制 SHA-256，分片加密时为 `None`。
+    pub(crate) sha256: Option<String>,
+}
+
+impl PartComment {
+    pub(crate) fn to_text(&self) -> String {
+        let mut text = format!("{} part={}/{}", MARKER, self.index, self.total);
+        if let Some(group) = &self.group {
+            text.push_str(&format!(" group={}", group));
+        }
+        text.push_str(&format!(" size={}", self.size));
+        if let Some(sha256) = &self.sha256 {
+            text.push_str(&format!(" sha256={}", sha256));
+        }
+        text
+    }
+
+    /// 解析注释；不是本程序写的注释返回 `None`。
+    fn parse(text: &str) -> Option<PartComment> {
+        let mut fields = text.split_whitespace();
+        if fields.next()? != MARKER {
+            return None;
+        }
+        let (mut position, mut group, mut size, mut sha256) = (None, None, None, None);
+        for field in fields {
+            match field.split_once('=')? {
+                ("part", value) => {
+                    let (index, total) = value.split_once('/')?;
+                    position = Some((index.parse().ok()?, total.parse().ok()?));
+                }
+                ("group", value) => group = Some(value.to_ascii_lowercase()),
+                ("size", value) => size = Some(value.parse().ok()?),
+                ("sha256", value) => sha256 = Some(value.to_ascii_lowercase()),
+                // 以后加入的字段
+                _ => {}
+            }
+        }
+        let (index, total) = position?;
+        Some(PartComment {
+            index,
+            total,
+            group,
+            size: size?,
+            sha256,
+        })
+    }
+}
+
+/// 共 `total` 份、每份内容不超过 `part_size` 字节时注释的最大长度，严格限制分片大小时为注释留出空间。
+pub(crate) fn max_len(total: usize, part_size: u64, encrypted: bool) -> usize {
+    PartComment {
+        index: total,
+        total,
+        group: Some("0".repeat(16)),
+        size: part_size,
+        sha256: (!encrypted).then(|| "0".repeat(64)),
+    }
+    .to_text()
+    .len()
+}
+
+/// 本次切分的分片组 ID：可续传的任务由任务指纹得出，续传时写出的分片与之前的同属一组；
+/// 否则随机生成。
+pub(crate) fn group_id(ctx: &JobContext) -> String {
+    match ctx.resume_fingerprint() {
+        Some(fingerprint) => {
+            format!("{:x}", Sha256::digest(fingerprint.as_bytes()))[..16].to_string()
+        }
+        None => format!("{:016x}", rand::random::<u64>()),
+    }
+}
+
+/// 读出分片 zip 的注释；不是 zip、读不出或没有本程序写的注释时返回 `None`。
+pub(crate) fn read(path: &Path) -> Option<PartComment> {
+    read_with_entry(path).map(|(comment, _)| comment)
+}
+
+/// 读出注释与第一个条目的名称（即分片原来的名称去掉 `.zip`）。
+fn read_with_entry(path: &Path) -> Option<(PartComment, Option<String>)> {
+    let file = File::open(path).ok()?;
+    let archive = ZipArchive::new(BufReader::new(file)).ok()?;
+    let comment = PartComment::parse(std::str::from_utf8(archive.comment()).ok()?)?;
+    Some((comment, archive.name_for_index(0).map(str::to_string)))
+}
+
+/// 按注释中的分片组 ID 与序号找出分片组，不看文件名。输入为文件时取它所在的组，输入为目录时
+/// 目录中须只有一组带 ID 的分片。输入不是带 ID 的分片、或目录中有多组时返回 `None`，按文件名查找。
+pub(crate) fn find_group(input_path: &Path) -> Result<Option<PartGroup>, String> {
+    let (dir, wanted) = if input_path.is_file() {
+        let Some(group) = read(input_path).and_then(|comment| comment.group) else {
+            return Ok(None);
+        };
+        let Some(dir) = input_path.parent() else {
+            return Ok(None);
+        };
+        (dir, Some(group))
+    } else if input_path.is_dir() {
+        (input_path, None)
+    } else {
+        return Ok(None);
+    };
+
+    let mut found: Vec<(PathBuf, PartComment, Option<String>)> = Vec::new();
+    for entry in fs::read_dir(dir).map_err(|e| e.to_string())? {
+        let path = entry.map_err(|e| e.to_string())?.path();
+        if !path.is_file() {
+            continue;
+        }
+        let Some((comment, entry_name)) = read_with_entry(&path) else {
+            continue;
+        };
+        if comment.group.is_some() && (wanted.is_none() || comment.group == wanted) {
+            found.push((path, comment, entry_name));
+        }
+    }
+    let groups: HashSet<&str> = found
+        .iter()
+        .filter_map(|(_, comment, _)| comment.group.as_deref())
+        .collect();
+    if groups.len() != 1 {
+        return Ok(None);
+    }
+
+    // 同一份分片下载了两次时只取其一
+    found.sort_by(|a, b| a.1.index.cmp(&b.1.index).then_with(|| a.0.cmp(&b.0)));
+    found.dedup_by_key(|(_, comment, _)| comment.index);
+    for (idx, (_, comment, _)) in found.iter().enumerate() {
+        if comment.index != idx + 1 {
+            return Err(tr!(MissingPart, idx + 1));
+        }
+    }
+    let total = found
+        .iter()
+        .map(|(_, comment, _)| comment.total)
+        .max()
+        .unwrap_or(0);
+    if total > found.len() {
+        return Err(tr!(MissingPart, found.len() + 1));
+    }
+
+    // 分片组名称取自条目名，条目名保留了分片原来的名称
+    let prefix = found
+        .iter()
+        .find_map(|(path, _, entry_name)| {
+            entry_name
+                .as_deref()
+                .and_then(parse_part_name)
+                .or_else(|| path.file_name()?.to_str().and_then(parse_part_name))
+                .map(|(prefix, _, _)| prefix)
+        })
+        .ok_or_else(|| tr!(UnrecognizedPartFileName))?;
+    let renamed = found
+        .iter()
+        .filter(|(path, comment, _)| {
+            path.file_name()
+                .and_then(|name| name.to_str())
+                .and_then(parse_part_name)
+                .is_none_or(|(_, index, _)| index != comment.index)
+        })
+        .count();
+    if renamed > 0 {
+        log::info!(
+            "按分片注释排列 {} 份分片，其中 {} 份的文件名与序号不符",
+            found.len(),
+            renamed
+        );
+    }
+    let parts = found
+        .into_iter()
+        .map(|(path, comment, _)| PartInfo {
+            index: comment.index,
+            path,
+        })
+        .collect();
+    Ok(Some(PartGroup { prefix, parts }))
+}
+
+/// 核对分片组中各分片注释记录的序号与总份数：序号与文件名不符说明分片被改过名，
+/// 总份数多于找到的分片说明缺少末尾的分片。没有注释的分片不核对。
+pub(crate) fn check_group(parts: &[PartInfo]) -> Result<(), String> {
+    let mut total = 0usize;
+    for part in parts {
+        if part
+            .path
+            .extension()
+            .is_none_or(|ext| !ext.eq_ignore_ascii_case("zip"))
+        {
+            continue;
+        }
+        let Some(comment) = read(&part.path) else {
+            continue;
+        };
+        if comment.index != part.index {
+            let name = part
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .to_string();
+            return Err(tr!(PartCommentIndexMismatch, name, comment.index));
+        }
+        total = total.max(comment.total);
+    }
+    if total > parts.len() {
+        return Err(tr!(MissingPart, parts.len() + 1));
+    }
+    Ok(())
+}
+
+/// 按注释核对一份分片的内容：先比较大小，注释中有 SHA-256 且 `hash` 为 `true` 时再解压计算校验值。
+/// 没有可比较的校验值时大小一致也只算 [`PartStatus::Unchecked`]。
+pub(crate) fn verify(
+    ctx: &JobContext,
+    path: &Path,
+    comment: &PartComment,
+    hash: bool,
+) -> Result<PartStatus, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let entry = archive.by_index_raw(0).map_err(|e| e.to_string())?;
+    if entry.size() != comment.size {
+        return Ok(PartStatus::SizeMismatch);
+    }
+    let Some(expected) = comment.sha256.as_deref().filter(|_| hash) else {
+        return Ok(PartStatus::Unchecked);
+    };
+    drop(entry);
+    let mut entry = archive.by_index(0).map_err(|e| e.to_string())?;
+    let mut hasher = HashingWriter::new(io::sink());
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = match entry.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            // CRC 不符也算作内容损坏
+            Err(_) => return Ok(PartStatus::HashMismatch),
+        };
+        hasher
+            .write_all(&buffer[..read_len])
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(if hasher.finish() == expected {
+        PartStatus::Ok
+    } else {
+        PartStatus::HashMismatch
+    })
+}
+
+/// 写入时顺带计算 SHA-256 的写入器，用于在打包时得到分片内容的校验值。
+pub(crate) struct HashingWriter<W> {
+    inner: W,
+    hasher: Sha256,
+}
+
+impl<W: Write> HashingWriter<W> {
+    pub(crate) fn new(inner: W) -> Self {
+        HashingWriter {
+            inner,
+            hasher: Sha256::new(),
+        }
+    }
+
+    /// 十六进制的 SHA-256。
+    pub(crate) fn finish(self) -> String {
+        format!("{:x}", self.hasher.finalize())
+    }
+}
+
+impl<W: Write> Write for HashingWriter<W> {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let written = self.inner.write(buf)?;
+        self.hasher.update(&buf[..written]);
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.inner.flush()
+    }
+}
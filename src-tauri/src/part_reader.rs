@@ -0,0 +1,128 @@
+use std::{
+    fs::{self, File},
+    io::{self, Read, Seek, SeekFrom},
+    path::PathBuf,
+};
+
+use crate::i18n::Msg;
+
+/// 分片中的一段连续数据：`path` 文件内从 `file_offset` 开始的 `len` 字节。
+#[derive(Debug, Clone)]
+pub(crate) struct PartSegment {
+    pub(crate) path: PathBuf,
+    pub(crate) file_offset: u64,
+    pub(crate) len: u64,
+}
+
+/// 把多个分片按顺序拼接成一个可随机读取的流，无需先合并到临时文件。
+pub(crate) struct PartReader {
+    segments: Vec<PartSegment>,
+    starts: Vec<u64>,
+    total_len: u64,
+    position: u64,
+    current: Option<(usize, File)>,
+    visited: Vec<usize>,
+}
+
+impl PartReader {
+    pub(crate) fn new(segments: Vec<PartSegment>) -> Self {
+        let mut starts = Vec::with_capacity(segments.len());
+        let mut total_len = 0u64;
+        for segment in &segments {
+            starts.push(total_len);
+            total_len += segment.len;
+        }
+        PartReader {
+            segments,
+            starts,
+            total_len,
+            position: 0,
+            current: None,
+            visited: Vec::new(),
+        }
+    }
+
+    pub(crate) fn from_files(paths: &[PathBuf]) -> io::Result<Self> {
+        let mut segments = Vec::with_capacity(paths.len());
+        for path in paths {
+            let len = fs::metadata(path)?.len();
+            segments.push(PartSegment {
+                path: path.clone(),
+                file_offset: 0,
+                len,
+            });
+        }
+        Ok(PartReader::new(segments))
+    }
+
+    /// 实际读取过的分段序号（从 0 开始，按首次读取顺序）。
+    pub(crate) fn visited_segments(&self) -> &[usize] {
+        &self.visited
+    }
+
+    fn segment_for_position(&self, position: u64) -> Option<usize> {
+        if position >= self.total_len {
+            return None;
+        }
+        let index = match self.starts.binary_search(&position) {
+            Ok(index) => index,
+            Err(index) => index - 1,
+        };
+        // 跳过长度为 0 的分段
+        (index..self.segments.len()).find(|&idx| self.segments[idx].len > 0)
+    }
+}
+
+impl Read for PartReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        let Some(index) = self.segment_for_position(self.position) else {
+            return Ok(0);
+        };
+        let segment_start = self.starts[index];
+        let segment = &self.segments[index];
+        let offset_in_segment = self.position - segment_start;
+        let available = segment.len - offset_in_segment;
+
+        let reopen = !matches!(&self.current, Some((current, _)) if *current == index);
+        if reopen {
+            self.current = Some((index, File::open(&segment.path)?));
+            if !self.visited.contains(&index) {
+                self.visited.push(index);
+            }
+        }
+        let (_, file) = self.current.as_mut().expect("current segment");
+        file.seek(SeekFrom::Start(segment.file_offset + offset_in_segment))?;
+
+        let want = std::cmp::min(buf.len() as u64, available) as usize;
+        let read_len = file.read(&mut buf[..want])?;
+        if read_len == 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::UnexpectedEof,
+                Msg::PartLengthMismatch.text(),
+            ));
+        }
+        self.position += read_len as u64;
+        Ok(read_len)
+    }
+}
+
+impl Seek for PartReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.total_len as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        if target < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                Msg::InvalidSeekPosition.text(),
+            ));
+        }
+        self.position = target as u64;
+        Ok(self.position)
+    }
+}
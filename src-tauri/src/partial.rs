@@ -0,0 +1,572 @@
+use std::{
+    cmp,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+use zip::{CompressionMethod, ZipArchive};
+
+use crate::{
+    collect_part_group, copy_n_with_progress, emit_progress,
+    i18n::tr,
+    index::{self, IndexedPart},
+    jobs::{CancelToken, JobContext},
+    keyfile, open_zip_file,
+    part_reader::{PartReader, PartSegment},
+    secrets,
+    seekable::SeekableReader,
+    EntryStreamEvent, ExtractEntryOptions, ExtractEntryResult, PartGroup, RangeRestoreOptions,
+    RangeRestoreResult, StreamEntryOptions, StreamEntryResult,
+};
+
+/// 只恢复原始数据中 `[offset, offset + length)` 这一段，仅读取覆盖该区间的分片。
+pub(crate) fn restore_range_blocking(
+    ctx: &JobContext,
+    mut options: RangeRestoreOptions,
+) -> Result<RangeRestoreResult, String> {
+    let input_path = PathBuf::from(options.input_path);
+    let output_path = PathBuf::from(options.output_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    if options.length == 0 {
+        return Err(tr!(RangeLengthNotPositive));
+    }
+    if let Some(parent) = output_path.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let password = password.as_deref();
+    let part_group = collect_part_group(&input_path)?;
+    let mut writer = BufWriter::new(File::create(&output_path).map_err(|e| e.to_string())?);
+
+    let (bytes_written, parts_read) = match options.merge_mode.as_str() {
+        "split-then-zip" => copy_range_split_then_zip(
+            ctx,
+            &part_group,
+            options.offset,
+            options.length,
+            password,
+            &mut writer,
+        )?,
+        "zip-then-split" => copy_range_zip_then_split(
+            ctx,
+            &part_group,
+            options.offset,
+            options.length,
+            password,
+            &mut writer,
+        )?,
+        "zstd-then-split" => {
+            copy_range_zstd_then_split(ctx, &part_group, options.offset, options.length, &mut writer)?
+        }
+        "7z-then-split" => return Err(tr!(SevenZipNoRange)),
+        "tar-gz-then-split" | "tar-zst-then-split" => return Err(tr!(TarNoRange)),
+        _ => return Err(tr!(UnknownMergeMode)),
+    };
+    writer.flush().map_err(|e| e.to_string())?;
+
+    Ok(RangeRestoreResult {
+        output_file: output_path.to_string_lossy().to_string(),
+        bytes_written,
+        parts_read,
+    })
+}
+
+const STREAM_CHUNK_SIZE: usize = 256 * 1024;
+
+fn clamp_range(offset: u64, length: u64, total_size: u64) -> Result<u64, String> {
+    if offset >= total_size {
+        return Err(tr!(OffsetOutOfRange, total_size));
+    }
+    Ok(cmp::min(length, total_size - offset))
+}
+
+fn copy_range_split_then_zip<W: Write>(
+    ctx: &JobContext,
+    part_group: &PartGroup,
+    offset: u64,
+    length: u64,
+    password: Option<&str>,
+    writer: &mut W,
+) -> Result<(u64, Vec<usize>), String> {
+    let layout = index::part_layout(part_group, "split-then-zip")?;
+    let total_size: u64 = layout.iter().map(|part| part.len).sum();
+    let part_ranges: Vec<(usize, PathBuf, u64, u64)> = part_group
+        .parts
+        .iter()
+        .zip(&layout)
+        .map(|(part, indexed)| (part.index, part.path.clone(), indexed.offset, indexed.len))
+        .collect();
+
+    let length = clamp_range(offset, length, total_size)?;
+    let end = offset + length;
+    let mut processed = 0u64;
+    let mut parts_read = Vec::new();
+
+    for (index, path, part_start, part_size) in &part_ranges {
+        let part_end = part_start + part_size;
+        if part_end <= offset {
+            continue;
+        }
+        if *part_start >= end {
+            break;
+        }
+        let skip = offset.saturating_sub(*part_start);
+        let take = cmp::min(end, part_end) - cmp::max(offset, *part_start);
+        parts_read.push(*index);
+
+        emit_progress(
+            ctx,
+            "restore-range",
+            processed,
+            length,
+            *index,
+            part_ranges.len(),
+            tr!(ReadingPart, index),
+        );
+        let mut progress = |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "restore-range",
+                processed,
+                length,
+                *index,
+                part_ranges.len(),
+                tr!(Restoring),
+            );
+        };
+
+        copy_part_entry_range(
+            path,
+            skip,
+            take,
+            password,
+            ctx.cancel_token(),
+            writer,
+            &mut progress,
+        )?;
+    }
+
+    Ok((length, parts_read))
+}
+
+/// 复制单个分片压缩包内唯一条目的一段数据。未加密的 Store 条目直接定位读取，其余需要从头解压后跳过。
+fn copy_part_entry_range<W: Write>(
+    path: &Path,
+    skip: u64,
+    take: u64,
+    password: Option<&str>,
+    cancel: &CancelToken,
+    writer: &mut W,
+    progress: &mut impl FnMut(u64),
+) -> Result<(), String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let (stored, data_start) = {
+        let raw = archive.by_index_raw(0).map_err(|e| e.to_string())?;
+        (
+            matches!(raw.compression(), CompressionMethod::Stored) && !raw.encrypted(),
+            raw.data_start(),
+        )
+    };
+
+    if stored {
+        let mut reader = archive.into_inner();
+        reader
+            .seek(SeekFrom::Start(data_start + skip))
+            .map_err(|e| e.to_string())?;
+        copy_n_with_progress(&mut reader, writer, take, cancel, progress)
+            .map_err(|e| e.to_string())?;
+        return Ok(());
+    }
+
+    let mut entry = open_zip_file(&mut archive, 0, password)?;
+    io::copy(&mut (&mut entry).take(skip), &mut io::sink()).map_err(|e| e.to_string())?;
+    copy_n_with_progress(&mut entry, writer, take, cancel, progress).map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn copy_range_zip_then_split<W: Write>(
+    ctx: &JobContext,
+    part_group: &PartGroup,
+    offset: u64,
+    length: u64,
+    password: Option<&str>,
+    writer: &mut W,
+) -> Result<(u64, Vec<usize>), String> {
+    let mut archive = open_part_archive(part_group, "zip-then-split")?;
+    if archive.len() != 1 {
+        return Err(tr!(ArchiveMultipleFilesNoRange));
+    }
+    let (total_size, stored, data_start) = {
+        let raw = archive.by_index_raw(0).map_err(|e| e.to_string())?;
+        if raw.is_dir() {
+            return Err(tr!(ArchiveInvalid));
+        }
+        (
+            raw.size(),
+            matches!(raw.compression(), CompressionMethod::Stored) && !raw.encrypted(),
+            raw.data_start(),
+        )
+    };
+    let length = clamp_range(offset, length, total_size)?;
+    let mut processed = 0u64;
+    let mut progress = |delta| {
+        processed += delta;
+        emit_progress(
+            ctx,
+            "restore-range",
+            processed,
+            length,
+            0,
+            0,
+            tr!(Restoring),
+        );
+    };
+
+    let reader = if stored {
+        let mut reader = archive.into_inner();
+        reader
+            .seek(SeekFrom::Start(data_start + offset))
+            .map_err(|e| e.to_string())?;
+        copy_n_with_progress(&mut reader, writer, length, ctx.cancel_token(), &mut progress)
+            .map_err(|e| e.to_string())?;
+        reader
+    } else {
+        {
+            let mut entry = open_zip_file(&mut archive, 0, password)?;
+            io::copy(&mut (&mut entry).take(offset), &mut io::sink())
+                .map_err(|e| e.to_string())?;
+            copy_n_with_progress(&mut entry, writer, length, ctx.cancel_token(), &mut progress)
+                .map_err(|e| e.to_string())?;
+        }
+        archive.into_inner()
+    };
+
+    let parts_read =
+        visited_part_indexes(part_group, reader.get_ref().part_reader().visited_segments());
+    Ok((length, parts_read))
+}
+
+fn copy_range_zstd_then_split<W: Write>(
+    ctx: &JobContext,
+    part_group: &PartGroup,
+    offset: u64,
+    length: u64,
+    writer: &mut W,
+) -> Result<(u64, Vec<usize>), String> {
+    let part_paths: Vec<PathBuf> = part_group.parts.iter().map(|part| part.path.clone()).collect();
+    let reader = PartReader::from_files(&part_paths).map_err(|e| e.to_string())?;
+    let mut reader = SeekableReader::open_parts(&part_paths, reader).map_err(|e| e.to_string())?;
+    let length = clamp_range(offset, length, reader.len())?;
+    reader
+        .seek(SeekFrom::Start(offset))
+        .map_err(|e| e.to_string())?;
+
+    let mut processed = 0u64;
+    copy_n_with_progress(&mut reader, writer, length, ctx.cancel_token(), |delta| {
+        processed += delta;
+        emit_progress(
+            ctx,
+            "restore-range",
+            processed,
+            length,
+            0,
+            0,
+            tr!(Restoring),
+        );
+    })
+    .map_err(|e| e.to_string())?;
+
+    let parts_read = visited_part_indexes(part_group, reader.get_ref().visited_segments());
+    Ok((length, parts_read))
+}
+
+fn visited_part_indexes(part_group: &PartGroup, visited: &[usize]) -> Vec<usize> {
+    let mut indexes: Vec<usize> = visited
+        .iter()
+        .filter_map(|segment| part_group.parts.get(*segment).map(|part| part.index))
+        .collect();
+    indexes.sort_unstable();
+    indexes
+}
+
+/// 分片拼接后的原始数据流；zstd 分片在此之上再做一层可随机读取的解压。
+pub(crate) enum PartSource {
+    Plain(PartReader),
+    Zstd(SeekableReader<PartReader>),
+}
+
+impl PartSource {
+    pub(crate) fn part_reader(&self) -> &PartReader {
+        match self {
+            PartSource::Plain(reader) => reader,
+            PartSource::Zstd(reader) => reader.get_ref(),
+        }
+    }
+}
+
+impl Read for PartSource {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        match self {
+            PartSource::Plain(reader) => reader.read(buf),
+            PartSource::Zstd(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl Seek for PartSource {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        match self {
+            PartSource::Plain(reader) => reader.seek(pos),
+            PartSource::Zstd(reader) => reader.seek(pos),
+        }
+    }
+}
+
+/// 不合并分片，直接把分片组当作一个 zip 压缩包打开（目录打包或先压缩后分割的产物）。
+pub(crate) fn open_part_archive(
+    part_group: &PartGroup,
+    merge_mode: &str,
+) -> Result<ZipArchive<BufReader<PartSource>>, String> {
+    let layout = index::part_layout(part_group, merge_mode)?;
+    open_archive_from_layout(part_group, merge_mode, &layout)
+}
+
+pub(crate) fn open_archive_from_layout(
+    part_group: &PartGroup,
+    merge_mode: &str,
+    layout: &[IndexedPart],
+) -> Result<ZipArchive<BufReader<PartSource>>, String> {
+    if merge_mode == "7z-then-split" {
+        return Err(tr!(SevenZipNoEntry));
+    }
+    if matches!(merge_mode, "tar-gz-then-split" | "tar-zst-then-split") {
+        return Err(tr!(TarNoEntry));
+    }
+    if !matches!(
+        merge_mode,
+        "split-then-zip" | "zip-then-split" | "zstd-then-split"
+    ) {
+        return Err(tr!(UnknownMergeMode));
+    }
+    // 先分割后压缩的分片：仅 Store 且未加密的分片可以直接定位到数据区
+    if layout.iter().any(|part| !part.seekable) {
+        return Err(tr!(PackedNoEntry));
+    }
+    let part_paths: Vec<PathBuf> = part_group.parts.iter().map(|part| part.path.clone()).collect();
+    let segments = part_paths
+        .iter()
+        .zip(layout)
+        .map(|(path, indexed)| PartSegment {
+            path: path.clone(),
+            file_offset: indexed.data_start,
+            len: indexed.len,
+        })
+        .collect();
+    let reader = PartReader::new(segments);
+    let source = if merge_mode == "zstd-then-split" {
+        PartSource::Zstd(
+            SeekableReader::open_parts(&part_paths, reader).map_err(|e| e.to_string())?,
+        )
+    } else {
+        PartSource::Plain(reader)
+    };
+    ZipArchive::new(BufReader::new(source))
+        .map_err(|_| tr!(NotZipNoEntry))
+}
+
+/// 按名称查找条目：优先完整路径匹配，否则按路径后缀匹配（可省略顶层目录）。
+pub(crate) fn find_entry_index<R: Read + Seek>(
+    archive: &ZipArchive<R>,
+    entry_name: &str,
+) -> Result<usize, String> {
+    let name = entry_name.replace('\\', "/");
+    let name = name.trim_start_matches('/');
+    if name.is_empty() {
+        return Err(tr!(EntryNameEmpty));
+    }
+    if let Some(index) = archive.index_for_name(name) {
+        return Ok(index);
+    }
+    let suffix = format!("/{}", name);
+    let mut matches = archive
+        .file_names()
+        .filter(|candidate| candidate.ends_with(&suffix))
+        .filter_map(|candidate| archive.index_for_name(candidate));
+    match (matches.next(), matches.next()) {
+        (Some(index), None) => Ok(index),
+        (Some(_), Some(_)) => Err(tr!(EntryAmbiguous)),
+        _ => Err(tr!(EntryNotFound, name)),
+    }
+}
+
+/// 从分片组中直接提取单个文件，只读取该文件所在的分片。
+pub(crate) fn extract_entry_blocking(
+    ctx: &JobContext,
+    mut options: ExtractEntryOptions,
+) -> Result<ExtractEntryResult, String> {
+    let input_path = PathBuf::from(options.input_path);
+    let output_dir = PathBuf::from(options.output_dir);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    fs::create_dir_all(&output_dir).map_err(|e| e.to_string())?;
+
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let password = password.as_deref();
+    let part_group = collect_part_group(&input_path)?;
+    let mut archive = open_part_archive(&part_group, options.merge_mode.as_str())?;
+    let index = find_entry_index(&archive, &options.entry_name)?;
+
+    let (entry_name, output_file) = {
+        let mut entry = open_zip_file(&mut archive, index, password)?;
+        if entry.is_dir() {
+            return Err(tr!(EntryIsDir));
+        }
+        let entry_name = entry.name().to_string();
+        let file_name = entry
+            .enclosed_name()
+            .and_then(|path| path.file_name().map(|name| name.to_os_string()))
+            .ok_or_else(|| tr!(CannotParseFileName))?;
+        let output_file = output_dir.join(file_name);
+
+        let total_bytes = entry.size();
+        let mut processed = 0u64;
+        emit_progress(ctx, "unzip", 0, total_bytes, 0, 0, tr!(Extracting));
+        let mut writer = BufWriter::new(File::create(&output_file).map_err(|e| e.to_string())?);
+        copy_n_with_progress(&mut entry, &mut writer, total_bytes, ctx.cancel_token(), |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "unzip",
+                processed,
+                total_bytes,
+                0,
+                0,
+                tr!(Extracting),
+            );
+        })
+        .map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())?;
+        (entry_name, output_file)
+    };
+
+    let source = archive.into_inner().into_inner();
+    let parts_read = visited_part_indexes(&part_group, source.part_reader().visited_segments());
+    Ok(ExtractEntryResult {
+        entry_name,
+        output_file: output_file.to_string_lossy().to_string(),
+        parts_read,
+    })
+}
+
+/// 不落临时文件，把单个条目（或其中一段）的内容写到目标路径，或按块交给 `on_data`；
+/// 每块之前先以 `on_event` 送出该块的位置与长度。
+pub(crate) fn stream_entry_blocking(
+    ctx: &JobContext,
+    mut options: StreamEntryOptions,
+    mut on_event: impl FnMut(EntryStreamEvent) -> Result<(), String>,
+    mut on_data: impl FnMut(&[u8]) -> Result<(), String>,
+) -> Result<StreamEntryResult, String> {
+    let input_path = PathBuf::from(options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let target_path = options.target_path.filter(|value| !value.is_empty()).map(PathBuf::from);
+    if let Some(parent) = target_path.as_ref().and_then(|path| path.parent()) {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+
+    secrets::resolve(&mut options.password, &mut options.secret_job_id)?;
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let password = password.as_deref();
+    let part_group = collect_part_group(&input_path)?;
+    let mut archive = open_part_archive(&part_group, options.merge_mode.as_str())?;
+    let index = find_entry_index(&archive, &options.entry_name)?;
+
+    let (entry_name, size, bytes_sent) = {
+        let mut entry = open_zip_file(&mut archive, index, password)?;
+        if entry.is_dir() {
+            return Err(tr!(EntryIsDir));
+        }
+        let entry_name = entry.name().to_string();
+        let size = entry.size();
+        let offset = options.offset.unwrap_or(0);
+        let length = if offset >= size {
+            0
+        } else {
+            cmp::min(options.length.unwrap_or(u64::MAX), size - offset)
+        };
+        on_event(EntryStreamEvent::Started {
+            entry_name: entry_name.clone(),
+            size,
+        })?;
+        if offset > 0 {
+            io::copy(&mut (&mut entry).take(offset), &mut io::sink())
+                .map_err(|e| e.to_string())?;
+        }
+
+        let mut processed = 0u64;
+        let mut progress = |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "stream",
+                processed,
+                length,
+                0,
+                0,
+                tr!(Reading),
+            );
+        };
+        match target_path.as_ref() {
+            Some(path) => {
+                let mut writer = BufWriter::new(File::create(path).map_err(|e| e.to_string())?);
+                let cancel = ctx.cancel_token();
+                copy_n_with_progress(&mut entry, &mut writer, length, cancel, &mut progress)
+                    .map_err(|e| e.to_string())?;
+                writer.flush().map_err(|e| e.to_string())?;
+            }
+            None => {
+                let mut buffer = vec![0u8; STREAM_CHUNK_SIZE];
+                let mut sent = 0u64;
+                while sent < length {
+                    ctx.check_cancelled()?;
+                    let want = cmp::min(buffer.len() as u64, length - sent) as usize;
+                    let read_len = entry.read(&mut buffer[..want]).map_err(|e| e.to_string())?;
+                    if read_len == 0 {
+                        return Err(tr!(EntryIncomplete));
+                    }
+                    on_event(EntryStreamEvent::Chunk {
+                        offset: offset + sent,
+                        length: read_len as u64,
+                    })?;
+                    on_data(&buffer[..read_len])?;
+                    sent += read_len as u64;
+                    progress(read_len as u64);
+                }
+            }
+        }
+        on_event(EntryStreamEvent::Finished { bytes_sent: length })?;
+        (entry_name, size, length)
+    };
+
+    let source = archive.into_inner().into_inner();
+    let parts_read = visited_part_indexes(&part_group, source.part_reader().visited_segments());
+    Ok(StreamEntryResult {
+        entry_name,
+        size,
+        bytes_sent,
+        target_path: target_path.map(|path| path.to_string_lossy().to_string()),
+        parts_read,
+    })
+}
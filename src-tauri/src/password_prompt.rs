@@ -0,0 +1,104 @@
+//! 密码错误或缺少密码时向界面重新要密码：不让已合并好的几十 GB 数据因为一次输错而白费。
+//! 解压合并结果（以及先分割后压缩合并前的试解密、加密容器开始解密时）失败在密码上时，通过 [`crate::prompts`] 发送
+//! `kind` 为 `passwordRequired` 的提问，附带 `archive`（需要密码的文件）、`reason`
+//! （`wrongPassword` 或 `passwordRequired`）与已尝试的次数 `attempt`；回答为 `{ password }`，
+//! 以新密码重试同一步骤。回答 `{ password: null }` 时放弃，照常以密码错误结束任务。
+//!
+//! 不能询问的场景（命令行、没有界面的任务）与以前一样直接返回错误。
+//!
+//! 每次尝试先核对密码再写出数据，只有核对失败（[`PasswordError::Wrong`]、
+//! [`PasswordError::Required`]）才重试；写出数据之后的错误一律作为 [`PasswordError::Other`]
+//! 结束任务，不会在已解压的文件上再解压一遍。RAR 解压到加密的条目才能发现密码不对，
+//! 此时先删掉本次写出的文件再重试。
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::{i18n::tr, jobs::JobContext, keyfile, prompts, secrets::SecretString};
+
+/// 核对密码或解压的错误：密码不对、缺少密码，或其他原因（已是给用户看的消息）。
+#[derive(Debug)]
+pub(crate) enum PasswordError {
+    Wrong,
+    Required,
+    Other(String),
+}
+
+impl From<String> for PasswordError {
+    fn from(err: String) -> Self {
+        PasswordError::Other(err)
+    }
+}
+
+impl From<PasswordError> for String {
+    fn from(err: PasswordError) -> Self {
+        match err {
+            PasswordError::Wrong => tr!(WrongPassword),
+            PasswordError::Required => tr!(PasswordRequired),
+            PasswordError::Other(err) => err,
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PasswordPrompt {
+    archive: String,
+    reason: &'static str,
+    attempt: u32,
+}
+
+#[derive(Deserialize)]
+struct PasswordAnswer {
+    password: Option<SecretString>,
+}
+
+/// 以 `password` 执行 `attempt`；因密码错误或缺少密码失败时向界面要新密码后重试，
+/// 直到成功、放弃或失败在其他原因上。`attempt` 需在写出数据之前核对密码，写出数据后才发现
+/// 密码不对时，先删掉本次写出的数据再返回 [`PasswordError::Wrong`]。`password` 已拼上密钥文件的
+/// 校验值；询问到的新密码同样拼上任务的密钥文件（[`JobContext::keyfile`]）后再试。
+pub(crate) fn retry<T>(
+    ctx: &JobContext,
+    archive: &Path,
+    password: Option<&str>,
+    mut attempt: impl FnMut(Option<&str>) -> Result<T, PasswordError>,
+) -> Result<T, String> {
+    let mut current = password.map(SecretString::from);
+    let mut attempts = 0u32;
+    loop {
+        let err = match attempt(current.as_deref()) {
+            Ok(value) => return Ok(value),
+            Err(err) => err,
+        };
+        let reason = match err {
+            PasswordError::Wrong => "wrongPassword",
+            PasswordError::Required => "passwordRequired",
+            PasswordError::Other(err) => return Err(err),
+        };
+        if !prompts::can_ask(ctx) {
+            return Err(err.into());
+        }
+        attempts += 1;
+        let answer = prompts::ask(
+            ctx,
+            "passwordRequired",
+            PasswordPrompt {
+                archive: archive.to_string_lossy().to_string(),
+                reason,
+                attempt: attempts,
+            },
+        )?;
+        let answer: PasswordAnswer =
+            serde_json::from_value(answer).map_err(|e| tr!(InvalidPromptAnswer, e))?;
+        match answer.password.filter(|value| !value.is_empty()) {
+            Some(password) => {
+                log::info!("以新密码重试：{}（第 {} 次）", archive.display(), attempts);
+                current = keyfile::effective_password(Some(&password), ctx.keyfile())?;
+            }
+            None => {
+                log::info!("放弃输入密码：{}", archive.display());
+                return Err(err.into());
+            }
+        }
+    }
+}
@@ -0,0 +1,241 @@
+//! 密码强度估算：加密打包往往要跑几个小时，开始前先给密码打分，界面据此拦下一眼就能猜中的密码。
+//!
+//! 做法参照 zxcvbn 的思路但大为简化：按出现的字符类别得出每个字符的熵，重复字符、连续的字母数字
+//! 与键盘上相邻的按键（`aaa`、`abc`、`123`、`qwerty`）只按 1 位计；去掉大小写、常见的字母替换
+//! （`p@ssw0rd`）与末尾的数字符号后是常见密码的，按常见密码表的大小计。估算的是离线穷举所需的
+//! 尝试次数，不考虑密钥派生的开销。
+
+use serde::Serialize;
+
+use crate::i18n::Msg;
+
+/// 常见密码表，只收录最常见的一小部分，足以拦下最容易猜中的情况。
+const COMMON_PASSWORDS: &[&str] = &[
+    "password",
+    "123456",
+    "12345678",
+    "123456789",
+    "1234567890",
+    "qwerty",
+    "qwertyuiop",
+    "abc123",
+    "111111",
+    "123123",
+    "000000",
+    "654321",
+    "666666",
+    "888888",
+    "5201314",
+    "iloveyou",
+    "admin",
+    "administrator",
+    "root",
+    "letmein",
+    "welcome",
+    "monkey",
+    "dragon",
+    "master",
+    "shadow",
+    "sunshine",
+    "princess",
+    "football",
+    "baseball",
+    "superman",
+    "batman",
+    "trustno1",
+    "starwars",
+    "freedom",
+    "whatever",
+    "hello",
+    "login",
+    "secret",
+    "changeme",
+    "default",
+    "test",
+    "guest",
+    "qazwsx",
+    "1q2w3e4r",
+    "zaq12wsx",
+    "asdfgh",
+    "asdfghjkl",
+    "zxcvbnm",
+    "woaini",
+    "iloveu",
+    "love",
+    "passwd",
+    "pass",
+    "access",
+    "mustang",
+    "michael",
+    "charlie",
+    "computer",
+];
+
+/// 命中常见密码时按约一万个候选（2^14）计，攻击者的字典远大于上表。
+const COMMON_PASSWORD_BITS: f64 = 14.0;
+
+const KEYBOARD_ROWS: &[&str] = &["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// 少于此长度时提示加长。
+const RECOMMENDED_LENGTH: usize = 12;
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PasswordStrength {
+    /// 0（极弱）至 4（很强），与 zxcvbn 的分级一致。
+    pub(crate) score: u8,
+    /// 估算的熵（位），即约 2^n 次尝试可以猜中。
+    pub(crate) entropy_bits: f64,
+    pub(crate) warnings: Vec<String>,
+}
+
+pub(crate) fn estimate(password: &str) -> PasswordStrength {
+    let chars: Vec<char> = password.chars().collect();
+    if chars.is_empty() {
+        return PasswordStrength {
+            score: 0,
+            entropy_bits: 0.0,
+            warnings: vec![Msg::PasswordEmpty.text().to_string()],
+        };
+    }
+
+    let mut warnings = Vec::new();
+    let pool_bits = (pool_size(&chars) as f64).log2();
+    let (mut bits, repeats, sequences) = pattern_bits(&chars, pool_bits);
+    if let Some(tail) = common_tail(&chars) {
+        // 再加上末尾追加的数字与符号
+        let (tail_bits, _, _) = pattern_bits(tail, pool_bits);
+        bits = bits.min(COMMON_PASSWORD_BITS + tail_bits);
+        warnings.push(Msg::PasswordCommon.text().to_string());
+    }
+    if repeats {
+        warnings.push(Msg::PasswordRepeats.text().to_string());
+    }
+    if sequences {
+        warnings.push(Msg::PasswordSequence.text().to_string());
+    }
+    if chars.len() < RECOMMENDED_LENGTH {
+        warnings.push(Msg::PasswordTooShort.text().to_string());
+    }
+    if class_count(&chars) == 1 {
+        warnings.push(Msg::PasswordSingleClass.text().to_string());
+    }
+
+    let score = match bits {
+        bits if bits < 28.0 => 0,
+        bits if bits < 40.0 => 1,
+        bits if bits < 56.0 => 2,
+        bits if bits < 72.0 => 3,
+        _ => 4,
+    };
+    PasswordStrength {
+        score,
+        entropy_bits: (bits * 10.0).round() / 10.0,
+        warnings,
+    }
+}
+
+/// 逐个字符累计熵，返回总位数以及是否出现了重复字符、连续字符。
+fn pattern_bits(chars: &[char], pool_bits: f64) -> (f64, bool, bool) {
+    let mut bits = 0.0;
+    let (mut repeats, mut sequences) = (false, false);
+    for (idx, &ch) in chars.iter().enumerate() {
+        let prev = idx.checked_sub(1).map(|prev| chars[prev]);
+        match prev {
+            Some(prev) if prev == ch => {
+                repeats = true;
+                bits += 1.0;
+            }
+            Some(prev) if is_sequential(prev, ch) => {
+                sequences = true;
+                bits += 1.0;
+            }
+            _ => bits += pool_bits,
+        }
+    }
+    (bits, repeats, sequences)
+}
+
+/// 两个字符在字母表、数字或键盘同一行上相邻（不分大小写与方向）。
+fn is_sequential(a: char, b: char) -> bool {
+    let (a, b) = (a.to_ascii_lowercase(), b.to_ascii_lowercase());
+    if a.is_ascii_alphanumeric() && b.is_ascii_alphanumeric() && (a as u32).abs_diff(b as u32) == 1
+    {
+        return true;
+    }
+    KEYBOARD_ROWS.iter().any(|row| {
+        row.as_bytes().windows(2).any(|pair| {
+            (pair[0] as char, pair[1] as char) == (a, b)
+                || (pair[1] as char, pair[0] as char) == (a, b)
+        })
+    })
+}
+
+/// 整个密码或去掉末尾的数字与符号后是常见密码时，返回去掉的部分。
+fn common_tail(chars: &[char]) -> Option<&[char]> {
+    if is_common(chars) {
+        return Some(&[][..]);
+    }
+    let base_len = chars
+        .iter()
+        .rposition(|ch| ch.is_alphabetic())
+        .map_or(0, |idx| idx + 1);
+    if base_len == 0 || base_len == chars.len() {
+        return None;
+    }
+    is_common(&chars[..base_len]).then_some(&chars[base_len..])
+}
+
+fn is_common(chars: &[char]) -> bool {
+    let lower: String = chars.iter().map(char::to_ascii_lowercase).collect();
+    let unleeted: String = chars.iter().map(|&ch| unleet(ch)).collect();
+    COMMON_PASSWORDS
+        .iter()
+        .any(|common| *common == lower || *common == unleeted)
+}
+
+/// 还原常见的字母替换并转为小写，如 `P@ssw0rd` 还原为 `password`。
+fn unleet(ch: char) -> char {
+    match ch {
+        '@' | '4' => 'a',
+        '3' => 'e',
+        '1' | '!' => 'i',
+        '0' => 'o',
+        '$' | '5' => 's',
+        '7' => 't',
+        ch => ch.to_ascii_lowercase(),
+    }
+}
+
+/// 出现的字符类别可能的字符总数：小写、大写、数字、ASCII 符号与其他字符。
+fn pool_size(chars: &[char]) -> u32 {
+    let mut size = 0;
+    if chars.iter().any(char::is_ascii_lowercase) {
+        size += 26;
+    }
+    if chars.iter().any(char::is_ascii_uppercase) {
+        size += 26;
+    }
+    if chars.iter().any(char::is_ascii_digit) {
+        size += 10;
+    }
+    if chars.iter().any(char::is_ascii_punctuation) || chars.contains(&' ') {
+        size += 33;
+    }
+    if chars.iter().any(|ch| !ch.is_ascii()) {
+        size += 100;
+    }
+    size.max(2)
+}
+
+fn class_count(chars: &[char]) -> usize {
+    [
+        chars.iter().any(char::is_ascii_lowercase),
+        chars.iter().any(char::is_ascii_uppercase),
+        chars.iter().any(char::is_ascii_digit),
+        chars.iter().any(|ch| !ch.is_ascii_alphanumeric()),
+    ]
+    .into_iter()
+    .filter(|present| *present)
+    .count()
+}
@@ -0,0 +1,211 @@
+//! 分割完成后的后处理插件（如病毒扫描、签名、上传）。
+//!
+//! 插件以子进程方式运行，放在插件目录下，每个插件一个描述文件 `<名称>.json`：
+//! `{"name": "scan", "command": "./scan.sh", "args": [], "description": "..."}`，
+//! 相对路径的 `command` 相对插件目录解析，插件的工作目录也是插件目录。
+//!
+//! 插件目录默认为程序所在目录下的 `plugins`，可通过环境变量 `FILE_SPLIT_PACKER_PLUGIN_DIR` 覆盖。
+//!
+//! 协议：向插件标准输入写入一行 JSON `{"version": 1, "parts": [...], "manifest": {...}}` 后关闭；
+//! 插件可在标准输出逐行输出 `{"message", "processedBytes", "totalBytes"}` 汇报进度（其他内容忽略），
+//! 退出码为 0 表示成功，否则以标准错误的最后一行作为错误信息。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    env, fs,
+    io::{BufRead, BufReader, Read, Write},
+    path::{Path, PathBuf},
+    process::{Command, Stdio},
+    thread,
+};
+
+use crate::{
+    emit_progress, emit_stream_progress,
+    i18n::{tr, Msg},
+    jobs::JobContext,
+};
+
+const PROTOCOL_VERSION: u32 = 1;
+const PLUGIN_DIR_ENV: &str = "FILE_SPLIT_PACKER_PLUGIN_DIR";
+
+/// 后处理步骤：输入为已完成的分片路径与清单（分割结果）。
+pub(crate) trait PostProcessor {
+    fn name(&self) -> &str;
+    fn process(&self, ctx: &JobContext, parts: &[String], manifest: &Value) -> Result<(), String>;
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct PluginInfo {
+    pub(crate) name: String,
+    pub(crate) command: String,
+    #[serde(default)]
+    pub(crate) args: Vec<String>,
+    #[serde(default)]
+    pub(crate) description: Option<String>,
+}
+
+/// 以子进程方式运行的插件。
+pub(crate) struct SubprocessPlugin {
+    info: PluginInfo,
+    dir: PathBuf,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct PluginProgress {
+    message: Option<String>,
+    processed_bytes: Option<u64>,
+    total_bytes: Option<u64>,
+}
+
+impl PostProcessor for SubprocessPlugin {
+    fn name(&self) -> &str {
+        &self.info.name
+    }
+
+    fn process(&self, ctx: &JobContext, parts: &[String], manifest: &Value) -> Result<(), String> {
+        let command = if Path::new(&self.info.command).components().count() > 1 {
+            self.dir.join(&self.info.command)
+        } else {
+            PathBuf::from(&self.info.command)
+        };
+        let mut child = Command::new(command)
+            .args(&self.info.args)
+            .current_dir(&self.dir)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| tr!(PluginStartFailed, self.info.name, e))?;
+
+        let request = json!({
+            "version": PROTOCOL_VERSION,
+            "parts": parts,
+            "manifest": manifest,
+        });
+        if let Some(mut stdin) = child.stdin.take() {
+            // 插件可能不读取输入，写入失败不视为错误
+            let _ = writeln!(stdin, "{}", request);
+        }
+
+        let mut stderr = child.stderr.take();
+        let stderr_reader = thread::spawn(move || {
+            let mut content = String::new();
+            if let Some(stderr) = stderr.as_mut() {
+                let _ = stderr.read_to_string(&mut content);
+            }
+            content
+        });
+        if let Some(stdout) = child.stdout.take() {
+            for line in BufReader::new(stdout).lines() {
+                let Ok(line) = line else {
+                    break;
+                };
+                if let Ok(progress) = serde_json::from_str::<PluginProgress>(&line) {
+                    let message = progress
+                        .message
+                        .unwrap_or_else(|| tr!(PluginRunning, self.info.name));
+                    let processed = progress.processed_bytes.unwrap_or(0);
+                    match progress.total_bytes {
+                        Some(total) => {
+                            emit_progress(ctx, "postprocess", processed, total, 0, 0, message)
+                        }
+                        None => emit_stream_progress(ctx, "postprocess", processed, 0, message),
+                    }
+                }
+            }
+        }
+
+        let status = child.wait().map_err(|e| e.to_string())?;
+        let stderr = stderr_reader.join().unwrap_or_default();
+        if !status.success() {
+            let reason = stderr
+                .lines()
+                .rev()
+                .find(|line| !line.trim().is_empty())
+                .unwrap_or(Msg::UnknownError.text())
+                .trim()
+                .to_string();
+            return Err(tr!(PluginFailed, self.info.name, reason));
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn plugin_dir() -> Option<PathBuf> {
+    if let Some(dir) = env::var_os(PLUGIN_DIR_ENV).filter(|value| !value.is_empty()) {
+        return Some(PathBuf::from(dir));
+    }
+    env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join("plugins")))
+}
+
+/// 扫描插件目录下的描述文件；无法解析的描述文件会被跳过。
+pub(crate) fn discover_plugins() -> Vec<SubprocessPlugin> {
+    let Some(dir) = plugin_dir() else {
+        return Vec::new();
+    };
+    let Ok(entries) = fs::read_dir(&dir) else {
+        return Vec::new();
+    };
+    let mut plugins: Vec<SubprocessPlugin> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().map(|ext| ext == "json").unwrap_or(false))
+        .filter_map(|path| {
+            let content = fs::read_to_string(&path).ok()?;
+            let info: PluginInfo = serde_json::from_str(&content).ok()?;
+            Some(SubprocessPlugin {
+                info,
+                dir: dir.clone(),
+            })
+        })
+        .collect();
+    plugins.sort_by(|a, b| a.info.name.cmp(&b.info.name));
+    plugins
+}
+
+pub(crate) fn list_plugins() -> Vec<PluginInfo> {
+    discover_plugins()
+        .into_iter()
+        .map(|plugin| plugin.info)
+        .collect()
+}
+
+/// 按顺序运行指定名称的插件，任一插件失败即停止。
+pub(crate) fn run_post_processors(
+    ctx: &JobContext,
+    names: &[String],
+    parts: &[String],
+    manifest: &Value,
+) -> Result<(), String> {
+    if names.is_empty() {
+        return Ok(());
+    }
+    let available = discover_plugins();
+    let mut selected: Vec<&dyn PostProcessor> = Vec::with_capacity(names.len());
+    for name in names {
+        let plugin = available
+            .iter()
+            .find(|plugin| plugin.name() == name)
+            .ok_or_else(|| tr!(PluginNotFound, name))?;
+        selected.push(plugin);
+    }
+    let total = selected.len();
+    for (idx, plugin) in selected.into_iter().enumerate() {
+        emit_progress(
+            ctx,
+            "postprocess",
+            0,
+            0,
+            idx + 1,
+            total,
+            tr!(RunningPlugin, plugin.name()),
+        );
+        plugin.process(ctx, parts, manifest)?;
+    }
+    Ok(())
+}
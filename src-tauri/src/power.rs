@@ -0,0 +1,101 @@
+//! 完成后的电源操作：打包任务或整个队列成功完成后关机、睡眠或休眠，适合通宵切分大型磁盘镜像。
+//! 失败或取消时不执行。
+//!
+//! 通过各平台的系统命令执行：Windows 为 `shutdown` 与 `rundll32 powrprof.dll`，
+//! macOS 为 `osascript` 与 `pmset`（不支持休眠），Linux 为 `systemctl`。
+
+use serde::Serialize;
+use std::process::Command;
+
+use crate::i18n::tr;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum PowerAction {
+    #[default]
+    None,
+    Shutdown,
+    Sleep,
+    Hibernate,
+}
+
+impl PowerAction {
+    /// 解析 `none`、`shutdown`、`sleep`、`hibernate`；空字符串视为 `none`。
+    pub(crate) fn parse(value: &str) -> Option<PowerAction> {
+        match value.trim() {
+            "" | "none" => Some(PowerAction::None),
+            "shutdown" => Some(PowerAction::Shutdown),
+            "sleep" => Some(PowerAction::Sleep),
+            "hibernate" => Some(PowerAction::Hibernate),
+            _ => None,
+        }
+    }
+
+    pub(crate) fn name(self) -> &'static str {
+        match self {
+            PowerAction::None => "none",
+            PowerAction::Shutdown => "shutdown",
+            PowerAction::Sleep => "sleep",
+            PowerAction::Hibernate => "hibernate",
+        }
+    }
+
+    /// 当前平台执行该操作的命令与参数，不支持时返回 `None`。
+    fn command(self) -> Option<(&'static str, &'static [&'static str])> {
+        if cfg!(windows) {
+            match self {
+                PowerAction::None => None,
+                PowerAction::Shutdown => Some(("shutdown", &["/s", "/t", "0"])),
+                PowerAction::Sleep => {
+                    Some(("rundll32.exe", &["powrprof.dll,SetSuspendState", "0,1,0"]))
+                }
+                PowerAction::Hibernate => Some(("shutdown", &["/h"])),
+            }
+        } else if cfg!(target_os = "macos") {
+            match self {
+                PowerAction::Shutdown => Some((
+                    "osascript",
+                    &["-e", "tell application \"System Events\" to shut down"],
+                )),
+                PowerAction::Sleep => Some(("pmset", &["sleepnow"])),
+                PowerAction::None | PowerAction::Hibernate => None,
+            }
+        } else {
+            match self {
+                PowerAction::None => None,
+                PowerAction::Shutdown => Some(("systemctl", &["poweroff"])),
+                PowerAction::Sleep => Some(("systemctl", &["suspend"])),
+                PowerAction::Hibernate => Some(("systemctl", &["hibernate"])),
+            }
+        }
+    }
+}
+
+/// 执行电源操作；`None` 时什么也不做。
+pub(crate) fn perform(action: PowerAction) -> Result<(), String> {
+    if action == PowerAction::None {
+        return Ok(());
+    }
+    let (program, args) = action
+        .command()
+        .ok_or_else(|| tr!(PowerActionUnsupported, action.name()))?;
+    log::info!("任务完成，执行电源操作：{}（{} {}）", action.name(), program, args.join(" "));
+    let status = Command::new(program)
+        .args(args)
+        .status()
+        .map_err(|e| tr!(PowerActionFailed, e))?;
+    if !status.success() {
+        return Err(tr!(PowerActionFailed, status));
+    }
+    Ok(())
+}
+
+/// 任务成功后调用；执行出错只记录日志，不影响任务结果。
+pub(crate) fn after_success(action: Option<&str>) {
+    let Some(action) = action.and_then(PowerAction::parse) else {
+        return;
+    };
+    if let Err(err) = perform(action) {
+        log::error!("{}", err);
+    }
+}
@@ -0,0 +1,111 @@
+//! 后台优先级：切分超大文件时降低工作线程的 CPU 与磁盘 I/O 优先级，前台程序不再卡顿，
+//! 代价是机器繁忙时切分变慢。
+//!
+//! 只调整本程序新建的线程：任务线程与并行压缩分片的线程池用完即结束，设置随之消失；
+//! seekable zstd 压缩与 BLAKE3 校验共用常驻的 [`background_pool`]，只供后台优先级的任务使用。
+//! 不影响界面与其他任务。各平台的做法：
+//!
+//! - Linux：nice 值设为 19，I/O 调度设为 best-effort 最低级（相当于 `ionice -c2 -n7`）
+//! - Windows：`THREAD_MODE_BACKGROUND_BEGIN`（I/O 优先级降为 very low）并把线程优先级设为最低
+//! - macOS：`PRIO_DARWIN_BG`，同时降低 CPU 与 I/O 优先级
+//!
+//! 其他平台不做调整。
+
+use std::{sync::OnceLock, thread};
+
+static BACKGROUND_POOL: OnceLock<rayon::ThreadPool> = OnceLock::new();
+
+/// 在新线程中以后台优先级运行 `run`，等待其结束并返回结果。
+pub(crate) fn run_in_background<T, F>(run: F) -> T
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    thread::scope(|scope| {
+        let worker = scope.spawn(|| {
+            lower_current_thread();
+            run()
+        });
+        match worker.join() {
+            Ok(value) => value,
+            Err(panic) => std::panic::resume_unwind(panic),
+        }
+    })
+}
+
+/// 以后台优先级运行的任务做并行计算时所用的线程池，代替 rayon 的全局线程池：
+/// 线程数与 CPU 核数相同，每个线程启动时降低优先级。
+pub(crate) fn background_pool() -> &'static rayon::ThreadPool {
+    BACKGROUND_POOL.get_or_init(|| {
+        rayon::ThreadPoolBuilder::new()
+            .thread_name(|index| format!("background-{}", index))
+            .start_handler(|_| lower_current_thread())
+            .build()
+            .expect("background thread pool")
+    })
+}
+
+/// `background` 时在 [`background_pool`] 中运行 `run`，否则直接运行（使用全局线程池）。
+pub(crate) fn install<T, F>(background: bool, run: F) -> T
+where
+    T: Send,
+    F: FnOnce() -> T + Send,
+{
+    if background {
+        background_pool().install(run)
+    } else {
+        run()
+    }
+}
+
+/// 降低当前线程的优先级，不可恢复，只能用于本程序新建、用完即结束的线程。
+#[cfg(target_os = "linux")]
+pub(crate) fn lower_current_thread() {
+    const IOPRIO_WHO_PROCESS: libc::c_long = 1;
+    const IOPRIO_CLASS_BE: libc::c_long = 2;
+    const IOPRIO_CLASS_SHIFT: libc::c_long = 13;
+    const IOPRIO_LOWEST_LEVEL: libc::c_long = 7;
+    // Linux 的 nice 值与 I/O 优先级按线程生效，用线程 ID 只调整当前线程
+    let tid = unsafe { libc::syscall(libc::SYS_gettid) };
+    if unsafe { libc::setpriority(libc::PRIO_PROCESS, tid as libc::id_t, 19) } != 0 {
+        log::debug!("降低线程 CPU 优先级失败：{}", std::io::Error::last_os_error());
+    }
+    let ioprio = (IOPRIO_CLASS_BE << IOPRIO_CLASS_SHIFT) | IOPRIO_LOWEST_LEVEL;
+    if unsafe { libc::syscall(libc::SYS_ioprio_set, IOPRIO_WHO_PROCESS, tid, ioprio) } != 0 {
+        log::debug!("降低线程 I/O 优先级失败：{}", std::io::Error::last_os_error());
+    }
+}
+
+#[cfg(target_os = "macos")]
+pub(crate) fn lower_current_thread() {
+    if unsafe { libc::setpriority(libc::PRIO_DARWIN_THREAD, 0, libc::PRIO_DARWIN_BG) } != 0 {
+        log::debug!("切换到后台优先级失败：{}", std::io::Error::last_os_error());
+    }
+}
+
+#[cfg(windows)]
+pub(crate) fn lower_current_thread() {
+    use std::ffi::c_void;
+
+    const THREAD_MODE_BACKGROUND_BEGIN: i32 = 0x0001_0000;
+    const THREAD_PRIORITY_LOWEST: i32 = -2;
+
+    #[link(name = "kernel32")]
+    extern "system" {
+        fn GetCurrentThread() -> *mut c_void;
+        fn SetThreadPriority(thread: *mut c_void, priority: i32) -> i32;
+    }
+
+    unsafe {
+        let thread = GetCurrentThread();
+        if SetThreadPriority(thread, THREAD_MODE_BACKGROUND_BEGIN) == 0 {
+            log::debug!("切换到后台模式失败：{}", std::io::Error::last_os_error());
+        }
+        if SetThreadPriority(thread, THREAD_PRIORITY_LOWEST) == 0 {
+            log::debug!("降低线程优先级失败：{}", std::io::Error::last_os_error());
+        }
+    }
+}
+
+#[cfg(not(any(target_os = "linux", target_os = "macos", windows)))]
+pub(crate) fn lower_current_thread() {}
@@ -0,0 +1,103 @@
+//! 任务中途向界面提问：发送 `job-prompt` 事件后阻塞等待，界面以 `answer_prompt` 命令回答。
+//! 事件为 `{ jobId, promptId, kind, ... }`，其余字段随提问的种类而定；回答为任意 JSON，
+//! 由提问方解析。等待期间照常响应取消。
+//!
+//! 只有在界面中运行、属于某个任务的操作才能提问，命令行等没有界面的场景直接报错。
+
+use serde::Serialize;
+use serde_json::Value;
+use std::{
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, RecvTimeoutError, Sender},
+        Mutex, OnceLock,
+    },
+    time::Duration,
+};
+use tauri::Emitter;
+
+use crate::{i18n::tr, jobs::JobContext};
+
+/// 等待回答时检查取消的间隔。
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct PromptEvent<'a, T: Serialize + Clone> {
+    job_id: &'a str,
+    prompt_id: &'a str,
+    kind: &'a str,
+    #[serde(flatten)]
+    details: T,
+}
+
+fn pending() -> &'static Mutex<HashMap<String, Sender<Value>>> {
+    static PENDING: OnceLock<Mutex<HashMap<String, Sender<Value>>>> = OnceLock::new();
+    PENDING.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 当前任务能否向界面提问。
+pub(crate) fn can_ask(ctx: &JobContext) -> bool {
+    ctx.app().is_some() && ctx.job_id().is_some()
+}
+
+/// 发送种类为 `kind` 的提问并等待回答；任务被取消时返回取消错误。
+pub(crate) fn ask<T: Serialize + Clone>(
+    ctx: &JobContext,
+    kind: &str,
+    details: T,
+) -> Result<Value, String> {
+    let (Some(app), Some(job_id)) = (ctx.app(), ctx.job_id()) else {
+        return Err(tr!(PromptNeedsUi));
+    };
+    static NEXT_ID: AtomicU64 = AtomicU64::new(1);
+    let prompt_id = format!("{}-{}", job_id, NEXT_ID.fetch_add(1, Ordering::Relaxed));
+    let (sender, receiver) = mpsc::channel();
+    pending()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .insert(prompt_id.clone(), sender);
+    let event = PromptEvent {
+        job_id,
+        prompt_id: &prompt_id,
+        kind,
+        details,
+    };
+    if let Err(err) = app.emit("job-prompt", event) {
+        forget(&prompt_id);
+        return Err(err.to_string());
+    }
+    log::info!("等待界面回答：{}（{}）", kind, prompt_id);
+
+    loop {
+        if let Err(err) = ctx.check_cancelled() {
+            forget(&prompt_id);
+            return Err(err);
+        }
+        match receiver.recv_timeout(POLL_INTERVAL) {
+            Ok(answer) => return Ok(answer),
+            Err(RecvTimeoutError::Timeout) => continue,
+            Err(RecvTimeoutError::Disconnected) => return Err(tr!(PromptNotFound, prompt_id)),
+        }
+    }
+}
+
+/// 回答 `prompt_id` 对应的提问。
+pub(crate) fn answer(prompt_id: &str, answer: Value) -> Result<(), String> {
+    let sender = pending()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(prompt_id)
+        .ok_or_else(|| tr!(PromptNotFound, prompt_id))?;
+    sender
+        .send(answer)
+        .map_err(|_| tr!(PromptNotFound, prompt_id))
+}
+
+fn forget(prompt_id: &str) {
+    pending()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .remove(prompt_id);
+}
@@ -0,0 +1,401 @@
+//! 任务队列：把多个打包、合并任务加入队列，由后台工作线程按加入顺序逐个运行，
+//! 前端不必等上一次 `process_file` 返回后再发起下一次。
+//!
+//! 加入队列时即解析参数，格式错误立即返回。队列变化时发送 `queue-changed` 事件（当前队列），
+//! 运行中任务的进度除照常发送 `split-progress` 外，还以 `queue-progress` 事件
+//! （`{queueId, jobId, progress}`）发送。运行中的任务不能移出队列，请用 `cancel_job` 取消；
+//! 任务结束后移出队列，结果可在任务列表中查询。
+//!
+//! 因临时性错误（网络共享短暂断开、文件被暂时锁定、超时等）失败的任务按重试策略
+//! 等待一段时间后再次运行，等待时间逐次加倍；参数错误等其他失败与取消不重试。
+//!
+//! 队列设置中的电源操作在队列清空、且本轮运行的任务全部成功时执行一次，随后恢复为 `none`。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::VecDeque,
+    io,
+    sync::{Arc, Mutex, OnceLock},
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::{
+    i18n::tr,
+    jobs::{self, Job, JobEvent, JobRequest, JobState},
+    power::{self, PowerAction},
+    ProgressPayload,
+};
+
+/// 等待重试期间检查条目是否已被移出的间隔。
+const RETRY_POLL: Duration = Duration::from_millis(100);
+
+/// 队列设置。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueueSettings {
+    /// 每个任务最多运行的次数（含第一次），为 1 时不重试。
+    pub(crate) max_attempts: u32,
+    /// 第一次重试前的等待时间（毫秒），之后每次加倍。
+    pub(crate) retry_delay_ms: u64,
+    /// 重试等待时间的上限（毫秒）。
+    pub(crate) max_retry_delay_ms: u64,
+    /// 队列全部成功完成后的电源操作，执行后恢复为 `none`。
+    pub(crate) power_action: PowerAction,
+}
+
+impl Default for QueueSettings {
+    fn default() -> Self {
+        QueueSettings {
+            max_attempts: 3,
+            retry_delay_ms: 2_000,
+            max_retry_delay_ms: 60_000,
+            power_action: PowerAction::None,
+        }
+    }
+}
+
+impl QueueSettings {
+    /// 第 `attempt` 次运行失败后的等待时间。
+    fn retry_delay(&self, attempt: u32) -> Duration {
+        let factor = 1u64 << attempt.saturating_sub(1).min(32);
+        Duration::from_millis(
+            self.retry_delay_ms
+                .saturating_mul(factor)
+                .min(self.max_retry_delay_ms),
+        )
+    }
+}
+
+/// `set_queue_settings` 的参数，未提供的字段保持不变。
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueueSettingsOptions {
+    max_attempts: Option<u32>,
+    retry_delay_ms: Option<u64>,
+    max_retry_delay_ms: Option<u64>,
+    /// `none`、`shutdown`、`sleep` 或 `hibernate`。
+    power_action: Option<String>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) enum QueueItemState {
+    Queued,
+    Running,
+    /// 上一次运行因临时性错误失败，等待重试。
+    Retrying,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct QueueItem {
+    pub(crate) id: String,
+    pub(crate) kind: String,
+    /// 输入路径，供界面显示。
+    pub(crate) label: String,
+    pub(crate) state: QueueItemState,
+    /// 开始运行后的任务 ID，可用于 `cancel_job`。
+    pub(crate) job_id: Option<String>,
+    pub(crate) progress: Option<ProgressPayload>,
+    pub(crate) enqueued_at: u64,
+    /// 已开始运行的次数。
+    pub(crate) attempt: u32,
+    /// 等待重试时为上一次失败的原因。
+    pub(crate) last_error: Option<String>,
+    /// 等待重试时为下一次运行的时间（Unix 毫秒）。
+    pub(crate) retry_at: Option<u64>,
+}
+
+struct QueueEntry {
+    id: String,
+    kind: &'static str,
+    label: String,
+    enqueued_at: u64,
+    app: Option<AppHandle>,
+    /// 每次运行时复制一份。
+    request: JobRequest,
+    /// 当前运行中的任务。
+    job: Option<Arc<Job>>,
+    attempt: u32,
+    last_error: Option<String>,
+    retry_at: Option<u64>,
+}
+
+impl QueueEntry {
+    fn item(&self) -> QueueItem {
+        QueueItem {
+            id: self.id.clone(),
+            kind: self.kind.to_string(),
+            label: self.label.clone(),
+            state: if self.job.is_some() {
+                QueueItemState::Running
+            } else if self.retry_at.is_some() {
+                QueueItemState::Retrying
+            } else {
+                QueueItemState::Queued
+            },
+            job_id: self.job.as_ref().map(|job| job.id().to_string()),
+            progress: self.job.as_ref().and_then(|job| job.snapshot().progress),
+            enqueued_at: self.enqueued_at,
+            attempt: self.attempt,
+            last_error: self.last_error.clone(),
+            retry_at: self.retry_at,
+        }
+    }
+}
+
+#[derive(Default)]
+struct Queue {
+    entries: VecDeque<QueueEntry>,
+    /// 工作线程是否在运行；队列清空后线程退出，下次加入时再启动。
+    worker: bool,
+    settings: QueueSettings,
+    /// 工作线程本轮启动以来是否有任务成功、是否有任务最终失败或被取消。
+    any_succeeded: bool,
+    any_failed: bool,
+}
+
+fn queue() -> &'static Mutex<Queue> {
+    static QUEUE: OnceLock<Mutex<Queue>> = OnceLock::new();
+    QUEUE.get_or_init(|| Mutex::new(Queue::default()))
+}
+
+fn lock() -> std::sync::MutexGuard<'static, Queue> {
+    queue().lock().unwrap_or_else(|e| e.into_inner())
+}
+
+/// 解析参数后加入队列末尾；`kind` 与 `options` 同 [`jobs::submit`]。
+pub(crate) fn enqueue(app: Option<AppHandle>, kind: &str, options: Value) -> Result<QueueItem, String> {
+    let label = options
+        .get("inputPath")
+        .and_then(Value::as_str)
+        .unwrap_or_default()
+        .to_string();
+    let request = JobRequest::parse(kind, options)?;
+    let entry = QueueEntry {
+        id: jobs::next_job_id(),
+        kind: request.kind(),
+        label,
+        enqueued_at: jobs::now_millis(),
+        app: app.clone(),
+        request,
+        job: None,
+        attempt: 0,
+        last_error: None,
+        retry_at: None,
+    };
+    let item = entry.item();
+    let start_worker = {
+        let mut queue = lock();
+        queue.entries.push_back(entry);
+        let start_worker = !std::mem::replace(&mut queue.worker, true);
+        if start_worker {
+            queue.any_succeeded = false;
+            queue.any_failed = false;
+        }
+        start_worker
+    };
+    log::info!("任务加入队列：{} {}（{}）", item.kind, item.label, item.id);
+    if start_worker {
+        thread::spawn(run_worker);
+    }
+    emit_changed(app.as_ref());
+    Ok(item)
+}
+
+/// 当前队列，运行中的在前。
+pub(crate) fn list() -> Vec<QueueItem> {
+    lock().entries.iter().map(QueueEntry::item).collect()
+}
+
+pub(crate) fn settings() -> QueueSettings {
+    lock().settings.clone()
+}
+
+/// 更新队列设置，对之后的重试生效。
+pub(crate) fn update_settings(options: QueueSettingsOptions) -> Result<QueueSettings, String> {
+    let mut queue = lock();
+    let mut settings = queue.settings.clone();
+    if let Some(value) = options.max_attempts {
+        if value == 0 {
+            return Err(tr!(MaxAttemptsZero));
+        }
+        settings.max_attempts = value;
+    }
+    if let Some(value) = options.retry_delay_ms {
+        settings.retry_delay_ms = value;
+    }
+    if let Some(value) = options.max_retry_delay_ms {
+        settings.max_retry_delay_ms = value;
+    }
+    if let Some(value) = options.power_action.as_deref() {
+        settings.power_action =
+            PowerAction::parse(value).ok_or_else(|| tr!(UnknownPowerAction, value))?;
+    }
+    queue.settings = settings.clone();
+    Ok(settings)
+}
+
+/// 移出尚未开始或等待重试的任务，返回剩余队列。
+pub(crate) fn remove(id: &str) -> Result<Vec<QueueItem>, String> {
+    let (app, items) = {
+        let mut queue = lock();
+        let pos = queue
+            .entries
+            .iter()
+            .position(|entry| entry.id == id)
+            .ok_or_else(|| tr!(QueueItemNotFound))?;
+        if queue.entries[pos].job.is_some() {
+            return Err(tr!(QueueItemRunning));
+        }
+        let entry = queue.entries.remove(pos);
+        let app = entry.and_then(|entry| entry.app);
+        (app, queue.entries.iter().map(QueueEntry::item).collect())
+    };
+    emit_changed(app.as_ref());
+    Ok(items)
+}
+
+/// 移出所有尚未开始或等待重试的任务，返回被移出的 ID。运行中的任务不受影响。
+pub(crate) fn clear() -> Vec<String> {
+    let removed: VecDeque<QueueEntry> = {
+        let mut queue = lock();
+        let (running, queued) = queue.entries.drain(..).partition(|entry| entry.job.is_some());
+        queue.entries = running;
+        queued
+    };
+    if let Some(entry) = removed.front() {
+        emit_changed(entry.app.as_ref());
+    }
+    removed.into_iter().map(|entry| entry.id).collect()
+}
+
+/// 依次运行队首的任务，直到队列为空。
+fn run_worker() {
+    loop {
+        let (id, job, app, attempt) = {
+            let mut queue = lock();
+            let Some(entry) = queue.entries.front_mut() else {
+                queue.worker = false;
+                let action = if queue.any_succeeded && !queue.any_failed {
+                    std::mem::take(&mut queue.settings.power_action)
+                } else {
+                    PowerAction::None
+                };
+                drop(queue);
+                if let Err(err) = power::perform(action) {
+                    log::error!("{}", err);
+                }
+                return;
+            };
+            entry.attempt += 1;
+            entry.retry_at = None;
+            let job = entry.request.clone().spawn(entry.app.clone());
+            entry.job = Some(job.clone());
+            (entry.id.clone(), job, entry.app.clone(), entry.attempt)
+        };
+        emit_changed(app.as_ref());
+
+        let mut outcome = None;
+        for event in job.subscribe() {
+            match event {
+                JobEvent::Progress(progress) => {
+                    if let Some(app) = &app {
+                        let _ = app.emit(
+                            "queue-progress",
+                            json!({ "queueId": id, "jobId": job.id(), "progress": progress }),
+                        );
+                    }
+                }
+                JobEvent::Finished(snapshot) => {
+                    log::info!("队列任务结束：{}（{:?}）", id, snapshot.state);
+                    outcome = Some(snapshot);
+                    break;
+                }
+            }
+        }
+
+        let settings = settings();
+        let succeeded = outcome
+            .as_ref()
+            .is_some_and(|snapshot| snapshot.state == JobState::Succeeded);
+        let retry_error = outcome
+            .filter(|snapshot| snapshot.state == JobState::Failed)
+            .and_then(|snapshot| snapshot.error)
+            .filter(|error| attempt < settings.max_attempts && is_transient(error));
+        let Some(error) = retry_error else {
+            {
+                let mut queue = lock();
+                queue.entries.retain(|entry| entry.id != id);
+                if succeeded {
+                    queue.any_succeeded = true;
+                } else {
+                    queue.any_failed = true;
+                }
+            }
+            emit_changed(app.as_ref());
+            continue;
+        };
+
+        let delay = settings.retry_delay(attempt);
+        log::warn!(
+            "队列任务因临时性错误失败，{} 毫秒后第 {} 次运行：{}（{}）",
+            delay.as_millis(),
+            attempt + 1,
+            id,
+            error
+        );
+        if let Some(entry) = lock().entries.iter_mut().find(|entry| entry.id == id) {
+            entry.job = None;
+            entry.last_error = Some(error);
+            entry.retry_at = Some(jobs::now_millis() + delay.as_millis() as u64);
+        }
+        emit_changed(app.as_ref());
+        // 等待期间条目被移出时直接运行下一个任务
+        let deadline = Instant::now() + delay;
+        while Instant::now() < deadline && lock().entries.iter().any(|entry| entry.id == id) {
+            thread::sleep(RETRY_POLL.min(deadline.saturating_duration_since(Instant::now())));
+        }
+    }
+}
+
+/// 错误信息中的系统错误码（`(os error N)`）属于可能自行恢复的类型时返回 `true`。
+fn is_transient(error: &str) -> bool {
+    let Some(start) = error.rfind("(os error ") else {
+        return false;
+    };
+    let digits = &error[start + "(os error ".len()..];
+    let Some(code) = digits
+        .split(')')
+        .next()
+        .and_then(|value| value.trim().parse::<i32>().ok())
+    else {
+        return false;
+    };
+    // Windows 的共享冲突与锁定冲突：文件被其他程序暂时占用
+    if cfg!(windows) && matches!(code, 32 | 33) {
+        return true;
+    }
+    matches!(
+        io::Error::from_raw_os_error(code).kind(),
+        io::ErrorKind::Interrupted
+            | io::ErrorKind::WouldBlock
+            | io::ErrorKind::TimedOut
+            | io::ErrorKind::ResourceBusy
+            | io::ErrorKind::ConnectionReset
+            | io::ErrorKind::ConnectionAborted
+            | io::ErrorKind::NetworkDown
+            | io::ErrorKind::NetworkUnreachable
+            | io::ErrorKind::HostUnreachable
+            | io::ErrorKind::StaleNetworkFileHandle
+    )
+}
+
+fn emit_changed(app: Option<&AppHandle>) {
+    if let Some(app) = app {
+        let _ = app.emit("queue-changed", list());
+    }
+}
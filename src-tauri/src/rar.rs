@@ -0,0 +1,311 @@
+//! RAR 的只读解压：合并得到的数据是 RAR 时（例如切分的原文件就是 `.rar`），
+//! 或选择的是其他软件生成的分卷 RAR（`名称.part1.rar`……，或旧式的 `名称.rar`、`名称.r00`……）时，
+//! 通过 UnRAR 解压，不支持生成 RAR。
+//!
+//! 分卷 RAR 无法在不重新打包的情况下合并为单个文件，还原时总是直接解压，由 UnRAR 依次读取各卷。
+//! 未启用 `rar` 特性构建时仍能识别 RAR，但解压会提示当前版本不支持。
+
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{extract, i18n::tr, jobs::JobContext, password_prompt::PasswordError, RestoreResult};
+
+/// RAR 1.5–4.x 的签名。
+const RAR4_SIGNATURE: [u8; 7] = *b"Rar!\x1a\x07\x00";
+/// RAR 5.0 的签名。
+const RAR5_SIGNATURE: [u8; 8] = *b"Rar!\x1a\x07\x01\x00";
+
+/// 按文件开头的签名判断是否为 RAR（不看扩展名）。
+pub(crate) fn is_rar_file(path: &Path) -> Result<bool, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut signature = [0u8; 8];
+    let mut read_len = 0;
+    while read_len < signature.len() {
+        let len = file
+            .read(&mut signature[read_len..])
+            .map_err(|e| e.to_string())?;
+        if len == 0 {
+            break;
+        }
+        read_len += len;
+    }
+    Ok(signature[..read_len].starts_with(&RAR4_SIGNATURE)
+        || signature[..read_len].starts_with(&RAR5_SIGNATURE))
+}
+
+/// 所选的文件或目录是否为一组分卷 RAR（至少两卷）。
+pub(crate) fn is_volume_set(input: &Path) -> bool {
+    matches!(first_volume(input), Ok(Some(_)))
+}
+
+/// 直接解压分卷 RAR，解压目录为第一卷去掉分卷后缀后的名称；`merged_file` 为空。
+pub(crate) fn restore(
+    ctx: &JobContext,
+    input: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<RestoreResult, String> {
+    let first = first_volume(input)?.ok_or_else(|| tr!(RarVolumesNotFound))?;
+    let name = first
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| tr!(RarVolumesNotFound))?;
+    let (stem, _) = volume_of(name).ok_or_else(|| tr!(RarVolumesNotFound))?;
+    let target_dir = output_dir.join(stem);
+    extract::extract_archive(ctx, &first, &target_dir, password, None)?;
+
+    let extracted = target_dir.to_string_lossy().to_string();
+    Ok(RestoreResult {
+        merged_file: None,
+        extracted_dir: Some(extracted.clone()),
+        output_files: vec![extracted],
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: Some(extract::ArchiveKind::Rar.name().to_string()),
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
+
+/// 分卷的命名方式。
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum VolumeNaming {
+    /// `名称.part1.rar`、`名称.part2.rar`……，序号位数与第一卷相同。
+    Parts { width: usize },
+    /// `名称.rar`、`名称.r00`、`名称.r01`……
+    Legacy,
+}
+
+/// 从文件名中分出名称与分卷命名方式，大小写不敏感。
+fn volume_of(name: &str) -> Option<(&str, VolumeNaming)> {
+    let lower = name.to_ascii_lowercase();
+    if let Some(rest) = lower.strip_suffix(".rar") {
+        let Some(dot) = rest.rfind(".part") else {
+            return Some((&name[..rest.len()], VolumeNaming::Legacy));
+        };
+        let digits = &rest[dot + ".part".len()..];
+        if dot > 0 && !digits.is_empty() && digits.bytes().all(|byte| byte.is_ascii_digit()) {
+            let width = digits.len();
+            return Some((&name[..dot], VolumeNaming::Parts { width }));
+        }
+        return Some((&name[..rest.len()], VolumeNaming::Legacy));
+    }
+    let dot = lower.rfind('.')?;
+    let suffix = &lower.as_bytes()[dot + 1..];
+    let legacy = suffix.len() == 3
+        && suffix[0] == b'r'
+        && suffix[1..].iter().all(|byte| byte.is_ascii_digit());
+    (dot > 0 && legacy).then(|| (&name[..dot], VolumeNaming::Legacy))
+}
+
+/// 在 `dir` 中按不区分大小写的文件名查找。
+fn find_in(dir: &Path, name: &str) -> Option<PathBuf> {
+    let exact = dir.join(name);
+    if exact.is_file() {
+        return Some(exact);
+    }
+    fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .find(|path| {
+            path.is_file()
+                && path
+                    .file_name()
+                    .and_then(|value| value.to_str())
+                    .is_some_and(|value| value.eq_ignore_ascii_case(name))
+        })
+}
+
+/// 找到分卷 RAR 的第一卷；只有一卷（普通 RAR）或不是 RAR 时返回 `None`。
+fn first_volume(input: &Path) -> Result<Option<PathBuf>, String> {
+    if input.is_dir() {
+        let mut firsts: Vec<PathBuf> = Vec::new();
+        for entry in fs::read_dir(input).map_err(|e| e.to_string())?.flatten() {
+            if let Some(first) = first_volume_of(&entry.path()) {
+                if !firsts.contains(&first) {
+                    firsts.push(first);
+                }
+            }
+        }
+        return match firsts.len() {
+            0 => Ok(None),
+            1 => Ok(firsts.pop()),
+            _ => Err(tr!(RarVolumesMultiple)),
+        };
+    }
+    Ok(first_volume_of(input))
+}
+
+fn first_volume_of(path: &Path) -> Option<PathBuf> {
+    if !path.is_file() {
+        return None;
+    }
+    let dir = path.parent()?;
+    let name = path.file_name()?.to_str()?;
+    let (stem, naming) = volume_of(name)?;
+    let (first, second) = match naming {
+        VolumeNaming::Parts { width } => (
+            format!("{}.part{:0width$}.rar", stem, 1, width = width),
+            format!("{}.part{:0width$}.rar", stem, 2, width = width),
+        ),
+        VolumeNaming::Legacy => (format!("{}.rar", stem), format!("{}.r00", stem)),
+    };
+    find_in(dir, &second)?;
+    let first = find_in(dir, &first)?;
+    is_rar_file(&first).ok()?.then_some(first)
+}
+
+/// 解压 RAR 到 `output_dir`（目录已由调用方创建）。分卷 RAR 需传入第一卷，其余各卷由 UnRAR 依次打开。
+///
+/// 只加密了条目数据的 RAR 要解压到加密的条目才能发现密码不对，此时删掉本次新建的文件与目录后
+/// 返回 [`PasswordError`]，以新密码重试时不会在解压了一半的目录上再解压一遍。
+#[cfg(feature = "rar")]
+pub(crate) fn extract(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<(), PasswordError> {
+    let mut created = Vec::new();
+    let result = extract_entries(ctx, archive_path, output_dir, password, &mut created);
+    if matches!(result, Err(PasswordError::Wrong | PasswordError::Required)) {
+        for path in created.iter().rev() {
+            let removed = if path.is_dir() {
+                fs::remove_dir(path)
+            } else {
+                fs::remove_file(path)
+            };
+            if let Err(err) = removed {
+                log::debug!("无法删除解压了一半的 {}：{}", path.display(), err);
+            }
+        }
+    }
+    result
+}
+
+#[cfg(feature = "rar")]
+fn extract_entries(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    created: &mut Vec<PathBuf>,
+) -> Result<(), PasswordError> {
+    use crate::{emit_progress, entry_names};
+    use unrar::{error::Code, Archive};
+
+    let open = || match password {
+        Some(password) => Archive::with_password(archive_path, password),
+        None => Archive::new(archive_path),
+    };
+
+    // 先列出条目得到总大小，跨卷的条目只列一次
+    let mut total_bytes = 0u64;
+    let mut total_entries = 0usize;
+    for header in open().open_for_listing().map_err(map_rar_error)? {
+        let header = header.map_err(map_rar_error)?;
+        if header.is_file() {
+            total_bytes += header.unpacked_size;
+        }
+        total_entries += 1;
+    }
+
+    let mut archive = open().open_for_processing().map_err(map_rar_error)?;
+    let mut processed = 0u64;
+    let mut index = 0usize;
+    while let Some(entry) = archive.read_header().map_err(map_rar_error)? {
+        ctx.check_cancelled()?;
+        index += 1;
+        let header = entry.entry();
+        let Some(name) = entry_names::enclosed(&header.filename.to_string_lossy())
+            .filter(|name| !name.as_os_str().is_empty())
+        else {
+            log::warn!("跳过不安全的 RAR 条目路径：{}", header.filename.display());
+            archive = entry.skip().map_err(map_rar_error)?;
+            continue;
+        };
+        let out_path = output_dir.join(name);
+        if header.is_directory() {
+            create_dirs(&out_path, created)?;
+            archive = entry.skip().map_err(map_rar_error)?;
+            continue;
+        }
+        if let Some(parent) = out_path.parent() {
+            create_dirs(parent, created)?;
+        }
+
+        emit_progress(
+            ctx,
+            "unzip",
+            processed,
+            total_bytes,
+            index,
+            total_entries,
+            tr!(Extracting),
+        );
+        let size = header.unpacked_size;
+        let encrypted = header.is_encrypted();
+        let entry_name = header.filename.to_string_lossy().to_string();
+        if !out_path.exists() {
+            created.push(out_path.clone());
+        }
+        archive = entry.extract_to(&out_path).map_err(|err| match err.code {
+            Code::MissingPassword => PasswordError::Required,
+            Code::BadPassword => PasswordError::Wrong,
+            // RAR 4 的条目用错误的密码解出时只会报告 CRC 错误
+            Code::BadData if encrypted && password.is_some() => PasswordError::Wrong,
+            Code::EOpen => PasswordError::Other(tr!(RarVolumeMissing)),
+            _ => PasswordError::Other(tr!(EntryCorrupt, entry_name, err)),
+        })?;
+        processed += size;
+        emit_progress(
+            ctx,
+            "unzip",
+            processed,
+            total_bytes,
+            index,
+            total_entries,
+            tr!(Extracting),
+        );
+    }
+    Ok(())
+}
+
+/// 创建目录及缺少的上级目录，新建的目录由外向内记入 `created`。
+#[cfg(feature = "rar")]
+fn create_dirs(path: &Path, created: &mut Vec<PathBuf>) -> Result<(), String> {
+    let missing: Vec<&Path> = path.ancestors().take_while(|dir| !dir.exists()).collect();
+    fs::create_dir_all(path).map_err(|e| e.to_string())?;
+    created.extend(missing.into_iter().rev().map(Path::to_path_buf));
+    Ok(())
+}
+
+#[cfg(not(feature = "rar"))]
+pub(crate) fn extract(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<(), PasswordError> {
+    let _ = (ctx, archive_path, output_dir, password);
+    Err(PasswordError::Other(tr!(RarUnavailable)))
+}
+
+#[cfg(feature = "rar")]
+fn map_rar_error(err: unrar::error::UnrarError) -> PasswordError {
+    use unrar::error::{Code, When};
+    match (err.code, err.when) {
+        (Code::MissingPassword, _) => PasswordError::Required,
+        (Code::BadPassword, _) => PasswordError::Wrong,
+        (Code::EOpen, When::Process) => PasswordError::Other(tr!(RarVolumeMissing)),
+        _ => PasswordError::Other(tr!(RarInvalid, err)),
+    }
+}
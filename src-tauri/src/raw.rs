@@ -0,0 +1,263 @@
+//! 直接拼接（`raw`）合并：把分片按顺序拼接回原文件，不解压，等同于 `cat` 或 `copy /b`。
+//!
+//! 除 `raw-split` 写出的 `<名称>.part-001` 外，也识别 split(1) 的分片命名，此时需选择第一份分片：
+//! 字母后缀 `xaa`、`xab`……（包括 GNU split 在 `yz` 之后自动加宽的 `zaaa`），
+//! 数字后缀 `x00`、`x01`……（`-d`，或 `--numeric-suffixes=1` 时从 `x01` 开始）。
+//!
+//! 分片目录中有完整性清单时由合并流程按清单核对；没有清单时若分片旁有 `sha256sum` 格式的
+//! 校验文件（`<名称>.sha256`、`<名称>.sha256sum` 或 `SHA256SUMS`），用它核对合并结果。
+
+use std::{
+    fs::{self, File},
+    io::Read,
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    collect_part_group, emit_progress, gaps,
+    i18n::tr,
+    integrity::{HashAlgorithm, Hasher},
+    jobs::JobContext,
+    merge_raw_parts, PartGroup, PartInfo, RestoreResult,
+};
+
+/// 找出分片组：先按本程序的分片命名，不认识时再按 split(1) 的命名从所选的第一份往后找。
+pub(crate) fn collect_raw_group(input_path: &Path) -> Result<PartGroup, String> {
+    match collect_part_group(input_path) {
+        Ok(group) => Ok(group),
+        Err(_) if input_path.is_file() => {
+            split_group(input_path).ok_or_else(|| tr!(RawFirstPartRequired))
+        }
+        Err(err) => Err(err),
+    }
+}
+
+pub(crate) fn restore_raw(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+) -> Result<RestoreResult, String> {
+    let part_group = gaps::collect_group(ctx, input_path, collect_raw_group)?;
+    let merged_name = part_group.prefix.trim_end_matches('.').to_string();
+    let temp_path = output_dir.join(format!("{}.merge.tmp", merged_name));
+    merge_raw_parts(ctx, &part_group, &temp_path)?;
+
+    let merged_path = output_dir.join(&merged_name);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let merged_file = merged_path.to_string_lossy().to_string();
+    Ok(RestoreResult {
+        merged_file: Some(merged_file.clone()),
+        extracted_dir: None,
+        output_files: vec![merged_file],
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
+
+/// 没有完整性清单时，按分片旁的 SHA-256 校验文件核对合并结果；找不到对应的记录时什么也不做。
+pub(crate) fn verify_checksum_file(
+    ctx: &JobContext,
+    input_path: &Path,
+    result: &mut RestoreResult,
+) -> Result<(), String> {
+    let Some(merged_file) = result.merged_file.clone() else {
+        return Ok(());
+    };
+    let merged_path = PathBuf::from(&merged_file);
+    let parts_dir = if input_path.is_dir() {
+        input_path
+    } else {
+        match input_path.parent() {
+            Some(dir) => dir,
+            None => return Ok(()),
+        }
+    };
+    let Some(name) = merged_path.file_name().and_then(|name| name.to_str()) else {
+        return Ok(());
+    };
+    let Some((sums_path, expected)) = find_checksum(parts_dir, name) else {
+        return Ok(());
+    };
+
+    let mut file = File::open(&merged_path).map_err(|e| e.to_string())?;
+    let total = file.metadata().map_err(|e| e.to_string())?.len();
+    let mut hasher = Hasher::new(ctx, HashAlgorithm::Sha256);
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut processed = 0u64;
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = file.read(&mut buffer).map_err(|e| e.to_string())?;
+        if read_len == 0 {
+            break;
+        }
+        hasher.update(&buffer[..read_len]);
+        processed += read_len as u64;
+        emit_progress(ctx, "verify", processed, total, 0, 0, tr!(VerifyingMerged));
+    }
+
+    let matched = hasher.finish() == expected;
+    if matched {
+        log::info!("合并结果与校验文件 {} 一致", sums_path.display());
+        result.mismatches.clear();
+    } else {
+        log::warn!(
+            "合并结果与校验文件 {} 不一致：{}",
+            sums_path.display(),
+            merged_file
+        );
+        result.mismatches = vec![merged_file];
+    }
+    result.verified = Some(matched);
+    Ok(())
+}
+
+/// 在 `dir` 中查找记录了 `name` 的 SHA-256 校验文件，返回文件路径与小写的十六进制校验值。
+fn find_checksum(dir: &Path, name: &str) -> Option<(PathBuf, String)> {
+    let candidates = [
+        (format!("{}.sha256", name), true),
+        (format!("{}.sha256sum", name), true),
+        ("SHA256SUMS".to_string(), false),
+    ];
+    for (file_name, dedicated) in candidates {
+        let path = dir.join(file_name);
+        let Ok(text) = fs::read_to_string(&path) else {
+            continue;
+        };
+        for line in text.lines() {
+            // `<校验值>  <文件名>`，二进制模式在文件名前加 `*`；文件名中可以有空格
+            let Some((hash, rest)) = line.trim_end().split_at_checked(64) else {
+                continue;
+            };
+            if !is_sha256(hash) || !(rest.is_empty() || rest.starts_with(' ')) {
+                continue;
+            }
+            let listed = rest.trim_start().trim_start_matches('*');
+            let listed_name =
+                (!listed.is_empty()).then(|| listed.rsplit(['/', '\\']).next().unwrap_or(listed));
+            // 专用的校验文件可以只写校验值
+            if listed_name == Some(name) || (dedicated && listed_name.is_none()) {
+                return Some((path, hash.to_ascii_lowercase()));
+            }
+        }
+    }
+    None
+}
+
+fn is_sha256(value: &str) -> bool {
+    value.len() == 64 && value.bytes().all(|byte| byte.is_ascii_hexdigit())
+}
+
+/// split(1) 后缀的字符集。
+#[derive(Clone, Copy)]
+enum SuffixAlphabet {
+    Letters,
+    Digits,
+}
+
+impl SuffixAlphabet {
+    fn first(self) -> u8 {
+        match self {
+            SuffixAlphabet::Letters => b'a',
+            SuffixAlphabet::Digits => b'0',
+        }
+    }
+
+    fn last(self) -> u8 {
+        match self {
+            SuffixAlphabet::Letters => b'z',
+            SuffixAlphabet::Digits => b'9',
+        }
+    }
+}
+
+/// 从第一份分片的文件名中分出前缀与后缀：后缀为至少两个 `a`，或至少两位的 `00…0`、`00…1`。
+fn first_suffix(name: &str) -> Option<(&str, SuffixAlphabet)> {
+    let bytes = name.as_bytes();
+    let run = |byte: u8, end: usize| {
+        bytes[..end]
+            .iter()
+            .rev()
+            .take_while(|&&value| value == byte)
+            .count()
+    };
+    let (len, alphabet) = match bytes.last()? {
+        b'a' => (run(b'a', bytes.len()), SuffixAlphabet::Letters),
+        b'0' => (run(b'0', bytes.len()), SuffixAlphabet::Digits),
+        b'1' => (run(b'0', bytes.len() - 1) + 1, SuffixAlphabet::Digits),
+        _ => return None,
+    };
+    if len < 2 || len >= bytes.len() {
+        return None;
+    }
+    Some((&name[..name.len() - len], alphabet))
+}
+
+/// 把后缀加一，溢出时返回 `None`。
+fn increment(counter: &mut [u8], alphabet: SuffixAlphabet) -> Option<()> {
+    for digit in counter.iter_mut().rev() {
+        if *digit == alphabet.last() {
+            *digit = alphabet.first();
+        } else {
+            *digit += 1;
+            return Some(());
+        }
+    }
+    None
+}
+
+/// 从所选的第一份往后逐个找 split(1) 的分片，遇到第一个不存在的文件名为止。
+fn split_group(first: &Path) -> Option<PartGroup> {
+    let name = first.file_name()?.to_str()?;
+    let dir = first.parent()?;
+    let (prefix, alphabet) = first_suffix(name)?;
+    // GNU split 未指定后缀长度时，后缀首位到达末字符后固定下来，其余部分加宽一位
+    let mut fixed = String::new();
+    let mut counter = name.as_bytes()[prefix.len()..].to_vec();
+    let mut parts = Vec::new();
+    loop {
+        let path = dir.join(format!(
+            "{}{}{}",
+            prefix,
+            fixed,
+            String::from_utf8_lossy(&counter)
+        ));
+        if !path.is_file() {
+            break;
+        }
+        parts.push(PartInfo {
+            index: parts.len() + 1,
+            path,
+        });
+        if increment(&mut counter, alphabet).is_none() {
+            break;
+        }
+        if counter[0] == alphabet.last() {
+            let plain = dir.join(format!(
+                "{}{}{}",
+                prefix,
+                fixed,
+                String::from_utf8_lossy(&counter)
+            ));
+            if !plain.is_file() {
+                fixed.push(alphabet.last() as char);
+                counter = vec![alphabet.first(); counter.len() + 1];
+            }
+        }
+    }
+    (!parts.is_empty()).then(|| PartGroup {
+        prefix: prefix.to_string(),
+        parts,
+    })
+}
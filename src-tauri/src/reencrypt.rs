@@ -0,0 +1,312 @@
+//! 更换分片密码：不合并，逐份解密再以新密码加密，省去合并后重新打包。支持每份分片各自加密的方式：
+//! - `split-then-zip`：每份分片 zip 中的条目以新密码重新加密（AES 强度默认 256 位），新密码留空时
+//!   去掉加密，原本不加密的分片也可以加上密码；
+//! - `age-split`：以新口令重新加密（只支持以口令加密的分片）；
+//! - 逐份独立加密的 `container-then-split`（`perPartKeys`）：以新密码和新的盐重新派生密钥。
+//!
+//! 先整体加密再切分的方式（`zip-then-split`、`7z-then-split`、整个容器切分的 `container-then-split`）
+//! 分片只是密文的片段，无法逐份更换。
+//!
+//! 全部分片先写到 `<分片>.reencrypt.tmp`，都成功后才替换原分片，中途出错或取消不会留下新旧密码混杂的
+//! 分片组。完成后按新分片更新完整性清单（原有签名随之失效）；恢复分片、异或校验分片与 PAR2 文件按旧
+//! 分片计算，需要重新生成。进度阶段为 `reencrypt`。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    ffi::OsString,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Path, PathBuf},
+};
+use zip::{ZipArchive, ZipWriter};
+
+use crate::{
+    age_split::{self, Seal, Unseal},
+    append, build_file_options, collect_part_group, container, copy_n_buffered, detect,
+    emit_progress,
+    entry_times::EntryTimes,
+    i18n::tr,
+    integrity::{self, Integrity},
+    jobs::JobContext,
+    keyfile, open_zip_file,
+    secrets::SecretString,
+    PartSha256,
+};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReencryptOptions {
+    /// 分片文件或分片目录。
+    pub(crate) input_path: String,
+    /// 分片的打包方式：`split-then-zip`、`age-split` 或 `container-then-split`；
+    /// 未设置时按完整性清单中记录的打包方式。
+    pub(crate) merge_mode: Option<String>,
+    /// 原密码；原分片不加密时留空。
+    pub(crate) old_password: Option<SecretString>,
+    pub(crate) old_keyfile: Option<String>,
+    /// 新密码；与 `new_keyfile` 都留空时去掉加密（仅 `split-then-zip`）。
+    pub(crate) new_password: Option<SecretString>,
+    pub(crate) new_keyfile: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ReencryptReport {
+    pub(crate) parts_dir: String,
+    pub(crate) output_files: Vec<String>,
+    /// 新分片是否加密。
+    pub(crate) encrypted: bool,
+    /// 按新分片更新后的校验值；没有完整性清单时为空。
+    pub(crate) part_sha256s: Vec<PartSha256>,
+}
+
+pub(crate) fn reencrypt_parts_blocking(
+    ctx: &JobContext,
+    options: ReencryptOptions,
+) -> Result<ReencryptReport, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let old_password = keyfile::effective_password(
+        options.old_password.as_deref(),
+        options.old_keyfile.as_deref(),
+    )?;
+    let new_password = keyfile::effective_password(
+        options.new_password.as_deref(),
+        options.new_keyfile.as_deref(),
+    )?;
+    let manifest = integrity::find(&input_path)?;
+    let merge_mode = pack_mode(options.merge_mode.as_deref(), &input_path, &manifest)?;
+
+    let part_group = collect_part_group(&input_path)?;
+    let paths: Vec<PathBuf> = part_group
+        .parts
+        .iter()
+        .map(|part| part.path.clone())
+        .collect();
+    let parts_dir = paths
+        .first()
+        .and_then(|path| path.parent())
+        .map(Path::to_path_buf)
+        .ok_or_else(|| tr!(NoPartsFound))?;
+    let total_bytes: u64 = paths
+        .iter()
+        .filter_map(|path| fs::metadata(path).ok())
+        .map(|meta| meta.len())
+        .sum();
+
+    let mut part = match merge_mode.as_str() {
+        "split-then-zip" => Reencrypt::Zip,
+        "age-split" => Reencrypt::Age(
+            Unseal::Passphrase(old_password.clone().ok_or_else(|| tr!(AgeSplitNeedsKey))?),
+            Seal::Passphrase(
+                new_password
+                    .clone()
+                    .ok_or_else(|| tr!(ReencryptNeedsNewPassword))?,
+            ),
+        ),
+        "container-then-split" => Reencrypt::Container(container::Resealer::new(
+            old_password
+                .as_deref()
+                .ok_or_else(|| tr!(ContainerNeedsPassword))?,
+            new_password
+                .as_deref()
+                .ok_or_else(|| tr!(ReencryptNeedsNewPassword))?,
+            paths.len(),
+        )?),
+        _ => return Err(tr!(ReencryptUnsupportedMode, merge_mode)),
+    };
+
+    // 先全部写到临时文件，都成功后再替换，避免留下新旧密码混杂的分片组
+    let temps: Vec<PathBuf> = paths.iter().map(|path| temp_path(path)).collect();
+    let mut processed = 0u64;
+    for (idx, (path, temp)) in paths.iter().zip(&temps).enumerate() {
+        ctx.check_cancelled()?;
+        emit_progress(
+            ctx,
+            "reencrypt",
+            processed,
+            total_bytes,
+            idx + 1,
+            paths.len(),
+            tr!(ReencryptingPart, idx + 1),
+        );
+        ctx.track_output(temp);
+        // 按明文字节数汇报进度，与分片大小略有出入
+        let mut on_progress = |delta: u64| {
+            processed = (processed + delta).min(total_bytes);
+            emit_progress(
+                ctx,
+                "reencrypt",
+                processed,
+                total_bytes,
+                idx + 1,
+                paths.len(),
+                tr!(ReencryptingPart, idx + 1),
+            );
+        };
+        match &mut part {
+            Reencrypt::Zip => reencrypt_zip_part(
+                ctx,
+                path,
+                temp,
+                old_password.as_deref(),
+                new_password.as_deref(),
+                on_progress,
+            ),
+            Reencrypt::Age(unseal, seal) => {
+                reencrypt_age_part(ctx, path, temp, unseal, seal, on_progress)
+            }
+            Reencrypt::Container(resealer) => {
+                resealer.reseal(ctx, idx + 1, path, temp, &mut on_progress)
+            }
+        }?;
+    }
+    for (path, temp) in paths.iter().zip(&temps) {
+        fs::rename(temp, path).map_err(|e| e.to_string())?;
+    }
+
+    let output_files: Vec<String> = paths
+        .iter()
+        .map(|path| path.to_string_lossy().to_string())
+        .collect();
+    let part_sha256s = match &manifest {
+        Some((_, integrity)) => integrity::record_parts(
+            ctx,
+            &parts_dir,
+            &manifest_base_name(integrity),
+            &output_files,
+        )?,
+        None => Vec::new(),
+    };
+    log::info!(
+        "已更换 {} 份分片的密码：{}（{}）",
+        paths.len(),
+        parts_dir.display(),
+        if new_password.is_some() {
+            "新密码"
+        } else {
+            "去掉加密"
+        }
+    );
+    Ok(ReencryptReport {
+        parts_dir: parts_dir.to_string_lossy().to_string(),
+        output_files,
+        encrypted: new_password.is_some(),
+        part_sha256s,
+    })
+}
+
+/// 各打包方式逐份重新加密时需要的状态。
+enum Reencrypt {
+    Zip,
+    Age(Unseal, Seal),
+    Container(container::Resealer),
+}
+
+/// 参数给出的打包方式，未给出或为 `auto` 时取清单中记录的方式；`auto` 且没有清单时按分片识别。
+pub(crate) fn pack_mode(
+    merge_mode: Option<&str>,
+    input_path: &Path,
+    manifest: &Option<(PathBuf, Integrity)>,
+) -> Result<String, String> {
+    match (merge_mode, manifest) {
+        (Some(mode), _) if !mode.is_empty() && mode != detect::AUTO => Ok(mode.to_string()),
+        (_, Some((_, integrity))) => Ok(integrity.pack_mode().to_string()),
+        (Some(detect::AUTO), None) => detect::detect_merge_mode(input_path, false),
+        _ => Err(tr!(ReencryptNeedsMode)),
+    }
+}
+
+/// 清单文件名去掉 `.manifest.json` 后的名称。
+pub(crate) fn manifest_base_name(integrity: &Integrity) -> String {
+    integrity
+        .path()
+        .file_name()
+        .and_then(|name| name.to_str())
+        .and_then(|name| name.strip_suffix(append::MANIFEST_SUFFIX))
+        .unwrap_or_default()
+        .to_string()
+}
+
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    let mut name: OsString = path.file_name().unwrap_or_default().to_os_string();
+    name.push(".reencrypt.tmp");
+    path.with_file_name(name)
+}
+
+/// 以原密码解压分片 zip 中的条目，按原来的名称与压缩方法以新密码写入新的 zip。
+pub(crate) fn reencrypt_zip_part(
+    ctx: &JobContext,
+    source: &Path,
+    target: &Path,
+    old_password: Option<&str>,
+    new_password: Option<&str>,
+    on_progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let file = File::open(source).map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    let mut entry = open_zip_file(&mut archive, 0, old_password)?;
+    let name = entry.name().to_string();
+    let size = entry.size();
+    let options = build_file_options(
+        new_password,
+        ctx.aes_mode(),
+        entry.compression(),
+        None,
+        size,
+        EntryTimes::from_zip(&entry).modified(),
+    );
+    let mut zip = ZipWriter::new(BufWriter::new(
+        File::create(target).map_err(|e| e.to_string())?,
+    ));
+    zip.start_file(name, options).map_err(|e| e.to_string())?;
+    copy_n_buffered(
+        &mut entry,
+        &mut zip,
+        size,
+        ctx.memory().buffer_size(),
+        ctx.cancel_token(),
+        on_progress,
+    )
+    .map_err(|e| e.to_string())?;
+    zip.finish()
+        .map_err(|e| e.to_string())?
+        .flush()
+        .map_err(|e| e.to_string())
+}
+
+/// 以原口令解密 age 分片，再以新口令加密写入 `target`。
+fn reencrypt_age_part(
+    ctx: &JobContext,
+    source: &Path,
+    target: &Path,
+    unseal: &Unseal,
+    seal: &Seal,
+    mut on_progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let mut reader = age_split::open_part(source, unseal)?;
+    let file = BufWriter::new(File::create(target).map_err(|e| e.to_string())?);
+    let mut writer = age_split::encryptor(seal)?
+        .wrap_output(file)
+        .map_err(|e| e.to_string())?;
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = match reader.read(&mut buffer) {
+            Ok(0) => break,
+            Ok(read_len) => read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(tr!(AgeDecryptFailed, source.display(), err)),
+        };
+        writer
+            .write_all(&buffer[..read_len])
+            .map_err(|e| e.to_string())?;
+        on_progress(read_len as u64);
+    }
+    writer
+        .finish()
+        .and_then(|mut file| file.flush())
+        .map_err(|e| e.to_string())
+}
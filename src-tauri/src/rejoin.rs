@@ -0,0 +1,174 @@
+//! 合并脚本：在分片目录中写出 `rejoin.bat`（Windows）与 `rejoin.sh`（macOS、Linux），
+//! 只用系统自带的 `copy /b`、`cat` 与 `tar`、`unzip` 按顺序拼接分片并解压到分片目录的上一级，
+//! 没有安装本程序的接收方也能还原。
+//!
+//! 脚本逐行列出每份分片，分片再多也不会超过命令行长度限制。脚本不核对清单，需要核对时用本程序合并。
+
+use std::{fs, path::Path};
+
+use crate::{i18n::tr, jobs::JobContext, parse_part_name};
+
+const BAT_FILE: &str = "rejoin.bat";
+const SH_FILE: &str = "rejoin.sh";
+
+/// 拼接结果的解压命令（Windows、Unix），`{}` 为拼接结果的路径；系统自带工具解不开时为 `None`。
+fn extract_commands(merge_mode: &str) -> Option<(&'static str, &'static str)> {
+    match merge_mode {
+        // Windows 10 起自带的 tar（bsdtar）可以解压 zip
+        "zip-then-split" => Some(("tar -xf \"{}\" -C ..", "unzip -o \"{}\" -d ..")),
+        "tar-gz-then-split" => Some(("tar -xzf \"{}\" -C ..", "tar -xzf \"{}\" -C ..")),
+        _ => None,
+    }
+}
+
+/// 写出两份合并脚本，返回写出的文件。`extractable` 为假时（如加密或 xz 压缩的 zip）只拼接不解压。
+pub(crate) fn write_scripts(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    merge_mode: &str,
+    parts: &[String],
+    extractable: bool,
+) -> Result<Vec<String>, String> {
+    let names = parts
+        .iter()
+        .map(|part| {
+            Path::new(part)
+                .file_name()
+                .map(|name| name.to_string_lossy().to_string())
+                .ok_or_else(|| tr!(CannotParseFileName))
+        })
+        .collect::<Result<Vec<_>, _>>()?;
+    let first = names.first().ok_or_else(|| tr!(NoPartsFound))?;
+    let (prefix, _, _) = parse_part_name(first).ok_or_else(|| tr!(UnrecognizedPartFileName))?;
+    let merged_name = prefix.trim_end_matches('.');
+    let extract = extract_commands(merge_mode).filter(|_| extractable);
+
+    let bat_path = parts_dir.join(BAT_FILE);
+    ctx.track_output(&bat_path);
+    fs::write(
+        &bat_path,
+        bat_script(merged_name, &names, extract.map(|(bat, _)| bat)),
+    )
+    .map_err(|e| e.to_string())?;
+    let sh_path = parts_dir.join(SH_FILE);
+    ctx.track_output(&sh_path);
+    fs::write(
+        &sh_path,
+        sh_script(merged_name, &names, extract.map(|(_, sh)| sh)),
+    )
+    .map_err(|e| e.to_string())?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&sh_path, fs::Permissions::from_mode(0o755))
+            .map_err(|e| e.to_string())?;
+    }
+    Ok(vec![
+        bat_path.to_string_lossy().to_string(),
+        sh_path.to_string_lossy().to_string(),
+    ])
+}
+
+/// 批处理中 `%` 需要写成 `%%`，其余字符在双引号内原样保留。
+fn bat_quote(value: &str) -> String {
+    format!("\"{}\"", value.replace('%', "%%"))
+}
+
+/// 单引号内只有单引号本身需要转义。
+fn sh_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', "'\\''"))
+}
+
+fn bat_script(merged_name: &str, parts: &[String], extract: Option<&str>) -> String {
+    let mut lines = vec![
+        "@echo off".to_string(),
+        // 以 UTF-8 读取后面的文件名与提示
+        "chcp 65001 >nul".to_string(),
+        format!("rem {}", tr!(RejoinScriptHeader)),
+    ];
+    if extract.is_some() {
+        lines.push(format!("rem {}", tr!(RejoinScriptExtractHint)));
+    }
+    lines.extend([
+        "setlocal".to_string(),
+        "cd /d \"%~dp0\"".to_string(),
+        format!("set \"OUT=..\\{}\"", merged_name.replace('%', "%%")),
+    ]);
+    if extract.is_some() {
+        lines.push("set \"EXTRACT=1\"".to_string());
+    }
+    lines.push("if exist \"%OUT%\" goto exists".to_string());
+    for (idx, part) in parts.iter().enumerate() {
+        if idx == 0 {
+            lines.push(format!(
+                "copy /b {} \"%OUT%\" >nul || goto failed",
+                bat_quote(part)
+            ));
+        } else {
+            lines.push(format!(
+                "copy /b \"%OUT%\" + {} \"%OUT%\" >nul || goto failed",
+                bat_quote(part)
+            ));
+        }
+    }
+    if let Some(command) = extract {
+        lines.push(format!(
+            "if \"%EXTRACT%\"==\"1\" {} || goto failed",
+            command.replace("{}", "%OUT%")
+        ));
+    }
+    lines.extend([
+        format!("echo {}", tr!(RejoinScriptDone, "%OUT%")),
+        "pause".to_string(),
+        "exit /b 0".to_string(),
+        ":exists".to_string(),
+        format!("echo {}", tr!(RejoinScriptTargetExists, "%OUT%")),
+        "pause".to_string(),
+        "exit /b 1".to_string(),
+        ":failed".to_string(),
+        "pause".to_string(),
+        "exit /b 1".to_string(),
+    ]);
+    let mut script = lines.join("\r\n");
+    script.push_str("\r\n");
+    script
+}
+
+fn sh_script(merged_name: &str, parts: &[String], extract: Option<&str>) -> String {
+    let mut lines = vec![
+        "#!/bin/sh".to_string(),
+        format!("# {}", tr!(RejoinScriptHeader)),
+    ];
+    if extract.is_some() {
+        lines.push(format!("# {}", tr!(RejoinScriptExtractHint)));
+    }
+    lines.extend([
+        "set -e".to_string(),
+        "cd \"$(dirname \"$0\")\"".to_string(),
+        format!("OUT={}", sh_quote(&format!("../{}", merged_name))),
+    ]);
+    if extract.is_some() {
+        lines.push("EXTRACT=1".to_string());
+    }
+    lines.extend([
+        "if [ -e \"$OUT\" ]; then".to_string(),
+        format!("    echo \"{}\" >&2", tr!(RejoinScriptTargetExists, "$OUT")),
+        "    exit 1".to_string(),
+        "fi".to_string(),
+    ]);
+    for (idx, part) in parts.iter().enumerate() {
+        let redirect = if idx == 0 { ">" } else { ">>" };
+        lines.push(format!("cat {} {} \"$OUT\"", sh_quote(part), redirect));
+    }
+    if let Some(command) = extract {
+        lines.extend([
+            "if [ \"$EXTRACT\" = 1 ]; then".to_string(),
+            format!("    {}", command.replace("{}", "$OUT")),
+            "fi".to_string(),
+        ]);
+    }
+    lines.push(format!("echo \"{}\"", tr!(RejoinScriptDone, "$OUT")));
+    let mut script = lines.join("\n");
+    script.push('\n');
+    script
+}
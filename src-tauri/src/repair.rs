@@ -0,0 +1,93 @@
+//! 修复分片：不合并，只核对分片目录中的分片，用恢复分片或异或校验分片（参见 [`crate::erasure`]）
+//! 重建缺失或损坏的分片；没有这些记录或恢复分片不够时，再用 PAR2 恢复文件（参见 [`crate::parity`]）
+//! 按数据块修复。修复后的分片写回分片目录，进度阶段为 `repair`。
+
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::{erasure, i18n::tr, jobs::JobContext, parity};
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepairOptions {
+    /// 分片文件或分片目录。
+    input_path: String,
+}
+
+/// 用一种恢复数据核对与修复的结果。
+pub(crate) struct Outcome {
+    /// `reedSolomon`、`xor` 或 `par2`。
+    pub(crate) method: &'static str,
+    /// 缺失或损坏的分片文件名。
+    pub(crate) damaged: Vec<String>,
+    /// 已重建并写回分片目录的分片。
+    pub(crate) repaired: Vec<String>,
+    /// 恢复数据不够、无法修复时的原因。
+    pub(crate) error: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct RepairReport {
+    parts_dir: String,
+    /// 用到的恢复数据：`reedSolomon`、`xor` 或 `par2`。
+    method: String,
+    /// 修复前缺失或损坏的分片文件名，为空表示分片都完好。
+    damaged_parts: Vec<String>,
+    repaired_parts: Vec<String>,
+}
+
+pub(crate) fn repair_parts_blocking(
+    ctx: &JobContext,
+    options: RepairOptions,
+) -> Result<RepairReport, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let parts_dir = if input_path.is_dir() {
+        input_path.clone()
+    } else {
+        input_path
+            .parent()
+            .map(Path::to_path_buf)
+            .unwrap_or_default()
+    };
+
+    let mut attempts = Vec::new();
+    if let Some(outcome) = erasure::repair(ctx, &input_path)? {
+        attempts.push(outcome);
+    }
+    if attempts
+        .last()
+        .is_none_or(|outcome| outcome.error.is_some())
+    {
+        if let Some(outcome) = parity::repair(ctx, &input_path)? {
+            attempts.push(outcome);
+        }
+    }
+    let Some(outcome) = attempts.pop() else {
+        return Err(tr!(NoRecoveryData, parts_dir.display()));
+    };
+    // 恢复分片不够而改用 PAR2 时，报告恢复分片核对出的损坏分片
+    let damaged_parts = attempts
+        .into_iter()
+        .next()
+        .map_or_else(|| outcome.damaged.clone(), |first| first.damaged);
+    if let Some(error) = outcome.error {
+        return Err(error);
+    }
+    log::info!(
+        "修复分片：{}（{} 份损坏，已修复 {} 份，{}）",
+        parts_dir.display(),
+        damaged_parts.len(),
+        outcome.repaired.len(),
+        outcome.method
+    );
+    Ok(RepairReport {
+        parts_dir: parts_dir.to_string_lossy().to_string(),
+        method: outcome.method.to_string(),
+        damaged_parts,
+        repaired_parts: outcome.repaired,
+    })
+}
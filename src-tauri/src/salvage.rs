@@ -0,0 +1,175 @@
+//! 尽力解压：zip 的中央目录损坏、读不出条目列表时，从头扫描本地文件头逐个找回条目。
+//! 读不出或损坏的条目用 [`JobContext::record_lost`] 记录后跳过，其余条目照常解压。
+
+use std::fs::{self, File};
+use std::io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write};
+use std::path::Path;
+
+use zip::read::read_zipfile_from_stream;
+
+use crate::{
+    conflicts, copy_n_buffered, emit_progress, entry_names,
+    entry_times::{DirTimes, EntryTimes},
+    i18n::tr,
+    jobs::JobContext,
+};
+
+const LOCAL_HEADER_SIGNATURE: [u8; 4] = [0x50, 0x4b, 0x03, 0x04];
+const SCAN_BUFFER_SIZE: usize = 64 * 1024;
+
+/// 按本地文件头解压 `zip_path` 中能找回的条目。扫描不用中央目录，也就不支持加密条目，加密条目记为丢失。
+pub(crate) fn extract_local_entries(
+    ctx: &JobContext,
+    zip_path: &Path,
+    output_dir: &Path,
+) -> Result<(), String> {
+    let total_bytes = fs::metadata(zip_path).map_err(|e| e.to_string())?.len();
+    let mut reader = BufReader::new(File::open(zip_path).map_err(|e| e.to_string())?);
+    let mut offset = 0u64;
+    let mut extracted = 0usize;
+    let mut dir_times = DirTimes::default();
+
+    while let Some(header) = find_local_header(&mut reader, offset).map_err(|e| e.to_string())? {
+        ctx.check_cancelled()?;
+        // 压缩数据中碰巧出现的签名读不出合法的文件名，跳过继续找
+        offset = header + 1;
+        let Some((raw_name, encrypted)) =
+            local_header(&mut reader, header).map_err(|e| e.to_string())?
+        else {
+            continue;
+        };
+        // 不是 UTF-8 的名称按 `filenameEncoding` 解读，读不出时多半不是真正的文件头
+        let decoded = std::str::from_utf8(&raw_name).is_err();
+        let Some(name) = String::from_utf8(raw_name.clone())
+            .ok()
+            .or_else(|| entry_names::decode_exact(ctx.name_encoding(), &raw_name))
+        else {
+            continue;
+        };
+        if encrypted {
+            log::warn!("尽力解压：跳过加密条目 {}（扫描文件头时无法解密）", name);
+            ctx.record_lost(name);
+            continue;
+        }
+        emit_progress(
+            ctx,
+            "unzip",
+            header,
+            total_bytes,
+            extracted + 1,
+            0,
+            tr!(Extracting),
+        );
+
+        reader
+            .seek(SeekFrom::Start(header))
+            .map_err(|e| e.to_string())?;
+        let mut entry = match read_zipfile_from_stream(&mut reader) {
+            Ok(Some(entry)) => entry,
+            Ok(None) => break,
+            Err(err) => {
+                log::warn!("尽力解压：{}", tr!(EntryCorrupt, name, err));
+                ctx.record_lost(name);
+                continue;
+            }
+        };
+        let relative = if decoded {
+            entry_names::enclosed(&name)
+        } else {
+            entry.enclosed_name()
+        };
+        let Some(relative) = relative else {
+            continue;
+        };
+        let out_path = output_dir.join(relative);
+        let times = EntryTimes::from_zip(&entry);
+        if entry.is_dir() || entry.name().ends_with('/') {
+            fs::create_dir_all(&out_path).map_err(|e| e.to_string())?;
+            dir_times.push(out_path, times);
+        } else {
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+            }
+            let Some(out_path) = conflicts::resolve(ctx, &out_path, &name, entry.size())? else {
+                // 跳过的条目也要读完，流的位置才会到下一个文件头
+                io::copy(&mut entry, &mut io::sink()).map_err(|e| e.to_string())?;
+                drop(entry);
+                offset = reader.stream_position().map_err(|e| e.to_string())?;
+                continue;
+            };
+            let mut writer = BufWriter::new(File::create(&out_path).map_err(|e| e.to_string())?);
+            let size = entry.size();
+            let buffer_size = ctx.memory().buffer_size();
+            let copied = copy_n_buffered(
+                &mut entry,
+                &mut writer,
+                size,
+                buffer_size,
+                ctx.cancel_token(),
+                |_| {},
+            )
+            // 读到条目末尾才会核对 CRC
+            .and_then(|()| entry.read(&mut [0u8; 1]).map(|_| ()))
+            .and_then(|()| writer.flush());
+            if let Err(err) = copied {
+                ctx.check_cancelled()?;
+                drop(writer);
+                let _ = fs::remove_file(&out_path);
+                log::warn!("尽力解压：{}", tr!(EntryCorrupt, name, err));
+                ctx.record_lost(name);
+                continue;
+            }
+            times.apply_to_file(&out_path, writer.get_ref());
+            extracted += 1;
+        }
+        // 条目读完后流的位置就是下一个文件头
+        drop(entry);
+        offset = reader.stream_position().map_err(|e| e.to_string())?;
+    }
+    dir_times.apply();
+    Ok(())
+}
+
+/// 从 `from` 开始找下一个本地文件头的签名。
+fn find_local_header<R: Read + Seek>(reader: &mut R, from: u64) -> io::Result<Option<u64>> {
+    reader.seek(SeekFrom::Start(from))?;
+    let mut buffer = vec![0u8; SCAN_BUFFER_SIZE];
+    let mut base = from;
+    let mut kept = 0usize;
+    loop {
+        let read = reader.read(&mut buffer[kept..])?;
+        if read == 0 {
+            return Ok(None);
+        }
+        let filled = kept + read;
+        if let Some(pos) = buffer[..filled]
+            .windows(LOCAL_HEADER_SIGNATURE.len())
+            .position(|window| window == LOCAL_HEADER_SIGNATURE)
+        {
+            return Ok(Some(base + pos as u64));
+        }
+        // 留下末尾几个字节，签名跨两次读取时也能找到
+        kept = filled.min(LOCAL_HEADER_SIGNATURE.len() - 1);
+        buffer.copy_within(filled - kept..filled, 0);
+        base += (filled - kept) as u64;
+    }
+}
+
+/// 读出本地文件头中的文件名原始字节与是否加密；文件名为空时返回 `None`。
+fn local_header<R: Read + Seek>(
+    reader: &mut R,
+    header: u64,
+) -> io::Result<Option<(Vec<u8>, bool)>> {
+    reader.seek(SeekFrom::Start(header))?;
+    let mut fixed = [0u8; 30];
+    if reader.read_exact(&mut fixed).is_err() {
+        return Ok(None);
+    }
+    let flags = u16::from_le_bytes([fixed[6], fixed[7]]);
+    let name_len = u16::from_le_bytes([fixed[26], fixed[27]]) as usize;
+    let mut name = vec![0u8; name_len];
+    if reader.read_exact(&mut name).is_err() {
+        return Ok(None);
+    }
+    Ok((!name.is_empty()).then_some((name, flags & 1 != 0)))
+}
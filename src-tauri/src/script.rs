@@ -0,0 +1,95 @@
+//! 打包时的脚本钩子（rhai）。脚本中按需定义以下函数，未定义的钩子不生效：
+//!
+//! - `name(base_name, input_path)`：返回新的输出名称（分片目录与分片文件名的前缀）
+//! - `filter(path, is_dir, size)`：打包目录时决定是否包含某个条目，`path` 为相对输入目录的路径
+//!   （以 `/` 分隔），返回 `false` 时跳过（目录会连同其内容一起跳过）
+//! - `on_part(path, index, total)`：每份分片完成后调用，`index` 从 1 开始；抛出异常会使任务失败
+
+use rhai::{CallFnOptions, Dynamic, Engine, FuncArgs, Scope, AST, INT};
+use std::path::{Path, PathBuf};
+
+use crate::i18n::tr;
+
+/// 单次钩子调用允许执行的最大操作数，防止脚本死循环卡住任务。
+const MAX_OPERATIONS: u64 = 10_000_000;
+
+pub(crate) struct ScriptHooks {
+    engine: Engine,
+    ast: AST,
+    has_name: bool,
+    has_filter: bool,
+    has_on_part: bool,
+}
+
+impl ScriptHooks {
+    pub(crate) fn load(path: &Path) -> Result<Self, String> {
+        let mut engine = Engine::new();
+        engine.set_max_operations(MAX_OPERATIONS);
+        let ast = engine
+            .compile_file(PathBuf::from(path))
+            .map_err(|e| tr!(ScriptLoadFailed, e))?;
+        let defines = |name: &str| ast.iter_functions().any(|f| f.name == name);
+        let (has_name, has_filter, has_on_part) =
+            (defines("name"), defines("filter"), defines("on_part"));
+        Ok(ScriptHooks {
+            engine,
+            ast,
+            has_name,
+            has_filter,
+            has_on_part,
+        })
+    }
+
+    /// 是否定义了 `filter` 钩子，即打包目录时可能跳过其中的条目。
+    pub(crate) fn has_filter(&self) -> bool {
+        self.has_filter
+    }
+
+    fn call(&self, name: &str, args: impl FuncArgs) -> Result<Dynamic, String> {
+        let options = CallFnOptions::new().eval_ast(false);
+        self.engine
+            .call_fn_with_options(options, &mut Scope::new(), &self.ast, name, args)
+            .map_err(|e| tr!(ScriptFnFailed, name, e))
+    }
+
+    pub(crate) fn base_name(&self, base_name: &str, input_path: &Path) -> Result<String, String> {
+        if !self.has_name {
+            return Ok(base_name.to_string());
+        }
+        let name = self
+            .call(
+                "name",
+                (
+                    base_name.to_string(),
+                    input_path.to_string_lossy().to_string(),
+                ),
+            )?
+            .into_string()
+            .map_err(|_| tr!(ScriptNameNotString))?;
+        let name = name.trim().to_string();
+        if name.is_empty() || name.contains(['/', '\\']) || name == "." || name == ".." {
+            return Err(tr!(ScriptNameInvalid, name));
+        }
+        Ok(name)
+    }
+
+    pub(crate) fn include(&self, rel_path: &str, is_dir: bool, size: u64) -> Result<bool, String> {
+        if !self.has_filter {
+            return Ok(true);
+        }
+        self.call(
+            "filter",
+            (rel_path.to_string(), is_dir, size.min(INT::MAX as u64) as INT),
+        )?
+        .as_bool()
+        .map_err(|_| tr!(ScriptFilterNotBool))
+    }
+
+    pub(crate) fn on_part(&self, path: &str, index: usize, total: usize) -> Result<(), String> {
+        if !self.has_on_part {
+            return Ok(());
+        }
+        self.call("on_part", (path.to_string(), index as INT, total as INT))
+            .map(|_| ())
+    }
+}
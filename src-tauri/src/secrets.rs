@@ -0,0 +1,110 @@
+//! 任务密码的内存存储：密码不必作为 `SplitOptions`/`RestoreOptions` 的 `password` 字段随参数 JSON
+//! 传递。界面先调用 `set_job_secret`，以自选的 `jobId`（如随机 UUID）登记密码，再在参数中以
+//! `secretJobId` 引用；任务开始前取出并从存储中删除，每个密码只能用一次。
+//!
+//! 存储中的密码在取出、替换或过期时清零；登记后迟迟未被取用的密码在 [`SECRET_TTL`] 后丢弃。
+//! 取出的密码以 [`SecretString`] 一直传到加解密处，中途不复制成普通的 `String`。
+
+use serde::{Deserialize, Serialize, Serializer};
+use std::{
+    collections::HashMap,
+    fmt,
+    ops::Deref,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use zeroize::Zeroizing;
+
+use crate::i18n::tr;
+
+/// 登记后未被任务取用的密码保留的时长。
+const SECRET_TTL: Duration = Duration::from_secs(10 * 60);
+
+/// 内存中的密码：丢弃时清零，`Debug` 不显示内容。任务参数中的 `password` 直接反序列化为此类型。
+#[derive(Clone, Default, Deserialize)]
+#[serde(from = "String")]
+pub(crate) struct SecretString(Zeroizing<String>);
+
+impl From<String> for SecretString {
+    fn from(value: String) -> Self {
+        SecretString(Zeroizing::new(value))
+    }
+}
+
+impl From<&str> for SecretString {
+    fn from(value: &str) -> Self {
+        SecretString(Zeroizing::new(value.to_string()))
+    }
+}
+
+impl Deref for SecretString {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Debug for SecretString {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("\"***\"")
+    }
+}
+
+/// 诊断信息按字段名隐去密码，见 [`crate::diagnostics`]。
+impl Serialize for SecretString {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+struct Secret {
+    value: Zeroizing<String>,
+    stored_at: Instant,
+}
+
+fn store() -> &'static Mutex<HashMap<String, Secret>> {
+    static STORE: OnceLock<Mutex<HashMap<String, Secret>>> = OnceLock::new();
+    STORE.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 以 `job_id` 登记密码，已有同名登记时替换。
+pub(crate) fn set(job_id: &str, secret: String) -> Result<(), String> {
+    if job_id.is_empty() {
+        return Err(tr!(JobSecretIdEmpty));
+    }
+    let mut secrets = store().lock().unwrap_or_else(|e| e.into_inner());
+    secrets.retain(|_, secret| secret.stored_at.elapsed() < SECRET_TTL);
+    secrets.insert(
+        job_id.to_string(),
+        Secret {
+            value: Zeroizing::new(secret),
+            stored_at: Instant::now(),
+        },
+    );
+    Ok(())
+}
+
+/// 取出并删除 `job_id` 登记的密码。
+fn take(job_id: &str) -> Result<Zeroizing<String>, String> {
+    let mut secrets = store().lock().unwrap_or_else(|e| e.into_inner());
+    secrets.retain(|_, secret| secret.stored_at.elapsed() < SECRET_TTL);
+    secrets
+        .remove(job_id)
+        .map(|secret| secret.value)
+        .ok_or_else(|| tr!(JobSecretNotFound, job_id))
+}
+
+/// 参数以 `secret_job_id` 引用了登记的密码时取出填入 `password`，并清除引用；
+/// 任务参数此后只在内存中持有密码。
+pub(crate) fn resolve(
+    password: &mut Option<SecretString>,
+    secret_job_id: &mut Option<String>,
+) -> Result<(), String> {
+    let Some(job_id) = secret_job_id.take().filter(|value| !value.is_empty()) else {
+        return Ok(());
+    };
+    *password = Some(SecretString(take(&job_id)?));
+    Ok(())
+}
@@ -0,0 +1,443 @@
+//! zstd seekable format（见 zstd contrib/seekable_format）：数据按固定大小切成相互独立的
+//! zstd 帧，末尾追加一个 skippable 帧记录每帧的压缩/原始大小。普通 zstd 工具可以照常解压，
+//! 本工具则可以只解压覆盖目标区间的那几帧。
+//!
+//! `zstd-then-split` 另把帧索引写入完整性清单的 `frames` 字段，按顺序记录每帧的压缩与原始大小，
+//! 受分片校验值与清单签名保护。随机读取时优先用它定位帧，不必先读最后一份分片末尾的索引表；
+//! 没有清单或与分片总长度对不上时仍读索引表。
+//! 用字典压缩的帧（见 [`crate::dictionary`]）在读取时从同一目录加载 `<名称>.zst.dict`。
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use std::{
+    cmp,
+    io::{self, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+    thread,
+};
+
+use zstd::dict::{DecoderDictionary, EncoderDictionary};
+
+use crate::{
+    dictionary,
+    i18n::Msg,
+    integrity,
+    memory::{MemoryBudget, ZSTD_WORKER_OVERHEAD},
+    parse_part_name, priority,
+};
+
+const SKIPPABLE_MAGIC: u32 = 0x184D_2A5E;
+const SEEKABLE_MAGIC: u32 = 0x8F92_EAB1;
+const SEEK_TABLE_FOOTER_SIZE: u64 = 9;
+const SEEK_TABLE_ENTRY_SIZE: u64 = 8;
+const SEEK_TABLE_CHECKSUM_FLAG: u8 = 0x80;
+
+pub(crate) const DEFAULT_FRAME_SIZE: usize = 4 * 1024 * 1024;
+const FRAME_INDEX_VERSION: u32 = 1;
+/// 完整性清单中记录帧索引的字段。
+const FRAME_INDEX_FIELD: &str = "frames";
+
+/// 清单 `frames` 字段的内容。
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct FrameIndex {
+    version: u32,
+    /// 拼接后压缩数据的总长度，包括末尾的索引表。
+    compressed_size: u64,
+    decompressed_size: u64,
+    /// 按顺序排列的 `[压缩大小, 原始大小]`，偏移量依次累加得出。
+    frames: Vec<[u64; 2]>,
+}
+
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct SeekFrame {
+    pub(crate) compressed_offset: u64,
+    pub(crate) compressed_size: u64,
+    pub(crate) decompressed_offset: u64,
+    pub(crate) decompressed_size: u64,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct SeekTable {
+    pub(crate) frames: Vec<SeekFrame>,
+}
+
+impl SeekTable {
+    fn from_sizes(sizes: impl IntoIterator<Item = (u64, u64)>) -> Self {
+        let mut frames = Vec::new();
+        let mut compressed_offset = 0u64;
+        let mut decompressed_offset = 0u64;
+        for (compressed_size, decompressed_size) in sizes {
+            frames.push(SeekFrame {
+                compressed_offset,
+                compressed_size,
+                decompressed_offset,
+                decompressed_size,
+            });
+            compressed_offset += compressed_size;
+            decompressed_offset += decompressed_size;
+        }
+        SeekTable { frames }
+    }
+
+    /// 帧数据之后追加的索引表（含 skippable 帧头）的长度。
+    fn trailer_len(&self) -> u64 {
+        8 + self.frames.len() as u64 * SEEK_TABLE_ENTRY_SIZE + SEEK_TABLE_FOOTER_SIZE
+    }
+
+    fn compressed_size(&self) -> u64 {
+        self.frames
+            .last()
+            .map(|frame| frame.compressed_offset + frame.compressed_size)
+            .unwrap_or(0)
+            + self.trailer_len()
+    }
+
+    pub(crate) fn read_from<R: Read + Seek>(reader: &mut R) -> io::Result<Self> {
+        let total_len = reader.seek(SeekFrom::End(0))?;
+        if total_len < SEEK_TABLE_FOOTER_SIZE + 8 {
+            return Err(invalid_data(Msg::NotSeekableZstd.text()));
+        }
+        reader.seek(SeekFrom::End(-(SEEK_TABLE_FOOTER_SIZE as i64)))?;
+        let mut footer = [0u8; SEEK_TABLE_FOOTER_SIZE as usize];
+        reader.read_exact(&mut footer)?;
+        let frame_count = u32::from_le_bytes(footer[0..4].try_into().unwrap()) as u64;
+        let descriptor = footer[4];
+        let magic = u32::from_le_bytes(footer[5..9].try_into().unwrap());
+        if magic != SEEKABLE_MAGIC {
+            return Err(invalid_data(Msg::NotSeekableZstd.text()));
+        }
+        let entry_size = if descriptor & SEEK_TABLE_CHECKSUM_FLAG != 0 {
+            SEEK_TABLE_ENTRY_SIZE + 4
+        } else {
+            SEEK_TABLE_ENTRY_SIZE
+        };
+        let table_len = frame_count * entry_size + SEEK_TABLE_FOOTER_SIZE;
+        if total_len < table_len + 8 {
+            return Err(invalid_data(Msg::ZstdSeekTableCorrupt.text()));
+        }
+        reader.seek(SeekFrom::End(-((table_len + 8) as i64)))?;
+        let mut header = [0u8; 8];
+        reader.read_exact(&mut header)?;
+        let skippable_magic = u32::from_le_bytes(header[0..4].try_into().unwrap());
+        let frame_len = u32::from_le_bytes(header[4..8].try_into().unwrap()) as u64;
+        if skippable_magic != SKIPPABLE_MAGIC || frame_len != table_len {
+            return Err(invalid_data(Msg::ZstdSeekTableCorrupt.text()));
+        }
+
+        let mut entries = vec![0u8; (frame_count * entry_size) as usize];
+        reader.read_exact(&mut entries)?;
+        let table = SeekTable::from_sizes(entries.chunks_exact(entry_size as usize).map(|entry| {
+            (
+                u32::from_le_bytes(entry[0..4].try_into().unwrap()) as u64,
+                u32::from_le_bytes(entry[4..8].try_into().unwrap()) as u64,
+            )
+        }));
+        let data_len = table
+            .frames
+            .last()
+            .map_or(0, |frame| frame.compressed_offset + frame.compressed_size);
+        if data_len + table_len + 8 != total_len {
+            return Err(invalid_data(Msg::ZstdSeekTableMismatch.text()));
+        }
+        Ok(table)
+    }
+
+    pub(crate) fn decompressed_size(&self) -> u64 {
+        self.frames
+            .last()
+            .map(|frame| frame.decompressed_offset + frame.decompressed_size)
+            .unwrap_or(0)
+    }
+
+    pub(crate) fn frame_for_offset(&self, offset: u64) -> Option<usize> {
+        if offset >= self.decompressed_size() {
+            return None;
+        }
+        let index = self
+            .frames
+            .partition_point(|frame| frame.decompressed_offset + frame.decompressed_size <= offset);
+        Some(index)
+    }
+}
+
+/// 内存上限内可同时压缩的帧数：每帧需要原始数据与压缩结果两份缓冲区，外加压缩上下文。
+pub(crate) fn max_workers(memory: MemoryBudget, frame_size: usize) -> usize {
+    let cores = thread::available_parallelism().map(|n| n.get()).unwrap_or(1);
+    memory.workers(cores, 2 * frame_size as u64 + ZSTD_WORKER_OVERHEAD)
+}
+
+/// 以 seekable 格式压缩 `reader` 的全部内容，每批最多 `workers` 帧并行压缩，返回写出的索引表。
+/// 给出 `dictionary` 时每帧都用它压缩，解压时需要同一份字典。`background` 时在降低了优先级的
+/// 线程池中压缩，参见 [`priority::background_pool`]。
+pub(crate) fn compress_seekable<R: Read, W: Write>(
+    reader: &mut R,
+    writer: &mut W,
+    level: i32,
+    frame_size: usize,
+    workers: usize,
+    dictionary: Option<&[u8]>,
+    background: bool,
+    mut progress: impl FnMut(u64),
+) -> io::Result<SeekTable> {
+    let batch = workers.max(1);
+    let prepared = dictionary.map(|dictionary| EncoderDictionary::copy(dictionary, level));
+    let mut sizes: Vec<(u32, u32)> = Vec::new();
+
+    loop {
+        let mut chunks = Vec::with_capacity(batch);
+        for _ in 0..batch {
+            let mut chunk = vec![0u8; frame_size];
+            let read_len = read_full(reader, &mut chunk)?;
+            if read_len == 0 {
+                break;
+            }
+            chunk.truncate(read_len);
+            chunks.push(chunk);
+            if read_len < frame_size {
+                break;
+            }
+        }
+        if chunks.is_empty() {
+            break;
+        }
+        let last_batch = chunks.last().map(|chunk| chunk.len() < frame_size).unwrap_or(true)
+            || chunks.len() < batch;
+
+        let compressed = priority::install(background, || {
+            chunks
+                .par_iter()
+                .map(|chunk| {
+                    let mut compressor = match &prepared {
+                        Some(prepared) => {
+                            zstd::bulk::Compressor::with_prepared_dictionary(prepared)?
+                        }
+                        None => zstd::bulk::Compressor::new(level)?,
+                    };
+                    compressor.include_checksum(true)?;
+                    compressor.compress(chunk)
+                })
+                .collect::<io::Result<Vec<Vec<u8>>>>()
+        })?;
+
+        for (chunk, frame) in chunks.iter().zip(compressed.iter()) {
+            writer.write_all(frame)?;
+            sizes.push((frame.len() as u32, chunk.len() as u32));
+            progress(chunk.len() as u64);
+        }
+        if last_batch {
+            break;
+        }
+    }
+
+    let table_len = sizes.len() as u64 * SEEK_TABLE_ENTRY_SIZE + SEEK_TABLE_FOOTER_SIZE;
+    let mut table = Vec::with_capacity(table_len as usize + 8);
+    table.extend_from_slice(&SKIPPABLE_MAGIC.to_le_bytes());
+    table.extend_from_slice(&(table_len as u32).to_le_bytes());
+    for (compressed_size, decompressed_size) in &sizes {
+        table.extend_from_slice(&compressed_size.to_le_bytes());
+        table.extend_from_slice(&decompressed_size.to_le_bytes());
+    }
+    table.extend_from_slice(&(sizes.len() as u32).to_le_bytes());
+    table.push(0);
+    table.extend_from_slice(&SEEKABLE_MAGIC.to_le_bytes());
+    writer.write_all(&table)?;
+    Ok(SeekTable::from_sizes(sizes.into_iter().map(
+        |(compressed_size, decompressed_size)| (compressed_size as u64, decompressed_size as u64),
+    )))
+}
+
+/// 把索引表写入分片目录中 `base_name` 的完整性清单，清单须已写好分片记录。
+pub(crate) fn record_frame_index(
+    parts_dir: &Path,
+    base_name: &str,
+    table: &SeekTable,
+) -> Result<(), String> {
+    let index = FrameIndex {
+        version: FRAME_INDEX_VERSION,
+        compressed_size: table.compressed_size(),
+        decompressed_size: table.decompressed_size(),
+        frames: table
+            .frames
+            .iter()
+            .map(|frame| [frame.compressed_size, frame.decompressed_size])
+            .collect(),
+    };
+    let value = serde_json::to_value(&index).map_err(|e| e.to_string())?;
+    integrity::record_field(parts_dir, base_name, FRAME_INDEX_FIELD, value)
+}
+
+/// 分片所在目录与拼接后的压缩数据名称（如 `name.zip.zst`），由第一份分片的文件名得出。
+fn archive_location(parts: &[PathBuf]) -> Option<(&Path, String)> {
+    let first = parts.first()?;
+    let (prefix, _, _) = parse_part_name(first.file_name()?.to_str()?)?;
+    Some((first.parent()?, prefix.trim_end_matches('.').to_string()))
+}
+
+/// 读取列有 `first_part` 的清单中的帧索引；没有、读不出或与压缩数据总长度 `compressed_size`
+/// 对不上时返回 `None`。
+fn read_frame_index(first_part: &Path, compressed_size: u64) -> Option<SeekTable> {
+    let (_, manifest) = integrity::find(first_part).ok().flatten()?;
+    let path = manifest.path();
+    let index: FrameIndex = match serde_json::from_value(manifest.field(FRAME_INDEX_FIELD)?) {
+        Ok(index) => index,
+        Err(err) => {
+            log::warn!(
+                "清单 {} 中的帧索引无法解析，改读索引表：{}",
+                path.display(),
+                err
+            );
+            return None;
+        }
+    };
+    let table = SeekTable::from_sizes(
+        index
+            .frames
+            .iter()
+            .map(|[compressed, decompressed]| (*compressed, *decompressed)),
+    );
+    if index.version != FRAME_INDEX_VERSION
+        || table.compressed_size() != compressed_size
+        || index.compressed_size != compressed_size
+        || table.decompressed_size() != index.decompressed_size
+    {
+        log::warn!("清单 {} 中的帧索引与分片不一致，改读索引表", path.display());
+        return None;
+    }
+    Some(table)
+}
+
+/// 在 seekable zstd 数据上提供解压后的随机读取。
+pub(crate) struct SeekableReader<R> {
+    inner: R,
+    table: SeekTable,
+    position: u64,
+    cache: Option<(usize, Vec<u8>)>,
+    dictionary: Option<DecoderDictionary<'static>>,
+}
+
+impl<R: Read + Seek> SeekableReader<R> {
+    /// `parts` 拼接成的 seekable 数据：有可用的帧索引时直接用它，否则读末尾的索引表；
+    /// 分片旁有字典时一并加载。
+    pub(crate) fn open_parts(parts: &[PathBuf], mut inner: R) -> io::Result<Self> {
+        let compressed_size = inner.seek(SeekFrom::End(0))?;
+        let location = archive_location(parts);
+        let index = parts
+            .first()
+            .and_then(|first| read_frame_index(first, compressed_size));
+        let table = match index {
+            Some(table) => table,
+            None => SeekTable::read_from(&mut inner)?,
+        };
+        let dictionary = match &location {
+            Some((parts_dir, archive_name)) => dictionary::load(parts_dir, archive_name)?,
+            None => None,
+        };
+        Ok(SeekableReader {
+            inner,
+            table,
+            position: 0,
+            cache: None,
+            dictionary: dictionary.map(|dictionary| DecoderDictionary::copy(&dictionary)),
+        })
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.table.decompressed_size()
+    }
+
+    /// 解压后区间 `[start, end)` 所在的帧在压缩数据中的字节区间。
+    pub(crate) fn compressed_range(&self, start: u64, end: u64) -> (u64, u64) {
+        let frames = &self.table.frames;
+        let first = self.table.frame_for_offset(start).unwrap_or(frames.len());
+        let last = self
+            .table
+            .frame_for_offset(end.saturating_sub(1).max(start))
+            .unwrap_or(frames.len().saturating_sub(1));
+        match (frames.get(first), frames.get(last)) {
+            (Some(first), Some(last)) => (
+                first.compressed_offset,
+                last.compressed_offset + last.compressed_size,
+            ),
+            _ => (0, 0),
+        }
+    }
+
+    pub(crate) fn get_ref(&self) -> &R {
+        &self.inner
+    }
+
+    fn load_frame(&mut self, index: usize) -> io::Result<()> {
+        if matches!(&self.cache, Some((cached, _)) if *cached == index) {
+            return Ok(());
+        }
+        let frame = self.table.frames[index];
+        self.inner.seek(SeekFrom::Start(frame.compressed_offset))?;
+        let mut compressed = vec![0u8; frame.compressed_size as usize];
+        self.inner.read_exact(&mut compressed)?;
+        let mut decompressor = match &self.dictionary {
+            Some(dictionary) => zstd::bulk::Decompressor::with_prepared_dictionary(dictionary)?,
+            None if zstd::zstd_safe::get_dict_id_from_frame(&compressed).is_some() => {
+                return Err(invalid_data(Msg::ZstdDictionaryMissing.text()));
+            }
+            None => zstd::bulk::Decompressor::new()?,
+        };
+        let data = decompressor.decompress(&compressed, frame.decompressed_size as usize)?;
+        if data.len() as u64 != frame.decompressed_size {
+            return Err(invalid_data(Msg::ZstdFrameSizeMismatch.text()));
+        }
+        self.cache = Some((index, data));
+        Ok(())
+    }
+}
+
+impl<R: Read + Seek> Read for SeekableReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some(index) = self.table.frame_for_offset(self.position) else {
+            return Ok(0);
+        };
+        self.load_frame(index)?;
+        let frame = self.table.frames[index];
+        let (_, data) = self.cache.as_ref().expect("cached frame");
+        let offset = (self.position - frame.decompressed_offset) as usize;
+        let len = cmp::min(buf.len(), data.len() - offset);
+        buf[..len].copy_from_slice(&data[offset..offset + len]);
+        self.position += len as u64;
+        Ok(len)
+    }
+}
+
+impl<R: Read + Seek> Seek for SeekableReader<R> {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.len() as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        if target < 0 {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidInput,
+                Msg::InvalidSeekPosition.text(),
+            ));
+        }
+        self.position = target as u64;
+        Ok(self.position)
+    }
+}
+
+fn read_full<R: Read>(reader: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut filled = 0;
+    while filled < buf.len() {
+        match reader.read(&mut buf[filled..]) {
+            Ok(0) => break,
+            Ok(read_len) => filled += read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(filled)
+}
+
+fn invalid_data(message: &str) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message.to_string())
+}
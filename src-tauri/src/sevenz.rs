@@ -0,0 +1,364 @@
+use sevenz_rust::{
+    lzma::LZMA2Options, AesEncoderOptions, Password, SevenZArchiveEntry,
+    SevenZMethod, SevenZMethodConfiguration, SevenZReader, SevenZWriter,
+};
+use std::{
+    fs::{self, File},
+    io::{self, BufWriter, Read, Seek, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    dir_total_size, emit_progress, entry_names,
+    entry_times::{DirTimes, EntryTimes},
+    i18n::tr,
+    include_dir_entry,
+    jobs::JobContext,
+    password_prompt::PasswordError,
+    PASSWORD_PROBE_SIZE,
+};
+
+pub(crate) const SEVENZ_SIGNATURE: [u8; 6] = [0x37, 0x7a, 0xbc, 0xaf, 0x27, 0x1c];
+
+struct ProgressReader<R, F> {
+    inner: R,
+    progress: F,
+}
+
+impl<R: Read, F: FnMut(u64)> Read for ProgressReader<R, F> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read_len = self.inner.read(buf)?;
+        if read_len > 0 {
+            (self.progress)(read_len as u64);
+        }
+        Ok(read_len)
+    }
+}
+
+/// 将文件或目录压缩为 7z（LZMA2）。设置密码时同时加密内容与文件头，
+/// 没有密码就无法列出其中的文件名。`compression_level` 为 LZMA2 的预设等级（0–9），默认 6。
+pub(crate) fn sevenz_path(
+    ctx: &JobContext,
+    input_path: &Path,
+    archive_path: &Path,
+    password: Option<&str>,
+    compression_level: Option<i64>,
+    phase: &str,
+) -> Result<(), String> {
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    let total_size = if metadata.is_dir() {
+        dir_total_size(ctx, input_path, input_path)?
+    } else {
+        metadata.len()
+    };
+    let root_name = input_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| tr!(CannotParseFileName))?
+        .to_string();
+
+    let mut writer = SevenZWriter::create(archive_path).map_err(map_sevenz_error)?;
+    let preset = compression_level.unwrap_or(6).clamp(0, 9) as u32;
+    let lzma2: SevenZMethodConfiguration = LZMA2Options::with_preset(preset).into();
+    let methods = match password {
+        Some(password) => vec![AesEncoderOptions::new(Password::from(password)).into(), lzma2],
+        None => vec![lzma2],
+    };
+    writer.set_content_methods(methods);
+    writer.set_encrypt_header(password.is_some());
+
+    emit_progress(ctx, phase, 0, total_size, 0, 0, tr!(Compressing));
+
+    let mut processed = 0u64;
+    if metadata.is_dir() {
+        writer
+            .push_archive_entry::<&[u8]>(
+                SevenZArchiveEntry::from_path(input_path, root_name.clone()),
+                None,
+            )
+            .map_err(map_sevenz_error)?;
+        add_sevenz_dir_entries(
+            ctx,
+            input_path,
+            input_path,
+            &root_name,
+            phase,
+            &mut processed,
+            total_size,
+            &mut writer,
+        )?;
+    } else {
+        push_sevenz_file(
+            ctx,
+            input_path,
+            root_name,
+            phase,
+            &mut processed,
+            total_size,
+            &mut writer,
+        )?;
+    }
+
+    writer.finish().map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+fn add_sevenz_dir_entries(
+    ctx: &JobContext,
+    root: &Path,
+    current: &Path,
+    root_name: &str,
+    phase: &str,
+    processed: &mut u64,
+    total_size: u64,
+    writer: &mut SevenZWriter<File>,
+) -> Result<(), String> {
+    let entries = fs::read_dir(current).map_err(|e| e.to_string())?;
+    for entry in entries {
+        ctx.check_cancelled()?;
+        let entry = entry.map_err(|e| e.to_string())?;
+        let path = entry.path();
+        let is_dir = path.is_dir();
+        let size = if is_dir {
+            0
+        } else {
+            entry.metadata().map_err(|e| e.to_string())?.len()
+        };
+        if !include_dir_entry(ctx, root, &path, is_dir, size)? {
+            continue;
+        }
+        let rel = path.strip_prefix(root).map_err(|e| e.to_string())?;
+        let entry_name = format!(
+            "{}/{}",
+            root_name,
+            rel.to_string_lossy().replace('\\', "/")
+        );
+
+        if is_dir {
+            writer
+                .push_archive_entry::<&[u8]>(
+                    SevenZArchiveEntry::from_path(&path, entry_name),
+                    None,
+                )
+                .map_err(map_sevenz_error)?;
+            add_sevenz_dir_entries(
+                ctx, root, &path, root_name, phase, processed, total_size, writer,
+            )?;
+        } else if path.is_file() {
+            push_sevenz_file(ctx, &path, entry_name, phase, processed, total_size, writer)?;
+        }
+    }
+    Ok(())
+}
+
+fn push_sevenz_file(
+    ctx: &JobContext,
+    path: &Path,
+    entry_name: String,
+    phase: &str,
+    processed: &mut u64,
+    total_size: u64,
+    writer: &mut SevenZWriter<File>,
+) -> Result<(), String> {
+    let entry = SevenZArchiveEntry::from_path(path, entry_name);
+    let reader = ProgressReader {
+        inner: File::open(path).map_err(|e| e.to_string())?,
+        progress: |delta| {
+            *processed += delta;
+            emit_progress(
+                ctx,
+                phase,
+                *processed,
+                total_size,
+                0,
+                0,
+                tr!(Compressing),
+            );
+        },
+    };
+    writer
+        .push_archive_entry(entry, Some(reader))
+        .map_err(map_sevenz_error)?;
+    Ok(())
+}
+
+/// 按文件开头的签名判断是否为 7z。
+pub(crate) fn is_sevenz_file(path: &Path) -> Result<bool, String> {
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    let mut signature = [0u8; 6];
+    match file.read_exact(&mut signature) {
+        Ok(()) => Ok(signature == SEVENZ_SIGNATURE),
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => Ok(false),
+        Err(err) => Err(err.to_string()),
+    }
+}
+
+/// 解压 7z 前先试解密第一个文件条目开头的数据，密码不对或缺少密码时在写出任何文件之前发现；
+/// 条目不超过试读大小时读到末尾，连同 CRC 一起核对。没有加密的数据块时不检查。
+pub(crate) fn check_password(
+    archive_path: &Path,
+    password: Option<&str>,
+) -> Result<(), PasswordError> {
+    let password = password.map(Password::from).unwrap_or_else(Password::empty);
+    let mut reader = SevenZReader::open(archive_path, password).map_err(password_error)?;
+    let encrypted = reader
+        .archive()
+        .folders
+        .iter()
+        .flat_map(|folder| &folder.coders)
+        .any(|coder| coder.decompression_method_id() == SevenZMethod::ID_AES256SHA256);
+    if !encrypted {
+        return Ok(());
+    }
+    reader
+        .for_each_entries(|entry, entry_reader| {
+            if entry.is_directory() || entry.size() == 0 {
+                return Ok(true);
+            }
+            let probe = if entry.size() <= PASSWORD_PROBE_SIZE {
+                entry.size() + 1
+            } else {
+                PASSWORD_PROBE_SIZE
+            };
+            io::copy(&mut entry_reader.take(probe), &mut io::sink())
+                .map_err(|err| sevenz_rust::Error::bad_password(err, true))?;
+            Ok(false)
+        })
+        .map_err(password_error)
+}
+
+/// 解压 7z 到 `output_dir`，跳过绝对路径与含 `..` 的条目。条目损坏时报告条目名称并删掉写了一半的文件。
+pub(crate) fn extract_sevenz(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+) -> Result<(), String> {
+    if !output_dir.exists() {
+        ctx.track_output(output_dir);
+    }
+    fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
+    let password = password.map(Password::from).unwrap_or_else(Password::empty);
+    let mut reader = SevenZReader::open(archive_path, password).map_err(map_sevenz_error)?;
+    let total_entries = reader.archive().files.len();
+    let total_bytes: u64 = reader.archive().files.iter().map(|entry| entry.size()).sum();
+    let mut processed = 0u64;
+    let mut index = 0usize;
+    let mut current: Option<(String, PathBuf)> = None;
+    let mut dir_times = DirTimes::default();
+
+    reader
+        .for_each_entries(|entry, entry_reader| {
+            // 返回 false 结束遍历，取消错误在遍历结束后返回
+            if ctx.is_cancelled() {
+                return Ok(false);
+            }
+            index += 1;
+            let Some(name) =
+                entry_names::enclosed(entry.name()).filter(|name| !name.as_os_str().is_empty())
+            else {
+                return Ok(true);
+            };
+            let out_path = output_dir.join(name);
+            if entry.is_directory() {
+                fs::create_dir_all(&out_path)?;
+                dir_times.push(out_path, EntryTimes::from_sevenz(entry));
+                return Ok(true);
+            }
+            if let Some(parent) = out_path.parent() {
+                fs::create_dir_all(parent)?;
+            }
+
+            emit_progress(
+                ctx,
+                "unzip",
+                processed,
+                total_bytes,
+                index,
+                total_entries,
+                tr!(Extracting),
+            );
+
+            current = Some((entry.name().to_string(), out_path.clone()));
+            let mut writer = BufWriter::new(File::create(&out_path)?);
+            let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+            loop {
+                let read_len = entry_reader.read(&mut buffer)?;
+                if read_len == 0 {
+                    break;
+                }
+                writer.write_all(&buffer[..read_len])?;
+                processed += read_len as u64;
+                emit_progress(
+                    ctx,
+                    "unzip",
+                    processed,
+                    total_bytes,
+                    index,
+                    total_entries,
+                    tr!(Extracting),
+                );
+            }
+            writer.flush()?;
+            EntryTimes::from_sevenz(entry).apply_to_file(&out_path, writer.get_ref());
+            current = None;
+            Ok(true)
+        })
+        .map_err(|err| {
+            let err = password_error(err);
+            let Some((name, out_path)) = current.take() else {
+                return String::from(err);
+            };
+            let _ = fs::remove_file(out_path);
+            match err {
+                PasswordError::Other(message) => tr!(EntryCorrupt, name, message),
+                err => err.into(),
+            }
+        })?;
+    ctx.check_cancelled()?;
+    dir_times.apply();
+
+    Ok(())
+}
+
+/// 只读取 7z 头部，返回文件条目数与解压后的总大小，以及是否加密。头部加密而没有密码时
+/// 读不出条目，条目数与总大小为 `None`。
+pub(crate) fn entry_totals<R: Read + Seek>(
+    source: R,
+    len: u64,
+    password: Option<&str>,
+) -> Result<(Option<(usize, u64)>, bool), String> {
+    let password = password.map(Password::from).unwrap_or_else(Password::empty);
+    let reader = match SevenZReader::new(source, len, password) {
+        Ok(reader) => reader,
+        Err(sevenz_rust::Error::PasswordRequired) => return Ok((None, true)),
+        Err(err) => return Err(map_sevenz_error(err)),
+    };
+    let encrypted = reader
+        .archive()
+        .folders
+        .iter()
+        .flat_map(|folder| &folder.coders)
+        .any(|coder| coder.decompression_method_id() == SevenZMethod::ID_AES256SHA256);
+    let files = reader
+        .archive()
+        .files
+        .iter()
+        .filter(|entry| !entry.is_directory());
+    let totals = files.fold((0, 0), |(count, size), entry| {
+        (count + 1, size + entry.size())
+    });
+    Ok((Some(totals), encrypted))
+}
+
+pub(crate) fn map_sevenz_error(err: sevenz_rust::Error) -> String {
+    password_error(err).into()
+}
+
+fn password_error(err: sevenz_rust::Error) -> PasswordError {
+    match err {
+        sevenz_rust::Error::PasswordRequired => PasswordError::Required,
+        sevenz_rust::Error::MaybeBadPassword(_) => PasswordError::Wrong,
+        _ => PasswordError::Other(err.to_string()),
+    }
+}
@@ -0,0 +1,212 @@
+//! 自解压输出：打包后把本程序复制到分片目录，命名为 `restore`（Windows 上为 `restore.exe`），
+//! 旁边的 `restore.json` 记录合并方式与第一份分片。接收方运行 `restore` 即可核对、合并并解压到
+//! 分片目录的上一级，无需安装本程序。
+//!
+//! 复制的是打包这一方的可执行文件，只能在相同的操作系统与 CPU 架构上运行。
+
+use serde::{Deserialize, Serialize};
+use std::{
+    env, fs,
+    io::{self, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    i18n::{self, tr, Msg},
+    jobs::JobContext,
+    restore_parts_blocking,
+    secrets::SecretString,
+    RestoreOptions,
+};
+
+const RESTORER_STEM: &str = "restore";
+const CONFIG_FILE: &str = "restore.json";
+const CONFIG_VERSION: u32 = 1;
+
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct RestorerConfig {
+    version: u32,
+    merge_mode: String,
+    /// 第一份分片的文件名，合并时作为输入。
+    first_part: String,
+    /// 分片是否加密，加密时运行前询问密码。
+    encrypted: bool,
+    /// 分片是否加密给了 GPG 公钥，合并前先用本机私钥解密。
+    #[serde(default)]
+    gpg: bool,
+}
+
+/// 打包方式对应的合并方式。
+pub(crate) fn merge_mode(pack_mode: &str) -> &str {
+    match pack_mode {
+        "raw-split" => "raw",
+        other => other,
+    }
+}
+
+/// 把本程序与合并设置写入分片目录，返回写出的文件。
+pub(crate) fn write_restorer(
+    ctx: &JobContext,
+    parts_dir: &Path,
+    pack_mode: &str,
+    first_part: &Path,
+    encrypted: bool,
+    gpg: bool,
+) -> Result<Vec<String>, String> {
+    let first_part = first_part
+        .file_name()
+        .map(|name| name.to_string_lossy().to_string())
+        .ok_or_else(|| tr!(CannotParseFileName))?;
+    let exe = env::current_exe().map_err(|e| tr!(RestorerCopyFailed, e))?;
+    let target = parts_dir.join(format!("{}{}", RESTORER_STEM, env::consts::EXE_SUFFIX));
+    ctx.track_output(&target);
+    fs::copy(&exe, &target).map_err(|e| tr!(RestorerCopyFailed, e))?;
+
+    let config = RestorerConfig {
+        version: CONFIG_VERSION,
+        merge_mode: merge_mode(pack_mode).to_string(),
+        first_part,
+        encrypted,
+        gpg,
+    };
+    let text = serde_json::to_string_pretty(&config).map_err(|e| e.to_string())?;
+    let config_path = parts_dir.join(CONFIG_FILE);
+    ctx.track_output(&config_path);
+    fs::write(&config_path, text).map_err(|e| e.to_string())?;
+    log::info!("已写入自解压程序：{}", target.display());
+    Ok(vec![
+        target.to_string_lossy().to_string(),
+        config_path.to_string_lossy().to_string(),
+    ])
+}
+
+/// 当前程序是否是分片目录中的自解压程序：文件名为 `restore`，且旁边有 `restore.json`。
+pub(crate) fn is_restorer() -> bool {
+    let Ok(exe) = env::current_exe() else {
+        return false;
+    };
+    exe.file_stem().is_some_and(|stem| stem == RESTORER_STEM)
+        && exe.with_file_name(CONFIG_FILE).is_file()
+}
+
+struct RestorerArgs {
+    output: Option<PathBuf>,
+    password: Option<String>,
+    keyfile: Option<String>,
+    age_identities: Vec<String>,
+}
+
+fn parse_args(args: &[String]) -> Result<Option<RestorerArgs>, String> {
+    let mut parsed = RestorerArgs {
+        output: None,
+        password: None,
+        keyfile: None,
+        age_identities: Vec::new(),
+    };
+    let mut iter = args.iter();
+    while let Some(arg) = iter.next() {
+        let mut value = |flag: &str| {
+            iter.next()
+                .cloned()
+                .ok_or_else(|| tr!(MissingFlagValue, flag))
+        };
+        match arg.as_str() {
+            "--help" | "-h" => return Ok(None),
+            "--output" | "-o" => parsed.output = Some(PathBuf::from(value(arg)?)),
+            "--password" => parsed.password = Some(value(arg)?),
+            "--keyfile" => parsed.keyfile = Some(value(arg)?),
+            "--age-identity" => parsed.age_identities.push(value(arg)?),
+            "--lang" => {
+                i18n::set_language_tag(&value(arg)?)?;
+            }
+            other => return Err(tr!(UnknownArgument, other)),
+        }
+    }
+    Ok(Some(parsed))
+}
+
+/// 读取密码，密码会显示在终端上。
+fn prompt_password() -> Result<String, String> {
+    eprint!("{}", Msg::RestorerPasswordPrompt);
+    io::stderr().flush().map_err(|e| e.to_string())?;
+    let mut line = String::new();
+    io::stdin()
+        .read_line(&mut line)
+        .map_err(|e| e.to_string())?;
+    let password = line.trim_end_matches(['\r', '\n']).to_string();
+    if password.is_empty() {
+        return Err(tr!(RestorerPasswordRequired));
+    }
+    Ok(password)
+}
+
+/// 合并自解压程序所在目录中的分片，默认输出到分片目录的上一级。
+pub(crate) fn run(args: &[String]) -> Result<(), String> {
+    let Some(parsed) = parse_args(args)? else {
+        println!("{}", Msg::RestorerUsage);
+        return Ok(());
+    };
+    let exe = env::current_exe().map_err(|e| e.to_string())?;
+    let parts_dir = exe.parent().ok_or_else(|| tr!(CannotParsePartsDir))?;
+    let text = fs::read_to_string(parts_dir.join(CONFIG_FILE))
+        .map_err(|e| tr!(RestorerConfigInvalid, e))?;
+    let config: RestorerConfig =
+        serde_json::from_str(&text).map_err(|e| tr!(RestorerConfigInvalid, e))?;
+    if config.version != CONFIG_VERSION {
+        return Err(tr!(RestorerConfigInvalid, config.version));
+    }
+
+    let output_dir = parsed
+        .output
+        .unwrap_or_else(|| parts_dir.parent().unwrap_or(parts_dir).to_path_buf());
+    let password = match parsed.password {
+        Some(password) => Some(password),
+        // 只用密钥文件加密时不必再询问密码
+        None if config.encrypted && parsed.keyfile.is_none() => Some(prompt_password()?),
+        None => None,
+    };
+    let result = restore_parts_blocking(
+        &JobContext::detached(),
+        RestoreOptions {
+            input_path: parts_dir
+                .join(&config.first_part)
+                .to_string_lossy()
+                .to_string(),
+            output_dir: output_dir.to_string_lossy().to_string(),
+            merge_mode: config.merge_mode,
+            password: password.map(SecretString::from),
+            keyfile: parsed.keyfile,
+            age_identities: Some(parsed.age_identities),
+            auto_extract: Some(true),
+            filter_command: None,
+            gpg_decrypt: Some(config.gpg),
+            secret_job_id: None,
+            max_memory: None,
+            adaptive_io: Some(true),
+            verify: Some(true),
+            trusted_keys: None,
+            require_signature: Some(false),
+            salvage: Some(false),
+            secure_delete: Some(false),
+            include: None,
+            exclude: None,
+            extract_from_parts: Some(false),
+            keep_archive: Some(true),
+            missing_parts: None,
+            resume: Some(true),
+            on_conflict: None,
+            filename_encoding: None,
+            urls: None,
+            keep_downloads: None,
+        },
+    )?;
+    for file in &result.output_files {
+        eprintln!("{}", file);
+    }
+    if result.verified == Some(false) {
+        return Err(tr!(RestoreVerifyFailed, result.mismatches.join(", ")));
+    }
+    eprintln!("{}", tr!(RestorerDone, output_dir.display()));
+    Ok(())
+}
@@ -0,0 +1,361 @@
+//! 清单签名（默认关闭）：打包参数 `signingKey` 指向 Ed25519 私钥文件时，清单写完后在其中记下
+//! 签名者的公钥指纹（`signer`），再对整个清单文件签名，写入 `<名称>.manifest.json.sig`。
+//! 合并时若清单带签名，先用签名文件中的公钥核对指纹与签名，不一致即视为清单被改动，拒绝合并；
+//! 签名通过后清单中的分片校验值才可信，分片被篡改由随后的完整性核对（参见 [`crate::integrity`]）发现。
+//!
+//! 提供了 `trustedKeys` 时只接受其中公钥的签名；未提供时签名有效即可合并，但结果中的
+//! `signature.trusted` 为 `false` 并记录警告。清单被追加打包等操作重写后旧签名随之删除。
+//!
+//! 密钥文件为 PEM：私钥为 PKCS#8，公钥（`<私钥文件>.pub`）为 SubjectPublicKeyInfo，
+//! 与 `openssl genpkey -algorithm ed25519` 生成的密钥通用。
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use ed25519_dalek::{Signature, Signer, SigningKey, VerifyingKey};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Map, Value};
+use sha2::{Digest, Sha256};
+use std::{
+    ffi::OsString,
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::i18n::tr;
+
+/// 清单中记录签名者的字段。
+pub(crate) const SIGNER_FIELD: &str = "signer";
+pub(crate) const SIGNATURE_SUFFIX: &str = ".sig";
+const ALGORITHM: &str = "ed25519";
+/// PKCS#8 私钥中 32 字节种子之前的固定 DER 前缀。
+const PKCS8_PREFIX: [u8; 16] = [
+    0x30, 0x2e, 0x02, 0x01, 0x00, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x04, 0x22, 0x04, 0x20,
+];
+/// SubjectPublicKeyInfo 中 32 字节公钥之前的固定 DER 前缀。
+const SPKI_PREFIX: [u8; 12] = [
+    0x30, 0x2a, 0x30, 0x05, 0x06, 0x03, 0x2b, 0x65, 0x70, 0x03, 0x21, 0x00,
+];
+
+/// `<清单>.sig` 的内容，公钥与签名均为十六进制。
+#[derive(Debug, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SignatureFile {
+    algorithm: String,
+    public_key: String,
+    signature: String,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct SignatureStatus {
+    /// 签名者公钥的指纹。
+    pub(crate) fingerprint: String,
+    /// 公钥在 `trustedKeys` 中；未提供 `trustedKeys` 时为 `false`。
+    pub(crate) trusted: bool,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct GenerateKeyOptions {
+    /// 私钥文件路径，公钥写在 `<私钥文件>.pub`。
+    key_path: String,
+    /// 覆盖已有的密钥文件，默认关闭。
+    overwrite: Option<bool>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct ImportKeyOptions {
+    /// 已有的私钥：PKCS#8（PEM 或 DER）文件，或内容为 64 位十六进制种子的文本文件。
+    source_path: String,
+    /// 转存的私钥文件路径，公钥写在 `<私钥文件>.pub`。
+    key_path: String,
+    overwrite: Option<bool>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct KeyInfo {
+    pub(crate) key_path: String,
+    pub(crate) public_key_path: String,
+    /// 十六进制公钥，可直接填入收件方的 `trustedKeys`。
+    pub(crate) public_key: String,
+    pub(crate) fingerprint: String,
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+fn from_hex(text: &str) -> Option<Vec<u8>> {
+    if !text.len().is_multiple_of(2) {
+        return None;
+    }
+    (0..text.len())
+        .step_by(2)
+        .map(|idx| u8::from_str_radix(text.get(idx..idx + 2)?, 16).ok())
+        .collect()
+}
+
+/// 公钥指纹：公钥 SHA-256 的前 16 字节，十六进制。
+pub(crate) fn fingerprint(public_key: &VerifyingKey) -> String {
+    hex(&Sha256::digest(public_key.as_bytes())[..16])
+}
+
+fn pem(label: &str, der: &[u8]) -> String {
+    let encoded = STANDARD.encode(der);
+    let mut text = format!("-----BEGIN {}-----\n", label);
+    for line in encoded.as_bytes().chunks(64) {
+        text.push_str(&String::from_utf8_lossy(line));
+        text.push('\n');
+    }
+    text.push_str(&format!("-----END {}-----\n", label));
+    text
+}
+
+/// PEM 文本、十六进制文本或 DER 原样，转为 DER 或原始字节。
+fn decode(data: &[u8]) -> Vec<u8> {
+    let Ok(text) = std::str::from_utf8(data).map(str::trim) else {
+        return data.to_vec();
+    };
+    if text.starts_with("-----BEGIN") {
+        let body: String = text
+            .lines()
+            .filter(|line| !line.starts_with("-----"))
+            .collect();
+        return STANDARD.decode(body.trim()).unwrap_or_default();
+    }
+    from_hex(text).unwrap_or_else(|| data.to_vec())
+}
+
+/// 32 字节原始值，或带固定前缀的 DER。
+fn key_bytes(data: &[u8], prefix: &[u8]) -> Option<[u8; 32]> {
+    let decoded = decode(data);
+    let raw = decoded.strip_prefix(prefix).unwrap_or(&decoded);
+    raw.try_into().ok()
+}
+
+fn parse_signing_key(data: &[u8]) -> Option<SigningKey> {
+    key_bytes(data, &PKCS8_PREFIX).map(|seed| SigningKey::from_bytes(&seed))
+}
+
+fn parse_public_key(data: &[u8]) -> Option<VerifyingKey> {
+    key_bytes(data, &SPKI_PREFIX).and_then(|bytes| VerifyingKey::from_bytes(&bytes).ok())
+}
+
+fn read_signing_key(path: &Path) -> Result<SigningKey, String> {
+    let data = fs::read(path).map_err(|e| tr!(SigningKeyReadFailed, path.display(), e))?;
+    parse_signing_key(&data).ok_or_else(|| tr!(SigningKeyInvalid, path.display()))
+}
+
+/// 打包参数校验用：私钥文件可以读出。
+pub(crate) fn check_signing_key(path: &Path) -> Result<(), String> {
+    read_signing_key(path).map(|_| ())
+}
+
+fn public_key_path(key_path: &Path) -> PathBuf {
+    let mut path = OsString::from(key_path.as_os_str());
+    path.push(".pub");
+    PathBuf::from(path)
+}
+
+fn signature_path(manifest_path: &Path) -> PathBuf {
+    let mut path = OsString::from(manifest_path.as_os_str());
+    path.push(SIGNATURE_SUFFIX);
+    PathBuf::from(path)
+}
+
+pub(crate) fn generate_key(options: GenerateKeyOptions) -> Result<KeyInfo, String> {
+    generate_key_file(
+        Path::new(&options.key_path),
+        options.overwrite.unwrap_or(false),
+    )
+}
+
+/// 生成新的 Ed25519 密钥对，私钥写到 `key_path`，公钥写到 `<key_path>.pub`。
+pub(crate) fn generate_key_file(key_path: &Path, overwrite: bool) -> Result<KeyInfo, String> {
+    let seed: [u8; 32] = rand::random();
+    write_key(&SigningKey::from_bytes(&seed), key_path, overwrite)
+}
+
+/// 导入已有的私钥，按本程序的格式转存并写出公钥。
+pub(crate) fn import_key(options: ImportKeyOptions) -> Result<KeyInfo, String> {
+    let key = read_signing_key(Path::new(&options.source_path))?;
+    write_key(
+        &key,
+        Path::new(&options.key_path),
+        options.overwrite.unwrap_or(false),
+    )
+}
+
+fn write_key(key: &SigningKey, key_path: &Path, overwrite: bool) -> Result<KeyInfo, String> {
+    let public_path = public_key_path(key_path);
+    for path in [key_path, public_path.as_path()] {
+        if !overwrite && path.exists() {
+            return Err(tr!(SigningKeyExists, path.display()));
+        }
+    }
+    let fail = |path: &Path, e: std::io::Error| tr!(SigningKeyWriteFailed, path.display(), e);
+    let mut der = PKCS8_PREFIX.to_vec();
+    der.extend_from_slice(&key.to_bytes());
+    let mut options = OpenOptions::new();
+    options.write(true).create(true).truncate(true);
+    // 私钥只允许本人读写
+    #[cfg(unix)]
+    std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+    let mut file = options.open(key_path).map_err(|e| fail(key_path, e))?;
+    file.write_all(pem("PRIVATE KEY", &der).as_bytes())
+        .map_err(|e| fail(key_path, e))?;
+
+    let public_key = key.verifying_key();
+    let mut der = SPKI_PREFIX.to_vec();
+    der.extend_from_slice(public_key.as_bytes());
+    fs::write(&public_path, pem("PUBLIC KEY", &der)).map_err(|e| fail(&public_path, e))?;
+    log::info!(
+        "已写入签名密钥：{}（指纹 {}）",
+        key_path.display(),
+        fingerprint(&public_key)
+    );
+    Ok(KeyInfo {
+        key_path: key_path.to_string_lossy().to_string(),
+        public_key_path: public_path.to_string_lossy().to_string(),
+        public_key: hex(public_key.as_bytes()),
+        fingerprint: fingerprint(&public_key),
+    })
+}
+
+fn read_object(path: &Path) -> Result<Map<String, Value>, String> {
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    match serde_json::from_str(&text).map_err(|e| tr!(ManifestInvalid, e))? {
+        Value::Object(object) => Ok(object),
+        _ => Err(tr!(ManifestInvalid, path.display())),
+    }
+}
+
+/// 在清单中记下签名者并对清单签名，返回签名文件。
+pub(crate) fn sign_manifest(manifest_path: &Path, key_path: &Path) -> Result<PathBuf, String> {
+    let key = read_signing_key(key_path)?;
+    let public_key = key.verifying_key();
+    let mut object = read_object(manifest_path)?;
+    object.insert(
+        SIGNER_FIELD.to_string(),
+        json!({ "algorithm": ALGORITHM, "fingerprint": fingerprint(&public_key) }),
+    );
+    let text = serde_json::to_string_pretty(&object).map_err(|e| e.to_string())?;
+    let temp = manifest_path.with_extension("json.tmp");
+    fs::write(&temp, &text).map_err(|e| e.to_string())?;
+    fs::rename(&temp, manifest_path).map_err(|e| e.to_string())?;
+
+    let signature = SignatureFile {
+        algorithm: ALGORITHM.to_string(),
+        public_key: hex(public_key.as_bytes()),
+        signature: hex(&key.sign(text.as_bytes()).to_bytes()),
+    };
+    let path = signature_path(manifest_path);
+    let text = serde_json::to_string_pretty(&signature).map_err(|e| e.to_string())?;
+    fs::write(&path, text).map_err(|e| tr!(SigningKeyWriteFailed, path.display(), e))?;
+    log::info!(
+        "已签名清单：{}（指纹 {}）",
+        manifest_path.display(),
+        fingerprint(&public_key)
+    );
+    Ok(path)
+}
+
+/// 重写清单前调用：去掉签名者字段并删除旧签名，改动后的清单不再带签名。
+pub(crate) fn discard(manifest_path: &Path, object: &mut Map<String, Value>) {
+    object.remove(SIGNER_FIELD);
+    let path = signature_path(manifest_path);
+    if path.is_file() && fs::remove_file(&path).is_ok() {
+        log::warn!("清单已改动，删除原有签名：{}", path.display());
+    }
+}
+
+/// 把 `trustedKeys` 中的每一项（公钥文件、十六进制公钥或指纹）转为指纹。
+fn trusted_fingerprints(keys: &[String]) -> Result<Vec<String>, String> {
+    keys.iter()
+        .filter(|key| !key.trim().is_empty())
+        .map(|key| {
+            let path = Path::new(key);
+            let data = if path.is_file() {
+                fs::read(path).map_err(|e| tr!(SigningKeyReadFailed, path.display(), e))?
+            } else {
+                key.trim()
+                    .replace(':', "")
+                    .to_ascii_lowercase()
+                    .into_bytes()
+            };
+            if let Some(public_key) = parse_public_key(&data) {
+                return Ok(fingerprint(&public_key));
+            }
+            match std::str::from_utf8(&data) {
+                Ok(text) if text.len() == 32 && from_hex(text).is_some() => Ok(text.to_string()),
+                _ => Err(tr!(TrustedKeyInvalid, key)),
+            }
+        })
+        .collect()
+}
+
+/// 合并前调用：清单带签名时核对签名，签名无效、缺失或签名者不在 `trusted_keys` 中时报错；
+/// `require` 为真时没有签名也报错。没有签名时返回 `None`。
+pub(crate) fn verify_manifest(
+    manifest_path: Option<&Path>,
+    trusted_keys: &[String],
+    require: bool,
+) -> Result<Option<SignatureStatus>, String> {
+    let trusted = trusted_fingerprints(trusted_keys)?;
+    let Some(manifest_path) = manifest_path else {
+        return if require {
+            Err(tr!(SignatureRequired))
+        } else {
+            Ok(None)
+        };
+    };
+    let name = manifest_path.display();
+    let data = fs::read(manifest_path).map_err(|e| e.to_string())?;
+    let signer = read_object(manifest_path)?
+        .get(SIGNER_FIELD)
+        .and_then(|signer| signer.get("fingerprint"))
+        .and_then(Value::as_str)
+        .map(str::to_string);
+    let path = signature_path(manifest_path);
+    if !path.is_file() {
+        return match signer {
+            Some(_) => Err(tr!(SignatureMissing, name)),
+            None if require => Err(tr!(SignatureRequired)),
+            None => Ok(None),
+        };
+    }
+    let text = fs::read_to_string(&path).map_err(|e| e.to_string())?;
+    let file: SignatureFile =
+        serde_json::from_str(&text).map_err(|_| tr!(SignatureInvalid, name))?;
+    let public_key = parse_public_key(file.public_key.as_bytes())
+        .filter(|_| file.algorithm == ALGORITHM)
+        .ok_or_else(|| tr!(SignatureInvalid, name))?;
+    let signature = from_hex(&file.signature)
+        .and_then(|bytes| Signature::from_slice(&bytes).ok())
+        .ok_or_else(|| tr!(SignatureInvalid, name))?;
+    let fingerprint = fingerprint(&public_key);
+    if signer.as_deref() != Some(fingerprint.as_str())
+        || public_key.verify_strict(&data, &signature).is_err()
+    {
+        return Err(tr!(SignatureInvalid, name));
+    }
+    let is_trusted = trusted.contains(&fingerprint);
+    if !trusted.is_empty() && !is_trusted {
+        return Err(tr!(SignatureUntrusted, fingerprint));
+    }
+    if is_trusted {
+        log::info!("清单签名有效：{}（指纹 {}）", name, fingerprint);
+    } else {
+        log::warn!(
+            "清单签名有效，但签名者不在信任的公钥中：{}（指纹 {}）",
+            name,
+            fingerprint
+        );
+    }
+    Ok(Some(SignatureStatus {
+        fingerprint,
+        trusted: is_trusted,
+    }))
+}
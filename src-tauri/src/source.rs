@@ -0,0 +1,42 @@
+//! 流式输入源：命名管道（FIFO）、Unix 域套接字与 Windows 命名管道。
+//! 其他进程可以直接把数据写入这些路径，由分割器边读边处理，无需先落盘为单个文件。
+
+use std::{
+    fs::File,
+    io::{self, Read},
+    path::Path,
+};
+
+/// 路径是否为流式输入源（长度未知、只能顺序读取一次）。
+pub(crate) fn is_stream_source(path: &Path) -> bool {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        std::fs::metadata(path)
+            .map(|meta| meta.file_type().is_fifo() || meta.file_type().is_socket())
+            .unwrap_or(false)
+    }
+    #[cfg(windows)]
+    {
+        let path = path.to_string_lossy();
+        path.starts_with(r"\\.\pipe\") || path.starts_with(r"\\?\pipe\")
+    }
+    #[cfg(not(any(unix, windows)))]
+    {
+        let _ = path;
+        false
+    }
+}
+
+/// 打开输入：套接字以客户端身份连接后读取，管道与普通文件直接打开。
+pub(crate) fn open_source(path: &Path) -> io::Result<Box<dyn Read + Send>> {
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::FileTypeExt;
+        if std::fs::metadata(path)?.file_type().is_socket() {
+            let stream = std::os::unix::net::UnixStream::connect(path)?;
+            return Ok(Box::new(stream));
+        }
+    }
+    Ok(Box::new(File::open(path)?))
+}
@@ -0,0 +1,737 @@
+//! 标准分卷 zip（`spanned-zip`）：按 PKWARE APPNOTE 8.5 节把打好的 zip 切成 `<名称>.z01`、`<名称>.z02`……
+//! 与最后一卷 `<名称>.zip`，WinZip、7-Zip、WinRAR 打开最后一卷即可解压，无需本程序合并。
+//!
+//! 第一卷以分卷签名 `PK\x07\x08` 开头；本地文件头与中央目录的每条记录都不跨卷，放不下时换到下一卷；
+//! 中央目录中的偏移改为相对各自所在卷的开头，结束记录整体放在最后一卷。只需一卷时直接输出普通的 zip。
+//! 合并时做相反的转换，把各卷拼成普通的 zip，不需要先写出临时文件就能测试其中的条目。
+
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, Read, Seek, SeekFrom, Write},
+    path::{Path, PathBuf},
+};
+
+use crate::{
+    copy_n_buffered, emit_progress, extract,
+    i18n::tr,
+    jobs::JobContext,
+    part_reader::{PartReader, PartSegment},
+    RestoreResult,
+};
+
+/// 每卷大小的下限与上限（APPNOTE 8.5.4）。
+pub(crate) const MIN_SEGMENT_SIZE: u64 = 64 * 1024;
+pub(crate) const MAX_SEGMENT_SIZE: u64 = u32::MAX as u64;
+
+const SPAN_SIGNATURE: [u8; 4] = *b"PK\x07\x08";
+/// 分卷过程只用了一卷时的临时标记（APPNOTE 8.5.7）。
+const SINGLE_SPAN_SIGNATURE: [u8; 4] = *b"PK00";
+const LOCAL_HEADER_SIGNATURE: u32 = 0x0403_4b50;
+const CENTRAL_HEADER_SIGNATURE: u32 = 0x0201_4b50;
+const EOCD_SIGNATURE: u32 = 0x0605_4b50;
+const ZIP64_EOCD_SIGNATURE: u32 = 0x0606_4b50;
+const ZIP64_LOCATOR_SIGNATURE: u32 = 0x0706_4b50;
+const EOCD_LEN: usize = 22;
+const ZIP64_LOCATOR_LEN: usize = 20;
+const ZIP64_EXTRA_ID: u16 = 0x0001;
+
+fn u16_at(bytes: &[u8], at: usize) -> u16 {
+    u16::from_le_bytes([bytes[at], bytes[at + 1]])
+}
+
+fn u32_at(bytes: &[u8], at: usize) -> u32 {
+    u32::from_le_bytes(bytes[at..at + 4].try_into().expect("4 bytes"))
+}
+
+fn u64_at(bytes: &[u8], at: usize) -> u64 {
+    u64::from_le_bytes(bytes[at..at + 8].try_into().expect("8 bytes"))
+}
+
+/// 写入 16 位字段；原值为 0xFFFF 时实际值在 zip64 记录中，保持不变。
+fn put_u16(bytes: &mut [u8], at: usize, value: u64) -> Result<(), String> {
+    if u16_at(bytes, at) == u16::MAX {
+        return Ok(());
+    }
+    let value = u16::try_from(value).map_err(|_| tr!(SpannedZipTooLarge))?;
+    bytes[at..at + 2].copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+/// 写入 32 位字段；原值为 0xFFFFFFFF 时实际值在 zip64 记录中，保持不变。
+fn put_u32(bytes: &mut [u8], at: usize, value: u64) -> Result<(), String> {
+    if u32_at(bytes, at) == u32::MAX {
+        return Ok(());
+    }
+    let value = u32::try_from(value).map_err(|_| tr!(SpannedZipTooLarge))?;
+    bytes[at..at + 4].copy_from_slice(&value.to_le_bytes());
+    Ok(())
+}
+
+fn put_u64(bytes: &mut [u8], at: usize, value: u64) {
+    bytes[at..at + 8].copy_from_slice(&value.to_le_bytes());
+}
+
+fn invalid(detail: impl std::fmt::Display) -> String {
+    tr!(SpannedZipInvalid, detail)
+}
+
+/// 按卷号读取的一组卷文件。
+struct Volumes<'a> {
+    paths: &'a [PathBuf],
+}
+
+impl Volumes<'_> {
+    /// 从第 `disk` 卷的 `offset` 处读取 `len` 字节，读到卷尾时接着读下一卷。`len` 来自压缩包中的
+    /// 记录，超过全部卷的总大小时直接报错，不按其预先分配内存。
+    fn read_at(&self, disk: u32, offset: u64, len: u64) -> Result<Vec<u8>, String> {
+        let mut total = 0u64;
+        for path in self.paths {
+            total += fs::metadata(path).map_err(|e| e.to_string())?.len();
+        }
+        if len > total {
+            return Err(invalid(format!("length {}", len)));
+        }
+        let mut data = Vec::with_capacity(len as usize);
+        let (mut disk, mut offset) = (disk as usize, offset);
+        while (data.len() as u64) < len {
+            let path = self
+                .paths
+                .get(disk)
+                .ok_or_else(|| invalid(format!("disk {}", disk + 1)))?;
+            let mut file = File::open(path).map_err(|e| e.to_string())?;
+            file.seek(SeekFrom::Start(offset))
+                .map_err(|e| e.to_string())?;
+            let want = len - data.len() as u64;
+            file.take(want)
+                .read_to_end(&mut data)
+                .map_err(|e| e.to_string())?;
+            disk += 1;
+            offset = 0;
+        }
+        Ok(data)
+    }
+}
+
+/// 中央目录与结束记录。`zip64` 为 zip64 结束记录与定位记录，没有时为 `None`。
+struct Directory {
+    records: Vec<Vec<u8>>,
+    cd_disk: u32,
+    cd_offset: u64,
+    cd_size: u64,
+    zip64: Option<(Vec<u8>, Vec<u8>)>,
+    eocd: Vec<u8>,
+}
+
+impl Directory {
+    fn read(volumes: &Volumes) -> Result<Directory, String> {
+        let last = volumes.paths.last().ok_or_else(|| invalid("no volume"))?;
+        let last_disk = (volumes.paths.len() - 1) as u32;
+        let last_len = fs::metadata(last).map_err(|e| e.to_string())?.len();
+        let tail_len = last_len.min((EOCD_LEN + u16::MAX as usize) as u64);
+        let tail = volumes.read_at(last_disk, last_len - tail_len, tail_len)?;
+        if tail.len() < EOCD_LEN {
+            return Err(invalid("end of central directory not found"));
+        }
+        let eocd_pos = (0..=tail.len().saturating_sub(EOCD_LEN))
+            .rev()
+            .find(|&pos| {
+                u32_at(&tail, pos) == EOCD_SIGNATURE
+                    && pos + EOCD_LEN + u16_at(&tail, pos + 20) as usize == tail.len()
+            })
+            .ok_or_else(|| invalid("end of central directory not found"))?;
+        let eocd = tail[eocd_pos..].to_vec();
+
+        let locator_pos = eocd_pos.checked_sub(ZIP64_LOCATOR_LEN);
+        let zip64 = match locator_pos.filter(|&pos| u32_at(&tail, pos) == ZIP64_LOCATOR_SIGNATURE) {
+            Some(pos) => {
+                let locator = tail[pos..eocd_pos].to_vec();
+                let (disk, offset) = (u32_at(&locator, 4), u64_at(&locator, 8));
+                let head = volumes.read_at(disk, offset, 12)?;
+                if head.len() < 12 || u32_at(&head, 0) != ZIP64_EOCD_SIGNATURE {
+                    return Err(invalid("zip64 end of central directory"));
+                }
+                let record_len = u64_at(&head, 4)
+                    .checked_add(12)
+                    .ok_or_else(|| invalid("zip64 end of central directory"))?;
+                let record = volumes.read_at(disk, offset, record_len)?;
+                if record.len() < 56 {
+                    return Err(invalid("zip64 end of central directory"));
+                }
+                Some((record, locator))
+            }
+            None => None,
+        };
+        let (cd_disk, cd_size, cd_offset) = match &zip64 {
+            Some((record, _)) => (u32_at(record, 20), u64_at(record, 40), u64_at(record, 48)),
+            None => (
+                u16_at(&eocd, 6) as u32,
+                u32_at(&eocd, 12) as u64,
+                u32_at(&eocd, 16) as u64,
+            ),
+        };
+
+        let cd = volumes.read_at(cd_disk, cd_offset, cd_size)?;
+        let mut records = Vec::new();
+        let mut pos = 0usize;
+        while pos < cd.len() {
+            if pos + 46 > cd.len() || u32_at(&cd, pos) != CENTRAL_HEADER_SIGNATURE {
+                return Err(invalid("central directory"));
+            }
+            let len = 46
+                + u16_at(&cd, pos + 28) as usize
+                + u16_at(&cd, pos + 30) as usize
+                + u16_at(&cd, pos + 32) as usize;
+            records.push(
+                cd.get(pos..pos + len)
+                    .ok_or_else(|| invalid("central directory"))?
+                    .to_vec(),
+            );
+            pos += len;
+        }
+        Ok(Directory {
+            records,
+            cd_disk,
+            cd_offset,
+            cd_size,
+            zip64,
+            eocd,
+        })
+    }
+}
+
+/// 中央目录记录中 zip64 扩展字段里各值的位置：依次为原大小、压缩后大小、本地文件头偏移、起始卷号，
+/// 只有头部对应字段为最大值时才出现。
+fn zip64_field(record: &[u8], field: usize) -> Option<usize> {
+    let flags = [
+        u32_at(record, 24) == u32::MAX,
+        u32_at(record, 20) == u32::MAX,
+        u32_at(record, 42) == u32::MAX,
+        u16_at(record, 34) == u16::MAX,
+    ];
+    if !flags[field] {
+        return None;
+    }
+    let skip: usize = flags[..field]
+        .iter()
+        .map(|&flag| if flag { 8 } else { 0 })
+        .sum();
+    let extra_start = 46 + u16_at(record, 28) as usize;
+    // 扩展字段声明的长度不可信，以记录的实际长度为界
+    let extra_end = (extra_start + u16_at(record, 30) as usize).min(record.len());
+    let mut pos = extra_start;
+    while pos + 4 <= extra_end {
+        let (id, size) = (u16_at(record, pos), u16_at(record, pos + 2) as usize);
+        if id == ZIP64_EXTRA_ID {
+            let at = pos + 4 + skip;
+            let width = if field == 3 { 4 } else { 8 };
+            return (at + width <= (pos + 4 + size).min(extra_end)).then_some(at);
+        }
+        pos += 4 + size;
+    }
+    None
+}
+
+/// 记录中的本地文件头位置：（所在卷号，卷内偏移）。
+fn record_location(record: &[u8]) -> Result<(u32, u64), String> {
+    let offset = match zip64_field(record, 2) {
+        Some(at) => u64_at(record, at),
+        None if u32_at(record, 42) == u32::MAX => return Err(invalid("zip64 extra field")),
+        None => u32_at(record, 42) as u64,
+    };
+    let disk = match zip64_field(record, 3) {
+        Some(at) => u32_at(record, at),
+        None => u16_at(record, 34) as u32,
+    };
+    Ok((disk, offset))
+}
+
+fn set_record_location(record: &mut [u8], disk: u32, offset: u64) -> Result<(), String> {
+    match zip64_field(record, 2) {
+        Some(at) => put_u64(record, at, offset),
+        None => put_u32(record, 42, offset)?,
+    }
+    match zip64_field(record, 3) {
+        Some(at) => record[at..at + 4].copy_from_slice(&disk.to_le_bytes()),
+        None => put_u16(record, 34, disk as u64)?,
+    }
+    Ok(())
+}
+
+/// 改写结束记录：`disk` 为最后一卷的卷号，`disk_entries` 为最后一卷上的中央目录记录数，
+/// `zip64_at` 为 zip64 结束记录所在的（卷号，卷内偏移）。
+fn patch_end_records(
+    directory: &mut Directory,
+    disk: u32,
+    cd_disk: u32,
+    cd_offset: u64,
+    disk_entries: u64,
+    zip64_at: (u32, u64),
+) -> Result<(), String> {
+    if let Some((record, locator)) = &mut directory.zip64 {
+        record[16..20].copy_from_slice(&disk.to_le_bytes());
+        record[20..24].copy_from_slice(&cd_disk.to_le_bytes());
+        put_u64(record, 24, disk_entries);
+        put_u64(record, 48, cd_offset);
+        locator[4..8].copy_from_slice(&zip64_at.0.to_le_bytes());
+        put_u64(locator, 8, zip64_at.1);
+        locator[16..20].copy_from_slice(&(disk + 1).to_le_bytes());
+    }
+    let eocd = &mut directory.eocd;
+    put_u16(eocd, 4, disk as u64)?;
+    put_u16(eocd, 6, cd_disk as u64)?;
+    put_u16(eocd, 8, disk_entries.min(u16::MAX as u64))?;
+    put_u32(eocd, 16, cd_offset)
+}
+
+/// 按卷写出：写满一卷换到下一卷，卷文件先按 `.zNN` 命名，结束时把最后一卷改名为 `.zip`。
+struct SpanWriter<'a> {
+    ctx: &'a JobContext,
+    parts_dir: &'a Path,
+    base_name: &'a str,
+    segment_size: u64,
+    written: u64,
+    file: BufWriter<File>,
+    paths: Vec<PathBuf>,
+}
+
+impl<'a> SpanWriter<'a> {
+    fn new(
+        ctx: &'a JobContext,
+        parts_dir: &'a Path,
+        base_name: &'a str,
+        segment_size: u64,
+    ) -> Result<Self, String> {
+        let path = volume_path(parts_dir, base_name, 1);
+        ctx.track_output(&path);
+        let file = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        Ok(SpanWriter {
+            ctx,
+            parts_dir,
+            base_name,
+            segment_size,
+            written: 0,
+            file,
+            paths: vec![path],
+        })
+    }
+
+    /// 当前写入位置：（卷号，卷内偏移）。
+    fn position(&self) -> (u32, u64) {
+        ((self.paths.len() - 1) as u32, self.written)
+    }
+
+    fn next_volume(&mut self) -> Result<(), String> {
+        self.file.flush().map_err(|e| e.to_string())?;
+        if self.paths.len() > u16::MAX as usize {
+            return Err(tr!(SpannedZipTooLarge));
+        }
+        let path = volume_path(self.parts_dir, self.base_name, self.paths.len() + 1);
+        self.ctx.track_output(&path);
+        self.file = BufWriter::new(File::create(&path).map_err(|e| e.to_string())?);
+        self.paths.push(path);
+        self.written = 0;
+        Ok(())
+    }
+
+    /// 头部记录不能跨卷：当前卷放不下 `len` 字节时换到下一卷。
+    fn reserve(&mut self, len: u64) -> Result<(), String> {
+        if self.written > 0 && self.written + len > self.segment_size {
+            self.next_volume()?;
+        }
+        Ok(())
+    }
+
+    fn write_all(&mut self, mut data: &[u8]) -> Result<(), String> {
+        while !data.is_empty() {
+            if self.written >= self.segment_size {
+                self.next_volume()?;
+            }
+            let len = data.len().min((self.segment_size - self.written) as usize);
+            self.file
+                .write_all(&data[..len])
+                .map_err(|e| e.to_string())?;
+            self.written += len as u64;
+            data = &data[len..];
+        }
+        Ok(())
+    }
+
+    fn finish(mut self) -> Result<Vec<String>, String> {
+        self.file.flush().map_err(|e| e.to_string())?;
+        drop(self.file);
+        if let Some(last) = self.paths.last_mut() {
+            let target = self.parts_dir.join(format!("{}.zip", self.base_name));
+            fs::rename(&*last, &target).map_err(|e| e.to_string())?;
+            self.ctx.track_output(&target);
+            *last = target;
+        }
+        Ok(self
+            .paths
+            .iter()
+            .map(|path| path.to_string_lossy().to_string())
+            .collect())
+    }
+}
+
+/// 第 `number` 卷（从 1 开始，不含最后一卷）的路径：`<名称>.z01`、`<名称>.z02`……
+fn volume_path(dir: &Path, base_name: &str, number: usize) -> PathBuf {
+    dir.join(format!("{}.z{:02}", base_name, number))
+}
+
+/// 把 `zip_path` 切成每卷不超过 `segment_size` 字节的分卷 zip，写入 `parts_dir`，进度阶段为 `split`。
+pub(crate) fn span_zip(
+    ctx: &JobContext,
+    zip_path: &Path,
+    parts_dir: &Path,
+    base_name: &str,
+    segment_size: u64,
+) -> Result<Vec<String>, String> {
+    let total = fs::metadata(zip_path).map_err(|e| e.to_string())?.len();
+    if total <= segment_size {
+        let target = parts_dir.join(format!("{}.zip", base_name));
+        ctx.track_output(&target);
+        fs::copy(zip_path, &target).map_err(|e| e.to_string())?;
+        return Ok(vec![target.to_string_lossy().to_string()]);
+    }
+
+    let sources = [zip_path.to_path_buf()];
+    let mut directory = Directory::read(&Volumes { paths: &sources })?;
+    let mut offsets = directory
+        .records
+        .iter()
+        .map(|record| record_location(record).map(|(_, offset)| offset))
+        .collect::<Result<Vec<_>, _>>()?;
+    offsets.sort_unstable();
+    offsets.dedup();
+
+    let mut reader = BufReader::new(File::open(zip_path).map_err(|e| e.to_string())?);
+    let mut writer = SpanWriter::new(ctx, parts_dir, base_name, segment_size)?;
+    writer.write_all(&SPAN_SIGNATURE)?;
+    let mut moved = HashMap::with_capacity(offsets.len());
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    let mut processed = 0u64;
+    for (idx, &offset) in offsets.iter().enumerate() {
+        ctx.check_cancelled()?;
+        reader
+            .seek(SeekFrom::Start(offset))
+            .map_err(|e| e.to_string())?;
+        let mut fixed = [0u8; 30];
+        reader.read_exact(&mut fixed).map_err(|e| e.to_string())?;
+        if u32_at(&fixed, 0) != LOCAL_HEADER_SIGNATURE {
+            return Err(invalid("local file header"));
+        }
+        let header_len = 30 + u16_at(&fixed, 26) as u64 + u16_at(&fixed, 28) as u64;
+        let next = offsets.get(idx + 1).copied().unwrap_or(directory.cd_offset);
+        writer.reserve(header_len)?;
+        moved.insert(offset, writer.position());
+        writer.write_all(&fixed)?;
+        // 文件头的其余部分与数据（含数据描述符）一起按原样复制
+        let mut remaining = next
+            .checked_sub(offset + 30)
+            .ok_or_else(|| invalid("local file header"))?;
+        while remaining > 0 {
+            ctx.check_cancelled()?;
+            let len = remaining.min(buffer.len() as u64) as usize;
+            reader
+                .read_exact(&mut buffer[..len])
+                .map_err(|e| e.to_string())?;
+            writer.write_all(&buffer[..len])?;
+            remaining -= len as u64;
+            processed += len as u64;
+            emit_progress(
+                ctx,
+                "split",
+                processed,
+                total,
+                writer.paths.len(),
+                0,
+                tr!(Writing),
+            );
+        }
+    }
+
+    let mut record_disks = Vec::with_capacity(directory.records.len());
+    for record in &mut directory.records {
+        let (_, offset) = record_location(record)?;
+        let (disk, moved_offset) = moved[&offset];
+        set_record_location(record, disk, moved_offset)?;
+        writer.reserve(record.len() as u64)?;
+        record_disks.push(writer.position());
+        writer.write_all(record)?;
+    }
+    let (cd_disk, cd_offset) = record_disks
+        .first()
+        .copied()
+        .unwrap_or_else(|| writer.position());
+
+    let end_len = directory
+        .zip64
+        .as_ref()
+        .map_or(0, |(record, locator)| record.len() + locator.len())
+        + directory.eocd.len();
+    writer.reserve(end_len as u64)?;
+    let zip64_at = writer.position();
+    let disk_entries = record_disks
+        .iter()
+        .filter(|(disk, _)| *disk == zip64_at.0)
+        .count() as u64;
+    patch_end_records(
+        &mut directory,
+        zip64_at.0,
+        cd_disk,
+        cd_offset,
+        disk_entries,
+        zip64_at,
+    )?;
+    if let Some((record, locator)) = &directory.zip64 {
+        writer.write_all(record)?;
+        writer.write_all(locator)?;
+    }
+    writer.write_all(&directory.eocd)?;
+    let output_files = writer.finish()?;
+    log::info!("分卷 zip：{} 卷", output_files.len());
+    Ok(output_files)
+}
+
+/// 找出分卷 zip 的全部卷：输入可以是任意一卷或所在目录，返回 `.z01`……`.zip` 的路径。
+/// Windows 上的软件可能写出大写的 `.Z01`、`.ZIP`，按最后一卷扩展名的大小写查找。
+pub(crate) fn find_volumes(input_path: &Path) -> Result<Vec<PathBuf>, String> {
+    let last = if input_path.is_dir() {
+        let mut found = Vec::new();
+        for entry in fs::read_dir(input_path).map_err(|e| e.to_string())? {
+            let path = entry.map_err(|e| e.to_string())?.path();
+            let Some(ext) = path.extension().and_then(|ext| ext.to_str()) else {
+                continue;
+            };
+            if ext.eq_ignore_ascii_case("zip") && volume_of(&path, ext, 1).is_file() {
+                found.push(path);
+            }
+        }
+        match found.len() {
+            1 => found.remove(0),
+            0 => return Err(tr!(SpannedZipNotFound)),
+            _ => return Err(tr!(SpannedZipMultiple)),
+        }
+    } else {
+        let ext = input_path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .unwrap_or_default();
+        let is_volume = ext.eq_ignore_ascii_case("zip")
+            || (ext.len() >= 3
+                && ext[..1].eq_ignore_ascii_case("z")
+                && ext[1..].bytes().all(|byte| byte.is_ascii_digit()));
+        if !is_volume {
+            return Err(tr!(SpannedZipNotFound));
+        }
+        let zip_ext = if ext.starts_with('Z') { "ZIP" } else { "zip" };
+        input_path.with_extension(zip_ext)
+    };
+    if !last.is_file() {
+        return Err(tr!(SpannedZipNotFound));
+    }
+    let ext = last
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .unwrap_or_default()
+        .to_string();
+    let mut volumes = Vec::new();
+    for number in 1.. {
+        let path = volume_of(&last, &ext, number);
+        if !path.is_file() {
+            break;
+        }
+        volumes.push(path);
+    }
+    volumes.push(last);
+    Ok(volumes)
+}
+
+/// 最后一卷 `last`（扩展名为 `zip_ext`）对应的第 `number` 卷，扩展名的大小写与 `zip_ext` 一致。
+fn volume_of(last: &Path, zip_ext: &str, number: usize) -> PathBuf {
+    let prefix = if zip_ext.starts_with('Z') { "Z" } else { "z" };
+    last.with_extension(format!("{}{:02}", prefix, number))
+}
+
+/// 输入是否为多于一卷的分卷 zip（任意一卷或只含一组分卷的目录）。
+pub(crate) fn is_spanned_set(input_path: &Path) -> bool {
+    find_volumes(input_path).is_ok_and(|volumes| volumes.len() > 1)
+}
+
+/// 把各卷当作一个普通 zip 读取：卷数据按顺序拼接（去掉分卷签名），中央目录与结束记录换成改写后的版本。
+pub(crate) struct SpannedReader {
+    body: PartReader,
+    body_len: u64,
+    tail: Vec<u8>,
+    position: u64,
+}
+
+impl SpannedReader {
+    pub(crate) fn open(volumes: &[PathBuf]) -> Result<SpannedReader, String> {
+        let first = volumes.first().ok_or_else(|| tr!(SpannedZipNotFound))?;
+        let mut signature = [0u8; 4];
+        let skip = match File::open(first).and_then(|mut file| file.read_exact(&mut signature)) {
+            Ok(()) if signature == SPAN_SIGNATURE || signature == SINGLE_SPAN_SIGNATURE => 4,
+            _ => 0,
+        };
+        let mut starts = Vec::with_capacity(volumes.len());
+        let mut lens = Vec::with_capacity(volumes.len());
+        let mut total = 0u64;
+        for path in volumes {
+            let len = fs::metadata(path).map_err(|e| e.to_string())?.len();
+            starts.push(total);
+            lens.push(len);
+            total += len;
+        }
+        let absolute = |disk: u32, offset: u64| -> Result<u64, String> {
+            let start = starts
+                .get(disk as usize)
+                .ok_or_else(|| invalid(format!("disk {}", disk + 1)))?;
+            (start + offset)
+                .checked_sub(skip)
+                .ok_or_else(|| invalid("offset"))
+        };
+
+        let mut directory = Directory::read(&Volumes { paths: volumes })?;
+        let body_len = absolute(directory.cd_disk, directory.cd_offset)?;
+        for record in &mut directory.records {
+            let (disk, offset) = record_location(record)?;
+            set_record_location(record, 0, absolute(disk, offset)?)?;
+        }
+        let entries = match &directory.zip64 {
+            Some((record, _)) => u64_at(record, 32),
+            None => u16_at(&directory.eocd, 10) as u64,
+        };
+        let zip64_offset = body_len + directory.cd_size;
+        patch_end_records(&mut directory, 0, 0, body_len, entries, (0, zip64_offset))?;
+
+        let mut segments = Vec::with_capacity(volumes.len());
+        for (idx, path) in volumes.iter().enumerate() {
+            let file_offset = if idx == 0 { skip } else { 0 };
+            let start = (starts[idx] + file_offset).saturating_sub(skip);
+            if start >= body_len {
+                break;
+            }
+            let len = (lens[idx] - file_offset).min(body_len - start);
+            segments.push(PartSegment {
+                path: path.clone(),
+                file_offset,
+                len,
+            });
+        }
+        let mut tail: Vec<u8> = directory.records.concat();
+        if let Some((record, locator)) = &directory.zip64 {
+            tail.extend_from_slice(record);
+            tail.extend_from_slice(locator);
+        }
+        tail.extend_from_slice(&directory.eocd);
+        Ok(SpannedReader {
+            body: PartReader::new(segments),
+            body_len,
+            tail,
+            position: 0,
+        })
+    }
+
+    pub(crate) fn len(&self) -> u64 {
+        self.body_len + self.tail.len() as u64
+    }
+}
+
+impl Read for SpannedReader {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read_len = if self.position < self.body_len {
+            let want = buf.len().min((self.body_len - self.position) as usize);
+            self.body.seek(SeekFrom::Start(self.position))?;
+            self.body.read(&mut buf[..want])?
+        } else {
+            let start = ((self.position - self.body_len) as usize).min(self.tail.len());
+            let len = buf.len().min(self.tail.len() - start);
+            buf[..len].copy_from_slice(&self.tail[start..start + len]);
+            len
+        };
+        self.position += read_len as u64;
+        Ok(read_len)
+    }
+}
+
+impl Seek for SpannedReader {
+    fn seek(&mut self, pos: SeekFrom) -> io::Result<u64> {
+        let target = match pos {
+            SeekFrom::Start(offset) => offset as i128,
+            SeekFrom::End(offset) => self.len() as i128 + offset as i128,
+            SeekFrom::Current(offset) => self.position as i128 + offset as i128,
+        };
+        self.position = u64::try_from(target)
+            .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "invalid seek"))?;
+        Ok(self.position)
+    }
+}
+
+/// 合并分卷 zip 为 `<名称>.zip`，可选解压到 `<名称>` 目录。
+pub(crate) fn restore(
+    ctx: &JobContext,
+    input_path: &Path,
+    output_dir: &Path,
+    password: Option<&str>,
+    auto_extract: bool,
+) -> Result<RestoreResult, String> {
+    let volumes = find_volumes(input_path)?;
+    let last = volumes.last().ok_or_else(|| tr!(SpannedZipNotFound))?;
+    let stem = last
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .ok_or_else(|| tr!(CannotParseFileName))?;
+    let mut reader = SpannedReader::open(&volumes)?;
+    let total = reader.len();
+
+    let merged_path = output_dir.join(format!("{}.zip", stem));
+    let temp_path = output_dir.join(format!("{}.zip.merge.tmp", stem));
+    ctx.track_output(&temp_path);
+    let mut writer = BufWriter::new(File::create(&temp_path).map_err(|e| e.to_string())?);
+    let mut processed = 0u64;
+    copy_n_buffered(
+        &mut reader,
+        &mut writer,
+        total,
+        ctx.memory().buffer_size(),
+        ctx.cancel_token(),
+        |delta| {
+            processed += delta;
+            emit_progress(ctx, "restore", processed, total, 0, 0, tr!(Merging));
+        },
+    )
+    .map_err(|e| e.to_string())?;
+    writer.flush().map_err(|e| e.to_string())?;
+    drop(writer);
+    if merged_path.exists() {
+        fs::remove_file(&merged_path).map_err(|e| e.to_string())?;
+    }
+    fs::rename(&temp_path, &merged_path).map_err(|e| e.to_string())?;
+    ctx.track_output(&merged_path);
+
+    let merged_file = merged_path.to_string_lossy().to_string();
+    let mut output_files = vec![merged_file.clone()];
+    let mut extracted_dir = None;
+    if auto_extract {
+        let target_dir = output_dir.join(&stem);
+        extract::extract_archive(ctx, &merged_path, &target_dir, password, None)?;
+        extracted_dir = Some(target_dir.to_string_lossy().to_string());
+        output_files.push(target_dir.to_string_lossy().to_string());
+    }
+    Ok(RestoreResult {
+        merged_file: Some(merged_file),
+        extracted_dir,
+        output_files,
+        verified: None,
+        mismatches: Vec::new(),
+        repaired_parts: Vec::new(),
+        signature: None,
+        lost_entries: Vec::new(),
+        archive_type: None,
+        detected_mode: None,
+        missing_ranges: Vec::new(),
+        conflicts: Vec::new(),
+    })
+}
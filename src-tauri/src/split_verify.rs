@@ -0,0 +1,198 @@
+//! 分割后核对（`verifyAfterSplit`）：打包完成后重新读取每一份分片，按打包方式解密、解压并拼接，
+//! 计算拼接结果的校验值并与源比对，一致才算打包成功，用于发现不稳定的 U 盘等设备上悄悄写坏的数据。
+//! 进度阶段为 `verify`。
+//!
+//! `split-then-zip`、`zip-then-split`、`raw-split`、`age-split`、`container-then-split` 与 `spanned-zip`
+//! 与完整性清单中打包时算出的 `payload` 比对（`spanned-zip` 把各卷还原为分卷前的 zip 再比对，
+//! `age-split` 只能核对以口令加密的分片，`container-then-split` 解密容器后比对）；
+//! `zstd-then-split` 的输入为普通文件时与源文件本身比对。其余情况（7z，目录或管道输入的 zstd）
+//! 没有可比对的源，改为测试其中每个文件的 CRC（参见 [`crate::test_archive`]）。
+
+use std::{
+    fs::File,
+    io::{BufReader, Read},
+    path::{Path, PathBuf},
+};
+
+use zip::ZipArchive;
+
+use crate::{
+    age_split::{self, Unseal},
+    container,
+    emit_progress,
+    i18n::tr,
+    integrity::{self, HashAlgorithm, Hasher},
+    jobs::JobContext,
+    open_zip_file,
+    part_reader::PartReader,
+    secrets::SecretString,
+    seekable::SeekableReader,
+    spanned,
+    test_archive::{self, TestArchiveOptions},
+};
+
+pub(crate) fn verify_split(
+    ctx: &JobContext,
+    pack_mode: &str,
+    input_path: &Path,
+    parts_dir: &Path,
+    output_files: &[String],
+    password: Option<&str>,
+) -> Result<(), String> {
+    let expected = match pack_mode {
+        "split-then-zip"
+        | "zip-then-split"
+        | "raw-split"
+        | "age-split"
+        | "container-then-split"
+        | "spanned-zip" => {
+            integrity::find(parts_dir)?.and_then(|(_, manifest)| {
+                manifest
+                    .payload()
+                    .map(|(size, hash)| (manifest.algorithm(), size, hash.to_string()))
+            })
+        }
+        "zstd-then-split" if input_path.is_file() => {
+            let source = File::open(input_path).map_err(|e| e.to_string())?;
+            let total = source.metadata().map_err(|e| e.to_string())?.len();
+            let (size, hash) = digest(ctx, HashAlgorithm::Sha256, source, total, &mut 0)?;
+            Some((HashAlgorithm::Sha256, size, hash))
+        }
+        _ => None,
+    };
+    let Some((algorithm, size, hash)) = expected else {
+        return test_parts(ctx, pack_mode, output_files, password);
+    };
+
+    let paths: Vec<PathBuf> = output_files.iter().map(PathBuf::from).collect();
+    let actual = match pack_mode {
+        "split-then-zip" => digest_part_entries(ctx, algorithm, &paths, password, size),
+        "age-split" => digest_age_parts(ctx, algorithm, &paths, password, size),
+        "container-then-split" => {
+            let password = password.ok_or_else(|| tr!(ContainerNeedsPassword))?;
+            let reader = container::open_parts(&paths, password)?;
+            digest(ctx, algorithm, reader, size, &mut 0)
+        }
+        "spanned-zip" => {
+            let reader = spanned::SpannedReader::open(&paths)?;
+            digest(ctx, algorithm, BufReader::new(reader), size, &mut 0)
+        }
+        "zstd-then-split" => {
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            let reader = SeekableReader::open_parts(&paths, reader).map_err(|e| tr!(SplitVerifyFailed, e))?;
+            digest(ctx, algorithm, reader, size, &mut 0)
+        }
+        _ => {
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            digest(ctx, algorithm, BufReader::new(reader), size, &mut 0)
+        }
+    }?;
+    if actual != (size, hash) {
+        return Err(tr!(SplitVerifyMismatch));
+    }
+    log::info!("分割后核对通过：{} 份分片（{:?}）", paths.len(), algorithm);
+    Ok(())
+}
+
+/// 依次解密、解压每份分片 zip 中的条目，计算拼接结果的大小与校验值。
+fn digest_part_entries(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    paths: &[PathBuf],
+    password: Option<&str>,
+    total: u64,
+) -> Result<(u64, String), String> {
+    let mut hasher = Hasher::new(ctx, algorithm);
+    let mut processed = 0u64;
+    for path in paths {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut archive =
+            ZipArchive::new(BufReader::new(file)).map_err(|e| tr!(SplitVerifyFailed, e))?;
+        let entry = open_zip_file(&mut archive, 0, password)?;
+        feed(ctx, &mut hasher, entry, total, &mut processed)?;
+    }
+    Ok((processed, hasher.finish()))
+}
+
+/// 依次以口令解密每份 age 分片，计算拼接结果的大小与校验值。
+fn digest_age_parts(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    paths: &[PathBuf],
+    password: Option<&str>,
+    total: u64,
+) -> Result<(u64, String), String> {
+    let unseal = Unseal::new(password, &[])?;
+    let mut hasher = Hasher::new(ctx, algorithm);
+    let mut processed = 0u64;
+    for path in paths {
+        feed(ctx, &mut hasher, age_split::open_part(path, &unseal)?, total, &mut processed)?;
+    }
+    Ok((processed, hasher.finish()))
+}
+
+fn digest(
+    ctx: &JobContext,
+    algorithm: HashAlgorithm,
+    reader: impl Read,
+    total: u64,
+    processed: &mut u64,
+) -> Result<(u64, String), String> {
+    let mut hasher = Hasher::new(ctx, algorithm);
+    feed(ctx, &mut hasher, reader, total, processed)?;
+    Ok((*processed, hasher.finish()))
+}
+
+/// 读完 `reader` 并计入校验值；读取出错（如 CRC 不符、解压失败）时核对失败。
+fn feed(
+    ctx: &JobContext,
+    hasher: &mut Hasher,
+    mut reader: impl Read,
+    total: u64,
+    processed: &mut u64,
+) -> Result<(), String> {
+    let mut buffer = vec![0u8; ctx.memory().buffer_size()];
+    loop {
+        ctx.check_cancelled()?;
+        let read_len = reader
+            .read(&mut buffer)
+            .map_err(|e| tr!(SplitVerifyFailed, e))?;
+        if read_len == 0 {
+            return Ok(());
+        }
+        hasher.update(&buffer[..read_len]);
+        *processed += read_len as u64;
+        emit_progress(ctx, "verify", *processed, total, 0, 0, tr!(VerifyingSplit));
+    }
+}
+
+/// 没有可比对的源时测试分片中每个文件的 CRC。
+fn test_parts(
+    ctx: &JobContext,
+    pack_mode: &str,
+    output_files: &[String],
+    password: Option<&str>,
+) -> Result<(), String> {
+    let Some(first) = output_files.first() else {
+        return Ok(());
+    };
+    let report = test_archive::test_archive_blocking(
+        ctx,
+        TestArchiveOptions {
+            input_path: first.clone(),
+            merge_mode: Some(pack_mode.to_string()),
+            password: password.map(SecretString::from),
+            keyfile: None,
+        },
+    )?;
+    if !report.ok {
+        let failures: Vec<String> = report
+            .failures
+            .iter()
+            .map(|failure| tr!(EntryCorrupt, failure.entry, failure.error))
+            .collect();
+        return Err(tr!(SplitVerifyFailed, failures.join("; ")));
+    }
+    log::info!("分割后核对通过：测试了 {} 个文件", report.entries_tested);
+    Ok(())
+}
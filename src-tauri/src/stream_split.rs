@@ -0,0 +1,131 @@
+//! 长度未知的数据流（管道、套接字、标准输入）边读边切分：写满一份即切换到下一份，
+//! 分片按出现顺序即时编号（`part-001` 或 `.001` 起，超过 999 份后自然变宽），总份数在结束时才确定。
+
+use std::{
+    cmp,
+    fs::File,
+    io::{self, BufWriter, Write},
+    path::PathBuf,
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Arc,
+    },
+};
+
+use crate::format_part_index;
+
+/// 编号的最小位数，与按已知份数切分时一致。
+const MIN_INDEX_WIDTH: usize = 3;
+
+/// 按固定大小把写入的数据切成分片文件；或只把其中一份写到标准输出。
+pub(crate) struct PartWriter {
+    part_size: u64,
+    target: PartTarget,
+    /// 当前分片序号（从 1 开始），可在写入过程中从外部读取用于汇报进度。
+    current: Arc<AtomicUsize>,
+    written: u64,
+    file: Option<BufWriter<File>>,
+    files: Vec<PathBuf>,
+}
+
+enum PartTarget {
+    Dir { dir: PathBuf, part_stem: String },
+    Stdout { part: usize, out: io::Stdout },
+}
+
+impl PartWriter {
+    /// 写入 `dir/<part_stem>001`、`<part_stem>002`……，如 `name.part-001` 或经典分卷命名的 `name.001`。
+    pub(crate) fn to_dir(part_size: u64, dir: PathBuf, part_stem: String) -> Self {
+        PartWriter::new(part_size, PartTarget::Dir { dir, part_stem })
+    }
+
+    /// 只把第 `part` 份（从 1 开始）写到标准输出，其余丢弃。
+    pub(crate) fn to_stdout(part_size: u64, part: usize) -> Self {
+        PartWriter::new(
+            part_size,
+            PartTarget::Stdout {
+                part,
+                out: io::stdout(),
+            },
+        )
+    }
+
+    fn new(part_size: u64, target: PartTarget) -> Self {
+        PartWriter {
+            part_size,
+            target,
+            current: Arc::new(AtomicUsize::new(1)),
+            written: 0,
+            file: None,
+            files: Vec::new(),
+        }
+    }
+
+    pub(crate) fn current_part(&self) -> Arc<AtomicUsize> {
+        self.current.clone()
+    }
+
+    /// 结束写入，返回实际生成的分片路径（写到标准输出时为空）。
+    pub(crate) fn finish(mut self) -> io::Result<Vec<PathBuf>> {
+        self.flush()?;
+        self.file = None;
+        Ok(self.files)
+    }
+
+    fn open_current(&mut self) -> io::Result<()> {
+        if let PartTarget::Dir { dir, part_stem } = &self.target {
+            let index = self.current.load(Ordering::Relaxed);
+            let path = dir.join(format!(
+                "{}{}",
+                part_stem,
+                format_part_index(index, MIN_INDEX_WIDTH)
+            ));
+            self.file = Some(BufWriter::new(File::create(&path)?));
+            self.files.push(path);
+        }
+        Ok(())
+    }
+}
+
+impl Write for PartWriter {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if buf.is_empty() {
+            return Ok(0);
+        }
+        if self.written == self.part_size {
+            self.flush()?;
+            self.file = None;
+            self.current.fetch_add(1, Ordering::Relaxed);
+            self.written = 0;
+        }
+        let len = cmp::min(buf.len() as u64, self.part_size - self.written) as usize;
+        let current = self.current.load(Ordering::Relaxed);
+        match &mut self.target {
+            PartTarget::Stdout { part, out } => {
+                if *part == current {
+                    out.write_all(&buf[..len])?;
+                }
+            }
+            PartTarget::Dir { .. } => {
+                if self.file.is_none() {
+                    self.open_current()?;
+                }
+                if let Some(file) = self.file.as_mut() {
+                    file.write_all(&buf[..len])?;
+                }
+            }
+        }
+        self.written += len as u64;
+        Ok(len)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        if let Some(file) = self.file.as_mut() {
+            file.flush()?;
+        }
+        if let PartTarget::Stdout { out, .. } = &mut self.target {
+            out.flush()?;
+        }
+        Ok(())
+    }
+}
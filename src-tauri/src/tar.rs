@@ -0,0 +1,982 @@
+//! tar 打包：把文件或目录写成 ustar 格式的 tar，保留 Unix 权限、属主、修改时间与符号链接，
+//! 外层再以 gzip 或 Zstandard 压缩为 `.tar.gz`、`.tar.zst`，供习惯 tar 包的 Linux 用户直接解包。
+//! 路径、链接目标或大小超出 ustar 字段的长度时写 PAX 扩展头；解包时同样识别 PAX 与 GNU 长文件名，
+//! 外层还可以是 xz 或不压缩。
+
+use flate2::{read::GzDecoder, write::GzEncoder, Compression};
+use lzma_rust2::XzReader;
+use std::{
+    cell::Cell,
+    fs::{self, File, Metadata},
+    io::{self, BufReader, BufWriter, Read, Write},
+    path::{Component, Path, PathBuf},
+    rc::Rc,
+    time::{Duration, UNIX_EPOCH},
+};
+
+use crate::{
+    copy_n_buffered, dir_total_size, emit_progress, entry_names, entry_times::set_mode, i18n::tr,
+    include_dir_entry, jobs::JobContext,
+};
+
+const BLOCK_SIZE: usize = 512;
+const NAME_LEN: usize = 100;
+
+/// 外层压缩方式。
+#[derive(Debug, Clone, Copy)]
+pub(crate) enum TarCompression {
+    /// gzip，等级 0–9。
+    Gzip(u32),
+    /// Zstandard，`workers` 大于 1 时多线程压缩。
+    Zstd { level: i32, workers: u32 },
+}
+
+impl TarCompression {
+    /// 合并方式对应的压缩方式；解压时用不到等级与线程数。
+    pub(crate) fn for_merge_mode(merge_mode: &str) -> Option<TarCompression> {
+        match merge_mode {
+            "tar-gz-then-split" => Some(TarCompression::Gzip(6)),
+            "tar-zst-then-split" => Some(TarCompression::Zstd {
+                level: 3,
+                workers: 1,
+            }),
+            _ => None,
+        }
+    }
+
+    /// 压缩包的扩展名。
+    pub(crate) fn extension(self) -> &'static str {
+        match self {
+            TarCompression::Gzip(_) => "tar.gz",
+            TarCompression::Zstd { .. } => "tar.zst",
+        }
+    }
+}
+
+/// 解包时的外层压缩，比打包多了不压缩的 tar 与 xz。
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum TarDecoding {
+    None,
+    Gzip,
+    Zstd,
+    Xz,
+}
+
+impl From<TarCompression> for TarDecoding {
+    fn from(compression: TarCompression) -> Self {
+        match compression {
+            TarCompression::Gzip(_) => TarDecoding::Gzip,
+            TarCompression::Zstd { .. } => TarDecoding::Zstd,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EntryKind {
+    File,
+    Dir,
+    Symlink,
+    HardLink,
+    /// 字符设备、FIFO 等，解包时跳过。
+    Other,
+}
+
+#[derive(Debug, Clone)]
+pub(crate) struct TarEntry {
+    pub(crate) name: String,
+    pub(crate) kind: EntryKind,
+    pub(crate) size: u64,
+    pub(crate) mode: u32,
+    pub(crate) mtime: u64,
+    pub(crate) link: Option<String>,
+}
+
+/// 写 tar 的条目头与数据，`finish` 写入结尾的两个空块。
+struct TarWriter<W: Write> {
+    inner: W,
+}
+
+impl<W: Write> TarWriter<W> {
+    fn append_header(&mut self, entry: &TarEntry, uid: u64, gid: u64) -> io::Result<()> {
+        let mut block = [0u8; BLOCK_SIZE];
+        let mut pax = Vec::new();
+        if !put_text(&mut block[..NAME_LEN], &entry.name) {
+            pax_record(&mut pax, "path", &entry.name);
+            put_text(&mut block[..NAME_LEN], &truncated(&entry.name, NAME_LEN));
+        }
+        if let Some(link) = &entry.link {
+            if !put_text(&mut block[157..257], link) {
+                pax_record(&mut pax, "linkpath", link);
+            }
+        }
+        put_octal(&mut block[100..108], u64::from(entry.mode));
+        for (field, value, key) in [(108..116, uid, "uid"), (116..124, gid, "gid")] {
+            if !put_octal(&mut block[field], value) {
+                pax_record(&mut pax, key, &value.to_string());
+            }
+        }
+        if !put_octal(&mut block[124..136], entry.size) {
+            pax_record(&mut pax, "size", &entry.size.to_string());
+        }
+        put_octal(&mut block[136..148], entry.mtime);
+        block[156] = match entry.kind {
+            EntryKind::Dir => b'5',
+            EntryKind::Symlink => b'2',
+            EntryKind::HardLink => b'1',
+            EntryKind::File | EntryKind::Other => b'0',
+        };
+        block[257..263].copy_from_slice(b"ustar\0");
+        block[263..265].copy_from_slice(b"00");
+
+        if !pax.is_empty() {
+            let mut header = [0u8; BLOCK_SIZE];
+            put_text(&mut header[..NAME_LEN], "././@PaxHeader");
+            put_octal(&mut header[100..108], 0o644);
+            put_octal(&mut header[124..136], pax.len() as u64);
+            put_octal(&mut header[136..148], entry.mtime);
+            header[156] = b'x';
+            header[257..263].copy_from_slice(b"ustar\0");
+            header[263..265].copy_from_slice(b"00");
+            set_checksum(&mut header);
+            self.inner.write_all(&header)?;
+            self.inner.write_all(&pax)?;
+            self.pad(pax.len() as u64)?;
+        }
+        set_checksum(&mut block);
+        self.inner.write_all(&block)
+    }
+
+    /// 数据不足一块时补零。
+    fn pad(&mut self, size: u64) -> io::Result<()> {
+        let rest = (BLOCK_SIZE - (size % BLOCK_SIZE as u64) as usize) % BLOCK_SIZE;
+        self.inner.write_all(&[0u8; BLOCK_SIZE][..rest])
+    }
+
+    fn finish(mut self) -> io::Result<W> {
+        self.inner.write_all(&[0u8; BLOCK_SIZE * 2])?;
+        Ok(self.inner)
+    }
+}
+
+/// 把文件或目录打包为 tar 并按 `compression` 压缩，写到 `archive_path`。
+pub(crate) fn tar_path(
+    ctx: &JobContext,
+    input_path: &Path,
+    archive_path: &Path,
+    compression: TarCompression,
+    phase: &str,
+) -> Result<(), String> {
+    let metadata = fs::metadata(input_path).map_err(|e| e.to_string())?;
+    let total_size = if metadata.is_dir() {
+        dir_total_size(ctx, input_path, input_path)?
+    } else {
+        metadata.len()
+    };
+    let root_name = input_path
+        .file_name()
+        .and_then(|name| name.to_str())
+        .ok_or_else(|| tr!(CannotParseFileName))?
+        .to_string();
+
+    let file = BufWriter::new(File::create(archive_path).map_err(|e| e.to_string())?);
+    emit_progress(ctx, phase, 0, total_size, 0, 0, tr!(Compressing));
+
+    let mut packer = Packer {
+        ctx,
+        root: input_path,
+        root_name: &root_name,
+        phase,
+        processed: 0,
+        total_size,
+    };
+    let file = match compression {
+        TarCompression::Gzip(level) => {
+            let encoder = GzEncoder::new(file, Compression::new(level.min(9)));
+            packer.write(encoder, &metadata)?.finish()
+        }
+        TarCompression::Zstd { level, workers } => {
+            let mut encoder = zstd::Encoder::new(file, level).map_err(|e| e.to_string())?;
+            encoder.include_checksum(true).map_err(|e| e.to_string())?;
+            if workers > 1 {
+                encoder.multithread(workers).map_err(|e| e.to_string())?;
+            }
+            packer.write(encoder, &metadata)?.finish()
+        }
+    };
+    file.and_then(|mut file| file.flush())
+        .map_err(|e| e.to_string())?;
+    Ok(())
+}
+
+struct Packer<'a> {
+    ctx: &'a JobContext,
+    root: &'a Path,
+    root_name: &'a str,
+    phase: &'a str,
+    processed: u64,
+    total_size: u64,
+}
+
+impl Packer<'_> {
+    /// 把整个输入写成 tar，返回写完结尾空块后的 `inner`。
+    fn write<W: Write>(&mut self, inner: W, metadata: &Metadata) -> Result<W, String> {
+        let mut tar = TarWriter { inner };
+        self.append(&mut tar, self.root, metadata)?;
+        tar.finish().map_err(|e| e.to_string())
+    }
+
+    fn append<W: Write>(
+        &mut self,
+        tar: &mut TarWriter<W>,
+        path: &Path,
+        metadata: &Metadata,
+    ) -> Result<(), String> {
+        self.ctx.check_cancelled()?;
+        let rel = path.strip_prefix(self.root).map_err(|e| e.to_string())?;
+        let name = if rel.as_os_str().is_empty() {
+            self.root_name.to_string()
+        } else {
+            format!(
+                "{}/{}",
+                self.root_name,
+                rel.to_string_lossy().replace('\\', "/")
+            )
+        };
+        let file_type = metadata.file_type();
+        let (kind, link) = if file_type.is_symlink() {
+            let target = fs::read_link(path).map_err(|e| e.to_string())?;
+            (
+                EntryKind::Symlink,
+                Some(target.to_string_lossy().replace('\\', "/")),
+            )
+        } else if file_type.is_dir() {
+            (EntryKind::Dir, None)
+        } else if file_type.is_file() {
+            (EntryKind::File, None)
+        } else {
+            log::warn!("tar 打包：跳过特殊文件 {}", path.display());
+            return Ok(());
+        };
+        let (mode, uid, gid) = unix_owner(metadata, kind);
+        let entry = TarEntry {
+            name: if kind == EntryKind::Dir {
+                format!("{}/", name)
+            } else {
+                name
+            },
+            kind,
+            size: if kind == EntryKind::File {
+                metadata.len()
+            } else {
+                0
+            },
+            mode,
+            mtime: metadata
+                .modified()
+                .ok()
+                .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+                .map_or(0, |duration| duration.as_secs()),
+            link,
+        };
+        tar.append_header(&entry, uid, gid)
+            .map_err(|e| e.to_string())?;
+
+        match kind {
+            EntryKind::File => self.append_data(tar, path, entry.size)?,
+            EntryKind::Dir => {
+                let mut children = fs::read_dir(path)
+                    .map_err(|e| e.to_string())?
+                    .collect::<Result<Vec<_>, _>>()
+                    .map_err(|e| e.to_string())?;
+                // 固定顺序，同样的输入得到同样的 tar
+                children.sort_by_key(|child| child.file_name());
+                for child in children {
+                    let child_path = child.path();
+                    // 不跟随符号链接
+                    let child_meta =
+                        fs::symlink_metadata(&child_path).map_err(|e| e.to_string())?;
+                    let is_dir = child_meta.is_dir();
+                    if !include_dir_entry(
+                        self.ctx,
+                        self.root,
+                        &child_path,
+                        is_dir,
+                        child_meta.len(),
+                    )? {
+                        continue;
+                    }
+                    self.append(tar, &child_path, &child_meta)?;
+                }
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    fn append_data<W: Write>(
+        &mut self,
+        tar: &mut TarWriter<W>,
+        path: &Path,
+        size: u64,
+    ) -> Result<(), String> {
+        let mut reader = BufReader::new(File::open(path).map_err(|e| e.to_string())?);
+        let (ctx, phase, total_size) = (self.ctx, self.phase, self.total_size);
+        let start = self.processed;
+        copy_n_buffered(
+            &mut reader,
+            &mut tar.inner,
+            size,
+            ctx.memory().buffer_size(),
+            ctx.cancel_token(),
+            |copied| {
+                emit_progress(
+                    ctx,
+                    phase,
+                    start + copied,
+                    total_size,
+                    0,
+                    0,
+                    tr!(Compressing),
+                )
+            },
+        )
+        .map_err(|e| ctx.check_cancelled().err().unwrap_or_else(|| e.to_string()))?;
+        self.processed += size;
+        tar.pad(size).map_err(|e| e.to_string())
+    }
+}
+
+#[cfg(unix)]
+fn unix_owner(metadata: &Metadata, _kind: EntryKind) -> (u32, u64, u64) {
+    use std::os::unix::fs::MetadataExt;
+    (
+        metadata.mode() & 0o7777,
+        u64::from(metadata.uid()),
+        u64::from(metadata.gid()),
+    )
+}
+
+#[cfg(not(unix))]
+fn unix_owner(_metadata: &Metadata, kind: EntryKind) -> (u32, u64, u64) {
+    match kind {
+        EntryKind::Dir => (0o755, 0, 0),
+        _ => (0o644, 0, 0),
+    }
+}
+
+/// 逐个读取 tar 条目；读完条目头后，`TarReader` 本身读出的就是该条目的数据。
+pub(crate) struct TarReader<R> {
+    inner: R,
+    remaining: u64,
+    padding: u64,
+}
+
+impl<R: Read> TarReader<R> {
+    pub(crate) fn new(inner: R) -> Self {
+        TarReader {
+            inner,
+            remaining: 0,
+            padding: 0,
+        }
+    }
+
+    /// 跳过上一条目未读的数据，读出下一个条目头；到达结尾的空块时返回 `None`。
+    pub(crate) fn next_entry(&mut self) -> io::Result<Option<TarEntry>> {
+        let (mut long_name, mut long_link, mut pax_size) = (None, None, None);
+        loop {
+            io::copy(
+                &mut (&mut self.inner).take(self.remaining + self.padding),
+                &mut io::sink(),
+            )?;
+            self.remaining = 0;
+            self.padding = 0;
+
+            let mut block = [0u8; BLOCK_SIZE];
+            if !read_block(&mut self.inner, &mut block)? || block.iter().all(|&byte| byte == 0) {
+                return Ok(None);
+            }
+            if !checksum_matches(&block) {
+                return Err(io::Error::new(
+                    io::ErrorKind::InvalidData,
+                    tr!(TarHeaderCorrupt),
+                ));
+            }
+            let size = pax_size.take().unwrap_or(parse_number(&block[124..136])?);
+            self.remaining = size;
+            self.padding = (BLOCK_SIZE as u64 - size % BLOCK_SIZE as u64) % BLOCK_SIZE as u64;
+
+            match block[156] {
+                // PAX 扩展头与 GNU 长文件名，作用于下一个条目
+                b'x' | b'L' | b'K' => {
+                    let mut data = Vec::new();
+                    (&mut self.inner).take(size).read_to_end(&mut data)?;
+                    self.remaining = 0;
+                    if block[156] == b'x' {
+                        for (key, value) in parse_pax(&data) {
+                            match key.as_str() {
+                                "path" => long_name = Some(value),
+                                "linkpath" => long_link = Some(value),
+                                "size" => pax_size = value.parse().ok(),
+                                _ => {}
+                            }
+                        }
+                    } else {
+                        let text = String::from_utf8_lossy(&data)
+                            .trim_end_matches('\0')
+                            .to_string();
+                        if block[156] == b'L' {
+                            long_name = Some(text);
+                        } else {
+                            long_link = Some(text);
+                        }
+                    }
+                    continue;
+                }
+                // 全局 PAX 头
+                b'g' => continue,
+                _ => {}
+            }
+
+            let name = long_name.take().unwrap_or_else(|| {
+                let name = field_text(&block[..NAME_LEN]);
+                let prefix = field_text(&block[345..500]);
+                if &block[257..262] == b"ustar" && !prefix.is_empty() {
+                    format!("{}/{}", prefix, name)
+                } else {
+                    name
+                }
+            });
+            let kind = match block[156] {
+                b'0' | b'\0' | b'7' => EntryKind::File,
+                b'5' => EntryKind::Dir,
+                b'2' => EntryKind::Symlink,
+                b'1' => EntryKind::HardLink,
+                _ => EntryKind::Other,
+            };
+            let link = matches!(kind, EntryKind::Symlink | EntryKind::HardLink).then(|| {
+                long_link
+                    .take()
+                    .unwrap_or_else(|| field_text(&block[157..257]))
+            });
+            return Ok(Some(TarEntry {
+                kind: if kind == EntryKind::File && name.ends_with('/') {
+                    EntryKind::Dir
+                } else {
+                    kind
+                },
+                name,
+                size,
+                mode: parse_number(&block[100..108])? as u32,
+                mtime: parse_number(&block[136..148])?,
+                link,
+            }));
+        }
+    }
+}
+
+impl<R: Read> Read for TarReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let limit = buf
+            .len()
+            .min(self.remaining.min(usize::MAX as u64) as usize);
+        if limit == 0 {
+            return Ok(0);
+        }
+        let read_len = self.inner.read(&mut buf[..limit])?;
+        if read_len == 0 {
+            return Err(io::ErrorKind::UnexpectedEof.into());
+        }
+        self.remaining -= read_len as u64;
+        Ok(read_len)
+    }
+}
+
+/// 按外层压缩打开解压后的数据流。
+pub(crate) fn open_decoder<'a, R: Read + 'a>(
+    reader: R,
+    decoding: TarDecoding,
+) -> io::Result<Box<dyn Read + 'a>> {
+    Ok(match decoding {
+        TarDecoding::None => Box::new(reader),
+        TarDecoding::Gzip => Box::new(GzDecoder::new(reader)),
+        TarDecoding::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        TarDecoding::Xz => Box::new(XzReader::new(reader, true)),
+    })
+}
+
+/// 解包 tar 压缩包到 `output_dir`，恢复权限与修改时间（Unix 上）。指向解包目录之外的链接不会创建，
+/// 经由之前解出的链接写到解包目录之外的条目也会跳过。
+pub(crate) fn extract_tar(
+    ctx: &JobContext,
+    archive_path: &Path,
+    output_dir: &Path,
+    decoding: TarDecoding,
+) -> Result<(), String> {
+    if !output_dir.exists() {
+        ctx.track_output(output_dir);
+    }
+    fs::create_dir_all(output_dir).map_err(|e| e.to_string())?;
+    let root = fs::canonicalize(output_dir).map_err(|e| e.to_string())?;
+    let file = File::open(archive_path).map_err(|e| e.to_string())?;
+    let total_bytes = file.metadata().map_err(|e| e.to_string())?.len();
+    let read_bytes = Rc::new(Cell::new(0u64));
+    let counter = CountingReader {
+        inner: BufReader::new(file),
+        count: Rc::clone(&read_bytes),
+    };
+    let decoder = open_decoder(counter, decoding).map_err(|e| e.to_string())?;
+    let mut reader = TarReader::new(decoder);
+    // 目录的权限与时间在其中的文件写完后再设置，以免只读目录挡住后续写入
+    let mut dirs = Vec::new();
+    let mut index = 0usize;
+
+    while let Some(entry) = reader.next_entry().map_err(|e| e.to_string())? {
+        ctx.check_cancelled()?;
+        index += 1;
+        let Some(relative) =
+            entry_names::enclosed(&entry.name).filter(|name| !name.as_os_str().is_empty())
+        else {
+            log::warn!("tar 解包：跳过不安全的路径 {}", entry.name);
+            continue;
+        };
+        // 上级目录按真实位置逐级创建，之前解出的链接不能把条目带到解包目录之外
+        let dir = match entry.kind {
+            EntryKind::Dir => relative.as_path(),
+            _ => relative.parent().unwrap_or(Path::new("")),
+        };
+        let Some(parent) = dir_inside(&root, dir).map_err(|e| e.to_string())? else {
+            log::warn!(
+                "tar 解包：跳过经由链接指向解包目录之外的条目 {}",
+                entry.name
+            );
+            continue;
+        };
+        let out_path = parent.join(relative.file_name().unwrap_or_default());
+        // 已有的符号链接或硬链接不跟随，先删掉再写
+        if matches!(
+            entry.kind,
+            EntryKind::File | EntryKind::Symlink | EntryKind::HardLink
+        ) && fs::symlink_metadata(&out_path).is_ok_and(|meta| !meta.is_dir())
+        {
+            fs::remove_file(&out_path).map_err(|e| e.to_string())?;
+        }
+        match entry.kind {
+            EntryKind::Dir => dirs.push((parent, entry)),
+            EntryKind::File => {
+                let mut writer =
+                    BufWriter::new(File::create(&out_path).map_err(|e| e.to_string())?);
+                copy_n_buffered(
+                    &mut reader,
+                    &mut writer,
+                    entry.size,
+                    ctx.memory().buffer_size(),
+                    ctx.cancel_token(),
+                    |_| {},
+                )
+                .and_then(|()| writer.flush())
+                .map_err(|e| ctx.check_cancelled().err().unwrap_or_else(|| e.to_string()))?;
+                let file = writer.into_inner().map_err(|e| e.to_string())?;
+                restore_metadata(&out_path, Some(&file), &entry);
+            }
+            EntryKind::Symlink | EntryKind::HardLink => {
+                let link = entry.link.as_deref().unwrap_or_default();
+                // 符号链接相对其所在目录，硬链接相对解包目录，且硬链接的目标须是已解出的文件
+                let target = match entry.kind {
+                    EntryKind::Symlink => resolve_link(&root, &parent, link),
+                    _ => resolve_link(&root, &root, link).filter(|target| target.is_file()),
+                };
+                let Some(target) = target else {
+                    log::warn!(
+                        "tar 解包：跳过指向解包目录之外的链接 {} -> {}",
+                        entry.name,
+                        link
+                    );
+                    continue;
+                };
+                let created = if entry.kind == EntryKind::HardLink {
+                    fs::hard_link(target, &out_path)
+                } else {
+                    create_symlink(link, &out_path)
+                };
+                if let Err(err) = created {
+                    log::warn!("tar 解包：无法创建链接 {}：{}", entry.name, err);
+                }
+            }
+            EntryKind::Other => {
+                log::warn!("tar 解包：跳过特殊文件 {}", entry.name);
+            }
+        }
+        emit_progress(
+            ctx,
+            "unzip",
+            read_bytes.get(),
+            total_bytes,
+            index,
+            0,
+            tr!(Extracting),
+        );
+    }
+    for (path, entry) in dirs.iter().rev() {
+        restore_metadata(path, None, entry);
+    }
+    Ok(())
+}
+
+/// 记下已读取的压缩数据字节数，用于显示解包进度。
+pub(crate) struct CountingReader<R> {
+    pub(crate) inner: R,
+    pub(crate) count: Rc<Cell<u64>>,
+}
+
+impl<R: Read> Read for CountingReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let read_len = self.inner.read(buf)?;
+        self.count.set(self.count.get() + read_len as u64);
+        Ok(read_len)
+    }
+}
+
+fn restore_metadata(path: &Path, file: Option<&File>, entry: &TarEntry) {
+    let modified = UNIX_EPOCH + Duration::from_secs(entry.mtime);
+    let result = match file {
+        Some(file) => file.set_modified(modified),
+        None => File::open(path).and_then(|dir| dir.set_modified(modified)),
+    };
+    if let Err(err) = result {
+        log::debug!("tar 解包：无法设置修改时间 {}：{}", path.display(), err);
+    }
+    set_mode(path, entry.mode);
+}
+
+#[cfg(unix)]
+fn create_symlink(target: &str, link: &Path) -> io::Result<()> {
+    std::os::unix::fs::symlink(target, link)
+}
+
+#[cfg(not(unix))]
+fn create_symlink(_target: &str, _link: &Path) -> io::Result<()> {
+    Err(io::ErrorKind::Unsupported.into())
+}
+
+/// 在解包目录 `root`（真实路径）中逐级创建目录 `relative`，返回其真实路径。已有的一级是符号链接时
+/// 跟随到真实位置，真实位置在 `root` 之外或链接悬空时返回 `None`。
+fn dir_inside(root: &Path, relative: &Path) -> io::Result<Option<PathBuf>> {
+    let mut current = root.to_path_buf();
+    for component in relative.components() {
+        let next = current.join(component);
+        match fs::symlink_metadata(&next) {
+            Ok(meta) if meta.is_symlink() => match fs::canonicalize(&next) {
+                Ok(real) if real.starts_with(root) && real.is_dir() => current = real,
+                _ => return Ok(None),
+            },
+            Ok(_) => current = next,
+            Err(err) if err.kind() == io::ErrorKind::NotFound => {
+                fs::create_dir(&next)?;
+                current = next;
+            }
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(Some(current))
+}
+
+/// 从 `base`（真实路径）出发解析链接目标，真实位置超出 `root` 或为绝对路径时返回 `None`。
+/// 已存在的部分跟随其中的链接；尚不存在的部分不能含 `..`，以免之后解出的链接把它带到外面。
+fn resolve_link(root: &Path, base: &Path, link: &str) -> Option<PathBuf> {
+    let mut resolved = base.to_path_buf();
+    let mut missing = false;
+    for component in Path::new(&link.replace('\\', "/")).components() {
+        match component {
+            Component::Normal(part) => {
+                resolved.push(part);
+                if !missing {
+                    match fs::canonicalize(&resolved) {
+                        Ok(real) => resolved = real,
+                        Err(_) => missing = true,
+                    }
+                }
+            }
+            Component::CurDir => {}
+            Component::ParentDir if !missing => {
+                resolved.pop();
+            }
+            _ => return None,
+        }
+        if !resolved.starts_with(root) {
+            return None;
+        }
+    }
+    Some(resolved)
+}
+
+/// 读满一块；数据在块的开头就结束时返回 `false`。
+fn read_block(reader: &mut impl Read, block: &mut [u8; BLOCK_SIZE]) -> io::Result<bool> {
+    let mut filled = 0;
+    while filled < BLOCK_SIZE {
+        match reader.read(&mut block[filled..]) {
+            Ok(0) if filled == 0 => return Ok(false),
+            Ok(0) => return Err(io::ErrorKind::UnexpectedEof.into()),
+            Ok(read_len) => filled += read_len,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => {}
+            Err(err) => return Err(err),
+        }
+    }
+    Ok(true)
+}
+
+/// 写入以 NUL 结尾（或正好填满）的文本字段；放不下或不是 ASCII 时返回 `false`。
+fn put_text(field: &mut [u8], text: &str) -> bool {
+    if text.len() > field.len() || !text.is_ascii() {
+        return false;
+    }
+    field[..text.len()].copy_from_slice(text.as_bytes());
+    true
+}
+
+/// 放不下时的 ustar 字段内容，真正的值在 PAX 头中。
+fn truncated(text: &str, len: usize) -> String {
+    text.chars().filter(char::is_ascii).take(len).collect()
+}
+
+/// 写入以 NUL 结尾的八进制数字；放不下时返回 `false`。
+fn put_octal(field: &mut [u8], value: u64) -> bool {
+    let digits = field.len() - 1;
+    let text = format!("{:0width$o}", value, width = digits);
+    if text.len() > digits {
+        return false;
+    }
+    field[..digits].copy_from_slice(text.as_bytes());
+    field[digits] = 0;
+    true
+}
+
+/// 八进制数字，或 GNU 的 base-256 编码（首字节最高位为 1）。
+fn parse_number(field: &[u8]) -> io::Result<u64> {
+    if field.first().is_some_and(|&byte| byte & 0x80 != 0) {
+        let value = field[1..]
+            .iter()
+            .fold(u64::from(field[0] & 0x7f), |value, &byte| {
+                (value << 8) | u64::from(byte)
+            });
+        return Ok(value);
+    }
+    let text = field_text(field);
+    let text = text.trim();
+    if text.is_empty() {
+        return Ok(0);
+    }
+    u64::from_str_radix(text, 8)
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, tr!(TarHeaderCorrupt)))
+}
+
+fn field_text(field: &[u8]) -> String {
+    let end = field
+        .iter()
+        .position(|&byte| byte == 0)
+        .unwrap_or(field.len());
+    String::from_utf8_lossy(&field[..end]).to_string()
+}
+
+fn set_checksum(block: &mut [u8; BLOCK_SIZE]) {
+    block[148..156].fill(b' ');
+    let sum: u32 = block.iter().map(|&byte| u32::from(byte)).sum();
+    let text = format!("{:06o}\0 ", sum);
+    block[148..156].copy_from_slice(text.as_bytes());
+}
+
+/// 数据开头是否为 tar 的条目头：不全为零且校验和正确，不要求 ustar 标记。
+pub(crate) fn is_tar_header(data: &[u8]) -> bool {
+    let Some(block) = data
+        .get(..BLOCK_SIZE)
+        .and_then(|block| <&[u8; BLOCK_SIZE]>::try_from(block).ok())
+    else {
+        return false;
+    };
+    block.iter().any(|&byte| byte != 0) && checksum_matches(block)
+}
+
+fn checksum_matches(block: &[u8; BLOCK_SIZE]) -> bool {
+    let Ok(expected) = parse_number(&block[148..156]) else {
+        return false;
+    };
+    let sum: u64 = block
+        .iter()
+        .enumerate()
+        .map(|(index, &byte)| {
+            if (148..156).contains(&index) {
+                u64::from(b' ')
+            } else {
+                u64::from(byte)
+            }
+        })
+        .sum();
+    sum == expected
+}
+
+/// PAX 记录：`<长度> <键>=<值>\n`，长度包含其本身。
+fn pax_record(out: &mut Vec<u8>, key: &str, value: &str) {
+    let body = format!(" {}={}\n", key, value);
+    let mut len = body.len() + 1;
+    while len.to_string().len() + body.len() > len {
+        len += 1;
+    }
+    out.extend_from_slice(format!("{}{}", len, body).as_bytes());
+}
+
+fn parse_pax(data: &[u8]) -> Vec<(String, String)> {
+    let mut records = Vec::new();
+    let mut rest = data;
+    while let Some(space) = rest.iter().position(|&byte| byte == b' ') {
+        let Some(len) = std::str::from_utf8(&rest[..space])
+            .ok()
+            .and_then(|len| len.parse::<usize>().ok())
+            .filter(|len| *len > space && *len <= rest.len())
+        else {
+            break;
+        };
+        let record = String::from_utf8_lossy(&rest[space + 1..len]);
+        if let Some((key, value)) = record.trim_end_matches('\n').split_once('=') {
+            records.push((key.to_string(), value.to_string()));
+        }
+        rest = &rest[len..];
+    }
+    records
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use std::os::unix::fs::symlink;
+
+    /// 临时目录下的解包目录 `out` 与其旁边的 `outside`，返回两者的真实路径。
+    fn temp_root(name: &str) -> (PathBuf, PathBuf) {
+        let dir = std::env::temp_dir().join(format!("fspt-{}-{}", name, std::process::id()));
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(dir.join("out")).unwrap();
+        fs::create_dir_all(dir.join("outside")).unwrap();
+        (
+            fs::canonicalize(dir.join("out")).unwrap(),
+            fs::canonicalize(dir.join("outside")).unwrap(),
+        )
+    }
+
+    fn remove_root(root: &Path) {
+        if let Some(dir) = root.parent() {
+            let _ = fs::remove_dir_all(dir);
+        }
+    }
+
+    fn entry(name: &str, kind: EntryKind, size: u64, link: Option<&str>) -> TarEntry {
+        TarEntry {
+            name: name.to_string(),
+            kind,
+            size,
+            mode: if kind == EntryKind::Dir { 0o755 } else { 0o644 },
+            mtime: 0,
+            link: link.map(str::to_string),
+        }
+    }
+
+    /// 按顺序写出条目，文件条目的内容为 `data`。
+    fn write_tar(path: &Path, entries: &[(TarEntry, &[u8])]) {
+        let mut tar = TarWriter { inner: Vec::new() };
+        for (entry, data) in entries {
+            tar.append_header(entry, 0, 0).unwrap();
+            tar.inner.extend_from_slice(data);
+            tar.pad(data.len() as u64).unwrap();
+        }
+        fs::write(path, tar.finish().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn parent_dir_targets_stay_inside_root() {
+        let (root, outside) = temp_root("parent");
+        fs::create_dir_all(root.join("a/b")).unwrap();
+        let base = root.join("a/b");
+
+        assert_eq!(resolve_link(&root, &base, "../c"), Some(root.join("a/c")));
+        assert_eq!(resolve_link(&root, &base, "../../c"), Some(root.join("c")));
+        assert_eq!(resolve_link(&root, &base, "../../../outside"), None);
+        assert_eq!(resolve_link(&root, &root, "../outside/x"), None);
+        // 尚不存在的部分之后可能被链接替换，其中的 `..` 不能信任
+        assert_eq!(resolve_link(&root, &root, "missing/../c"), None);
+        // 已存在的链接跟随到真实位置再判断
+        symlink(&outside, root.join("escape")).unwrap();
+        assert_eq!(resolve_link(&root, &root, "escape/x"), None);
+
+        remove_root(&root);
+    }
+
+    #[test]
+    fn absolute_targets_are_rejected() {
+        let (root, outside) = temp_root("absolute");
+
+        assert_eq!(resolve_link(&root, &root, "/etc/passwd"), None);
+        assert_eq!(resolve_link(&root, &root, &outside.to_string_lossy()), None);
+        assert_eq!(
+            resolve_link(&root, &root, &root.join("a").to_string_lossy()),
+            None
+        );
+        // 条目名称去掉开头的根，仍落在解包目录内
+        assert_eq!(
+            entry_names::enclosed("/etc/passwd"),
+            Some(PathBuf::from("etc/passwd"))
+        );
+        assert_eq!(entry_names::enclosed("../a"), None);
+
+        remove_root(&root);
+    }
+
+    #[test]
+    fn dir_inside_follows_only_inner_links() {
+        let (root, outside) = temp_root("dir-inside");
+        fs::create_dir(root.join("real")).unwrap();
+        symlink(root.join("real"), root.join("inner")).unwrap();
+        symlink(&outside, root.join("escape")).unwrap();
+        symlink(root.join("gone"), root.join("dangling")).unwrap();
+
+        assert_eq!(
+            dir_inside(&root, Path::new("inner/sub")).unwrap(),
+            Some(root.join("real/sub"))
+        );
+        assert!(root.join("real/sub").is_dir());
+        assert_eq!(dir_inside(&root, Path::new("escape")).unwrap(), None);
+        assert_eq!(dir_inside(&root, Path::new("escape/sub")).unwrap(), None);
+        assert_eq!(dir_inside(&root, Path::new("dangling/sub")).unwrap(), None);
+        assert!(!outside.join("sub").exists());
+
+        remove_root(&root);
+    }
+
+    #[test]
+    fn files_written_through_symlinks_stay_inside_root() {
+        let (root, outside) = temp_root("through-link");
+        let archive = root.parent().unwrap().join("links.tar");
+        let abs = outside.to_string_lossy().to_string();
+        write_tar(
+            &archive,
+            &[
+                (entry("real", EntryKind::Dir, 0, None), b""),
+                (entry("inner", EntryKind::Symlink, 0, Some("real")), b""),
+                (entry("inner/kept.txt", EntryKind::File, 4, None), b"kept"),
+                (entry("up", EntryKind::Symlink, 0, Some("../outside")), b""),
+                (entry("up/evil.txt", EntryKind::File, 4, None), b"evil"),
+                (entry("abs", EntryKind::Symlink, 0, Some(&abs)), b""),
+                (entry("abs/evil.txt", EntryKind::File, 4, None), b"evil"),
+                (entry("../evil.txt", EntryKind::File, 4, None), b"evil"),
+            ],
+        );
+
+        extract_tar(&JobContext::detached(), &archive, &root, TarDecoding::None).unwrap();
+
+        assert_eq!(fs::read(root.join("real/kept.txt")).unwrap(), b"kept");
+        assert!(root.join("inner").is_symlink());
+        // 指向外面的链接不创建，之后经由它的同名路径落在解包目录内的普通目录里
+        assert!(!root.join("up").is_symlink());
+        assert!(!root.join("abs").is_symlink());
+        assert_eq!(fs::read_dir(&outside).unwrap().count(), 0);
+        assert!(!root.parent().unwrap().join("evil.txt").exists());
+
+        remove_root(&root);
+    }
+}
@@ -0,0 +1,512 @@
+//! 测试压缩包：不写出任何文件，逐个解密、解压分片组或合并后的压缩包中的每个文件并核对 CRC，
+//! 相当于 `7z t`，可在下载完成后、占用磁盘空间合并之前确认分片可用。进度阶段为 `test`。
+//!
+//! 某个文件出错时记下原因后继续测试其余文件；7z 固实块中的数据解压出错时，同一块中其后的文件
+//! 无法再读取，一并记为出错。出错的文件同时给出其数据所在的分片与分片内的字节区间，
+//! 只需重新下载这几份分片。
+
+use serde::{Deserialize, Serialize};
+use sevenz_rust::{Archive, BlockDecoder, Password};
+use std::{
+    fs::File,
+    io::{self, BufReader, Read, Seek},
+    path::{Path, PathBuf},
+};
+use zip::ZipArchive;
+
+use crate::{
+    append, collect_part_group, detect, emit_progress,
+    i18n::tr,
+    index::{self, IndexedPart, PartRange},
+    integrity, is_zip_file,
+    jobs::JobContext,
+    keyfile, open_zip_file, parse_part_name,
+    part_reader::PartReader,
+    partial,
+    secrets::SecretString,
+    seekable::SeekableReader,
+    sevenz::{map_sevenz_error, SEVENZ_SIGNATURE},
+    spanned,
+    tar::{self, TarCompression, TarReader},
+    PartGroup,
+};
+
+/// 7z 起始头的长度，打包数据的位置从其后算起。
+const SEVENZ_HEADER_SIZE: u64 = 32;
+const GZIP_SIGNATURE: [u8; 2] = [0x1f, 0x8b];
+const ZSTD_SIGNATURE: [u8; 4] = [0x28, 0xb5, 0x2f, 0xfd];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TestArchiveOptions {
+    /// 分片文件、分片目录，或合并后的 zip / 7z 文件。
+    pub(crate) input_path: String,
+    /// 分片的打包方式；输入为合并后的压缩包时不需要。
+    pub(crate) merge_mode: Option<String>,
+    pub(crate) password: Option<SecretString>,
+    /// 打包时使用的密钥文件。
+    pub(crate) keyfile: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TestReport {
+    /// 测试过的文件数（不含目录）。
+    pub(crate) entries_tested: usize,
+    /// 解压后读过的总字节数。
+    pub(crate) bytes_tested: u64,
+    pub(crate) failures: Vec<TestFailure>,
+    pub(crate) ok: bool,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct TestFailure {
+    /// 出错的文件；先分割后压缩的分片本身出错时为分片文件名。
+    pub(crate) entry: String,
+    pub(crate) error: String,
+    /// 出错数据所在的分片与分片内的字节区间；7z 固实块给出整个块的区间，
+    /// 分片经过压缩或加密时给出该分片的整个数据区。
+    pub(crate) locations: Vec<PartRange>,
+}
+
+/// 把出错数据在拼接数据中的位置换算为分片文件内的字节区间。
+enum Locator {
+    /// 分片（或单个文件）的布局。
+    Parts(Vec<IndexedPart>),
+    /// zstd 解压后的位置先按帧换算为压缩数据中的位置。
+    Zstd(Vec<IndexedPart>, SeekableReader<PartReader>),
+}
+
+impl Locator {
+    fn locate(&self, start: u64, end: u64) -> Vec<PartRange> {
+        match self {
+            Locator::Parts(parts) => index::locate_range(parts, start, end),
+            Locator::Zstd(parts, reader) => {
+                let (start, end) = reader.compressed_range(start, end);
+                index::locate_range(parts, start, end)
+            }
+        }
+    }
+}
+
+/// 逐个读完条目并累计进度与出错的条目。
+struct Tester<'a> {
+    ctx: &'a JobContext,
+    password: Option<&'a str>,
+    locator: Option<Locator>,
+    total_bytes: u64,
+    processed: u64,
+    entries_tested: usize,
+    failures: Vec<TestFailure>,
+}
+
+impl Tester<'_> {
+    /// `range` 为出错数据在当前读取的数据中的区间。
+    fn fail(
+        &mut self,
+        entry: impl Into<String>,
+        error: impl Into<String>,
+        range: Option<(u64, u64)>,
+    ) {
+        let locations = match (&self.locator, range) {
+            (Some(locator), Some((start, end))) => locator.locate(start, end),
+            _ => Vec::new(),
+        };
+        let failure = TestFailure {
+            entry: entry.into(),
+            error: error.into(),
+            locations,
+        };
+        log::warn!(
+            "测试未通过：{}（{}）{}",
+            failure.entry,
+            failure.error,
+            index::describe_ranges(&failure.locations)
+        );
+        self.failures.push(failure);
+    }
+
+    /// 解压 tar 并读完其中每个文件。压缩流中途出错后无法继续，其后的文件不再测试。
+    fn test_tar(&mut self, source: impl Read, compression: TarCompression) -> Result<(), String> {
+        let decoder = tar::open_decoder(source, compression.into()).map_err(|e| e.to_string())?;
+        let mut reader = TarReader::new(decoder);
+        loop {
+            let entry = match reader.next_entry() {
+                Ok(Some(entry)) => entry,
+                Ok(None) => return Ok(()),
+                Err(err) => {
+                    self.fail(tr!(TestTarStream), err.to_string(), None);
+                    return Ok(());
+                }
+            };
+            if entry.kind != tar::EntryKind::File {
+                continue;
+            }
+            self.entries_tested += 1;
+            self.total_bytes += entry.size;
+            let result = self.drain(&mut reader);
+            self.ctx.check_cancelled()?;
+            if let Err(err) = result {
+                self.fail(entry.name, err.to_string(), None);
+                return Ok(());
+            }
+        }
+    }
+
+    /// 读完 `reader` 但不保存；取消时提前返回，由调用方检查。
+    fn drain(&mut self, reader: &mut dyn Read) -> io::Result<()> {
+        let mut buffer = vec![0u8; self.ctx.memory().buffer_size()];
+        loop {
+            if self.ctx.is_cancelled() {
+                return Ok(());
+            }
+            let read_len = match reader.read(&mut buffer) {
+                Ok(0) => return Ok(()),
+                Ok(read_len) => read_len,
+                Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+                Err(err) => return Err(err),
+            };
+            self.processed += read_len as u64;
+            emit_progress(
+                self.ctx,
+                "test",
+                self.processed,
+                self.total_bytes,
+                self.entries_tested,
+                0,
+                tr!(TestingArchive),
+            );
+        }
+    }
+
+    fn test_zip<R: Read + Seek>(&mut self, archive: &mut ZipArchive<R>) -> Result<(), String> {
+        for index in 0..archive.len() {
+            if let Ok(entry) = archive.by_index_raw(index) {
+                self.total_bytes += entry.size();
+            }
+        }
+        for index in 0..archive.len() {
+            let name = archive
+                .name_for_index(index)
+                .unwrap_or_default()
+                .to_string();
+            let result = match open_zip_file(archive, index, self.password) {
+                Ok(mut entry) if !entry.is_dir() => {
+                    self.entries_tested += 1;
+                    self.drain(&mut entry).map_err(|e| e.to_string())
+                }
+                Ok(_) => Ok(()),
+                Err(err) => {
+                    self.entries_tested += 1;
+                    Err(err)
+                }
+            };
+            self.ctx.check_cancelled()?;
+            if let Err(err) = result {
+                let range = archive
+                    .by_index_raw(index)
+                    .ok()
+                    .map(|raw| (raw.data_start(), raw.data_start() + raw.compressed_size()));
+                self.fail(name, err, range);
+            }
+        }
+        Ok(())
+    }
+
+    fn test_sevenz<R: Read + Seek>(&mut self, mut source: R, len: u64) -> Result<(), String> {
+        let password = self
+            .password
+            .map(Password::from)
+            .unwrap_or_else(Password::empty);
+        let archive =
+            Archive::read(&mut source, len, password.as_slice()).map_err(map_sevenz_error)?;
+        self.total_bytes += archive.files.iter().map(|entry| entry.size()).sum::<u64>();
+        for folder_index in 0..archive.folders.len() {
+            let decoder =
+                BlockDecoder::new(folder_index, &archive, password.as_slice(), &mut source);
+            let names: Vec<String> = decoder
+                .entries()
+                .iter()
+                .filter(|entry| !entry.is_directory())
+                .map(|entry| entry.name().to_string())
+                .collect();
+            let first_pack = archive.stream_map.folder_first_pack_stream_index[folder_index];
+            let pack_count = archive.folders[folder_index].packed_streams.len();
+            let start = SEVENZ_HEADER_SIZE
+                + archive.pack_pos
+                + archive.stream_map.pack_stream_offsets[first_pack];
+            let end = start
+                + archive.pack_sizes[first_pack..first_pack + pack_count]
+                    .iter()
+                    .sum::<u64>();
+            let mut reached = 0usize;
+            let result = decoder.for_each_entries(&mut |entry, reader| {
+                if entry.is_directory() {
+                    return Ok(true);
+                }
+                reached += 1;
+                self.entries_tested += 1;
+                match self.drain(reader) {
+                    Ok(()) => Ok(!self.ctx.is_cancelled()),
+                    Err(err) => {
+                        // CRC 不符时数据流本身完好，可以继续读后面的文件
+                        let checksum = err
+                            .get_ref()
+                            .and_then(|inner| inner.downcast_ref::<sevenz_rust::Error>())
+                            .is_some_and(|inner| {
+                                matches!(inner, sevenz_rust::Error::ChecksumVerificationFailed)
+                            });
+                        self.fail(entry.name(), err.to_string(), Some((start, end)));
+                        Ok(checksum)
+                    }
+                }
+            });
+            self.ctx.check_cancelled()?;
+            // 数据块无法解码（如密码错误）或中途出错：块中尚未读到的文件都记为出错
+            let error = match result {
+                Ok(_) => tr!(TestBlockBroken),
+                Err(err) => map_sevenz_error(err),
+            };
+            for name in &names[reached..] {
+                self.entries_tested += 1;
+                self.fail(name.clone(), error.clone(), Some((start, end)));
+            }
+        }
+        Ok(())
+    }
+}
+
+pub(crate) fn test_archive_blocking(
+    ctx: &JobContext,
+    options: TestArchiveOptions,
+) -> Result<TestReport, String> {
+    let input_path = PathBuf::from(&options.input_path);
+    if !input_path.exists() {
+        return Err(tr!(InputPartsNotFound));
+    }
+    let password =
+        keyfile::effective_password(options.password.as_deref(), options.keyfile.as_deref())?;
+    let mut tester = Tester {
+        ctx,
+        password: password.as_deref(),
+        locator: None,
+        total_bytes: 0,
+        processed: 0,
+        entries_tested: 0,
+        failures: Vec::new(),
+    };
+    emit_progress(ctx, "test", 0, 0, 0, 0, tr!(TestingArchive));
+
+    let merge_mode = detect::resolve(options.merge_mode.as_deref(), &input_path)?;
+    let merge_mode = merge_mode.as_deref().unwrap_or_default();
+    let is_part = input_path.is_dir()
+        || input_path
+            .file_name()
+            .and_then(|name| name.to_str())
+            .and_then(parse_part_name)
+            .is_some();
+    let spanned_zip = merge_mode == "spanned-zip"
+        || (merge_mode == "zip-then-split" && spanned::is_spanned_set(&input_path));
+    if spanned_zip {
+        // 各卷拼成的普通 zip 直接在内存中改写中央目录，不写出临时文件
+        let volumes = spanned::find_volumes(&input_path)?;
+        let reader = spanned::SpannedReader::open(&volumes)?;
+        let mut archive = ZipArchive::new(BufReader::new(reader)).map_err(|e| e.to_string())?;
+        tester.test_zip(&mut archive)?;
+    } else if is_part {
+        // 追加过的分片目录逐批测试
+        let groups = match append::load_manifest(&input_path)? {
+            Some((_, manifest)) if input_path.is_dir() && merge_mode == "zip-then-split" => {
+                manifest
+                    .first_parts(&input_path)
+                    .iter()
+                    .map(|first| collect_part_group(first))
+                    .collect::<Result<Vec<_>, _>>()?
+            }
+            _ => vec![collect_part_group(&input_path)?],
+        };
+        for group in &groups {
+            test_group(&mut tester, group, merge_mode)?;
+        }
+        narrow_locations(ctx, &input_path, &mut tester.failures)?;
+    } else {
+        test_merged(&mut tester, &input_path)?;
+    }
+
+    log::info!(
+        "测试压缩包：{}（{} 个文件，{} 个出错）",
+        input_path.display(),
+        tester.entries_tested,
+        tester.failures.len()
+    );
+    Ok(TestReport {
+        entries_tested: tester.entries_tested,
+        bytes_tested: tester.processed,
+        ok: tester.failures.is_empty(),
+        failures: tester.failures,
+    })
+}
+
+/// 出错的文件跨多份分片时，用完整性清单中的校验值找出其中真正损坏的分片。
+fn narrow_locations(
+    ctx: &JobContext,
+    input_path: &Path,
+    failures: &mut [TestFailure],
+) -> Result<(), String> {
+    let mut names: Vec<String> = failures
+        .iter()
+        .filter(|failure| failure.locations.len() > 1)
+        .flat_map(|failure| failure.locations.iter().map(|range| range.part.clone()))
+        .collect();
+    names.sort();
+    names.dedup();
+    if names.is_empty() {
+        return Ok(());
+    }
+    let Some((parts_dir, integrity)) = integrity::find(input_path)? else {
+        return Ok(());
+    };
+    let damaged = integrity::damaged_parts(ctx, &parts_dir, &integrity, &names)?;
+    for failure in failures.iter_mut() {
+        if failure
+            .locations
+            .iter()
+            .any(|range| damaged.contains(&range.part))
+        {
+            failure
+                .locations
+                .retain(|range| damaged.contains(&range.part));
+        }
+    }
+    Ok(())
+}
+
+/// 合并后的 zip、7z、tar.gz 或 tar.zst 文件。
+fn test_merged(tester: &mut Tester, path: &Path) -> Result<(), String> {
+    let mut signature = [0u8; 6];
+    let mut file = File::open(path).map_err(|e| e.to_string())?;
+    tester.locator = Some(Locator::Parts(vec![index::whole_file(path)?]));
+    let read_len = file.read(&mut signature).map_err(|e| e.to_string())?;
+    if read_len == signature.len() && signature == SEVENZ_SIGNATURE {
+        let len = file.metadata().map_err(|e| e.to_string())?.len();
+        file.rewind().map_err(|e| e.to_string())?;
+        return tester.test_sevenz(BufReader::new(file), len);
+    }
+    let tar_mode = if read_len >= 2 && signature[..2] == GZIP_SIGNATURE {
+        Some("tar-gz-then-split")
+    } else if read_len >= 4 && signature[..4] == ZSTD_SIGNATURE {
+        Some("tar-zst-then-split")
+    } else {
+        None
+    };
+    if let Some(compression) = tar_mode.and_then(TarCompression::for_merge_mode) {
+        file.rewind().map_err(|e| e.to_string())?;
+        return tester.test_tar(BufReader::new(file), compression);
+    }
+    if !is_zip_file(path)? {
+        return Err(tr!(TestUnsupportedArchive, path.display()));
+    }
+    file.rewind().map_err(|e| e.to_string())?;
+    let mut archive = ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string())?;
+    tester.test_zip(&mut archive)
+}
+
+fn test_group(tester: &mut Tester, part_group: &PartGroup, merge_mode: &str) -> Result<(), String> {
+    let paths: Vec<PathBuf> = part_group
+        .parts
+        .iter()
+        .map(|part| part.path.clone())
+        .collect();
+    if merge_mode != "split-then-zip" {
+        tester.locator = Some(Locator::Parts(index::part_layout(part_group, merge_mode)?));
+    }
+    match merge_mode {
+        "split-then-zip" => test_split_then_zip(tester, part_group),
+        "zip-then-split" => {
+            tester.test_zip(&mut partial::open_part_archive(part_group, merge_mode)?)
+        }
+        "7z-then-split" => {
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            let len = paths
+                .iter()
+                .map(|path| path.metadata().map(|meta| meta.len()))
+                .sum::<io::Result<u64>>()
+                .map_err(|e| e.to_string())?;
+            tester.test_sevenz(reader, len)
+        }
+        "zstd-then-split" => {
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            let mut reader = SeekableReader::open_parts(&paths, reader).map_err(|e| e.to_string())?;
+            if let Some(Locator::Parts(layout)) = tester.locator.take() {
+                let frames = PartReader::from_files(&paths)
+                    .and_then(|reader| SeekableReader::open_parts(&paths, reader))
+                    .map_err(|e| e.to_string())?;
+                tester.locator = Some(Locator::Zstd(layout, frames));
+            }
+            let mut signature = [0u8; 4];
+            let is_zip = reader.read_exact(&mut signature).is_ok() && signature == *b"PK\x03\x04";
+            if is_zip {
+                return tester.test_zip(&mut partial::open_part_archive(part_group, merge_mode)?);
+            }
+            reader.rewind().map_err(|e| e.to_string())?;
+            tester.total_bytes += reader.len();
+            tester.entries_tested += 1;
+            let before = tester.processed;
+            let result = tester.drain(&mut reader);
+            tester.ctx.check_cancelled()?;
+            if let Err(err) = result {
+                // 出错位置所在的帧
+                let offset = tester.processed - before;
+                tester.fail(
+                    part_group.prefix.trim_end_matches('.'),
+                    err.to_string(),
+                    Some((offset, offset + 1)),
+                );
+            }
+            Ok(())
+        }
+        _ => {
+            let compression = TarCompression::for_merge_mode(merge_mode)
+                .ok_or_else(|| tr!(UnknownMergeMode))?;
+            let reader = PartReader::from_files(&paths).map_err(|e| e.to_string())?;
+            tester.test_tar(BufReader::new(reader), compression)
+        }
+    }
+}
+
+/// 先分割后压缩：每份分片是一个 zip，先测试分片本身；分片都完好且拼接出的内容是未压缩、
+/// 未加密的 zip 时，再测试其中的文件。
+fn test_split_then_zip(tester: &mut Tester, part_group: &PartGroup) -> Result<(), String> {
+    let failed = tester.failures.len();
+    for part in &part_group.parts {
+        let name = part
+            .path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        // 分片本身的位置即分片文件内的位置
+        let whole = index::whole_file(&part.path)?;
+        let len = whole.len;
+        tester.locator = Some(Locator::Parts(vec![whole]));
+        let archive = File::open(&part.path)
+            .map_err(|e| e.to_string())
+            .and_then(|file| ZipArchive::new(BufReader::new(file)).map_err(|e| e.to_string()));
+        match archive {
+            Ok(mut archive) if archive.len() == 1 => tester.test_zip(&mut archive)?,
+            Ok(_) => tester.fail(name, tr!(PartArchiveInvalid), Some((0, len))),
+            Err(err) => tester.fail(name, err, Some((0, len))),
+        }
+    }
+    if tester.failures.len() > failed {
+        return Ok(());
+    }
+    let layout = index::part_layout(part_group, "split-then-zip")?;
+    let archive = partial::open_archive_from_layout(part_group, "split-then-zip", &layout);
+    tester.locator = Some(Locator::Parts(layout));
+    match archive {
+        Ok(mut archive) => tester.test_zip(&mut archive),
+        // 内容不是 zip，或分片经过压缩、加密而无法直接读取：分片的 CRC 已覆盖全部内容
+        Err(_) => Ok(()),
+    }
+}
@@ -0,0 +1,245 @@
+//! 按实测设备速度调整复制参数：任务开始时读取源的开头一段、在目标目录写入一个临时探测文件，
+//! 以较慢一端的吞吐量选择缓冲区大小与并发数。机械硬盘、SMB 共享等慢速设备使用小缓冲区、单线程，
+//! 避免多线程交错读写造成频繁寻道；NVMe 等高速设备使用大缓冲区、满并发。
+//!
+//! `benchmark_disk` 命令用更多数据测得的速度会按目录记住，之后的任务直接使用，不再探测。
+
+use rand::RngCore;
+use serde::Serialize;
+use std::{
+    collections::HashMap,
+    fs::{self, File},
+    io::{self, Read, Write},
+    path::{Path, PathBuf},
+    process,
+    sync::{Mutex, OnceLock},
+    time::{Duration, Instant},
+};
+
+use crate::{i18n::tr, memory::MemoryBudget, source};
+
+/// 每端最多读写的探测数据量。
+const PROBE_SIZE: usize = 8 * 1024 * 1024;
+const PROBE_CHUNK_SIZE: usize = 1024 * 1024;
+/// 慢速设备上探测最多耗时，达到后按已传输的数据计算。
+const PROBE_TIME_LIMIT: Duration = Duration::from_secs(1);
+/// 探测数据少于该值时（如源文件很小）结果不可信，不据此调整。
+const MIN_PROBE_BYTES: u64 = 1024 * 1024;
+/// 低于该速度（字节/秒）视为机械硬盘或网络共享。
+const SLOW_DEVICE_SPEED: f64 = 150.0 * 1024.0 * 1024.0;
+/// 高于该速度视为 NVMe 等高速设备。
+const FAST_DEVICE_SPEED: f64 = 1000.0 * 1024.0 * 1024.0;
+const SLOW_BUFFER_SIZE: usize = 1024 * 1024;
+const MEDIUM_BUFFER_SIZE: usize = 4 * 1024 * 1024;
+const FAST_BUFFER_SIZE: usize = 16 * 1024 * 1024;
+/// `benchmark_disk` 默认每项读写的数据量。
+pub(crate) const DEFAULT_BENCHMARK_SIZE: u64 = 256 * 1024 * 1024;
+/// `benchmark_disk` 每项最多耗时。
+const BENCHMARK_TIME_LIMIT: Duration = Duration::from_secs(5);
+
+static MEASURED: OnceLock<Mutex<HashMap<PathBuf, DiskSpeed>>> = OnceLock::new();
+
+fn measured() -> &'static Mutex<HashMap<PathBuf, DiskSpeed>> {
+    MEASURED.get_or_init(|| Mutex::new(HashMap::new()))
+}
+
+/// 一个路径的顺序读写速度（字节/秒），无法测量的一项为 `None`。
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct DiskSpeed {
+    pub(crate) path: String,
+    pub(crate) read_bytes_per_sec: Option<f64>,
+    pub(crate) write_bytes_per_sec: Option<f64>,
+}
+
+/// 按设备速度选择的缓冲区大小与并发上限。
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct IoLimits {
+    pub(crate) buffer_size: usize,
+    /// `None` 表示不额外限制并发。
+    pub(crate) max_workers: Option<usize>,
+}
+
+impl IoLimits {
+    pub(crate) fn for_speed(bytes_per_sec: f64) -> Self {
+        if bytes_per_sec < SLOW_DEVICE_SPEED {
+            IoLimits {
+                buffer_size: SLOW_BUFFER_SIZE,
+                max_workers: Some(1),
+            }
+        } else if bytes_per_sec < FAST_DEVICE_SPEED {
+            IoLimits {
+                buffer_size: MEDIUM_BUFFER_SIZE,
+                max_workers: None,
+            }
+        } else {
+            IoLimits {
+                buffer_size: FAST_BUFFER_SIZE,
+                max_workers: None,
+            }
+        }
+    }
+}
+
+/// 探测 `source`（可省略，如标准输入）与目标目录 `dest` 的速度，在 `memory` 的基础上
+/// 叠加设备限制。两端都无法探测时原样返回。
+pub(crate) fn tune(memory: MemoryBudget, source: Option<&Path>, dest: &Path) -> MemoryBudget {
+    let read = source.and_then(|path| {
+        remembered(path)
+            .and_then(|speed| speed.read_bytes_per_sec)
+            .or_else(|| probe_read(path))
+    });
+    let write = remembered(dest)
+        .and_then(|speed| speed.write_bytes_per_sec)
+        .or_else(|| probe_write(dest));
+    let speed = match (read, write) {
+        (Some(read), Some(write)) => read.min(write),
+        (Some(speed), None) | (None, Some(speed)) => speed,
+        (None, None) => {
+            log::debug!("设备测速未得到有效结果，使用默认缓冲区与并发");
+            return memory;
+        }
+    };
+    let limits = IoLimits::for_speed(speed);
+    log::info!(
+        "设备测速：读取 {}，写入 {}；缓冲区 {} 字节，并发上限 {}",
+        format_speed(read),
+        format_speed(write),
+        limits.buffer_size,
+        limits
+            .max_workers
+            .map(|workers| workers.to_string())
+            .unwrap_or_else(|| "不限".to_string())
+    );
+    memory.with_io_limits(limits.buffer_size, limits.max_workers)
+}
+
+fn format_speed(speed: Option<f64>) -> String {
+    match speed {
+        Some(speed) => format!("{:.1} MiB/s", speed / (1024.0 * 1024.0)),
+        None => "未测".to_string(),
+    }
+}
+
+/// 测量 `path` 的顺序读写速度并记住结果，供之后的任务调整参数。读取的是该文件
+/// （目录取其中第一个非空文件），写入的是其所在目录下的临时文件。
+pub(crate) fn benchmark(path: &Path, size: u64) -> Result<DiskSpeed, String> {
+    if !path.exists() {
+        return Err(tr!(BenchmarkPathNotFound, path.display()));
+    }
+    if source::is_stream_source(path) {
+        return Err(tr!(BenchmarkStreamPath, path.display()));
+    }
+    let size = size.max(MIN_PROBE_BYTES);
+    let read = measure_read(path, size, BENCHMARK_TIME_LIMIT);
+    let write = speed_dir(path).and_then(|dir| measure_write(dir, size, BENCHMARK_TIME_LIMIT));
+    let result = DiskSpeed {
+        path: path.to_string_lossy().to_string(),
+        read_bytes_per_sec: read,
+        write_bytes_per_sec: write,
+    };
+    log::info!(
+        "磁盘测速 {}：读取 {}，写入 {}",
+        path.display(),
+        format_speed(read),
+        format_speed(write)
+    );
+    if let (Some(dir), Ok(mut map)) = (speed_dir(path), measured().lock()) {
+        map.insert(dir.to_path_buf(), result.clone());
+    }
+    Ok(result)
+}
+
+/// 测速结果按目录记录：文件取其所在目录。
+fn speed_dir(path: &Path) -> Option<&Path> {
+    if path.is_dir() {
+        Some(path)
+    } else {
+        path.parent().filter(|dir| !dir.as_os_str().is_empty())
+    }
+}
+
+fn remembered(path: &Path) -> Option<DiskSpeed> {
+    let dir = speed_dir(path)?;
+    measured().lock().ok()?.get(dir).cloned()
+}
+
+/// 读取源文件（目录取其中第一个非空文件）开头最多 `PROBE_SIZE` 字节，返回字节/秒。
+pub(crate) fn probe_read(path: &Path) -> Option<f64> {
+    measure_read(path, PROBE_SIZE as u64, PROBE_TIME_LIMIT)
+}
+
+/// 在目录中写入临时探测文件并同步到磁盘，返回字节/秒。
+pub(crate) fn probe_write(dir: &Path) -> Option<f64> {
+    measure_write(dir, PROBE_SIZE as u64, PROBE_TIME_LIMIT)
+}
+
+/// 管道与套接字读取后无法回退，不做测量。
+fn measure_read(path: &Path, size: u64, limit: Duration) -> Option<f64> {
+    if source::is_stream_source(path) {
+        return None;
+    }
+    let file_path = if path.is_dir() {
+        first_file(path)?
+    } else {
+        path.to_path_buf()
+    };
+    let mut file = File::open(file_path).ok()?;
+    let mut buffer = vec![0u8; PROBE_CHUNK_SIZE];
+    let started = Instant::now();
+    let mut total = 0u64;
+    while total < size && started.elapsed() < limit {
+        let read = file.read(&mut buffer).ok()?;
+        if read == 0 {
+            break;
+        }
+        total += read as u64;
+    }
+    speed(total, started.elapsed())
+}
+
+/// 探测文件写完并同步到磁盘后删除。
+fn measure_write(dir: &Path, size: u64, limit: Duration) -> Option<f64> {
+    let path = dir.join(format!(".file-split-packer-probe-{}.tmp", process::id()));
+    let result = write_probe_file(&path, size, limit);
+    let _ = fs::remove_file(&path);
+    result.ok().flatten()
+}
+
+fn write_probe_file(path: &Path, size: u64, limit: Duration) -> io::Result<Option<f64>> {
+    // 随机数据，避免带透明压缩的文件系统（ZFS、btrfs）把测速结果放大
+    let mut chunk = vec![0u8; PROBE_CHUNK_SIZE];
+    rand::thread_rng().fill_bytes(&mut chunk);
+    let mut file = File::create(path)?;
+    let started = Instant::now();
+    let mut total = 0u64;
+    while total < size && started.elapsed() < limit {
+        file.write_all(&chunk)?;
+        total += chunk.len() as u64;
+    }
+    file.sync_all()?;
+    Ok(speed(total, started.elapsed()))
+}
+
+fn speed(bytes: u64, elapsed: Duration) -> Option<f64> {
+    if bytes < MIN_PROBE_BYTES {
+        return None;
+    }
+    Some(bytes as f64 / elapsed.as_secs_f64().max(1e-6))
+}
+
+/// 深度优先查找目录中第一个非空普通文件。
+fn first_file(dir: &Path) -> Option<PathBuf> {
+    let mut entries: Vec<PathBuf> = fs::read_dir(dir)
+        .ok()?
+        .flatten()
+        .map(|entry| entry.path())
+        .collect();
+    entries.sort();
+    let (files, dirs): (Vec<PathBuf>, Vec<PathBuf>) =
+        entries.into_iter().partition(|path| path.is_file());
+    files
+        .into_iter()
+        .find(|path| fs::metadata(path).map(|meta| meta.len() > 0).unwrap_or(false))
+        .or_else(|| dirs.iter().find_map(|dir| first_file(dir)))
+}
@@ -0,0 +1,357 @@
+//! 打包参数校验：一次检查全部字段，返回每个字段的问题，界面可以把错误显示在对应的输入框旁。
+//! 字段名与前端参数一致（camelCase）。
+
+use serde::Serialize;
+use std::path::Path;
+
+use crate::{
+    age_split,
+    i18n::{tr, Msg},
+    integrity::HashAlgorithm,
+    erasure, memory, parity, parse_aes_strength, parse_compression_method, plugins,
+    power::PowerAction, script, signing, source, spanned, SplitOptions,
+};
+
+const PACK_MODES: [&str; 10] = [
+    "split-then-zip",
+    "zip-then-split",
+    "7z-then-split",
+    "zstd-then-split",
+    "tar-gz-then-split",
+    "tar-zst-then-split",
+    "raw-split",
+    "age-split",
+    "container-then-split",
+    "spanned-zip",
+];
+const DIR_SPLIT_MODES: [&str; 2] = ["compress-split-store", "store-split-compress"];
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct FieldError {
+    pub(crate) field: &'static str,
+    pub(crate) message: String,
+}
+
+#[derive(Default)]
+struct Errors(Vec<FieldError>);
+
+impl Errors {
+    fn add(&mut self, field: &'static str, message: impl Into<String>) {
+        self.0.push(FieldError {
+            field,
+            message: message.into(),
+        });
+    }
+}
+
+/// 检查打包参数，返回全部字段错误；为空表示通过。
+pub(crate) fn validate_split(options: &SplitOptions) -> Vec<FieldError> {
+    let mut errors = Errors::default();
+    let input_path = Path::new(&options.input_path);
+    let input_exists = !options.input_path.is_empty() && input_path.exists();
+    if options.input_path.is_empty() {
+        errors.add("inputPath", Msg::FieldRequired.text());
+    } else if !input_exists {
+        errors.add("inputPath", Msg::InputFileNotFound.text());
+    }
+    let is_stream = input_exists && source::is_stream_source(input_path);
+    let is_dir = input_exists && !is_stream && input_path.is_dir();
+
+    let output_dir = Path::new(&options.output_dir);
+    if options.output_dir.is_empty() {
+        errors.add("outputDir", Msg::FieldRequired.text());
+    } else if output_dir.exists() && !output_dir.is_dir() {
+        errors.add("outputDir", Msg::OutputDirNotDir.text());
+    }
+
+    match options.split_by.as_str() {
+        "size" => match options.size_bytes {
+            None => errors.add("sizeBytes", Msg::MissingPartSize.text()),
+            Some(0) => errors.add("sizeBytes", Msg::PartSizeNotPositive.text()),
+            Some(_) => {}
+        },
+        "count" => match options.count {
+            None => errors.add("count", Msg::MissingPartCount.text()),
+            Some(0) => errors.add("count", Msg::PartCountNotPositive.text()),
+            Some(_) => {}
+        },
+        _ => errors.add("splitBy", Msg::UnknownSplitBy.text()),
+    }
+
+    let pack_mode = options.pack_mode.as_str();
+    let keyfile = options.keyfile.as_deref().filter(|value| !value.is_empty());
+    let has_password = options
+        .password
+        .as_deref()
+        .is_some_and(|value| !value.is_empty())
+        || options
+            .secret_job_id
+            .as_deref()
+            .is_some_and(|value| !value.is_empty())
+        || keyfile.is_some();
+    if let Some(keyfile) = keyfile {
+        if !Path::new(keyfile).is_file() {
+            errors.add("keyfile", tr!(KeyfileNotFound, keyfile));
+        }
+    }
+    if !PACK_MODES.contains(&pack_mode) {
+        errors.add("packMode", Msg::UnknownPackMode.text());
+    } else if is_stream && pack_mode != "zstd-then-split" {
+        errors.add("packMode", Msg::StreamRequiresZstd.text());
+    }
+    if pack_mode == "zstd-then-split" && has_password {
+        errors.add("password", Msg::ZstdNoPassword.text());
+    }
+    if pack_mode.starts_with("tar-") && has_password {
+        errors.add("password", Msg::TarNoPassword.text());
+    }
+    if pack_mode == "raw-split" {
+        if has_password {
+            errors.add("password", Msg::RawSplitNoPassword.text());
+        }
+        if is_dir {
+            errors.add("inputPath", Msg::RawSplitNeedsFile.text());
+        }
+    }
+    if pack_mode == "container-then-split" && !has_password {
+        errors.add("password", Msg::ContainerNeedsPassword.text());
+    }
+    let has_gpg_recipients = options
+        .gpg_recipients
+        .as_deref()
+        .is_some_and(|values| values.iter().any(|value| !value.trim().is_empty()));
+    let age_recipients = options
+        .age_recipients
+        .as_deref()
+        .filter(|values| values.iter().any(|value| !value.trim().is_empty()));
+    if pack_mode == "age-split" {
+        if is_dir {
+            errors.add("inputPath", Msg::AgeSplitNeedsFile.text());
+        }
+        match age_recipients {
+            Some(values) => {
+                if has_password {
+                    errors.add("password", Msg::AgeSplitKeyConflict.text());
+                }
+                if let Err(message) = age_split::parse_recipients(values) {
+                    errors.add("ageRecipients", message);
+                }
+                if options.verify_after_split.unwrap_or(false) {
+                    errors.add("verifyAfterSplit", Msg::AgeVerifyNeedsPassphrase.text());
+                }
+            }
+            None if !has_password => errors.add("password", Msg::AgeSplitNeedsKey.text()),
+            None => {}
+        }
+    } else if age_recipients.is_some() {
+        errors.add("ageRecipients", Msg::AgeRecipientsNeedAgeSplit.text());
+    }
+    if options.destroy_source.unwrap_or(false) {
+        if is_stream {
+            errors.add("destroySource", Msg::DestroySourceNeedsPath.text());
+        } else if is_dir && is_inside(output_dir, input_path) {
+            errors.add("destroySource", Msg::DestroySourceContainsOutput.text());
+        } else if is_dir && script_filters(options.script_path.as_deref()) {
+            errors.add("destroySource", Msg::DestroySourceFiltered.text());
+        }
+        if (pack_mode == "age-split" && age_recipients.is_some()) || has_gpg_recipients {
+            errors.add("destroySource", Msg::DestroySourceNeedsVerify.text());
+        }
+        if options
+            .filter_command
+            .as_deref()
+            .is_some_and(|value| !value.trim().is_empty())
+        {
+            errors.add("destroySource", Msg::DestroySourceFilterCommand.text());
+        }
+    }
+    if pack_mode == "spanned-zip" {
+        if options.split_by != "size" {
+            errors.add("splitBy", Msg::SpannedZipNeedsSize.text());
+        } else if options.size_bytes.is_some_and(|size| {
+            !(spanned::MIN_SEGMENT_SIZE..=spanned::MAX_SEGMENT_SIZE).contains(&size)
+        }) {
+            errors.add("sizeBytes", Msg::SpannedZipSegmentSize.text());
+        }
+        if options
+            .filter_command
+            .as_deref()
+            .is_some_and(|value| !value.trim().is_empty())
+        {
+            errors.add("filterCommand", Msg::SpannedZipNoFilter.text());
+        }
+        if has_gpg_recipients {
+            errors.add("gpgRecipients", Msg::SpannedZipNoGpg.text());
+        }
+    }
+
+    if let Some(mode) = options.dir_split_mode.as_deref().filter(|value| !value.is_empty()) {
+        if !DIR_SPLIT_MODES.contains(&mode) {
+            errors.add("dirSplitMode", tr!(UnknownDirSplitMode, mode));
+        } else if mode == "store-split-compress"
+            && pack_mode == "split-then-zip"
+            && options.split_by == "size"
+            && is_dir
+        {
+            errors.add("dirSplitMode", Msg::MaxSizeRequiresStore.text());
+        }
+    }
+
+    if let Some(max) = options.max_memory {
+        if max < memory::MIN_MEMORY {
+            errors.add("maxMemory", tr!(MaxMemoryTooSmall, memory::MIN_MEMORY));
+        }
+    }
+
+    if let Some(path) = options.script_path.as_deref().filter(|value| !value.is_empty()) {
+        if !Path::new(path).is_file() {
+            errors.add("scriptPath", tr!(ScriptNotFound, path));
+        }
+    }
+
+    if let Some(names) = options.post_processors.as_deref().filter(|names| !names.is_empty()) {
+        let available = plugins::list_plugins();
+        for name in names {
+            if !available.iter().any(|plugin| &plugin.name == name) {
+                errors.add("postProcessors", tr!(PluginNotFound, name));
+            }
+        }
+    }
+
+    if let Some(action) = options.power_action.as_deref() {
+        if PowerAction::parse(action).is_none() {
+            errors.add("powerAction", tr!(UnknownPowerAction, action));
+        }
+    }
+
+    if let Some(algorithm) = options.hash_algorithm.as_deref() {
+        if HashAlgorithm::parse(algorithm).is_none() {
+            errors.add("hashAlgorithm", tr!(UnknownHashAlgorithm, algorithm));
+        }
+    }
+
+    if options.par2_redundancy.is_some_and(|value| value > parity::MAX_REDUNDANCY) {
+        errors.add("par2Redundancy", tr!(Par2RedundancyTooHigh, parity::MAX_REDUNDANCY));
+    }
+    if options
+        .redundancy_parts
+        .is_some_and(|count| count > erasure::MAX_RECOVERY_PARTS)
+    {
+        errors.add("redundancyParts", tr!(RecoveryPartsTooMany, erasure::MAX_RECOVERY_PARTS));
+    } else if options.redundancy_parts.is_some_and(|count| count > 0)
+        && options.xor_parity.unwrap_or(false)
+    {
+        errors.add("xorParity", Msg::XorParityWithRecoveryParts.text());
+    }
+
+    if let Some(method) = options.compression_method.as_deref().filter(|value| !value.is_empty()) {
+        if parse_compression_method(method).is_none() {
+            errors.add("compressionMethod", tr!(UnknownCompressionMethod, method));
+        } else if method != "deflate" && !matches!(pack_mode, "split-then-zip" | "zip-then-split" | "spanned-zip") {
+            errors.add("compressionMethod", Msg::CompressionMethodNeedsZip.text());
+        }
+    }
+    if let Some(bits) = options.aes_strength {
+        if parse_aes_strength(bits).is_none() {
+            errors.add("aesStrength", tr!(UnknownAesStrength, bits));
+        } else if bits != 256
+            && !matches!(pack_mode, "split-then-zip" | "zip-then-split" | "spanned-zip")
+        {
+            errors.add("aesStrength", Msg::AesStrengthNeedsZip.text());
+        }
+    }
+    if options.encrypt_names.unwrap_or(false) {
+        if !has_password {
+            errors.add("encryptNames", Msg::EncryptNamesNeedsPassword.text());
+        } else if !matches!(pack_mode, "zip-then-split" | "7z-then-split") {
+            errors.add("encryptNames", Msg::EncryptNamesNeedsArchive.text());
+        }
+    }
+
+    if options.seven_zip_volumes.unwrap_or(false) && pack_mode != "7z-then-split" {
+        errors.add("sevenZipVolumes", Msg::SevenZipVolumesNeeds7z.text());
+    }
+    if options.per_part_keys.unwrap_or(false) && pack_mode != "container-then-split" {
+        errors.add("perPartKeys", Msg::PerPartKeysNeedsContainer.text());
+    }
+    if options.numbered_volumes.unwrap_or(false)
+        && matches!(pack_mode, "split-then-zip" | "age-split" | "spanned-zip")
+    {
+        errors.add("numberedVolumes", Msg::NumberedVolumesNeedsByteSplit.text());
+    }
+    if options.self_extracting.unwrap_or(false)
+        && options
+            .filter_command
+            .as_deref()
+            .is_some_and(|value| !value.trim().is_empty())
+    {
+        errors.add("selfExtracting", Msg::SelfExtractingNoFilter.text());
+    }
+    if options.rejoin_scripts.unwrap_or(false) {
+        if matches!(pack_mode, "split-then-zip" | "age-split" | "spanned-zip") {
+            errors.add("rejoinScripts", Msg::RejoinScriptsNeedsByteSplit.text());
+        } else if options
+            .filter_command
+            .as_deref()
+            .is_some_and(|value| !value.trim().is_empty())
+        {
+            errors.add("rejoinScripts", Msg::RejoinScriptsNoFilter.text());
+        } else if has_gpg_recipients {
+            errors.add("rejoinScripts", Msg::RejoinScriptsNoGpg.text());
+        }
+    }
+    if options.zstd_dictionary.unwrap_or(false) && pack_mode != "zstd-then-split" {
+        errors.add("zstdDictionary", Msg::ZstdDictionaryNeedsZstd.text());
+    }
+
+    if let Some(key_path) = options.signing_key.as_deref().filter(|value| !value.is_empty()) {
+        if !matches!(
+            options.pack_mode.as_str(),
+            "split-then-zip"
+                | "zip-then-split"
+                | "raw-split"
+                | "age-split"
+                | "container-then-split"
+                | "spanned-zip"
+                | "zstd-then-split"
+        ) {
+            errors.add("signingKey", Msg::SigningNeedsManifest.text());
+        } else if let Err(message) = signing::check_signing_key(Path::new(key_path)) {
+            errors.add("signingKey", message);
+        }
+    }
+
+    errors.0
+}
+
+/// `path` 是否位于 `dir` 之中（含相同）；`path` 尚不存在时按其最近的已存在上级判断。
+fn is_inside(path: &Path, dir: &Path) -> bool {
+    let (Ok(dir), Ok(path)) = (dir.canonicalize(), std::path::absolute(path)) else {
+        return false;
+    };
+    path.ancestors()
+        .find_map(|ancestor| ancestor.canonicalize().ok())
+        .is_some_and(|ancestor| ancestor.starts_with(&dir))
+}
+
+/// 脚本定义了 `filter` 钩子时，打包的可能只是源目录的一部分。无法加载的脚本打包开始时即报错，此处不管。
+fn script_filters(script_path: Option<&str>) -> bool {
+    script_path
+        .filter(|value| !value.is_empty())
+        .and_then(|path| script::ScriptHooks::load(Path::new(path)).ok())
+        .is_some_and(|hooks| hooks.has_filter())
+}
+
+/// 校验失败时把全部字段错误合并成一条错误信息，供只接受字符串错误的调用方使用。
+pub(crate) fn check_split(options: &SplitOptions) -> Result<(), String> {
+    let errors = validate_split(options);
+    if errors.is_empty() {
+        return Ok(());
+    }
+    let details: Vec<String> = errors
+        .iter()
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect();
+    Err(tr!(InvalidOptions, details.join("; ")))
+}
@@ -0,0 +1,250 @@
+//! 监视目录（默认关闭）：定时扫描指定目录，出现新的文件或文件夹时按保存的打包参数
+//! 加入任务队列（参见 [`crate::queue`]），由队列调用 `process_file_blocking` 打包，进度事件与界面发起的打包相同。
+//!
+//! 用扫描而不是文件系统通知：导出程序往往要写很久，新条目的大小与修改时间（文件夹为递归统计）
+//! 连续一段时间不变才视为写完。开始监视时已有的条目、隐藏文件、常见的下载临时文件
+//! 以及输出目录与 `.parts` 目录都不处理；条目删除后再次出现会重新处理。
+
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::{
+    collections::{HashMap, HashSet},
+    fs,
+    path::{Path, PathBuf},
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, Mutex, OnceLock,
+    },
+    thread,
+    time::{Duration, Instant},
+};
+use tauri::{AppHandle, Emitter};
+
+use crate::{checkpoint, i18n::tr, queue, validation, SplitOptions};
+
+const DEFAULT_INTERVAL_MS: u64 = 2_000;
+const DEFAULT_SETTLE_MS: u64 = 5_000;
+/// 停止后扫描线程最迟多久退出。
+const STOP_POLL: Duration = Duration::from_millis(200);
+/// 仍在写入的下载或导出临时文件后缀。
+const TEMP_SUFFIXES: [&str; 6] = [".tmp", ".part", ".partial", ".crdownload", ".download", "~"];
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WatchOptions {
+    watch_dir: String,
+    /// 打包参数模板，字段与 `process_file` 相同，`inputPath` 由新出现的条目填入。
+    options: Value,
+    /// 扫描间隔（毫秒），默认 2000。
+    interval_ms: Option<u64>,
+    /// 新条目的大小与修改时间保持不变多久后才打包（毫秒），默认 5000。
+    settle_ms: Option<u64>,
+}
+
+#[derive(Debug, Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub(crate) struct WatchInfo {
+    watch_dir: String,
+    interval_ms: u64,
+    settle_ms: u64,
+    /// 已加入队列的条目数。
+    enqueued: usize,
+}
+
+struct RunningWatch {
+    info: Arc<Mutex<WatchInfo>>,
+    stopped: Arc<AtomicBool>,
+}
+
+fn running() -> &'static Mutex<Option<RunningWatch>> {
+    static RUNNING: OnceLock<Mutex<Option<RunningWatch>>> = OnceLock::new();
+    RUNNING.get_or_init(|| Mutex::new(None))
+}
+
+/// 开始监视目录；同一时间只能监视一个目录。
+pub(crate) fn start(app: Option<AppHandle>, options: WatchOptions) -> Result<WatchInfo, String> {
+    let mut guard = running().lock().unwrap_or_else(|e| e.into_inner());
+    if let Some(current) = guard.as_ref() {
+        let dir = current.info.lock().unwrap_or_else(|e| e.into_inner()).watch_dir.clone();
+        return Err(tr!(WatchAlreadyRunning, dir));
+    }
+    let watch_dir = PathBuf::from(&options.watch_dir);
+    if !watch_dir.is_dir() {
+        return Err(tr!(WatchDirNotFound, options.watch_dir));
+    }
+    // 用监视目录本身代入模板，提前发现参数错误
+    let sample = split_options(&options.options, &watch_dir)?;
+    let problems: Vec<String> = validation::validate_split(&sample)
+        .into_iter()
+        .filter(|error| error.field != "inputPath")
+        .map(|error| format!("{}: {}", error.field, error.message))
+        .collect();
+    if !problems.is_empty() {
+        return Err(tr!(InvalidOptions, problems.join("; ")));
+    }
+
+    let info = Arc::new(Mutex::new(WatchInfo {
+        watch_dir: options.watch_dir.clone(),
+        interval_ms: options.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS).max(100),
+        settle_ms: options.settle_ms.unwrap_or(DEFAULT_SETTLE_MS),
+        enqueued: 0,
+    }));
+    let stopped = Arc::new(AtomicBool::new(false));
+    let watcher = Watcher {
+        app,
+        dir: watch_dir,
+        output_dir: PathBuf::from(&sample.output_dir),
+        template: options.options,
+        info: info.clone(),
+        stopped: stopped.clone(),
+    };
+    let started = info.lock().unwrap_or_else(|e| e.into_inner()).clone();
+    log::info!("开始监视目录：{}", started.watch_dir);
+    thread::spawn(move || watcher.run());
+    *guard = Some(RunningWatch { info, stopped });
+    Ok(started)
+}
+
+pub(crate) fn stop() -> Result<(), String> {
+    let current = running()
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| tr!(WatchNotRunning))?;
+    current.stopped.store(true, Ordering::Relaxed);
+    Ok(())
+}
+
+pub(crate) fn status() -> Option<WatchInfo> {
+    let guard = running().lock().unwrap_or_else(|e| e.into_inner());
+    guard
+        .as_ref()
+        .map(|current| current.info.lock().unwrap_or_else(|e| e.into_inner()).clone())
+}
+
+/// 把条目路径代入打包参数模板。
+fn split_options(template: &Value, input_path: &Path) -> Result<SplitOptions, String> {
+    let mut value = template.clone();
+    let object = value
+        .as_object_mut()
+        .ok_or_else(|| tr!(InvalidJobOptions, "options"))?;
+    object.insert("inputPath".to_string(), json!(input_path.to_string_lossy()));
+    serde_json::from_value(value).map_err(|e| tr!(InvalidJobOptions, e))
+}
+
+/// 尚未写完的新条目：上一次扫描到的（大小、修改时间、文件数）及其开始保持不变的时间。
+struct Pending {
+    stats: (u64, u64, u64),
+    since: Instant,
+}
+
+struct Watcher {
+    app: Option<AppHandle>,
+    dir: PathBuf,
+    output_dir: PathBuf,
+    template: Value,
+    info: Arc<Mutex<WatchInfo>>,
+    stopped: Arc<AtomicBool>,
+}
+
+impl Watcher {
+    fn run(self) {
+        let (interval, settle) = {
+            let info = self.info.lock().unwrap_or_else(|e| e.into_inner());
+            (
+                Duration::from_millis(info.interval_ms),
+                Duration::from_millis(info.settle_ms),
+            )
+        };
+        // 开始监视时已有的条目不处理
+        let mut seen: HashSet<PathBuf> = self.scan().into_iter().collect();
+        let mut pending: HashMap<PathBuf, Pending> = HashMap::new();
+        while self.sleep(interval) {
+            let current = self.scan();
+            for path in &current {
+                if seen.contains(path) {
+                    continue;
+                }
+                let Some(stats) = checkpoint::input_stats(path) else {
+                    continue;
+                };
+                let entry = pending.entry(path.clone()).or_insert(Pending {
+                    stats,
+                    since: Instant::now(),
+                });
+                if entry.stats != stats {
+                    *entry = Pending {
+                        stats,
+                        since: Instant::now(),
+                    };
+                    continue;
+                }
+                if entry.since.elapsed() < settle {
+                    continue;
+                }
+                pending.remove(path);
+                seen.insert(path.clone());
+                self.enqueue(path);
+            }
+            let current: HashSet<PathBuf> = current.into_iter().collect();
+            seen.retain(|path| current.contains(path));
+            pending.retain(|path, _| current.contains(path));
+        }
+        log::info!("停止监视目录：{}", self.dir.display());
+    }
+
+    /// 等待一个扫描间隔，期间被停止时返回 `false`。
+    fn sleep(&self, interval: Duration) -> bool {
+        let deadline = Instant::now() + interval;
+        while !self.stopped.load(Ordering::Relaxed) {
+            let now = Instant::now();
+            if now >= deadline {
+                return true;
+            }
+            thread::sleep(STOP_POLL.min(deadline - now));
+        }
+        false
+    }
+
+    /// 目录中可以打包的条目。
+    fn scan(&self) -> Vec<PathBuf> {
+        let Ok(entries) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        entries
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| self.is_candidate(path))
+            .collect()
+    }
+
+    fn is_candidate(&self, path: &Path) -> bool {
+        let Some(name) = path.file_name().and_then(|name| name.to_str()) else {
+            return false;
+        };
+        let lower = name.to_ascii_lowercase();
+        !name.starts_with('.')
+            && !lower.ends_with(".parts")
+            && !TEMP_SUFFIXES.iter().any(|suffix| lower.ends_with(suffix))
+            && path != self.output_dir
+    }
+
+    fn enqueue(&self, path: &Path) {
+        let mut options = self.template.clone();
+        if let Some(object) = options.as_object_mut() {
+            object.insert("inputPath".to_string(), json!(path.to_string_lossy()));
+        }
+        match queue::enqueue(self.app.clone(), "pack", options) {
+            Ok(item) => {
+                log::info!("监视目录出现新条目，已加入队列：{}", path.display());
+                self.info.lock().unwrap_or_else(|e| e.into_inner()).enqueued += 1;
+                if let Some(app) = &self.app {
+                    let _ = app.emit(
+                        "watch-enqueued",
+                        json!({ "path": path.to_string_lossy(), "queueId": item.id }),
+                    );
+                }
+            }
+            Err(err) => log::error!("监视目录的新条目无法加入队列：{}（{}）", path.display(), err),
+        }
+    }
+}
@@ -0,0 +1,109 @@
+//! 安全删除：先用随机数据覆写文件内容并落盘，再改为随机名称后删除，原内容与原文件名都不会
+//! 留在原来的数据块与目录项中。用于分割后销毁源文件（`destroySource`）与安全删除中间文件
+//! （`secureDelete`）。
+//!
+//! 只对原地覆写的存储有效：SSD 的磨损均衡、写时复制文件系统（Btrfs、ZFS、APFS）、快照、
+//! 回收站与云盘同步都可能保留旧数据，这些情况下请配合全盘加密。
+//! 覆写一旦开始就做完，不响应取消，避免留下一半被覆写的文件。
+
+use rand::RngCore;
+use std::{
+    fs::{self, OpenOptions},
+    io::Write,
+    path::{Path, PathBuf},
+};
+
+use crate::{emit_progress, i18n::tr, jobs::JobContext};
+
+/// 覆写时每次写入的上限。
+const WIPE_BUFFER_SIZE: usize = 1024 * 1024;
+
+/// 覆写并删除 `path`：文件直接处理，目录处理其中的全部文件后自下而上删除各级目录。
+/// 符号链接只删除链接本身。`message` 为进度提示。
+pub(crate) fn wipe_path(ctx: &JobContext, path: &Path, message: String) -> Result<(), String> {
+    let mut files = Vec::new();
+    let mut dirs = Vec::new();
+    collect(path, &mut files, &mut dirs)?;
+    let total: u64 = files
+        .iter()
+        .filter_map(|file| fs::symlink_metadata(file).ok())
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len())
+        .sum();
+    let mut processed = 0u64;
+    for (idx, file) in files.iter().enumerate() {
+        emit_progress(
+            ctx,
+            "wipe",
+            processed,
+            total,
+            idx + 1,
+            files.len(),
+            message.clone(),
+        );
+        wipe_file(ctx, file, |delta| {
+            processed += delta;
+            emit_progress(
+                ctx,
+                "wipe",
+                processed,
+                total,
+                idx + 1,
+                files.len(),
+                message.clone(),
+            );
+        })?;
+    }
+    // 子目录先于父目录收集到末尾，倒序删除
+    for dir in dirs.iter().rev() {
+        fs::remove_dir(dir).map_err(|e| tr!(WipeFailed, dir.display(), e))?;
+    }
+    log::info!("已覆写并删除：{}（{} 个文件）", path.display(), files.len());
+    Ok(())
+}
+
+/// 覆写并删除单个文件；`on_progress` 收到每次覆写的字节数。符号链接只删除链接本身。
+pub(crate) fn wipe_file(
+    ctx: &JobContext,
+    path: &Path,
+    mut on_progress: impl FnMut(u64),
+) -> Result<(), String> {
+    let fail = |e: std::io::Error| tr!(WipeFailed, path.display(), e);
+    let metadata = fs::symlink_metadata(path).map_err(fail)?;
+    if metadata.is_file() {
+        let mut file = OpenOptions::new().write(true).open(path).map_err(fail)?;
+        let mut buffer = vec![0u8; ctx.memory().buffer_size().clamp(1, WIPE_BUFFER_SIZE)];
+        let mut rng = rand::thread_rng();
+        let mut remaining = metadata.len();
+        while remaining > 0 {
+            let len = buffer.len().min(remaining as usize);
+            rng.fill_bytes(&mut buffer[..len]);
+            file.write_all(&buffer[..len]).map_err(fail)?;
+            remaining -= len as u64;
+            on_progress(len as u64);
+        }
+        file.sync_all().map_err(fail)?;
+    }
+    // 改为随机名称后再删除，原文件名不留在目录项中；改名失败时直接删除
+    let renamed = path.with_file_name(format!("{:016x}", rand::random::<u64>()));
+    let target = match fs::rename(path, &renamed) {
+        Ok(()) => renamed,
+        Err(_) => path.to_path_buf(),
+    };
+    fs::remove_file(&target).map_err(fail)
+}
+
+fn collect(path: &Path, files: &mut Vec<PathBuf>, dirs: &mut Vec<PathBuf>) -> Result<(), String> {
+    let metadata = fs::symlink_metadata(path).map_err(|e| tr!(WipeFailed, path.display(), e))?;
+    if !metadata.is_dir() {
+        files.push(path.to_path_buf());
+        return Ok(());
+    }
+    dirs.push(path.to_path_buf());
+    let entries = fs::read_dir(path).map_err(|e| tr!(WipeFailed, path.display(), e))?;
+    for entry in entries {
+        let entry = entry.map_err(|e| tr!(WipeFailed, path.display(), e))?;
+        collect(&entry.path(), files, dirs)?;
+    }
+    Ok(())
+}
@@ -1,6 +1,6 @@
 import { createSignal, onCleanup, onMount, Show, For } from "solid-js";
 import { invoke } from "@tauri-apps/api/core";
-import { confirm, open as openDialog } from "@tauri-apps/plugin-dialog";
+import { confirm, open as openDialog, save as saveDialog } from "@tauri-apps/plugin-dialog";
 import { exists, readDir, stat } from "@tauri-apps/plugin-fs";
 import { openPath, revealItemInDir } from "@tauri-apps/plugin-opener";
 import { listen } from "@tauri-apps/api/event";
@@ -13,10 +13,11 @@ import mergePartsLua from "../xxtouch/merge_parts.lua?raw";
 type ProgressPayload = {
   phase: string;
   processedBytes: number;
-  totalBytes: number;
+  totalBytes: number | null;
   partIndex: number;
   partTotal: number;
   message: string;
+  jobId?: string;
 };
 
 type SplitResult = {
@@ -25,12 +26,174 @@ type SplitResult = {
   isDir: boolean;
   baseName: string;
   partSha256s: { path: string; sha256: string }[];
+  recoveryFiles: string[];
+  restoreFiles: string[];
 };
 
+type FieldError = {
+  field: string;
+  message: string;
+};
+
+type PasswordStrength = {
+  score: number;
+  entropyBits: number;
+  warnings: string[];
+};
+
+const STRENGTH_LABELS = ["极弱", "弱", "一般", "强", "很强"];
+
 type RestoreResult = {
   mergedFile?: string;
   extractedDir?: string;
   outputFiles: string[];
+  verified: boolean | null;
+  mismatches: string[];
+  repairedParts: string[];
+  signature: { fingerprint: string; trusted: boolean } | null;
+  lostEntries: string[];
+  archiveType?: string | null;
+  detectedMode?: string | null;
+  missingRanges?: { part: number; start: number; end: number; estimated: boolean }[];
+  conflicts?: {
+    path: string;
+    action: "overwrite" | "skip" | "rename";
+    renamedTo?: string | null;
+  }[];
+};
+
+type ConflictPrompt = {
+  jobId: string;
+  promptId: string;
+  kind: "extractConflict";
+  path: string;
+  entry: string;
+  size: number;
+  existingSize: number;
+};
+
+type PasswordPrompt = {
+  jobId: string;
+  promptId: string;
+  kind: "passwordRequired";
+  archive: string;
+  reason: "wrongPassword" | "passwordRequired";
+  attempt: number;
+};
+
+type BatchRestoreResult = {
+  groups: {
+    name: string;
+    firstPart: string;
+    partCount: number;
+    result: RestoreResult | null;
+    error: string | null;
+  }[];
+  succeeded: number;
+  failed: number;
+};
+
+type KeyInfo = {
+  keyPath: string;
+  publicKeyPath: string;
+  publicKey: string;
+  fingerprint: string;
+};
+
+type VerifyReport = {
+  partsDir: string;
+  hasManifest: boolean;
+  hashesChecked: boolean;
+  expectedParts: number | null;
+  foundParts: number;
+  missingIndices: number[];
+  parts: {
+    name: string;
+    status:
+      | "ok"
+      | "unchecked"
+      | "missing"
+      | "sizeMismatch"
+      | "hashMismatch"
+      | "unlisted";
+  }[];
+  ok: boolean;
+};
+
+type RepairReport = {
+  partsDir: string;
+  method: "reedSolomon" | "xor" | "par2";
+  damagedParts: string[];
+  repairedParts: string[];
+};
+
+type ReencryptReport = {
+  partsDir: string;
+  outputFiles: string[];
+  encrypted: boolean;
+};
+
+type ContentNode = {
+  name: string;
+  path: string;
+  isDir: boolean;
+  size: number;
+  compressedSize: number;
+  modified: string | null;
+  encrypted: boolean;
+  children: ContentNode[];
+};
+
+type ContentsReport = {
+  files: number;
+  totalSize: number;
+  compressedSize: number;
+  encrypted: boolean;
+  entries: ContentNode[];
+};
+
+type DecryptReport = {
+  partsDir: string;
+  outputFiles: string[];
+  packMode: string;
+};
+
+type TestReport = {
+  entriesTested: number;
+  bytesTested: number;
+  failures: {
+    entry: string;
+    error: string;
+    locations: { part: string; start: number; end: number }[];
+  }[];
+  ok: boolean;
+};
+
+const repairMethodText: Record<RepairReport["method"], string> = {
+  reedSolomon: "恢复分片",
+  xor: "异或校验分片",
+  par2: "PAR2 恢复文件",
+};
+
+const partStatusText: Record<VerifyReport["parts"][number]["status"], string> = {
+  ok: "正常",
+  unchecked: "未校验",
+  missing: "缺失",
+  sizeMismatch: "大小不一致",
+  hashMismatch: "校验值不一致",
+  unlisted: "不在清单中",
+};
+
+type QueueItem = {
+  id: string;
+  kind: string;
+  label: string;
+  state: "queued" | "running" | "retrying";
+  jobId?: string;
+  progress?: ProgressPayload;
+  enqueuedAt: number;
+  attempt: number;
+  lastError?: string;
 };
 
 const unitToBytes = (value: number, unit: string) => {
@@ -109,27 +272,112 @@ function App() {
   const [countValue, setCountValue] = createSignal(4);
   const [password, setPassword] = createSignal("");
   const [compressionLevel, setCompressionLevel] = createSignal("6");
+  const [powerAction, setPowerAction] = createSignal<
+    "none" | "shutdown" | "sleep" | "hibernate"
+  >("none");
+  const [queuePowerAction, setQueuePowerAction] = createSignal("none");
+  const [backgroundPriority, setBackgroundPriority] = createSignal(false);
+  const [verifyAfterSplit, setVerifyAfterSplit] = createSignal(false);
+  const [destroySource, setDestroySource] = createSignal(false);
+  const [secureDelete, setSecureDelete] = createSignal(false);
+  const [sevenZipVolumes, setSevenZipVolumes] = createSignal(false);
+  const [perPartKeys, setPerPartKeys] = createSignal(false);
+  const [numberedVolumes, setNumberedVolumes] = createSignal(false);
+  const [selfExtracting, setSelfExtracting] = createSignal(false);
+  const [rejoinScripts, setRejoinScripts] = createSignal(false);
+  const [zstdDictionary, setZstdDictionary] = createSignal(false);
+  const [compressionMethod, setCompressionMethod] = createSignal<
+    "deflate" | "bzip2" | "xz"
+  >("deflate");
+  const [aesStrength, setAesStrength] = createSignal("256");
+  const [encryptNames, setEncryptNames] = createSignal(false);
+  const [keyfile, setKeyfile] = createSignal("");
+  const [passwordStrength, setPasswordStrength] =
+    createSignal<PasswordStrength | null>(null);
+  const [ageRecipients, setAgeRecipients] = createSignal("");
+  const [gpgRecipients, setGpgRecipients] = createSignal("");
+  const [hashAlgorithm, setHashAlgorithm] = createSignal<"sha256" | "blake3">(
+    "sha256"
+  );
+  const [par2Redundancy, setPar2Redundancy] = createSignal("0");
+  const [redundancyParts, setRedundancyParts] = createSignal("0");
+  const [signingKey, setSigningKey] = createSignal("");
+  const [watchDir, setWatchDir] = createSignal("");
+  const [watching, setWatching] = createSignal(false);
   const [packMode, setPackMode] = createSignal<
-    "split-then-zip" | "zip-then-split"
+    | "split-then-zip"
+    | "zip-then-split"
+    | "7z-then-split"
+    | "zstd-then-split"
+    | "tar-gz-then-split"
+    | "tar-zst-then-split"
+    | "raw-split"
+    | "age-split"
+    | "container-then-split"
+    | "spanned-zip"
   >("split-then-zip");
   const [dirSplitMode, setDirSplitMode] = createSignal<
     "compress-split-store" | "store-split-compress"
   >("compress-split-store");
   const [running, setRunning] = createSignal(false);
-  const [progress, setProgress] = createSignal<ProgressPayload | null>(null);
+  const [jobProgress, setJobProgress] = createSignal<
+    Record<string, ProgressPayload>
+  >({});
   const [error, setError] = createSignal("");
+  const [fieldErrors, setFieldErrors] = createSignal<Record<string, string>>({});
+  const fieldError = (field: string) => fieldErrors()[field];
   const [success, setSuccess] = createSignal("");
+  const [queueItems, setQueueItems] = createSignal<QueueItem[]>([]);
   const [outputFiles, setOutputFiles] = createSignal<string[]>([]);
   const [luaSnippet, setLuaSnippet] = createSignal("");
   const [copyHint, setCopyHint] = createSignal("");
   const [openHint, setOpenHint] = createSignal("");
   const [restoreInputPath, setRestoreInputPath] = createSignal("");
   const [restoreOutputDir, setRestoreOutputDir] = createSignal("");
+  const [restoreUrls, setRestoreUrls] = createSignal("");
+  const [keepDownloads, setKeepDownloads] = createSignal(false);
   const [restoreMode, setRestoreMode] = createSignal<
-    "split-then-zip" | "zip-then-split"
+    | "auto"
+    | "split-then-zip"
+    | "zip-then-split"
+    | "7z-then-split"
+    | "zstd-then-split"
+    | "tar-gz-then-split"
+    | "tar-zst-then-split"
+    | "raw"
+    | "age-split"
+    | "container-then-split"
+    | "spanned-zip"
   >("split-then-zip");
   const [restorePassword, setRestorePassword] = createSignal("");
+  const [restoreKeyfile, setRestoreKeyfile] = createSignal("");
+  const [reencryptPassword, setReencryptPassword] = createSignal("");
+  const [restoreAgeIdentity, setRestoreAgeIdentity] = createSignal("");
+  const [restoreGpg, setRestoreGpg] = createSignal(false);
   const [restoreAutoExtract, setRestoreAutoExtract] = createSignal(true);
+  const [trustedKeys, setTrustedKeys] = createSignal("");
+  const [extractInclude, setExtractInclude] = createSignal("");
+  const [extractExclude, setExtractExclude] = createSignal("");
+  const [extractFromParts, setExtractFromParts] = createSignal(false);
+  const [keepArchive, setKeepArchive] = createSignal(true);
+  const [requireSignature, setRequireSignature] = createSignal(false);
+  const [salvage, setSalvage] = createSignal(false);
+  const [missingParts, setMissingParts] = createSignal<"" | "fill" | "truncate">("");
+  const [onConflict, setOnConflict] = createSignal<
+    "" | "overwrite" | "skip" | "rename" | "ask"
+  >("");
+  const [filenameEncoding, setFilenameEncoding] = createSignal("");
+  const [conflictPrompt, setConflictPrompt] = createSignal<ConflictPrompt | null>(null);
+  const [conflictApplyAll, setConflictApplyAll] = createSignal(false);
+  const [passwordPrompt, setPasswordPrompt] = createSignal<PasswordPrompt | null>(null);
+  const [promptPassword, setPromptPassword] = createSignal("");
+  const [restoreSecureDelete, setRestoreSecureDelete] = createSignal(false);
+  const [verifyReport, setVerifyReport] = createSignal<VerifyReport | null>(
+    null
+  );
+  const [contentsReport, setContentsReport] = createSignal<ContentsReport | null>(
+    null
+  );
   const [dropHint, setDropHint] = createSignal<DropTarget | null>(null);
   const getLogicalPoint = (position: { x: number; y: number }) => {
     try {
@@ -214,9 +462,46 @@ function App() {
     const unlisten = await listen<ProgressPayload>(
       "split-progress",
       (event) => {
-        setProgress(event.payload);
+        const key = event.payload.jobId ?? "";
+        setJobProgress((current) => ({ ...current, [key]: event.payload }));
+      }
+    );
+    const unlistenFinished = await listen<{ id: string }>(
+      "job-finished",
+      (event) => {
+        setJobProgress((current) => {
+          const rest = { ...current };
+          delete rest[event.payload.id];
+          return rest;
+        });
+      }
+    );
+    const unlistenPrompt = await listen<ConflictPrompt | PasswordPrompt>(
+      "job-prompt",
+      (event) => {
+        if (event.payload.kind === "passwordRequired") {
+          setPromptPassword("");
+          setPasswordPrompt(event.payload);
+          return;
+        }
+        if (event.payload.kind !== "extractConflict") return;
+        setConflictApplyAll(false);
+        setConflictPrompt(event.payload);
       }
     );
+    const unlistenQueue = await listen<QueueItem[]>("queue-changed", (event) => {
+      setQueueItems(event.payload);
+      if (event.payload.length === 0) {
+        void invoke<{ powerAction: string }>("get_queue_settings").then(
+          (settings) => setQueuePowerAction(settings.powerAction)
+        );
+      }
+    });
+    void invoke<QueueItem[]>("list_queue").then(setQueueItems);
+    void invoke<{ watchDir: string } | null>("get_watch_folder").then((info) => {
+      setWatching(!!info);
+      if (info) setWatchDir(info.watchDir);
+    });
     const unlistenDrop = await getCurrentWebview().onDragDropEvent((event) => {
       if (event.payload.type === "leave") {
         dropTarget = null;
@@ -280,6 +565,9 @@ function App() {
     });
     onCleanup(() => {
       unlisten();
+      unlistenFinished();
+      unlistenPrompt();
+      unlistenQueue();
       unlistenDrop();
     });
   });
@@ -290,6 +578,35 @@ function App() {
     setInputPath(selected);
   };
 
+  const chooseKeyfile = async (setter: (path: string) => void) => {
+    const selected = await openDialog({ multiple: false, directory: false });
+    if (!selected || Array.isArray(selected)) return;
+    setter(selected);
+  };
+
+  const chooseSigningKey = async () => {
+    const selected = await openDialog({ multiple: false, directory: false });
+    if (!selected || Array.isArray(selected)) return;
+    setSigningKey(selected);
+  };
+
+  const generateSigningKey = async () => {
+    resetStatus();
+    const selected = await saveDialog({ defaultPath: "signing-key.pem" });
+    if (!selected) return;
+    try {
+      const info = await invoke<KeyInfo>("generate_signing_key", {
+        options: { keyPath: selected },
+      });
+      setSigningKey(info.keyPath);
+      setSuccess(
+        `已生成密钥，公钥在 ${info.publicKeyPath}（指纹 ${info.fingerprint}），请发给收件方`
+      );
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
   const chooseRestoreFile = async () => {
     const selected = await openDialog({ multiple: false, directory: false });
     if (!selected || Array.isArray(selected)) return;
@@ -408,12 +725,14 @@ function App() {
 
   const resetStatus = () => {
     setError("");
+    setFieldErrors({});
     setSuccess("");
     setOutputFiles([]);
     setLuaSnippet("");
     setCopyHint("");
     setOpenHint("");
-    setProgress(null);
+    setVerifyReport(null);
+    setContentsReport(null);
   };
 
   const ensurePartsDir = async (baseName: string, resolvedOutput: string) => {
@@ -421,20 +740,58 @@ function App() {
     try {
       const existsDir = await exists(partsDir);
       if (!existsDir) {
-        return { proceed: true, overwrite: false };
+        return { proceed: true, overwrite: false, resume: true };
       }
       const entries = await readDir(partsDir);
       if (entries.length === 0) {
-        return { proceed: true, overwrite: false };
+        return { proceed: true, overwrite: false, resume: true };
+      }
+      if (entries.some((entry) => entry.name === ".checkpoint.json")) {
+        const resume = await confirm(
+          "检测到上次未完成的切分，是否从中断处继续？参数或输入有变化时将无法继续。",
+          { title: "继续切分", kind: "info" }
+        );
+        if (resume) {
+          return { proceed: true, overwrite: false, resume: true };
+        }
       }
       const confirmed = await confirm(
         "检测到已存在的分片目录，继续将覆盖其中内容，是否确认？",
         { title: "确认覆盖", kind: "warning" }
       );
-      return { proceed: confirmed, overwrite: confirmed };
+      return { proceed: confirmed, overwrite: confirmed, resume: false };
     } catch (err) {
       setError("无法检查输出目录，请手动确认分片目录是否可写");
-      return { proceed: false, overwrite: false };
+      return { proceed: false, overwrite: false, resume: false };
+    }
+  };
+
+  const answerConflict = async (action: "overwrite" | "skip" | "rename") => {
+    const prompt = conflictPrompt();
+    if (!prompt) return;
+    setConflictPrompt(null);
+    try {
+      await invoke("answer_prompt", {
+        promptId: prompt.promptId,
+        answer: { action, applyToAll: conflictApplyAll() },
+      });
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const answerPassword = async (password: string | null) => {
+    const prompt = passwordPrompt();
+    if (!prompt) return;
+    setPasswordPrompt(null);
+    setPromptPassword("");
+    try {
+      await invoke("answer_prompt", {
+        promptId: prompt.promptId,
+        answer: { password },
+      });
+    } catch (err) {
+      setError(String(err));
     }
   };
 
@@ -452,7 +809,180 @@ function App() {
     await startPack();
   };
 
-  const startPack = async () => {
+  const enqueueProcess = async () => {
+    if (workMode() === "restore") {
+      await startRestore(true);
+      return;
+    }
+    await startPack(true);
+  };
+
+  const removeQueued = async (id: string) => {
+    try {
+      setQueueItems(await invoke<QueueItem[]>("remove_queued_job", { id }));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const clearQueue = async () => {
+    try {
+      await invoke<string[]>("clear_queue");
+      setQueueItems(await invoke<QueueItem[]>("list_queue"));
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const changeQueuePowerAction = async (value: string) => {
+    try {
+      const settings = await invoke<{ powerAction: string }>(
+        "set_queue_settings",
+        { settings: { powerAction: value } }
+      );
+      setQueuePowerAction(settings.powerAction);
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const enqueue = async (kind: string, options: object) => {
+    try {
+      await invoke<QueueItem>("enqueue_job", { kind, options });
+      setSuccess("已加入队列");
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const cancelProcess = async () => {
+    try {
+      await invoke<string[]>("cancel_job", {});
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const packOptions = (resolvedOutput: string) => ({
+    outputDir: resolvedOutput,
+    splitBy: splitBy(),
+    sizeBytes:
+      splitBy() === "size" ? unitToBytes(sizeValue(), sizeUnit()) : undefined,
+    count: splitBy() === "count" ? countValue() : undefined,
+    packMode: packMode(),
+    dirSplitMode: dirSplitMode(),
+    password: password().trim() ? password().trim() : undefined,
+    keyfile: keyfile() || undefined,
+    ageRecipients:
+      packMode() === "age-split" && ageRecipients().trim()
+        ? ageRecipients().split(/[\s,，]+/).filter(Boolean)
+        : undefined,
+    gpgRecipients:
+      packMode() !== "spanned-zip" && gpgRecipients().trim()
+        ? gpgRecipients().split(/[\s,，]+/).filter(Boolean)
+        : undefined,
+    compressionLevel: Number(compressionLevel()),
+    powerAction: powerAction(),
+    backgroundPriority: backgroundPriority(),
+    hashAlgorithm: hashAlgorithm(),
+    par2Redundancy: Number(par2Redundancy()) || undefined,
+    redundancyParts: Number(redundancyParts()) || undefined,
+    xorParity: redundancyParts() === "xor",
+    signingKey: signingKey() || undefined,
+    verifyAfterSplit: verifyAfterSplit(),
+    destroySource: destroySource(),
+    secureDelete: secureDelete(),
+    sevenZipVolumes: packMode() === "7z-then-split" && sevenZipVolumes(),
+    perPartKeys: packMode() === "container-then-split" && perPartKeys(),
+    numberedVolumes:
+      packMode() !== "split-then-zip" &&
+      packMode() !== "age-split" &&
+      packMode() !== "spanned-zip" &&
+      numberedVolumes(),
+    selfExtracting: selfExtracting(),
+    rejoinScripts:
+      packMode() !== "split-then-zip" &&
+      packMode() !== "age-split" &&
+      packMode() !== "spanned-zip" &&
+      rejoinScripts(),
+    zstdDictionary: packMode() === "zstd-then-split" && zstdDictionary(),
+    aesStrength:
+      (password().trim() || keyfile()) &&
+      (packMode() === "split-then-zip" ||
+        packMode() === "zip-then-split" ||
+        packMode() === "spanned-zip")
+        ? Number(aesStrength())
+        : undefined,
+    encryptNames:
+      !!(password().trim() || keyfile()) &&
+      (packMode() === "zip-then-split" || packMode() === "7z-then-split") &&
+      encryptNames(),
+    compressionMethod:
+      packMode() === "split-then-zip" ||
+      packMode() === "zip-then-split" ||
+      packMode() === "spanned-zip"
+        ? compressionMethod()
+        : undefined,
+  });
+
+  const chooseWatchDir = async () => {
+    const selected = await openDialog({ multiple: false, directory: true });
+    if (!selected || Array.isArray(selected)) return;
+    setWatchDir(selected);
+  };
+
+  const toggleWatch = async () => {
+    resetStatus();
+    try {
+      if (watching()) {
+        await invoke("stop_watch_folder");
+        setWatching(false);
+        setSuccess("已停止监视");
+        return;
+      }
+      if (!watchDir()) {
+        setError("请先选择监视目录");
+        return;
+      }
+      if (!outputDir()) {
+        setError("请指定输出目录");
+        return;
+      }
+      await invoke("start_watch_folder", {
+        options: {
+          watchDir: watchDir(),
+          // 每个文件打包完都关机没有意义，改在队列面板设置全部完成后的操作
+          options: { ...packOptions(outputDir()), powerAction: "none" },
+        },
+      });
+      setWatching(true);
+      setSuccess("正在监视目录，新文件会自动加入队列");
+    } catch (err) {
+      setError(String(err));
+    }
+  };
+
+  const updatePasswordStrength = async (value: string) => {
+    setPassword(value);
+    if (!value.trim()) {
+      setPasswordStrength(null);
+      return;
+    }
+    try {
+      const strength = await invoke<PasswordStrength>(
+        "estimate_password_strength",
+        { password: value.trim() },
+      );
+      // 输入较快时只保留最后一次的结果
+      if (password() === value) {
+        setPasswordStrength(strength);
+      }
+    } catch {
+      setPasswordStrength(null);
+    }
+  };
+
+  const startPack = async (queued = false) => {
     resetStatus();
 
     if (!inputPath()) {
@@ -476,8 +1006,23 @@ function App() {
       setError("份数必须大于 0");
       return;
     }
+    // 加密打包往往要跑很久，一眼就能猜中的密码直接拦下；同时使用密钥文件时不限制
+    if (password().trim() && !keyfile() && passwordStrength()?.score === 0) {
+      setFieldErrors({ password: "密码太弱，请加长或改用不常见的组合" });
+      setError("参数有误，请检查标出的选项");
+      return;
+    }
+    if (
+      destroySource() &&
+      !(await confirm(
+        `打包完成并核对通过后将覆写并删除 ${inputPath()}，无法恢复。确定继续？`,
+        { title: "销毁源文件", kind: "warning" }
+      ))
+    ) {
+      return;
+    }
     const baseName = extractName(inputPath());
-    let overwriteDecision = { proceed: true, overwrite: false };
+    let overwriteDecision = { proceed: true, overwrite: false, resume: true };
     if (outputDir()) {
       overwriteDecision = await ensurePartsDir(baseName, resolvedOutput);
       if (!overwriteDecision.proceed) {
@@ -485,28 +1030,68 @@ function App() {
       }
     }
 
+    // 密码不放进参数 JSON：校验通过后以随机 ID 单独登记，参数中只引用这个 ID
+    const secret = password().trim();
     const payload = {
       inputPath: inputPath(),
-      outputDir: resolvedOutput,
-      splitBy: splitBy(),
-      sizeBytes:
-        splitBy() === "size"
-          ? unitToBytes(sizeValue(), sizeUnit())
-          : undefined,
-      count: splitBy() === "count" ? countValue() : undefined,
-      packMode: packMode(),
-      dirSplitMode: dirSplitMode(),
-      password: password().trim() ? password().trim() : undefined,
-      compressionLevel: Number(compressionLevel()),
+      ...packOptions(resolvedOutput),
+      password: undefined,
+      secretJobId: secret ? crypto.randomUUID() : undefined,
       overwriteParts: overwriteDecision.overwrite,
+      resume: overwriteDecision.resume,
     };
 
+    try {
+      const problems = await invoke<FieldError[]>("validate_split_options", {
+        options: payload,
+      });
+      if (problems.length > 0) {
+        const byField: Record<string, string> = {};
+        for (const problem of problems) {
+          byField[problem.field] = byField[problem.field]
+            ? `${byField[problem.field]}；${problem.message}`
+            : problem.message;
+        }
+        setFieldErrors(byField);
+        setError("参数有误，请检查标出的选项");
+        return;
+      }
+      if (payload.secretJobId) {
+        await invoke("set_job_secret", { jobId: payload.secretJobId, secret });
+      }
+    } catch (err) {
+      setError(String(err));
+      return;
+    }
+
+    if (queued) {
+      await enqueue("pack", payload);
+      return;
+    }
+
     try {
       setRunning(true);
       const result = await invoke<SplitResult>("process_file", {
         options: payload,
       });
-      setOutputFiles(result.outputFiles);
+      setOutputFiles([
+        ...result.outputFiles,
+        ...(result.recoveryFiles || []),
+        ...(result.restoreFiles || []),
+      ]);
+      if (
+        packMode() === "7z-then-split" ||
+        packMode() === "zstd-then-split" ||
+        packMode() === "tar-gz-then-split" ||
+        packMode() === "tar-zst-then-split" ||
+        packMode() === "raw-split" ||
+        packMode() === "age-split" ||
+        packMode() === "container-then-split" ||
+        packMode() === "spanned-zip"
+      ) {
+        setSuccess(`完成：共输出 ${result.parts} 份`);
+        return;
+      }
       const baseName = result.baseName || extractName(inputPath());
       const fileList = result.outputFiles.map((filePath) => {
         const relative = toRelative(filePath, resolvedOutput);
@@ -609,27 +1194,96 @@ function App() {
     }
   };
 
-  const startRestore = async () => {
+  const startRestore = async (queued = false, allGroups = false) => {
     resetStatus();
 
-    if (!restoreInputPath()) {
-      setError("请先选择分片文件或目录");
+    const urls = restoreUrls().split(/\s+/).filter(Boolean);
+    if (!restoreInputPath() && urls.length === 0) {
+      setError("请先选择分片文件或目录，或填写分片网址");
       return;
     }
-    const resolvedOutput = restoreOutputDir() || extractDir(restoreInputPath());
+    const resolvedOutput =
+      restoreOutputDir() || (urls.length === 0 ? extractDir(restoreInputPath()) : "");
     if (!resolvedOutput) {
       setError("请指定输出目录");
       return;
     }
     const payload = {
-      inputPath: restoreInputPath(),
+      inputPath: urls.length > 0 ? "" : restoreInputPath(),
       outputDir: resolvedOutput,
+      urls: urls.length > 0 ? urls : undefined,
+      keepDownloads: urls.length > 0 && keepDownloads(),
       mergeMode: restoreMode(),
-      password: restorePassword().trim()
-        ? restorePassword().trim()
-        : undefined,
+      secretJobId: restorePassword().trim() ? crypto.randomUUID() : undefined,
+      keyfile: restoreKeyfile() || undefined,
+      gpgDecrypt: restoreGpg(),
+      ageIdentities:
+        (restoreMode() === "age-split" || restoreMode() === "auto") &&
+          restoreAgeIdentity()
+          ? [restoreAgeIdentity()]
+          : undefined,
       autoExtract: restoreAutoExtract(),
+      trustedKeys: trustedKeys().trim()
+        ? trustedKeys().split(/[\s,，]+/).filter(Boolean)
+        : undefined,
+      requireSignature: requireSignature(),
+      salvage: salvage(),
+      secureDelete: restoreSecureDelete(),
+      include:
+        restoreAutoExtract() && extractInclude().trim()
+          ? extractInclude().split(/[\s,，]+/).filter(Boolean)
+          : undefined,
+      exclude:
+        restoreAutoExtract() && extractExclude().trim()
+          ? extractExclude().split(/[\s,，]+/).filter(Boolean)
+          : undefined,
+      extractFromParts: restoreAutoExtract() && extractFromParts(),
+      keepArchive: !restoreAutoExtract() || keepArchive(),
+      missingParts: missingParts() || undefined,
+      onConflict: restoreAutoExtract() && onConflict() ? onConflict() : undefined,
+      filenameEncoding:
+        restoreAutoExtract() && filenameEncoding() ? filenameEncoding() : undefined,
     };
+    if (payload.secretJobId) {
+      try {
+        await invoke("set_job_secret", {
+          jobId: payload.secretJobId,
+          secret: restorePassword().trim(),
+        });
+      } catch (err) {
+        setError(String(err));
+        return;
+      }
+    }
+
+    if (queued) {
+      await enqueue("restore", payload);
+      return;
+    }
+
+    if (allGroups) {
+      try {
+        setRunning(true);
+        const batch = await invoke<BatchRestoreResult>("restore_all_groups", {
+          options: payload,
+        });
+        setOutputFiles(batch.groups.flatMap((group) => group.result?.outputFiles || []));
+        const failed = batch.groups.filter((group) => group.error);
+        if (failed.length) {
+          setError(
+            `已合并 ${batch.succeeded} 组，${failed.length} 组失败：` +
+              failed.map((group) => `${group.name}（${group.error}）`).join("；")
+          );
+        } else {
+          setSuccess(`全部 ${batch.succeeded} 组分片合并完成`);
+        }
+      } catch (err) {
+        setError(String(err));
+      } finally {
+        setRunning(false);
+      }
+      return;
+    }
 
     try {
       setRunning(true);
@@ -637,10 +1291,58 @@ function App() {
         options: payload,
       });
       setOutputFiles(result.outputFiles || []);
-      if (result.extractedDir) {
-        setSuccess("合并并解包完成");
+      const repaired = result.repairedParts?.length
+        ? `（已用恢复分片重建 ${result.repairedParts.length} 份）`
+        : "";
+      const archiveType = result.archiveType ? `（${result.archiveType}）` : "";
+      const detected = result.detectedMode ? `，识别为 ${result.detectedMode}` : "";
+      const skipped = result.conflicts?.filter((item) => item.action === "skip").length ?? 0;
+      const renamed = result.conflicts?.filter((item) => item.action === "rename").length ?? 0;
+      const conflicts =
+        skipped || renamed
+          ? `（同名文件：跳过 ${skipped} 个，改名 ${renamed} 个）`
+          : "";
+      const signature = result.signature
+        ? result.signature.trusted
+          ? "，清单签名可信"
+          : `；注意：清单签名有效，但签名者（指纹 ${result.signature.fingerprint}）不在信任的公钥中`
+        : "";
+      if (result.missingRanges?.length) {
+        const ranges = result.missingRanges
+          .map(
+            (range) =>
+              `第 ${range.part} 份（字节 ${range.start}–${range.end}${range.estimated ? "，推算" : ""}）`
+          )
+          .join("、");
+        setError(
+          missingParts() === "truncate"
+            ? `合并完成，但缺少以下分片，结果截断在第一处缺失之前：${ranges}`
+            : `合并完成，但缺少以下分片，已补零：${ranges}`
+        );
+      } else if (result.lostEntries?.length) {
+        setError(
+          `合并完成，但以下 ${result.lostEntries.length} 个条目已损坏，已跳过：${result.lostEntries.join("、")}`
+        );
+      } else if (result.verified === false) {
+        setError(
+          `合并完成，但以下文件与清单中的校验值不一致：${result.mismatches.join("、")}`
+        );
+      } else if (result.extractedDir) {
+        setSuccess(
+          (result.verified ? "合并并解包完成，已与清单核对" : "合并并解包完成") +
+            archiveType +
+            detected +
+            conflicts +
+            repaired +
+            signature
+        );
       } else {
-        setSuccess("合并完成");
+        setSuccess(
+          (result.verified ? "合并完成，已与清单核对" : "合并完成") +
+            detected +
+            repaired +
+            signature
+        );
       }
     } catch (err) {
       setError(String(err));
@@ -649,40 +1351,241 @@ function App() {
     }
   };
 
-  const currentProgress = () => {
-    const data = progress();
-    if (!data || data.totalBytes === 0) return 0;
-    return Math.min(100, (data.processedBytes / data.totalBytes) * 100);
-  };
-
-  const copyLuaSnippet = async () => {
-    if (!luaSnippet()) return;
+  const verifyRestoreParts = async () => {
+    resetStatus();
+    if (!restoreInputPath()) {
+      setError("请先选择分片文件或目录");
+      return;
+    }
     try {
-      await navigator.clipboard.writeText(luaSnippet());
-      setCopyHint("已复制");
+      setRunning(true);
+      const report = await invoke<VerifyReport>("verify_parts", {
+        options: { inputPath: restoreInputPath() },
+      });
+      setVerifyReport(report);
+      if (!report.ok) {
+        setError("预检未通过，请检查下方列出的分片");
+      } else if (report.hasManifest) {
+        setSuccess(`预检通过：${report.foundParts} 份分片与清单一致`);
+      } else {
+        setSuccess(`序号连续，共 ${report.foundParts} 份（没有清单，未校验内容）`);
+      }
     } catch (err) {
-      setCopyHint("复制失败");
+      setError(String(err));
+    } finally {
+      setRunning(false);
     }
   };
 
-  const openPartsFolder = async () => {
-    const files = outputFiles();
-    if (!files.length) return;
-    const primary =
-      workMode() === "restore" && files.length > 1
-        ? files[files.length - 1]
-        : files[0];
-    const dir = workMode() === "restore" && files.length > 1
-      ? primary
-      : extractDir(primary);
-    if (!dir) return;
+  const repairRestoreParts = async () => {
+    resetStatus();
+    if (!restoreInputPath()) {
+      setError("请先选择分片文件或目录");
+      return;
+    }
     try {
-      setOpenHint("");
-      await openPath(dir);
-      setOpenHint("已打开");
+      setRunning(true);
+      const report = await invoke<RepairReport>("repair_parts", {
+        options: { inputPath: restoreInputPath() },
+      });
+      if (report.damagedParts.length === 0) {
+        setSuccess("分片都完好，不需要修复");
+      } else {
+        setSuccess(
+          `已用${repairMethodText[report.method]}修复 ${report.repairedParts.length} 份分片：${report.damagedParts.join("、")}`
+        );
+      }
     } catch (err) {
-      try {
-        await revealItemInDir(primary);
+      setError(String(err));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const previewRestoreContents = async () => {
+    resetStatus();
+    if (!restoreInputPath()) {
+      setError("请先选择分片文件或目录");
+      return;
+    }
+    try {
+      setRunning(true);
+      const report = await invoke<ContentsReport>("list_archive_contents", {
+        options: { inputPath: restoreInputPath(), mergeMode: restoreMode() },
+      });
+      setContentsReport(report);
+      setSuccess(
+        `共 ${report.files} 个文件，解压后 ${formatBytes(report.totalSize)}` +
+          (report.encrypted ? "，含加密文件，解压需要密码" : "")
+      );
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const renderContentNodes = (nodes: ContentNode[]) => (
+    <ul class="contents-tree">
+      <For each={nodes}>
+        {(node) => (
+          <li>
+            <Show
+              when={node.isDir}
+              fallback={
+                <span>
+                  {node.name}（{formatBytes(node.size)}
+                  {node.modified ? `，${node.modified}` : ""}
+                  {node.encrypted ? "，加密" : ""}）
+                </span>
+              }
+            >
+              <details>
+                <summary>
+                  {node.name}/（{formatBytes(node.size)}）
+                </summary>
+                {renderContentNodes(node.children)}
+              </details>
+            </Show>
+          </li>
+        )}
+      </For>
+    </ul>
+  );
+
+  const reencryptRestoreParts = async () => {
+    resetStatus();
+    if (!restoreInputPath()) {
+      setError("请先选择分片文件或目录");
+      return;
+    }
+    try {
+      setRunning(true);
+      const report = await invoke<ReencryptReport>("reencrypt_parts", {
+        options: {
+          inputPath: restoreInputPath(),
+          mergeMode: restoreMode(),
+          oldPassword: restorePassword().trim() ? restorePassword().trim() : undefined,
+          oldKeyfile: restoreKeyfile() || undefined,
+          newPassword: reencryptPassword().trim() ? reencryptPassword().trim() : undefined,
+        },
+      });
+      setSuccess(
+        (report.encrypted
+          ? `已以新密码重新加密 ${report.outputFiles.length} 份分片`
+          : `已去掉 ${report.outputFiles.length} 份分片的加密`) +
+          "；恢复分片、异或校验分片与 PAR2 文件需重新生成"
+      );
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const decryptRestoreParts = async () => {
+    resetStatus();
+    if (!restoreInputPath()) {
+      setError("请先选择分片文件或目录");
+      return;
+    }
+    try {
+      setRunning(true);
+      const report = await invoke<DecryptReport>("decrypt_parts", {
+        options: {
+          inputPath: restoreInputPath(),
+          mergeMode: restoreMode(),
+          password: restorePassword().trim() ? restorePassword().trim() : undefined,
+          keyfile: restoreKeyfile() || undefined,
+          ageIdentities:
+            (restoreMode() === "age-split" || restoreMode() === "auto") &&
+            restoreAgeIdentity()
+              ? [restoreAgeIdentity()]
+              : undefined,
+        },
+      });
+      setSuccess(
+        `已去掉 ${report.outputFiles.length} 份分片的加密，现为 ${report.packMode} 分片；` +
+          "恢复分片、异或校验分片与 PAR2 文件需重新生成"
+      );
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const testRestoreParts = async () => {
+    resetStatus();
+    if (!restoreInputPath()) {
+      setError("请先选择分片文件或目录");
+      return;
+    }
+    try {
+      setRunning(true);
+      const report = await invoke<TestReport>("test_archive", {
+        options: {
+          inputPath: restoreInputPath(),
+          mergeMode: restoreMode(),
+          password: restorePassword().trim() ? restorePassword().trim() : undefined,
+          keyfile: restoreKeyfile() || undefined,
+        },
+      });
+      if (report.ok) {
+        setSuccess(`测试通过：${report.entriesTested} 个文件均可正常解压`);
+      } else {
+        setError(
+          `测试未通过：${report.failures
+            .map((failure) =>
+              failure.locations.length
+                ? `${failure.entry}（${failure.error}，位于 ${failure.locations
+                    .map((range) => `${range.part} 第 ${range.start}–${range.end} 字节`)
+                    .join("、")}）`
+                : `${failure.entry}（${failure.error}）`
+            )
+            .join("、")}`
+        );
+      }
+    } catch (err) {
+      setError(String(err));
+    } finally {
+      setRunning(false);
+    }
+  };
+
+  const progressPercent = (data: ProgressPayload) => {
+    if (!data.totalBytes) return 0;
+    return Math.min(100, (data.processedBytes / data.totalBytes) * 100);
+  };
+
+  const copyLuaSnippet = async () => {
+    if (!luaSnippet()) return;
+    try {
+      await navigator.clipboard.writeText(luaSnippet());
+      setCopyHint("已复制");
+    } catch (err) {
+      setCopyHint("复制失败");
+    }
+  };
+
+  const openPartsFolder = async () => {
+    const files = outputFiles();
+    if (!files.length) return;
+    const primary =
+      workMode() === "restore" && files.length > 1
+        ? files[files.length - 1]
+        : files[0];
+    const dir = workMode() === "restore" && files.length > 1
+      ? primary
+      : extractDir(primary);
+    if (!dir) return;
+    try {
+      setOpenHint("");
+      await openPath(dir);
+      setOpenHint("已打开");
+    } catch (err) {
+      try {
+        await revealItemInDir(primary);
         setOpenHint("已打开");
       } catch (inner) {
         setOpenHint("打开失败");
@@ -771,6 +1674,9 @@ function App() {
                 选择文件
               </button>
             </div>
+            <Show when={fieldError("inputPath")}>
+              <p class="field-error">{fieldError("inputPath")}</p>
+            </Show>
           </div>
 
           <div
@@ -793,6 +1699,9 @@ function App() {
                 选择目录
               </button>
             </div>
+            <Show when={fieldError("outputDir")}>
+              <p class="field-error">{fieldError("outputDir")}</p>
+            </Show>
           </div>
         </div>
 
@@ -851,6 +1760,11 @@ function App() {
               />
             </label>
           </div>
+          <Show when={fieldError("sizeBytes") || fieldError("count")}>
+            <p class="field-error">
+              {fieldError("sizeBytes") || fieldError("count")}
+            </p>
+          </Show>
 
           <Show when={packMode() === "split-then-zip"}>
             <div class="field">
@@ -877,6 +1791,9 @@ function App() {
                   <span>先 Store → 切分 → 压缩</span>
                 </label>
               </div>
+              <Show when={fieldError("dirSplitMode")}>
+                <p class="field-error">{fieldError("dirSplitMode")}</p>
+              </Show>
             </div>
           </Show>
         </div>
@@ -914,175 +1831,1241 @@ function App() {
                 filename.parts/filename.zip.part-0001
               </span>
             </label>
-          </div>
-
-          <div class="field">
-            <label>压缩密码（可选）</label>
-            <input
-              type="password"
-              value={password()}
-              placeholder="留空则不加密"
-              onInput={(e) => setPassword(e.currentTarget.value)}
-              disabled={running()}
-            />
-          </div>
-          <div class="field">
-            <label>压缩等级</label>
-            <select
-              value={compressionLevel()}
-              onChange={(e) => setCompressionLevel(e.currentTarget.value)}
-              disabled={running()}
-            >
-              <option value="1">速度优先（1）</option>
-              <option value="6">平衡（6）</option>
-              <option value="9">体积优先（9）</option>
-            </select>
-          </div>
-        </div>
-
-
-        <div class="card" classList={{ hidden: workMode() !== "restore" }}>
-          <h2>分片来源与输出</h2>
-          <div
-            class="field dropzone"
-            data-drop-target="input-restore"
-            onDrop={handleFileDrop}
-            onDragEnter={handleDragEnter("input-restore")}
-            onDragOver={handleDragOver("input-restore")}
-            onDragLeave={handleDragLeave("input-restore")}
-            classList={{ "drop-active": dropHint() === "input-restore" }}
-          >
-            <label>分片文件/目录</label>
-            <div class="path-row multi">
-              <input
-                readOnly
-                value={restoreInputPath()}
-                placeholder="选择任意分片文件或 .parts 目录"
-              />
-              <button onClick={chooseRestoreFile} disabled={running()}>
-                选文件
-              </button>
-              <button onClick={chooseRestoreFolder} disabled={running()}>
-                选目录
-              </button>
-            </div>
-            <p class="hint">支持拖拽分片文件或分片目录。</p>
-          </div>
-
-          <div
-            class="field"
-            data-drop-target="output-restore"
-            onDrop={handleRestoreOutputDrop}
-            onDragEnter={handleDragEnter("output-restore")}
-            onDragOver={handleDragOver("output-restore")}
-            onDragLeave={handleDragLeave("output-restore")}
-            classList={{ "drop-active": dropHint() === "output-restore" }}
-          >
-            <label>输出目录</label>
-            <div class="path-row">
-              <input
-                readOnly
-                value={restoreOutputDir()}
-                placeholder="合并输出目录（默认同目录）"
-              />
-              <button onClick={chooseRestoreOutput} disabled={running()}>
-                选择目录
-              </button>
-            </div>
-          </div>
-        </div>
-
-        <div class="card" classList={{ hidden: workMode() !== "restore" }}>
-          <h2>合并方式</h2>
-          <div class="option-row">
             <label class="option inline">
               <span class="option-label">
                 <input
                   type="radio"
-                  name="restoreMode"
-                  checked={restoreMode() === "split-then-zip"}
-                  onChange={() => setRestoreMode("split-then-zip")}
+                  name="packMode"
+                  checked={packMode() === "7z-then-split"}
+                  onChange={() => setPackMode("7z-then-split")}
                   disabled={running()}
                 />
-                <span>解包合并</span>
+                <span>先 7z 压缩然后分割</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.7z.part-0001（设置密码时加密文件名）
               </span>
-              <span class="option-hint">对应 先分割后逐个压缩</span>
             </label>
             <label class="option inline">
               <span class="option-label">
                 <input
                   type="radio"
-                  name="restoreMode"
-                  checked={restoreMode() === "zip-then-split"}
-                  onChange={() => setRestoreMode("zip-then-split")}
+                  name="packMode"
+                  checked={packMode() === "zstd-then-split"}
+                  onChange={() => setPackMode("zstd-then-split")}
                   disabled={running()}
                 />
-                <span>合并解包</span>
+                <span>先 zstd 压缩然后分割（可随机读取）</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.zst.part-0001（不支持密码）
               </span>
-              <span class="option-hint">对应 先压缩后分割</span>
             </label>
-          </div>
-        </div>
-
-        <div class="card" classList={{ hidden: workMode() !== "restore" }}>
-          <h2>解包设置</h2>
-          <label class="option inline">
-            <span class="option-label">
-              <input
-                type="checkbox"
-                checked={restoreAutoExtract()}
-                onChange={(e) => setRestoreAutoExtract(e.currentTarget.checked)}
-                disabled={running()}
-              />
-              <span>合并后自动解压</span>
-            </span>
-            <span class="option-hint">合并结果为 Zip 时自动解包</span>
-          </label>
-          <div class="field">
-            <label>解密/解压密码（可选）</label>
-            <input
-              type="password"
-              value={restorePassword()}
-              placeholder="分片或 Zip 密码"
-              onInput={(e) => setRestorePassword(e.currentTarget.value)}
-              disabled={running()}
-            />
-          </div>
-        </div>
-
-        <div class="card accent">
-          <h2>执行</h2>
-          <button class="primary" onClick={startProcess} disabled={running()}>
-            {running()
-              ? "处理中..."
-              : workMode() === "pack"
-              ? "开始切分"
-              : "开始合并"}
-          </button>
-
-          <Show when={progress()}>
-            <div class="progress">
-              <div class="progress-bar">
-                <div
-                  class="progress-fill"
-                  style={{ width: `${currentProgress()}%` }}
-                />
-              </div>
-              <div class="progress-meta">
-                <span>{progress()!.message}</span>
-                <span>
-                  {formatBytes(progress()!.processedBytes)} /{" "}
-                  {formatBytes(progress()!.totalBytes)}
-                </span>
-              </div>
-              <Show when={progress()!.partTotal > 0}>
-                <div class="progress-part">
-                  分片 {progress()!.partIndex} / {progress()!.partTotal}
-                </div>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="packMode"
+                  checked={packMode() === "tar-gz-then-split"}
+                  onChange={() => setPackMode("tar-gz-then-split")}
+                  disabled={running()}
+                />
+                <span>先 tar.gz 打包然后分割</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.tar.gz.part-0001（保留 Unix 权限与符号链接，不支持密码）
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="packMode"
+                  checked={packMode() === "tar-zst-then-split"}
+                  onChange={() => setPackMode("tar-zst-then-split")}
+                  disabled={running()}
+                />
+                <span>先 tar.zst 打包然后分割（多线程）</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.tar.zst.part-0001（压缩级别 1–19，不支持密码）
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="packMode"
+                  checked={packMode() === "raw-split"}
+                  onChange={() => setPackMode("raw-split")}
+                  disabled={running()}
+                />
+                <span>直接分割（不压缩）</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.part-001（仅单个文件，适合已压缩的数据，可用 cat 或
+                copy /b 拼接）
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="packMode"
+                  checked={packMode() === "age-split"}
+                  onChange={() => setPackMode("age-split")}
+                  disabled={running()}
+                />
+                <span>age 加密分割</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.part-001.age（仅单个文件，每份以 age 加密，口令为密码或加密给
+                age 公钥，可用 age -d 单独解密）
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="packMode"
+                  checked={packMode() === "container-then-split"}
+                  onChange={() => setPackMode("container-then-split")}
+                  disabled={running()}
+                />
+                <span>加密容器然后分割</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.fspc.part-001（必须设置密码，Argon2id 派生密钥，比 zip AES
+                更能抵抗暴力破解，只能用本程序合并）
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="packMode"
+                  checked={packMode() === "spanned-zip"}
+                  onChange={() => setPackMode("spanned-zip")}
+                  disabled={running()}
+                />
+                <span>标准分卷 zip</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.z01 … filename.zip（只能按大小，每卷 64 KiB 至 4
+                GiB，WinZip、7-Zip 打开 .zip 即可解压）
+              </span>
+            </label>
+          </div>
+          <Show when={fieldError("packMode")}>
+            <p class="field-error">{fieldError("packMode")}</p>
+          </Show>
+          <Show when={packMode() === "7z-then-split"}>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={sevenZipVolumes()}
+                  onChange={(e) => setSevenZipVolumes(e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>7-Zip 分卷命名</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.7z.001，可直接用 7-Zip 打开
+              </span>
+            </label>
+          </Show>
+          <Show when={packMode() === "container-then-split"}>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={perPartKeys()}
+                  onChange={(e) => setPerPartKeys(e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>逐份独立加密</span>
+              </span>
+              <span class="option-hint">
+                每份分片用按序号派生的子密钥单独加密，可单独解密与核对
+              </span>
+            </label>
+          </Show>
+          <Show when={fieldError("perPartKeys")}>
+            <p class="field-error">{fieldError("perPartKeys")}</p>
+          </Show>
+          <Show
+            when={
+              packMode() !== "split-then-zip" &&
+              packMode() !== "age-split" &&
+              packMode() !== "spanned-zip" &&
+              packMode() !== "7z-then-split"
+            }
+          >
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={numberedVolumes()}
+                  onChange={(e) => setNumberedVolumes(e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>.001 分卷命名</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/filename.zip.001，HJSplit、FFSJ 与 7-Zip 可直接合并
+              </span>
+            </label>
+            <Show when={fieldError("numberedVolumes")}>
+              <p class="field-error">{fieldError("numberedVolumes")}</p>
+            </Show>
+          </Show>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={selfExtracting()}
+                onChange={(e) => setSelfExtracting(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>附带自解压程序</span>
+            </span>
+            <span class="option-hint">
+              filename.parts/restore，接收方运行即可合并解压，仅限与本机相同的系统
+            </span>
+          </label>
+          <Show when={fieldError("selfExtracting")}>
+            <p class="field-error">{fieldError("selfExtracting")}</p>
+          </Show>
+          <Show
+            when={
+              packMode() !== "split-then-zip" &&
+              packMode() !== "age-split" &&
+              packMode() !== "spanned-zip"
+            }
+          >
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={rejoinScripts()}
+                  onChange={(e) => setRejoinScripts(e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>附带合并脚本</span>
+              </span>
+              <span class="option-hint">
+                filename.parts/rejoin.bat 与 rejoin.sh，只用系统自带工具拼接并解压
+              </span>
+            </label>
+            <Show when={fieldError("rejoinScripts")}>
+              <p class="field-error">{fieldError("rejoinScripts")}</p>
+            </Show>
+          </Show>
+          <Show when={packMode() === "zstd-then-split"}>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={zstdDictionary()}
+                  onChange={(e) => setZstdDictionary(e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>训练 zstd 字典</span>
+              </span>
+              <span class="option-hint">
+                打包目录时从中抽样训练字典，大量相似的小文件压缩率更高
+              </span>
+            </label>
+            <Show when={fieldError("zstdDictionary")}>
+              <p class="field-error">{fieldError("zstdDictionary")}</p>
+            </Show>
+          </Show>
+
+          <div class="field">
+            <label>压缩密码（可选）</label>
+            <input
+              type="password"
+              value={password()}
+              placeholder="留空则不加密"
+              onInput={(e) => updatePasswordStrength(e.currentTarget.value)}
+              disabled={running()}
+            />
+            <Show when={passwordStrength()}>
+              {(strength) => (
+                <p class="hint">
+                  密码强度：{STRENGTH_LABELS[strength().score]}（约{" "}
+                  {strength().entropyBits} 位）
+                  <For each={strength().warnings}>
+                    {(warning) => <span>；{warning}</span>}
+                  </For>
+                </p>
+              )}
+            </Show>
+            <Show when={fieldError("password")}>
+              <p class="field-error">{fieldError("password")}</p>
+            </Show>
+          </div>
+          <div class="field">
+            <label>密钥文件（可选）</label>
+            <div class="path-row multi">
+              <input
+                readOnly
+                value={keyfile()}
+                placeholder="代替或补充密码，合并时需选同一个文件"
+              />
+              <button
+                onClick={() => chooseKeyfile(setKeyfile)}
+                disabled={running()}
+              >
+                选择
+              </button>
+              <Show when={keyfile()}>
+                <button onClick={() => setKeyfile("")} disabled={running()}>
+                  清除
+                </button>
+              </Show>
+            </div>
+            <Show when={fieldError("keyfile")}>
+              <p class="field-error">{fieldError("keyfile")}</p>
+            </Show>
+          </div>
+          <Show when={packMode() === "age-split"}>
+            <div class="field">
+              <label>age 接收方公钥（可选）</label>
+              <input
+                value={ageRecipients()}
+                placeholder="age1… 公钥或接收方文件路径，多个用空格或逗号分隔；填写后不能再设密码"
+                onInput={(e) => setAgeRecipients(e.currentTarget.value)}
+                disabled={running()}
+              />
+              <Show when={fieldError("ageRecipients")}>
+                <p class="field-error">{fieldError("ageRecipients")}</p>
+              </Show>
+            </div>
+          </Show>
+          <Show when={packMode() !== "spanned-zip"}>
+            <div class="field">
+              <label>GPG 接收方（可选）</label>
+              <input
+                value={gpgRecipients()}
+                placeholder="打包后把分片加密给这些 GPG 公钥（指纹、密钥 ID 或邮箱），多个用空格或逗号分隔"
+                onInput={(e) => setGpgRecipients(e.currentTarget.value)}
+                disabled={running()}
+              />
+              <Show when={fieldError("gpgRecipients")}>
+                <p class="field-error">{fieldError("gpgRecipients")}</p>
+              </Show>
+            </div>
+          </Show>
+          <Show
+            when={
+              (password().trim() || keyfile()) &&
+              (packMode() === "split-then-zip" ||
+                packMode() === "zip-then-split" ||
+                packMode() === "spanned-zip")
+            }
+          >
+            <div class="field">
+              <label>加密强度</label>
+              <select
+                value={aesStrength()}
+                onChange={(e) => setAesStrength(e.currentTarget.value)}
+                disabled={running()}
+              >
+                <option value="256">AES-256（默认）</option>
+                <option value="192">AES-192</option>
+                <option value="128">AES-128（大文件更快）</option>
+              </select>
+              <Show when={fieldError("aesStrength")}>
+                <p class="field-error">{fieldError("aesStrength")}</p>
+              </Show>
+            </div>
+          </Show>
+          <Show
+            when={
+              (password().trim() || keyfile()) &&
+              (packMode() === "zip-then-split" || packMode() === "7z-then-split")
+            }
+          >
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={encryptNames()}
+                  onChange={(e) => setEncryptNames(e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>加密文件名</span>
+              </span>
+              <span class="option-hint">
+                不知道密码时看不到压缩包中的文件名与目录结构
+              </span>
+            </label>
+            <Show when={fieldError("encryptNames")}>
+              <p class="field-error">{fieldError("encryptNames")}</p>
+            </Show>
+          </Show>
+          <Show
+            when={
+              packMode() === "split-then-zip" ||
+              packMode() === "zip-then-split" ||
+              packMode() === "spanned-zip"
+            }
+          >
+            <div class="field">
+              <label>压缩算法</label>
+              <select
+                value={compressionMethod()}
+                onChange={(e) =>
+                  setCompressionMethod(
+                    e.currentTarget.value as "deflate" | "bzip2" | "xz"
+                  )
+                }
+                disabled={running()}
+              >
+                <option value="deflate">Deflate（兼容性最好）</option>
+                <option value="bzip2">Bzip2（文本压缩率更高，较慢）</option>
+                <option value="xz">XZ / LZMA2（压缩率最高，最慢）</option>
+              </select>
+              <Show when={compressionMethod() === "xz"}>
+                <p class="hint">
+                  需要 7-Zip 或本程序解压，系统自带的解压功能打不开
+                </p>
+              </Show>
+              <Show when={fieldError("compressionMethod")}>
+                <p class="field-error">{fieldError("compressionMethod")}</p>
+              </Show>
+            </div>
+          </Show>
+          <div class="field">
+            <label>压缩等级</label>
+            <select
+              value={compressionLevel()}
+              onChange={(e) => setCompressionLevel(e.currentTarget.value)}
+              disabled={running()}
+            >
+              <option value="1">速度优先（1）</option>
+              <option value="6">平衡（6）</option>
+              <option value="9">体积优先（9）</option>
+            </select>
+          </div>
+          <div class="field">
+            <label>校验算法</label>
+            <select
+              value={hashAlgorithm()}
+              onChange={(e) =>
+                setHashAlgorithm(e.currentTarget.value as "sha256" | "blake3")
+              }
+              disabled={running()}
+            >
+              <option value="sha256">SHA-256</option>
+              <option value="blake3">BLAKE3（多线程，适合大文件）</option>
+            </select>
+            <Show when={fieldError("hashAlgorithm")}>
+              <p class="field-error">{fieldError("hashAlgorithm")}</p>
+            </Show>
+          </div>
+          <div class="field">
+            <label>PAR2 恢复数据</label>
+            <select
+              value={par2Redundancy()}
+              onChange={(e) => setPar2Redundancy(e.currentTarget.value)}
+              disabled={running()}
+            >
+              <option value="0">不生成</option>
+              <option value="5">5%</option>
+              <option value="10">10%</option>
+              <option value="20">20%</option>
+              <option value="30">30%</option>
+            </select>
+            <Show when={fieldError("par2Redundancy")}>
+              <p class="field-error">{fieldError("par2Redundancy")}</p>
+            </Show>
+          </div>
+          <div class="field">
+            <label>恢复分片</label>
+            <select
+              value={redundancyParts()}
+              onChange={(e) => setRedundancyParts(e.currentTarget.value)}
+              disabled={running()}
+            >
+              <option value="0">不生成</option>
+              <option value="xor">异或校验（1 份，最快）</option>
+              <option value="1">1 份</option>
+              <option value="2">2 份</option>
+              <option value="3">3 份</option>
+              <option value="4">4 份</option>
+            </select>
+            <Show when={fieldError("redundancyParts") || fieldError("xorParity")}>
+              <p class="field-error">
+                {fieldError("redundancyParts") || fieldError("xorParity")}
+              </p>
+            </Show>
+          </div>
+          <div class="field">
+            <label>清单签名私钥（可选）</label>
+            <div class="path-row multi">
+              <input
+                readOnly
+                value={signingKey()}
+                placeholder="Ed25519 私钥文件，对完整性清单签名"
+              />
+              <button onClick={chooseSigningKey} disabled={running()}>
+                选择
+              </button>
+              <button onClick={generateSigningKey} disabled={running()}>
+                生成
+              </button>
+              <Show when={signingKey()}>
+                <button onClick={() => setSigningKey("")} disabled={running()}>
+                  清除
+                </button>
+              </Show>
+            </div>
+            <Show when={fieldError("signingKey")}>
+              <p class="field-error">{fieldError("signingKey")}</p>
+            </Show>
+          </div>
+          <div class="field">
+            <label>完成后</label>
+            <select
+              value={powerAction()}
+              onChange={(e) =>
+                setPowerAction(
+                  e.currentTarget.value as
+                    | "none"
+                    | "shutdown"
+                    | "sleep"
+                    | "hibernate"
+                )
+              }
+              disabled={running()}
+            >
+              <option value="none">不操作</option>
+              <option value="shutdown">关机</option>
+              <option value="sleep">睡眠</option>
+              <option value="hibernate">休眠</option>
+            </select>
+            <Show when={fieldError("powerAction")}>
+              <p class="field-error">{fieldError("powerAction")}</p>
+            </Show>
+          </div>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={backgroundPriority()}
+                onChange={(e) => setBackgroundPriority(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>后台优先级</span>
+            </span>
+            <span class="option-hint">降低 CPU 与磁盘优先级，切分时不拖慢其他程序</span>
+          </label>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={verifyAfterSplit()}
+                onChange={(e) => setVerifyAfterSplit(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>分割后核对</span>
+            </span>
+            <span class="option-hint">重新读取每份分片并与源比对，发现写坏的数据</span>
+          </label>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={destroySource()}
+                onChange={(e) => setDestroySource(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>分割后销毁源文件</span>
+            </span>
+            <span class="option-hint">
+              全部完成且核对通过后覆写并删除源文件或目录，无法恢复；SSD 与写时复制文件系统上可能仍有残留
+            </span>
+          </label>
+          <Show when={fieldError("destroySource")}>
+            <p class="field-error">{fieldError("destroySource")}</p>
+          </Show>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={secureDelete()}
+                onChange={(e) => setSecureDelete(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>安全删除中间文件</span>
+            </span>
+            <span class="option-hint">
+              中间文件与取消后的输出先覆写再删除；设置密码时明文中间文件总会覆写
+            </span>
+          </label>
+        </div>
+
+        <div class="card" classList={{ hidden: workMode() !== "pack" }}>
+          <h2>监视目录</h2>
+          <div class="field">
+            <label>监视目录</label>
+            <div class="path-row">
+              <input
+                readOnly
+                value={watchDir()}
+                placeholder="出现新文件或文件夹时按当前设置自动切分"
+              />
+              <button onClick={chooseWatchDir} disabled={watching()}>
+                选择
+              </button>
+            </div>
+          </div>
+          <button class="ghost" onClick={toggleWatch}>
+            {watching() ? "停止监视" : "开始监视"}
+          </button>
+        </div>
+
+
+        <div class="card" classList={{ hidden: workMode() !== "restore" }}>
+          <h2>分片来源与输出</h2>
+          <div
+            class="field dropzone"
+            data-drop-target="input-restore"
+            onDrop={handleFileDrop}
+            onDragEnter={handleDragEnter("input-restore")}
+            onDragOver={handleDragOver("input-restore")}
+            onDragLeave={handleDragLeave("input-restore")}
+            classList={{ "drop-active": dropHint() === "input-restore" }}
+          >
+            <label>分片文件/目录</label>
+            <div class="path-row multi">
+              <input
+                readOnly
+                value={restoreInputPath()}
+                placeholder="选择任意分片文件或 .parts 目录"
+              />
+              <button onClick={chooseRestoreFile} disabled={running()}>
+                选文件
+              </button>
+              <button onClick={chooseRestoreFolder} disabled={running()}>
+                选目录
+              </button>
+              <button onClick={verifyRestoreParts} disabled={running()}>
+                预检
+              </button>
+              <button onClick={repairRestoreParts} disabled={running()}>
+                修复
+              </button>
+              <button onClick={previewRestoreContents} disabled={running()}>
+                预览
+              </button>
+              <button onClick={testRestoreParts} disabled={running()}>
+                测试
+              </button>
+              <button onClick={reencryptRestoreParts} disabled={running()}>
+                改密码
+              </button>
+              <button onClick={decryptRestoreParts} disabled={running()}>
+                去加密
+              </button>
+            </div>
+            <p class="hint">
+              支持拖拽分片文件或分片目录。预检只核对分片，不合并；预览不解压，列出压缩包中的文件；修复用恢复分片或 PAR2
+              恢复文件重建损坏的分片，写回分片目录；测试按当前合并方式与密码逐个解压并核对
+              CRC，不写出文件；改密码以下方的密码解密后用新密码重新加密分片，去加密原地把分片转为不加密的分片，均不合并。
+            </p>
+            <Show when={verifyReport()}>
+              {(report) => (
+                <ul class="verify-report">
+                  <Show when={report().missingIndices.length > 0}>
+                    <li class="bad">
+                      缺少第 {report().missingIndices.join("、")} 份
+                    </li>
+                  </Show>
+                  <For
+                    each={report().parts.filter(
+                      (part) => part.status !== "ok" && part.status !== "unchecked"
+                    )}
+                  >
+                    {(part) => (
+                      <li class="bad">
+                        {part.name}：{partStatusText[part.status]}
+                      </li>
+                    )}
+                  </For>
+                </ul>
+              )}
+            </Show>
+            <Show when={contentsReport()}>
+              {(report) => renderContentNodes(report().entries)}
+            </Show>
+          </div>
+
+          <div class="field">
+            <label>分片网址（可选）</label>
+            <textarea
+              rows={3}
+              value={restoreUrls()}
+              placeholder="每行一个分片的 http(s) 网址，或一个 .manifest.json 清单的网址"
+              onInput={(e) => setRestoreUrls(e.currentTarget.value)}
+              disabled={running()}
+            />
+            <p class="hint">
+              填写后先下载到输出目录再合并，忽略上面选择的分片；给出清单网址时按清单下载其中列出的分片。下载中断会自动续传，合并失败时保留已下载的分片，重新开始只下载缺少的部分。
+            </p>
+            <Show when={restoreUrls().trim()}>
+              <label class="option inline">
+                <span class="option-label">
+                  <input
+                    type="checkbox"
+                    checked={keepDownloads()}
+                    onChange={(e) => setKeepDownloads(e.currentTarget.checked)}
+                    disabled={running()}
+                  />
+                  <span>合并后保留下载的分片</span>
+                </span>
+              </label>
+            </Show>
+          </div>
+
+          <div
+            class="field"
+            data-drop-target="output-restore"
+            onDrop={handleRestoreOutputDrop}
+            onDragEnter={handleDragEnter("output-restore")}
+            onDragOver={handleDragOver("output-restore")}
+            onDragLeave={handleDragLeave("output-restore")}
+            classList={{ "drop-active": dropHint() === "output-restore" }}
+          >
+            <label>输出目录</label>
+            <div class="path-row">
+              <input
+                readOnly
+                value={restoreOutputDir()}
+                placeholder="合并输出目录（默认同目录）"
+              />
+              <button onClick={chooseRestoreOutput} disabled={running()}>
+                选择目录
+              </button>
+            </div>
+          </div>
+        </div>
+
+        <div class="card" classList={{ hidden: workMode() !== "restore" }}>
+          <h2>合并方式</h2>
+          <div class="option-row">
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "auto"}
+                  onChange={() => setRestoreMode("auto")}
+                  disabled={running()}
+                />
+                <span>自动识别</span>
+              </span>
+              <span class="option-hint">按清单、分片命名与第一份分片的内容判断</span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "split-then-zip"}
+                  onChange={() => setRestoreMode("split-then-zip")}
+                  disabled={running()}
+                />
+                <span>解包合并</span>
+              </span>
+              <span class="option-hint">对应 先分割后逐个压缩</span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "zip-then-split"}
+                  onChange={() => setRestoreMode("zip-then-split")}
+                  disabled={running()}
+                />
+                <span>合并解包</span>
+              </span>
+              <span class="option-hint">
+                对应 先压缩后分割，也可还原其他软件生成的 .z01/.zip 分卷与 .zip.001 分卷，
+                并直接解压分卷 RAR
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "7z-then-split"}
+                  onChange={() => setRestoreMode("7z-then-split")}
+                  disabled={running()}
+                />
+                <span>合并解包 7z</span>
+              </span>
+              <span class="option-hint">对应 先 7z 压缩然后分割</span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "zstd-then-split"}
+                  onChange={() => setRestoreMode("zstd-then-split")}
+                  disabled={running()}
+                />
+                <span>合并解包 zstd</span>
+              </span>
+              <span class="option-hint">对应 先 zstd 压缩然后分割</span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "tar-gz-then-split"}
+                  onChange={() => setRestoreMode("tar-gz-then-split")}
+                  disabled={running()}
+                />
+                <span>合并解包 tar.gz</span>
+              </span>
+              <span class="option-hint">对应 先 tar.gz 打包然后分割</span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "tar-zst-then-split"}
+                  onChange={() => setRestoreMode("tar-zst-then-split")}
+                  disabled={running()}
+                />
+                <span>合并解包 tar.zst</span>
+              </span>
+              <span class="option-hint">对应 先 tar.zst 打包然后分割</span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "raw"}
+                  onChange={() => setRestoreMode("raw")}
+                  disabled={running()}
+                />
+                <span>直接拼接</span>
+              </span>
+              <span class="option-hint">
+                对应 直接分割，也可拼接 split 生成的分片（选择第一份，如 xaa）
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "age-split"}
+                  onChange={() => setRestoreMode("age-split")}
+                  disabled={running()}
+                />
+                <span>解密 age 分片</span>
+              </span>
+              <span class="option-hint">
+                对应 age 加密分割，用密码或 age 身份文件解密后拼接
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "container-then-split"}
+                  onChange={() => setRestoreMode("container-then-split")}
+                  disabled={running()}
+                />
+                <span>解密加密容器</span>
+              </span>
+              <span class="option-hint">
+                对应 加密容器然后分割，用密码解密后可自动解压
+              </span>
+            </label>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="radio"
+                  name="restoreMode"
+                  checked={restoreMode() === "spanned-zip"}
+                  onChange={() => setRestoreMode("spanned-zip")}
+                  disabled={running()}
+                />
+                <span>合并分卷 zip</span>
+              </span>
+              <span class="option-hint">
+                对应 标准分卷 zip，也可合并 WinZip、zip -s 生成的 .z01 分卷
+              </span>
+            </label>
+          </div>
+        </div>
+
+        <div class="card" classList={{ hidden: workMode() !== "restore" }}>
+          <h2>解包设置</h2>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={restoreAutoExtract()}
+                onChange={(e) => setRestoreAutoExtract(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>合并后自动解压</span>
+            </span>
+            <span class="option-hint">
+              合并结果为 zip、7z、RAR、tar（.gz/.zst/.xz）或 .gz/.xz 文件时自动解包
+            </span>
+          </label>
+          <Show when={restoreAutoExtract()}>
+            <div class="field">
+              <label>只解压匹配的文件（可选）</label>
+              <input
+                value={extractInclude()}
+                placeholder="如 *.psd，多个用空格或逗号分隔；仅用于 zip"
+                onInput={(e) => setExtractInclude(e.currentTarget.value)}
+                disabled={running()}
+              />
+            </div>
+            <div class="field">
+              <label>跳过匹配的文件（可选）</label>
+              <input
+                value={extractExclude()}
+                placeholder="如 */node_modules/*，多个用空格或逗号分隔"
+                onInput={(e) => setExtractExclude(e.currentTarget.value)}
+                disabled={running()}
+              />
+            </div>
+            <div class="field">
+              <label>已有同名文件</label>
+              <select
+                value={onConflict()}
+                onChange={(e) =>
+                  setOnConflict(
+                    e.currentTarget.value as "" | "overwrite" | "skip" | "rename" | "ask"
+                  )
+                }
+                disabled={running()}
+              >
+                <option value="">覆盖（默认）</option>
+                <option value="skip">跳过，保留已有文件</option>
+                <option value="rename">改名为“名称 (1)”</option>
+                <option value="ask">逐个询问</option>
+              </select>
+            </div>
+            <div class="field">
+              <label>zip 文件名编码</label>
+              <select
+                value={filenameEncoding()}
+                onChange={(e) => setFilenameEncoding(e.currentTarget.value)}
+                disabled={running()}
+              >
+                <option value="">CP437（默认）</option>
+                <option value="auto">自动识别</option>
+                <option value="gbk">GBK（简体中文）</option>
+                <option value="shift_jis">Shift-JIS（日文）</option>
+                <option value="big5">Big5（繁体中文）</option>
+                <option value="euc-kr">EUC-KR（韩文）</option>
+              </select>
+            </div>
+            <label class="option inline">
+              <span class="option-label">
+                <input
+                  type="checkbox"
+                  checked={!keepArchive()}
+                  onChange={(e) => setKeepArchive(!e.currentTarget.checked)}
+                  disabled={running()}
+                />
+                <span>只保留解压结果</span>
+              </span>
+              <span class="option-hint">
+                解压并核对后删除合并得到的压缩包；zip-then-split 直接从分片解压
+              </span>
+            </label>
+            <Show
+              when={
+                (restoreMode() === "zip-then-split" || restoreMode() === "auto") &&
+                keepArchive()
+              }
+            >
+              <label class="option inline">
+                <span class="option-label">
+                  <input
+                    type="checkbox"
+                    checked={extractFromParts()}
+                    onChange={(e) => setExtractFromParts(e.currentTarget.checked)}
+                    disabled={running()}
+                  />
+                  <span>直接从分片解压</span>
+                </span>
+                <span class="option-hint">
+                  不生成合并后的 zip，所需磁盘空间减半；7z、RAR 等仍先合并
+                </span>
+              </label>
+            </Show>
+          </Show>
+          <div class="field">
+            <label>解密/解压密码（可选）</label>
+            <input
+              type="password"
+              value={restorePassword()}
+              placeholder="分片或 Zip 密码"
+              onInput={(e) => setRestorePassword(e.currentTarget.value)}
+              disabled={running()}
+            />
+          </div>
+          <div class="field">
+            <label>新密码（改密码时使用）</label>
+            <input
+              type="password"
+              value={reencryptPassword()}
+              placeholder="留空时去掉加密（仅先分割后压缩）"
+              onInput={(e) => setReencryptPassword(e.currentTarget.value)}
+              disabled={running()}
+            />
+          </div>
+          <div class="field">
+            <label>密钥文件（可选）</label>
+            <div class="path-row multi">
+              <input
+                readOnly
+                value={restoreKeyfile()}
+                placeholder="打包时选择的密钥文件"
+              />
+              <button
+                onClick={() => chooseKeyfile(setRestoreKeyfile)}
+                disabled={running()}
+              >
+                选择
+              </button>
+              <Show when={restoreKeyfile()}>
+                <button onClick={() => setRestoreKeyfile("")} disabled={running()}>
+                  清除
+                </button>
               </Show>
             </div>
+          </div>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={restoreGpg()}
+                onChange={(e) => setRestoreGpg(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>先用 GPG 解密分片</span>
+            </span>
+            <span class="option-hint">
+              分片打包时加密给了 GPG 公钥，用本机密钥环中的私钥解密后再合并
+            </span>
+          </label>
+          <Show when={restoreMode() === "age-split" || restoreMode() === "auto"}>
+            <div class="field">
+              <label>age 身份文件（可选）</label>
+              <div class="path-row multi">
+                <input
+                  readOnly
+                  value={restoreAgeIdentity()}
+                  placeholder="分片加密给公钥时，选择对应的私钥（AGE-SECRET-KEY-1…）文件"
+                />
+                <button
+                  onClick={() => chooseKeyfile(setRestoreAgeIdentity)}
+                  disabled={running()}
+                >
+                  选择
+                </button>
+                <Show when={restoreAgeIdentity()}>
+                  <button
+                    onClick={() => setRestoreAgeIdentity("")}
+                    disabled={running()}
+                  >
+                    清除
+                  </button>
+                </Show>
+              </div>
+            </div>
+          </Show>
+          <div class="field">
+            <label>信任的签名公钥（可选）</label>
+            <input
+              value={trustedKeys()}
+              placeholder="公钥文件路径、十六进制公钥或指纹，多个用空格或逗号分隔"
+              onInput={(e) => setTrustedKeys(e.currentTarget.value)}
+              disabled={running()}
+            />
+          </div>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={requireSignature()}
+                onChange={(e) => setRequireSignature(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>要求清单签名</span>
+            </span>
+            <span class="option-hint">清单没有签名时拒绝合并</span>
+          </label>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={salvage()}
+                onChange={(e) => setSalvage(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>尽力解压</span>
+            </span>
+            <span class="option-hint">跳过损坏的条目，解压其余文件（zip）</span>
+          </label>
+          <div class="field">
+            <label>缺失分片</label>
+            <select
+              value={missingParts()}
+              onChange={(e) =>
+                setMissingParts(e.currentTarget.value as "" | "fill" | "truncate")
+              }
+              disabled={running()}
+            >
+              <option value="">报错（默认）</option>
+              <option value="fill">补零，保留其后的数据</option>
+              <option value="truncate">截断到第一处缺失</option>
+            </select>
+            <p class="hint">
+              分片丢失且无法用恢复分片重建时，合并其余分片并列出缺失的字节范围；适合抢救视频等容忍缺损的文件，仅用于 raw 与先压缩后分割
+            </p>
+          </div>
+          <label class="option inline">
+            <span class="option-label">
+              <input
+                type="checkbox"
+                checked={restoreSecureDelete()}
+                onChange={(e) => setRestoreSecureDelete(e.currentTarget.checked)}
+                disabled={running()}
+              />
+              <span>安全删除临时文件</span>
+            </span>
+            <span class="option-hint">临时目录与取消后的输出先覆写再删除</span>
+          </label>
+        </div>
+
+        <div class="card accent">
+          <h2>执行</h2>
+          <button class="primary" onClick={startProcess} disabled={running()}>
+            {running()
+              ? "处理中..."
+              : workMode() === "pack"
+              ? "开始切分"
+              : "开始合并"}
+          </button>
+          <button class="ghost queue-button" onClick={enqueueProcess}>
+            加入队列
+          </button>
+          <Show when={workMode() === "restore"}>
+            <button
+              class="ghost queue-button"
+              onClick={() => void startRestore(false, true)}
+              disabled={running()}
+              title="目录中有多组分片时逐组合并，某组失败时继续合并其余各组"
+            >
+              全部合并
+            </button>
+          </Show>
+          <Show when={running()}>
+            <button class="ghost cancel-button" onClick={cancelProcess}>
+              取消
+            </button>
+          </Show>
+
+          <Show when={queueItems().length > 0}>
+            <div class="queue">
+              <div class="queue-header">
+                <span>队列（{queueItems().length}）</span>
+                <select
+                  value={queuePowerAction()}
+                  onChange={(e) =>
+                    void changeQueuePowerAction(e.currentTarget.value)
+                  }
+                >
+                  <option value="none">全部完成后不操作</option>
+                  <option value="shutdown">全部完成后关机</option>
+                  <option value="sleep">全部完成后睡眠</option>
+                  <option value="hibernate">全部完成后休眠</option>
+                </select>
+                <button class="ghost" onClick={clearQueue}>
+                  清空等待中
+                </button>
+              </div>
+              <ul>
+                <For each={queueItems()}>
+                  {(item) => (
+                    <li>
+                      <span class="queue-state" title={item.lastError}>
+                        {item.state === "running"
+                          ? "运行中"
+                          : item.state === "retrying"
+                          ? `等待重试（第 ${item.attempt} 次失败）`
+                          : "等待中"}
+                      </span>
+                      <span class="queue-label">
+                        {item.kind === "restore" ? "合并" : "切分"}{" "}
+                        {extractName(item.label) || item.label}
+                      </span>
+                      <Show when={item.state !== "running"}>
+                        <button
+                          class="ghost"
+                          onClick={() => removeQueued(item.id)}
+                        >
+                          移除
+                        </button>
+                      </Show>
+                    </li>
+                  )}
+                </For>
+              </ul>
+            </div>
           </Show>
 
+          <For each={Object.values(jobProgress())}>
+            {(data) => (
+              <div class="progress">
+                <div class="progress-bar">
+                  <div
+                    class="progress-fill"
+                    style={{ width: `${progressPercent(data)}%` }}
+                  />
+                </div>
+                <div class="progress-meta">
+                  <span>{data.message}</span>
+                  <span>
+                    {formatBytes(data.processedBytes)} /{" "}
+                    {data.totalBytes == null
+                      ? "未知"
+                      : formatBytes(data.totalBytes)}
+                  </span>
+                </div>
+                <Show when={data.partTotal > 0}>
+                  <div class="progress-part">
+                    分片 {data.partIndex} / {data.partTotal}
+                  </div>
+                </Show>
+              </div>
+            )}
+          </For>
+
           <Show when={error()}>
             <div class="alert error">{error()}</div>
           </Show>
@@ -1110,6 +3093,82 @@ function App() {
         </section>
       </Show>
 
+      <Show when={conflictPrompt()}>
+        {(prompt) => (
+          <div class="prompt-overlay">
+            <div class="card prompt-dialog">
+              <h2>已有同名文件</h2>
+              <p class="prompt-path">{prompt().path}</p>
+              <p class="hint">
+                已有文件 {formatBytes(prompt().existingSize)}，压缩包中的 {prompt().entry}{" "}
+                {formatBytes(prompt().size)}
+              </p>
+              <label class="option inline">
+                <span class="option-label">
+                  <input
+                    type="checkbox"
+                    checked={conflictApplyAll()}
+                    onChange={(e) => setConflictApplyAll(e.currentTarget.checked)}
+                  />
+                  <span>其余同名文件同样处理</span>
+                </span>
+              </label>
+              <div class="prompt-actions">
+                <button class="primary" onClick={() => void answerConflict("overwrite")}>
+                  覆盖
+                </button>
+                <button class="ghost" onClick={() => void answerConflict("skip")}>
+                  跳过
+                </button>
+                <button class="ghost" onClick={() => void answerConflict("rename")}>
+                  改名保留两者
+                </button>
+              </div>
+            </div>
+          </div>
+        )}
+      </Show>
+
+      <Show when={passwordPrompt()}>
+        {(prompt) => (
+          <div class="prompt-overlay">
+            <div class="card prompt-dialog">
+              <h2>{prompt().reason === "wrongPassword" ? "密码错误" : "需要密码"}</h2>
+              <p class="prompt-path">{prompt().archive}</p>
+              <p class="hint">
+                输入正确的密码后从这一步继续，已合并的数据不必重来
+                {prompt().attempt > 1 ? `（已尝试 ${prompt().attempt} 次）` : ""}
+              </p>
+              <div class="field">
+                <input
+                  type="password"
+                  value={promptPassword()}
+                  placeholder="解压密码"
+                  onInput={(e) => setPromptPassword(e.currentTarget.value)}
+                  onKeyDown={(e) => {
+                    if (e.key === "Enter" && promptPassword()) {
+                      void answerPassword(promptPassword());
+                    }
+                  }}
+                />
+              </div>
+              <div class="prompt-actions">
+                <button
+                  class="primary"
+                  disabled={!promptPassword()}
+                  onClick={() => void answerPassword(promptPassword())}
+                >
+                  重试
+                </button>
+                <button class="ghost" onClick={() => void answerPassword(null)}>
+                  放弃
+                </button>
+              </div>
+            </div>
+          </div>
+        )}
+      </Show>
+
       <Show when={outputFiles().length > 0}>
         <section class="result output">
           <div class="result-header">